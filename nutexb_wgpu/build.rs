@@ -1,6 +1,5 @@
-fn main() {
-    // TODO: Only rerun if the shader change?
-    let wgsl_source = std::fs::read_to_string("src/shader.wgsl").unwrap();
+fn write_shader_module(wgsl_path: &str, shader_name: &str) {
+    let wgsl_source = std::fs::read_to_string(wgsl_path).unwrap();
 
     // Generate the Rust bindings and write to a file.
     let text = &wgsl_to_wgpu::create_shader_module_embedded(
@@ -14,5 +13,12 @@ fn main() {
     .unwrap();
 
     let out_dir = std::env::var("OUT_DIR").unwrap();
-    std::fs::write(format!("{out_dir}/shader.rs"), text.as_bytes()).unwrap();
+    std::fs::write(format!("{out_dir}/{shader_name}.rs"), text.as_bytes()).unwrap();
+}
+
+fn main() {
+    // TODO: Only rerun if the shader change?
+    write_shader_module("src/shader.wgsl", "shader");
+    write_shader_module("src/mipmap.wgsl", "mipmap");
+    write_shader_module("src/thumbnail.wgsl", "thumbnail");
 }
@@ -0,0 +1,282 @@
+use wgpu::{util::DeviceExt, TextureDimension, TextureFormat};
+
+/// The uncompressed format used for `format` on devices without [wgpu::Features::TEXTURE_COMPRESSION_BC].
+/// Returns `None` if `format` is not block compressed.
+///
+/// BC4 and BC5 store their channels in red and green with blue set to zero.
+/// BC6H is stored as 16-bit floats to preserve values outside the 0.0 to 1.0 range.
+/// This matches the precision of BC6H and is filterable without additional device features.
+pub fn decompressed_format(format: TextureFormat) -> Option<TextureFormat> {
+    match format {
+        TextureFormat::Bc1RgbaUnorm
+        | TextureFormat::Bc2RgbaUnorm
+        | TextureFormat::Bc3RgbaUnorm
+        | TextureFormat::Bc4RUnorm
+        | TextureFormat::Bc5RgUnorm
+        | TextureFormat::Bc7RgbaUnorm => Some(TextureFormat::Rgba8Unorm),
+        TextureFormat::Bc1RgbaUnormSrgb
+        | TextureFormat::Bc2RgbaUnormSrgb
+        | TextureFormat::Bc3RgbaUnormSrgb
+        | TextureFormat::Bc7RgbaUnormSrgb => Some(TextureFormat::Rgba8UnormSrgb),
+        TextureFormat::Bc4RSnorm | TextureFormat::Bc5RgSnorm => Some(TextureFormat::Rgba8Snorm),
+        TextureFormat::Bc6hRgbUfloat | TextureFormat::Bc6hRgbFloat => {
+            Some(TextureFormat::Rgba16Float)
+        }
+        _ => None,
+    }
+}
+
+/// Creates a texture from layer major `data` like [wgpu::util::DeviceExt::create_texture_with_data].
+///
+/// Block compressed data is decoded on the CPU if the device does not support
+/// [wgpu::Features::TEXTURE_COMPRESSION_BC]. The texture then uses the format from
+/// [decompressed_format] and uses significantly more memory than the compressed data.
+/// This allows rendering on backends without BC support like some mobile devices.
+pub fn create_texture_with_data(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    desc: &wgpu::TextureDescriptor,
+    data: &[u8],
+) -> wgpu::Texture {
+    let fallback_format = decompressed_format(desc.format).filter(|_| {
+        !device
+            .features()
+            .contains(wgpu::Features::TEXTURE_COMPRESSION_BC)
+    });
+
+    match fallback_format {
+        Some(format) => {
+            let decompressed = decompress_data(
+                data,
+                desc.size,
+                desc.dimension,
+                desc.format,
+                desc.mip_level_count,
+            );
+            let view_formats: Vec<_> = desc
+                .view_formats
+                .iter()
+                .map(|f| decompressed_format(*f).unwrap_or(*f))
+                .collect();
+
+            device.create_texture_with_data(
+                queue,
+                &wgpu::TextureDescriptor {
+                    format,
+                    view_formats: &view_formats,
+                    ..desc.clone()
+                },
+                wgpu::util::TextureDataOrder::LayerMajor,
+                &decompressed,
+            )
+        }
+        None => device.create_texture_with_data(
+            queue,
+            desc,
+            wgpu::util::TextureDataOrder::LayerMajor,
+            data,
+        ),
+    }
+}
+
+// Decode the layer major data for all layers and mipmaps.
+fn decompress_data(
+    data: &[u8],
+    size: wgpu::Extent3d,
+    dimension: TextureDimension,
+    format: TextureFormat,
+    mip_level_count: u32,
+) -> Vec<u8> {
+    let block_size = format.block_copy_size(None).unwrap_or(0) as usize;
+
+    // 3D textures store the depth in each mipmap instead of separate layers.
+    let layers = match dimension {
+        TextureDimension::D3 => 1,
+        _ => size.depth_or_array_layers,
+    };
+
+    let mut output = Vec::new();
+    let mut offset = 0;
+    for _ in 0..layers {
+        for mip in 0..mip_level_count {
+            let mip_size = size.mip_level_size(mip, dimension);
+            let depth = match dimension {
+                TextureDimension::D3 => mip_size.depth_or_array_layers,
+                _ => 1,
+            };
+
+            let surface_size = mip_size.width.div_ceil(4) as usize
+                * mip_size.height.div_ceil(4) as usize
+                * block_size;
+            for _ in 0..depth {
+                // Missing data is decoded as zeros to always fill the texture.
+                let end = (offset + surface_size).min(data.len());
+                let surface = data.get(offset..end).unwrap_or_default();
+                output.extend(decompress_surface(
+                    surface,
+                    mip_size.width,
+                    mip_size.height,
+                    format,
+                ));
+                offset += surface_size;
+            }
+        }
+    }
+    output
+}
+
+fn decompress_surface(surface: &[u8], width: u32, height: u32, format: TextureFormat) -> Vec<u8> {
+    let (width, height) = (width as usize, height as usize);
+    let block_size = format.block_copy_size(None).unwrap_or(0) as usize;
+    let pixel_size = decompressed_format(format)
+        .and_then(|f| f.block_copy_size(None))
+        .unwrap_or(4) as usize;
+
+    let mut output = vec![0u8; width * height * pixel_size];
+    if block_size == 0 {
+        return output;
+    }
+
+    let width_blocks = width.div_ceil(4);
+    for (i, block) in surface.chunks_exact(block_size).enumerate() {
+        let pixels = decompress_block(block, format);
+        let (block_x, block_y) = (i % width_blocks * 4, i / width_blocks * 4);
+
+        // Blocks for mipmaps smaller than 4x4 pixels are only partially used.
+        for y in 0..4 {
+            for x in 0..4 {
+                let (output_x, output_y) = (block_x + x, block_y + y);
+                if output_x < width && output_y < height {
+                    let input_offset = (y * 4 + x) * pixel_size;
+                    let output_offset = (output_y * width + output_x) * pixel_size;
+                    output[output_offset..output_offset + pixel_size]
+                        .copy_from_slice(&pixels[input_offset..input_offset + pixel_size]);
+                }
+            }
+        }
+    }
+    output
+}
+
+// The bits for 1.0 as a 16-bit float.
+const HALF_ONE: u16 = 0x3C00;
+
+// Decode a single 4x4 block to tightly packed pixels in the decompressed format.
+fn decompress_block(block: &[u8], format: TextureFormat) -> Vec<u8> {
+    let signed = matches!(
+        format,
+        TextureFormat::Bc4RSnorm | TextureFormat::Bc5RgSnorm | TextureFormat::Bc6hRgbFloat
+    );
+    // Snorm formats represent 1.0 as 127.
+    let one = if signed { 127 } else { 255 };
+
+    match format {
+        TextureFormat::Bc1RgbaUnorm | TextureFormat::Bc1RgbaUnormSrgb => {
+            let mut rgba = vec![0u8; 64];
+            bcdec_rs::bc1(block, &mut rgba, 16);
+            rgba
+        }
+        TextureFormat::Bc2RgbaUnorm | TextureFormat::Bc2RgbaUnormSrgb => {
+            let mut rgba = vec![0u8; 64];
+            bcdec_rs::bc2(block, &mut rgba, 16);
+            rgba
+        }
+        TextureFormat::Bc3RgbaUnorm | TextureFormat::Bc3RgbaUnormSrgb => {
+            let mut rgba = vec![0u8; 64];
+            bcdec_rs::bc3(block, &mut rgba, 16);
+            rgba
+        }
+        TextureFormat::Bc4RUnorm | TextureFormat::Bc4RSnorm => {
+            let mut r = [0u8; 16];
+            bcdec_rs::bc4(block, &mut r, 4, signed);
+            r.iter().flat_map(|r| [*r, 0, 0, one]).collect()
+        }
+        TextureFormat::Bc5RgUnorm | TextureFormat::Bc5RgSnorm => {
+            let mut rg = [0u8; 32];
+            bcdec_rs::bc5(block, &mut rg, 8, signed);
+            rg.chunks_exact(2)
+                .flat_map(|rg| [rg[0], rg[1], 0, one])
+                .collect()
+        }
+        TextureFormat::Bc6hRgbUfloat | TextureFormat::Bc6hRgbFloat => {
+            // Decode to half floats to avoid requiring wgpu::Features::FLOAT32_FILTERABLE.
+            let mut rgb = [0u16; 48];
+            bcdec_rs::bc6h_half(block, &mut rgb, 12, signed);
+            let rgba: Vec<_> = rgb
+                .chunks_exact(3)
+                .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], HALF_ONE])
+                .collect();
+            bytemuck::cast_slice(&rgba).to_vec()
+        }
+        TextureFormat::Bc7RgbaUnorm | TextureFormat::Bc7RgbaUnormSrgb => {
+            let mut rgba = vec![0u8; 64];
+            bcdec_rs::bc7(block, &mut rgba, 16);
+            rgba
+        }
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A BC1 block with both endpoints set to red and all indices using the first endpoint.
+    const BC1_RED: [u8; 8] = [0x00, 0xF8, 0x00, 0xF8, 0, 0, 0, 0];
+
+    #[test]
+    fn decompressed_format_uncompressed() {
+        assert_eq!(None, decompressed_format(TextureFormat::Rgba8Unorm));
+    }
+
+    #[test]
+    fn decompressed_format_bc6h_filterable() {
+        let format = decompressed_format(TextureFormat::Bc6hRgbUfloat).unwrap();
+        assert_eq!(TextureFormat::Rgba16Float, format);
+        assert!(format
+            .guaranteed_format_features(wgpu::Features::empty())
+            .flags
+            .contains(wgpu::TextureFormatFeatureFlags::FILTERABLE));
+    }
+
+    #[test]
+    fn decompress_surface_bc1() {
+        let data = decompress_surface(&BC1_RED, 4, 4, TextureFormat::Bc1RgbaUnorm);
+        assert_eq!([255, 0, 0, 255].repeat(16), data);
+    }
+
+    #[test]
+    fn decompress_data_bc1_mipmaps() {
+        // Mipmaps smaller than a block only use part of the block.
+        let size = wgpu::Extent3d {
+            width: 8,
+            height: 8,
+            depth_or_array_layers: 1,
+        };
+        let data = decompress_data(
+            &BC1_RED.repeat(4 + 1 + 1 + 1),
+            size,
+            TextureDimension::D2,
+            TextureFormat::Bc1RgbaUnorm,
+            4,
+        );
+        assert_eq!([255, 0, 0, 255].repeat(64 + 16 + 4 + 1), data);
+    }
+
+    #[test]
+    fn decompress_data_missing_data() {
+        let size = wgpu::Extent3d {
+            width: 4,
+            height: 4,
+            depth_or_array_layers: 2,
+        };
+        let data = decompress_data(
+            &BC1_RED,
+            size,
+            TextureDimension::D2,
+            TextureFormat::Bc1RgbaUnorm,
+            1,
+        );
+        assert_eq!([[255, 0, 0, 255].repeat(16), vec![0; 64]].concat(), data);
+    }
+}
@@ -0,0 +1,396 @@
+use image_dds::{ImageFormat, Mipmaps, Quality, Surface};
+use nutexb::{NutexbFile, NutexbFormat};
+use thiserror::Error;
+use wgpu::{TextureDimension, TextureFormat};
+
+use crate::wgpu_format;
+
+/// Errors that can occur while converting a [wgpu::Texture] to a [NutexbFile].
+#[derive(Debug, Error)]
+pub enum ExportTextureError {
+    #[error("the texture format {0:?} is not supported for export")]
+    UnsupportedFormat(TextureFormat),
+
+    #[error("the texture was not created with wgpu::TextureUsages::COPY_SRC")]
+    MissingCopySrc,
+
+    #[error("an error occurred while reading the texture data from the GPU")]
+    ReadbackError,
+
+    #[error("an error occurred while encoding the texture data to {0:?}")]
+    EncodeError(
+        NutexbFormat,
+        #[source] Box<dyn std::error::Error + Send + Sync>,
+    ),
+
+    #[error("an error occurred while swizzling nutexb data")]
+    SwizzleError(#[source] Box<dyn std::error::Error + Send + Sync>),
+}
+
+/// Reads back `texture` and converts it to a nutexb with the given `format` and `name`.
+///
+/// The data is copied without any conversion if the texture already uses `format`.
+/// Otherwise the data is decoded and encoded again on the CPU,
+/// which can be slow for large textures and block compressed formats like BC7.
+/// All layers and mipmaps are preserved, so edits to a texture from [create_texture](crate::create_texture)
+/// can be saved in the original format.
+///
+/// The `texture` must be created with [wgpu::TextureUsages::COPY_SRC].
+pub fn export_nutexb(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+    name: &str,
+    format: NutexbFormat,
+) -> Result<NutexbFile, ExportTextureError> {
+    let source_format = image_dds_format(texture.format())
+        .ok_or(ExportTextureError::UnsupportedFormat(texture.format()))?;
+    let target_format = image_dds_format(wgpu_format(format))
+        .ok_or(ExportTextureError::UnsupportedFormat(wgpu_format(format)))?;
+
+    let data = read_texture_data(device, queue, texture)?;
+
+    // 3D textures store the depth in each mipmap instead of separate layers.
+    let (depth, layers) = match texture.dimension() {
+        TextureDimension::D3 => (texture.depth_or_array_layers(), 1),
+        _ => (1, texture.depth_or_array_layers()),
+    };
+    let surface = Surface {
+        width: texture.width(),
+        height: texture.height(),
+        depth,
+        layers,
+        mipmaps: texture.mip_level_count(),
+        image_format: source_format,
+        data,
+    };
+
+    let surface = if source_format == target_format {
+        surface
+    } else if is_float(target_format) {
+        // Avoid clamping values outside the 0.0 to 1.0 range for BC6H.
+        surface
+            .decode_rgbaf32()
+            .and_then(|s| s.encode(target_format, Quality::Normal, Mipmaps::FromSurface))
+            .map_err(|e| ExportTextureError::EncodeError(format, e.into()))?
+    } else {
+        surface
+            .decode_rgba8()
+            .and_then(|s| s.encode(target_format, Quality::Normal, Mipmaps::FromSurface))
+            .map_err(|e| ExportTextureError::EncodeError(format, e.into()))?
+    };
+
+    // The encoded data already uses the image_dds equivalent of the nutexb format.
+    let surface = nutexb::Surface {
+        width: surface.width,
+        height: surface.height,
+        depth: surface.depth,
+        layers: surface.layers,
+        mipmaps: surface.mipmaps,
+        image_format: format,
+        data: surface.data,
+    };
+    NutexbFile::from_surface(surface, name).map_err(|e| ExportTextureError::SwizzleError(e.into()))
+}
+
+/// Reads the data for all layers and mipmaps of `texture` without any format conversions.
+///
+/// The data is layer major and tightly packed like the data for [create_texture_with_data](crate::create_texture_with_data).
+/// This submits commands to `queue` and blocks until the copies complete.
+///
+/// The `texture` must be created with [wgpu::TextureUsages::COPY_SRC].
+pub fn read_texture_data(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+) -> Result<Vec<u8>, ExportTextureError> {
+    if !texture.usage().contains(wgpu::TextureUsages::COPY_SRC) {
+        return Err(ExportTextureError::MissingCopySrc);
+    }
+
+    let format = texture.format();
+    let regions = copy_regions(
+        texture.size(),
+        texture.dimension(),
+        format,
+        texture.mip_level_count(),
+    )
+    .ok_or(ExportTextureError::UnsupportedFormat(format))?;
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("nutexb_wgpu Export Encoder"),
+    });
+
+    // Use a buffer for each region to avoid aligning offsets within a shared buffer.
+    let buffers: Vec<_> = regions
+        .iter()
+        .map(|region| {
+            let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("nutexb_wgpu Export Buffer"),
+                size: region.padded_bytes_per_row as u64
+                    * region.rows as u64
+                    * region.size.depth_or_array_layers as u64,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            });
+
+            encoder.copy_texture_to_buffer(
+                wgpu::ImageCopyTexture {
+                    texture,
+                    mip_level: region.mip_level,
+                    origin: wgpu::Origin3d {
+                        x: 0,
+                        y: 0,
+                        z: region.layer,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                wgpu::ImageCopyBuffer {
+                    buffer: &buffer,
+                    layout: wgpu::ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: Some(region.padded_bytes_per_row),
+                        rows_per_image: Some(region.rows),
+                    },
+                },
+                region.size,
+            );
+
+            buffer
+        })
+        .collect();
+    queue.submit(std::iter::once(encoder.finish()));
+
+    let receivers: Vec<_> = buffers
+        .iter()
+        .map(|buffer| {
+            let (sender, receiver) = std::sync::mpsc::channel();
+            buffer
+                .slice(..)
+                .map_async(wgpu::MapMode::Read, move |result| {
+                    let _ = sender.send(result);
+                });
+            receiver
+        })
+        .collect();
+    device.poll(wgpu::Maintain::Wait);
+
+    let mut data = Vec::new();
+    for ((buffer, receiver), region) in buffers.iter().zip(receivers).zip(&regions) {
+        receiver
+            .recv()
+            .ok()
+            .and_then(|r| r.ok())
+            .ok_or(ExportTextureError::ReadbackError)?;
+
+        data.extend(unpad_rows(
+            &buffer.slice(..).get_mapped_range(),
+            region.bytes_per_row,
+            region.padded_bytes_per_row,
+        ));
+        buffer.unmap();
+    }
+    Ok(data)
+}
+
+/// A copy of a single mipmap for a layer or all depth slices of a 3D texture.
+#[derive(Debug, PartialEq)]
+struct CopyRegion {
+    layer: u32,
+    mip_level: u32,
+    size: wgpu::Extent3d,
+    bytes_per_row: u32,
+    padded_bytes_per_row: u32,
+    rows: u32,
+}
+
+// Regions in layer major order to match the layout of the nutexb data.
+fn copy_regions(
+    size: wgpu::Extent3d,
+    dimension: TextureDimension,
+    format: TextureFormat,
+    mip_level_count: u32,
+) -> Option<Vec<CopyRegion>> {
+    let (block_width, block_height) = format.block_dimensions();
+    let block_size = format.block_copy_size(None)?;
+
+    let layers = match dimension {
+        TextureDimension::D3 => 1,
+        _ => size.depth_or_array_layers,
+    };
+
+    let regions = (0..layers)
+        .flat_map(|layer| {
+            (0..mip_level_count).map(move |mip_level| {
+                // Copies of compressed formats must cover entire blocks.
+                let mip_size = size
+                    .mip_level_size(mip_level, dimension)
+                    .physical_size(format);
+                let bytes_per_row = mip_size.width / block_width * block_size;
+                CopyRegion {
+                    layer,
+                    mip_level,
+                    size: wgpu::Extent3d {
+                        depth_or_array_layers: match dimension {
+                            TextureDimension::D3 => mip_size.depth_or_array_layers,
+                            _ => 1,
+                        },
+                        ..mip_size
+                    },
+                    bytes_per_row,
+                    padded_bytes_per_row: bytes_per_row
+                        .next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT),
+                    rows: mip_size.height / block_height,
+                }
+            })
+        })
+        .collect();
+    Some(regions)
+}
+
+fn unpad_rows(data: &[u8], bytes_per_row: u32, padded_bytes_per_row: u32) -> Vec<u8> {
+    data.chunks(padded_bytes_per_row as usize)
+        .flat_map(|row| &row[..bytes_per_row as usize])
+        .copied()
+        .collect()
+}
+
+fn is_float(format: ImageFormat) -> bool {
+    matches!(
+        format,
+        ImageFormat::Rgba32Float | ImageFormat::BC6hRgbUfloat | ImageFormat::BC6hRgbSfloat
+    )
+}
+
+fn image_dds_format(format: TextureFormat) -> Option<ImageFormat> {
+    match format {
+        TextureFormat::R8Unorm => Some(ImageFormat::R8Unorm),
+        TextureFormat::Rgba8Unorm => Some(ImageFormat::Rgba8Unorm),
+        TextureFormat::Rgba8UnormSrgb => Some(ImageFormat::Rgba8UnormSrgb),
+        TextureFormat::Bgra8Unorm => Some(ImageFormat::Bgra8Unorm),
+        TextureFormat::Bgra8UnormSrgb => Some(ImageFormat::Bgra8UnormSrgb),
+        TextureFormat::Rgba32Float => Some(ImageFormat::Rgba32Float),
+        TextureFormat::Bc1RgbaUnorm => Some(ImageFormat::BC1RgbaUnorm),
+        TextureFormat::Bc1RgbaUnormSrgb => Some(ImageFormat::BC1RgbaUnormSrgb),
+        TextureFormat::Bc2RgbaUnorm => Some(ImageFormat::BC2RgbaUnorm),
+        TextureFormat::Bc2RgbaUnormSrgb => Some(ImageFormat::BC2RgbaUnormSrgb),
+        TextureFormat::Bc3RgbaUnorm => Some(ImageFormat::BC3RgbaUnorm),
+        TextureFormat::Bc3RgbaUnormSrgb => Some(ImageFormat::BC3RgbaUnormSrgb),
+        TextureFormat::Bc4RUnorm => Some(ImageFormat::BC4RUnorm),
+        TextureFormat::Bc4RSnorm => Some(ImageFormat::BC4RSnorm),
+        TextureFormat::Bc5RgUnorm => Some(ImageFormat::BC5RgUnorm),
+        TextureFormat::Bc5RgSnorm => Some(ImageFormat::BC5RgSnorm),
+        TextureFormat::Bc6hRgbUfloat => Some(ImageFormat::BC6hRgbUfloat),
+        TextureFormat::Bc6hRgbFloat => Some(ImageFormat::BC6hRgbSfloat),
+        TextureFormat::Bc7RgbaUnorm => Some(ImageFormat::BC7RgbaUnorm),
+        TextureFormat::Bc7RgbaUnormSrgb => Some(ImageFormat::BC7RgbaUnormSrgb),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copy_regions_bc7_mipmaps() {
+        let size = wgpu::Extent3d {
+            width: 8,
+            height: 8,
+            depth_or_array_layers: 1,
+        };
+        let regions =
+            copy_regions(size, TextureDimension::D2, TextureFormat::Bc7RgbaUnorm, 4).unwrap();
+
+        // Mipmaps smaller than a block still copy an entire block.
+        let rows: Vec<_> = regions
+            .iter()
+            .map(|r| (r.size.width, r.size.height, r.bytes_per_row, r.rows))
+            .collect();
+        assert_eq!(
+            vec![(8, 8, 32, 2), (4, 4, 16, 1), (4, 4, 16, 1), (4, 4, 16, 1)],
+            rows
+        );
+        assert!(regions.iter().all(|r| r.padded_bytes_per_row == 256));
+    }
+
+    #[test]
+    fn copy_regions_cube_map_layer_major() {
+        let size = wgpu::Extent3d {
+            width: 4,
+            height: 4,
+            depth_or_array_layers: 6,
+        };
+        let regions =
+            copy_regions(size, TextureDimension::D2, TextureFormat::Rgba8Unorm, 2).unwrap();
+        let order: Vec<_> = regions.iter().map(|r| (r.layer, r.mip_level)).collect();
+        assert_eq!((0, 0), order[0]);
+        assert_eq!((0, 1), order[1]);
+        assert_eq!((5, 1), order[11]);
+        assert_eq!(12, regions.len());
+    }
+
+    #[test]
+    fn copy_regions_3d_depth() {
+        let size = wgpu::Extent3d {
+            width: 4,
+            height: 4,
+            depth_or_array_layers: 4,
+        };
+        let regions =
+            copy_regions(size, TextureDimension::D3, TextureFormat::Rgba8Unorm, 2).unwrap();
+        let depths: Vec<_> = regions
+            .iter()
+            .map(|r| r.size.depth_or_array_layers)
+            .collect();
+        assert_eq!(vec![4, 2], depths);
+    }
+
+    #[test]
+    fn copy_regions_depth_format() {
+        let size = wgpu::Extent3d {
+            width: 4,
+            height: 4,
+            depth_or_array_layers: 1,
+        };
+        assert_eq!(
+            None,
+            copy_regions(size, TextureDimension::D2, TextureFormat::Depth24Plus, 1)
+        );
+    }
+
+    #[test]
+    fn unpad_rows_removes_padding() {
+        let data = [1, 2, 0, 0, 3, 4, 0, 0];
+        assert_eq!(vec![1, 2, 3, 4], unpad_rows(&data, 2, 4));
+    }
+
+    #[test]
+    fn image_dds_format_all_nutexb_formats() {
+        // Every nutexb format should be supported for export.
+        for format in [
+            NutexbFormat::R8Unorm,
+            NutexbFormat::R8G8B8A8Unorm,
+            NutexbFormat::R8G8B8A8Srgb,
+            NutexbFormat::B8G8R8A8Unorm,
+            NutexbFormat::B8G8R8A8Srgb,
+            NutexbFormat::R32G32B32A32Float,
+            NutexbFormat::BC1Unorm,
+            NutexbFormat::BC1Srgb,
+            NutexbFormat::BC2Unorm,
+            NutexbFormat::BC2Srgb,
+            NutexbFormat::BC3Unorm,
+            NutexbFormat::BC3Srgb,
+            NutexbFormat::BC4Unorm,
+            NutexbFormat::BC4Snorm,
+            NutexbFormat::BC5Unorm,
+            NutexbFormat::BC5Snorm,
+            NutexbFormat::BC6Sfloat,
+            NutexbFormat::BC6Ufloat,
+            NutexbFormat::BC7Unorm,
+            NutexbFormat::BC7Srgb,
+        ] {
+            assert!(image_dds_format(wgpu_format(format)).is_some());
+        }
+    }
+}
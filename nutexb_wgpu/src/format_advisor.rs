@@ -0,0 +1,284 @@
+use nutexb::NutexbFormat;
+
+use crate::wgpu_format;
+
+/// Properties of RGBA texture data relevant for selecting a compressed format.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextureAnalysis {
+    /// At least one pixel has an alpha value other than `255`.
+    pub uses_alpha: bool,
+    /// At least one pixel has an alpha value other than `0` or `255`.
+    pub uses_partial_alpha: bool,
+    /// The red, green, and blue channels are equal for all pixels.
+    pub is_grayscale: bool,
+    /// The red and green channels encode unit length normal vectors
+    /// with the Z component stored in the blue channel.
+    pub is_normal_map: bool,
+    /// The average absolute difference between horizontally adjacent pixels from `0.0` to `1.0`.
+    /// Lower values indicate smooth gradients that are prone to banding with BC1.
+    pub gradient_step: f32,
+}
+
+/// A suggested format from [suggest_format] and its estimated size in bytes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FormatSuggestion {
+    pub format: NutexbFormat,
+    pub size_in_bytes: usize,
+}
+
+// Gradients with smaller steps between pixels should use higher quality formats.
+// TODO: Find a better threshold based on actual encoded textures.
+const SMOOTH_GRADIENT_STEP: f32 = 2.0 / 255.0;
+
+// The fraction of pixels that must be valid normals to detect a normal map.
+const NORMAL_MAP_THRESHOLD: f32 = 0.95;
+
+// The maximum difference between the stored and reconstructed Z component for normals.
+const NORMAL_Z_TOLERANCE: f32 = 0.1;
+
+/// Analyze the contents of tightly packed RGBA8 pixel data with dimensions `width` x `height`.
+///
+/// Data for compressed textures can be converted with [TextureRenderer::render_to_texture_2d_rgba](crate::TextureRenderer::render_to_texture_2d_rgba).
+pub fn analyze_rgba8(data: &[u8], width: u32, height: u32) -> TextureAnalysis {
+    let pixels: Vec<_> = data.chunks_exact(4).collect();
+
+    let uses_alpha = pixels.iter().any(|p| p[3] != 255);
+    let uses_partial_alpha = pixels.iter().any(|p| p[3] != 0 && p[3] != 255);
+    let is_grayscale = pixels.iter().all(|p| p[0] == p[1] && p[1] == p[2]);
+
+    let normal_count = pixels.iter().filter(|p| is_unit_normal(p)).count();
+    let is_normal_map = !pixels.is_empty()
+        && !is_grayscale
+        && normal_count as f32 / pixels.len() as f32 >= NORMAL_MAP_THRESHOLD;
+
+    TextureAnalysis {
+        uses_alpha,
+        uses_partial_alpha,
+        is_grayscale,
+        is_normal_map,
+        gradient_step: gradient_step(&pixels, width as usize, height as usize),
+    }
+}
+
+/// Suggest a compressed format for a texture with the given `analysis`.
+///
+/// Set `srgb` to `true` for color textures like col maps.
+/// The size estimate includes `mipmap_count` mipmaps for each of the `layers`.
+pub fn suggest_format(
+    analysis: &TextureAnalysis,
+    srgb: bool,
+    width: u32,
+    height: u32,
+    mipmap_count: u32,
+    layers: u32,
+) -> FormatSuggestion {
+    let smooth = analysis.gradient_step < SMOOTH_GRADIENT_STEP;
+
+    let format = if analysis.is_normal_map {
+        NutexbFormat::BC5Unorm
+    } else if analysis.is_grayscale && !analysis.uses_alpha {
+        NutexbFormat::BC4Unorm
+    } else if analysis.uses_partial_alpha {
+        if smooth {
+            bc7(srgb)
+        } else if srgb {
+            NutexbFormat::BC3Srgb
+        } else {
+            NutexbFormat::BC3Unorm
+        }
+    } else if smooth {
+        bc7(srgb)
+    } else if srgb {
+        // BC1 also supports 1-bit alpha.
+        NutexbFormat::BC1Srgb
+    } else {
+        NutexbFormat::BC1Unorm
+    };
+
+    FormatSuggestion {
+        format,
+        size_in_bytes: estimated_size(format, width, height, mipmap_count, layers),
+    }
+}
+
+/// The size in bytes of the image data for a texture with the given `format` and dimensions.
+///
+/// This does not include any padding from swizzling.
+pub fn estimated_size(
+    format: NutexbFormat,
+    width: u32,
+    height: u32,
+    mipmap_count: u32,
+    layers: u32,
+) -> usize {
+    let format = wgpu_format(format);
+    let (block_width, block_height) = format.block_dimensions();
+    let block_size = format.block_copy_size(None).unwrap_or_default() as usize;
+
+    let mip_size: usize = (0..mipmap_count)
+        .map(|mip| {
+            let mip_width = (width >> mip).max(1);
+            let mip_height = (height >> mip).max(1);
+            mip_width.div_ceil(block_width) as usize
+                * mip_height.div_ceil(block_height) as usize
+                * block_size
+        })
+        .sum();
+
+    mip_size * layers as usize
+}
+
+fn bc7(srgb: bool) -> NutexbFormat {
+    if srgb {
+        NutexbFormat::BC7Srgb
+    } else {
+        NutexbFormat::BC7Unorm
+    }
+}
+
+fn is_unit_normal(pixel: &[u8]) -> bool {
+    // Normal maps store XY in RG with Z derived in the shader.
+    let x = pixel[0] as f32 / 255.0 * 2.0 - 1.0;
+    let y = pixel[1] as f32 / 255.0 * 2.0 - 1.0;
+    let z = pixel[2] as f32 / 255.0 * 2.0 - 1.0;
+
+    // Allow some error from 8-bit quantization.
+    let length_squared = x * x + y * y;
+    if length_squared > 1.05 {
+        return false;
+    }
+
+    // Most colors have RG values within the unit circle,
+    // so also check that the stored Z matches the reconstructed Z.
+    let reconstructed_z = (1.0 - length_squared).max(0.0).sqrt();
+    (reconstructed_z - z).abs() <= NORMAL_Z_TOLERANCE
+}
+
+fn gradient_step(pixels: &[&[u8]], width: usize, height: usize) -> f32 {
+    if width < 2 || pixels.len() < width * height {
+        return 0.0;
+    }
+
+    let mut sum = 0u64;
+    let mut count = 0u64;
+    for row in pixels.chunks_exact(width).take(height) {
+        for pair in row.windows(2) {
+            for c in 0..3 {
+                sum += pair[0][c].abs_diff(pair[1][c]) as u64;
+            }
+            count += 3;
+        }
+    }
+
+    sum as f32 / count as f32 / 255.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(rgba: [u8; 4], width: u32, height: u32) -> Vec<u8> {
+        rgba.repeat((width * height) as usize)
+    }
+
+    #[test]
+    fn analyze_empty() {
+        assert_eq!(
+            TextureAnalysis {
+                uses_alpha: false,
+                uses_partial_alpha: false,
+                is_grayscale: true,
+                is_normal_map: false,
+                gradient_step: 0.0
+            },
+            analyze_rgba8(&[], 0, 0)
+        );
+    }
+
+    #[test]
+    fn suggest_grayscale() {
+        let analysis = analyze_rgba8(&solid([128, 128, 128, 255], 4, 4), 4, 4);
+        assert_eq!(
+            FormatSuggestion {
+                format: NutexbFormat::BC4Unorm,
+                size_in_bytes: 8
+            },
+            suggest_format(&analysis, false, 4, 4, 1, 1)
+        );
+    }
+
+    #[test]
+    fn suggest_normal_map() {
+        let analysis = analyze_rgba8(&solid([128, 128, 255, 255], 4, 4), 4, 4);
+        assert!(analysis.is_normal_map);
+        assert_eq!(
+            NutexbFormat::BC5Unorm,
+            suggest_format(&analysis, false, 4, 4, 1, 1).format
+        );
+    }
+
+    #[test]
+    fn suggest_tilted_normal_map() {
+        // Normals rotated around the Y-axis with Z stored in the blue channel.
+        let data: Vec<_> = (0..16)
+            .flat_map(|i| {
+                let angle = i as f32 / 16.0;
+                let x = ((angle.sin() + 1.0) / 2.0 * 255.0).round() as u8;
+                let z = ((angle.cos() + 1.0) / 2.0 * 255.0).round() as u8;
+                [x, 128, z, 255]
+            })
+            .collect();
+        let analysis = analyze_rgba8(&data, 16, 1);
+        assert!(analysis.is_normal_map);
+    }
+
+    #[test]
+    fn analyze_color_texture_not_normal_map() {
+        // RG values for skin and sky tones are within the unit circle.
+        let data: Vec<_> = (0..16u8)
+            .flat_map(|i| {
+                [
+                    [200 + i * 2, 150 + i, 120 + i, 255],
+                    [100 + i * 4, 120 + i * 3, 180 + i * 2, 255],
+                ]
+            })
+            .flatten()
+            .collect();
+        let analysis = analyze_rgba8(&data, 32, 1);
+        assert!(!analysis.is_normal_map);
+        assert_ne!(
+            NutexbFormat::BC5Unorm,
+            suggest_format(&analysis, true, 32, 1, 1, 1).format
+        );
+    }
+
+    #[test]
+    fn suggest_partial_alpha_srgb() {
+        let data = [[255, 0, 0, 128], [0, 0, 255, 0]].concat().repeat(8);
+        let analysis = analyze_rgba8(&data, 4, 4);
+        assert_eq!(
+            NutexbFormat::BC3Srgb,
+            suggest_format(&analysis, true, 4, 4, 1, 1).format
+        );
+    }
+
+    #[test]
+    fn suggest_smooth_gradient() {
+        let data: Vec<_> = (0..16u8).flat_map(|i| [i, 255 - i, 0, 255]).collect();
+        let analysis = analyze_rgba8(&data, 16, 1);
+        assert_eq!(
+            NutexbFormat::BC7Srgb,
+            suggest_format(&analysis, true, 16, 1, 1, 1).format
+        );
+    }
+
+    #[test]
+    fn estimated_size_bc7_cube_mipmaps() {
+        // 64x64, 32x32, 16x16, 8x8, 4x4, 2x2, 1x1
+        let mip_blocks = 256 + 64 + 16 + 4 + 1 + 1 + 1;
+        assert_eq!(
+            mip_blocks * 16 * 6,
+            estimated_size(NutexbFormat::BC7Srgb, 64, 64, 7, 6)
+        );
+    }
+}
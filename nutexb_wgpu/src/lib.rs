@@ -4,12 +4,22 @@ use shader::bind_groups::BindGroup0;
 use shader::set_bind_groups;
 use shader::{create_pipeline_layout, create_shader_module};
 use thiserror::Error;
-use wgpu::{util::DeviceExt, Limits};
+use wgpu::util::DeviceExt;
 use wgpu::{
     Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
     TextureViewDimension,
 };
 
+mod decompress;
+mod export;
+pub mod format_advisor;
+mod mipmap;
+mod thumbnail;
+
+pub use decompress::{create_texture_with_data, decompressed_format};
+pub use export::{export_nutexb, read_texture_data, ExportTextureError};
+pub use mipmap::{generate_mipmaps, mipmap_format, GenerateMipmapsError};
+
 #[allow(dead_code)]
 mod shader {
     include!(concat!(env!("OUT_DIR"), "/shader.rs"));
@@ -24,8 +34,16 @@ pub struct RenderSettings {
     pub mipmap: f32,
     /// The depth or array layer to render.
     /// Cube maps have six layers.
+    /// 2D array textures can select any of their layers.
     /// Depth textures should take values up to the texture's depth in pixels.
     pub layer: u32,
+    /// How to combine the texture with the comparison texture if present.
+    pub compare_mode: CompareMode,
+    /// How to display the texture channels after any comparisons.
+    pub channel_mode: ChannelMode,
+    /// How to display the faces of cube maps.
+    /// This has no effect for other texture dimensions.
+    pub cube_layout: CubeLayout,
 }
 
 impl Default for RenderSettings {
@@ -34,15 +52,85 @@ impl Default for RenderSettings {
             render_rgba: [true; 4],
             mipmap: 0.0,
             layer: 0,
+            compare_mode: CompareMode::Difference,
+            channel_mode: ChannelMode::Rgba,
+            cube_layout: CubeLayout::Face,
         }
     }
 }
 
+/// Layouts for displaying cube maps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CubeLayout {
+    /// The single face selected by [RenderSettings::layer].
+    Face,
+    /// All six faces in a horizontal cross with Y+ and Y- above and below Z+.
+    /// The output should have a 4:3 aspect ratio to keep the faces square.
+    Cross,
+    /// An equirectangular projection of all directions like a panorama.
+    /// The output should have a 2:1 aspect ratio.
+    Equirectangular,
+}
+
+/// A color channel of a texture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    Red,
+    Green,
+    Blue,
+    Alpha,
+}
+
+/// Modes for displaying the channels of a texture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelMode {
+    /// Display the channels enabled by [RenderSettings::render_rgba].
+    /// A single enabled channel is displayed as grayscale.
+    Rgba,
+    /// Display a single channel as grayscale like the alpha channel or a PRM channel.
+    Grayscale(Channel),
+    /// Display the given input channels for the output `[red, green, blue]`.
+    Remap([Channel; 3]),
+    /// Reconstruct the Z component from the red and green channels like the game
+    /// for normal maps with only two channels like BC5.
+    NormalMap,
+}
+
+impl ChannelMode {
+    fn mode_remap(&self) -> (u32, [u32; 4]) {
+        match self {
+            ChannelMode::Rgba => (0, [0, 1, 2, 3]),
+            ChannelMode::Grayscale(c) => (1, [*c as u32, *c as u32, *c as u32, 3]),
+            ChannelMode::Remap([r, g, b]) => (1, [*r as u32, *g as u32, *b as u32, 3]),
+            ChannelMode::NormalMap => (2, [0, 1, 2, 3]),
+        }
+    }
+}
+
+/// Modes for comparing a texture against a second 2D texture like an original and a re-encode.
+/// See [TextureRenderer::update_comparison].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompareMode {
+    /// The absolute difference for each channel.
+    Difference,
+    /// The texture on the left and the comparison texture on the right.
+    /// The split position is in UV coordinates from `0.0` to `1.0`.
+    Split(f32),
+}
+
 fn shader_settings(
     settings: &RenderSettings,
     dim: TextureViewDimension,
     size: (u32, u32, u32),
+    compare: bool,
 ) -> crate::shader::RenderSettings {
+    let (compare_mode, compare_split) = match (compare, settings.compare_mode) {
+        (false, _) => (0, 0.0),
+        (true, CompareMode::Difference) => (1, 0.0),
+        (true, CompareMode::Split(split)) => (2, split),
+    };
+    let (channel_mode, channel_remap) = settings.channel_mode.mode_remap();
+
     crate::shader::RenderSettings {
         render_rgba: settings.render_rgba.map(|b| if b { 1.0 } else { 0.0 }),
         mipmap: [settings.mipmap; 4],
@@ -51,9 +139,19 @@ fn shader_settings(
             TextureViewDimension::D2 => 0,
             TextureViewDimension::Cube => 1,
             TextureViewDimension::D3 => 2,
+            TextureViewDimension::D2Array => 3,
             _ => 0,
         }; 4],
         texture_size: [size.0 as f32, size.1 as f32, size.2 as f32, 0.0],
+        compare_mode: [compare_mode; 4],
+        compare_split: [compare_split; 4],
+        channel_mode: [channel_mode; 4],
+        channel_remap,
+        cube_layout: [match settings.cube_layout {
+            CubeLayout::Face => 0,
+            CubeLayout::Cross => 1,
+            CubeLayout::Equirectangular => 2,
+        }; 4],
     }
 }
 
@@ -91,12 +189,27 @@ pub enum CreateTextureError {
         block_height
     )]
     UnalignedHeight { height: u32, block_height: u32 },
+
+    #[error("the cube map width {} and height {} are not equal", width, height)]
+    NonSquareCubeMap { width: u32, height: u32 },
+
+    #[error(
+        "the texture data has {} bytes but requires at least {} bytes",
+        actual,
+        expected
+    )]
+    InsufficientData { expected: u64, actual: u64 },
 }
 
 /// Converts `nutexb` into a texture with the same format.
 ///
 /// sRGB and non sRGB variants of the format are available as view formats.
 /// Using the texture's original format in the view is always available.
+///
+/// Textures with a single mipmap can be given a full mip chain with [generate_mipmaps].
+///
+/// Block compressed textures are decoded on the CPU if the device does not support
+/// [wgpu::Features::TEXTURE_COMPRESSION_BC]. See [create_texture_with_data].
 pub fn create_texture(
     nutexb: &NutexbFile,
     device: &wgpu::Device,
@@ -121,10 +234,11 @@ pub fn create_texture(
         return Err(CreateTextureError::ZeroLayers);
     }
 
+    let limits = device.limits();
     let max_dimension = if nutexb.footer.depth > 1 {
-        Limits::default().max_texture_dimension_3d
+        limits.max_texture_dimension_3d
     } else {
-        Limits::default().max_texture_dimension_2d
+        limits.max_texture_dimension_2d
     };
 
     // TODO: Show dimensions?
@@ -135,7 +249,7 @@ pub fn create_texture(
         return Err(CreateTextureError::DimensionExceedsLimit);
     }
 
-    if nutexb.footer.layer_count > Limits::default().max_texture_array_layers {
+    if nutexb.footer.layer_count > limits.max_texture_array_layers {
         return Err(CreateTextureError::LayerCountExceedsLimit);
     }
 
@@ -160,9 +274,17 @@ pub fn create_texture(
         wgpu::TextureDimension::D2
     };
 
+    if nutexb.footer.layer_count == 6 && size.width != size.height {
+        return Err(CreateTextureError::NonSquareCubeMap {
+            width: size.width,
+            height: size.height,
+        });
+    }
+
     let label = nutexb.footer.string.to_string();
 
     let max_mips = size.max_mips(dimension);
+    let mip_level_count = std::cmp::min(nutexb.footer.mipmap_count, max_mips);
     if nutexb.footer.mipmap_count > max_mips {
         warn!(
             "Mipmap count {} exceeds the maximum of {} for Nutexb {:?}.",
@@ -175,14 +297,24 @@ pub fn create_texture(
         .deswizzled_data()
         .map_err(|_| CreateTextureError::SwizzleError)?;
 
-    let texture = device.create_texture_with_data(
+    // Check the size to avoid panics when creating the texture.
+    let expected = required_data_size(size, dimension, format, mip_level_count);
+    if (data.len() as u64) < expected {
+        return Err(CreateTextureError::InsufficientData {
+            expected,
+            actual: data.len() as u64,
+        });
+    }
+
+    let texture = create_texture_with_data(
+        device,
         queue,
         &wgpu::TextureDescriptor {
             label: Some(&label),
             size,
             // TODO: Should this be an error?
             // TODO: How does in game handle this case?
-            mip_level_count: std::cmp::min(nutexb.footer.mipmap_count, max_mips),
+            mip_level_count,
             sample_count: 1,
             dimension,
             format,
@@ -191,21 +323,58 @@ pub fn create_texture(
                 | wgpu::TextureUsages::TEXTURE_BINDING,
             view_formats: &[format.add_srgb_suffix(), format.remove_srgb_suffix()],
         },
-        wgpu::util::TextureDataOrder::LayerMajor,
         &data,
     );
 
     // Return the dimensions since this isn't accessible from the texture itself.
+    let dim = view_dimension(nutexb.footer.depth, nutexb.footer.layer_count);
+
+    Ok((texture, dim))
+}
+
+fn view_dimension(depth: u32, layer_count: u32) -> wgpu::TextureViewDimension {
     // TODO: Are there other dimensions for nutexb?
-    let dim = if nutexb.footer.depth > 1 {
+    if depth > 1 {
         wgpu::TextureViewDimension::D3
-    } else if nutexb.footer.layer_count == 6 {
+    } else if layer_count == 6 {
         wgpu::TextureViewDimension::Cube
+    } else if layer_count > 1 {
+        wgpu::TextureViewDimension::D2Array
     } else {
         wgpu::TextureViewDimension::D2
+    }
+}
+
+// The size in bytes of the layer major data for all layers and mipmaps.
+fn required_data_size(
+    size: wgpu::Extent3d,
+    dimension: wgpu::TextureDimension,
+    format: wgpu::TextureFormat,
+    mip_level_count: u32,
+) -> u64 {
+    let (block_width, block_height) = format.block_dimensions();
+    let block_size = format.block_copy_size(None).unwrap_or(0) as u64;
+
+    // 3D textures store the depth in each mipmap instead of separate layers.
+    let layers = match dimension {
+        wgpu::TextureDimension::D3 => 1,
+        _ => size.depth_or_array_layers,
     };
 
-    Ok((texture, dim))
+    let layer_size: u64 = (0..mip_level_count)
+        .map(|mip| {
+            let mip_size = size.mip_level_size(mip, dimension);
+            let width_blocks = mip_size.width.div_ceil(block_width) as u64;
+            let height_blocks = mip_size.height.div_ceil(block_height) as u64;
+            let depth = match dimension {
+                wgpu::TextureDimension::D3 => mip_size.depth_or_array_layers as u64,
+                _ => 1,
+            };
+            width_blocks * height_blocks * depth * block_size
+        })
+        .sum();
+
+    layer_size * layers as u64
 }
 
 fn wgpu_format(format: nutexb::NutexbFormat) -> wgpu::TextureFormat {
@@ -240,12 +409,14 @@ pub const RGBA_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
 pub struct TextureRenderer {
     pipeline: wgpu::RenderPipeline,
     rgba_pipeline: wgpu::RenderPipeline,
+    thumbnail_pipeline: wgpu::ComputePipeline,
     settings_buffer: wgpu::Buffer,
     sampler: wgpu::Sampler,
     bindgroup: Option<BindGroup0>,
     // Workaround for sharing the same pipeline.
     // Unused textures still need a resource bound.
     default_2d: wgpu::TextureView,
+    default_2d_array: wgpu::TextureView,
     default_3d: wgpu::TextureView,
     default_cube: wgpu::TextureView,
 }
@@ -270,6 +441,7 @@ impl TextureRenderer {
             &RenderSettings::default(),
             TextureViewDimension::D2,
             (1, 1, 1),
+            false,
         );
         let settings_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("nutexb_wgpu Render Settings"),
@@ -278,16 +450,19 @@ impl TextureRenderer {
         });
 
         let default_2d = default_texture_2d(device, queue);
+        let default_2d_array = default_texture_2d_array(device, queue);
         let default_3d = default_texture_3d(device, queue);
         let default_cube = default_texture_cube(device, queue);
 
         Self {
             pipeline: create_render_pipeline(device, surface_format),
             rgba_pipeline: create_render_pipeline(device, RGBA_FORMAT),
+            thumbnail_pipeline: thumbnail::create_pipeline(device),
             settings_buffer,
             sampler,
             bindgroup: None,
             default_2d,
+            default_2d_array,
             default_3d,
             default_cube,
         }
@@ -318,6 +493,33 @@ impl TextureRenderer {
             device,
             queue,
             texture,
+            None,
+            texture_dimension,
+            texture_size,
+            settings,
+        );
+        self.bindgroup = Some(bind_group);
+    }
+
+    /// Sets the next texture to render from `texture` and `dimension`
+    /// compared against the 2D texture `compare_texture` using [RenderSettings::compare_mode].
+    ///
+    /// This is useful for checking re-encoded textures or regenerated mipmaps against the originals.
+    pub fn update_comparison(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        texture: &wgpu::Texture,
+        compare_texture: &wgpu::Texture,
+        texture_dimension: wgpu::TextureViewDimension,
+        texture_size: (u32, u32, u32),
+        settings: &RenderSettings,
+    ) {
+        let bind_group = self.create_bind_group(
+            device,
+            queue,
+            texture,
+            Some(compare_texture),
             texture_dimension,
             texture_size,
             settings,
@@ -325,12 +527,63 @@ impl TextureRenderer {
         self.bindgroup = Some(bind_group);
     }
 
+    /// Calculate the mean absolute error of the RGBA values of `texture` and the 2D texture `compare_texture`
+    /// in the range `0.0` to `1.0`.
+    ///
+    /// Both textures are first rendered to RGBA at `render_width` x `render_height`
+    /// using the mipmap and layer from `settings`.
+    pub fn mean_absolute_error(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        texture: &wgpu::Texture,
+        compare_texture: &wgpu::Texture,
+        texture_dimension: wgpu::TextureViewDimension,
+        texture_size: (u32, u32, u32),
+        render_width: u32,
+        render_height: u32,
+        settings: &RenderSettings,
+    ) -> f32 {
+        // Compare all channels even if some are disabled for rendering.
+        let settings = RenderSettings {
+            render_rgba: [true; 4],
+            channel_mode: ChannelMode::Rgba,
+            ..settings.clone()
+        };
+
+        let rgba = self.render_to_texture_2d_rgba(
+            device,
+            queue,
+            texture,
+            texture_dimension,
+            texture_size,
+            render_width,
+            render_height,
+            &settings,
+        );
+        let compare_rgba = self.render_to_texture_2d_rgba(
+            device,
+            queue,
+            compare_texture,
+            wgpu::TextureViewDimension::D2,
+            (compare_texture.width(), compare_texture.height(), 1),
+            render_width,
+            render_height,
+            &settings,
+        );
+
+        let data = read_texture_2d_rgba(device, queue, &rgba);
+        let compare_data = read_texture_2d_rgba(device, queue, &compare_rgba);
+        mean_absolute_error(&data, &compare_data)
+    }
+
     /// Render a texture to a 2D RGBA texture.
     ///
     /// This allows compressed textures like BC7 to be used as thumbnails in some applications.
     /// Cube maps and 3D textures will only render a single 2D face or slice based on the render settings.
     ///
     /// The sRGB suffix is ignored to avoid overly dark textures.
+    /// Use [TextureRenderer::render_thumbnails] for efficiently converting many textures.
     pub fn render_to_texture_2d_rgba(
         &self,
         device: &wgpu::Device,
@@ -342,11 +595,11 @@ impl TextureRenderer {
         render_height: u32,
         settings: &RenderSettings,
     ) -> wgpu::Texture {
-        // TODO: Is this more efficient using compute shaders?
         let texture_bind_group = self.create_bind_group(
             device,
             queue,
             texture,
+            None,
             texture_dimension,
             texture_size,
             settings,
@@ -364,7 +617,8 @@ impl TextureRenderer {
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2, // TODO: Convert 3d to 2d?
             format: RGBA_FORMAT,
-            usage: wgpu::TextureUsages::COPY_DST
+            usage: wgpu::TextureUsages::COPY_SRC
+                | wgpu::TextureUsages::COPY_DST
                 | wgpu::TextureUsages::TEXTURE_BINDING
                 | wgpu::TextureUsages::RENDER_ATTACHMENT,
             view_formats: &[],
@@ -413,6 +667,7 @@ impl TextureRenderer {
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         texture: &wgpu::Texture,
+        compare_texture: Option<&wgpu::Texture>,
         dimension: wgpu::TextureViewDimension,
         size: (u32, u32, u32),
         settings: &RenderSettings,
@@ -427,7 +682,15 @@ impl TextureRenderer {
             ..Default::default()
         });
 
-        let shader_settings = shader_settings(settings, dimension, size);
+        let compare_view = compare_texture.map(|t| {
+            t.create_view(&wgpu::TextureViewDescriptor {
+                dimension: Some(wgpu::TextureViewDimension::D2),
+                format: Some(t.format().remove_srgb_suffix()),
+                ..Default::default()
+            })
+        });
+
+        let shader_settings = shader_settings(settings, dimension, size, compare_view.is_some());
         queue.write_buffer(
             &self.settings_buffer,
             0,
@@ -437,21 +700,49 @@ impl TextureRenderer {
         // Workaround for using the same pipeline.
         // Bind all resources and just choose one at render time.
         // TODO: Add dim to render settings.
-        let (t_color_2d, t_color_cube, t_color_3d) = match dimension {
-            TextureViewDimension::D2 => (&view, &self.default_cube, &self.default_3d),
-            TextureViewDimension::Cube => (&self.default_2d, &view, &self.default_3d),
-            TextureViewDimension::D3 => (&self.default_2d, &self.default_cube, &view),
-            _ => (&self.default_2d, &self.default_cube, &self.default_3d),
+        let (t_color_2d, t_color_2d_array, t_color_cube, t_color_3d) = match dimension {
+            TextureViewDimension::D2 => (
+                &view,
+                &self.default_2d_array,
+                &self.default_cube,
+                &self.default_3d,
+            ),
+            TextureViewDimension::D2Array => (
+                &self.default_2d,
+                &view,
+                &self.default_cube,
+                &self.default_3d,
+            ),
+            TextureViewDimension::Cube => (
+                &self.default_2d,
+                &self.default_2d_array,
+                &view,
+                &self.default_3d,
+            ),
+            TextureViewDimension::D3 => (
+                &self.default_2d,
+                &self.default_2d_array,
+                &self.default_cube,
+                &view,
+            ),
+            _ => (
+                &self.default_2d,
+                &self.default_2d_array,
+                &self.default_cube,
+                &self.default_3d,
+            ),
         };
 
         shader::bind_groups::BindGroup0::from_bindings(
             device,
             shader::bind_groups::BindGroupLayout0 {
                 t_color_2d,
+                t_color_2d_array,
                 t_color_cube,
                 t_color_3d,
                 s_color: &self.sampler,
                 render_settings: self.settings_buffer.as_entire_buffer_binding(),
+                t_compare_2d: compare_view.as_ref().unwrap_or(&self.default_2d),
             },
         )
     }
@@ -497,6 +788,80 @@ fn draw_textured_triangle(
     render_pass.draw(0..3, 0..1);
 }
 
+fn read_texture_2d_rgba(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+) -> Vec<u8> {
+    let width = texture.width();
+    let height = texture.height();
+
+    // Copies require rows to be aligned.
+    let unpadded_bytes_per_row = width * 4;
+    let bytes_per_row = unpadded_bytes_per_row.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("nutexb_wgpu Readback Buffer"),
+        size: bytes_per_row as u64 * height as u64,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Readback Encoder"),
+    });
+    encoder.copy_texture_to_buffer(
+        wgpu::ImageCopyTexture {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(bytes_per_row),
+                rows_per_image: Some(height),
+            },
+        },
+        texture.size(),
+    );
+    queue.submit(std::iter::once(encoder.finish()));
+
+    let buffer_slice = buffer.slice(..);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+        sender.send(result).unwrap();
+    });
+    device.poll(wgpu::Maintain::Wait);
+    receiver.recv().unwrap().unwrap();
+
+    // Remove the row padding.
+    let data = buffer_slice
+        .get_mapped_range()
+        .chunks(bytes_per_row as usize)
+        .flat_map(|row| &row[..unpadded_bytes_per_row as usize])
+        .copied()
+        .collect();
+    buffer.unmap();
+    data
+}
+
+fn mean_absolute_error(a: &[u8], b: &[u8]) -> f32 {
+    let count = a.len().min(b.len());
+    if count == 0 {
+        return 0.0;
+    }
+
+    let sum: u64 = a
+        .iter()
+        .zip(b.iter())
+        .map(|(a, b)| a.abs_diff(*b) as u64)
+        .sum();
+    sum as f32 / count as f32 / 255.0
+}
+
 fn default_texture_2d(device: &wgpu::Device, queue: &wgpu::Queue) -> wgpu::TextureView {
     let size = Extent3d {
         width: 1,
@@ -525,6 +890,34 @@ fn default_texture_2d(device: &wgpu::Device, queue: &wgpu::Queue) -> wgpu::Textu
         })
 }
 
+fn default_texture_2d_array(device: &wgpu::Device, queue: &wgpu::Queue) -> wgpu::TextureView {
+    let size = Extent3d {
+        width: 1,
+        height: 1,
+        depth_or_array_layers: 1,
+    };
+    device
+        .create_texture_with_data(
+            queue,
+            &TextureDescriptor {
+                label: Some("nutexb_wgpu Default 2D Array"),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: TextureFormat::Rgba8Unorm,
+                usage: TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            &[0; 4],
+        )
+        .create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        })
+}
+
 fn default_texture_3d(device: &wgpu::Device, queue: &wgpu::Queue) -> wgpu::TextureView {
     let size = Extent3d {
         width: 1,
@@ -580,3 +973,116 @@ fn default_texture_cube(device: &wgpu::Device, queue: &wgpu::Queue) -> wgpu::Tex
             ..Default::default()
         })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn required_data_size_bc7_mipmaps() {
+        // 16x16 with 4x4 blocks of 16 bytes: 4*4*16 + 2*2*16 + 1*1*16 + 1*1*16
+        let size = wgpu::Extent3d {
+            width: 16,
+            height: 16,
+            depth_or_array_layers: 1,
+        };
+        assert_eq!(
+            352,
+            required_data_size(
+                size,
+                wgpu::TextureDimension::D2,
+                wgpu::TextureFormat::Bc7RgbaUnorm,
+                4
+            )
+        );
+    }
+
+    #[test]
+    fn required_data_size_cube_map() {
+        let size = wgpu::Extent3d {
+            width: 4,
+            height: 4,
+            depth_or_array_layers: 6,
+        };
+        assert_eq!(
+            6 * (64 + 16 + 4),
+            required_data_size(
+                size,
+                wgpu::TextureDimension::D2,
+                wgpu::TextureFormat::Rgba8Unorm,
+                3
+            )
+        );
+    }
+
+    #[test]
+    fn required_data_size_3d() {
+        let size = wgpu::Extent3d {
+            width: 4,
+            height: 4,
+            depth_or_array_layers: 4,
+        };
+        assert_eq!(
+            4 * (64 + 8),
+            required_data_size(
+                size,
+                wgpu::TextureDimension::D3,
+                wgpu::TextureFormat::Rgba8Unorm,
+                2
+            )
+        );
+    }
+
+    #[test]
+    fn view_dimension_layers() {
+        assert_eq!(wgpu::TextureViewDimension::D2, view_dimension(1, 1));
+        assert_eq!(wgpu::TextureViewDimension::D2Array, view_dimension(1, 2));
+        assert_eq!(wgpu::TextureViewDimension::Cube, view_dimension(1, 6));
+        assert_eq!(wgpu::TextureViewDimension::D2Array, view_dimension(1, 12));
+        assert_eq!(wgpu::TextureViewDimension::D3, view_dimension(4, 1));
+    }
+
+    #[test]
+    fn mean_absolute_error_empty() {
+        assert_eq!(0.0, mean_absolute_error(&[], &[]));
+    }
+
+    #[test]
+    fn mean_absolute_error_identical() {
+        assert_eq!(0.0, mean_absolute_error(&[1, 2, 3, 4], &[1, 2, 3, 4]));
+    }
+
+    #[test]
+    fn mean_absolute_error_different() {
+        assert_eq!(
+            0.5,
+            mean_absolute_error(&[0, 255, 0, 255], &[255, 255, 0, 0])
+        );
+    }
+
+    #[test]
+    fn shader_settings_channel_modes() {
+        let channels = |channel_mode| {
+            let settings = RenderSettings {
+                channel_mode,
+                ..Default::default()
+            };
+            let s = shader_settings(&settings, TextureViewDimension::D2, (1, 1, 1), false);
+            (s.channel_mode[0], s.channel_remap)
+        };
+        assert_eq!((0, [0, 1, 2, 3]), channels(ChannelMode::Rgba));
+        assert_eq!(
+            (1, [3, 3, 3, 3]),
+            channels(ChannelMode::Grayscale(Channel::Alpha))
+        );
+        assert_eq!(
+            (1, [1, 0, 2, 3]),
+            channels(ChannelMode::Remap([
+                Channel::Green,
+                Channel::Red,
+                Channel::Blue
+            ]))
+        );
+        assert_eq!((2, [0, 1, 2, 3]), channels(ChannelMode::NormalMap));
+    }
+}
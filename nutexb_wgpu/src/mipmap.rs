@@ -0,0 +1,248 @@
+use thiserror::Error;
+use wgpu::{TextureDimension, TextureFormat};
+
+#[allow(dead_code)]
+mod shader {
+    include!(concat!(env!("OUT_DIR"), "/mipmap.rs"));
+}
+
+/// Errors that can occur while generating mipmaps with [generate_mipmaps].
+#[derive(Debug, Error)]
+pub enum GenerateMipmapsError {
+    #[error("generating mipmaps is not supported for {:?} textures", dimension)]
+    UnsupportedDimension { dimension: TextureDimension },
+
+    #[error("generating mipmaps is not supported for {:?} textures", format)]
+    UnsupportedFormat { format: TextureFormat },
+}
+
+/// Creates a copy of the 2D, 2D array, or cube map `texture` with a full mip chain
+/// generated on the GPU by repeatedly halving the base mip level.
+///
+/// This is useful for textures created with a single mipmap that alias when viewed at a distance.
+/// Any existing mipmaps after the base level are replaced.
+///
+/// Formats that can't be rendered to like BC7 are decoded to an uncompressed format.
+/// See [mipmap_format] for the format of the returned texture.
+/// Integer and depth formats are not supported.
+pub fn generate_mipmaps(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+) -> Result<wgpu::Texture, GenerateMipmapsError> {
+    // TODO: Support 3D textures using a compute shader?
+    if texture.dimension() != TextureDimension::D2 {
+        return Err(GenerateMipmapsError::UnsupportedDimension {
+            dimension: texture.dimension(),
+        });
+    }
+
+    // Integer and depth textures can't be averaged as floats.
+    if !matches!(
+        texture.format().sample_type(None, Some(device.features())),
+        Some(wgpu::TextureSampleType::Float { .. })
+    ) {
+        return Err(GenerateMipmapsError::UnsupportedFormat {
+            format: texture.format(),
+        });
+    }
+
+    let size = texture.size();
+    let format = mipmap_format(device, texture.format());
+    let mip_level_count = size.max_mips(TextureDimension::D2);
+
+    let output = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("nutexb_wgpu Mipmaps"),
+        size,
+        mip_level_count,
+        sample_count: 1,
+        dimension: TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::COPY_SRC
+            | wgpu::TextureUsages::COPY_DST
+            | wgpu::TextureUsages::TEXTURE_BINDING
+            | wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[format.add_srgb_suffix(), format.remove_srgb_suffix()],
+    });
+
+    let bind_group_layout = create_bind_group_layout(device);
+    let copy_pipeline = create_pipeline(device, &bind_group_layout, format, "fs_copy");
+    let downsample_pipeline = create_pipeline(device, &bind_group_layout, format, "fs_downsample");
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("nutexb_wgpu Mipmap Encoder"),
+    });
+
+    // Cube maps and arrays generate mipmaps for each layer separately.
+    for layer in 0..size.depth_or_array_layers {
+        // Render the base level from the original texture to also decode compressed formats.
+        // Sampling at texel centers makes this an exact copy for uncompressed formats.
+        let mut source = mip_view(texture, layer, 0);
+
+        for mip in 0..mip_level_count {
+            let target = mip_view(&output, layer, mip);
+
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("nutexb_wgpu Mipmap Bind Group"),
+                layout: &bind_group_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&source),
+                }],
+            });
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("nutexb_wgpu Mipmap Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            if mip == 0 {
+                render_pass.set_pipeline(&copy_pipeline);
+            } else {
+                render_pass.set_pipeline(&downsample_pipeline);
+            }
+            render_pass.set_bind_group(0, &bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+            drop(render_pass);
+
+            // Each level is downsampled from the previous level.
+            source = target;
+        }
+    }
+
+    queue.submit(std::iter::once(encoder.finish()));
+
+    Ok(output)
+}
+
+/// The format of the texture created by [generate_mipmaps] for a texture with the given `format`.
+///
+/// Formats that support rendering and linear filtering are unchanged.
+/// Other formats use an uncompressed format with the same color space.
+pub fn mipmap_format(device: &wgpu::Device, format: TextureFormat) -> TextureFormat {
+    let features = format.guaranteed_format_features(device.features());
+    if features
+        .allowed_usages
+        .contains(wgpu::TextureUsages::RENDER_ATTACHMENT)
+        && features
+            .flags
+            .contains(wgpu::TextureFormatFeatureFlags::FILTERABLE)
+    {
+        format
+    } else {
+        uncompressed_format(format)
+    }
+}
+
+fn uncompressed_format(format: TextureFormat) -> TextureFormat {
+    match format {
+        // Preserve values outside the range 0.0 to 1.0.
+        TextureFormat::Rgba32Float
+        | TextureFormat::Bc6hRgbFloat
+        | TextureFormat::Bc6hRgbUfloat
+        | TextureFormat::Bc4RSnorm
+        | TextureFormat::Bc5RgSnorm => TextureFormat::Rgba16Float,
+        f if f.is_srgb() => TextureFormat::Rgba8UnormSrgb,
+        _ => TextureFormat::Rgba8Unorm,
+    }
+}
+
+fn mip_view(texture: &wgpu::Texture, layer: u32, mip: u32) -> wgpu::TextureView {
+    texture.create_view(&wgpu::TextureViewDescriptor {
+        dimension: Some(wgpu::TextureViewDimension::D2),
+        base_mip_level: mip,
+        mip_level_count: Some(1),
+        base_array_layer: layer,
+        array_layer_count: Some(1),
+        ..Default::default()
+    })
+}
+
+fn create_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    // The generated layout assumes filterable textures.
+    // Formats like Rgba32Float can only be bound as unfilterable without additional features.
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("nutexb_wgpu Mipmap Bind Group Layout"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        }],
+    })
+}
+
+fn create_pipeline(
+    device: &wgpu::Device,
+    bind_group_layout: &wgpu::BindGroupLayout,
+    format: TextureFormat,
+    entry_point: &str,
+) -> wgpu::RenderPipeline {
+    let module = shader::create_shader_module(device);
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("nutexb_wgpu Mipmap Pipeline Layout"),
+        bind_group_layouts: &[bind_group_layout],
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("nutexb_wgpu Mipmap Pipeline"),
+        layout: Some(&layout),
+        vertex: wgpu::VertexState {
+            module: &module,
+            entry_point: Some("vs_main"),
+            buffers: &[],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &module,
+            entry_point: Some(entry_point),
+            targets: &[Some(format.into())],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uncompressed_formats() {
+        assert_eq!(
+            TextureFormat::Rgba8UnormSrgb,
+            uncompressed_format(TextureFormat::Bc7RgbaUnormSrgb)
+        );
+        assert_eq!(
+            TextureFormat::Rgba8Unorm,
+            uncompressed_format(TextureFormat::Bc1RgbaUnorm)
+        );
+        assert_eq!(
+            TextureFormat::Rgba16Float,
+            uncompressed_format(TextureFormat::Bc6hRgbUfloat)
+        );
+        assert_eq!(
+            TextureFormat::Rgba16Float,
+            uncompressed_format(TextureFormat::Bc5RgSnorm)
+        );
+    }
+}
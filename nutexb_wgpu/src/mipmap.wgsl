@@ -0,0 +1,39 @@
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
+    // A fullscreen triangle using index calculations.
+    var out: VertexOutput;
+    let x = f32((i32(in_vertex_index) << 1u) & 2);
+    let y = f32(i32(in_vertex_index & 2u));
+    out.clip_position = vec4(x * 2.0 - 1.0, y * 2.0 - 1.0, 0.0, 1.0);
+    out.tex_coords = vec2(x, 1.0 - y);
+    return out;
+}
+
+// The view only contains the previous mip level.
+// Load texels without a sampler to support formats without filtering like Rgba32Float.
+@group(0) @binding(0)
+var t_color: texture_2d<f32>;
+
+@fragment
+fn fs_copy(in: VertexOutput) -> @location(0) vec4<f32> {
+    // The base level has the same size as the source.
+    return textureLoad(t_color, vec2<u32>(in.clip_position.xy), 0);
+}
+
+@fragment
+fn fs_downsample(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Average each 2x2 block of texels when halving the size.
+    // Clamp to the edge for dimensions that can't be halved further.
+    let max_coords = textureDimensions(t_color) - 1u;
+    let coords = vec2<u32>(in.clip_position.xy) * 2u;
+    let c00 = textureLoad(t_color, min(coords, max_coords), 0);
+    let c10 = textureLoad(t_color, min(coords + vec2(1u, 0u), max_coords), 0);
+    let c01 = textureLoad(t_color, min(coords + vec2(0u, 1u), max_coords), 0);
+    let c11 = textureLoad(t_color, min(coords + vec2(1u, 1u), max_coords), 0);
+    return (c00 + c10 + c01 + c11) * 0.25;
+}
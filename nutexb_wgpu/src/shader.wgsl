@@ -20,6 +20,14 @@ struct RenderSettings {
     layer: vec4<u32>,
     texture_slot: vec4<u32>,
     texture_size: vec4<f32>,
+    compare_mode: vec4<u32>,
+    compare_split: vec4<f32>,
+    // mode, _, _, _
+    channel_mode: vec4<u32>,
+    // The input channel index for each output channel.
+    channel_remap: vec4<u32>,
+    // layout, _, _, _
+    cube_layout: vec4<u32>,
 };
 
 @group(0) @binding(0)
@@ -34,62 +42,152 @@ var s_color: sampler;
 @group(0) @binding(4)
 var<uniform> render_settings: RenderSettings;
 
-@fragment
-fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+// The second texture for comparisons is always 2D.
+@group(0) @binding(5)
+var t_compare_2d: texture_2d<f32>;
+
+@group(0) @binding(6)
+var t_color_2d_array: texture_2d_array<f32>;
+
+fn cube_face_coords(face: u32, tex_coords: vec2<f32>) -> vec3<f32> {
+    // Match the orientation of an array of 2D textures when selecting faces.
+    // This matches the behavior of many texture viewers.
+    switch (face) {
+        case 0u: {
+            // X+
+            return normalize(vec3(1.0, (1.0 - tex_coords.yx) * 2.0 - 1.0));
+        }
+        case 1u: {
+            // X-
+            return normalize(vec3(-1.0, (1.0 - tex_coords.y) * 2.0 - 1.0, tex_coords.x * 2.0 - 1.0));
+        }
+        case 2u: {
+            // Y+
+            return normalize(vec3(tex_coords.x * 2.0 - 1.0, 1.0, tex_coords.y * 2.0 - 1.0));
+        }
+        case 3u: {
+            // Y-
+            return normalize(vec3(tex_coords.x * 2.0 - 1.0, -1.0, (1.0 - tex_coords.y) * 2.0 - 1.0));
+        }
+        case 4u: {
+            // Z+
+            return normalize(vec3(tex_coords.x * 2.0 - 1.0, (1.0 - tex_coords.y) * 2.0 - 1.0, 1.0));
+        }
+        case 5u: {
+            // Z-
+            return normalize(vec3((1.0 - tex_coords.x) * 2.0 - 1.0, (1.0 - tex_coords.y) * 2.0 - 1.0, -1.0));
+        }
+        default: {
+            // Use X+ by default.
+            return normalize(vec3(1.0, (1.0 - tex_coords.yx) * 2.0 - 1.0));
+        }
+    }
+}
+
+// Returns the zero vector for regions that don't contain a face.
+fn cube_coords(tex_coords: vec2<f32>) -> vec3<f32> {
+    switch (render_settings.cube_layout.x) {
+        case 1u: {
+            // Horizontal cross with Y+ and Y- above and below Z+.
+            //    Y+
+            // X- Z+ X+ Z-
+            //    Y-
+            let grid = tex_coords * vec2(4.0, 3.0);
+            let cell = vec2<u32>(clamp(grid, vec2(0.0), vec2(3.0, 2.0)));
+            let face_coords = fract(grid);
+            if (cell.y == 1u) {
+                let faces = array<u32, 4>(1u, 4u, 0u, 5u);
+                return cube_face_coords(faces[cell.x], face_coords);
+            } else if (cell.x == 1u) {
+                return cube_face_coords(select(3u, 2u, cell.y == 0u), face_coords);
+            }
+            return vec3(0.0);
+        }
+        case 2u: {
+            // Equirectangular with the center of Z+ in the center.
+            let longitude = (tex_coords.x * 2.0 - 1.0) * 3.14159265;
+            let latitude = (0.5 - tex_coords.y) * 3.14159265;
+            return vec3(
+                sin(longitude) * cos(latitude),
+                sin(latitude),
+                cos(longitude) * cos(latitude)
+            );
+        }
+        default: {
+            return cube_face_coords(render_settings.layer.x, tex_coords);
+        }
+    }
+}
+
+fn sample_texture(tex_coords: vec2<f32>) -> vec4<f32> {
     var outColor = vec4(0.0);
     switch (render_settings.texture_slot.x) {
         case 0u: {
             // 2D
-            outColor = textureSampleLevel(t_color_2d, s_color, in.tex_coords, render_settings.mipmap.x);
+            outColor = textureSampleLevel(t_color_2d, s_color, tex_coords, render_settings.mipmap.x);
         }
         case 1u: {
             // Cube
-            // Match the orientation of an array of 2D textures when selecting faces.
-            // This matches the behavior of many texture viewers.
-            var coords = vec3(0.0);
-            switch (render_settings.layer.x) {
-                case 0u: {
-                    // X+
-                    coords = normalize(vec3(1.0, (1.0 - in.tex_coords.yx) * 2.0 - 1.0));
-                }
-                case 1u: {
-                    // X-
-                    coords = normalize(vec3(-1.0, (1.0 - in.tex_coords.y) * 2.0 - 1.0, in.tex_coords.x * 2.0 - 1.0));
-                }
-                case 2u: {
-                    // Y+
-                    coords = normalize(vec3(in.tex_coords.x * 2.0 - 1.0, 1.0, in.tex_coords.y * 2.0 - 1.0));
-                }
-                case 3u: {
-                    // Y-
-                    coords = normalize(vec3(in.tex_coords.x * 2.0 - 1.0, -1.0, (1.0 - in.tex_coords.y) * 2.0 - 1.0));
-                }
-                case 4u: {
-                    // Z+
-                    coords = normalize(vec3(in.tex_coords.x * 2.0 - 1.0, (1.0 - in.tex_coords.y) * 2.0 - 1.0, 1.0));
-                }
-                case 5u: {
-                    // Z-
-                    coords = normalize(vec3((1.0 - in.tex_coords.x) * 2.0 - 1.0, (1.0 - in.tex_coords.y) * 2.0 - 1.0, -1.0));
-                }
-                default: {
-                    // Use X+ by default.
-                    coords = normalize(vec3(1.0, (1.0 - in.tex_coords.yx) * 2.0 - 1.0));
-                }
+            let coords = cube_coords(tex_coords);
+            if (all(coords == vec3(0.0))) {
+                // Unused regions of the cross layout.
+                outColor = vec4(0.0);
+            } else {
+                outColor = textureSampleLevel(t_color_cube, s_color, coords, render_settings.mipmap.x);
             }
-             
-            outColor = textureSampleLevel(t_color_cube, s_color, coords, render_settings.mipmap.x);
         }
         case 2u: {
             // 3D
-            let coords = vec3(in.tex_coords, f32(render_settings.layer.x) / render_settings.texture_size.z);
+            let coords = vec3(tex_coords, f32(render_settings.layer.x) / render_settings.texture_size.z);
             outColor = textureSampleLevel(t_color_3d, s_color, coords, render_settings.mipmap.x);
         }
+        case 3u: {
+            // 2D Array
+            outColor = textureSampleLevel(t_color_2d_array, s_color, tex_coords, render_settings.layer.x, render_settings.mipmap.x);
+        }
         default: {
             outColor = vec4(0.0);
         }
     }
-    
+
+    return outColor;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    var outColor = sample_texture(in.tex_coords);
+
+    switch (render_settings.compare_mode.x) {
+        case 1u: {
+            // Difference
+            let compareColor = textureSampleLevel(t_compare_2d, s_color, in.tex_coords, render_settings.mipmap.x);
+            outColor = abs(outColor - compareColor);
+        }
+        case 2u: {
+            // Split with the original texture on the left.
+            if (in.tex_coords.x > render_settings.compare_split.x) {
+                outColor = textureSampleLevel(t_compare_2d, s_color, in.tex_coords, render_settings.mipmap.x);
+            }
+        }
+        default: {}
+    }
+
+    switch (render_settings.channel_mode.x) {
+        case 1u: {
+            // Remap
+            let remap = render_settings.channel_remap;
+            return vec4(outColor[remap.x], outColor[remap.y], outColor[remap.z], 1.0);
+        }
+        case 2u: {
+            // Normal map
+            // Reconstruct Z from XY like the game does for two channel normal maps.
+            let xy = outColor.rg * 2.0 - 1.0;
+            let z = sqrt(max(1.0 - dot(xy, xy), 0.0));
+            return vec4(vec3(xy, z) * 0.5 + 0.5, 1.0);
+        }
+        default: {}
+    }
+
     // Use grayscale for single channels.
     let rgba = render_settings.render_rgba;
     if (rgba.r == 1.0 && rgba.g == 0.0 && rgba.b == 0.0) {
@@ -0,0 +1,104 @@
+use wgpu::{TextureDimension, TextureViewDimension};
+
+use crate::{TextureRenderer, RGBA_FORMAT};
+
+#[allow(dead_code)]
+mod shader {
+    include!(concat!(env!("OUT_DIR"), "/thumbnail.rs"));
+}
+
+pub(crate) fn create_pipeline(device: &wgpu::Device) -> wgpu::ComputePipeline {
+    shader::compute::create_main_pipeline(device)
+}
+
+impl TextureRenderer {
+    /// Render each of the `textures` to a `width` x `height` 2D RGBA texture
+    /// using a compute shader with a single queue submission.
+    ///
+    /// This is much faster than [TextureRenderer::render_to_texture_2d_rgba]
+    /// for generating thumbnails for an entire folder of textures.
+    /// Cube maps and 2D array textures use the first face or layer.
+    /// 3D textures are not supported and produce black thumbnails.
+    ///
+    /// The returned textures have the format [RGBA_FORMAT] and support copies for reading the data.
+    /// The sRGB suffix is ignored to avoid overly dark textures.
+    pub fn render_thumbnails(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        textures: &[&wgpu::Texture],
+        width: u32,
+        height: u32,
+    ) -> Vec<wgpu::Texture> {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("nutexb_wgpu Thumbnail Encoder"),
+        });
+
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("nutexb_wgpu Thumbnail Pass"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_pipeline(&self.thumbnail_pipeline);
+
+        let [workgroup_x, workgroup_y, _] = shader::compute::MAIN_WORKGROUP_SIZE;
+
+        let thumbnails = textures
+            .iter()
+            .map(|texture| {
+                let output = device.create_texture(&wgpu::TextureDescriptor {
+                    label: Some("nutexb_wgpu Thumbnail"),
+                    size: wgpu::Extent3d {
+                        width,
+                        height,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    format: RGBA_FORMAT,
+                    usage: wgpu::TextureUsages::COPY_SRC
+                        | wgpu::TextureUsages::TEXTURE_BINDING
+                        | wgpu::TextureUsages::STORAGE_BINDING,
+                    view_formats: &[],
+                });
+
+                if texture.dimension() == TextureDimension::D2 {
+                    // Views of the first layer work for 2D, 2D array, and cube textures.
+                    let view = texture.create_view(&wgpu::TextureViewDescriptor {
+                        dimension: Some(TextureViewDimension::D2Array),
+                        format: Some(texture.format().remove_srgb_suffix()),
+                        array_layer_count: Some(1),
+                        ..Default::default()
+                    });
+                    let output_view = output.create_view(&wgpu::TextureViewDescriptor::default());
+
+                    let bind_group = shader::bind_groups::BindGroup0::from_bindings(
+                        device,
+                        shader::bind_groups::BindGroupLayout0 {
+                            t_color: &view,
+                            s_color: &self.sampler,
+                            output: &output_view,
+                        },
+                    );
+                    shader::set_bind_groups(&mut compute_pass, &bind_group);
+
+                    // Round up to cover the entire output.
+                    compute_pass.dispatch_workgroups(
+                        width.div_ceil(workgroup_x),
+                        height.div_ceil(workgroup_y),
+                        1,
+                    );
+                }
+
+                output
+            })
+            .collect();
+
+        drop(compute_pass);
+
+        // Ensure the texture writes happen before returning the textures.
+        queue.submit(std::iter::once(encoder.finish()));
+
+        thumbnails
+    }
+}
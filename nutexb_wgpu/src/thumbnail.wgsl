@@ -0,0 +1,22 @@
+@group(0) @binding(0)
+var t_color: texture_2d_array<f32>;
+@group(0) @binding(1)
+var s_color: sampler;
+@group(0) @binding(2)
+var output: texture_storage_2d<rgba8unorm, write>;
+
+@compute @workgroup_size(8, 8, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(output);
+    if (id.x >= size.x || id.y >= size.y) {
+        return;
+    }
+
+    // Select the mip level closest to the output size to reduce aliasing.
+    let scale = vec2<f32>(textureDimensions(t_color).xy) / vec2<f32>(size);
+    let level = max(log2(max(scale.x, scale.y)), 0.0);
+
+    let tex_coords = (vec2<f32>(id.xy) + 0.5) / vec2<f32>(size);
+    let color = textureSampleLevel(t_color, s_color, tex_coords, 0, level);
+    textureStore(output, id.xy, color);
+}
@@ -39,7 +39,7 @@ impl<'a> State<'a> {
             .request_device(
                 &wgpu::DeviceDescriptor {
                     label: None,
-                    required_features: wgpu::Features::TEXTURE_COMPRESSION_BC,
+                    required_features: adapter.features() & wgpu::Features::TEXTURE_COMPRESSION_BC,
                     required_limits: wgpu::Limits::default(),
                     memory_hints: wgpu::MemoryHints::default(),
                 },
@@ -74,6 +74,7 @@ impl<'a> State<'a> {
             render_rgba: [true; 4],
             mipmap,
             layer,
+            ..Default::default()
         };
 
         // Use the full texture width and height.
@@ -0,0 +1,146 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use futures::executor::block_on;
+use ssbh_data::mesh_data::{AttributeData, MeshObjectData, VectorData};
+use ssbh_data::prelude::*;
+use ssbh_wgpu::{
+    ModelFolder, ModelRenderOptions, RenderModel, SharedRenderData, SsbhRenderer,
+    OPTIONAL_FEATURES, REQUIRED_FEATURES,
+};
+
+// Stages have hundreds of mesh objects compared to fighters.
+const MESH_COUNTS: [usize; 4] = [1, 100, 500, 1000];
+const VERTEX_COUNT: usize = 300;
+
+fn device() -> Option<(wgpu::Device, wgpu::Queue)> {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::all(),
+        ..Default::default()
+    });
+    let adapter = block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+        power_preference: wgpu::PowerPreference::HighPerformance,
+        ..Default::default()
+    }))?;
+    block_on(adapter.request_device(
+        &wgpu::DeviceDescriptor {
+            label: None,
+            required_features: REQUIRED_FEATURES | (adapter.features() & OPTIONAL_FEATURES),
+            required_limits: wgpu::Limits::default(),
+            memory_hints: wgpu::MemoryHints::default(),
+        },
+        None,
+    ))
+    .ok()
+}
+
+fn stage_folder(mesh_count: usize) -> ModelFolder {
+    let objects = (0..mesh_count)
+        .map(|i| MeshObjectData {
+            name: format!("mesh{i}"),
+            positions: vec![AttributeData {
+                name: "Position0".to_string(),
+                data: VectorData::Vector3(vec![[i as f32, 0.0, 0.0]; VERTEX_COUNT]),
+            }],
+            vertex_indices: (0..VERTEX_COUNT as u32).collect(),
+            ..Default::default()
+        })
+        .collect();
+
+    ModelFolder {
+        meshes: vec![(
+            "model.numshb".to_owned(),
+            Ok(MeshData {
+                major_version: 1,
+                minor_version: 10,
+                objects,
+            }),
+        )],
+        meshexes: Vec::new(),
+        skels: Vec::new(),
+        matls: Vec::new(),
+        modls: Vec::new(),
+        adjs: Vec::new(),
+        anims: Vec::new(),
+        hlpbs: Vec::new(),
+        nutexbs: Vec::new(),
+        xmbs: Vec::new(),
+        custom_files: Vec::new(),
+    }
+}
+
+fn create_stage_model_benchmark(c: &mut Criterion) {
+    let Some((device, queue)) = device() else {
+        eprintln!("Skipping GPU benchmarks without a compatible adapter.");
+        return;
+    };
+    let shared_data = SharedRenderData::new(&device, &queue);
+
+    let mut group = c.benchmark_group("create_stage_model");
+    group.sample_size(10);
+    for count in MESH_COUNTS {
+        let folder = stage_folder(count);
+
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(count), &count, |b, _| {
+            b.iter(|| RenderModel::from_folder(&device, &queue, &folder, &shared_data));
+        });
+    }
+    group.finish();
+}
+
+fn render_stage_model_benchmark(c: &mut Criterion) {
+    let Some((device, queue)) = device() else {
+        eprintln!("Skipping GPU benchmarks without a compatible adapter.");
+        return;
+    };
+    let shared_data = SharedRenderData::new(&device, &queue);
+
+    let format = wgpu::TextureFormat::Rgba8Unorm;
+    let renderer = SsbhRenderer::new(&device, &queue, 512, 512, 1.0, [0.0; 4], format);
+    let output = device.create_texture(&wgpu::TextureDescriptor {
+        label: None,
+        size: wgpu::Extent3d {
+            width: 512,
+            height: 512,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+    let output_view = output.create_view(&wgpu::TextureViewDescriptor::default());
+
+    // Skinning is dispatched for each mesh every frame even without materials.
+    let mut group = c.benchmark_group("render_stage_model");
+    for count in MESH_COUNTS {
+        let model = RenderModel::from_folder(&device, &queue, &stage_folder(count), &shared_data);
+        let models = [model];
+
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(count), &count, |b, _| {
+            b.iter(|| {
+                let mut encoder =
+                    device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+                drop(renderer.render_models(
+                    &mut encoder,
+                    &output_view,
+                    &models,
+                    shared_data.database(),
+                    &ModelRenderOptions::default(),
+                ));
+                queue.submit([encoder.finish()]);
+                device.poll(wgpu::Maintain::Wait);
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    create_stage_model_benchmark,
+    render_stage_model_benchmark
+);
+criterion_main!(benches);
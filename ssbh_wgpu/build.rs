@@ -18,26 +18,45 @@ fn write_shader_module(wgsl_source: &str, shader_name: &str) {
     std::fs::write(format!("{out_dir}/{shader_name}.rs"), text.as_bytes()).unwrap();
 }
 
+// Shaders created from another shader by replacing text like binding types.
+// Each variant is (shader_name, source_path, [(from, to)]).
+const SHADER_VARIANTS: &[(&str, &str, &[(&str, &str)])] = &[(
+    "dof_single_sample",
+    "src/shader/dof.wgsl",
+    // The depth texture binding type depends on the sample count.
+    &[("texture_depth_multisampled_2d", "texture_depth_2d")],
+)];
+
 fn main() {
     // TODO: Only rerun if the shaders change?
-    let mut shader_paths: Vec<_> = std::fs::read_dir("src/shader")
+    let mut shaders: Vec<_> = std::fs::read_dir("src/shader")
         .unwrap()
         .filter_map(|p| Some(p.ok()?.path()))
         .filter(|p| p.extension().unwrap().to_string_lossy() == "wgsl")
+        .map(|p| {
+            let shader_name = p.file_stem().unwrap().to_string_lossy().to_string();
+            let wgsl_source = std::fs::read_to_string(p).unwrap();
+            (shader_name, wgsl_source)
+        })
         .collect();
 
+    for (shader_name, source_path, replacements) in SHADER_VARIANTS {
+        let wgsl_source = replacements.iter().fold(
+            std::fs::read_to_string(source_path).unwrap(),
+            |source, (from, to)| source.replace(from, to),
+        );
+        shaders.push((shader_name.to_string(), wgsl_source));
+    }
+
     // Use alphabetical order for consistency.
-    shader_paths.sort();
+    shaders.sort();
 
     let mut f = String::new();
     writeln!(&mut f, "// File automatically generated by build.rs.").unwrap();
     writeln!(&mut f, "// Changes made to this file will not be saved.").unwrap();
 
     // Create each shader module and add it to shader.rs.
-    for shader_path in shader_paths {
-        let file_name = shader_path.with_extension("");
-        let shader_name = file_name.file_name().unwrap().to_string_lossy().to_string();
-
+    for (shader_name, wgsl_source) in shaders {
         writeln!(&mut f, "#[allow(dead_code)]").unwrap();
         writeln!(&mut f, "pub mod {shader_name} {{").unwrap();
         writeln!(
@@ -47,7 +66,6 @@ fn main() {
         .unwrap();
         writeln!(&mut f, "}}").unwrap();
 
-        let wgsl_source = std::fs::read_to_string(shader_path).unwrap();
         write_shader_module(&wgsl_source, &shader_name);
     }
 
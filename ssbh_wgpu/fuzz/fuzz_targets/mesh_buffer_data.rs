@@ -0,0 +1,20 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use ssbh_wgpu::{fuzzing::mesh_buffer_data, ModelFolder};
+use wgpu::Limits;
+
+fuzz_target!(|model: ModelFolder| {
+    // Check for panics when converting mesh data without needing a GPU.
+    let data = mesh_buffer_data(
+        model.find_mesh(),
+        model.find_skel(),
+        model.find_adj(),
+        &Limits::default(),
+    );
+
+    let index_count: usize = model
+        .find_mesh()
+        .map(|m| m.objects.iter().map(|o| o.vertex_indices.len()).sum())
+        .unwrap_or_default();
+    assert!(data.indices.len() <= index_count);
+});
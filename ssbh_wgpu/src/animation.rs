@@ -1,9 +1,12 @@
+use std::{collections::BTreeMap, str::FromStr};
+
 use self::constraints::{apply_aim_constraint, apply_orient_constraint};
-use crate::{shader::skinning::AnimatedWorldTransforms, RenderMesh};
+use crate::{shader::skinning::SkinningTransform, MaterialValue, RenderMesh};
 use indexmap::IndexSet;
 use ssbh_data::{
-    anim_data::{GroupType, TrackValues, TransformFlags},
-    matl_data::MatlEntryData,
+    anim_data::{GroupData, GroupType, NodeData, TrackData, TrackValues, TransformFlags},
+    hlpb_data::{AimConstraintData, OrientConstraintData},
+    matl_data::{MatlEntryData, ParamId},
     prelude::*,
     skel_data::BoneData,
     Vector3, Vector4,
@@ -12,8 +15,9 @@ use ssbh_data::{
 pub mod camera;
 mod constraints;
 pub mod lighting;
+pub mod retarget;
 
-/// The maximum number of bones supported by the shader's uniform buffer.
+/// The maximum number of bones supported by the shader's uniform and storage buffers.
 pub const MAX_BONE_COUNT: usize = 512;
 
 // Animation process is Skel, Anim -> Vec<AnimatedBone> -> [Mat4; 512], [Mat4; 512] -> Buffers.
@@ -65,6 +69,15 @@ impl<'a> AnimatedBone<'a> {
     fn transform(&self) -> glam::Mat4 {
         glam::Mat4::from_cols_array_2d(&self.bone.transform)
     }
+
+    fn rest_transform(&self) -> AnimTransform {
+        let (scale, rotation, translation) = self.transform().to_scale_rotation_translation();
+        AnimTransform {
+            translation,
+            rotation,
+            scale,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -93,6 +106,56 @@ impl AnimTransform {
         // The order is reversed here since glam is column-major.
         translation * glam::Mat4::from_scale(scale_compensation) * rotation * scale
     }
+
+    // Apply the difference between additive and rest to self.
+    fn add(&self, additive: &Self, rest: &Self) -> Self {
+        Self {
+            translation: self.translation + (additive.translation - rest.translation),
+            rotation: (self.rotation * rest.rotation.inverse() * additive.rotation).normalize(),
+            scale: self.scale * additive.scale / rest.scale,
+        }
+    }
+
+    fn blend(&self, other: &Self, factor: f32) -> Self {
+        Self {
+            translation: self.translation.lerp(other.translation, factor),
+            rotation: self.rotation.slerp(other.rotation, factor),
+            scale: self.scale.lerp(other.scale, factor),
+        }
+    }
+}
+
+/// The skinning transforms for each bone in the skel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnimatedWorldTransforms {
+    /// `bone_world.inv() * animated_bone_world`
+    pub transforms: [glam::Mat4; MAX_BONE_COUNT],
+    /// The inverse transpose of [transforms](#structfield.transforms) for normals and tangents.
+    pub transforms_inv_transpose: [glam::Mat4; MAX_BONE_COUNT],
+}
+
+impl AnimatedWorldTransforms {
+    /// The transforms for the first `bone_count` bones in the layout of the skinning shader.
+    pub(crate) fn skinning_transforms(&self, bone_count: usize) -> Vec<SkinningTransform> {
+        self.transforms
+            .iter()
+            .zip(self.transforms_inv_transpose.iter())
+            .take(bone_count)
+            .map(|(transform, transform_inv_transpose)| SkinningTransform {
+                transform: *transform,
+                transform_inv_transpose: *transform_inv_transpose,
+            })
+            .collect()
+    }
+}
+
+/// The number of bones to allocate in the skinning buffer for a skel with `bone_count` bones.
+///
+/// Models with fewer bones use less memory than allocating [MAX_BONE_COUNT] bones for every model.
+pub(crate) fn skinning_buffer_bone_count(bone_count: usize) -> usize {
+    // Buffer bindings can't be empty.
+    // Use an even count to keep the size a multiple of 256 bytes like the offset alignment.
+    bone_count.clamp(1, MAX_BONE_COUNT).next_multiple_of(2)
 }
 
 pub struct AnimationTransforms {
@@ -103,6 +166,8 @@ pub struct AnimationTransforms {
     /// The world transform of each bone in the skeleton.
     // TODO: This name is confusing since it's still animated rather than using the rest pose.
     pub world_transforms: [glam::Mat4; MAX_BONE_COUNT],
+    /// The animated transform of each bone relative to its parent before applying constraints.
+    pub local_transforms: [glam::Mat4; MAX_BONE_COUNT],
 }
 
 impl AnimationTransforms {
@@ -115,6 +180,7 @@ impl AnimationTransforms {
                 transforms_inv_transpose: [glam::Mat4::IDENTITY; MAX_BONE_COUNT],
             },
             world_transforms: [glam::Mat4::IDENTITY; MAX_BONE_COUNT],
+            local_transforms: [glam::Mat4::IDENTITY; MAX_BONE_COUNT],
         }
     }
 
@@ -123,6 +189,7 @@ impl AnimationTransforms {
         // Calculate the world transforms for parenting mesh objects to bones.
         // The skel pose should already match the "pose" in the mesh geometry.
         let mut world_transforms = [glam::Mat4::IDENTITY; MAX_BONE_COUNT];
+        let mut local_transforms = [glam::Mat4::IDENTITY; MAX_BONE_COUNT];
 
         // TODO: Add tests to make sure this is transposed correctly?
         for (i, bone) in skel.bones.iter().enumerate().take(MAX_BONE_COUNT) {
@@ -133,6 +200,7 @@ impl AnimationTransforms {
                 .unwrap_or(glam::Mat4::IDENTITY);
 
             world_transforms[i] = bone_world;
+            local_transforms[i] = glam::Mat4::from_cols_array_2d(&bone.transform);
         }
 
         Self {
@@ -141,10 +209,78 @@ impl AnimationTransforms {
                 transforms_inv_transpose: [glam::Mat4::IDENTITY; MAX_BONE_COUNT],
             },
             world_transforms,
+            local_transforms,
         }
     }
 }
 
+/// The translation, rotation, and scale of a bone relative to its parent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoneTransform {
+    pub translation: glam::Vec3,
+    pub rotation: glam::Quat,
+    pub scale: glam::Vec3,
+}
+
+/// The pose of each bone in a skel calculated on the CPU with [evaluate_skel_pose].
+///
+/// Each field has one element for each bone in the same order as the skel.
+/// This only includes the first [MAX_BONE_COUNT] bones.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkelPose {
+    /// The animated world transform of each bone.
+    pub world_transforms: Vec<glam::Mat4>,
+    /// The animated world transform of each bone relative to its resting pose used for skinning.
+    /// This is equal to `bone_world.inv() * animated_bone_world`.
+    pub skinning_transforms: Vec<glam::Mat4>,
+    /// The animated transform of each bone relative to its parent after applying constraints.
+    pub local_transforms: Vec<BoneTransform>,
+}
+
+/// Evaluates the bone transforms for `skel` at `frame` without needing a GPU device.
+///
+/// This uses the same calculations as [RenderModel::apply_anims](crate::RenderModel::apply_anims)
+/// including scale compensation and `hlpb` constraints.
+/// This allows sampling animations for exporting or tools like inverse kinematics.
+pub fn evaluate_skel_pose<'a>(
+    skel: &SkelData,
+    anims: impl IntoIterator<Item = &'a AnimData>,
+    hlpb: Option<&HlpbData>,
+    frame: f32,
+) -> SkelPose {
+    // The transforms are too large to comfortably fit on the stack.
+    let mut transforms = Box::new(AnimationTransforms::identity());
+    animate_skel(&mut transforms, skel, anims.into_iter(), hlpb, frame);
+
+    let bone_count = skel.bones.len().min(MAX_BONE_COUNT);
+    let world_transforms = transforms.world_transforms[..bone_count].to_vec();
+
+    let local_transforms = skel
+        .bones
+        .iter()
+        .take(bone_count)
+        .zip(&world_transforms)
+        .map(|(bone, world)| {
+            let local = match bone.parent_index.and_then(|p| world_transforms.get(p)) {
+                Some(parent_world) => parent_world.inverse() * *world,
+                None => *world,
+            };
+            let (scale, rotation, translation) = local.to_scale_rotation_translation();
+            BoneTransform {
+                translation,
+                rotation,
+                scale,
+            }
+        })
+        .collect();
+
+    SkelPose {
+        skinning_transforms: transforms.animated_world_transforms.transforms[..bone_count].to_vec(),
+        world_transforms,
+        local_transforms,
+    }
+}
+
 pub trait Visibility {
     fn name(&self) -> &str;
     fn set_visibility(&mut self, visibility: bool);
@@ -182,6 +318,77 @@ pub fn animate_skel<'a>(
     hlpb: Option<&HlpbData>,
     current_frame: f32,
 ) {
+    animate_skel_layers(
+        result,
+        skel,
+        anims.map(|a| (a, AnimLayerMode::Override)),
+        hlpb,
+        current_frame,
+    );
+}
+
+/// Determines how an animation layer combines with the previous layers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AnimLayerMode {
+    /// Replace the transforms for any animated bones.
+    #[default]
+    Override,
+    /// Add the animated transforms relative to the resting pose to the previous layers.
+    /// This is used for partial animations like facial expressions or flinching
+    /// that combine with the fighter's body animation.
+    Additive,
+}
+
+/// Applies each animation layer in order on top of the previous layers.
+///
+/// [animate_skel] is equivalent to using [AnimLayerMode::Override] for all layers.
+pub fn animate_skel_layers<'a>(
+    result: &mut AnimationTransforms,
+    skel: &SkelData,
+    layers: impl Iterator<Item = (&'a AnimData, AnimLayerMode)>,
+    hlpb: Option<&HlpbData>,
+    current_frame: f32,
+) {
+    let mut bones = animated_bones(skel, layers, current_frame);
+    animate_skel_inner(result, &mut bones, &skel.bones, hlpb);
+}
+
+/// Blends the skeletal animations from `anim_a` at `frame_a` and `anim_b` at `frame_b`.
+///
+/// A `blend_factor` of `0.0` uses only `anim_a`, and a `blend_factor` of `1.0` uses only `anim_b`.
+/// This allows previewing transitions between animations like idle to walk.
+/// Bones animated in only one of the animations blend with the resting pose from the `skel`.
+#[allow(clippy::too_many_arguments)]
+pub fn animate_skel_blended(
+    result: &mut AnimationTransforms,
+    skel: &SkelData,
+    anim_a: &AnimData,
+    frame_a: f32,
+    anim_b: &AnimData,
+    frame_b: f32,
+    blend_factor: f32,
+    hlpb: Option<&HlpbData>,
+) {
+    let mut bones = animated_bones(
+        skel,
+        std::iter::once((anim_a, AnimLayerMode::Override)),
+        frame_a,
+    );
+    let bones_b = animated_bones(
+        skel,
+        std::iter::once((anim_b, AnimLayerMode::Override)),
+        frame_b,
+    );
+    blend_bones(&mut bones, &bones_b, blend_factor.clamp(0.0, 1.0));
+
+    animate_skel_inner(result, &mut bones, &skel.bones, hlpb);
+}
+
+fn animated_bones<'a, 'b>(
+    skel: &'a SkelData,
+    layers: impl Iterator<Item = (&'b AnimData, AnimLayerMode)>,
+    current_frame: f32,
+) -> Vec<(usize, AnimatedBone<'a>)> {
     // TODO: Avoid allocating here?
     // TODO: Just take the bones or groups directly?
     let mut bones: Vec<_> = skel
@@ -203,11 +410,33 @@ pub fn animate_skel<'a>(
         .collect();
 
     // TODO: Is it faster to use a separate array for animation info?
-    for anim in anims {
-        apply_transforms(&mut bones, anim, current_frame);
+    for (anim, mode) in layers {
+        apply_transforms(&mut bones, anim, mode, current_frame);
     }
 
-    animate_skel_inner(result, &mut bones, &skel.bones, hlpb);
+    bones
+}
+
+fn blend_bones(
+    bones: &mut [(usize, AnimatedBone)],
+    bones_b: &[(usize, AnimatedBone)],
+    factor: f32,
+) {
+    for ((_, a), (_, b)) in bones.iter_mut().zip(bones_b) {
+        if a.anim_transform.is_none() && b.anim_transform.is_none() {
+            continue;
+        }
+
+        let transform_a = a.anim_transform.unwrap_or_else(|| a.rest_transform());
+        let transform_b = b.anim_transform.unwrap_or_else(|| b.rest_transform());
+        a.anim_transform = Some(transform_a.blend(&transform_b, factor));
+
+        // Flags can't be blended, so use the values from the more heavily weighted animation.
+        if factor >= 0.5 {
+            a.compensate_scale = b.compensate_scale;
+            a.flags = b.flags;
+        }
+    }
 }
 
 pub fn animate_skel_inner(
@@ -243,6 +472,7 @@ pub fn animate_skel_inner(
         let bone = &bones[*i];
         let (parent_world, current) = calculate_world_transform(bones, &bone.1, result);
         result.world_transforms[bone.0] = parent_world * current;
+        result.local_transforms[bone.0] = current;
     }
 
     for i in &evaluation_order {
@@ -268,6 +498,100 @@ pub fn animate_skel_inner(
     }
 }
 
+/// A single `hlpb` constraint for previewing edits with [preview_constraint].
+#[derive(Debug, Clone, PartialEq)]
+pub enum HlpbConstraint {
+    Orient(OrientConstraintData),
+    Aim(AimConstraintData),
+}
+
+impl HlpbConstraint {
+    fn target_bone_name(&self) -> &str {
+        match self {
+            HlpbConstraint::Orient(o) => &o.target_bone_name,
+            HlpbConstraint::Aim(a) => &a.target_bone_name1,
+        }
+    }
+}
+
+/// Applies `constraint` to the current pose in `result`
+/// and updates only the constraint's target bone and its descendants.
+///
+/// This is much cheaper than animating the entire skel again,
+/// so editors can show changes to constraint parameters immediately.
+/// The pose should already be calculated by [animate_skel] for the same `skel`.
+/// Other constraints affecting the target bone or its descendants are not applied again.
+/// Returns `false` without modifying `result` if the skel has no bone matching the target bone name.
+pub fn preview_constraint(
+    result: &mut AnimationTransforms,
+    skel: &SkelData,
+    constraint: &HlpbConstraint,
+) -> bool {
+    let bone_count = skel.bones.len().min(MAX_BONE_COUNT);
+    let Some(target) = skel.bones[..bone_count]
+        .iter()
+        .position(|b| b.name == constraint.target_bone_name())
+    else {
+        return false;
+    };
+
+    // Parents appear before their children in breadth first order.
+    let mut subtree = IndexSet::new();
+    subtree.insert(target);
+    let mut next = 0;
+    while let Some(parent) = subtree.get_index(next).copied() {
+        for (i, bone) in skel.bones[..bone_count].iter().enumerate() {
+            if bone.parent_index == Some(parent) {
+                // The set also prevents infinite loops for cyclic parenting.
+                subtree.insert(i);
+            }
+        }
+        next += 1;
+    }
+
+    let parent_world = |world_transforms: &[glam::Mat4], i: usize| {
+        skel.bones[i]
+            .parent_index
+            .and_then(|p| world_transforms.get(p).copied())
+            .unwrap_or(glam::Mat4::IDENTITY)
+    };
+
+    // Constraints use the unconstrained world transform of the target.
+    let local = result.local_transforms[target];
+    let target_parent_world = parent_world(&result.world_transforms, target);
+    result.world_transforms[target] = target_parent_world * local;
+
+    let current = match constraint {
+        HlpbConstraint::Orient(o) => {
+            apply_orient_constraint(&result.world_transforms, &skel.bones, o, local)
+        }
+        HlpbConstraint::Aim(a) => {
+            apply_aim_constraint(&result.world_transforms, &skel.bones, a, local)
+        }
+    }
+    .unwrap_or(local);
+    result.world_transforms[target] = target_parent_world * current;
+
+    for i in subtree.iter().skip(1).copied() {
+        result.world_transforms[i] =
+            parent_world(&result.world_transforms, i) * result.local_transforms[i];
+    }
+
+    for i in subtree {
+        let bone_world = skel
+            .calculate_world_transform(&skel.bones[i])
+            .map(|t| glam::Mat4::from_cols_array_2d(&t))
+            .unwrap_or(glam::Mat4::IDENTITY);
+        let anim_transform = result.world_transforms[i] * bone_world.inverse();
+
+        result.animated_world_transforms.transforms[i] = anim_transform;
+        result.animated_world_transforms.transforms_inv_transpose[i] =
+            anim_transform.inverse().transpose();
+    }
+
+    true
+}
+
 fn evaluation_order(bones: &[(usize, AnimatedBone)]) -> IndexSet<usize> {
     // The parent-child relationship determines the evaluation order.
     // The order is partial since only a parent and child bone are comparable.
@@ -367,6 +691,7 @@ fn calculate_world_transform(
 fn apply_transforms<'a>(
     bones: &mut [(usize, AnimatedBone)],
     anim: &AnimData,
+    mode: AnimLayerMode,
     frame: f32,
 ) -> Option<AnimatedBone<'a>> {
     for group in &anim.groups {
@@ -377,7 +702,18 @@ fn apply_transforms<'a>(
                     // TODO: Multiple transform tracks per bone?
                     if let Some(track) = node.tracks.first() {
                         if let TrackValues::Transform(values) = &track.values {
-                            *bone = create_animated_bone(frame, bone.bone, track, values);
+                            match mode {
+                                AnimLayerMode::Override => {
+                                    *bone = create_animated_bone(frame, bone.bone, track, values);
+                                }
+                                AnimLayerMode::Additive => {
+                                    // Keep the flags from the base layer.
+                                    let rest = bone.rest_transform();
+                                    let base = bone.anim_transform.unwrap_or(rest);
+                                    let additive = frame_value(values, frame).into();
+                                    bone.anim_transform = Some(base.add(&additive, &rest));
+                                }
+                            }
                         }
                     }
                 }
@@ -414,6 +750,38 @@ pub fn animate_visibility<V: Visibility>(anim: &AnimData, frame: f32, meshes: &m
     }
 }
 
+/// Creates a visibility group with a single frame from the mesh names and visibility in `meshes`.
+///
+/// Node names remove the `_VIS` tags from mesh names.
+/// Meshes with the same node name are visible if any of the meshes are visible.
+///
+/// [animate_visibility] applies a node to all meshes starting with the node name,
+/// so a node like `"A"` also affects meshes like `"AB_VIS_O_OBJShape"`.
+/// Nodes are sorted by name, so the longer node `"AB"` is applied last and takes priority.
+pub fn visibility_group<'a>(meshes: impl IntoIterator<Item = (&'a str, bool)>) -> GroupData {
+    let mut visibility = BTreeMap::new();
+    for (name, is_visible) in meshes {
+        let node_name = name.find("_VIS").map(|i| &name[..i]).unwrap_or(name);
+        *visibility.entry(node_name).or_insert(false) |= is_visible;
+    }
+
+    GroupData {
+        group_type: GroupType::Visibility,
+        nodes: visibility
+            .into_iter()
+            .map(|(name, value)| NodeData {
+                name: name.to_string(),
+                tracks: vec![TrackData {
+                    name: "Visibility".to_string(),
+                    compensate_scale: false,
+                    values: TrackValues::Boolean(vec![value]),
+                    transform_flags: TransformFlags::default(),
+                }],
+            })
+            .collect(),
+    }
+}
+
 // TODO: Add tests for this.
 pub fn animate_materials(
     anim: &AnimData,
@@ -441,44 +809,125 @@ pub fn animate_materials(
     changed_materials
 }
 
+/// Blends the material parameters in `a` and `b` from [animate_materials] for the same materials.
+///
+/// A `factor` of `0.0` uses only `a`, and a `factor` of `1.0` uses only `b`.
+/// Booleans can't be blended and use the value from the more heavily weighted materials.
+pub fn blend_materials(
+    a: &[MatlEntryData],
+    b: &[MatlEntryData],
+    factor: f32,
+) -> Vec<MatlEntryData> {
+    let mut blended_materials = a.to_vec();
+
+    for material in &mut blended_materials {
+        if let Some(material_b) = b
+            .iter()
+            .find(|m| m.material_label == material.material_label)
+        {
+            for param in &mut material.floats {
+                if let Some(param_b) = material_b
+                    .floats
+                    .iter()
+                    .find(|p| p.param_id == param.param_id)
+                {
+                    param.data = param.data.interpolate(&param_b.data, factor);
+                }
+            }
+            for param in &mut material.vectors {
+                if let Some(param_b) = material_b
+                    .vectors
+                    .iter()
+                    .find(|p| p.param_id == param.param_id)
+                {
+                    param.data = param.data.interpolate(&param_b.data, factor);
+                }
+            }
+            if factor >= 0.5 {
+                for param in &mut material.booleans {
+                    if let Some(param_b) = material_b
+                        .booleans
+                        .iter()
+                        .find(|p| p.param_id == param.param_id)
+                    {
+                        param.data = param_b.data;
+                    }
+                }
+            }
+        }
+    }
+
+    blended_materials
+}
+
+/// Evaluates the material tracks in `anim` at `frame` for each material label.
+///
+/// These are the interpolated values applied by [animate_materials],
+/// which only applies values for materials and parameters already present in the matl.
+/// This is useful for inspecting or graphing animated parameters like
+/// [ParamId::CustomVector8](ssbh_data::matl_data::ParamId::CustomVector8) over time.
+/// Tracks that don't affect material parameters like UV transforms are skipped.
+pub fn sample_material_tracks(anim: &AnimData, frame: f32) -> Vec<(String, MaterialValue)> {
+    anim.groups
+        .iter()
+        .filter(|g| g.group_type == GroupType::Material)
+        .flat_map(|g| &g.nodes)
+        .flat_map(|node| {
+            node.tracks
+                .iter()
+                .filter_map(move |track| Some((node.name.clone(), track_value(track, frame)?)))
+        })
+        .collect()
+}
+
+fn track_value(track: &ssbh_data::anim_data::TrackData, frame: f32) -> Option<MaterialValue> {
+    let param_id = ParamId::from_str(&track.name).ok()?;
+    // TODO: UV transforms?
+    match &track.values {
+        TrackValues::Float(v) => Some(MaterialValue::Float(param_id, frame_value(v, frame))),
+        TrackValues::Boolean(v) => Some(MaterialValue::Boolean(param_id, frame_value(v, frame))),
+        TrackValues::Vector4(v) => Some(MaterialValue::Vector(
+            param_id,
+            frame_value(v, frame).to_array(),
+        )),
+        TrackValues::Transform(_) | TrackValues::UvTransform(_) | TrackValues::PatternIndex(_) => {
+            None
+        }
+    }
+}
+
 fn apply_material_track(
     node: &ssbh_data::anim_data::NodeData,
     frame: f32,
     changed_material: &mut MatlEntryData,
 ) {
-    for track in &node.tracks {
-        // TODO: Update material parameters based on the type.
-        match &track.values {
-            TrackValues::Transform(_) => todo!(),
-            TrackValues::UvTransform(_) => {
-                // TODO: UV transforms?
-            }
-            TrackValues::Float(v) => {
+    for value in node.tracks.iter().filter_map(|t| track_value(t, frame)) {
+        match value {
+            MaterialValue::Float(id, value) => {
                 if let Some(param) = changed_material
                     .floats
                     .iter_mut()
-                    .find(|p| track.name == p.param_id.to_string())
+                    .find(|p| p.param_id == id)
                 {
-                    param.data = frame_value(v, frame);
+                    param.data = value;
                 }
             }
-            TrackValues::PatternIndex(_) => (),
-            TrackValues::Boolean(v) => {
+            MaterialValue::Boolean(id, value) => {
                 if let Some(param) = changed_material
                     .booleans
                     .iter_mut()
-                    .find(|p| track.name == p.param_id.to_string())
+                    .find(|p| p.param_id == id)
                 {
-                    param.data = frame_value(v, frame);
+                    param.data = value;
                 }
             }
-            TrackValues::Vector4(v) => {
+            MaterialValue::Vector(id, value) => {
                 if let Some(param) = changed_material
                     .vectors
                     .iter_mut()
-                    .find(|p| track.name == p.param_id.to_string())
+                    .find(|p| p.param_id == id)
                 {
-                    param.data = frame_value(v, frame);
+                    param.data = value.into();
                 }
             }
         }
@@ -619,6 +1068,72 @@ mod tests {
         );
     }
 
+    #[test]
+    fn evaluate_skel_pose_no_anims() {
+        let mut child = identity_bone("B", Some(0));
+        child.transform[3] = [1.0, 2.0, 3.0, 1.0];
+        let mut root = identity_bone("A", None);
+        root.transform[3] = [0.0, 1.0, 0.0, 1.0];
+
+        let pose = evaluate_skel_pose(
+            &SkelData {
+                major_version: 1,
+                minor_version: 0,
+                bones: vec![root, child],
+            },
+            [],
+            None,
+            0.0,
+        );
+
+        assert_eq!(2, pose.world_transforms.len());
+        assert_matrix_relative_eq!(
+            [
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [1.0, 3.0, 3.0, 1.0]
+            ],
+            pose.world_transforms[1].to_cols_array_2d()
+        );
+
+        // The rest pose has no deformation for skinning.
+        assert_matrix_relative_eq!(
+            glam::Mat4::IDENTITY.to_cols_array_2d(),
+            pose.skinning_transforms[1].to_cols_array_2d()
+        );
+
+        assert_eq!(
+            glam::vec3(1.0, 2.0, 3.0),
+            pose.local_transforms[1].translation
+        );
+        assert_eq!(glam::Quat::IDENTITY, pose.local_transforms[1].rotation);
+        assert_eq!(glam::Vec3::ONE, pose.local_transforms[1].scale);
+    }
+
+    #[test]
+    fn skinning_buffer_bone_count_alignment() {
+        assert_eq!(2, skinning_buffer_bone_count(0));
+        assert_eq!(4, skinning_buffer_bone_count(3));
+        assert_eq!(128, skinning_buffer_bone_count(128));
+        assert_eq!(MAX_BONE_COUNT, skinning_buffer_bone_count(600));
+    }
+
+    #[test]
+    fn skinning_transforms_bone_count() {
+        let mut transforms = AnimationTransforms::identity().animated_world_transforms;
+        transforms.transforms[1] = glam::Mat4::from_translation(glam::Vec3::X);
+        transforms.transforms_inv_transpose[1] = glam::Mat4::from_scale(glam::Vec3::Y);
+
+        let skinning = transforms.skinning_transforms(2);
+        assert_eq!(2, skinning.len());
+        assert_eq!(transforms.transforms[1], skinning[1].transform);
+        assert_eq!(
+            transforms.transforms_inv_transpose[1],
+            skinning[1].transform_inv_transpose
+        );
+    }
+
     #[test]
     fn animation_transforms_from_skel_512_bones() {
         AnimationTransforms::from_skel(&SkelData {
@@ -903,6 +1418,184 @@ mod tests {
         );
     }
 
+    fn translation_anim(name: &str, translation: Vector3) -> AnimData {
+        AnimData {
+            major_version: 2,
+            minor_version: 0,
+            final_frame_index: 0.0,
+            groups: vec![GroupData {
+                group_type: GroupType::Transform,
+                nodes: vec![NodeData {
+                    name: name.to_string(),
+                    tracks: vec![TrackData {
+                        name: "Transform".to_string(),
+                        compensate_scale: false,
+                        values: TrackValues::Transform(vec![Transform {
+                            scale: Vector3::new(1.0, 1.0, 1.0),
+                            rotation: Vector4::new(0.0, 0.0, 0.0, 1.0),
+                            translation,
+                        }]),
+                        transform_flags: TransformFlags::default(),
+                    }],
+                }],
+            }],
+        }
+    }
+
+    #[test]
+    fn apply_animation_blended() {
+        let mut rest_bone = identity_bone("B", None);
+        rest_bone.transform[3] = [0.0, 2.0, 0.0, 1.0];
+
+        let mut transforms = AnimationTransforms::identity();
+        animate_skel_blended(
+            &mut transforms,
+            &SkelData {
+                major_version: 1,
+                minor_version: 0,
+                bones: vec![identity_bone("A", None), rest_bone],
+            },
+            &translation_anim("A", Vector3::new(2.0, 4.0, 6.0)),
+            0.0,
+            &translation_anim("B", Vector3::new(0.0, 4.0, 0.0)),
+            0.0,
+            0.25,
+            None,
+        );
+
+        // Bones animated in only one anim blend with the rest pose.
+        assert_matrix_relative_eq!(
+            glam::Mat4::from_translation(glam::vec3(1.5, 3.0, 4.5)).to_cols_array_2d(),
+            transforms.world_transforms[0].to_cols_array_2d()
+        );
+        assert_matrix_relative_eq!(
+            glam::Mat4::from_translation(glam::vec3(0.0, 2.5, 0.0)).to_cols_array_2d(),
+            transforms.world_transforms[1].to_cols_array_2d()
+        );
+    }
+
+    #[test]
+    fn apply_animation_additive_layer() {
+        let mut bone = identity_bone("A", None);
+        bone.transform[3] = [0.0, 1.0, 0.0, 1.0];
+
+        let mut transforms = AnimationTransforms::identity();
+        animate_skel_layers(
+            &mut transforms,
+            &SkelData {
+                major_version: 1,
+                minor_version: 0,
+                bones: vec![bone],
+            },
+            [
+                (
+                    &translation_anim("A", Vector3::new(2.0, 3.0, 0.0)),
+                    AnimLayerMode::Override,
+                ),
+                (
+                    &translation_anim("A", Vector3::new(0.0, 2.0, 4.0)),
+                    AnimLayerMode::Additive,
+                ),
+            ]
+            .into_iter(),
+            None,
+            0.0,
+        );
+
+        // The additive layer moves the bone by (0, 1, 4) from the rest pose.
+        assert_matrix_relative_eq!(
+            glam::Mat4::from_translation(glam::vec3(2.0, 4.0, 4.0)).to_cols_array_2d(),
+            transforms.world_transforms[0].to_cols_array_2d()
+        );
+    }
+
+    #[test]
+    fn sample_material_tracks_interpolated() {
+        let track = |name: &str, values| TrackData {
+            name: name.to_string(),
+            compensate_scale: false,
+            values,
+            transform_flags: TransformFlags::default(),
+        };
+        let anim = AnimData {
+            major_version: 2,
+            minor_version: 0,
+            final_frame_index: 1.0,
+            groups: vec![GroupData {
+                group_type: GroupType::Material,
+                nodes: vec![NodeData {
+                    name: "a".to_string(),
+                    tracks: vec![
+                        track("CustomFloat8", TrackValues::Float(vec![1.0, 3.0])),
+                        track(
+                            "CustomVector3",
+                            TrackValues::Vector4(vec![
+                                Vector4::new(0.0, 0.0, 0.0, 0.0),
+                                Vector4::new(2.0, 4.0, 6.0, 8.0),
+                            ]),
+                        ),
+                        track("CustomBoolean1", TrackValues::Boolean(vec![true, false])),
+                        // Unrecognized parameters are skipped.
+                        track("Unknown", TrackValues::Float(vec![1.0])),
+                    ],
+                }],
+            }],
+        };
+
+        assert_eq!(
+            vec![
+                (
+                    "a".to_string(),
+                    MaterialValue::Float(ParamId::CustomFloat8, 2.0)
+                ),
+                (
+                    "a".to_string(),
+                    MaterialValue::Vector(ParamId::CustomVector3, [1.0, 2.0, 3.0, 4.0])
+                ),
+                (
+                    "a".to_string(),
+                    MaterialValue::Boolean(ParamId::CustomBoolean1, true)
+                ),
+            ],
+            sample_material_tracks(&anim, 0.5)
+        );
+    }
+
+    #[test]
+    fn blend_material_values() {
+        let material = |float, vector, boolean| MatlEntryData {
+            material_label: "a".to_string(),
+            shader_label: String::new(),
+            blend_states: Vec::new(),
+            floats: vec![ssbh_data::matl_data::FloatParam {
+                param_id: ssbh_data::matl_data::ParamId::CustomFloat0,
+                data: float,
+            }],
+            booleans: vec![ssbh_data::matl_data::BooleanParam {
+                param_id: ssbh_data::matl_data::ParamId::CustomBoolean0,
+                data: boolean,
+            }],
+            vectors: vec![ssbh_data::matl_data::Vector4Param {
+                param_id: ssbh_data::matl_data::ParamId::CustomVector0,
+                data: vector,
+            }],
+            rasterizer_states: Vec::new(),
+            samplers: Vec::new(),
+            textures: Vec::new(),
+            uv_transforms: Vec::new(),
+        };
+
+        let blended = blend_materials(
+            &[material(1.0, Vector4::new(0.0, 0.0, 0.0, 0.0), false)],
+            &[material(3.0, Vector4::new(4.0, 8.0, 0.0, 1.0), true)],
+            0.75,
+        );
+        assert_eq!(
+            vec![material(2.5, Vector4::new(3.0, 6.0, 0.0, 0.75), true)],
+            blended
+        );
+    }
+
     #[test]
     fn apply_animation_middle_bone_no_inherit_scale_no_compensate_scale() {
         let mut transforms = AnimationTransforms::identity();
@@ -1479,6 +2172,92 @@ mod tests {
         );
     }
 
+    #[test]
+    fn preview_constraint_subtree() {
+        // Constrain L0 to R0 and check that the child L1 follows L0.
+        let l0 = identity_bone("L0", None);
+        let mut l1 = identity_bone("L1", Some(0));
+        l1.transform = glam::Mat4::from_translation(glam::vec3(1.0, 0.0, 0.0)).to_cols_array_2d();
+        let mut r0 = identity_bone("R0", None);
+        r0.transform = glam::Mat4::from_rotation_z(90.0f32.to_radians()).to_cols_array_2d();
+
+        let skel = SkelData {
+            major_version: 1,
+            minor_version: 0,
+            bones: vec![l0, l1, r0],
+        };
+        let mut transforms = AnimationTransforms::from_skel(&skel);
+
+        assert!(preview_constraint(
+            &mut transforms,
+            &skel,
+            &HlpbConstraint::Orient(OrientConstraintData {
+                name: "constraint1".into(),
+                parent_bone_name1: "Root".into(),
+                parent_bone_name2: "Root".into(),
+                source_bone_name: "R0".into(),
+                target_bone_name: "L0".into(),
+                unk_type: 2,
+                constraint_axes: Vector3::new(1.0, 1.0, 1.0),
+                quat1: Vector4::new(0.0, 0.0, 0.0, 1.0),
+                quat2: Vector4::new(0.0, 0.0, 0.0, 1.0),
+                range_min: Vector3::new(-180.0, -180.0, -180.0),
+                range_max: Vector3::new(180.0, 180.0, 180.0),
+            }),
+        ));
+
+        assert_matrix_relative_eq!(
+            transforms.world_transforms[2].to_cols_array_2d(),
+            transforms.world_transforms[0].to_cols_array_2d()
+        );
+        assert_matrix_relative_eq!(
+            (transforms.world_transforms[0]
+                * glam::Mat4::from_translation(glam::vec3(1.0, 0.0, 0.0)))
+            .to_cols_array_2d(),
+            transforms.world_transforms[1].to_cols_array_2d()
+        );
+        // Only the target is rotated relative to its rest pose.
+        assert_matrix_relative_eq!(
+            glam::Mat4::from_rotation_z(90.0f32.to_radians()).to_cols_array_2d(),
+            transforms.animated_world_transforms.transforms[0].to_cols_array_2d()
+        );
+        // The source bone is unchanged.
+        assert_matrix_relative_eq!(
+            glam::Mat4::IDENTITY.to_cols_array_2d(),
+            transforms.animated_world_transforms.transforms[2].to_cols_array_2d()
+        );
+    }
+
+    #[test]
+    fn preview_constraint_missing_target() {
+        let skel = SkelData {
+            major_version: 1,
+            minor_version: 0,
+            bones: vec![identity_bone("A", None)],
+        };
+        let mut transforms = AnimationTransforms::from_skel(&skel);
+
+        assert!(!preview_constraint(
+            &mut transforms,
+            &skel,
+            &HlpbConstraint::Aim(AimConstraintData {
+                name: "constraint1".into(),
+                aim_bone_name1: "A".into(),
+                aim_bone_name2: "A".into(),
+                aim_type1: "DEFAULT".into(),
+                aim_type2: "DEFAULT".into(),
+                target_bone_name1: "B".into(),
+                target_bone_name2: "B".into(),
+                unk1: 0,
+                unk2: 1,
+                aim: Vector3::new(1.0, 0.0, 0.0),
+                up: Vector3::new(0.0, 1.0, 0.0),
+                quat1: Vector4::new(0.0, 0.0, 0.0, 1.0),
+                quat2: Vector4::new(0.0, 0.0, 0.0, 1.0),
+            }),
+        ));
+    }
+
     #[test]
     fn apply_animation_visibility() {
         // Test that the _VIS tags are ignored in name handling.
@@ -1527,6 +2306,83 @@ mod tests {
         assert_eq!(true, meshes[2].1);
     }
 
+    #[test]
+    fn visibility_group_meshes() {
+        let meshes = vec![
+            ("A_VIS_O_OBJSHAPE", true),
+            ("B_VIS_O_OBJSHAPE", false),
+            ("B_VIS_O_OBJSHAPE", true),
+            ("C", false),
+        ];
+
+        let group = visibility_group(meshes);
+        assert_eq!(GroupType::Visibility, group.group_type);
+        assert_eq!(
+            vec![
+                ("A", TrackValues::Boolean(vec![true])),
+                ("B", TrackValues::Boolean(vec![true])),
+                ("C", TrackValues::Boolean(vec![false])),
+            ],
+            group
+                .nodes
+                .iter()
+                .map(|n| (n.name.as_str(), n.tracks[0].values.clone()))
+                .collect::<Vec<_>>()
+        );
+
+        // Applying the group should preserve the visibility.
+        let mut new_meshes = vec![
+            ("A_VIS_O_OBJSHAPE".to_string(), false),
+            ("C".to_string(), true),
+        ];
+        animate_visibility(
+            &AnimData {
+                major_version: 2,
+                minor_version: 0,
+                final_frame_index: 0.0,
+                groups: vec![group],
+            },
+            0.0,
+            &mut new_meshes,
+        );
+        assert_eq!(true, new_meshes[0].1);
+        assert_eq!(false, new_meshes[1].1);
+    }
+
+    #[test]
+    fn visibility_group_prefix_names() {
+        // "A" also matches "AB_VIS_O_OBJSHAPE" when animating.
+        let meshes = vec![("AB_VIS_O_OBJSHAPE", true), ("A_VIS_O_OBJSHAPE", false)];
+
+        let group = visibility_group(meshes);
+        assert_eq!(
+            vec!["A", "AB"],
+            group
+                .nodes
+                .iter()
+                .map(|n| n.name.as_str())
+                .collect::<Vec<_>>()
+        );
+
+        // Applying the group should preserve the visibility.
+        let mut new_meshes = vec![
+            ("AB_VIS_O_OBJSHAPE".to_string(), false),
+            ("A_VIS_O_OBJSHAPE".to_string(), true),
+        ];
+        animate_visibility(
+            &AnimData {
+                major_version: 2,
+                minor_version: 0,
+                final_frame_index: 0.0,
+                groups: vec![group],
+            },
+            0.0,
+            &mut new_meshes,
+        );
+        assert_eq!(true, new_meshes[0].1);
+        assert_eq!(false, new_meshes[1].1);
+    }
+
     #[test]
     fn evaluation_order_empty() {
         assert!(evaluation_order(&mut Vec::new()).is_empty());
@@ -1,8 +1,14 @@
 use super::{frame_value, AnimTransform};
-use crate::CameraTransforms;
+use crate::{BoundingSphere, CameraTransforms};
 use glam::{vec4, Mat4, Quat, Vec3};
-use ssbh_data::anim_data::{AnimData, GroupType, TrackValues};
+use ssbh_data::{
+    anim_data::{
+        AnimData, GroupData, GroupType, NodeData, TrackData, TrackValues, Transform, TransformFlags,
+    },
+    Vector3, Vector4,
+};
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct CameraAnimValues {
     pub scale: Vec3,
     pub rotation: Quat,
@@ -45,6 +51,164 @@ impl CameraAnimValues {
             screen_dimensions,
         }
     }
+
+    /// Convert camera transforms from [CameraAnimValues::to_transforms] or a viewport camera
+    /// back to animation values.
+    ///
+    /// This assumes the view has no scale and the projection is from [glam::Mat4::perspective_rh].
+    pub fn from_transforms(transforms: &CameraTransforms) -> Self {
+        let model_view = transforms.model_view_matrix;
+        let rotation = Quat::from_mat4(&model_view).normalize();
+        // The model view is R * T, so the translation is rotated.
+        let translation = rotation.inverse() * model_view.w_axis.truncate();
+
+        // Invert the values used for the perspective projection matrix.
+        let projection = transforms.projection_matrix;
+        let fov_y_radians = 2.0 * (1.0 / projection.y_axis.y).atan();
+        let near_clip = projection.w_axis.z / projection.z_axis.z;
+        let far_clip = projection.w_axis.z / (1.0 + projection.z_axis.z);
+
+        Self {
+            scale: Vec3::ONE,
+            rotation,
+            translation,
+            fov_y_radians,
+            near_clip,
+            far_clip,
+        }
+    }
+
+    fn transform(&self) -> Transform {
+        // Invert the conversions in animate_camera.
+        let rotation = self.rotation.conjugate();
+        let translation = -self.translation;
+        Transform {
+            scale: Vector3::new(self.scale.x, self.scale.y, self.scale.z),
+            rotation: Vector4::new(rotation.x, rotation.y, rotation.z, rotation.w),
+            translation: Vector3::new(translation.x, translation.y, translation.z),
+        }
+    }
+}
+
+/// The camera at a specific frame for authoring camera anims with [camera_anim].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraKeyframe {
+    pub frame: f32,
+    pub values: CameraAnimValues,
+}
+
+/// Calculate the camera at `frame` by smoothly interpolating `keyframes` sorted by frame.
+///
+/// Translations follow a Catmull-Rom spline through each keyframe for smooth fly-throughs.
+/// Rotations use spherical linear interpolation, and the remaining values use linear interpolation.
+/// Frames outside the keyframe range use the first or last keyframe.
+/// Returns `None` if `keyframes` is empty.
+pub fn interpolate_camera(keyframes: &[CameraKeyframe], frame: f32) -> Option<CameraAnimValues> {
+    let first = keyframes.first()?;
+    let last = keyframes.last()?;
+    if frame <= first.frame {
+        return Some(first.values);
+    }
+    if frame >= last.frame {
+        return Some(last.values);
+    }
+
+    // Find the keyframes on either side of the frame.
+    let end = keyframes
+        .partition_point(|k| k.frame <= frame)
+        .clamp(1, keyframes.len() - 1);
+    let start = end - 1;
+
+    let k1 = &keyframes[start];
+    let k2 = &keyframes[end];
+    let length = k2.frame - k1.frame;
+    let t = if length > 0.0 {
+        (frame - k1.frame) / length
+    } else {
+        0.0
+    };
+
+    // Repeat the endpoints for the first and last segments.
+    let p0 = keyframes[start.saturating_sub(1)].values.translation;
+    let p3 = keyframes[(end + 1).min(keyframes.len() - 1)]
+        .values
+        .translation;
+    let translation = catmull_rom(p0, k1.values.translation, k2.values.translation, p3, t);
+
+    let a = &k1.values;
+    let b = &k2.values;
+    Some(CameraAnimValues {
+        scale: a.scale.lerp(b.scale, t),
+        rotation: a.rotation.slerp(b.rotation, t),
+        translation,
+        fov_y_radians: a.fov_y_radians + (b.fov_y_radians - a.fov_y_radians) * t,
+        near_clip: a.near_clip + (b.near_clip - a.near_clip) * t,
+        far_clip: a.far_clip + (b.far_clip - a.far_clip) * t,
+    })
+}
+
+fn catmull_rom(p0: Vec3, p1: Vec3, p2: Vec3, p3: Vec3, t: f32) -> Vec3 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    0.5 * (2.0 * p1
+        + (p2 - p0) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+}
+
+/// Create a camera anim from `keyframes` sorted by frame that can be saved as a camera nuanmb.
+///
+/// The anim is baked to a value for every frame from `0` to the last keyframe using [interpolate_camera].
+/// The nodes use the names from fighter camera anims like `"gya_camera"` and `"gya_cameraShape"`,
+/// so the anim can be played back with [animate_camera].
+pub fn camera_anim(keyframes: &[CameraKeyframe]) -> AnimData {
+    let final_frame = keyframes
+        .last()
+        .map(|k| k.frame.max(0.0).ceil())
+        .unwrap_or(0.0);
+
+    let values: Vec<_> = (0..=final_frame as usize)
+        .filter_map(|frame| interpolate_camera(keyframes, frame as f32))
+        .collect();
+
+    let track = |name: &str, values: TrackValues| TrackData {
+        name: name.to_owned(),
+        compensate_scale: false,
+        transform_flags: TransformFlags::default(),
+        values,
+    };
+    let float_track = |name: &str, f: fn(&CameraAnimValues) -> f32| {
+        track(name, TrackValues::Float(values.iter().map(f).collect()))
+    };
+
+    AnimData {
+        major_version: 2,
+        minor_version: 0,
+        final_frame_index: final_frame,
+        groups: vec![
+            GroupData {
+                group_type: GroupType::Transform,
+                nodes: vec![NodeData {
+                    name: "gya_camera".to_owned(),
+                    tracks: vec![track(
+                        "Transform",
+                        TrackValues::Transform(values.iter().map(|v| v.transform()).collect()),
+                    )],
+                }],
+            },
+            GroupData {
+                group_type: GroupType::Camera,
+                nodes: vec![NodeData {
+                    name: "gya_cameraShape".to_owned(),
+                    tracks: vec![
+                        float_track("FarClip", |v| v.far_clip),
+                        float_track("FieldOfView", |v| v.fov_y_radians),
+                        float_track("NearClip", |v| v.near_clip),
+                    ],
+                }],
+            },
+        ],
+    }
 }
 
 /// Calculate the camera transform from the tracks in `anim` at the given `frame`.
@@ -75,41 +239,22 @@ pub fn animate_camera(
     }?;
 
     // TODO: What happens with animations that don't include this node?
-    let camera_node = anim
-        .groups
-        .iter()
-        .find(|g| g.group_type == GroupType::Camera)
-        .and_then(|group| {
-            group
-                .nodes
-                .iter()
-                .find(|n| n.name == "gya_cameraShape" || n.name == "camera_stageShape")
-        });
+    let camera_node = camera_shape_node(anim);
 
     let near_clip = camera_node
-        .and_then(|node| node.tracks.iter().find(|t| t.name == "NearClip"))
-        .and_then(|track| match &track.values {
-            TrackValues::Float(values) => Some(frame_value(values, frame)),
-            _ => None,
-        })
+        .and_then(|node| float_track_value(node, "NearClip", frame))
         .unwrap_or(default_near_clip);
 
     let far_clip = camera_node
-        .and_then(|node| node.tracks.iter().find(|t| t.name == "FarClip"))
-        .and_then(|track| match &track.values {
-            TrackValues::Float(values) => Some(frame_value(values, frame)),
-            _ => None,
-        })
+        .and_then(|node| float_track_value(node, "FarClip", frame))
         .unwrap_or(default_far_clip);
 
     let fov_y_radians = camera_node
-        .and_then(|node| node.tracks.iter().find(|t| t.name == "FieldOfView"))
-        .and_then(|track| match &track.values {
-            TrackValues::Float(values) => Some(frame_value(values, frame)),
-            _ => None,
-        })
+        .and_then(|node| float_track_value(node, "FieldOfView", frame))
         .unwrap_or(default_fov);
 
+    // TODO: Animate DofSettings once the in game depth of field tracks are known.
+
     let scale = transform.scale;
 
     // TODO: Why do we negate w like for lighting rotations?
@@ -128,15 +273,101 @@ pub fn animate_camera(
     })
 }
 
+fn camera_shape_node(anim: &AnimData) -> Option<&NodeData> {
+    anim.groups
+        .iter()
+        .find(|g| g.group_type == GroupType::Camera)?
+        .nodes
+        .iter()
+        .find(|n| n.name == "gya_cameraShape" || n.name == "camera_stageShape")
+}
+
+fn float_track_value(node: &NodeData, name: &str, frame: f32) -> Option<f32> {
+    match &node.tracks.iter().find(|t| t.name == name)?.values {
+        TrackValues::Float(values) => Some(frame_value(values, frame)),
+        _ => None,
+    }
+}
+
+/// Settings for an automatic orbiting camera from [showcase_camera].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShowcaseSettings {
+    /// The horizontal distance from the center of the bounds.
+    pub radius: f32,
+    /// The vertical offset from the center of the bounds.
+    pub height: f32,
+    /// The orbit speed around the vertical axis in degrees per second.
+    pub degrees_per_second: f32,
+    /// Smoothly move between [radius](#structfield.radius) and the distance that frames the bounds
+    /// with this period in seconds when [Some].
+    pub bounce_period: Option<f32>,
+}
+
+impl Default for ShowcaseSettings {
+    fn default() -> Self {
+        Self {
+            radius: 60.0,
+            height: 10.0,
+            degrees_per_second: 15.0,
+            bounce_period: None,
+        }
+    }
+}
+
+/// Calculate a camera slowly orbiting `bounds` after `time_seconds` for kiosk style model displays.
+///
+/// The camera always looks at the center of `bounds` and stays outside the bounds to avoid clipping into the model.
+/// Bounds can be calculated for all visible models using [scene_bounds](crate::scene_bounds).
+pub fn showcase_camera(
+    settings: &ShowcaseSettings,
+    bounds: &BoundingSphere,
+    time_seconds: f32,
+    fov_y_radians: f32,
+    near_clip: f32,
+    far_clip: f32,
+) -> CameraAnimValues {
+    let mut distance = settings.radius;
+    if let Some(period) = settings.bounce_period.filter(|p| *p > 0.0) {
+        // Fit the bounds vertically in the view.
+        // TODO: Account for the aspect ratio for narrow viewports.
+        let framed_distance = bounds.radius / (fov_y_radians / 2.0).sin();
+        let t = 0.5 - 0.5 * (time_seconds / period * 2.0 * std::f32::consts::PI).cos();
+        distance += (framed_distance - distance) * t;
+    }
+
+    // Keep the camera outside the bounds.
+    let min_distance = bounds.radius + near_clip;
+    let min_horizontal = (min_distance * min_distance - settings.height * settings.height)
+        .max(0.0)
+        .sqrt();
+    let distance = distance.max(min_horizontal);
+
+    let angle = (settings.degrees_per_second * time_seconds).to_radians();
+    let eye = bounds.center
+        + Vec3::new(
+            distance * angle.sin(),
+            settings.height,
+            distance * angle.cos(),
+        );
+
+    // The look at matrix has the same RT transform order used for camera animations.
+    let view = Mat4::look_at_rh(eye, bounds.center, Vec3::Y);
+
+    CameraAnimValues {
+        scale: Vec3::ONE,
+        rotation: Quat::from_mat4(&view),
+        translation: -eye,
+        fov_y_radians,
+        near_clip,
+        far_clip,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    use crate::assert_matrix_relative_eq;
-    use ssbh_data::{
-        anim_data::{GroupData, NodeData, TrackData, Transform, TransformFlags},
-        Vector3, Vector4,
-    };
+    use crate::{assert_matrix_relative_eq, assert_vector_relative_eq};
 
     // TODO: Test missing how missing data and capitalization is handled in game.
     // TODO: Test if transform scale has any impact in game.
@@ -211,4 +442,160 @@ mod tests {
             transform.mvp_matrix.to_cols_array_2d()
         );
     }
+
+    fn keyframe(frame: f32, translation: Vec3, fov_y_radians: f32) -> CameraKeyframe {
+        CameraKeyframe {
+            frame,
+            values: CameraAnimValues {
+                scale: Vec3::ONE,
+                rotation: Quat::from_rotation_y(frame / 10.0),
+                translation,
+                fov_y_radians,
+                near_clip: 1.0,
+                far_clip: 1000.0,
+            },
+        }
+    }
+
+    #[test]
+    fn interpolate_camera_keyframes() {
+        let keyframes = [
+            keyframe(0.0, Vec3::ZERO, 0.5),
+            keyframe(10.0, Vec3::new(10.0, 0.0, 0.0), 1.0),
+        ];
+        assert_eq!(None, interpolate_camera(&[], 0.0));
+        assert_eq!(
+            Some(keyframes[0].values),
+            interpolate_camera(&keyframes, -1.0)
+        );
+        assert_eq!(
+            Some(keyframes[1].values),
+            interpolate_camera(&keyframes, 20.0)
+        );
+
+        // A spline with only two points is a straight line.
+        let values = interpolate_camera(&keyframes, 5.0).unwrap();
+        assert_vector_relative_eq!([5.0, 0.0, 0.0], values.translation.to_array());
+        assert_eq!(0.75, values.fov_y_radians);
+    }
+
+    #[test]
+    fn interpolate_camera_spline_passes_through_keyframes() {
+        let keyframes = [
+            keyframe(0.0, Vec3::ZERO, 0.5),
+            keyframe(10.0, Vec3::new(10.0, 5.0, 0.0), 0.5),
+            keyframe(30.0, Vec3::new(0.0, 10.0, 20.0), 0.5),
+        ];
+        let values = interpolate_camera(&keyframes, 10.0).unwrap();
+        assert_vector_relative_eq!([10.0, 5.0, 0.0], values.translation.to_array());
+    }
+
+    #[test]
+    fn camera_anim_round_trip() {
+        let keyframes = [
+            keyframe(0.0, Vec3::new(-5.0, -10.0, -70.0), 0.5),
+            keyframe(4.0, Vec3::new(5.0, -10.0, -50.0), 0.7),
+        ];
+        let anim = camera_anim(&keyframes);
+        assert_eq!(4.0, anim.final_frame_index);
+
+        for k in keyframes {
+            let values = animate_camera(&anim, k.frame, 0.1, 0.1, 0.1).unwrap();
+            assert_vector_relative_eq!(
+                k.values.translation.to_array(),
+                values.translation.to_array()
+            );
+            assert_vector_relative_eq!(
+                (k.values.rotation * Vec3::Z).to_array(),
+                (values.rotation * Vec3::Z).to_array()
+            );
+            assert_eq!(k.values.fov_y_radians, values.fov_y_radians);
+            assert_eq!(1.0, values.near_clip);
+            assert_eq!(1000.0, values.far_clip);
+        }
+    }
+
+    #[test]
+    fn camera_values_from_transforms() {
+        let values = CameraAnimValues {
+            scale: Vec3::ONE,
+            rotation: Quat::from_rotation_y(0.5),
+            translation: Vec3::new(1.0, -2.0, -30.0),
+            fov_y_radians: 0.5,
+            near_clip: 0.5,
+            far_clip: 100.0,
+        };
+        let transforms = values.to_transforms(128, 128, 1.0);
+        let converted = CameraAnimValues::from_transforms(&transforms);
+
+        assert_vector_relative_eq!(
+            values.translation.to_array(),
+            converted.translation.to_array()
+        );
+        assert_vector_relative_eq!(
+            (values.rotation * Vec3::Z).to_array(),
+            (converted.rotation * Vec3::Z).to_array()
+        );
+        approx::assert_relative_eq!(0.5, converted.fov_y_radians, epsilon = 0.0001f32);
+        approx::assert_relative_eq!(0.5, converted.near_clip, epsilon = 0.0001f32);
+        approx::assert_relative_eq!(100.0, converted.far_clip, epsilon = 0.01f32);
+    }
+
+    #[test]
+    fn showcase_camera_orbit() {
+        let settings = ShowcaseSettings {
+            radius: 10.0,
+            height: 2.0,
+            degrees_per_second: 90.0,
+            bounce_period: None,
+        };
+        let bounds = BoundingSphere {
+            center: Vec3::new(0.0, 5.0, 0.0),
+            radius: 1.0,
+        };
+
+        let values = showcase_camera(&settings, &bounds, 0.0, 0.5, 0.1, 100.0);
+        let transforms = values.to_transforms(128, 128, 1.0);
+        assert_vector_relative_eq!([0.0, 7.0, 10.0, 1.0], transforms.camera_pos.to_array());
+
+        let values = showcase_camera(&settings, &bounds, 1.0, 0.5, 0.1, 100.0);
+        let transforms = values.to_transforms(128, 128, 1.0);
+        assert_vector_relative_eq!([10.0, 7.0, 0.0, 1.0], transforms.camera_pos.to_array());
+    }
+
+    #[test]
+    fn showcase_camera_outside_bounds() {
+        let settings = ShowcaseSettings {
+            radius: 1.0,
+            height: 0.0,
+            degrees_per_second: 0.0,
+            bounce_period: None,
+        };
+        let bounds = BoundingSphere {
+            center: Vec3::ZERO,
+            radius: 5.0,
+        };
+
+        let values = showcase_camera(&settings, &bounds, 0.0, 0.5, 1.0, 100.0);
+        assert_vector_relative_eq!([0.0, 0.0, -6.0], values.translation.to_array());
+    }
+
+    #[test]
+    fn showcase_camera_bounce() {
+        let settings = ShowcaseSettings {
+            radius: 10.0,
+            height: 0.0,
+            degrees_per_second: 0.0,
+            bounce_period: Some(2.0),
+        };
+        let bounds = BoundingSphere {
+            center: Vec3::ZERO,
+            radius: 1.0,
+        };
+
+        // Halfway through the period frames the bounds.
+        let values = showcase_camera(&settings, &bounds, 1.0, 1.0, 0.1, 100.0);
+        let framed_distance = 1.0 / 0.5f32.sin();
+        assert_vector_relative_eq!([0.0, 0.0, -framed_distance], values.translation.to_array());
+    }
 }
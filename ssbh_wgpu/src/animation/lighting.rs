@@ -1,13 +1,16 @@
 use std::str::FromStr;
 
+use strum::{Display, EnumIter, EnumString};
+
 use ssbh_data::{
-    anim_data::{AnimData, GroupType, NodeData, TrackValues},
+    anim_data::{AnimData, GroupData, GroupType, NodeData, TrackValues},
     matl_data::ParamId,
 };
 
 use crate::{
     shader::model::{Light, SceneAttributesForShaderFx, StageUniforms},
     uniforms::{boolean_index, float_index, vector_index},
+    BoundingBox,
 };
 
 use super::frame_value;
@@ -23,6 +26,52 @@ pub fn light_transform(rotation: glam::Quat, scale: glam::Vec3) -> glam::Mat4 {
     perspective_matrix * model_view
 }
 
+/// Calculates a light transform with the given light `direction` that contains `bounds`.
+///
+/// The projection is stabilized to reduce shimmering from small changes to `bounds`
+/// by snapping the projection to texels in a shadow map with resolution `shadow_map_size`.
+/// Returns `None` if `direction` is zero.
+pub fn fit_light_transform(
+    direction: glam::Vec4,
+    bounds: &BoundingBox,
+    shadow_map_size: u32,
+) -> Option<glam::Mat4> {
+    let direction = direction.truncate().try_normalize()?;
+
+    // Match the view of light_transform by mapping the direction to +Z.
+    let up = if direction.y.abs() > 0.99 {
+        glam::Vec3::X
+    } else {
+        glam::Vec3::Y
+    };
+    let view = glam::Mat4::look_to_rh(glam::Vec3::ZERO, -direction, up);
+
+    // Spheres are rotation invariant, so the size won't change with the light direction.
+    let sphere = bounds.bounding_sphere();
+    let center = view.transform_point3(sphere.center);
+
+    // Round the radius up in small increments to avoid changing the size every frame.
+    let radius = 2.0f32.powf((sphere.radius.max(0.001).log2() * 8.0).ceil() / 8.0);
+
+    // Snap the center to shadow map texels.
+    // Pad by a texel on each side to account for snapping.
+    let texel_size = 2.0 * radius / shadow_map_size.saturating_sub(2).max(1) as f32;
+    let extent = radius + texel_size;
+    let x = (center.x / texel_size).floor() * texel_size;
+    let y = (center.y / texel_size).floor() * texel_size;
+
+    let projection = glam::Mat4::orthographic_rh(
+        x - extent,
+        x + extent,
+        y - extent,
+        y + extent,
+        -(center.z + radius),
+        -(center.z - radius),
+    );
+
+    Some(projection * view)
+}
+
 fn light_direction(rotation: glam::Quat) -> glam::Vec4 {
     glam::Mat4::from_quat(rotation) * glam::Vec4::Z
 }
@@ -59,6 +108,226 @@ impl StageUniforms {
     }
 }
 
+/// Built in lighting for applications without access to the game's stage lighting files.
+/// Apply a preset with [SsbhRenderer::set_lighting_preset](crate::SsbhRenderer::set_lighting_preset).
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Display, EnumIter, EnumString)]
+pub enum LightingPreset {
+    /// The default lighting for the training stage.
+    Training,
+    /// A neutral overhead light with reduced rim lighting and no fog for comparing models.
+    ///
+    /// This is a hand tuned variation of [LightingPreset::Training] and not based on any stage in game.
+    NeutralOverhead,
+    /// A brighter frontal light with stronger rim lighting for well lit screenshots.
+    ///
+    /// This is a hand tuned variation of [LightingPreset::Training] and not based on any stage in game.
+    BrightFrontal,
+}
+
+impl LightingPreset {
+    pub(crate) fn stage_uniforms(&self) -> StageUniforms {
+        match self {
+            LightingPreset::Training => StageUniforms::training(),
+            LightingPreset::NeutralOverhead => preset_uniforms(
+                glam::quat(-0.608761, 0.0, 0.0, 0.793353),
+                3.0,
+                glam::vec4(1.0, 1.0, 1.0, 0.5),
+            ),
+            LightingPreset::BrightFrontal => preset_uniforms(
+                glam::quat(-0.25882, -0.12941, -0.034675, 0.956707),
+                6.0,
+                glam::vec4(1.0, 1.0, 1.0, 1.5),
+            ),
+        }
+    }
+}
+
+// Approximate lighting by adjusting the character light and scene attributes of the training stage.
+fn preset_uniforms(rotation: glam::Quat, intensity: f32, rim_color: glam::Vec4) -> StageUniforms {
+    let mut uniforms = StageUniforms::training();
+    uniforms.light_chr.color = glam::Vec4::splat(intensity);
+    uniforms.light_chr.set_rotation(rotation.normalize());
+
+    // Rim lighting.
+    uniforms.scene_attributes.custom_vector[8] = rim_color;
+    // Push the distance fog far enough away to not affect models.
+    uniforms.scene_attributes.custom_vector[13] = glam::vec4(1000000.0, 2000000.0, 1.0, 0.0);
+    uniforms
+}
+
+/// Parameters for a single directional light in [LightingSettings].
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct LightSettings {
+    /// The rotation of the light from the "Transform" track.
+    /// Euler angles from a UI can be converted with [glam::Quat::from_euler].
+    pub rotation: glam::Quat,
+    /// The half extents of the shadow projection from the "Transform" track's scale.
+    pub scale: glam::Vec3,
+    /// The light color from "CustomVector0".
+    pub color: glam::Vec4,
+    /// The light intensity from "CustomFloat0" that scales the color.
+    pub intensity: f32,
+}
+
+impl Default for LightSettings {
+    /// An unused light with no color.
+    fn default() -> Self {
+        Self {
+            rotation: glam::Quat::IDENTITY,
+            scale: glam::Vec3::ONE,
+            color: glam::Vec4::ZERO,
+            intensity: 0.0,
+        }
+    }
+}
+
+impl LightSettings {
+    /// The direction the light points for displaying the rotation with a widget in a UI.
+    pub fn direction(&self) -> glam::Vec3 {
+        light_direction(self.rotation.normalize()).truncate()
+    }
+
+    fn light(&self) -> Light {
+        let rotation = self.rotation.normalize();
+        Light {
+            color: self.color * self.intensity,
+            direction: light_direction(rotation),
+            transform: light_transform(rotation, self.scale),
+        }
+    }
+
+    fn from_light(light: &Light) -> Self {
+        // The color and intensity are combined in the uniforms.
+        Self {
+            rotation: light.rotation(),
+            scale: light_scale(light.transform),
+            color: light.color,
+            intensity: 1.0,
+        }
+    }
+}
+
+/// Stage lighting parameters for editing lighting without a lighting anim.
+/// Apply the settings with [SsbhRenderer::update_lighting](crate::SsbhRenderer::update_lighting).
+///
+/// The values match the nodes and tracks in lighting anims like light00.nuanmb.
+/// The default uses the lighting for the training stage.
+#[derive(Debug, PartialEq, Clone)]
+pub struct LightingSettings {
+    /// The "LightChr" light used for shading and shadows on fighters.
+    pub light_chr: LightSettings,
+    /// The "LightStg0" to "LightStg7" lights assigned to models by the model's light set.
+    pub light_stage: [LightSettings; 8],
+    /// The "sceneAttributesForShaderFX" values for "CustomVector0" to "CustomVector63".
+    /// This includes values like the rim lighting color in "CustomVector8".
+    pub custom_vectors: [glam::Vec4; 64],
+    /// The "sceneAttributesForShaderFX" values for "CustomFloat0" to "CustomFloat19".
+    pub custom_floats: [f32; 20],
+    /// The "sceneAttributesForShaderFX" values for "CustomBoolean0" to "CustomBoolean19".
+    pub custom_booleans: [bool; 20],
+}
+
+impl Default for LightingSettings {
+    fn default() -> Self {
+        Self::from_stage_uniforms(&StageUniforms::training())
+    }
+}
+
+impl LightingSettings {
+    pub(crate) fn from_stage_uniforms(uniforms: &StageUniforms) -> Self {
+        let attributes = &uniforms.scene_attributes;
+        Self {
+            light_chr: LightSettings::from_light(&uniforms.light_chr),
+            light_stage: uniforms.light_stage.map(|l| LightSettings::from_light(&l)),
+            custom_vectors: attributes.custom_vector,
+            custom_floats: attributes.custom_float.map(|v| v.x),
+            custom_booleans: attributes.custom_boolean.map(|v| v.x != 0),
+        }
+    }
+
+    /// The light with `name` like `"LightChr"` or `"LightStg0"`.
+    /// Returns `None` if the light is not used for rendering.
+    pub fn light(&self, name: &str) -> Option<&LightSettings> {
+        match stage_light_index(name)? {
+            Some(i) => self.light_stage.get(i),
+            None => Some(&self.light_chr),
+        }
+    }
+
+    /// Replaces the light with `name` like `"LightChr"` or `"LightStg0"`
+    /// and returns `true` if the light is used for rendering.
+    ///
+    /// This allows editing individual lights from [animate_lights]
+    /// and reapplying them with [SsbhRenderer::update_lighting](crate::SsbhRenderer::update_lighting).
+    pub fn set_light(&mut self, name: &str, settings: LightSettings) -> bool {
+        let light = match stage_light_index(name) {
+            Some(Some(i)) => self.light_stage.get_mut(i),
+            Some(None) => Some(&mut self.light_chr),
+            None => None,
+        };
+        match light {
+            Some(light) => {
+                *light = settings;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub(crate) fn stage_uniforms(&self) -> StageUniforms {
+        StageUniforms {
+            light_chr: self.light_chr.light(),
+            light_stage: self.light_stage.map(|l| l.light()),
+            scene_attributes: SceneAttributesForShaderFx {
+                custom_boolean: self.custom_booleans.map(|b| glam::uvec4(b as u32, 0, 0, 0)),
+                custom_vector: self.custom_vectors,
+                custom_float: self.custom_floats.map(|f| glam::vec4(f, 0.0, 0.0, 0.0)),
+            },
+        }
+    }
+}
+
+// Some(None) for LightChr and Some(Some(index)) for LightStg lights.
+fn stage_light_index(name: &str) -> Option<Option<usize>> {
+    if name == "LightChr" {
+        Some(None)
+    } else {
+        name.strip_prefix("LightStg")?.parse().ok().map(Some)
+    }
+}
+
+impl Light {
+    /// Sets the direction and transform from `rotation` while preserving the scale of the transform.
+    pub fn set_rotation(&mut self, rotation: glam::Quat) {
+        let scale = light_scale(self.transform);
+        self.direction = light_direction(rotation);
+        self.transform = light_transform(rotation, scale);
+    }
+
+    /// The rotation used to calculate the transform with [light_transform].
+    pub fn rotation(&self) -> glam::Quat {
+        // The orthographic projection scales the rows of the inverse rotation.
+        // The rows of the inverse rotation are the columns of the rotation.
+        // The Z-axis is also negated by the projection.
+        let row = |i| self.transform.row(i).truncate().normalize_or_zero();
+        let rotation = glam::Mat3::from_cols(row(0), row(1), -row(2));
+        glam::Quat::from_mat3(&rotation).normalize()
+    }
+}
+
+fn light_scale(transform: glam::Mat4) -> glam::Vec3 {
+    // Invert the scaling from the orthographic projection in light_transform.
+    let scale = |i, factor: f32| {
+        let length = transform.row(i).truncate().length();
+        if length > 0.0 {
+            1.0 / (length * factor)
+        } else {
+            1.0
+        }
+    };
+    glam::vec3(scale(0, 1.0), scale(1, 1.0), scale(2, 2.0))
+}
+
 impl Default for Light {
     fn default() -> Self {
         Self {
@@ -79,50 +348,75 @@ impl Default for SceneAttributesForShaderFx {
     }
 }
 
-// TODO: Test cases.
-pub fn animate_lighting(data: &AnimData, frame: f32) -> StageUniforms {
-    let transform_group = data
-        .groups
-        .iter()
-        .find(|g| g.group_type == GroupType::Transform);
+/// A light node from a lighting anim like `"LightChr"`, `"LightStg0"`, or `"LightDirectional0"`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct AnimLight {
+    /// The name of the node in the anim.
+    pub name: String,
+    pub settings: LightSettings,
+}
 
-    // TODO: use LightStg0 for the shadow direction?
-    let light_chr = transform_group.and_then(|g| g.nodes.iter().find(|n| n.name == "LightChr"));
-    let light_chr = light_chr
-        .map(|n| light_from_node(n, frame))
+/// Evaluates every light node in the lighting anim `data` at `frame` in the order they appear in the anim.
+///
+/// This includes lights that aren't used for rendering like `"LightDirectional0"` or `"LightPoint0"`.
+/// Lights used for rendering can be edited and reapplied with [LightingSettings::set_light].
+pub fn animate_lights(data: &AnimData, frame: f32) -> Vec<AnimLight> {
+    transform_group(data)
+        .map(|g| {
+            g.nodes
+                .iter()
+                .filter(|n| n.name.starts_with("Light"))
+                .map(|n| AnimLight {
+                    name: n.name.clone(),
+                    settings: light_settings_from_node(n, frame),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Evaluates the lighting anim `data` at `frame` like [animate_lighting].
+///
+/// Stages with multiple forms like Battlefield and Omega use a separate lighting anim for each form.
+/// The settings can be edited and applied with [SsbhRenderer::update_lighting](crate::SsbhRenderer::update_lighting).
+/// Lights missing from the anim have no color.
+pub fn animate_lighting_settings(data: &AnimData, frame: f32) -> LightingSettings {
+    let scene_attributes = transform_group(data)
+        .and_then(|g| {
+            g.nodes
+                .iter()
+                .find(|n| n.name == "sceneAttributesForShaderFX")
+        })
+        .map(|n| scene_attributes_from_node(n, frame))
         .unwrap_or_default();
 
+    let mut settings = LightingSettings {
+        light_chr: LightSettings::default(),
+        light_stage: [LightSettings::default(); 8],
+        custom_vectors: scene_attributes.custom_vector,
+        custom_floats: scene_attributes.custom_float.map(|v| v.x),
+        custom_booleans: scene_attributes.custom_boolean.map(|v| v.x != 0),
+    };
+
+    // TODO: use LightStg0 for the shadow direction?
     // TODO: What is the upper limit for the number of light sets.
     // In game lighting anim files seem to have no more than 8.
-    let mut light_stage = [Light::default(); 8];
-    if let Some(group) = transform_group {
-        // TODO: How to correctly map stage lights to indices?
-        for (i, node) in group
-            .nodes
-            .iter()
-            .filter(|n| n.name.starts_with("LightStg"))
-            .enumerate()
-        {
-            if let Some(light) = light_stage.get_mut(i) {
-                *light = light_from_node(node, frame);
-            }
-        }
+    // Unused lights like LightDirectional0 are ignored.
+    for light in animate_lights(data, frame) {
+        settings.set_light(&light.name, light.settings);
     }
 
-    let scene_attributes = transform_group.and_then(|g| {
-        g.nodes
-            .iter()
-            .find(|n| n.name == "sceneAttributesForShaderFX")
-    });
-    let scene_attributes = scene_attributes
-        .map(|n| scene_attributes_from_node(n, frame))
-        .unwrap_or_default();
+    settings
+}
 
-    StageUniforms {
-        light_chr,
-        light_stage,
-        scene_attributes,
-    }
+pub fn animate_lighting(data: &AnimData, frame: f32) -> StageUniforms {
+    animate_lighting_settings(data, frame).stage_uniforms()
+}
+
+fn transform_group(data: &AnimData) -> Option<&GroupData> {
+    data.groups
+        .iter()
+        .find(|g| g.group_type == GroupType::Transform)
 }
 
 fn scene_attributes_from_node(node: &NodeData, frame: f32) -> SceneAttributesForShaderFx {
@@ -158,7 +452,7 @@ fn scene_attributes_from_node(node: &NodeData, frame: f32) -> SceneAttributesFor
     attributes
 }
 
-fn light_from_node(node: &NodeData, frame: f32) -> Light {
+fn light_settings_from_node(node: &NodeData, frame: f32) -> LightSettings {
     // TODO: Avoid unwrap.
     // TODO: Default to intensity of 1.0 instead?
     let float0 = node
@@ -199,10 +493,11 @@ fn light_from_node(node: &NodeData, frame: f32) -> Light {
         .map(|t| glam::Vec3::from_array(t.scale.to_array()))
         .unwrap_or(glam::Vec3::ONE);
 
-    Light {
-        color: glam::Vec4::from_array(vector0.to_array()) * float0,
-        direction: light_direction(rotation),
-        transform: light_transform(rotation, scale),
+    LightSettings {
+        rotation,
+        scale,
+        color: glam::Vec4::from_array(vector0.to_array()),
+        intensity: float0,
     }
 }
 
@@ -210,8 +505,91 @@ fn light_from_node(node: &NodeData, frame: f32) -> Light {
 mod tests {
     use super::*;
 
-    use crate::assert_matrix_relative_eq;
+    use crate::{assert_matrix_relative_eq, assert_vector_relative_eq};
     use approx::assert_relative_eq;
+    use ssbh_data::{
+        anim_data::{TrackData, Transform, TransformFlags},
+        Vector3, Vector4,
+    };
+
+    fn light_node(name: &str, intensity: f32) -> NodeData {
+        NodeData {
+            name: name.to_string(),
+            tracks: vec![
+                TrackData {
+                    name: "CustomFloat0".to_string(),
+                    compensate_scale: false,
+                    values: TrackValues::Float(vec![intensity]),
+                    transform_flags: TransformFlags::default(),
+                },
+                TrackData {
+                    name: "Transform".to_string(),
+                    compensate_scale: false,
+                    values: TrackValues::Transform(vec![Transform {
+                        scale: Vector3::new(10.0, 10.0, 10.0),
+                        rotation: Vector4::new(0.0, 0.0, 0.0, 1.0),
+                        translation: Vector3::new(0.0, 0.0, 0.0),
+                    }]),
+                    transform_flags: TransformFlags::default(),
+                },
+            ],
+        }
+    }
+
+    fn lighting_anim() -> AnimData {
+        AnimData {
+            major_version: 2,
+            minor_version: 0,
+            final_frame_index: 0.0,
+            groups: vec![GroupData {
+                group_type: GroupType::Transform,
+                nodes: vec![
+                    light_node("LightChr", 4.0),
+                    light_node("LightStg1", 2.0),
+                    light_node("LightDirectional0", 1.0),
+                    NodeData {
+                        name: "sceneAttributesForShaderFX".to_string(),
+                        tracks: Vec::new(),
+                    },
+                ],
+            }],
+        }
+    }
+
+    #[test]
+    fn animate_lights_all_lights() {
+        let lights = animate_lights(&lighting_anim(), 0.0);
+        assert_eq!(
+            vec!["LightChr", "LightStg1", "LightDirectional0"],
+            lights.iter().map(|l| l.name.as_str()).collect::<Vec<_>>()
+        );
+        assert_eq!(4.0, lights[0].settings.intensity);
+        assert_eq!(glam::Vec3::splat(10.0), lights[0].settings.scale);
+        assert_eq!(glam::Vec3::Z, lights[2].settings.direction());
+    }
+
+    #[test]
+    fn animate_lighting_settings_light_indices() {
+        let settings = animate_lighting_settings(&lighting_anim(), 0.0);
+        assert_eq!(4.0, settings.light_chr.intensity);
+        assert_eq!(0.0, settings.light_stage[0].intensity);
+        assert_eq!(2.0, settings.light_stage[1].intensity);
+        assert_eq!(Some(&settings.light_stage[1]), settings.light("LightStg1"));
+        assert_eq!(None, settings.light("LightDirectional0"));
+    }
+
+    #[test]
+    fn lighting_settings_set_light() {
+        let mut settings = LightingSettings::default();
+        let light = LightSettings {
+            intensity: 3.0,
+            ..Default::default()
+        };
+        assert!(settings.set_light("LightStg7", light));
+        assert_eq!(light, settings.light_stage[7]);
+        assert!(!settings.set_light("LightStg8", light));
+        assert!(!settings.set_light("LightPoint0", light));
+    }
 
     // Test cases based on matching the variance shadow map from in game.
     // The LightStg0 rotation changes the fighter shadow direction.
@@ -284,6 +662,123 @@ mod tests {
         )
     }
 
+    #[test]
+    fn fit_light_transform_zero_direction() {
+        let bounds = BoundingBox {
+            min: glam::Vec3::ZERO,
+            max: glam::Vec3::ONE,
+        };
+        assert_eq!(None, fit_light_transform(glam::Vec4::ZERO, &bounds, 1024));
+    }
+
+    #[test]
+    fn fit_light_transform_contains_bounds() {
+        let bounds = BoundingBox {
+            min: glam::vec3(-10.0, 0.0, -5.0),
+            max: glam::vec3(10.0, 20.0, 5.0),
+        };
+        let direction = light_direction(glam::quat(-0.453154, -0.365998, -0.211309, 0.784886));
+        let transform = fit_light_transform(direction, &bounds, 1024).unwrap();
+
+        // All corners should be within the clip space volume.
+        for corner in bounds.corners() {
+            let p = transform.project_point3(corner);
+            assert!(p.x.abs() <= 1.0 && p.y.abs() <= 1.0, "{p:?}");
+            assert!(p.z >= 0.0 && p.z <= 1.0, "{p:?}");
+        }
+    }
+
+    #[test]
+    fn fit_light_transform_stable() {
+        // Small changes to the bounds shouldn't change the projection.
+        let direction = glam::vec4(0.0, 0.0, 1.0, 0.0);
+        let bounds = BoundingBox {
+            min: glam::Vec3::splat(-1.0),
+            max: glam::Vec3::splat(1.0),
+        };
+        let offset_bounds = BoundingBox {
+            min: glam::Vec3::splat(-1.0001),
+            max: glam::Vec3::splat(1.0001),
+        };
+        assert_eq!(
+            fit_light_transform(direction, &bounds, 1024),
+            fit_light_transform(direction, &offset_bounds, 1024)
+        );
+    }
+
+    #[test]
+    fn lighting_preset_uniforms() {
+        assert_eq!(
+            StageUniforms::training(),
+            LightingPreset::Training.stage_uniforms()
+        );
+
+        // The neutral preset lights the model from above.
+        let uniforms = LightingPreset::NeutralOverhead.stage_uniforms();
+        assert!(uniforms.light_chr.direction.y > 0.9);
+        assert_eq!(glam::Vec4::splat(3.0), uniforms.light_chr.color);
+
+        // Presets preserve the light scale used for shadows.
+        let uniforms = LightingPreset::BrightFrontal.stage_uniforms();
+        assert_vector_relative_eq!(
+            [25.0, 25.0, 25.0],
+            light_scale(uniforms.light_chr.transform).to_array()
+        );
+    }
+
+    #[test]
+    fn lighting_settings_training() {
+        let settings = LightingSettings::default();
+        assert_eq!(glam::Vec4::ONE, settings.custom_vectors[8]);
+        assert!(settings
+            .light_stage
+            .iter()
+            .all(|l| l.color == glam::Vec4::ZERO));
+
+        let uniforms = settings.stage_uniforms();
+        let training = StageUniforms::training();
+        assert_eq!(training.light_chr.color, uniforms.light_chr.color);
+        assert_vector_relative_eq!(
+            training.light_chr.direction.to_array(),
+            uniforms.light_chr.direction.to_array()
+        );
+        assert_matrix_relative_eq!(
+            training.light_chr.transform.to_cols_array_2d(),
+            uniforms.light_chr.transform.to_cols_array_2d()
+        );
+        assert_eq!(training.scene_attributes, uniforms.scene_attributes);
+    }
+
+    #[test]
+    fn lighting_settings_intensity() {
+        let settings = LightingSettings {
+            light_chr: LightSettings {
+                rotation: glam::Quat::from_rotation_x(-1.0),
+                scale: glam::vec3(20.0, 20.0, 20.0),
+                color: glam::vec4(1.0, 0.5, 0.25, 1.0),
+                intensity: 2.0,
+            },
+            custom_floats: [1.5; 20],
+            custom_booleans: [true; 20],
+            ..Default::default()
+        };
+
+        let uniforms = settings.stage_uniforms();
+        assert_eq!(glam::vec4(2.0, 1.0, 0.5, 2.0), uniforms.light_chr.color);
+        assert_vector_relative_eq!(
+            light_direction(glam::Quat::from_rotation_x(-1.0)).to_array(),
+            uniforms.light_chr.direction.to_array()
+        );
+        assert_eq!(
+            glam::vec4(1.5, 0.0, 0.0, 0.0),
+            uniforms.scene_attributes.custom_float[3]
+        );
+        assert_eq!(
+            glam::uvec4(1, 0, 0, 0),
+            uniforms.scene_attributes.custom_boolean[3]
+        );
+    }
+
     // Test cases based on the direction vector from in game uniform buffers.
     // TODO: Add additional test cases from more stages.
     #[test]
@@ -294,4 +789,44 @@ mod tests {
         assert_relative_eq!(0.32139426, dir.z, epsilon = 0.0001f32);
         assert_eq!(0.0, dir.w);
     }
+
+    #[test]
+    fn light_rotation_from_transform() {
+        let rotation = glam::quat(-0.453154, -0.365998, -0.211309, 0.784886).normalize();
+        let light = Light {
+            transform: light_transform(rotation, glam::vec3(25.0, 25.0, 25.0)),
+            ..Default::default()
+        };
+        assert_vector_relative_eq!(
+            (rotation * glam::Vec3::Z).to_array(),
+            (light.rotation() * glam::Vec3::Z).to_array()
+        );
+        assert_vector_relative_eq!(
+            (rotation * glam::Vec3::X).to_array(),
+            (light.rotation() * glam::Vec3::X).to_array()
+        );
+    }
+
+    #[test]
+    fn set_light_rotation_preserves_scale() {
+        let scale = glam::vec3(25.0, 20.0, 15.0);
+        let mut light = Light {
+            color: glam::Vec4::ONE,
+            direction: light_direction(glam::Quat::IDENTITY),
+            transform: light_transform(glam::Quat::IDENTITY, scale),
+        };
+
+        let rotation = glam::Quat::from_rotation_x(1.0);
+        light.set_rotation(rotation);
+
+        assert_eq!(glam::Vec4::ONE, light.color);
+        assert_vector_relative_eq!(
+            light_direction(rotation).to_array(),
+            light.direction.to_array()
+        );
+        assert_matrix_relative_eq!(
+            light_transform(rotation, scale).to_cols_array_2d(),
+            light.transform.to_cols_array_2d()
+        );
+    }
 }
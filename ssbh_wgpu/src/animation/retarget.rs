@@ -0,0 +1,168 @@
+//! Retargeting animations to skeletons with different bone proportions.
+//!
+//! This allows previewing an animation for one fighter on another fighter's model.
+use std::collections::HashMap;
+
+use glam::Vec3;
+use ssbh_data::{
+    anim_data::{AnimData, GroupType, NodeData, TrackValues},
+    skel_data::SkelData,
+    Vector3,
+};
+
+/// Creates a copy of `anim` for the `target` skel from an anim authored for the `source` skel.
+///
+/// Bones are matched by name.
+/// Bones in `remap` use the mapped name like `"ArmL"` to `"L_Arm"`,
+/// and other bones keep the same name. Use an empty map if both skels share the same bone names.
+///
+/// Rotation and scale are copied unchanged, so the skels should have similar rest orientations.
+/// Translations are offset from the target's rest pose to keep the target's bone lengths
+/// while preserving animated movement like root motion.
+/// Transform tracks for bones not in the `target` skel are removed.
+/// Other groups like visibility and material tracks are unchanged.
+pub fn retarget(
+    anim: &AnimData,
+    source: &SkelData,
+    target: &SkelData,
+    remap: &HashMap<String, String>,
+) -> AnimData {
+    let mut retargeted = anim.clone();
+    for group in &mut retargeted.groups {
+        if group.group_type == GroupType::Transform {
+            group.nodes = group
+                .nodes
+                .iter()
+                .filter_map(|node| retarget_node(node, source, target, remap))
+                .collect();
+        }
+    }
+    retargeted
+}
+
+fn retarget_node(
+    node: &NodeData,
+    source: &SkelData,
+    target: &SkelData,
+    remap: &HashMap<String, String>,
+) -> Option<NodeData> {
+    let name = remap.get(&node.name).unwrap_or(&node.name);
+    let target_translation = rest_translation(target, name)?;
+
+    let mut tracks = node.tracks.clone();
+
+    // Bones missing from the source skel have no rest pose to compare against.
+    if let Some(source_translation) = rest_translation(source, &node.name) {
+        let offset = target_translation - source_translation;
+        for track in &mut tracks {
+            if let TrackValues::Transform(values) = &mut track.values {
+                for value in values {
+                    let translation = Vec3::from(value.translation.to_array()) + offset;
+                    value.translation = Vector3::new(translation.x, translation.y, translation.z);
+                }
+            }
+        }
+    }
+
+    Some(NodeData {
+        name: name.clone(),
+        tracks,
+    })
+}
+
+fn rest_translation(skel: &SkelData, name: &str) -> Option<Vec3> {
+    skel.bones.iter().find(|b| b.name == name).map(|b| {
+        glam::Mat4::from_cols_array_2d(&b.transform)
+            .w_axis
+            .truncate()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ssbh_data::{
+        anim_data::{GroupData, TrackData, Transform, TransformFlags},
+        skel_data::{BillboardType, BoneData},
+        Vector4,
+    };
+
+    use crate::test_utils::skel;
+
+    fn bone(name: &str, translation: Vec3) -> BoneData {
+        BoneData {
+            name: name.to_string(),
+            transform: glam::Mat4::from_translation(translation).to_cols_array_2d(),
+            parent_index: None,
+            billboard_type: BillboardType::Disabled,
+        }
+    }
+
+    fn transform_node(name: &str, translation: Vector3) -> NodeData {
+        NodeData {
+            name: name.to_string(),
+            tracks: vec![TrackData {
+                name: "Transform".to_string(),
+                compensate_scale: false,
+                values: TrackValues::Transform(vec![Transform {
+                    scale: Vector3::new(1.0, 1.0, 1.0),
+                    rotation: Vector4::new(0.0, 0.0, 0.0, 1.0),
+                    translation,
+                }]),
+                transform_flags: TransformFlags::default(),
+            }],
+        }
+    }
+
+    #[test]
+    fn retarget_remapped_bones() {
+        let source = skel(vec![
+            bone("ArmL", Vec3::new(0.0, 1.0, 0.0)),
+            bone("Tail", Vec3::ZERO),
+        ]);
+        let target = skel(vec![
+            bone("L_Arm", Vec3::new(0.0, 3.0, 0.0)),
+            bone("Hip", Vec3::new(1.0, 0.0, 0.0)),
+        ]);
+        let anim = AnimData {
+            major_version: 2,
+            minor_version: 0,
+            final_frame_index: 0.0,
+            groups: vec![GroupData {
+                group_type: GroupType::Transform,
+                nodes: vec![
+                    transform_node("ArmL", Vector3::new(1.0, 2.0, 0.0)),
+                    transform_node("Tail", Vector3::new(1.0, 2.0, 3.0)),
+                    // Bones missing from the source skel keep their translation.
+                    transform_node("Hip", Vector3::new(4.0, 5.0, 6.0)),
+                ],
+            }],
+        };
+
+        let remap = HashMap::from([("ArmL".to_string(), "L_Arm".to_string())]);
+        let retargeted = retarget(&anim, &source, &target, &remap);
+
+        let nodes = &retargeted.groups[0].nodes;
+        assert_eq!(
+            vec!["L_Arm", "Hip"],
+            nodes.iter().map(|n| n.name.as_str()).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            TrackValues::Transform(vec![Transform {
+                scale: Vector3::new(1.0, 1.0, 1.0),
+                rotation: Vector4::new(0.0, 0.0, 0.0, 1.0),
+                translation: Vector3::new(1.0, 4.0, 0.0),
+            }]),
+            nodes[0].tracks[0].values
+        );
+        assert_eq!(
+            TrackValues::Transform(vec![Transform {
+                scale: Vector3::new(1.0, 1.0, 1.0),
+                rotation: Vector4::new(0.0, 0.0, 0.0, 1.0),
+                translation: Vector3::new(4.0, 5.0, 6.0),
+            }]),
+            nodes[1].tracks[0].values
+        );
+    }
+}
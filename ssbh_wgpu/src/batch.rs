@@ -0,0 +1,731 @@
+//! Rendering screenshots of many model folders for regression testing.
+//!
+//! Each folder is loaded, rendered, and freed before the next folder to save on memory.
+//! The returned [BatchReport] lists any load or render errors for each folder.
+//! Use [compare_to_baseline] to find images that changed from a previous batch
+//! such as after editing the shader database or lighting.
+//!
+//! ```rust no_run
+//! # fn test(device: &wgpu::Device, queue: &wgpu::Queue, renderer: &mut ssbh_wgpu::SsbhRenderer, shared_data: &ssbh_wgpu::SharedRenderData) {
+//! use ssbh_wgpu::batch::{model_folder_paths, render_folders, BatchSettings};
+//!
+//! let folders = model_folder_paths("/fighter/mario");
+//! let settings = BatchSettings::default();
+//! let report = render_folders(device, queue, renderer, shared_data, "/fighter/mario", &folders, "output", &settings);
+//! std::fs::write("output/report.json", report.to_json().to_string()).unwrap();
+//! # }
+//! ```
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+use ssbh_data::prelude::*;
+use walkdir::WalkDir;
+
+use crate::{
+    viewport::frame_models, CameraTransforms, CaptureSettings, LoadDiagnostics,
+    MeshValidationError, ModelFolder, ModelRenderOptions, RenderModel, SharedRenderData,
+    SsbhRenderer,
+};
+
+/// The camera used for each folder in [render_folders].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BatchCamera {
+    /// Fit the visible meshes in each folder with the given vertical field of view in radians.
+    Frame { fov_y: f32 },
+    /// Use the same camera for all folders.
+    ///
+    /// The rotation is applied after the translation with angles in radians.
+    Fixed {
+        translation: glam::Vec3,
+        rotation: glam::Vec3,
+        fov_y: f32,
+    },
+}
+
+impl Default for BatchCamera {
+    fn default() -> Self {
+        // Match the default camera for fighter models.
+        Self::Fixed {
+            translation: glam::vec3(0.0, -8.0, -60.0),
+            rotation: glam::Vec3::ZERO,
+            fov_y: 0.5,
+        }
+    }
+}
+
+/// Settings for rendering folders with [render_folders].
+#[derive(Debug)]
+pub struct BatchSettings {
+    pub width: u32,
+    pub height: u32,
+    pub camera: BatchCamera,
+    /// The anim file names like `"a00wait2.nuanmb"` to try in order for each folder.
+    ///
+    /// Anims are loaded from the matching motion folder like `"/mario/motion/body/c00"`
+    /// for `"/mario/model/body/c00"`. Folders without any of the anims use the rest pose.
+    pub anim_names: Vec<String>,
+    /// The frame to render for the anim if found.
+    pub frame: f32,
+    pub render_options: ModelRenderOptions,
+    pub capture_settings: CaptureSettings,
+}
+
+impl Default for BatchSettings {
+    fn default() -> Self {
+        Self {
+            width: 512,
+            height: 512,
+            camera: BatchCamera::default(),
+            anim_names: Vec::new(),
+            frame: 0.0,
+            render_options: ModelRenderOptions::default(),
+            capture_settings: CaptureSettings::default(),
+        }
+    }
+}
+
+/// The results for a single folder from [render_folders].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FolderReport {
+    /// The model folder like `"/mario/model/body/c00"`.
+    pub folder: PathBuf,
+    /// The saved image path like `"output/mario_model_body_c00.png"` or `None` if the image failed to save.
+    pub image: Option<PathBuf>,
+    /// The anim file name used for the pose if found.
+    pub anim: Option<String>,
+    /// The files, textures, and meshes that failed to load.
+    pub load: LoadDiagnostics,
+    /// Meshes that loaded but render incorrectly.
+    pub validation: Vec<MeshValidationError>,
+    /// A description of why rendering or saving the image failed.
+    pub render_error: Option<String>,
+}
+
+impl FolderReport {
+    /// Returns `true` if the folder loaded and rendered without any errors.
+    pub fn is_ok(&self) -> bool {
+        self.load.is_empty() && self.validation.is_empty() && self.render_error.is_none()
+    }
+
+    /// Converts the report to JSON with error messages as strings.
+    pub fn to_json(&self) -> Value {
+        let files: Vec<_> = self
+            .load
+            .files
+            .iter()
+            .chain(self.load.textures.iter())
+            .map(|f| json!({ "name": f.name, "message": f.message }))
+            .collect();
+
+        let meshes: Vec<_> = self
+            .load
+            .meshes
+            .iter()
+            .map(|m| json!({ "name": m.name, "subindex": m.subindex, "message": m.message }))
+            .chain(self.validation.iter().map(|e| {
+                json!({ "name": e.name, "subindex": e.subindex, "message": e.kind.to_string() })
+            }))
+            .collect();
+
+        let texture_fallbacks: Vec<_> = self
+            .load
+            .texture_fallbacks
+            .iter()
+            .map(|f| {
+                json!({
+                    "material_label": f.material_label,
+                    "param_id": format!("{:?}", f.param_id),
+                    "path": f.path,
+                    "fallback": f.fallback,
+                    "message": f.message,
+                })
+            })
+            .collect();
+
+        json!({
+            "folder": self.folder.to_string_lossy(),
+            "image": self.image.as_ref().map(|p| p.to_string_lossy()),
+            "anim": self.anim,
+            "files": files,
+            "meshes": meshes,
+            "texture_fallbacks": texture_fallbacks,
+            "render_error": self.render_error,
+        })
+    }
+}
+
+/// The results for all folders from [render_folders].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BatchReport {
+    pub folders: Vec<FolderReport>,
+}
+
+impl BatchReport {
+    /// The folders with at least one error.
+    pub fn failed(&self) -> impl Iterator<Item = &FolderReport> {
+        self.folders.iter().filter(|f| !f.is_ok())
+    }
+
+    /// Converts the report to JSON with an entry for each folder.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "folders": self.folders.iter().map(FolderReport::to_json).collect::<Vec<_>>(),
+            "failed": self.failed().count(),
+        })
+    }
+}
+
+/// The difference between two images from [compare_images].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageDiff {
+    /// The fraction of pixels from 0.0 to 1.0 with any channel outside the pixel tolerance.
+    pub changed_pixels: f32,
+    /// The largest difference for any channel from 0 to 255.
+    pub max_difference: u8,
+    /// The mean structural similarity index of the luminance from -1.0 to 1.0.
+    /// Identical images have a value of 1.0.
+    pub ssim: f32,
+}
+
+/// The limits for an image to be considered unchanged in [compare_to_baseline].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiffThreshold {
+    /// The largest channel difference from 0 to 255 to ignore for each pixel.
+    /// Small differences are common between GPUs and drivers.
+    pub pixel_tolerance: u8,
+    /// The largest allowed [ImageDiff::changed_pixels].
+    pub max_changed_pixels: f32,
+    /// The smallest allowed [ImageDiff::ssim].
+    pub min_ssim: f32,
+}
+
+impl Default for DiffThreshold {
+    fn default() -> Self {
+        Self {
+            pixel_tolerance: 2,
+            max_changed_pixels: 0.001,
+            min_ssim: 0.99,
+        }
+    }
+}
+
+impl DiffThreshold {
+    /// Returns `true` if `diff` is outside the allowed limits.
+    pub fn is_changed(&self, diff: &ImageDiff) -> bool {
+        diff.changed_pixels > self.max_changed_pixels || diff.ssim < self.min_ssim
+    }
+}
+
+/// The comparison for a single image from [compare_to_baseline].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageComparison {
+    /// The model folder like `"/mario/model/body/c00"`.
+    pub folder: PathBuf,
+    /// The baseline image path like `"baseline/mario_model_body_c00.png"`.
+    pub baseline: PathBuf,
+    /// The differences from the baseline if both images loaded with the same dimensions.
+    pub diff: Option<ImageDiff>,
+    /// `true` if the image differs beyond the threshold or could not be compared.
+    pub changed: bool,
+    /// A description of why the images could not be compared.
+    pub error: Option<String>,
+}
+
+/// The results for all images from [compare_to_baseline].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ComparisonReport {
+    pub images: Vec<ImageComparison>,
+}
+
+impl ComparisonReport {
+    /// The images that changed beyond the threshold or could not be compared.
+    pub fn changed(&self) -> impl Iterator<Item = &ImageComparison> {
+        self.images.iter().filter(|i| i.changed)
+    }
+
+    /// Converts the report to JSON with an entry for each changed image.
+    pub fn to_json(&self) -> Value {
+        let changed: Vec<_> = self
+            .changed()
+            .map(|i| {
+                json!({
+                    "folder": i.folder.to_string_lossy(),
+                    "baseline": i.baseline.to_string_lossy(),
+                    "changed_pixels": i.diff.map(|d| d.changed_pixels),
+                    "max_difference": i.diff.map(|d| d.max_difference),
+                    "ssim": i.diff.map(|d| d.ssim),
+                    "error": i.error,
+                })
+            })
+            .collect();
+
+        json!({
+            "compared": self.images.len(),
+            "changed": changed,
+        })
+    }
+}
+
+/// Compares the rendered images in `report` with images of the same name in `baseline_folder`.
+///
+/// Folders without a rendered image are skipped.
+/// Missing baseline images or images with different dimensions are always considered changed.
+pub fn compare_to_baseline<P: AsRef<Path>>(
+    report: &BatchReport,
+    baseline_folder: P,
+    threshold: &DiffThreshold,
+) -> ComparisonReport {
+    let images = report
+        .folders
+        .iter()
+        .filter_map(|folder| {
+            let image = folder.image.as_ref()?;
+            let baseline = baseline_folder.as_ref().join(image.file_name()?);
+
+            let diff = image::open(image)
+                .and_then(|i| Ok((i.to_rgba8(), image::open(&baseline)?.to_rgba8())))
+                .map_err(|e| e.to_string())
+                .and_then(|(image, baseline)| {
+                    compare_images(&image, &baseline, threshold.pixel_tolerance).ok_or_else(|| {
+                        format!(
+                            "dimensions {:?} do not match baseline dimensions {:?}",
+                            image.dimensions(),
+                            baseline.dimensions()
+                        )
+                    })
+                });
+
+            Some(ImageComparison {
+                folder: folder.folder.clone(),
+                baseline,
+                diff: diff.as_ref().ok().copied(),
+                changed: diff
+                    .as_ref()
+                    .map(|d| threshold.is_changed(d))
+                    .unwrap_or(true),
+                error: diff.err(),
+            })
+        })
+        .collect();
+
+    ComparisonReport { images }
+}
+
+// The window size in pixels for the SSIM calculation.
+const SSIM_WINDOW: u32 = 8;
+
+/// Calculates the differences between two images or `None` if the dimensions do not match.
+///
+/// Channel differences less than or equal to `pixel_tolerance` do not count as changed pixels.
+pub fn compare_images(
+    a: &image::RgbaImage,
+    b: &image::RgbaImage,
+    pixel_tolerance: u8,
+) -> Option<ImageDiff> {
+    if a.dimensions() != b.dimensions() {
+        return None;
+    }
+
+    let mut changed = 0usize;
+    let mut max_difference = 0u8;
+    for (pa, pb) in a.pixels().zip(b.pixels()) {
+        let difference =
+            pa.0.iter()
+                .zip(pb.0.iter())
+                .map(|(ca, cb)| ca.abs_diff(*cb))
+                .max()
+                .unwrap_or(0);
+        max_difference = max_difference.max(difference);
+        if difference > pixel_tolerance {
+            changed += 1;
+        }
+    }
+
+    let pixel_count = a.pixels().len().max(1);
+    Some(ImageDiff {
+        changed_pixels: changed as f32 / pixel_count as f32,
+        max_difference,
+        ssim: ssim(a, b),
+    })
+}
+
+// Mean SSIM over non overlapping windows of the luminance.
+// https://en.wikipedia.org/wiki/Structural_similarity
+fn ssim(a: &image::RgbaImage, b: &image::RgbaImage) -> f32 {
+    const C1: f32 = (0.01 * 255.0) * (0.01 * 255.0);
+    const C2: f32 = (0.03 * 255.0) * (0.03 * 255.0);
+
+    let luma = |p: &image::Rgba<u8>| {
+        0.2126 * p.0[0] as f32 + 0.7152 * p.0[1] as f32 + 0.0722 * p.0[2] as f32
+    };
+
+    let (width, height) = a.dimensions();
+    let mut total = 0.0;
+    let mut window_count = 0;
+    for y0 in (0..height).step_by(SSIM_WINDOW as usize) {
+        for x0 in (0..width).step_by(SSIM_WINDOW as usize) {
+            // Partial windows at the edges still cover every pixel.
+            let values: Vec<_> = (y0..(y0 + SSIM_WINDOW).min(height))
+                .flat_map(|y| (x0..(x0 + SSIM_WINDOW).min(width)).map(move |x| (x, y)))
+                .map(|(x, y)| (luma(a.get_pixel(x, y)), luma(b.get_pixel(x, y))))
+                .collect();
+
+            let n = values.len() as f32;
+            let mean_a = values.iter().map(|(a, _)| a).sum::<f32>() / n;
+            let mean_b = values.iter().map(|(_, b)| b).sum::<f32>() / n;
+            let (mut var_a, mut var_b, mut covariance) = (0.0, 0.0, 0.0);
+            for (a, b) in &values {
+                var_a += (a - mean_a) * (a - mean_a) / n;
+                var_b += (b - mean_b) * (b - mean_b) / n;
+                covariance += (a - mean_a) * (b - mean_b) / n;
+            }
+
+            total += ((2.0 * mean_a * mean_b + C1) * (2.0 * covariance + C2))
+                / ((mean_a * mean_a + mean_b * mean_b + C1) * (var_a + var_b + C2));
+            window_count += 1;
+        }
+    }
+
+    if window_count > 0 {
+        total / window_count as f32
+    } else {
+        1.0
+    }
+}
+
+/// Recursively finds the folders in `root` containing a numshb file sorted by path.
+///
+/// Unlike [load_model_folders](crate::load_model_folders), this does not limit the recursion depth
+/// and only returns paths, so folders can be loaded one at a time.
+pub fn model_folder_paths<P: AsRef<Path>>(root: P) -> Vec<PathBuf> {
+    let mut folders: Vec<_> = WalkDir::new(root)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| {
+            e.file_type().is_file()
+                && e.path().extension().and_then(|e| e.to_str()) == Some("numshb")
+        })
+        .filter_map(|e| e.path().parent().map(Path::to_owned))
+        .collect();
+    folders.sort();
+    folders.dedup();
+    folders
+}
+
+/// Renders each of the `folders` to a PNG image in `output_folder`.
+///
+/// Images are named using the path relative to `source_folder`
+/// like `"mario_model_body_c00.png"` for `"/fighter/mario/model/body/c00"` in `"/fighter"`.
+/// Errors are recorded in the report instead of stopping the batch.
+#[allow(clippy::too_many_arguments)]
+pub fn render_folders<P: AsRef<Path>, O: AsRef<Path>>(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    renderer: &mut SsbhRenderer,
+    shared_data: &SharedRenderData,
+    source_folder: P,
+    folders: &[PathBuf],
+    output_folder: O,
+    settings: &BatchSettings,
+) -> BatchReport {
+    let folders = folders
+        .iter()
+        .map(|folder| {
+            let image = output_folder
+                .as_ref()
+                .join(output_name(source_folder.as_ref(), folder))
+                .with_extension("png");
+            let report = render_folder(
+                device,
+                queue,
+                renderer,
+                shared_data,
+                folder,
+                &image,
+                settings,
+            );
+
+            // Clean up resources before loading the next folder.
+            queue.submit(std::iter::empty());
+            device.poll(wgpu::Maintain::Wait);
+
+            report
+        })
+        .collect();
+
+    BatchReport { folders }
+}
+
+fn render_folder(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    renderer: &mut SsbhRenderer,
+    shared_data: &SharedRenderData,
+    folder: &Path,
+    image_path: &Path,
+    settings: &BatchSettings,
+) -> FolderReport {
+    let model = ModelFolder::load_folder(folder);
+    let (mut render_model, load) =
+        RenderModel::from_folder_with_diagnostics(device, queue, &model, shared_data);
+
+    let anim = find_anim(folder, &settings.anim_names);
+    if let Some((_, anim)) = &anim {
+        render_model.apply_anims(
+            queue,
+            std::iter::once(anim),
+            model.find_skel(),
+            model.find_matl(),
+            model.find_hlpb(),
+            shared_data,
+            settings.frame,
+        );
+    }
+
+    let render_models = [render_model];
+    let aspect = settings.width as f32 / settings.height.max(1) as f32;
+    let camera = match settings.camera {
+        BatchCamera::Frame { fov_y } => frame_models(&render_models, fov_y, aspect),
+        BatchCamera::Fixed {
+            translation,
+            rotation,
+            fov_y,
+        } => fixed_camera(translation, rotation, fov_y, aspect),
+    };
+    renderer.update_camera(
+        queue,
+        CameraTransforms {
+            screen_dimensions: glam::vec4(settings.width as f32, settings.height as f32, 1.0, 0.0),
+            ..camera
+        },
+    );
+
+    let result = renderer
+        .render_to_image(
+            device,
+            queue,
+            settings.width,
+            settings.height,
+            &render_models,
+            shared_data.database(),
+            &settings.render_options,
+            &settings.capture_settings,
+        )
+        .map_err(|e| e.to_string())
+        .and_then(|image| image.save(image_path).map_err(|e| e.to_string()));
+
+    FolderReport {
+        folder: folder.to_owned(),
+        image: result.is_ok().then(|| image_path.to_owned()),
+        anim: anim.map(|(name, _)| name),
+        load,
+        validation: render_models[0].validation_errors(shared_data.database()),
+        render_error: result.err(),
+    }
+}
+
+fn find_anim(folder: &Path, anim_names: &[String]) -> Option<(String, AnimData)> {
+    let anim_folder = motion_folder(folder);
+    anim_names.iter().find_map(|name| {
+        AnimData::from_file(anim_folder.join(name))
+            .ok()
+            .map(|anim| (name.clone(), anim))
+    })
+}
+
+// Fighter anims are in a separate motion folder like /mario/motion/body/c00.
+// Only replace the last "model" component to avoid changing parent folders like /models.
+fn motion_folder(folder: &Path) -> PathBuf {
+    let components: Vec<_> = folder.components().collect();
+    let model_index = components.iter().rposition(|c| c.as_os_str() == "model");
+
+    components
+        .iter()
+        .enumerate()
+        .map(|(i, c)| {
+            if Some(i) == model_index {
+                Path::new("motion")
+            } else {
+                Path::new(c.as_os_str())
+            }
+        })
+        .collect()
+}
+
+fn fixed_camera(
+    translation: glam::Vec3,
+    rotation: glam::Vec3,
+    fov_y: f32,
+    aspect: f32,
+) -> CameraTransforms {
+    let model_view_matrix = glam::Mat4::from_translation(translation)
+        * glam::Mat4::from_rotation_x(rotation.x)
+        * glam::Mat4::from_rotation_y(rotation.y);
+    // Use a large far clip distance to include stage skyboxes.
+    let projection_matrix = glam::Mat4::perspective_rh(fov_y, aspect, 1.0, 400000.0);
+    let mvp_matrix = projection_matrix * model_view_matrix;
+
+    CameraTransforms {
+        model_view_matrix,
+        projection_matrix,
+        mvp_matrix,
+        mvp_inv_matrix: mvp_matrix.inverse(),
+        camera_pos: model_view_matrix.inverse().col(3),
+        screen_dimensions: glam::vec4(aspect, 1.0, 1.0, 0.0),
+    }
+}
+
+// Convert fighter/mario/model/body/c00 to mario_model_body_c00.
+fn output_name(source_folder: &Path, folder: &Path) -> String {
+    folder
+        .strip_prefix(source_folder)
+        .unwrap_or(folder)
+        .components()
+        .filter_map(|c| match c {
+            std::path::Component::Normal(c) => Some(c.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::FileDiagnostic;
+
+    #[test]
+    fn output_name_relative_path() {
+        assert_eq!(
+            "mario_model_body_c00",
+            output_name(
+                Path::new("/fighter"),
+                Path::new("/fighter/mario/model/body/c00")
+            )
+        );
+    }
+
+    #[test]
+    fn motion_folder_fighter() {
+        assert_eq!(
+            Path::new("/fighter/mario/motion/body/c00"),
+            motion_folder(Path::new("/fighter/mario/model/body/c00"))
+        );
+    }
+
+    #[test]
+    fn motion_folder_parent_contains_model() {
+        assert_eq!(
+            Path::new("/home/u/models/model/mario/motion/body/c00"),
+            motion_folder(Path::new("/home/u/models/model/mario/model/body/c00"))
+        );
+    }
+
+    #[test]
+    fn motion_folder_no_model() {
+        assert_eq!(
+            Path::new("/stage/battlefield/normal"),
+            motion_folder(Path::new("/stage/battlefield/normal"))
+        );
+    }
+
+    #[test]
+    fn output_name_outside_source_folder() {
+        assert_eq!(
+            "stage_model",
+            output_name(Path::new("/fighter"), Path::new("/stage/model"))
+        );
+    }
+
+    #[test]
+    fn compare_images_identical() {
+        let image =
+            image::RgbaImage::from_fn(16, 16, |x, y| image::Rgba([x as u8, y as u8, 0, 255]));
+        assert_eq!(
+            Some(ImageDiff {
+                changed_pixels: 0.0,
+                max_difference: 0,
+                ssim: 1.0
+            }),
+            compare_images(&image, &image, 0)
+        );
+    }
+
+    #[test]
+    fn compare_images_changed_pixels() {
+        let a = image::RgbaImage::from_pixel(4, 4, image::Rgba([128, 128, 128, 255]));
+        let mut b = a.clone();
+        b.put_pixel(0, 0, image::Rgba([129, 128, 128, 255]));
+        b.put_pixel(1, 0, image::Rgba([228, 128, 128, 255]));
+
+        let diff = compare_images(&a, &b, 1).unwrap();
+        assert_eq!(1.0 / 16.0, diff.changed_pixels);
+        assert_eq!(100, diff.max_difference);
+        assert!(diff.ssim < 1.0);
+    }
+
+    #[test]
+    fn compare_images_different_dimensions() {
+        let a = image::RgbaImage::new(4, 4);
+        let b = image::RgbaImage::new(4, 8);
+        assert_eq!(None, compare_images(&a, &b, 0));
+    }
+
+    #[test]
+    fn batch_report_to_json_failed() {
+        let report = BatchReport {
+            folders: vec![
+                FolderReport {
+                    folder: PathBuf::from("a"),
+                    image: Some(PathBuf::from("a.png")),
+                    anim: None,
+                    load: LoadDiagnostics::default(),
+                    validation: Vec::new(),
+                    render_error: None,
+                },
+                FolderReport {
+                    folder: PathBuf::from("b"),
+                    image: None,
+                    anim: Some("a00wait2.nuanmb".to_string()),
+                    load: LoadDiagnostics {
+                        files: vec![FileDiagnostic {
+                            name: "model.numatb".to_string(),
+                            message: "error".to_string(),
+                        }],
+                        ..Default::default()
+                    },
+                    validation: Vec::new(),
+                    render_error: Some("error".to_string()),
+                },
+            ],
+        };
+
+        assert_eq!(
+            json!({
+                "folders": [
+                    {
+                        "folder": "a",
+                        "image": "a.png",
+                        "anim": null,
+                        "files": [],
+                        "meshes": [],
+                        "texture_fallbacks": [],
+                        "render_error": null
+                    },
+                    {
+                        "folder": "b",
+                        "image": null,
+                        "anim": "a00wait2.nuanmb",
+                        "files": [{ "name": "model.numatb", "message": "error" }],
+                        "meshes": [],
+                        "texture_fallbacks": [],
+                        "render_error": "error"
+                    }
+                ],
+                "failed": 1
+            }),
+            report.to_json()
+        );
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::HashSet;
+
+use ssbh_data::{hlpb_data::HlpbData, skel_data::SkelData};
+
+/// Settings for hiding bones to make dense skeletons easier to view.
+///
+/// The default filter shows all bones.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BoneFilter {
+    /// Only show these bones and their descendants or all bones if empty.
+    pub root_bones: Vec<String>,
+    /// Hide bones with names matching any of these patterns.
+    /// Patterns can use `*` to match any characters like `"H_*"` or `"*_null"`.
+    pub excluded_patterns: Vec<String>,
+    /// Hide bones with a joint shorter than this length in logical pixels on screen.
+    /// Bones without a parent are always shown.
+    pub min_screen_size: f32,
+}
+
+impl BoneFilter {
+    /// A filter that hides helper, null, and effect bones.
+    pub fn exclude_helper_bones() -> Self {
+        Self {
+            excluded_patterns: vec!["H_*".to_string(), "*_null".to_string(), "*_eff".to_string()],
+            ..Default::default()
+        }
+    }
+
+    /// Returns `true` if `name` matches any of the excluded patterns.
+    pub fn is_excluded(&self, name: &str) -> bool {
+        self.excluded_patterns
+            .iter()
+            .any(|p| matches_pattern(name, p))
+    }
+
+    /// Calculates the visibility for each bone in `skel` before applying the screen size.
+    pub fn visible_bones(&self, skel: &SkelData) -> Vec<bool> {
+        let names: Vec<_> = skel.bones.iter().map(|b| b.name.as_str()).collect();
+        let parent_indices: Vec<_> = skel.bones.iter().map(|b| b.parent_index).collect();
+        self.visible_bones_from_hierarchy(&names, &parent_indices)
+    }
+
+    pub(crate) fn visible_bones_from_hierarchy<S: AsRef<str>>(
+        &self,
+        names: &[S],
+        parent_indices: &[Option<usize>],
+    ) -> Vec<bool> {
+        (0..names.len())
+            .map(|i| {
+                !self.is_excluded(names[i].as_ref())
+                    && (self.root_bones.is_empty()
+                        || self.has_root_ancestor(names, parent_indices, i))
+            })
+            .collect()
+    }
+
+    fn has_root_ancestor<S: AsRef<str>>(
+        &self,
+        names: &[S],
+        parent_indices: &[Option<usize>],
+        index: usize,
+    ) -> bool {
+        let mut current = Some(index);
+        // Limit the iterations in case the skeleton has cycles.
+        for _ in 0..names.len() {
+            match current.and_then(|i| names.get(i).map(|n| (i, n))) {
+                Some((i, name)) => {
+                    if self.root_bones.iter().any(|r| r == name.as_ref()) {
+                        return true;
+                    }
+                    current = parent_indices.get(i).copied().flatten();
+                }
+                None => return false,
+            }
+        }
+        false
+    }
+}
+
+/// The names of the helper bones driven by the aim and orient constraints in `hlpb`.
+pub fn constrained_bone_names(hlpb: &HlpbData) -> HashSet<String> {
+    hlpb.aim_constraints
+        .iter()
+        .map(|c| c.target_bone_name2.clone())
+        .chain(
+            hlpb.orient_constraints
+                .iter()
+                .map(|c| c.target_bone_name.clone()),
+        )
+        .collect()
+}
+
+/// Returns `true` if `name` matches `pattern` where `*` matches any sequence of characters.
+pub(crate) fn matches_pattern(name: &str, pattern: &str) -> bool {
+    let name: Vec<_> = name.chars().collect();
+    let pattern: Vec<_> = pattern.chars().collect();
+
+    // Greedy wildcard matching that backtracks to the most recent '*'.
+    let (mut n, mut p) = (0, 0);
+    let mut star = None;
+    while n < name.len() {
+        if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == name[n] {
+            n += 1;
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ssbh_data::skel_data::{BillboardType, BoneData};
+
+    use crate::test_utils::skel;
+
+    fn bone(name: &str, parent_index: Option<usize>) -> BoneData {
+        BoneData {
+            name: name.to_string(),
+            transform: [[0.0; 4]; 4],
+            parent_index,
+            billboard_type: BillboardType::Disabled,
+        }
+    }
+
+    #[test]
+    fn matches_pattern_exact() {
+        assert!(matches_pattern("Trans", "Trans"));
+        assert!(!matches_pattern("Trans", "Rot"));
+        assert!(!matches_pattern("Trans", "Tran"));
+    }
+
+    #[test]
+    fn matches_pattern_wildcards() {
+        assert!(matches_pattern("H_ExoHipN_L", "H_*"));
+        assert!(matches_pattern("Head_null", "*_null"));
+        assert!(matches_pattern("Arm_eff_L", "*_eff*"));
+        assert!(matches_pattern("", "*"));
+        assert!(!matches_pattern("Hip", "H_*"));
+        assert!(!matches_pattern("Head_nullL", "*_null"));
+    }
+
+    #[test]
+    fn visible_bones_default() {
+        let skel = skel(vec![bone("Trans", None), bone("H_A", Some(0))]);
+        assert_eq!(vec![true, true], BoneFilter::default().visible_bones(&skel));
+    }
+
+    #[test]
+    fn visible_bones_exclude_helper_bones() {
+        let skel = skel(vec![
+            bone("Trans", None),
+            bone("H_A", Some(0)),
+            bone("Head_null", Some(0)),
+            bone("Hand_eff", Some(0)),
+        ]);
+        assert_eq!(
+            vec![true, false, false, false],
+            BoneFilter::exclude_helper_bones().visible_bones(&skel)
+        );
+    }
+
+    #[test]
+    fn visible_bones_root_bones() {
+        let skel = skel(vec![
+            bone("Trans", None),
+            bone("ArmL", Some(0)),
+            bone("HandL", Some(1)),
+            bone("ArmR", Some(0)),
+        ]);
+        let filter = BoneFilter {
+            root_bones: vec!["ArmL".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(vec![false, true, true, false], filter.visible_bones(&skel));
+    }
+
+    #[test]
+    fn visible_bones_root_bones_cycle() {
+        let skel = skel(vec![bone("A", Some(1)), bone("B", Some(0))]);
+        let filter = BoneFilter {
+            root_bones: vec!["C".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(vec![false, false], filter.visible_bones(&skel));
+    }
+}
@@ -0,0 +1,237 @@
+use glam::{Vec2, Vec3, Vec4, Vec4Swizzles};
+
+use crate::CameraTransforms;
+
+// These should match the scale factors in skeleton.wgsl.
+pub(crate) const BONE_SCALE: f32 = 0.0025;
+pub(crate) const JOINT_SCALE: f32 = 0.005;
+
+/// The screen space area covered by a bone and its joint in physical pixels.
+///
+/// Regions use the same projection and sizes as [ModelRenderOptions::draw_bones](crate::ModelRenderOptions::draw_bones)
+/// to support hovering or clicking bones without reading back rendered pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoneHitRegion {
+    /// The index of the bone in the skel.
+    pub bone_index: usize,
+    /// The projected bone position with the origin at the top left corner of the screen.
+    pub center: Vec2,
+    /// The radius of the bone sphere.
+    pub radius: f32,
+    /// The joint between the bone and its parent or `None` for bones without a parent.
+    pub joint: Option<JointHitRegion>,
+    /// The clip space depth of the bone position for sorting overlapping regions.
+    pub depth: f32,
+}
+
+/// A capsule from a bone to its parent in physical pixels.
+///
+/// The capsule approximates the joint pyramid and slightly overestimates its area.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JointHitRegion {
+    pub start: Vec2,
+    pub end: Vec2,
+    pub radius: f32,
+}
+
+impl BoneHitRegion {
+    /// Returns `true` if `point` is inside the bone sphere or joint.
+    pub fn contains(&self, point: Vec2) -> bool {
+        self.contains_bone(point) || self.contains_joint(point)
+    }
+
+    /// Returns `true` if `point` is inside the bone sphere.
+    pub fn contains_bone(&self, point: Vec2) -> bool {
+        point.distance(self.center) <= self.radius
+    }
+
+    /// Returns `true` if `point` is inside the joint to the parent bone.
+    pub fn contains_joint(&self, point: Vec2) -> bool {
+        self.joint
+            .map(|j| distance_to_segment(point, j.start, j.end) <= j.radius)
+            .unwrap_or(false)
+    }
+}
+
+/// Finds the region closest to the camera containing `point`.
+///
+/// Bone spheres take priority over joints since joints often overlap other bones.
+pub fn hit_test(regions: &[BoneHitRegion], point: Vec2) -> Option<&BoneHitRegion> {
+    let closest = |contains: fn(&BoneHitRegion, Vec2) -> bool| {
+        regions
+            .iter()
+            .filter(|r| contains(r, point))
+            .min_by(|a, b| a.depth.total_cmp(&b.depth))
+    };
+    closest(BoneHitRegion::contains_bone).or_else(|| closest(BoneHitRegion::contains_joint))
+}
+
+/// Calculates the regions for bones with world space `positions` and `parent_indices`.
+/// Bones are skipped if `visible` is `false` or if they are behind the camera.
+///
+/// The `min_screen_size` in logical pixels should match [BoneFilter::min_screen_size](crate::BoneFilter::min_screen_size).
+pub(crate) fn bone_hit_regions(
+    positions: &[Vec3],
+    parent_indices: &[Option<usize>],
+    visible: &[bool],
+    min_screen_size: f32,
+    camera: &CameraTransforms,
+) -> Vec<BoneHitRegion> {
+    let scale_factor = camera.screen_dimensions.z;
+
+    positions
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| visible.get(*i).copied().unwrap_or(true))
+        .filter_map(|(i, position)| {
+            let (center, clip) = project(*position, camera)?;
+
+            let distance = position.distance(camera.camera_pos.xyz());
+            let radius = screen_radius(distance * BONE_SCALE, clip.w, camera);
+
+            let parent = parent_indices
+                .get(i)
+                .copied()
+                .flatten()
+                .and_then(|p| positions.get(p));
+
+            let joint = match parent {
+                Some(parent) => {
+                    // The parent may be behind the camera even if the bone is visible.
+                    let (end, _) = project(*parent, camera)?;
+                    if center.distance(end) / scale_factor < min_screen_size {
+                        return None;
+                    }
+                    Some(JointHitRegion {
+                        start: center,
+                        end,
+                        radius: screen_radius(distance * JOINT_SCALE, clip.w, camera),
+                    })
+                }
+                None => None,
+            };
+
+            Some(BoneHitRegion {
+                bone_index: i,
+                center,
+                radius,
+                joint,
+                depth: clip.z / clip.w,
+            })
+        })
+        .collect()
+}
+
+pub(crate) fn project(position: Vec3, camera: &CameraTransforms) -> Option<(Vec2, Vec4)> {
+    let clip = camera.mvp_matrix * position.extend(1.0);
+    if clip.w <= 0.0 {
+        return None;
+    }
+
+    let (x, y) = crate::viewport::world_to_screen(
+        position,
+        camera.mvp_matrix,
+        camera.screen_dimensions.x as u32,
+        camera.screen_dimensions.y as u32,
+    );
+    Some((Vec2::new(x, y), clip))
+}
+
+fn screen_radius(world_radius: f32, clip_w: f32, camera: &CameraTransforms) -> f32 {
+    // Perspective and orthographic projections both scale view space y by the same factor.
+    // Convert from clip space [-1,1] to screen space [0,height].
+    world_radius * camera.projection_matrix.y_axis.y.abs() / clip_w
+        * camera.screen_dimensions.y
+        * 0.5
+}
+
+pub(crate) fn distance_to_segment(point: Vec2, start: Vec2, end: Vec2) -> f32 {
+    let segment = end - start;
+    let length_squared = segment.length_squared();
+    if length_squared == 0.0 {
+        return point.distance(start);
+    }
+
+    let t = ((point - start).dot(segment) / length_squared).clamp(0.0, 1.0);
+    point.distance(start + segment * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_utils::orthographic_camera;
+
+    #[test]
+    fn distance_to_segment_endpoints() {
+        let start = Vec2::new(0.0, 0.0);
+        let end = Vec2::new(10.0, 0.0);
+        assert_eq!(2.0, distance_to_segment(Vec2::new(-2.0, 0.0), start, end));
+        assert_eq!(3.0, distance_to_segment(Vec2::new(5.0, 3.0), start, end));
+        assert_eq!(1.0, distance_to_segment(Vec2::new(0.0, 1.0), start, start));
+    }
+
+    #[test]
+    fn bone_hit_regions_parent() {
+        let regions = bone_hit_regions(
+            &[Vec3::ZERO, Vec3::new(0.5, 0.0, 0.0)],
+            &[None, Some(0)],
+            &[true, true],
+            0.0,
+            &orthographic_camera(200.0, 100.0),
+        );
+        assert_eq!(2, regions.len());
+
+        assert_eq!(Vec2::new(100.0, 50.0), regions[0].center);
+        assert_eq!(None, regions[0].joint);
+
+        // The bone radius is 10.0 * 0.0025 in world units.
+        assert!(approx::relative_eq!(
+            1.25,
+            regions[0].radius,
+            epsilon = 0.0001
+        ));
+        let joint = regions[1].joint.unwrap();
+        assert_eq!(Vec2::new(150.0, 50.0), joint.start);
+        assert_eq!(Vec2::new(100.0, 50.0), joint.end);
+    }
+
+    #[test]
+    fn bone_hit_regions_hidden() {
+        let regions = bone_hit_regions(
+            &[
+                Vec3::ZERO,
+                Vec3::new(0.01, 0.0, 0.0),
+                Vec3::new(0.5, 0.0, 0.0),
+            ],
+            &[None, Some(0), Some(0)],
+            &[false, true, true],
+            2.0,
+            &orthographic_camera(200.0, 100.0),
+        );
+        // Bone 1 is 1 pixel from its parent on screen.
+        assert_eq!(1, regions.len());
+        assert_eq!(2, regions[0].bone_index);
+    }
+
+    #[test]
+    fn hit_test_prefers_bones() {
+        let regions = bone_hit_regions(
+            &[Vec3::ZERO, Vec3::new(0.5, 0.0, 0.0)],
+            &[None, Some(0)],
+            &[true, true],
+            0.0,
+            &orthographic_camera(200.0, 100.0),
+        );
+        // The joint from bone 1 overlaps bone 0.
+        assert_eq!(
+            Some(0),
+            hit_test(&regions, Vec2::new(100.5, 50.0)).map(|r| r.bone_index)
+        );
+        assert_eq!(
+            Some(1),
+            hit_test(&regions, Vec2::new(125.0, 50.0)).map(|r| r.bone_index)
+        );
+        assert_eq!(None, hit_test(&regions, Vec2::new(125.0, 10.0)));
+    }
+}
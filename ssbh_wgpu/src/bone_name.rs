@@ -1,6 +1,11 @@
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use strum::{Display, EnumIter, EnumString};
 
-use crate::{viewport::world_to_screen, RenderModel};
+use crate::{viewport::world_to_screen, BoneFilter, RenderModel};
 use glam::Vec4Swizzles;
 use glyphon::{
     Attrs, Buffer, Cache, Color, FontSystem, Metrics, Resolution, Shaping, SwashCache, TextArea,
@@ -15,10 +20,149 @@ pub struct BoneNameRenderer {
     renderer: TextRenderer,
 }
 
+/// How to handle bone names that overlap names closer to the camera.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone, Display, EnumIter, EnumString)]
+pub enum LabelOverlap {
+    /// Draw all names even if they overlap.
+    #[default]
+    Allow,
+    /// Skip names that overlap a name closer to the camera.
+    Hide,
+    /// Move names down until they no longer overlap.
+    /// Names that still overlap after [MAX_LABEL_OFFSETS] attempts are skipped.
+    Offset,
+}
+
+/// The maximum number of line heights to move a name for [LabelOverlap::Offset].
+pub const MAX_LABEL_OFFSETS: usize = 4;
+
+/// Settings for which bone names to draw and how they should appear.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoneNameOptions {
+    /// The font size in pixels for names at [Self::reference_distance].
+    pub font_size: f32,
+    /// Scale the font size by `reference_distance / distance` if `Some`
+    /// or use a constant font size if `None`.
+    pub reference_distance: Option<f32>,
+    /// The minimum and maximum font size in pixels when scaling with distance.
+    pub font_size_range: (f32, f32),
+    /// Hide names for bones that are excluded by the filter.
+    /// The [BoneFilter::min_screen_size] is ignored.
+    pub filter: BoneFilter,
+    /// Only draw names for these bones or all bones if empty.
+    pub included_bones: HashSet<String>,
+    /// The default RGBA text color.
+    pub color: [u8; 4],
+    /// Text colors for specific bone names that override [Self::color].
+    pub name_colors: HashMap<String, [u8; 4]>,
+    /// Bone names to highlight with [Self::selected_color].
+    /// Selected names take priority over other names for [Self::overlap].
+    pub selected_bones: HashSet<String>,
+    pub selected_color: [u8; 4],
+    /// Bone names to draw with [Self::constrained_color] like the helper bones from
+    /// [constrained_bone_names](crate::constrained_bone_names).
+    /// Selection and [Self::name_colors] take priority over the constrained color.
+    pub constrained_bones: HashSet<String>,
+    pub constrained_color: [u8; 4],
+    /// How to handle names that overlap names closer to the camera.
+    pub overlap: LabelOverlap,
+}
+
+impl Default for BoneNameOptions {
+    fn default() -> Self {
+        Self {
+            font_size: 12.0,
+            reference_distance: None,
+            font_size_range: (6.0, 24.0),
+            filter: BoneFilter::default(),
+            included_bones: HashSet::new(),
+            color: [255, 255, 255, 255],
+            name_colors: HashMap::new(),
+            selected_bones: HashSet::new(),
+            selected_color: [255, 255, 0, 255],
+            constrained_bones: HashSet::new(),
+            // Match the helper bone color used for drawing skeletons.
+            constrained_color: [149, 0, 203, 255],
+            overlap: LabelOverlap::Allow,
+        }
+    }
+}
+
+impl BoneNameOptions {
+    fn text_color(&self, name: &str) -> [u8; 4] {
+        if self.selected_bones.contains(name) {
+            self.selected_color
+        } else if let Some(color) = self.name_colors.get(name) {
+            *color
+        } else if self.constrained_bones.contains(name) {
+            self.constrained_color
+        } else {
+            self.color
+        }
+    }
+
+    fn is_included(&self, name: &str) -> bool {
+        self.included_bones.is_empty() || self.included_bones.contains(name)
+    }
+
+    fn scaled_font_size(&self, distance: f32) -> f32 {
+        match self.reference_distance {
+            Some(reference) if distance > 0.0 => {
+                let (min, max) = self.font_size_range;
+                (self.font_size * reference / distance).clamp(min, max.max(min))
+            }
+            _ => self.font_size,
+        }
+    }
+}
+
 struct BoneText {
     buffer: Buffer,
     left: f32,
     top: f32,
+    color: [u8; 4],
+}
+
+struct BoneLabel<'a> {
+    name: &'a str,
+    left: f32,
+    top: f32,
+    distance: f32,
+    selected: bool,
+}
+
+/// A screen space rectangle in pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct LabelRect {
+    left: f32,
+    top: f32,
+    width: f32,
+    height: f32,
+}
+
+impl LabelRect {
+    fn overlaps(&self, other: &LabelRect) -> bool {
+        self.left < other.left + other.width
+            && other.left < self.left + self.width
+            && self.top < other.top + other.height
+            && other.top < self.top + self.height
+    }
+}
+
+/// Finds the position for `rect` that doesn't overlap the already `placed` rects
+/// or `None` if the label should be skipped.
+fn place_label(rect: LabelRect, placed: &[LabelRect], overlap: LabelOverlap) -> Option<LabelRect> {
+    let is_free = |rect: &LabelRect| !placed.iter().any(|r| r.overlaps(rect));
+    match overlap {
+        LabelOverlap::Allow => Some(rect),
+        LabelOverlap::Hide => is_free(&rect).then_some(rect),
+        LabelOverlap::Offset => (0..=MAX_LABEL_OFFSETS)
+            .map(|i| LabelRect {
+                top: rect.top + rect.height * i as f32,
+                ..rect
+            })
+            .find(is_free),
+    }
 }
 
 impl BoneNameRenderer {
@@ -67,9 +211,9 @@ impl BoneNameRenderer {
         width: u32,
         height: u32,
         mvp: glam::Mat4,
-        font_size: f32,
+        options: &BoneNameOptions,
     ) {
-        self.prepare(device, queue, models, width, height, mvp, font_size);
+        self.prepare(device, queue, models, width, height, mvp, options);
         self.render(render_pass);
     }
 
@@ -82,18 +226,56 @@ impl BoneNameRenderer {
         width: u32,
         height: u32,
         mvp: glam::Mat4,
-        font_size: f32,
+        options: &BoneNameOptions,
     ) {
-        // TODO: create buffers ahead of time to avoid per frame allocations?
-        let mut bone_texts = Vec::new();
+        let mut labels = Vec::new();
         for model in models {
-            for (name, transform) in model.bone_names_animated_world_transforms() {
-                let bone_text =
-                    self.create_bone_text(name, transform, mvp, width, height, font_size);
-                bone_texts.push(bone_text);
+            let visible_bones = model.visible_bones(&options.filter);
+            for ((name, transform), visible) in model
+                .bone_names_animated_world_transforms()
+                .zip(visible_bones)
+            {
+                if visible && options.is_included(name) {
+                    if let Some(label) = bone_label(name, transform, mvp, width, height, options) {
+                        labels.push(label);
+                    }
+                }
             }
         }
 
+        // Draw selected and closer names first so they take priority for overlapping names.
+        labels.sort_by(|a, b| {
+            b.selected
+                .cmp(&a.selected)
+                .then(a.distance.total_cmp(&b.distance))
+        });
+
+        // TODO: create buffers ahead of time to avoid per frame allocations?
+        let mut bone_texts = Vec::new();
+        let mut rects = Vec::new();
+        for label in labels {
+            let font_size = options.scaled_font_size(label.distance);
+            let mut bone_text = self.create_bone_text(&label, width, height, font_size, options);
+
+            let rect = LabelRect {
+                left: bone_text.left,
+                top: bone_text.top,
+                width: bone_text
+                    .buffer
+                    .layout_runs()
+                    .map(|r| r.line_w)
+                    .fold(0.0, f32::max),
+                height: font_size,
+            };
+            let Some(rect) = place_label(rect, &rects, options.overlap) else {
+                continue;
+            };
+            bone_text.top = rect.top;
+
+            rects.push(rect);
+            bone_texts.push(bone_text);
+        }
+
         let text_areas = bone_texts.iter().map(|b| TextArea {
             buffer: &b.buffer,
             left: b.left,
@@ -105,7 +287,7 @@ impl BoneNameRenderer {
                 right: width as i32,
                 bottom: height as i32,
             },
-            default_color: Color::rgb(255, 255, 255),
+            default_color: Color::rgba(b.color[0], b.color[1], b.color[2], b.color[3]),
             custom_glyphs: &[],
         });
 
@@ -137,12 +319,11 @@ impl BoneNameRenderer {
     // TODO: Should these be cached and stored?
     fn create_bone_text(
         &mut self,
-        text: &str,
-        transform: glam::Mat4,
-        mvp: glam::Mat4,
+        label: &BoneLabel,
         width: u32,
         height: u32,
         font_size: f32,
+        options: &BoneNameOptions,
     ) -> BoneText {
         let mut buffer = Buffer::new(
             &mut self.font_system,
@@ -157,12 +338,188 @@ impl BoneNameRenderer {
             Some(width as f32),
             Some(height as f32),
         );
-        buffer.set_text(&mut self.font_system, text, Attrs::new(), Shaping::Advanced);
+        buffer.set_text(
+            &mut self.font_system,
+            label.name,
+            Attrs::new(),
+            Shaping::Advanced,
+        );
         buffer.shape_until_scroll(&mut self.font_system, false);
 
-        let position = transform * glam::vec4(0.0, 0.0, 0.0, 1.0);
-        let (left, top) = world_to_screen(position.xyz(), mvp, width, height);
+        BoneText {
+            buffer,
+            left: label.left,
+            top: label.top,
+            color: options.text_color(label.name),
+        }
+    }
+}
+
+fn bone_label<'a>(
+    name: &'a str,
+    transform: glam::Mat4,
+    mvp: glam::Mat4,
+    width: u32,
+    height: u32,
+    options: &BoneNameOptions,
+) -> Option<BoneLabel<'a>> {
+    let position = transform * glam::vec4(0.0, 0.0, 0.0, 1.0);
+
+    // Skip bones behind the camera to avoid drawing mirrored names.
+    // The clip space w is the view space depth for perspective projections.
+    let distance = (mvp * position).w;
+    if distance <= 0.0 {
+        return None;
+    }
+
+    let (left, top) = world_to_screen(position.xyz(), mvp, width, height);
+
+    Some(BoneLabel {
+        name,
+        left,
+        top,
+        distance,
+        selected: options.selected_bones.contains(name),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn label_rects_overlap() {
+        let a = LabelRect {
+            left: 0.0,
+            top: 0.0,
+            width: 10.0,
+            height: 5.0,
+        };
+        let b = LabelRect {
+            left: 9.0,
+            top: 4.0,
+            width: 10.0,
+            height: 5.0,
+        };
+        assert!(a.overlaps(&b));
+        assert!(b.overlaps(&a));
+    }
+
+    #[test]
+    fn label_rects_touching_edges() {
+        let a = LabelRect {
+            left: 0.0,
+            top: 0.0,
+            width: 10.0,
+            height: 5.0,
+        };
+        let b = LabelRect {
+            left: 10.0,
+            top: 0.0,
+            width: 10.0,
+            height: 5.0,
+        };
+        assert!(!a.overlaps(&b));
+    }
+
+    #[test]
+    fn scaled_font_size_constant() {
+        let options = BoneNameOptions::default();
+        assert_eq!(12.0, options.scaled_font_size(100.0));
+    }
+
+    #[test]
+    fn scaled_font_size_distance() {
+        let options = BoneNameOptions {
+            font_size: 12.0,
+            reference_distance: Some(10.0),
+            font_size_range: (6.0, 24.0),
+            ..Default::default()
+        };
+        assert_eq!(12.0, options.scaled_font_size(10.0));
+        assert_eq!(8.0, options.scaled_font_size(15.0));
+        assert_eq!(6.0, options.scaled_font_size(100.0));
+        assert_eq!(24.0, options.scaled_font_size(1.0));
+    }
+
+    #[test]
+    fn text_color_selected() {
+        let options = BoneNameOptions {
+            name_colors: [("Hip".to_string(), [255, 0, 0, 255])].into(),
+            selected_bones: ["Head".to_string()].into(),
+            ..Default::default()
+        };
+        assert_eq!([255, 0, 0, 255], options.text_color("Hip"));
+        assert_eq!([255, 255, 0, 255], options.text_color("Head"));
+        assert_eq!([255, 255, 255, 255], options.text_color("Trans"));
+    }
+
+    #[test]
+    fn text_color_constrained() {
+        let options = BoneNameOptions {
+            selected_bones: ["H_Elbow".to_string()].into(),
+            constrained_bones: ["H_Elbow".to_string(), "H_Knee".to_string()].into(),
+            ..Default::default()
+        };
+        assert_eq!([255, 255, 0, 255], options.text_color("H_Elbow"));
+        assert_eq!([149, 0, 203, 255], options.text_color("H_Knee"));
+        assert_eq!([255, 255, 255, 255], options.text_color("Hip"));
+    }
+
+    #[test]
+    fn included_bones() {
+        let options = BoneNameOptions {
+            included_bones: ["Hip".to_string()].into(),
+            ..Default::default()
+        };
+        assert!(options.is_included("Hip"));
+        assert!(!options.is_included("Head"));
+        assert!(BoneNameOptions::default().is_included("Head"));
+    }
+
+    fn rect(top: f32) -> LabelRect {
+        LabelRect {
+            left: 0.0,
+            top,
+            width: 10.0,
+            height: 5.0,
+        }
+    }
+
+    #[test]
+    fn place_label_allow() {
+        assert_eq!(
+            Some(rect(0.0)),
+            place_label(rect(0.0), &[rect(0.0)], LabelOverlap::Allow)
+        );
+    }
+
+    #[test]
+    fn place_label_hide() {
+        assert_eq!(
+            None,
+            place_label(rect(0.0), &[rect(0.0)], LabelOverlap::Hide)
+        );
+        assert_eq!(
+            Some(rect(5.0)),
+            place_label(rect(5.0), &[rect(0.0)], LabelOverlap::Hide)
+        );
+    }
+
+    #[test]
+    fn place_label_offset() {
+        // Move past both of the stacked labels.
+        assert_eq!(
+            Some(rect(10.0)),
+            place_label(rect(0.0), &[rect(0.0), rect(4.0)], LabelOverlap::Offset)
+        );
+    }
 
-        BoneText { buffer, left, top }
+    #[test]
+    fn place_label_offset_no_space() {
+        let placed: Vec<_> = (0..=MAX_LABEL_OFFSETS)
+            .map(|i| rect(i as f32 * 5.0))
+            .collect();
+        assert_eq!(None, place_label(rect(0.0), &placed, LabelOverlap::Offset));
     }
 }
@@ -1,8 +1,9 @@
 use crate::{
     animation::AnimationTransforms,
+    constrained_bone_names,
     renderer::INVERTED_STENCIL_MASK_STATE,
     shape::{sphere_indices, sphere_vertices, IndexedMeshBuffers},
-    DeviceBufferExt,
+    BoneFilter, DeviceBufferExt,
 };
 use glam::Vec4Swizzles;
 use ssbh_data::{hlpb_data::HlpbData, skel_data::SkelData};
@@ -128,11 +129,48 @@ pub fn bone_colors_buffer(
 ) -> wgpu::Buffer {
     device.create_buffer_from_data(
         "Bone Colors Buffer",
-        &bone_colors(skel, hlpb),
-        wgpu::BufferUsages::UNIFORM,
+        &bone_colors(skel, hlpb, None),
+        wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
     )
 }
 
+pub fn bone_visibility_buffer(device: &wgpu::Device, skel: Option<&SkelData>) -> wgpu::Buffer {
+    device.create_buffer_from_data(
+        "Bone Visibility Buffer",
+        &[bone_visibility(skel, &BoneFilter::default())],
+        wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    )
+}
+
+pub fn bone_visibility(
+    skel: Option<&SkelData>,
+    filter: &BoneFilter,
+) -> crate::shader::skeleton::BoneVisibility {
+    // Pack values into vec4 to match the shader.
+    let mut visible = [glam::UVec4::ZERO; crate::animation::MAX_BONE_COUNT / 4];
+    let mut parent_indices = [glam::IVec4::splat(-1); crate::animation::MAX_BONE_COUNT / 4];
+
+    if let Some(skel) = skel {
+        let visible_bones = filter.visible_bones(skel);
+        for (i, (bone, is_visible)) in skel
+            .bones
+            .iter()
+            .zip(visible_bones)
+            .enumerate()
+            .take(crate::animation::MAX_BONE_COUNT)
+        {
+            visible[i / 4][i % 4] = is_visible as u32;
+            parent_indices[i / 4][i % 4] = bone.parent_index.map(|p| p as i32).unwrap_or(-1);
+        }
+    }
+
+    crate::shader::skeleton::BoneVisibility {
+        visible,
+        parent_indices,
+        min_screen_size: glam::vec4(filter.min_screen_size, 0.0, 0.0, 0.0),
+    }
+}
+
 pub fn bone_vertex_buffer(device: &wgpu::Device) -> wgpu::Buffer {
     device.create_buffer_from_data(
         "Bone Vertex Buffer",
@@ -254,32 +292,28 @@ fn pyramid_indices() -> Vec<u32> {
     ]
 }
 
-fn bone_colors(skel: Option<&SkelData>, hlpb: Option<&HlpbData>) -> Vec<[f32; 4]> {
+pub fn bone_colors(
+    skel: Option<&SkelData>,
+    hlpb: Option<&HlpbData>,
+    color: Option<[f32; 4]>,
+) -> Vec<[f32; 4]> {
     // Match the color scheme used for the Blender addon.
-    let helper_color = [0.3, 0.0, 0.6, 1.0];
-    let default_color = [0.65, 0.65, 0.65, 1.0];
+    let helper_color = color.unwrap_or([0.3, 0.0, 0.6, 1.0]);
+    let default_color = color.unwrap_or([0.65, 0.65, 0.65, 1.0]);
+
+    let helper_bones = hlpb.map(constrained_bone_names).unwrap_or_default();
 
     let mut colors = vec![[0.0; 4]; crate::animation::MAX_BONE_COUNT];
     if let Some(skel) = skel {
         for (i, bone) in skel.bones.iter().enumerate() {
-            colors[i] = default_color;
-
             // TODO: Check for swing bones.
 
             // Color helper bones using a different color.
-            if let Some(hlpb) = hlpb {
-                for constraint in &hlpb.aim_constraints {
-                    if bone.name == constraint.target_bone_name2 {
-                        colors[i] = helper_color;
-                    }
-                }
-
-                for constraint in &hlpb.orient_constraints {
-                    if bone.name == constraint.target_bone_name {
-                        colors[i] = helper_color;
-                    }
-                }
-            }
+            colors[i] = if helper_bones.contains(&bone.name) {
+                helper_color
+            } else {
+                default_color
+            };
         }
     }
     colors
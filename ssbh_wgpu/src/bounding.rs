@@ -0,0 +1,252 @@
+use glam::{Mat4, Vec3, Vec4};
+
+use crate::RenderModel;
+
+/// An axis aligned bounding box.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+/// A sphere containing all points in a [BoundingBox].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingSphere {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl BoundingBox {
+    /// Calculates the bounds of `points` or `None` if there are no points.
+    pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Option<Self> {
+        points.into_iter().fold(None, |bounds, p| {
+            Some(match bounds {
+                Some(BoundingBox { min, max }) => BoundingBox {
+                    min: min.min(p),
+                    max: max.max(p),
+                },
+                None => BoundingBox { min: p, max: p },
+            })
+        })
+    }
+
+    /// The smallest box containing both `self` and `other`.
+    pub fn union(&self, other: &BoundingBox) -> BoundingBox {
+        BoundingBox {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    /// The bounds of the corners of `self` after applying `transform`.
+    pub fn transform(&self, transform: Mat4) -> BoundingBox {
+        // The points are nonempty, so this always succeeds.
+        BoundingBox::from_points(
+            self.corners()
+                .into_iter()
+                .map(|p| transform.transform_point3(p)),
+        )
+        .unwrap_or(*self)
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) / 2.0
+    }
+
+    pub fn corners(&self) -> [Vec3; 8] {
+        let BoundingBox { min, max } = *self;
+        [
+            Vec3::new(min.x, min.y, min.z),
+            Vec3::new(max.x, min.y, min.z),
+            Vec3::new(min.x, max.y, min.z),
+            Vec3::new(max.x, max.y, min.z),
+            Vec3::new(min.x, min.y, max.z),
+            Vec3::new(max.x, min.y, max.z),
+            Vec3::new(min.x, max.y, max.z),
+            Vec3::new(max.x, max.y, max.z),
+        ]
+    }
+
+    pub fn bounding_sphere(&self) -> BoundingSphere {
+        BoundingSphere {
+            center: self.center(),
+            radius: (self.max - self.min).length() / 2.0,
+        }
+    }
+}
+
+impl BoundingSphere {
+    /// A sphere containing `self` after applying `transform`.
+    /// Non uniform scaling uses the largest scale to conservatively scale the radius.
+    pub fn transform(&self, transform: Mat4) -> BoundingSphere {
+        let scale = transform
+            .x_axis
+            .truncate()
+            .length()
+            .max(transform.y_axis.truncate().length())
+            .max(transform.z_axis.truncate().length());
+        BoundingSphere {
+            center: transform.transform_point3(self.center),
+            radius: self.radius * scale,
+        }
+    }
+}
+
+/// The six clipping planes of a camera's view volume for culling objects outside the view.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    // Planes are stored as (normal, distance) with normals pointing inside the frustum.
+    planes: [Vec4; 6],
+}
+
+impl Frustum {
+    /// Extracts the planes from a combined view projection matrix like [crate::CameraTransforms::mvp_matrix].
+    /// The projection should use a clip space depth range of `0.0` to `1.0` like WGPU.
+    pub fn from_matrix(mvp: Mat4) -> Self {
+        let (r0, r1, r2, r3) = (mvp.row(0), mvp.row(1), mvp.row(2), mvp.row(3));
+        let planes = [r3 + r0, r3 - r0, r3 + r1, r3 - r1, r2, r3 - r2].map(|p| {
+            // Normalize to make sphere distance checks use world units.
+            let length = p.truncate().length();
+            if length > 0.0 {
+                p / length
+            } else {
+                p
+            }
+        });
+        Self { planes }
+    }
+
+    /// Returns `true` if any part of `sphere` may be inside the frustum.
+    /// This is conservative and may return `true` for some spheres near the corners.
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.planes
+            .iter()
+            .all(|p| p.truncate().dot(sphere.center) + p.w >= -sphere.radius)
+    }
+}
+
+/// Calculates the combined bounds of all the visible meshes in the visible `render_models`.
+///
+/// If `animated` is `true`, the bounds account for the current pose from [RenderModel::apply_anims].
+/// Animated bounds are conservative and may be larger than the actual skinned geometry.
+/// Returns `None` if there are no visible meshes.
+pub fn scene_bounds(render_models: &[RenderModel], animated: bool) -> Option<BoundingBox> {
+    render_models
+        .iter()
+        .filter(|m| m.is_visible)
+        .filter_map(|m| m.bounding_box(animated))
+        .reduce(|a, b| a.union(&b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::assert_vector_relative_eq;
+
+    #[test]
+    fn from_points_empty() {
+        assert_eq!(None, BoundingBox::from_points(Vec::new()));
+    }
+
+    #[test]
+    fn from_points() {
+        assert_eq!(
+            Some(BoundingBox {
+                min: Vec3::new(-1.0, 0.0, -3.0),
+                max: Vec3::new(2.0, 5.0, 3.0)
+            }),
+            BoundingBox::from_points([
+                Vec3::new(-1.0, 5.0, 3.0),
+                Vec3::new(2.0, 0.0, -3.0),
+                Vec3::new(0.0, 1.0, 0.0)
+            ])
+        );
+    }
+
+    #[test]
+    fn transform_rotation() {
+        let bounds = BoundingBox {
+            min: Vec3::new(0.0, 0.0, 0.0),
+            max: Vec3::new(2.0, 1.0, 1.0),
+        }
+        .transform(Mat4::from_rotation_z(90.0f32.to_radians()));
+
+        assert_vector_relative_eq!([-1.0, 0.0, 0.0], bounds.min.to_array());
+        assert_vector_relative_eq!([0.0, 2.0, 1.0], bounds.max.to_array());
+    }
+
+    #[test]
+    fn bounding_sphere() {
+        assert_eq!(
+            BoundingSphere {
+                center: Vec3::new(1.0, 1.0, 1.0),
+                radius: 3.0f32.sqrt()
+            },
+            BoundingBox {
+                min: Vec3::ZERO,
+                max: Vec3::splat(2.0)
+            }
+            .bounding_sphere()
+        );
+    }
+
+    fn test_frustum() -> Frustum {
+        let view = Mat4::look_at_rh(Vec3::new(0.0, 0.0, 10.0), Vec3::ZERO, Vec3::Y);
+        let projection = Mat4::perspective_rh(0.5, 1.0, 1.0, 100.0);
+        Frustum::from_matrix(projection * view)
+    }
+
+    #[test]
+    fn frustum_intersects_sphere_inside() {
+        assert!(test_frustum().intersects_sphere(&BoundingSphere {
+            center: Vec3::ZERO,
+            radius: 1.0
+        }));
+    }
+
+    #[test]
+    fn frustum_intersects_sphere_outside() {
+        let frustum = test_frustum();
+        // Behind the camera.
+        assert!(!frustum.intersects_sphere(&BoundingSphere {
+            center: Vec3::new(0.0, 0.0, 20.0),
+            radius: 1.0
+        }));
+        // Past the far plane.
+        assert!(!frustum.intersects_sphere(&BoundingSphere {
+            center: Vec3::new(0.0, 0.0, -200.0),
+            radius: 1.0
+        }));
+        // Off to the side.
+        assert!(!frustum.intersects_sphere(&BoundingSphere {
+            center: Vec3::new(50.0, 0.0, 0.0),
+            radius: 1.0
+        }));
+    }
+
+    #[test]
+    fn frustum_intersects_sphere_partially_inside() {
+        // The center is outside the left plane but the radius overlaps the view.
+        assert!(test_frustum().intersects_sphere(&BoundingSphere {
+            center: Vec3::new(-5.0, 0.0, 0.0),
+            radius: 4.0
+        }));
+    }
+
+    #[test]
+    fn transform_sphere_non_uniform_scale() {
+        let sphere = BoundingSphere {
+            center: Vec3::new(1.0, 0.0, 0.0),
+            radius: 2.0,
+        };
+        let transform = Mat4::from_scale_rotation_translation(
+            Vec3::new(1.0, 3.0, 2.0),
+            glam::Quat::IDENTITY,
+            Vec3::new(0.0, 5.0, 0.0),
+        );
+        let sphere = sphere.transform(transform);
+        assert_vector_relative_eq!([1.0, 5.0, 0.0], sphere.center.to_array());
+        assert_eq!(6.0, sphere.radius);
+    }
+}
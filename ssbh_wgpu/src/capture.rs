@@ -0,0 +1,431 @@
+use crate::{
+    animation::camera::{showcase_camera, CameraAnimValues, ShowcaseSettings},
+    BoundingSphere, ModelRenderOptions, RenderModel, ShaderDatabase, SsbhRenderer, SsbhWgpuError,
+};
+
+/// Settings for rendering to an image with [SsbhRenderer::render_to_image](crate::SsbhRenderer::render_to_image).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CaptureSettings {
+    /// Render at this multiple of the output dimensions and downscale for smoother edges.
+    /// A value of `1` disables supersampling.
+    pub supersampling: u32,
+    /// Render with a fully transparent background instead of the renderer's clear color.
+    /// See [SsbhRenderer::set_transparent_background](crate::SsbhRenderer::set_transparent_background).
+    pub transparent_background: bool,
+}
+
+impl Default for CaptureSettings {
+    fn default() -> Self {
+        Self {
+            supersampling: 1,
+            transparent_background: false,
+        }
+    }
+}
+
+/// Settings for rendering a rotating model with [SsbhRenderer::render_turntable].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TurntableSettings {
+    /// The number of frames for a single full rotation.
+    pub frame_count: u32,
+    /// The horizontal distance from the center of the models
+    /// or `None` to fit the models vertically in the view.
+    pub radius: Option<f32>,
+    /// The vertical offset from the center of the models.
+    pub height: f32,
+    /// The settings used to render each frame.
+    pub capture: CaptureSettings,
+}
+
+impl Default for TurntableSettings {
+    fn default() -> Self {
+        Self {
+            frame_count: 60,
+            radius: None,
+            height: 0.0,
+            capture: CaptureSettings::default(),
+        }
+    }
+}
+
+/// An iterator over the frames of a turntable from [SsbhRenderer::render_turntable].
+///
+/// Each frame is rendered when calling [Iterator::next].
+/// The renderer's camera is restored when the iterator is dropped.
+pub struct Turntable<'a> {
+    pub(crate) renderer: &'a mut SsbhRenderer,
+    pub(crate) device: &'a wgpu::Device,
+    pub(crate) queue: &'a wgpu::Queue,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) scale_factor: f64,
+    pub(crate) render_models: &'a [RenderModel],
+    pub(crate) shader_database: &'a ShaderDatabase,
+    pub(crate) options: &'a ModelRenderOptions,
+    pub(crate) settings: TurntableSettings,
+    pub(crate) bounds: BoundingSphere,
+    // Turntable cameras use the projection of the camera before rendering.
+    pub(crate) camera: CameraAnimValues,
+    pub(crate) previous_camera: crate::CameraTransforms,
+    pub(crate) frame: u32,
+}
+
+impl Iterator for Turntable<'_> {
+    type Item = Result<image::RgbaImage, SsbhWgpuError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.frame >= self.settings.frame_count {
+            return None;
+        }
+
+        let camera = turntable_camera(&self.settings, &self.bounds, self.frame, &self.camera);
+        self.renderer.update_camera(
+            self.queue,
+            camera.to_transforms(self.width, self.height, self.scale_factor),
+        );
+        self.frame += 1;
+
+        Some(self.renderer.render_to_image(
+            self.device,
+            self.queue,
+            self.width,
+            self.height,
+            self.render_models,
+            self.shader_database,
+            self.options,
+            &self.settings.capture,
+        ))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.settings.frame_count.saturating_sub(self.frame) as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+impl Drop for Turntable<'_> {
+    fn drop(&mut self) {
+        self.renderer
+            .update_camera(self.queue, self.previous_camera);
+    }
+}
+
+fn turntable_camera(
+    settings: &TurntableSettings,
+    bounds: &BoundingSphere,
+    frame: u32,
+    camera: &CameraAnimValues,
+) -> CameraAnimValues {
+    // Fit the bounds vertically in the view.
+    // TODO: Account for the aspect ratio for narrow images.
+    let radius = settings
+        .radius
+        .unwrap_or_else(|| bounds.radius / (camera.fov_y_radians / 2.0).sin());
+
+    // Complete one rotation every second to use the frame fraction as the time.
+    showcase_camera(
+        &ShowcaseSettings {
+            radius,
+            height: settings.height,
+            degrees_per_second: 360.0,
+            bounce_period: None,
+        },
+        bounds,
+        frame as f32 / settings.frame_count.max(1) as f32,
+        camera.fov_y_radians,
+        camera.near_clip,
+        camera.far_clip,
+    )
+}
+
+/// Encodes `frames` as a looping animated PNG that plays at `frames_per_second`.
+///
+/// All frames must have the same dimensions like the frames from [SsbhRenderer::render_turntable].
+pub fn encode_apng(
+    frames: &[image::RgbaImage],
+    frames_per_second: u16,
+) -> Result<Vec<u8>, SsbhWgpuError> {
+    let (width, height) = frames.first().map(|f| f.dimensions()).unwrap_or((0, 0));
+    if frames.iter().any(|f| f.dimensions() != (width, height)) {
+        return Err(SsbhWgpuError::ImageDataMismatch);
+    }
+
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    // A play count of 0 loops forever.
+    encoder.set_animated(frames.len() as u32, 0)?;
+    encoder.set_frame_delay(1, frames_per_second.max(1))?;
+
+    let mut writer = encoder.write_header()?;
+    for frame in frames {
+        writer.write_image_data(frame.as_raw())?;
+    }
+    writer.finish()?;
+
+    Ok(bytes)
+}
+
+/// Returns `true` if `format` needs its red and blue channels swapped to be RGBA.
+pub(crate) fn is_bgra(format: wgpu::TextureFormat) -> Result<bool, SsbhWgpuError> {
+    match format {
+        wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => Ok(false),
+        wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => Ok(true),
+        _ => Err(SsbhWgpuError::UnsupportedSurfaceFormat(format)),
+    }
+}
+
+/// Rows in a texture to buffer copy must be aligned to [wgpu::COPY_BYTES_PER_ROW_ALIGNMENT].
+pub(crate) fn padded_bytes_per_row(width: u32) -> u32 {
+    let alignment = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    (width * 4).div_ceil(alignment) * alignment
+}
+
+pub(crate) fn copy_output_to_buffer(
+    device: &wgpu::Device,
+    encoder: &mut wgpu::CommandEncoder,
+    output: &wgpu::Texture,
+) -> wgpu::Buffer {
+    let size = output.size();
+    let bytes_per_row = padded_bytes_per_row(size.width);
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Capture Buffer"),
+        size: bytes_per_row as u64 * size.height as u64,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    encoder.copy_texture_to_buffer(
+        wgpu::ImageCopyTexture {
+            aspect: wgpu::TextureAspect::All,
+            texture: output,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+        },
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(bytes_per_row),
+                rows_per_image: Some(size.height),
+            },
+        },
+        size,
+    );
+
+    buffer
+}
+
+/// Waits for the GPU to finish writing to `buffer` and returns the tightly packed RGBA pixels.
+pub(crate) fn read_buffer_rgba(
+    device: &wgpu::Device,
+    buffer: &wgpu::Buffer,
+    width: u32,
+    height: u32,
+    bgra: bool,
+) -> Result<Vec<u8>, SsbhWgpuError> {
+    let slice = buffer.slice(..);
+    map_slice(device, &slice)?;
+
+    let rgba = unpad_rows(
+        &slice.get_mapped_range(),
+        width,
+        height,
+        padded_bytes_per_row(width),
+        bgra,
+    );
+    buffer.unmap();
+
+    Ok(rgba)
+}
+
+/// Blocks until `slice` is mapped for reading.
+pub(crate) fn map_slice(
+    device: &wgpu::Device,
+    slice: &wgpu::BufferSlice<'_>,
+) -> Result<(), SsbhWgpuError> {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        // The receiver is only dropped if this function already returned.
+        let _ = sender.send(result);
+    });
+    device.poll(wgpu::Maintain::Wait);
+    receiver
+        .recv()
+        .map_err(|_| SsbhWgpuError::BufferMapCanceled)??;
+    Ok(())
+}
+
+fn unpad_rows(data: &[u8], width: u32, height: u32, bytes_per_row: u32, bgra: bool) -> Vec<u8> {
+    let row_size = width as usize * 4;
+    let mut rgba: Vec<u8> = data
+        .chunks_exact(bytes_per_row as usize)
+        .take(height as usize)
+        .flat_map(|row| &row[..row_size])
+        .copied()
+        .collect();
+
+    if bgra {
+        rgba.chunks_exact_mut(4).for_each(|p| p.swap(0, 2));
+    }
+
+    rgba
+}
+
+/// Averages each `factor` x `factor` block of pixels in the RGBA `data`.
+///
+/// Colors are weighted by alpha to avoid dark edges on transparent backgrounds.
+pub(crate) fn downsample(data: &[u8], width: u32, height: u32, factor: u32) -> Vec<u8> {
+    let (output_width, output_height) = (width / factor, height / factor);
+    let mut output = Vec::with_capacity(output_width as usize * output_height as usize * 4);
+
+    for y in 0..output_height {
+        for x in 0..output_width {
+            let mut color_sum = [0.0f32; 3];
+            let mut weighted_sum = [0.0f32; 3];
+            let mut alpha_sum = 0.0f32;
+
+            for sy in y * factor..(y + 1) * factor {
+                for sx in x * factor..(x + 1) * factor {
+                    let i = (sy * width + sx) as usize * 4;
+                    let pixel = &data[i..i + 4];
+                    let alpha = pixel[3] as f32 / 255.0;
+                    for ((sum, weighted), value) in color_sum
+                        .iter_mut()
+                        .zip(weighted_sum.iter_mut())
+                        .zip(&pixel[..3])
+                    {
+                        *sum += *value as f32;
+                        *weighted += *value as f32 * alpha;
+                    }
+                    alpha_sum += alpha;
+                }
+            }
+
+            let count = (factor * factor) as f32;
+            output.extend(color_sum.iter().zip(weighted_sum).map(|(sum, weighted)| {
+                let value = if alpha_sum > 0.0 {
+                    weighted / alpha_sum
+                } else {
+                    sum / count
+                };
+                value.round() as u8
+            }));
+            output.push((alpha_sum / count * 255.0).round() as u8);
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::assert_vector_relative_eq;
+
+    fn camera() -> CameraAnimValues {
+        CameraAnimValues {
+            scale: glam::Vec3::ONE,
+            rotation: glam::Quat::IDENTITY,
+            translation: glam::Vec3::ZERO,
+            fov_y_radians: 1.0,
+            near_clip: 0.1,
+            far_clip: 1000.0,
+        }
+    }
+
+    #[test]
+    fn turntable_camera_rotation() {
+        let settings = TurntableSettings {
+            frame_count: 4,
+            radius: Some(10.0),
+            ..Default::default()
+        };
+        let bounds = BoundingSphere {
+            center: glam::vec3(0.0, 5.0, 0.0),
+            radius: 1.0,
+        };
+
+        let values = turntable_camera(&settings, &bounds, 0, &camera());
+        assert_vector_relative_eq!([0.0, -5.0, -10.0], values.translation.to_array());
+
+        let values = turntable_camera(&settings, &bounds, 2, &camera());
+        assert_vector_relative_eq!([0.0, -5.0, 10.0], values.translation.to_array());
+        assert_eq!(1.0, values.fov_y_radians);
+    }
+
+    #[test]
+    fn turntable_camera_fit_bounds() {
+        let bounds = BoundingSphere {
+            center: glam::Vec3::ZERO,
+            radius: 1.0,
+        };
+        let values = turntable_camera(&TurntableSettings::default(), &bounds, 0, &camera());
+        let distance = 1.0 / 0.5f32.sin();
+        assert_vector_relative_eq!([0.0, 0.0, -distance], values.translation.to_array());
+    }
+
+    #[test]
+    fn encode_apng_frames() {
+        let frames = vec![image::RgbaImage::new(2, 2); 3];
+        let bytes = encode_apng(&frames, 30).unwrap();
+
+        let reader = png::Decoder::new(&bytes[..]).read_info().unwrap();
+        assert_eq!((2, 2), reader.info().size());
+        assert_eq!(3, reader.info().animation_control.unwrap().num_frames);
+    }
+
+    #[test]
+    fn encode_apng_mismatched_dimensions() {
+        let frames = [image::RgbaImage::new(2, 2), image::RgbaImage::new(1, 1)];
+        assert!(matches!(
+            encode_apng(&frames, 30),
+            Err(SsbhWgpuError::ImageDataMismatch)
+        ));
+    }
+
+    #[test]
+    fn padded_bytes_per_row_alignment() {
+        assert_eq!(256, padded_bytes_per_row(1));
+        assert_eq!(256, padded_bytes_per_row(64));
+        assert_eq!(512, padded_bytes_per_row(65));
+    }
+
+    #[test]
+    fn unpad_rows_bgra() {
+        let mut data = vec![0u8; 512];
+        data[0..4].copy_from_slice(&[1, 2, 3, 4]);
+        data[256..260].copy_from_slice(&[5, 6, 7, 8]);
+        assert_eq!(
+            vec![3, 2, 1, 4, 7, 6, 5, 8],
+            unpad_rows(&data, 1, 2, 256, true)
+        );
+    }
+
+    #[test]
+    fn downsample_opaque() {
+        let data = [
+            [0, 0, 0, 255],
+            [255, 255, 255, 255],
+            [0, 0, 0, 255],
+            [255, 255, 255, 255],
+        ]
+        .concat();
+        assert_eq!(vec![128, 128, 128, 255], downsample(&data, 2, 2, 2));
+    }
+
+    #[test]
+    fn downsample_transparent_edges() {
+        // Transparent pixels shouldn't darken the color.
+        let data = [[255, 0, 0, 255], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]].concat();
+        assert_eq!(vec![255, 0, 0, 64], downsample(&data, 2, 2, 2));
+    }
+
+    #[test]
+    fn downsample_fully_transparent() {
+        assert_eq!(vec![0, 0, 0, 0], downsample(&[0u8; 16], 2, 2, 2));
+    }
+}
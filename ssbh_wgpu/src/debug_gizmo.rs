@@ -0,0 +1,359 @@
+use std::f32::consts::PI;
+
+use crate::{
+    animation::MAX_BONE_COUNT, renderer::DEPTH_STENCIL_FORMAT, shader::model::StageUniforms,
+    CameraTransforms, DeviceBufferExt, QueueExt,
+};
+
+// Each line is a pair of vertices with interleaved positions and colors.
+const FRUSTUM_VERTEX_COUNT: usize = 12 * 2;
+const CIRCLE_SEGMENTS: usize = 16;
+// An arrow with four lines for the head and a sphere using three circles.
+const LIGHT_VERTEX_COUNT: usize = (5 + 3 * CIRCLE_SEGMENTS) * 2;
+const MAX_LIGHT_VERTEX_COUNT: usize = 9 * LIGHT_VERTEX_COUNT;
+// A single line for each pair of corresponding bones.
+const MAX_COMPARISON_VERTEX_COUNT: usize = MAX_BONE_COUNT * 2;
+
+// Bones with the same index in both skeletons.
+const MATCHING_BONE_COLOR: [f32; 4] = [1.0, 1.0, 0.0, 1.0];
+// Bones that have a different index in each skeleton.
+const REORDERED_BONE_COLOR: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+
+/// Line gizmos for debugging animated cameras, stage lighting, and skeleton differences.
+pub struct DebugGizmoRenderData {
+    pipeline: wgpu::RenderPipeline,
+    bind_group: crate::shader::debug_gizmo::bind_groups::BindGroup0,
+    frustum_buffer: wgpu::Buffer,
+    frustum_vertex_count: u32,
+    lights_buffer: wgpu::Buffer,
+    lights_vertex_count: u32,
+    comparison_buffer: wgpu::Buffer,
+    comparison_vertex_count: u32,
+}
+
+impl DebugGizmoRenderData {
+    pub fn new(
+        device: &wgpu::Device,
+        camera_buffer: &wgpu::Buffer,
+        surface_format: wgpu::TextureFormat,
+    ) -> Self {
+        let shader = crate::shader::debug_gizmo::create_shader_module(device);
+        let layout = crate::shader::debug_gizmo::create_pipeline_layout(device);
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Debug Gizmo Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[
+                    crate::shader::debug_gizmo::VertexInput::vertex_buffer_layout(
+                        wgpu::VertexStepMode::Vertex,
+                    ),
+                ],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(surface_format.into())],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::LineList,
+                ..Default::default()
+            },
+            // Draw on top of the models but let bones draw over the gizmos.
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_STENCIL_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let bind_group = crate::shader::debug_gizmo::bind_groups::BindGroup0::from_bindings(
+            device,
+            crate::shader::debug_gizmo::bind_groups::BindGroupLayout0 {
+                camera: camera_buffer.as_entire_buffer_binding(),
+            },
+        );
+
+        // Allocate enough space for the maximum number of lines to avoid recreating buffers.
+        let frustum_buffer = device.create_buffer_from_data(
+            "Debug Gizmo Frustum Buffer",
+            &[[0.0f32; 4]; FRUSTUM_VERTEX_COUNT * 2],
+            wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        );
+        let lights_buffer = device.create_buffer_from_data(
+            "Debug Gizmo Lights Buffer",
+            &[[0.0f32; 4]; MAX_LIGHT_VERTEX_COUNT * 2],
+            wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        );
+        let comparison_buffer = device.create_buffer_from_data(
+            "Debug Gizmo Skeleton Comparison Buffer",
+            &[[0.0f32; 4]; MAX_COMPARISON_VERTEX_COUNT * 2],
+            wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        );
+
+        Self {
+            pipeline,
+            bind_group,
+            frustum_buffer,
+            frustum_vertex_count: 0,
+            lights_buffer,
+            lights_vertex_count: 0,
+            comparison_buffer,
+            comparison_vertex_count: 0,
+        }
+    }
+
+    /// Updates the lines between bones with the same name in `a` and `b`
+    /// or hides the lines if either skeleton is empty.
+    pub fn update_skeleton_comparison(
+        &mut self,
+        queue: &wgpu::Queue,
+        a: &[(&str, glam::Vec3)],
+        b: &[(&str, glam::Vec3)],
+    ) {
+        let vertices = skeleton_comparison_vertices(a, b);
+        self.comparison_vertex_count = (vertices.len() / 2) as u32;
+        if !vertices.is_empty() {
+            queue.write_data(&self.comparison_buffer, &vertices);
+        }
+    }
+
+    /// Updates the frustum lines for `camera` or hides the frustum if `None`.
+    pub fn update_frustum(&mut self, queue: &wgpu::Queue, camera: Option<&CameraTransforms>) {
+        let vertices = camera.map(frustum_vertices).unwrap_or_default();
+        self.frustum_vertex_count = (vertices.len() / 2) as u32;
+        if !vertices.is_empty() {
+            queue.write_data(&self.frustum_buffer, &vertices);
+        }
+    }
+
+    /// Updates the arrows and spheres for the lights in `stage_uniforms` pointing at `target`.
+    pub fn update_lights(
+        &mut self,
+        queue: &wgpu::Queue,
+        stage_uniforms: &StageUniforms,
+        target: glam::Vec3,
+        distance: f32,
+    ) {
+        let vertices = lights_vertices(stage_uniforms, target, distance);
+        self.lights_vertex_count = (vertices.len() / 2) as u32;
+        if !vertices.is_empty() {
+            queue.write_data(&self.lights_buffer, &vertices);
+        }
+    }
+
+    pub fn draw(
+        &self,
+        pass: &mut wgpu::RenderPass<'_>,
+        draw_frustum: bool,
+        draw_lights: bool,
+        draw_comparison: bool,
+    ) {
+        pass.set_pipeline(&self.pipeline);
+        crate::shader::debug_gizmo::set_bind_groups(pass, &self.bind_group);
+
+        if draw_frustum && self.frustum_vertex_count > 0 {
+            pass.set_vertex_buffer(0, self.frustum_buffer.slice(..));
+            pass.draw(0..self.frustum_vertex_count, 0..1);
+        }
+
+        if draw_lights && self.lights_vertex_count > 0 {
+            pass.set_vertex_buffer(0, self.lights_buffer.slice(..));
+            pass.draw(0..self.lights_vertex_count, 0..1);
+        }
+
+        if draw_comparison && self.comparison_vertex_count > 0 {
+            pass.set_vertex_buffer(0, self.comparison_buffer.slice(..));
+            pass.draw(0..self.comparison_vertex_count, 0..1);
+        }
+    }
+}
+
+fn frustum_vertices(camera: &CameraTransforms) -> Vec<[f32; 4]> {
+    // Unproject the corners of the near and far planes.
+    let corners: Vec<_> = [0.0, 1.0]
+        .into_iter()
+        .flat_map(|z| {
+            [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+                .map(|(x, y)| camera.mvp_inv_matrix.project_point3(glam::vec3(x, y, z)))
+        })
+        .collect();
+
+    let color = [1.0, 1.0, 1.0, 1.0];
+    let mut vertices = Vec::new();
+    for i in 0..4 {
+        let j = (i + 1) % 4;
+        // Near plane, far plane, and the edges connecting them.
+        for (a, b) in [(i, j), (i + 4, j + 4), (i, i + 4)] {
+            add_line(&mut vertices, corners[a], corners[b], color);
+        }
+    }
+    vertices
+}
+
+fn lights_vertices(
+    stage_uniforms: &StageUniforms,
+    target: glam::Vec3,
+    distance: f32,
+) -> Vec<[f32; 4]> {
+    let mut vertices = Vec::new();
+
+    let lights =
+        std::iter::once(&stage_uniforms.light_chr).chain(stage_uniforms.light_stage.iter());
+    for light in lights {
+        // Unused stage lights have no color.
+        let color = light.color.truncate();
+        let max_component = color.max_element();
+        if max_component <= 0.0 {
+            continue;
+        }
+        // Light colors can be greater than 1.0, so normalize for display.
+        let color = (color / max_component.max(1.0)).extend(1.0).to_array();
+
+        // The light direction points towards the light.
+        let direction = light
+            .direction
+            .truncate()
+            .try_normalize()
+            .unwrap_or(glam::Vec3::Z);
+        let position = target + direction * distance;
+
+        // An arrow from the light towards the target.
+        add_line(&mut vertices, position, target, color);
+        let head_length = distance * 0.1;
+        let (side_a, side_b) = direction.any_orthonormal_pair();
+        for side in [side_a, -side_a, side_b, -side_b] {
+            let head = target + (direction + side * 0.5) * head_length;
+            add_line(&mut vertices, head, target, color);
+        }
+
+        // A sphere at the light position using circles on each axis.
+        let radius = distance * 0.05;
+        let axes = [
+            (glam::Vec3::X, glam::Vec3::Y),
+            (glam::Vec3::Y, glam::Vec3::Z),
+            (glam::Vec3::Z, glam::Vec3::X),
+        ];
+        for (u, v) in axes {
+            for i in 0..CIRCLE_SEGMENTS {
+                let point = |i: usize| {
+                    let angle = 2.0 * PI * i as f32 / CIRCLE_SEGMENTS as f32;
+                    let (s, c) = angle.sin_cos();
+                    position + (u * c + v * s) * radius
+                };
+                add_line(&mut vertices, point(i), point(i + 1), color);
+            }
+        }
+    }
+
+    vertices
+}
+
+fn skeleton_comparison_vertices(
+    a: &[(&str, glam::Vec3)],
+    b: &[(&str, glam::Vec3)],
+) -> Vec<[f32; 4]> {
+    let mut vertices = Vec::new();
+    for (i, (name, position)) in a.iter().enumerate().take(MAX_BONE_COUNT) {
+        // Match bones by name since the bone order may differ between skeletons.
+        if let Some((j, (_, other_position))) = b.iter().enumerate().find(|(_, (n, _))| n == name) {
+            let color = if i == j {
+                MATCHING_BONE_COLOR
+            } else {
+                REORDERED_BONE_COLOR
+            };
+            add_line(&mut vertices, *position, *other_position, color);
+        }
+    }
+    vertices
+}
+
+fn add_line(vertices: &mut Vec<[f32; 4]>, a: glam::Vec3, b: glam::Vec3, color: [f32; 4]) {
+    // Pos0 Color0 Pos1 Color1
+    vertices.push(a.extend(1.0).to_array());
+    vertices.push(color);
+    vertices.push(b.extend(1.0).to_array());
+    vertices.push(color);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::assert_vector_relative_eq;
+
+    #[test]
+    fn frustum_vertices_near_far_corners() {
+        let projection_matrix = glam::Mat4::perspective_rh(90.0f32.to_radians(), 1.0, 1.0, 10.0);
+        let mvp_matrix = projection_matrix;
+        let camera = CameraTransforms {
+            model_view_matrix: glam::Mat4::IDENTITY,
+            projection_matrix,
+            mvp_matrix,
+            mvp_inv_matrix: mvp_matrix.inverse(),
+            camera_pos: glam::Vec4::W,
+            screen_dimensions: glam::Vec4::ONE,
+        };
+        let vertices = frustum_vertices(&camera);
+        assert_eq!(FRUSTUM_VERTEX_COUNT * 2, vertices.len());
+
+        // The first line is along the bottom of the near plane.
+        assert_vector_relative_eq!([-1.0, -1.0, -1.0, 1.0], vertices[0]);
+        assert_vector_relative_eq!([1.0, -1.0, -1.0, 1.0], vertices[2]);
+    }
+
+    #[test]
+    fn skeleton_comparison_vertices_by_name() {
+        let a = [("Trans", glam::Vec3::ZERO), ("Hip", glam::Vec3::Y)];
+        let b = [
+            ("Hip", glam::Vec3::ONE),
+            ("Trans", glam::Vec3::X),
+            ("Head", glam::Vec3::Z),
+        ];
+        assert_eq!(
+            vec![
+                [0.0, 0.0, 0.0, 1.0],
+                REORDERED_BONE_COLOR,
+                [1.0, 0.0, 0.0, 1.0],
+                REORDERED_BONE_COLOR,
+                [0.0, 1.0, 0.0, 1.0],
+                REORDERED_BONE_COLOR,
+                [1.0, 1.0, 1.0, 1.0],
+                REORDERED_BONE_COLOR,
+            ],
+            skeleton_comparison_vertices(&a, &b)
+        );
+    }
+
+    #[test]
+    fn skeleton_comparison_vertices_matching_order() {
+        let a = [("Trans", glam::Vec3::ZERO)];
+        let b = [("Trans", glam::Vec3::X)];
+        let vertices = skeleton_comparison_vertices(&a, &b);
+        assert_eq!(MATCHING_BONE_COLOR, vertices[1]);
+    }
+
+    #[test]
+    fn skeleton_comparison_vertices_empty() {
+        assert!(skeleton_comparison_vertices(&[("Trans", glam::Vec3::ZERO)], &[]).is_empty());
+    }
+
+    #[test]
+    fn lights_vertices_skip_unused_lights() {
+        let stage_uniforms = StageUniforms::training();
+        let vertices = lights_vertices(&stage_uniforms, glam::Vec3::ZERO, 10.0);
+        // Each vertex has a position and color.
+        assert!(!vertices.is_empty());
+        assert_eq!(0, vertices.len() % (LIGHT_VERTEX_COUNT * 2));
+        assert!(vertices.len() <= MAX_LIGHT_VERTEX_COUNT * 2);
+    }
+}
@@ -0,0 +1,77 @@
+use thiserror::Error;
+
+/// Errors that can occur while loading, updating, rendering, or exporting with an [SsbhRenderer](crate::SsbhRenderer).
+#[derive(Debug, Error)]
+pub enum SsbhWgpuError {
+    #[error("failed to create texture: {0}")]
+    CreateTexture(#[from] nutexb_wgpu::CreateTextureError),
+
+    #[error(
+        "expected a texture with dimension {:?} but found {:?}",
+        expected,
+        actual
+    )]
+    TextureDimension {
+        expected: wgpu::TextureViewDimension,
+        actual: wgpu::TextureViewDimension,
+    },
+
+    #[error("the surface format {0:?} is not supported for capturing images")]
+    UnsupportedSurfaceFormat(wgpu::TextureFormat),
+
+    #[error(
+        "the capture size {}x{} with {}x supersampling exceeds the limit of {} pixels",
+        width,
+        height,
+        supersampling,
+        max_dimension
+    )]
+    CaptureSizeExceedsLimit {
+        width: u32,
+        height: u32,
+        supersampling: u32,
+        max_dimension: u32,
+    },
+
+    #[error("failed to map buffer for reading: {0}")]
+    BufferMap(#[from] wgpu::BufferAsyncError),
+
+    #[error("the buffer mapping was canceled before completing")]
+    BufferMapCanceled,
+
+    #[error("the captured data does not match the image dimensions")]
+    ImageDataMismatch,
+
+    #[error("failed to encode animated PNG: {0}")]
+    EncodePng(#[from] png::EncodingError),
+
+    #[error("no mesh exists with name {0:?}")]
+    MeshName(String),
+
+    #[error("colorSet{0} is not a supported vertex color attribute")]
+    ColorSetIndex(usize),
+
+    #[error("expected {} vertex color values but found {}", expected, actual)]
+    ColorSetLength { expected: usize, actual: usize },
+
+    #[error("no default texture exists with name {0:?}")]
+    DefaultTexture(String),
+
+    #[error("failed to read file: {0}")]
+    ReadFile(String),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error("invalid mesh object: {0}")]
+    MeshObject(#[from] ssbh_data::mesh_data::error::Error),
+
+    #[error("mesh object has no vertices")]
+    EmptyMesh,
+
+    #[error("vertex index {0} is out of range")]
+    VertexIndex(u32),
+
+    #[error("failed to write glTF JSON: {0}")]
+    Json(#[from] serde_json::Error),
+}
@@ -0,0 +1,563 @@
+//! Conversions from loaded model data to standard file formats.
+//!
+//! Exported files use the same vertex, skinning, and animation code as rendering.
+//! This allows previewing models and animations in an application before handing the data to other tools.
+use std::path::Path;
+
+use log::error;
+use serde_json::{json, Value};
+use ssbh_data::{mesh_data::MeshObjectData, prelude::*};
+
+use crate::{
+    animation::{evaluate_skel_pose, AnimationTransforms, MAX_BONE_COUNT},
+    vertex::{buffer0, buffer1, skin_weights},
+    ModelFolder, SsbhWgpuError,
+};
+
+// glTF constants for accessors and buffer views.
+const FLOAT: u32 = 5126;
+const UNSIGNED_SHORT: u32 = 5123;
+const UNSIGNED_INT: u32 = 5125;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+// Smash Ultimate animations play at 60 frames per second.
+const FRAMES_PER_SECOND: f32 = 60.0;
+
+/// Writes the `model` and an optional `anim` to `path` as a binary glTF 2.0 file.
+///
+/// See [gltf_bytes] for details on the exported data.
+pub fn write_glb<P: AsRef<Path>>(
+    path: P,
+    model: &ModelFolder,
+    anim: Option<&AnimData>,
+) -> Result<(), SsbhWgpuError> {
+    std::fs::write(path, gltf_bytes(model, anim)?)?;
+    Ok(())
+}
+
+/// Converts the `model` and an optional `anim` to the bytes of a binary glTF 2.0 file.
+///
+/// The skel is exported as a node hierarchy with a single skin for all skinned meshes.
+/// Mesh objects without vertex weights are parented to their parent bone.
+/// Animations are baked to a keyframe for each frame with linear interpolation
+/// after applying any hlpb constraints from the model.
+/// Invalid mesh objects are skipped.
+// TODO: Export materials and textures.
+// TODO: Export visibility tracks?
+pub fn gltf_bytes(model: &ModelFolder, anim: Option<&AnimData>) -> Result<Vec<u8>, SsbhWgpuError> {
+    let skel = model.find_skel();
+    let mesh = model.find_mesh();
+
+    let mut builder = GltfBuilder::default();
+
+    // Bone nodes come first, so node indices match bone indices.
+    let bones = skel
+        .map(|s| &s.bones[..s.bones.len().min(MAX_BONE_COUNT)])
+        .unwrap_or_default();
+    let rest_pose = skel
+        .map(AnimationTransforms::from_skel)
+        .unwrap_or_else(AnimationTransforms::identity);
+
+    let mut nodes: Vec<Value> = bones
+        .iter()
+        .enumerate()
+        .map(|(i, bone)| {
+            let local = local_transform(&rest_pose.world_transforms, bone.parent_index, i);
+            let (scale, rotation, translation) = local.to_scale_rotation_translation();
+            json!({
+                "name": bone.name,
+                "translation": translation.to_array(),
+                "rotation": rotation.to_array(),
+                "scale": scale.to_array(),
+            })
+        })
+        .collect();
+
+    let mut children = vec![Vec::new(); bones.len()];
+    let mut scene_nodes = Vec::new();
+    for (i, bone) in bones.iter().enumerate() {
+        match bone.parent_index.filter(|p| *p < bones.len()) {
+            Some(parent) => children[parent].push(i),
+            None => scene_nodes.push(i),
+        }
+    }
+
+    let mut skins = Vec::new();
+    if !bones.is_empty() {
+        let inverse_bind_matrices: Vec<f32> = rest_pose.world_transforms[..bones.len()]
+            .iter()
+            .flat_map(|t| t.inverse().to_cols_array())
+            .collect();
+        let accessor = builder.add_accessor(
+            bytemuck::cast_slice(&inverse_bind_matrices),
+            bones.len(),
+            FLOAT,
+            "MAT4",
+            None,
+        );
+        skins.push(json!({
+            "inverseBindMatrices": accessor,
+            "joints": (0..bones.len()).collect::<Vec<_>>(),
+        }));
+    }
+
+    let mut meshes = Vec::new();
+    for mesh_object in mesh.iter().flat_map(|m| &m.objects) {
+        let primitive = match mesh_primitive(&mut builder, mesh_object, skel) {
+            Ok(primitive) => primitive,
+            Err(e) => {
+                error!("Error exporting mesh {}: {}", mesh_object.name, e);
+                continue;
+            }
+        };
+
+        let mesh_index = meshes.len();
+        meshes.push(json!({
+            "name": mesh_object.name,
+            "primitives": [primitive],
+        }));
+
+        let node_index = nodes.len();
+        let parent = bones
+            .iter()
+            .position(|b| b.name == mesh_object.parent_bone_name);
+
+        if !mesh_object.bone_influences.is_empty() && !bones.is_empty() {
+            nodes.push(json!({
+                "name": mesh_object.name,
+                "mesh": mesh_index,
+                "skin": 0,
+            }));
+            scene_nodes.push(node_index);
+        } else if let Some(parent) = parent {
+            // Vertices are relative to the parent bone like in the skinning shader.
+            nodes.push(json!({ "name": mesh_object.name, "mesh": mesh_index }));
+            children[parent].push(node_index);
+        } else {
+            nodes.push(json!({ "name": mesh_object.name, "mesh": mesh_index }));
+            scene_nodes.push(node_index);
+        }
+    }
+
+    for (node, children) in nodes.iter_mut().zip(children) {
+        if !children.is_empty() {
+            node["children"] = json!(children);
+        }
+    }
+
+    let mut animations = Vec::new();
+    if let (Some(skel), Some(anim)) = (skel, anim) {
+        if !bones.is_empty() {
+            animations.push(animation(&mut builder, skel, anim, model.find_hlpb()));
+        }
+    }
+
+    let mut gltf = json!({
+        "asset": { "version": "2.0", "generator": "ssbh_wgpu" },
+        "scene": 0,
+        "scenes": [{ "nodes": scene_nodes }],
+        "nodes": nodes,
+    });
+    if !meshes.is_empty() {
+        gltf["meshes"] = json!(meshes);
+    }
+    if !skins.is_empty() {
+        gltf["skins"] = json!(skins);
+    }
+    if !animations.is_empty() {
+        gltf["animations"] = json!(animations);
+    }
+
+    Ok(builder.write_glb(gltf)?)
+}
+
+fn local_transform(
+    world_transforms: &[glam::Mat4],
+    parent_index: Option<usize>,
+    index: usize,
+) -> glam::Mat4 {
+    match parent_index.and_then(|p| world_transforms.get(p)) {
+        Some(parent_world) => parent_world.inverse() * world_transforms[index],
+        None => world_transforms[index],
+    }
+}
+
+fn mesh_primitive(
+    builder: &mut GltfBuilder,
+    mesh_object: &MeshObjectData,
+    skel: Option<&SkelData>,
+) -> Result<Value, SsbhWgpuError> {
+    let vertex_count = mesh_object.vertex_count()?;
+    if vertex_count == 0 {
+        return Err(SsbhWgpuError::EmptyMesh);
+    }
+    if let Some(index) = mesh_object
+        .vertex_indices
+        .iter()
+        .find(|i| **i as usize >= vertex_count)
+    {
+        return Err(SsbhWgpuError::VertexIndex(*index));
+    }
+
+    let vertices0 = buffer0(mesh_object)?;
+    let vertices1 = buffer1(mesh_object)?;
+
+    let positions: Vec<[f32; 3]> = vertices0
+        .iter()
+        .map(|v| v.position0.truncate().into())
+        .collect();
+    let normals: Vec<[f32; 3]> = vertices0
+        .iter()
+        .map(|v| {
+            v.normal0
+                .truncate()
+                .try_normalize()
+                .unwrap_or(glam::Vec3::Z)
+                .into()
+        })
+        .collect();
+    // glTF uses a top left origin for UVs.
+    let uvs: Vec<[f32; 2]> = vertices1
+        .iter()
+        .map(|v| [v.map1_uvset.x, 1.0 - v.map1_uvset.y])
+        .collect();
+
+    let (min, max) = positions.iter().fold(
+        (glam::Vec3::splat(f32::MAX), glam::Vec3::splat(f32::MIN)),
+        |(min, max), p| (min.min((*p).into()), max.max((*p).into())),
+    );
+
+    let position = builder.add_accessor(
+        bytemuck::cast_slice(&positions),
+        vertex_count,
+        FLOAT,
+        "VEC3",
+        Some(ARRAY_BUFFER),
+    );
+    builder.accessors[position]["min"] = json!(min.to_array());
+    builder.accessors[position]["max"] = json!(max.to_array());
+
+    let mut attributes = json!({
+        "POSITION": position,
+        "NORMAL": builder.add_accessor(
+            bytemuck::cast_slice(&normals),
+            vertex_count,
+            FLOAT,
+            "VEC3",
+            Some(ARRAY_BUFFER),
+        ),
+        "TEXCOORD_0": builder.add_accessor(
+            bytemuck::cast_slice(&uvs),
+            vertex_count,
+            FLOAT,
+            "VEC2",
+            Some(ARRAY_BUFFER),
+        ),
+    });
+
+    if !mesh_object.bone_influences.is_empty() && skel.is_some() {
+        let (joints, weights) = joints_weights(&skin_weights(mesh_object, skel)?);
+        attributes["JOINTS_0"] = json!(builder.add_accessor(
+            bytemuck::cast_slice(&joints),
+            vertex_count,
+            UNSIGNED_SHORT,
+            "VEC4",
+            Some(ARRAY_BUFFER),
+        ));
+        attributes["WEIGHTS_0"] = json!(builder.add_accessor(
+            bytemuck::cast_slice(&weights),
+            vertex_count,
+            FLOAT,
+            "VEC4",
+            Some(ARRAY_BUFFER),
+        ));
+    }
+
+    let indices = builder.add_accessor(
+        bytemuck::cast_slice(&mesh_object.vertex_indices),
+        mesh_object.vertex_indices.len(),
+        UNSIGNED_INT,
+        "SCALAR",
+        Some(ELEMENT_ARRAY_BUFFER),
+    );
+
+    Ok(json!({
+        "attributes": attributes,
+        "indices": indices,
+    }))
+}
+
+// Convert the skinning weights to glTF joints and normalized weights.
+fn joints_weights(
+    weights: &[crate::shader::skinning::VertexWeight],
+) -> (Vec<[u16; 4]>, Vec<[f32; 4]>) {
+    weights
+        .iter()
+        .map(|w| {
+            // Unused influences have an index of -1.
+            let joints = w.bone_indices.to_array().map(|i| i.max(0) as u16);
+            let mut weights = w.weights.to_array();
+            for (weight, index) in weights.iter_mut().zip(w.bone_indices.to_array()) {
+                if index < 0 {
+                    *weight = 0.0;
+                }
+            }
+
+            let sum: f32 = weights.iter().sum();
+            if sum > 0.0 {
+                (joints, weights.map(|w| w / sum))
+            } else {
+                // glTF requires weights to sum to 1.0.
+                ([0; 4], [1.0, 0.0, 0.0, 0.0])
+            }
+        })
+        .unzip()
+}
+
+fn animation(
+    builder: &mut GltfBuilder,
+    skel: &SkelData,
+    anim: &AnimData,
+    hlpb: Option<&HlpbData>,
+) -> Value {
+    let bone_count = skel.bones.len().min(MAX_BONE_COUNT);
+    let frame_count = anim.final_frame_index.max(0.0).floor() as usize + 1;
+
+    let times: Vec<f32> = (0..frame_count)
+        .map(|f| f as f32 / FRAMES_PER_SECOND)
+        .collect();
+    let input = builder.add_accessor(
+        bytemuck::cast_slice(&times),
+        frame_count,
+        FLOAT,
+        "SCALAR",
+        None,
+    );
+    builder.accessors[input]["min"] = json!([0.0]);
+    builder.accessors[input]["max"] = json!([times.last().copied().unwrap_or_default()]);
+
+    let mut translations = vec![Vec::new(); bone_count];
+    let mut rotations: Vec<Vec<glam::Quat>> = vec![Vec::new(); bone_count];
+    let mut scales = vec![Vec::new(); bone_count];
+
+    // Bake the animation to include constraints and scale compensation.
+    for frame in 0..frame_count {
+        let pose = evaluate_skel_pose(skel, [anim], hlpb, frame as f32);
+
+        for (i, local) in pose.local_transforms.iter().enumerate() {
+            // Avoid flipping between equivalent quaternions when interpolating.
+            let mut rotation = local.rotation;
+            if let Some(previous) = rotations[i].last() {
+                if previous.dot(rotation) < 0.0 {
+                    rotation = -rotation;
+                }
+            }
+
+            translations[i].push(local.translation.to_array());
+            rotations[i].push(rotation);
+            scales[i].push(local.scale.to_array());
+        }
+    }
+
+    let mut samplers = Vec::new();
+    let mut channels = Vec::new();
+    for i in 0..bone_count {
+        let rotations: Vec<[f32; 4]> = rotations[i].iter().map(|r| r.to_array()).collect();
+        let outputs: [(&str, &[u8], &str); 3] = [
+            (
+                "translation",
+                bytemuck::cast_slice(&translations[i]),
+                "VEC3",
+            ),
+            ("rotation", bytemuck::cast_slice(&rotations), "VEC4"),
+            ("scale", bytemuck::cast_slice(&scales[i]), "VEC3"),
+        ];
+
+        for (path, data, type_) in outputs {
+            let output = builder.add_accessor(data, frame_count, FLOAT, type_, None);
+            channels.push(json!({
+                "sampler": samplers.len(),
+                "target": { "node": i, "path": path },
+            }));
+            samplers.push(json!({
+                "input": input,
+                "output": output,
+                "interpolation": "LINEAR",
+            }));
+        }
+    }
+
+    json!({
+        "name": "animation",
+        "samplers": samplers,
+        "channels": channels,
+    })
+}
+
+#[derive(Default)]
+struct GltfBuilder {
+    buffer: Vec<u8>,
+    buffer_views: Vec<Value>,
+    accessors: Vec<Value>,
+}
+
+impl GltfBuilder {
+    fn add_accessor(
+        &mut self,
+        data: &[u8],
+        count: usize,
+        component_type: u32,
+        type_: &str,
+        target: Option<u32>,
+    ) -> usize {
+        // Accessor offsets must be aligned to the component size.
+        while self.buffer.len() % 4 != 0 {
+            self.buffer.push(0);
+        }
+
+        let mut view = json!({
+            "buffer": 0,
+            "byteOffset": self.buffer.len(),
+            "byteLength": data.len(),
+        });
+        if let Some(target) = target {
+            view["target"] = json!(target);
+        }
+        self.buffer.extend_from_slice(data);
+        self.buffer_views.push(view);
+
+        self.accessors.push(json!({
+            "bufferView": self.buffer_views.len() - 1,
+            "componentType": component_type,
+            "count": count,
+            "type": type_,
+        }));
+        self.accessors.len() - 1
+    }
+
+    fn write_glb(mut self, mut gltf: Value) -> Result<Vec<u8>, serde_json::Error> {
+        if !self.accessors.is_empty() {
+            gltf["buffers"] = json!([{ "byteLength": self.buffer.len() }]);
+            gltf["bufferViews"] = json!(self.buffer_views);
+            gltf["accessors"] = json!(self.accessors);
+        }
+
+        // Chunks must be aligned to 4 bytes.
+        let mut json = serde_json::to_vec(&gltf)?;
+        while json.len() % 4 != 0 {
+            json.push(b' ');
+        }
+        while self.buffer.len() % 4 != 0 {
+            self.buffer.push(0);
+        }
+
+        let has_buffer = !self.buffer.is_empty();
+        let mut length = 12 + 8 + json.len();
+        if has_buffer {
+            length += 8 + self.buffer.len();
+        }
+
+        let mut bytes = Vec::with_capacity(length);
+        bytes.extend_from_slice(b"glTF");
+        bytes.extend_from_slice(&2u32.to_le_bytes());
+        bytes.extend_from_slice(&(length as u32).to_le_bytes());
+
+        bytes.extend_from_slice(&(json.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(b"JSON");
+        bytes.extend_from_slice(&json);
+
+        if has_buffer {
+            bytes.extend_from_slice(&(self.buffer.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(b"BIN\0");
+            bytes.extend_from_slice(&self.buffer);
+        }
+
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ssbh_data::skel_data::{BillboardType, BoneData};
+
+    fn empty_folder() -> ModelFolder {
+        ModelFolder {
+            meshes: Vec::new(),
+            meshexes: Vec::new(),
+            skels: Vec::new(),
+            matls: Vec::new(),
+            modls: Vec::new(),
+            adjs: Vec::new(),
+            anims: Vec::new(),
+            hlpbs: Vec::new(),
+            nutexbs: Vec::new(),
+            xmbs: Vec::new(),
+            custom_files: Vec::new(),
+        }
+    }
+
+    fn json_chunk(bytes: &[u8]) -> Value {
+        let length = u32::from_le_bytes(bytes[12..16].try_into().unwrap()) as usize;
+        assert_eq!(b"JSON", &bytes[16..20]);
+        serde_json::from_slice(&bytes[20..20 + length]).unwrap()
+    }
+
+    #[test]
+    fn gltf_bytes_empty() {
+        let bytes = gltf_bytes(&empty_folder(), None).unwrap();
+        assert_eq!(b"glTF", &bytes[0..4]);
+        assert_eq!(
+            bytes.len() as u32,
+            u32::from_le_bytes(bytes[8..12].try_into().unwrap())
+        );
+
+        let gltf = json_chunk(&bytes);
+        assert_eq!("2.0", gltf["asset"]["version"]);
+        assert_eq!(json!([]), gltf["nodes"]);
+    }
+
+    #[test]
+    fn gltf_bytes_skel_hierarchy() {
+        let mut folder = empty_folder();
+        let bone = |name: &str, parent_index| BoneData {
+            name: name.to_string(),
+            transform: glam::Mat4::from_translation(glam::vec3(1.0, 2.0, 3.0)).to_cols_array_2d(),
+            parent_index,
+            billboard_type: BillboardType::Disabled,
+        };
+        folder.skels.push((
+            "model.nusktb".to_string(),
+            Ok(SkelData {
+                major_version: 1,
+                minor_version: 0,
+                bones: vec![bone("Trans", None), bone("Rot", Some(0))],
+            }),
+        ));
+
+        let gltf = json_chunk(&gltf_bytes(&folder, None).unwrap());
+        assert_eq!(json!([0]), gltf["scenes"][0]["nodes"]);
+        assert_eq!(json!([1]), gltf["nodes"][0]["children"]);
+        assert_eq!("Rot", gltf["nodes"][1]["name"]);
+        assert_eq!(json!([1.0, 2.0, 3.0]), gltf["nodes"][1]["translation"]);
+        assert_eq!(json!([0, 1]), gltf["skins"][0]["joints"]);
+    }
+
+    #[test]
+    fn joints_weights_normalized() {
+        let (joints, weights) = joints_weights(&[
+            crate::shader::skinning::VertexWeight {
+                bone_indices: glam::IVec4::new(2, 3, -1, -1),
+                weights: glam::vec4(0.25, 0.25, 0.0, 0.0),
+            },
+            crate::shader::skinning::VertexWeight {
+                bone_indices: glam::IVec4::splat(-1),
+                weights: glam::Vec4::ZERO,
+            },
+        ]);
+        assert_eq!(vec![[2, 3, 0, 0], [0, 0, 0, 0]], joints);
+        assert_eq!(vec![[0.5, 0.5, 0.0, 0.0], [1.0, 0.0, 0.0, 0.0]], weights);
+    }
+}
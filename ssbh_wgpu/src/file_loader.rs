@@ -0,0 +1,135 @@
+use std::{any::Any, path::Path, sync::Arc};
+
+use crate::SsbhWgpuError;
+
+type LoadFn = dyn Fn(&Path) -> Result<Box<dyn Any + Send + Sync>, SsbhWgpuError> + Send + Sync;
+
+/// Loaders for additional file types in a model folder.
+///
+/// This allows applications to read files like prc configs or custom metadata
+/// alongside the model files with [ModelFolder::load_folder_with_loaders](crate::ModelFolder::load_folder_with_loaders)
+/// or [ModelLoader::with_loaders](crate::ModelLoader::with_loaders).
+/// Loaders are cheap to clone and can be sent to other threads.
+///
+/// # Examples
+/**
+```rust no_run
+use ssbh_wgpu::{FileLoaders, ModelFolder};
+
+let mut loaders = FileLoaders::new();
+loaders.register("txt", |path| Ok(std::fs::read_to_string(path)?));
+
+let folder = ModelFolder::load_folder_with_loaders("/mario/model/body/c00", &loaders);
+let notes: Option<&String> = folder.find_custom("notes.txt");
+```
+ */
+#[derive(Clone, Default)]
+pub struct FileLoaders {
+    loaders: Vec<(String, Arc<LoadFn>)>,
+}
+
+impl FileLoaders {
+    /// Creates an empty registry with no custom loaders.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads files with `extension` like `"prc"` using `load`.
+    /// The extension should not include the leading `'.'`.
+    ///
+    /// Errors other than [SsbhWgpuError::Io] can be reported with [SsbhWgpuError::ReadFile].
+    /// Registering an extension again replaces the previous loader.
+    /// The loaded files are stored in [ModelFolder::custom_files](crate::ModelFolder::custom_files).
+    pub fn register<T, F>(&mut self, extension: &str, load: F)
+    where
+        T: Any + Send + Sync,
+        F: Fn(&Path) -> Result<T, SsbhWgpuError> + Send + Sync + 'static,
+    {
+        let load: Arc<LoadFn> =
+            Arc::new(move |path: &Path| Ok(Box::new(load(path)?) as Box<dyn Any + Send + Sync>));
+
+        self.loaders.retain(|(e, _)| e != extension);
+        self.loaders.push((extension.to_owned(), load));
+    }
+
+    /// The registered extensions in the order they were registered.
+    pub fn extensions(&self) -> impl Iterator<Item = &str> {
+        self.loaders.iter().map(|(e, _)| e.as_str())
+    }
+
+    pub(crate) fn loaders(&self) -> impl Iterator<Item = (&str, &LoadFn)> {
+        self.loaders.iter().map(|(e, l)| (e.as_str(), l.as_ref()))
+    }
+}
+
+impl std::fmt::Debug for FileLoaders {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FileLoaders")
+            .field("extensions", &self.extensions().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// A file read by a loader registered with [FileLoaders::register].
+pub struct CustomFile {
+    data: Box<dyn Any + Send + Sync>,
+}
+
+impl CustomFile {
+    pub(crate) fn new(data: Box<dyn Any + Send + Sync>) -> Self {
+        Self { data }
+    }
+
+    /// Returns the loaded data if it has type `T`.
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.data.downcast_ref()
+    }
+}
+
+impl std::fmt::Debug for CustomFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CustomFile").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::ModelFolder;
+
+    #[test]
+    fn register_replaces_extension() {
+        let mut loaders = FileLoaders::new();
+        loaders.register("txt", |_| Ok(1u32));
+        loaders.register("prc", |_| Ok(2u32));
+        loaders.register("txt", |_| Ok(3u32));
+        assert_eq!(vec!["prc", "txt"], loaders.extensions().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn load_folder_custom_files() {
+        let folder = std::env::temp_dir().join("ssbh_wgpu_load_folder_custom_files");
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::write(folder.join("notes.txt"), "abc").unwrap();
+        std::fs::write(folder.join("invalid.num"), "abc").unwrap();
+
+        let mut loaders = FileLoaders::new();
+        loaders.register("txt", |path| Ok(std::fs::read_to_string(path)?));
+        loaders.register("num", |path| {
+            std::fs::read_to_string(path)?
+                .parse::<u32>()
+                .map_err(|e| SsbhWgpuError::ReadFile(e.to_string()))
+        });
+
+        let model = ModelFolder::load_folder_with_loaders(&folder, &loaders);
+        std::fs::remove_dir_all(&folder).unwrap();
+
+        assert_eq!(Some(&"abc".to_string()), model.find_custom("notes.txt"));
+        // The type must match the loader.
+        assert_eq!(None, model.find_custom::<u32>("notes.txt"));
+        assert_eq!(None, model.find_custom::<u32>("invalid.num"));
+        assert_eq!(1, model.file_errors().len());
+        assert_eq!("invalid.num", model.file_errors()[0].name);
+    }
+}
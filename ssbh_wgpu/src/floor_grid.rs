@@ -1,7 +1,4 @@
-use crate::{
-    renderer::{DEPTH_FORMAT, MSAA_SAMPLE_COUNT},
-    shape::IndexedMeshBuffers,
-};
+use crate::{renderer::DEPTH_FORMAT, shape::IndexedMeshBuffers};
 
 pub struct FloorGridRenderData {
     pipeline: wgpu::RenderPipeline,
@@ -14,6 +11,7 @@ impl FloorGridRenderData {
         device: &wgpu::Device,
         camera_buffer: &wgpu::Buffer,
         surface_format: wgpu::TextureFormat,
+        sample_count: u32,
     ) -> Self {
         let shader = crate::shader::floor_grid::create_shader_module(device);
         let layout = crate::shader::floor_grid::create_pipeline_layout(device);
@@ -63,7 +61,7 @@ impl FloorGridRenderData {
                 bias: wgpu::DepthBiasState::default(),
             }),
             multisample: wgpu::MultisampleState {
-                count: MSAA_SAMPLE_COUNT,
+                count: sample_count,
                 ..Default::default()
             },
             multiview: None,
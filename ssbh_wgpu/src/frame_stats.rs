@@ -0,0 +1,33 @@
+use std::time::Duration;
+
+/// CPU timings for the most recent frame.
+///
+/// Times are measured on the CPU and do not include time spent executing commands on the GPU.
+/// A scene is likely CPU bound if the total time is close to the frame time.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct FrameStats {
+    /// The time spent evaluating animations in [RenderModel::apply_anims](crate::RenderModel::apply_anims).
+    pub animation: Duration,
+    /// The time spent writing animated data to buffers in [RenderModel::apply_anims](crate::RenderModel::apply_anims).
+    pub buffer_writes: Duration,
+    /// The time spent recording commands in [SsbhRenderer::render_models](crate::SsbhRenderer::render_models).
+    pub encode: Duration,
+    /// The number of pipeline changes when drawing the shaded models.
+    pub pipeline_binds: u32,
+    /// The number of bind group changes when drawing the shaded models.
+    pub bind_group_binds: u32,
+}
+
+impl FrameStats {
+    /// The total CPU time for the frame.
+    pub fn total(&self) -> Duration {
+        self.animation + self.buffer_writes + self.encode
+    }
+}
+
+/// CPU timings for [RenderModel::apply_anims](crate::RenderModel::apply_anims).
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) struct AnimationStats {
+    pub animation: Duration,
+    pub buffer_writes: Duration,
+}
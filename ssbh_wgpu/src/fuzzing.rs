@@ -0,0 +1,5 @@
+//! CPU side conversions that don't require a GPU device.
+//!
+//! These functions are only exposed for fuzz testing and may change without warning.
+pub use crate::model::mesh_creation::{mesh_buffer_data, MeshBufferData};
+pub use crate::vertex::{buffer0, buffer1, skin_weights};
@@ -0,0 +1,235 @@
+use std::{
+    cell::{Cell, RefCell},
+    sync::mpsc::{Receiver, TryRecvError},
+    time::Duration,
+};
+
+/// Optional WGPU features for recording [GpuTimings] with [SsbhRenderer::enable_gpu_timings](crate::SsbhRenderer::enable_gpu_timings).
+pub const GPU_TIMING_FEATURES: wgpu::Features = wgpu::Features::from_bits_truncate(
+    wgpu::Features::TIMESTAMP_QUERY.bits() | wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS.bits(),
+);
+
+/// GPU timings for the passes in [SsbhRenderer::render_models](crate::SsbhRenderer::render_models).
+///
+/// Times are measured on the GPU using timestamp queries.
+/// The pass with the largest time is likely the bottleneck for GPU bound scenes.
+/// Passes that are skipped with the current settings have a time of zero.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct GpuTimings {
+    /// The compute passes for skinning and recalculating normals.
+    pub skinning: Duration,
+    /// The depth and variance shadow map passes for each light.
+    pub shadows: Duration,
+    /// The main model pass or the model pass for the current debug mode.
+    pub model: Duration,
+    /// The depth of field pass if enabled.
+    pub depth_of_field: Duration,
+    /// The threshold, blur, combine, and upscale passes for bloom.
+    pub bloom: Duration,
+    /// The final color grading and tone mapping pass.
+    pub post_processing: Duration,
+    /// The skeleton, silhouette, and outline passes.
+    pub overlays: Duration,
+}
+
+impl GpuTimings {
+    /// The total GPU time for the frame.
+    pub fn total(&self) -> Duration {
+        self.skinning
+            + self.shadows
+            + self.model
+            + self.depth_of_field
+            + self.bloom
+            + self.post_processing
+            + self.overlays
+    }
+}
+
+/// The timestamp written after each group of passes.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum GpuPass {
+    Start,
+    Skinning,
+    Shadows,
+    Model,
+    DepthOfField,
+    Bloom,
+    PostProcessing,
+    Overlays,
+}
+
+const TIMESTAMP_COUNT: u32 = GpuPass::Overlays as u32 + 1;
+const TIMESTAMP_BUFFER_SIZE: u64 = TIMESTAMP_COUNT as u64 * std::mem::size_of::<u64>() as u64;
+
+enum ReadbackState {
+    Idle,
+    Copied,
+    Mapping(Receiver<Result<(), wgpu::BufferAsyncError>>),
+}
+
+pub(crate) struct GpuProfiler {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+    // Nanoseconds per timestamp tick.
+    period: f32,
+    state: RefCell<ReadbackState>,
+    is_recording: Cell<bool>,
+    next_timestamp: Cell<u32>,
+    timings: Cell<Option<GpuTimings>>,
+}
+
+impl GpuProfiler {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("GPU Timings Query Set"),
+            ty: wgpu::QueryType::Timestamp,
+            count: TIMESTAMP_COUNT,
+        });
+
+        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("GPU Timings Resolve Buffer"),
+            size: TIMESTAMP_BUFFER_SIZE,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("GPU Timings Readback Buffer"),
+            size: TIMESTAMP_BUFFER_SIZE,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            query_set,
+            resolve_buffer,
+            readback_buffer,
+            period: queue.get_timestamp_period(),
+            state: RefCell::new(ReadbackState::Idle),
+            is_recording: Cell::new(false),
+            next_timestamp: Cell::new(0),
+            timings: Cell::new(None),
+        }
+    }
+
+    pub fn timings(&self) -> Option<GpuTimings> {
+        self.timings.get()
+    }
+
+    /// Reads the timestamps from a previous frame if available and starts recording a new frame.
+    pub fn begin_frame(&self, encoder: &mut wgpu::CommandEncoder) {
+        // Avoid blocking on the GPU by only recording timestamps when the readback buffer is unused.
+        // This means the timings are usually a few frames behind.
+        let mut state = self.state.borrow_mut();
+        match &*state {
+            ReadbackState::Idle => (),
+            ReadbackState::Copied => {
+                // The copy was submitted with the previous frame's commands.
+                let (sender, receiver) = std::sync::mpsc::channel();
+                self.readback_buffer
+                    .slice(..)
+                    .map_async(wgpu::MapMode::Read, move |result| {
+                        let _ = sender.send(result);
+                    });
+                *state = ReadbackState::Mapping(receiver);
+            }
+            ReadbackState::Mapping(receiver) => match receiver.try_recv() {
+                Ok(Ok(())) => {
+                    let timestamps: Vec<u64> =
+                        bytemuck::cast_slice(&self.readback_buffer.slice(..).get_mapped_range())
+                            .to_vec();
+                    self.readback_buffer.unmap();
+
+                    self.timings
+                        .set(Some(timings_from_timestamps(&timestamps, self.period)));
+                    *state = ReadbackState::Idle;
+                }
+                Ok(Err(_)) | Err(TryRecvError::Disconnected) => *state = ReadbackState::Idle,
+                Err(TryRecvError::Empty) => (),
+            },
+        }
+
+        self.is_recording.set(matches!(*state, ReadbackState::Idle));
+        self.next_timestamp.set(0);
+        self.timestamp(encoder, GpuPass::Start);
+    }
+
+    /// Records the end of `pass` and the start of the next pass.
+    ///
+    /// Passes skipped since the previous timestamp are recorded with a time of zero.
+    pub fn timestamp(&self, encoder: &mut wgpu::CommandEncoder, pass: GpuPass) {
+        if self.is_recording.get() {
+            // Write every query to avoid resolving uninitialized values.
+            for i in self.next_timestamp.get()..=pass as u32 {
+                encoder.write_timestamp(&self.query_set, i);
+            }
+            self.next_timestamp
+                .set(self.next_timestamp.get().max(pass as u32 + 1));
+        }
+    }
+
+    pub fn end_frame(&self, encoder: &mut wgpu::CommandEncoder) {
+        if self.is_recording.get() {
+            self.timestamp(encoder, GpuPass::Overlays);
+            encoder.resolve_query_set(&self.query_set, 0..TIMESTAMP_COUNT, &self.resolve_buffer, 0);
+            encoder.copy_buffer_to_buffer(
+                &self.resolve_buffer,
+                0,
+                &self.readback_buffer,
+                0,
+                TIMESTAMP_BUFFER_SIZE,
+            );
+            *self.state.borrow_mut() = ReadbackState::Copied;
+            self.is_recording.set(false);
+        }
+    }
+}
+
+fn timings_from_timestamps(timestamps: &[u64], period: f32) -> GpuTimings {
+    let duration = |end: GpuPass| {
+        let end = end as usize;
+        let ticks = timestamps[end].saturating_sub(timestamps[end - 1]);
+        Duration::from_nanos((ticks as f64 * period as f64) as u64)
+    };
+
+    GpuTimings {
+        skinning: duration(GpuPass::Skinning),
+        shadows: duration(GpuPass::Shadows),
+        model: duration(GpuPass::Model),
+        depth_of_field: duration(GpuPass::DepthOfField),
+        bloom: duration(GpuPass::Bloom),
+        post_processing: duration(GpuPass::PostProcessing),
+        overlays: duration(GpuPass::Overlays),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timings_from_timestamps_period() {
+        let timings = timings_from_timestamps(&[10, 20, 20, 50, 60, 80, 90, 100], 2.0);
+        assert_eq!(
+            GpuTimings {
+                skinning: Duration::from_nanos(20),
+                shadows: Duration::ZERO,
+                model: Duration::from_nanos(60),
+                depth_of_field: Duration::from_nanos(20),
+                bloom: Duration::from_nanos(40),
+                post_processing: Duration::from_nanos(20),
+                overlays: Duration::from_nanos(20),
+            },
+            timings
+        );
+        assert_eq!(Duration::from_nanos(180), timings.total());
+    }
+
+    #[test]
+    fn timings_from_timestamps_out_of_order() {
+        // Timestamps may not be monotonic on some platforms.
+        let timings = timings_from_timestamps(&[10, 5, 5, 5, 5, 5, 5, 5], 1.0);
+        assert_eq!(Duration::ZERO, timings.skinning);
+    }
+}
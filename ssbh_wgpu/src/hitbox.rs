@@ -0,0 +1,48 @@
+//! Types for previewing fighter hitboxes and hurtboxes with [SsbhRenderer::render_hitboxes](crate::SsbhRenderer::render_hitboxes).
+//!
+//! This library does not parse fighter scripts.
+//! Applications should convert the values from script commands to [HitboxCapsule].
+
+/// The type of collision shape, which determines the color when rendering.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum HitboxKind {
+    /// An attack hitbox like ATTACK in fighter scripts.
+    #[default]
+    Hitbox,
+    /// A grab box like CATCH in fighter scripts.
+    Grab,
+    /// A vulnerable hurtbox.
+    Hurtbox,
+    /// A hurtbox that ignores hits.
+    Intangible,
+    /// A hurtbox that takes hits without damage.
+    Invincible,
+}
+
+impl HitboxKind {
+    /// The RGBA color used for rendering.
+    pub fn color(&self) -> [f32; 4] {
+        match self {
+            HitboxKind::Hitbox => [1.0, 0.0, 0.0, 1.0],
+            HitboxKind::Grab => [1.0, 0.0, 1.0, 1.0],
+            HitboxKind::Hurtbox => [1.0, 1.0, 0.0, 1.0],
+            HitboxKind::Intangible => [0.0, 0.0, 1.0, 1.0],
+            HitboxKind::Invincible => [0.0, 1.0, 0.0, 1.0],
+        }
+    }
+}
+
+/// A capsule attached to a bone like the hitboxes and hurtboxes in fighter scripts.
+///
+/// The `start` and `end` offsets are relative to the bone's transform.
+/// A capsule with no `end` is drawn as a sphere.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HitboxCapsule {
+    /// The name of the bone in the skel like `"armr"`.
+    /// The name is not case sensitive.
+    pub bone_name: String,
+    pub kind: HitboxKind,
+    pub start: [f32; 3],
+    pub end: Option<[f32; 3]>,
+    pub radius: f32,
+}
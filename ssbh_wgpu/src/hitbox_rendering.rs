@@ -0,0 +1,166 @@
+use ssbh_data::skel_data::SkelData;
+
+use crate::{
+    hitbox::HitboxCapsule,
+    shape::{capsule_mesh_buffers, IndexedMeshBuffers},
+    swing_rendering::{ShapeBuffers, SwingRenderData},
+};
+
+/// Per shape data for hitbox and hurtbox capsules.
+///
+/// Shapes use the swing shader and are attached to a bone,
+/// so capsules animate on the GPU without updating any buffers.
+pub struct HitboxData {
+    // Bindings can't be empty, so only create buffers if there are shapes.
+    shape_buffers: Option<ShapeBuffers>,
+    // Each capsule has unique vertex data based on its length and radius.
+    capsule_buffers: Vec<IndexedMeshBuffers>,
+}
+
+impl HitboxData {
+    pub fn new() -> Self {
+        Self {
+            shape_buffers: None,
+            capsule_buffers: Vec::new(),
+        }
+    }
+
+    pub fn from_hitboxes(
+        device: &wgpu::Device,
+        hitboxes: &[HitboxCapsule],
+        skel: Option<&SkelData>,
+    ) -> Self {
+        let (per_shape, capsule_buffers): (Vec<_>, Vec<_>) = hitboxes
+            .iter()
+            .map(|h| {
+                let (height, per_shape) = hitbox_per_shape(h, skel);
+                let buffers = capsule_mesh_buffers(device, height, h.radius, h.radius);
+                (per_shape, buffers)
+            })
+            .unzip();
+
+        Self {
+            shape_buffers: ShapeBuffers::new(device, &per_shape),
+            capsule_buffers,
+        }
+    }
+}
+
+fn hitbox_per_shape(
+    hitbox: &HitboxCapsule,
+    skel: Option<&SkelData>,
+) -> (f32, crate::shader::swing::PerShape) {
+    let start = glam::Vec3::from(hitbox.start);
+    let end = hitbox.end.map(glam::Vec3::from).unwrap_or(start);
+
+    // Assume the shape is along the Z-axis and has unit dimensions.
+    // The bone transform is applied in the shader after positioning the capsule.
+    let direction = end - start;
+    let rotation = glam::Quat::from_rotation_arc(glam::Vec3::Z, direction.normalize_or_zero());
+    let center = (start + end) / 2.0;
+
+    let per_shape = crate::shader::swing::PerShape {
+        bone_indices: glam::IVec4::new(bone_index(skel, &hitbox.bone_name), -1, -1, -1),
+        start_transform: glam::Mat4::from_translation(center) * glam::Mat4::from_quat(rotation),
+        color: hitbox.kind.color().into(),
+    };
+
+    (direction.length(), per_shape)
+}
+
+fn bone_index(skel: Option<&SkelData>, name: &str) -> i32 {
+    skel.and_then(|skel| {
+        skel.bones
+            .iter()
+            .position(|b| b.name.eq_ignore_ascii_case(name))
+    })
+    .map(|i| i as i32)
+    .unwrap_or(-1)
+}
+
+pub fn draw_hitboxes(
+    render_data: &SwingRenderData,
+    pass: &mut wgpu::RenderPass<'_>,
+    swing_pipeline: &wgpu::RenderPipeline,
+    swing_camera_bind_group: &crate::shader::swing::bind_groups::BindGroup0,
+) {
+    let hitboxes = &render_data.hitboxes;
+    let Some(shape_buffers) = &hitboxes.shape_buffers else {
+        return;
+    };
+
+    pass.set_pipeline(swing_pipeline);
+    crate::shader::swing::set_bind_groups(
+        pass,
+        swing_camera_bind_group,
+        &render_data.bind_group1,
+        &shape_buffers.bind_group,
+    );
+
+    for (i, buffers) in hitboxes.capsule_buffers.iter().enumerate() {
+        let i = i as u32;
+        buffers.set(pass);
+        pass.draw_indexed(0..buffers.index_count, 0, i..i + 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::hitbox::HitboxKind;
+    use ssbh_data::skel_data::{BillboardType, BoneData};
+
+    fn skel() -> SkelData {
+        SkelData {
+            major_version: 1,
+            minor_version: 0,
+            bones: vec![BoneData {
+                name: "ArmR".to_string(),
+                transform: [[0.0; 4]; 4],
+                parent_index: None,
+                billboard_type: BillboardType::Disabled,
+            }],
+        }
+    }
+
+    #[test]
+    fn hitbox_per_shape_sphere() {
+        let hitbox = HitboxCapsule {
+            bone_name: "armr".to_string(),
+            kind: HitboxKind::Hurtbox,
+            start: [1.0, 2.0, 3.0],
+            end: None,
+            radius: 2.0,
+        };
+        let (height, per_shape) = hitbox_per_shape(&hitbox, Some(&skel()));
+        assert_eq!(0.0, height);
+        assert_eq!(glam::IVec4::new(0, -1, -1, -1), per_shape.bone_indices);
+        assert_eq!(
+            glam::Mat4::from_translation(glam::vec3(1.0, 2.0, 3.0)),
+            per_shape.start_transform
+        );
+        assert_eq!(
+            glam::Vec4::from(HitboxKind::Hurtbox.color()),
+            per_shape.color
+        );
+    }
+
+    #[test]
+    fn hitbox_per_shape_capsule() {
+        let hitbox = HitboxCapsule {
+            bone_name: "missing".to_string(),
+            kind: HitboxKind::Hitbox,
+            start: [0.0, 2.0, 0.0],
+            end: Some([0.0, 2.0, 4.0]),
+            radius: 1.0,
+        };
+        let (height, per_shape) = hitbox_per_shape(&hitbox, Some(&skel()));
+        assert_eq!(4.0, height);
+        assert_eq!(-1, per_shape.bone_indices.x);
+        assert_eq!(
+            glam::Mat4::from_translation(glam::vec3(0.0, 2.0, 2.0)),
+            per_shape.start_transform
+        );
+    }
+}
@@ -0,0 +1,217 @@
+//! Reloading materials, animations, and textures after they change on disk.
+//!
+//! This allows editing files in external programs without restarting the application.
+//!
+//! ```rust no_run
+//! # fn test(device: &wgpu::Device, queue: &wgpu::Queue, shared_data: &ssbh_wgpu::SharedRenderData) {
+//! use ssbh_wgpu::{hot_reload::FolderWatcher, ModelFolder, RenderModel};
+//!
+//! let mut model = ModelFolder::load_folder("/mario/model/body/c00");
+//! let mut render_model = RenderModel::from_folder(device, queue, &model, shared_data);
+//! let mut watcher = FolderWatcher::new("/mario/model/body/c00");
+//!
+//! // Poll regularly such as once per frame.
+//! for event in watcher.poll() {
+//!     if let Err(e) = render_model.reload(device, queue, &event, model.find_matl(), shared_data) {
+//!         println!("{e}");
+//!     }
+//!     model.update_file(event);
+//! }
+//! # }
+//! ```
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use log::error;
+use nutexb_wgpu::NutexbFile;
+use ssbh_data::prelude::*;
+
+use crate::{FileDiagnostic, ModelFolder, RenderModel, SharedRenderData, SsbhWgpuError};
+
+/// A file in a watched folder that was created or modified since the last [FolderWatcher::poll].
+#[derive(Debug)]
+pub enum ReloadEvent {
+    /// The `"model.numatb"` was modified.
+    Matl(MatlData),
+    /// An anim file like `"model.nuanmb"` was modified.
+    Anim(String, AnimData),
+    /// A nutexb file like `"def_mario_001_col.nutexb"` was modified.
+    Nutexb(String, NutexbFile),
+    /// A modified file that could not be read.
+    /// This can happen if the file is read before an external program finishes writing it.
+    /// The file will be read again when it is next modified.
+    Error(FileDiagnostic),
+}
+
+/// Detects changes to the reloadable files in a model folder.
+///
+/// Changes are detected by comparing file modification times,
+/// so this works on any platform without requiring a file system notification API.
+#[derive(Debug)]
+pub struct FolderWatcher {
+    folder: PathBuf,
+    modified: HashMap<PathBuf, SystemTime>,
+}
+
+impl FolderWatcher {
+    /// Starts watching `folder` for changes to `"model.numatb"`, nuanmb, and nutexb files.
+    /// Files that already exist are not reported until they are modified.
+    pub fn new<P: AsRef<Path>>(folder: P) -> Self {
+        let folder = folder.as_ref().to_owned();
+        let modified = modified_times(&folder).collect();
+        Self { folder, modified }
+    }
+
+    /// Returns events for the watched files that were created or modified since the last call.
+    pub fn poll(&mut self) -> Vec<ReloadEvent> {
+        let mut events = Vec::new();
+
+        for (path, time) in modified_times(&self.folder) {
+            if self.modified.get(&path) == Some(&time) {
+                continue;
+            }
+            self.modified.insert(path.clone(), time);
+
+            if let Some(event) = read_event(&path) {
+                events.push(event);
+            }
+        }
+
+        events
+    }
+}
+
+fn modified_times(folder: &Path) -> impl Iterator<Item = (PathBuf, SystemTime)> {
+    std::fs::read_dir(folder)
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .filter(|e| is_watched(&e.path()))
+        .filter_map(|e| Some((e.path(), e.metadata().ok()?.modified().ok()?)))
+}
+
+fn is_watched(path: &Path) -> bool {
+    let extension = path.extension().and_then(|e| e.to_str());
+    path.file_name().and_then(|f| f.to_str()) == Some("model.numatb")
+        || matches!(extension, Some("nuanmb") | Some("nutexb"))
+}
+
+fn read_event(path: &Path) -> Option<ReloadEvent> {
+    let name = path.file_name()?.to_string_lossy().to_string();
+    let extension = path.extension()?.to_str()?;
+
+    let result = match extension {
+        "numatb" => MatlData::from_file(path).map(ReloadEvent::Matl),
+        "nuanmb" => AnimData::from_file(path).map(|a| ReloadEvent::Anim(name.clone(), a)),
+        "nutexb" => NutexbFile::read_from_file(path)
+            .map(|n| ReloadEvent::Nutexb(name.clone(), n))
+            .map_err(Into::into),
+        _ => return None,
+    };
+
+    Some(result.unwrap_or_else(|e| {
+        error!("Error reloading {:?}: {}", path, e);
+        ReloadEvent::Error(FileDiagnostic {
+            name,
+            message: e.to_string(),
+        })
+    }))
+}
+
+impl ModelFolder {
+    /// Replaces or adds the file for `event` to keep the folder in sync with the files on disk.
+    pub fn update_file(&mut self, event: ReloadEvent) {
+        fn update<T>(files: &mut crate::ModelFiles<T>, name: String, data: T) {
+            match files.iter_mut().find(|(f, _)| f == &name) {
+                Some((_, file)) => *file = Ok(data),
+                None => files.push((name, Ok(data))),
+            }
+        }
+
+        match event {
+            ReloadEvent::Matl(matl) => update(&mut self.matls, "model.numatb".to_owned(), matl),
+            ReloadEvent::Anim(name, anim) => update(&mut self.anims, name, anim),
+            ReloadEvent::Nutexb(name, nutexb) => update(&mut self.nutexbs, name, nutexb),
+            ReloadEvent::Error(_) => (),
+        }
+    }
+}
+
+impl RenderModel {
+    /// Applies the changes from `event` to the materials and textures for this model.
+    ///
+    /// Materials are recreated using `matl` for texture changes.
+    /// Anim changes take effect after updating the [ModelFolder] with [ModelFolder::update_file]
+    /// and calling [RenderModel::apply_anims].
+    pub fn reload(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        event: &ReloadEvent,
+        matl: Option<&MatlData>,
+        shared_data: &SharedRenderData,
+    ) -> Result<(), SsbhWgpuError> {
+        match event {
+            ReloadEvent::Matl(matl) => {
+                self.recreate_materials(device, &matl.entries, shared_data);
+            }
+            ReloadEvent::Nutexb(name, nutexb) => {
+                let (texture, dimension) = nutexb_wgpu::create_texture(nutexb, device, queue)?;
+                self.set_texture(name, texture, dimension);
+
+                // Material bind groups reference the previous texture.
+                if let Some(matl) = matl {
+                    self.recreate_materials(device, &matl.entries, shared_data);
+                }
+            }
+            ReloadEvent::Anim(_, _) | ReloadEvent::Error(_) => (),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{fs::File, time::Duration};
+
+    #[test]
+    fn is_watched_files() {
+        assert!(is_watched(Path::new("model.numatb")));
+        assert!(is_watched(Path::new("model.nuanmb")));
+        assert!(is_watched(Path::new("a/b/def_mario_001_col.nutexb")));
+        assert!(!is_watched(Path::new("model.numshb")));
+        assert!(!is_watched(Path::new("other.numatb")));
+    }
+
+    #[test]
+    fn poll_modified_files() {
+        let folder = std::env::temp_dir().join("ssbh_wgpu_poll_modified_files");
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::write(folder.join("model.numatb"), "invalid").unwrap();
+        std::fs::write(folder.join("model.numshb"), "invalid").unwrap();
+
+        let mut watcher = FolderWatcher::new(&folder);
+        assert!(watcher.poll().is_empty());
+
+        // Explicitly set the time since some file systems have low precision timestamps.
+        let file = File::options()
+            .write(true)
+            .open(folder.join("model.numatb"))
+            .unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(10))
+            .unwrap();
+        drop(file);
+
+        let events = watcher.poll();
+        assert!(watcher.poll().is_empty());
+        std::fs::remove_dir_all(&folder).unwrap();
+
+        assert_eq!(1, events.len());
+        assert!(matches!(&events[0], ReloadEvent::Error(e) if e.name == "model.numatb"));
+    }
+}
@@ -2,12 +2,15 @@ use bytemuck::Pod;
 use encase::{internal::WriteInto, ShaderSize, ShaderType, StorageBuffer};
 use log::{error, info};
 use model::pipeline::PipelineData;
+use renderer::{DEPTH_FORMAT, OIT_ACCUMULATION_FORMAT, OIT_REVEALAGE_FORMAT, RGBA_COLOR_FORMAT};
 use ssbh_data::prelude::*;
 use std::{
-    error::Error,
+    io::Cursor,
     path::{Path, PathBuf},
+    sync::Mutex,
     time::Duration,
 };
+use texture_cache::TextureCache;
 use walkdir::WalkDir;
 use wgpu::util::DeviceExt;
 use xmb_lib::XmbFile;
@@ -17,10 +20,30 @@ use xmb_lib::XmbFile;
 pub use nutexb_wgpu::NutexbFile;
 
 pub mod animation;
+pub mod batch;
+mod bone_filter;
+mod bone_hit;
 mod bone_name;
 mod bone_rendering;
+mod bounding;
+mod capture;
+mod debug_gizmo;
+mod error;
+pub mod export;
+mod file_loader;
 mod floor_grid;
+#[cfg(feature = "frame_stats")]
+mod frame_stats;
+#[cfg(feature = "arbitrary")]
+pub mod fuzzing;
+mod gpu_timings;
+pub mod hitbox;
+mod hitbox_rendering;
+pub mod hot_reload;
+mod light_gizmo;
+mod loader;
 mod model;
+mod picking;
 mod render_settings;
 mod renderer;
 mod sampler;
@@ -29,38 +52,92 @@ mod shader_database;
 mod shape;
 pub mod swing;
 mod swing_rendering;
+#[cfg(test)]
+mod test_utils;
 mod texture;
+mod texture_cache;
+mod transform_gizmo;
 mod uniforms;
 mod vertex;
 pub mod viewport;
 
-pub use bone_name::BoneNameRenderer;
-pub use model::{RenderMesh, RenderModel};
+pub use bone_filter::{constrained_bone_names, BoneFilter};
+pub use bone_hit::{hit_test, BoneHitRegion, JointHitRegion};
+pub use bone_name::{BoneNameOptions, BoneNameRenderer, LabelOverlap, MAX_LABEL_OFFSETS};
+pub use bounding::{scene_bounds, BoundingBox, BoundingSphere, Frustum};
+pub use capture::{encode_apng, CaptureSettings, Turntable, TurntableSettings};
+pub use error::SsbhWgpuError;
+pub use file_loader::{CustomFile, FileLoaders};
+#[cfg(feature = "frame_stats")]
+pub use frame_stats::FrameStats;
+pub use gpu_timings::{GpuTimings, GPU_TIMING_FEATURES};
+pub use loader::{LoadProgress, ModelLoader};
+pub use model::{
+    FileDiagnostic, LoadDiagnostics, MeshDiagnostic, MeshSelector, MeshValidationError,
+    MeshValidationErrorKind, RenderMesh, RenderModel, SkinnedVertices, TextureFallback,
+};
+pub use picking::{pick_bone, pick_mesh, BoneHit, MeshHit};
 pub use render_settings::{
-    DebugMode, ModelRenderOptions, RenderSettings, SkinningSettings, TransitionMaterial,
+    DebugMode, DofSettings, DrawOrder, InvalidMeshStyle, LodMode, MaterialComparison, MaterialMask,
+    ModelRenderOptions, PostProcessSettings, ProfileSettings, RenderProfile, RenderSettings,
+    RestPose, SkinningSettings, ToneMapping, TransitionCurve, TransitionMaterial, TransparencyMode,
+    UvLayoutOptions, UvMap,
 };
-pub use renderer::SsbhRenderer;
+pub use renderer::{ScissorRect, SsbhRenderer, TextureAllocation, Viewport};
 pub use shader::model::CameraTransforms;
 pub use shader_database::{split_param, ShaderDatabase, ShaderProgram};
 pub use texture::{create_default_textures, load_default_spec_cube};
+pub use transform_gizmo::{GizmoAxis, GizmoMode, TransformGizmo};
+pub use uniforms::{material_uniforms, MaterialUniforms, MaterialValue};
 
 // TODO: Find a way to avoid using the format features for filterable f32 textures.
 /// Required WGPU features for using this library.
 /// This library currently only supports WGPU on native desktop platforms.
+///
+/// Request any supported [OPTIONAL_FEATURES] as well for better performance.
 pub const REQUIRED_FEATURES: wgpu::Features = wgpu::Features::from_bits_truncate(
-    wgpu::Features::TEXTURE_COMPRESSION_BC.bits()
-        | wgpu::Features::ADDRESS_MODE_CLAMP_TO_BORDER.bits()
+    wgpu::Features::ADDRESS_MODE_CLAMP_TO_BORDER.bits()
         | wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES.bits()
         | wgpu::Features::POLYGON_MODE_LINE.bits()
         | wgpu::Features::DEPTH32FLOAT_STENCIL8.bits()
         | wgpu::Features::TEXTURE_FORMAT_16BIT_NORM.bits(),
 );
 
+/// Optional WGPU features used by this library if supported by the adapter.
+///
+/// Textures are decoded to uncompressed formats on the CPU without [wgpu::Features::TEXTURE_COMPRESSION_BC].
+/// This takes longer to load and uses more memory.
+pub const OPTIONAL_FEATURES: wgpu::Features =
+    wgpu::Features::from_bits_truncate(wgpu::Features::TEXTURE_COMPRESSION_BC.bits());
+
+/// The MSAA sample counts for [SsbhRenderer::new] and [SsbhRenderer::set_sample_count] supported by `adapter` in increasing order.
+///
+/// Lower sample counts improve performance on low end devices at the cost of more aliased edges.
+pub fn supported_sample_counts(adapter: &wgpu::Adapter) -> Vec<u32> {
+    // REQUIRED_FEATURES enables the adapter specific sample counts.
+    // Weighted blended transparency also uses multisampled accumulation and revealage textures.
+    let formats = [
+        RGBA_COLOR_FORMAT,
+        DEPTH_FORMAT,
+        OIT_ACCUMULATION_FORMAT,
+        OIT_REVEALAGE_FORMAT,
+    ]
+    .map(|format| adapter.get_texture_format_features(format).flags);
+    [1, 2, 4, 8]
+        .into_iter()
+        .filter(|count| formats.iter().all(|f| f.sample_count_supported(*count)))
+        .collect()
+}
+
+/// The WGSL source for the model shader used as a starting point for [SharedRenderData::register_custom_shader].
+pub const MODEL_SHADER_WGSL: &str = include_str!("shader/model.wgsl");
+
 // TODO: Better name?
 pub struct SharedRenderData {
     pipeline_data: PipelineData,
     default_textures: Vec<(String, wgpu::Texture, wgpu::TextureViewDimension)>,
     database: ShaderDatabase,
+    texture_cache: Mutex<TextureCache>,
 }
 
 impl SharedRenderData {
@@ -69,6 +146,7 @@ impl SharedRenderData {
             pipeline_data: PipelineData::new(device),
             default_textures: create_default_textures(device, queue),
             database: ShaderDatabase::new(),
+            texture_cache: Mutex::new(TextureCache::new()),
         }
     }
 
@@ -80,8 +158,130 @@ impl SharedRenderData {
         &self.database
     }
 
+    /// The MSAA sample count for model pipelines from [SharedRenderData::set_sample_count].
+    pub fn sample_count(&self) -> u32 {
+        self.pipeline_data.sample_count
+    }
+
+    /// Sets the MSAA sample count for creating model pipelines. The default is 4.
+    ///
+    /// This must match [SsbhRenderer::sample_count], so use this value for [SsbhRenderer::new]
+    /// and [SsbhRenderer::set_sample_count].
+    /// Existing models need to update their pipelines with [RenderModel::recreate_pipelines].
+    pub fn set_sample_count(&mut self, sample_count: u32) {
+        self.pipeline_data.sample_count = sample_count;
+    }
+
+    /// `true` if model pipelines use alpha to coverage for materials with `alpha_sample_to_coverage` enabled.
+    pub fn alpha_to_coverage(&self) -> bool {
+        self.pipeline_data.alpha_to_coverage
+    }
+
+    /// Enables or disables alpha to coverage for creating model pipelines. The default is `true`.
+    ///
+    /// Disabling alpha to coverage renders these materials with hard alpha tested edges
+    /// similar to rendering without MSAA.
+    /// Existing models need to update their pipelines with [RenderModel::recreate_pipelines].
+    pub fn set_alpha_to_coverage(&mut self, enabled: bool) {
+        self.pipeline_data.alpha_to_coverage = enabled;
+    }
+
+    /// Draws materials using `shader_label` with the fragment shader `entry_point` from `wgsl`
+    /// instead of the default model shader.
+    ///
+    /// The `shader_label` can include the render pass tag like `"SFX_PBS_0100000008008269_opaque"`
+    /// or omit the tag to apply to all render passes like `"SFX_PBS_0100000008008269"`.
+    /// Registering a shader for the same label replaces the previous shader.
+    ///
+    /// The `wgsl` uses the vertex outputs and bind groups from [MODEL_SHADER_WGSL],
+    /// so the simplest approach is to copy the model shader and edit or add fragment entry points.
+    /// Invalid WGSL is reported to the `device` like any other WGPU validation error.
+    /// Blended meshes using a custom shader are always drawn in order
+    /// even with [TransparencyMode::WeightedBlended](crate::TransparencyMode::WeightedBlended).
+    ///
+    /// Existing models need to update their pipelines with [RenderModel::recreate_pipelines].
+    pub fn register_custom_shader(
+        &mut self,
+        device: &wgpu::Device,
+        shader_label: &str,
+        wgsl: &str,
+        entry_point: &str,
+    ) {
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(shader_label),
+            source: wgpu::ShaderSource::Wgsl(wgsl.into()),
+        });
+        self.pipeline_data
+            .add_custom_shader(shader_label, Some(module), entry_point);
+    }
+
+    /// Draws materials using `shader_label` with a different fragment `entry_point` from [MODEL_SHADER_WGSL].
+    /// See [SharedRenderData::register_custom_shader].
+    pub fn register_custom_entry_point(&mut self, shader_label: &str, entry_point: &str) {
+        self.pipeline_data
+            .add_custom_shader(shader_label, None, entry_point);
+    }
+
+    /// Removes the custom shader registered for `shader_label`
+    /// and returns `true` if a shader was removed.
+    ///
+    /// Existing models need to update their pipelines with [RenderModel::recreate_pipelines].
+    pub fn remove_custom_shader(&mut self, shader_label: &str) -> bool {
+        let count = self.pipeline_data.custom_shaders.len();
+        self.pipeline_data
+            .custom_shaders
+            .retain(|s| s.shader_label != shader_label);
+        self.pipeline_data.custom_shaders.len() != count
+    }
+
+    /// The shader labels with a custom shader from [SharedRenderData::register_custom_shader].
+    pub fn custom_shader_labels(&self) -> impl Iterator<Item = &str> {
+        self.pipeline_data
+            .custom_shaders
+            .iter()
+            .map(|s| s.shader_label.as_str())
+    }
+
+    /// The maximum estimated memory in bytes for textures shared between models
+    /// from [SharedRenderData::set_texture_memory_budget].
+    pub fn texture_memory_budget(&self) -> Option<u64> {
+        self.texture_cache.lock().unwrap().budget()
+    }
+
+    /// Sets the maximum estimated memory in bytes for cached textures.
+    ///
+    /// Models created with this shared data share textures with identical nutexb data.
+    /// The default of `None` only shares textures while a model still uses them,
+    /// so dropping models frees their textures.
+    /// Setting a budget keeps recently used textures alive after their models are dropped.
+    /// Cached textures are evicted in least recently used order when the budget is exceeded
+    /// and uploaded again the next time a model uses them.
+    /// Textures are only freed once no [RenderModel] references them,
+    /// so the memory usage may exceed the budget while models are still alive.
+    pub fn set_texture_memory_budget(&mut self, budget: Option<u64>) {
+        self.texture_cache.get_mut().unwrap().set_budget(budget);
+    }
+
+    /// The estimated memory in bytes for all the textures shared between models.
+    ///
+    /// This does not include default textures or textures
+    /// edited with [RenderModel::replace_texture].
+    pub fn texture_memory_usage(&self) -> u64 {
+        self.texture_cache.lock().unwrap().memory_usage()
+    }
+
+    /// The number of unique textures shared between models.
+    pub fn shared_texture_count(&self) -> usize {
+        self.texture_cache.lock().unwrap().len()
+    }
+
+    /// Releases all cached textures not currently used by a [RenderModel].
+    pub fn clear_texture_cache(&mut self) {
+        self.texture_cache.get_mut().unwrap().clear();
+    }
+
     /// Updates the default texture for `#replace_cubemap` from `nutexb`.
-    /// Invalid nutexb files are ignored.
+    /// Invalid nutexb files return an error and leave the texture unchanged.
     ///
     /// Textures will need to be updated for each [RenderModel] with
     /// [RenderModel::recreate_materials] for this change to take effect.
@@ -90,19 +290,22 @@ impl SharedRenderData {
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         nutexb: &NutexbFile,
-    ) {
-        // TODO: Return errors.
-        if let Some((_, texture, _)) = self
+    ) -> Result<(), SsbhWgpuError> {
+        let (new_texture, dimension) = nutexb_wgpu::create_texture(nutexb, device, queue)?;
+        if dimension != wgpu::TextureViewDimension::Cube {
+            return Err(SsbhWgpuError::TextureDimension {
+                expected: wgpu::TextureViewDimension::Cube,
+                actual: dimension,
+            });
+        }
+
+        let (_, texture, _) = self
             .default_textures
             .iter_mut()
             .find(|(name, _, _)| name == "#replace_cubemap")
-        {
-            if let Ok((new_texture, wgpu::TextureViewDimension::Cube)) =
-                nutexb_wgpu::create_texture(nutexb, device, queue)
-            {
-                *texture = new_texture;
-            }
-        }
+            .ok_or_else(|| SsbhWgpuError::DefaultTexture("#replace_cubemap".to_owned()))?;
+        *texture = new_texture;
+        Ok(())
     }
 
     /// Resets the default texture for `#replace_cubemap` to its default value.
@@ -118,7 +321,7 @@ impl SharedRenderData {
     }
 }
 
-pub type ModelFiles<T> = Vec<(String, Result<T, Box<dyn Error>>)>;
+pub type ModelFiles<T> = Vec<(String, Result<T, SsbhWgpuError>)>;
 
 /// A collection of supported rendering related files in a model or animation folder.
 ///
@@ -137,6 +340,8 @@ pub struct ModelFolder {
     pub hlpbs: ModelFiles<HlpbData>,
     pub nutexbs: ModelFiles<NutexbFile>,
     pub xmbs: ModelFiles<XmbFile>,
+    /// Files read by the custom loaders from [ModelFolder::load_folder_with_loaders].
+    pub custom_files: ModelFiles<CustomFile>,
 }
 
 #[cfg(feature = "arbitrary")]
@@ -154,12 +359,19 @@ impl<'a> arbitrary::Arbitrary<'a> for ModelFolder {
             nutexbs: vec![], // TODO: Createy arbitrary nutexbs
             meshexes: vec![("model.numshexb".to_owned(), Ok(u.arbitrary()?))],
             xmbs: vec![("model.xmb".to_owned(), Ok(u.arbitrary()?))],
+            custom_files: Vec::new(),
         })
     }
 }
 
 impl ModelFolder {
     pub fn load_folder<P: AsRef<Path>>(folder: P) -> Self {
+        Self::load_folder_with_loaders(folder, &FileLoaders::default())
+    }
+
+    /// Loads the supported files like [ModelFolder::load_folder]
+    /// and any additional files with an extension registered in `loaders`.
+    pub fn load_folder_with_loaders<P: AsRef<Path>>(folder: P, loaders: &FileLoaders) -> Self {
         let files: Vec<_> = std::fs::read_dir(folder)
             .map(|dir| dir.filter_map(|p| p.ok().map(|p| p.path())).collect())
             .unwrap_or_default();
@@ -173,13 +385,50 @@ impl ModelFolder {
             anims: read_files(&files, "nuanmb", AnimData::from_file),
             adjs: read_files(&files, "adjb", AdjData::from_file),
             hlpbs: read_files(&files, "nuhlpb", HlpbData::from_file),
-            nutexbs: read_files(&files, "nutexb", |p| {
-                NutexbFile::read_from_file(p).map_err(Into::into)
-            }),
+            nutexbs: read_files(&files, "nutexb", NutexbFile::read_from_file),
             xmbs: read_files(&files, "xmb", XmbFile::from_file),
+            custom_files: loaders
+                .loaders()
+                .flat_map(|(extension, load)| {
+                    read_files(&files, extension, |p| load(&p).map(CustomFile::new))
+                })
+                .collect(),
+        }
+    }
+
+    /// Parses the supported files from their names and contents without accessing the file system.
+    ///
+    /// This allows loading models from ARC archives, network sources, or other in memory data
+    /// without first extracting the files to a temporary folder.
+    /// Names can be full paths like `"fighter/mario/model/body/c00/model.numshb"`,
+    /// but only the file name is stored.
+    /// Files with unsupported extensions are ignored, including extensions for custom loaders.
+    pub fn from_files(files: impl IntoIterator<Item = (String, Vec<u8>)>) -> Self {
+        let files: Vec<_> = files.into_iter().collect();
+
+        Self {
+            meshes: read_bytes(&files, "numshb", MeshData::read),
+            meshexes: read_bytes(&files, "numshexb", MeshExData::read),
+            skels: read_bytes(&files, "nusktb", SkelData::read),
+            matls: read_bytes(&files, "numatb", MatlData::read),
+            modls: read_bytes(&files, "numdlb", ModlData::read),
+            anims: read_bytes(&files, "nuanmb", AnimData::read),
+            adjs: read_bytes(&files, "adjb", AdjData::read),
+            hlpbs: read_bytes(&files, "nuhlpb", HlpbData::read),
+            nutexbs: read_bytes(&files, "nutexb", NutexbFile::read),
+            xmbs: read_bytes(&files, "xmb", XmbFile::read),
+            custom_files: Vec::new(),
         }
     }
 
+    /// Finds the file with `file_name` in [custom_files](#structfield.custom_files) if it loaded as type `T`.
+    pub fn find_custom<T: std::any::Any>(&self, file_name: &str) -> Option<&T> {
+        self.custom_files
+            .iter()
+            .find(|(f, _)| f == file_name)
+            .and_then(|(_, f)| f.as_ref().ok()?.downcast_ref())
+    }
+
     /// Finds the `"model.numdlb"` file in [modls](#structfield.modls).
     pub fn find_modl(&self) -> Option<&ModlData> {
         self.modls
@@ -188,6 +437,22 @@ impl ModelFolder {
             .and_then(|(_, m)| m.as_ref().ok())
     }
 
+    /// Finds the modl file with `file_name` like `"model_paint.numdlb"` in [modls](#structfield.modls).
+    ///
+    /// Some stages have additional modl files besides `"model.numdlb"` for effects like ink.
+    pub fn find_modl_by_name(&self, file_name: &str) -> Option<&ModlData> {
+        find_file(&self.modls, file_name)
+    }
+
+    /// The names of the modl files that loaded successfully like `"model.numdlb"` or `"model_paint.numdlb"`.
+    pub fn modl_names(&self) -> Vec<&str> {
+        self.modls
+            .iter()
+            .filter(|(_, m)| m.is_ok())
+            .map(|(f, _)| f.as_str())
+            .collect()
+    }
+
     /// Finds the `"model.numatb"` file in [matls](#structfield.matls).
     pub fn find_matl(&self) -> Option<&MatlData> {
         self.matls
@@ -260,6 +525,31 @@ impl ModelFolder {
             .and_then(|(_, m)| m.as_ref().ok())
     }
 
+    /// Finds the files that failed to read.
+    pub fn file_errors(&self) -> Vec<FileDiagnostic> {
+        fn errors<T>(files: &ModelFiles<T>) -> impl Iterator<Item = FileDiagnostic> + '_ {
+            files.iter().filter_map(|(name, f)| {
+                f.as_ref().err().map(|e| FileDiagnostic {
+                    name: name.clone(),
+                    message: e.to_string(),
+                })
+            })
+        }
+
+        errors(&self.meshes)
+            .chain(errors(&self.meshexes))
+            .chain(errors(&self.skels))
+            .chain(errors(&self.matls))
+            .chain(errors(&self.modls))
+            .chain(errors(&self.adjs))
+            .chain(errors(&self.anims))
+            .chain(errors(&self.hlpbs))
+            .chain(errors(&self.nutexbs))
+            .chain(errors(&self.xmbs))
+            .chain(errors(&self.custom_files))
+            .collect()
+    }
+
     // Returns `true` if the folder has no supported files.
     pub fn is_empty(&self) -> bool {
         self.meshes.is_empty()
@@ -270,6 +560,7 @@ impl ModelFolder {
             && self.adjs.is_empty()
             && self.hlpbs.is_empty()
             && self.nutexbs.is_empty()
+            && self.custom_files.is_empty()
     }
 }
 
@@ -305,15 +596,12 @@ pub fn load_render_models<'a>(
 pub fn load_model_folders<P: AsRef<Path>>(root: P) -> Vec<(PathBuf, ModelFolder)> {
     let start = std::time::Instant::now();
 
-    // The ARC paths only need a max depth of 4 for model files.
-    // Examples include mario/model/body/c00 or mario_galaxy/normal/model/stc_ring_set.
-    // Opening the entire fighter folder has a depth of 5 and will likely crash.
-    let models: Vec<_> = WalkDir::new(root)
-        .max_depth(4)
+    let models: Vec<_> = model_folder_paths(root.as_ref())
         .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.file_type().is_dir())
-        .map(|e| (e.path().to_owned(), ModelFolder::load_folder(e.path())))
+        .map(|p| {
+            let model = ModelFolder::load_folder(&p);
+            (p, model)
+        })
         .collect();
 
     info!(
@@ -325,9 +613,30 @@ pub fn load_model_folders<P: AsRef<Path>>(root: P) -> Vec<(PathBuf, ModelFolder)
     models
 }
 
-fn read_files<T, F>(files: &[PathBuf], extension: &str, read_t: F) -> ModelFiles<T>
+fn model_folder_paths(root: &Path) -> Vec<PathBuf> {
+    // The ARC paths only need a max depth of 4 for model files.
+    // Examples include mario/model/body/c00 or mario_galaxy/normal/model/stc_ring_set.
+    // Opening the entire fighter folder has a depth of 5 and will likely crash.
+    WalkDir::new(root)
+        .max_depth(4)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_dir())
+        .map(|e| e.path().to_owned())
+        .collect()
+}
+
+pub(crate) fn find_file<'a, T>(files: &'a ModelFiles<T>, file_name: &str) -> Option<&'a T> {
+    files
+        .iter()
+        .find(|(f, _)| f == file_name)
+        .and_then(|(_, f)| f.as_ref().ok())
+}
+
+fn read_files<T, E, F>(files: &[PathBuf], extension: &str, read_t: F) -> ModelFiles<T>
 where
-    F: Fn(PathBuf) -> Result<T, Box<dyn Error>>,
+    E: std::fmt::Display,
+    F: Fn(PathBuf) -> Result<T, E>,
 {
     files
         .iter()
@@ -337,7 +646,8 @@ where
                 p.file_name()?.to_string_lossy().to_string(),
                 read_t(p.clone()).map_err(|e| {
                     error!("Error reading {:?}: {}", p, e);
-                    e
+                    // Store the message to allow sending folders between threads.
+                    SsbhWgpuError::ReadFile(e.to_string())
                 }),
             ))
         })
@@ -511,11 +821,58 @@ pub fn next_frame(
     next_frame
 }
 
+fn read_bytes<T, E, F>(files: &[(String, Vec<u8>)], extension: &str, read_t: F) -> ModelFiles<T>
+where
+    E: std::fmt::Display,
+    F: Fn(&mut Cursor<&[u8]>) -> Result<T, E>,
+{
+    files
+        .iter()
+        .map(|(name, bytes)| (Path::new(name), bytes))
+        .filter(|(p, _)| p.extension().and_then(|p| p.to_str()) == Some(extension))
+        .filter_map(|(p, bytes)| {
+            Some((
+                p.file_name()?.to_string_lossy().to_string(),
+                read_t(&mut Cursor::new(bytes.as_slice())).map_err(|e| {
+                    error!("Error reading {:?}: {}", p, e);
+                    // Store the message to allow sending folders between threads.
+                    SsbhWgpuError::ReadFile(e.to_string())
+                }),
+            ))
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::time::Duration;
 
+    #[test]
+    fn model_folder_from_files() {
+        let folder = ModelFolder::from_files([
+            ("a/b/model.numatb".to_owned(), vec![0u8; 4]),
+            ("model.txt".to_owned(), vec![1u8; 4]),
+        ]);
+
+        assert!(folder.matls[0].1.is_err());
+        assert_eq!(1, folder.file_errors().len());
+        assert_eq!("model.numatb", folder.file_errors()[0].name);
+        assert!(folder.custom_files.is_empty());
+    }
+
+    #[test]
+    fn model_folder_modl_names() {
+        let folder = ModelFolder::from_files([
+            ("model.numdlb".to_owned(), vec![0u8; 4]),
+            ("model_paint.numdlb".to_owned(), vec![0u8; 4]),
+        ]);
+
+        // Only include modls that can be used for rendering.
+        assert!(folder.modl_names().is_empty());
+        assert!(folder.find_modl_by_name("model_paint.numdlb").is_none());
+    }
+
     #[test]
     fn next_frame_loop_zero_final_frame() {
         assert_eq!(0.0, next_frame(1.0, Duration::from_secs(0), 0.0, 1.0, true));
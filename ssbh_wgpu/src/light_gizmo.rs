@@ -0,0 +1,234 @@
+use std::f32::consts::PI;
+
+use crate::{shape::IndexedMeshBuffers, DeviceBufferExt, QueueExt};
+
+pub struct LightGizmoRenderData {
+    pipeline: wgpu::RenderPipeline,
+    camera_bind_group: crate::shader::light_gizmo::bind_groups::BindGroup0,
+    gizmo_bind_group: crate::shader::light_gizmo::bind_groups::BindGroup1,
+    gizmo_buffer: wgpu::Buffer,
+    buffers: IndexedMeshBuffers,
+}
+
+impl LightGizmoRenderData {
+    pub fn new(
+        device: &wgpu::Device,
+        camera_buffer: &wgpu::Buffer,
+        surface_format: wgpu::TextureFormat,
+    ) -> Self {
+        let shader = crate::shader::light_gizmo::create_shader_module(device);
+        let layout = crate::shader::light_gizmo::create_pipeline_layout(device);
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Light Gizmo Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[
+                    crate::shader::light_gizmo::VertexInput::vertex_buffer_layout(
+                        wgpu::VertexStepMode::Vertex,
+                    ),
+                ],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(surface_format.into())],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            // Draw on top of everything in the final pass like bones.
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let camera_bind_group = crate::shader::light_gizmo::bind_groups::BindGroup0::from_bindings(
+            device,
+            crate::shader::light_gizmo::bind_groups::BindGroupLayout0 {
+                camera: camera_buffer.as_entire_buffer_binding(),
+            },
+        );
+
+        let gizmo_buffer = device.create_buffer_from_data(
+            "Light Gizmo Buffer",
+            &[gizmo_uniforms(glam::Vec4::Z, glam::Vec3::ZERO)],
+            wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        );
+
+        let gizmo_bind_group = crate::shader::light_gizmo::bind_groups::BindGroup1::from_bindings(
+            device,
+            crate::shader::light_gizmo::bind_groups::BindGroupLayout1 {
+                gizmo: gizmo_buffer.as_entire_buffer_binding(),
+            },
+        );
+
+        let (vertices, indices) = arrow(16);
+        let buffers = IndexedMeshBuffers::from_vertices(device, &vertices, &indices);
+
+        Self {
+            pipeline,
+            camera_bind_group,
+            gizmo_bind_group,
+            gizmo_buffer,
+            buffers,
+        }
+    }
+
+    /// Points the arrow along the light `direction` with the tip at `target`.
+    pub fn update(&self, queue: &wgpu::Queue, direction: glam::Vec4, target: glam::Vec3) {
+        queue.write_data(&self.gizmo_buffer, &[gizmo_uniforms(direction, target)]);
+    }
+
+    pub fn draw(&self, pass: &mut wgpu::RenderPass<'_>) {
+        pass.set_pipeline(&self.pipeline);
+
+        crate::shader::light_gizmo::set_bind_groups(
+            pass,
+            &self.camera_bind_group,
+            &self.gizmo_bind_group,
+        );
+
+        self.buffers.set(pass);
+
+        pass.draw_indexed(0..self.buffers.index_count, 0, 0..1);
+    }
+}
+
+fn gizmo_uniforms(
+    direction: glam::Vec4,
+    target: glam::Vec3,
+) -> crate::shader::light_gizmo::LightGizmo {
+    crate::shader::light_gizmo::LightGizmo {
+        transform: gizmo_transform(direction, target),
+        color: glam::vec4(1.0, 0.8, 0.2, 1.0),
+    }
+}
+
+fn gizmo_transform(direction: glam::Vec4, target: glam::Vec3) -> glam::Mat4 {
+    // The light direction points towards the light.
+    // The arrow should point from the light towards the target.
+    let direction = direction
+        .truncate()
+        .try_normalize()
+        .unwrap_or(glam::Vec3::Z);
+    let rotation = glam::Quat::from_rotation_arc(glam::Vec3::Y, -direction);
+    glam::Mat4::from_rotation_translation(rotation, target)
+}
+
+// An arrow along the Y-axis with the tip at the origin and unit length.
+fn arrow(segments: u32) -> (Vec<[f32; 4]>, Vec<u32>) {
+    // Pos0 Nrm0 Pos1 Nrm1 ...
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+
+    let shaft_radius = 0.02;
+    let head_radius = 0.06;
+    let head_start = -0.25;
+
+    let mut add_quad = |vertices: &mut Vec<[f32; 4]>, quad: [([f32; 3], [f32; 3]); 4]| {
+        let start = (vertices.len() / 2) as u32;
+        for (p, n) in quad {
+            vertices.push([p[0], p[1], p[2], 1.0]);
+            vertices.push([n[0], n[1], n[2], 0.0]);
+        }
+        indices.extend([start, start + 1, start + 2, start + 2, start + 3, start]);
+    };
+
+    for i in 0..segments {
+        let a0 = 2.0 * PI * i as f32 / segments as f32;
+        let a1 = 2.0 * PI * (i + 1) as f32 / segments as f32;
+        let (s0, c0) = a0.sin_cos();
+        let (s1, c1) = a1.sin_cos();
+
+        // Shaft sides.
+        add_quad(
+            &mut vertices,
+            [
+                ([shaft_radius * c0, -1.0, shaft_radius * s0], [c0, 0.0, s0]),
+                ([shaft_radius * c1, -1.0, shaft_radius * s1], [c1, 0.0, s1]),
+                (
+                    [shaft_radius * c1, head_start, shaft_radius * s1],
+                    [c1, 0.0, s1],
+                ),
+                (
+                    [shaft_radius * c0, head_start, shaft_radius * s0],
+                    [c0, 0.0, s0],
+                ),
+            ],
+        );
+
+        // Underside of the head.
+        add_quad(
+            &mut vertices,
+            [
+                (
+                    [shaft_radius * c0, head_start, shaft_radius * s0],
+                    [0.0, -1.0, 0.0],
+                ),
+                (
+                    [shaft_radius * c1, head_start, shaft_radius * s1],
+                    [0.0, -1.0, 0.0],
+                ),
+                (
+                    [head_radius * c1, head_start, head_radius * s1],
+                    [0.0, -1.0, 0.0],
+                ),
+                (
+                    [head_radius * c0, head_start, head_radius * s0],
+                    [0.0, -1.0, 0.0],
+                ),
+            ],
+        );
+
+        // Cone sides with a degenerate quad at the tip.
+        let slope = head_radius / -head_start;
+        let n0 = glam::vec3(c0, slope, s0).normalize().to_array();
+        let n1 = glam::vec3(c1, slope, s1).normalize().to_array();
+        add_quad(
+            &mut vertices,
+            [
+                ([head_radius * c0, head_start, head_radius * s0], n0),
+                ([head_radius * c1, head_start, head_radius * s1], n1),
+                ([0.0, 0.0, 0.0], n1),
+                ([0.0, 0.0, 0.0], n0),
+            ],
+        );
+    }
+
+    (vertices, indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::assert_vector_relative_eq;
+
+    #[test]
+    fn gizmo_transform_points_to_target() {
+        let transform = gizmo_transform(glam::vec4(0.0, 1.0, 0.0, 0.0), glam::vec3(1.0, 2.0, 3.0));
+
+        // The tail of the arrow should be towards the light.
+        assert_vector_relative_eq!(
+            [1.0, 2.0, 3.0],
+            transform.transform_point3(glam::Vec3::ZERO).to_array()
+        );
+        assert_vector_relative_eq!(
+            [1.0, 3.0, 3.0],
+            transform
+                .transform_point3(glam::vec3(0.0, -1.0, 0.0))
+                .to_array()
+        );
+    }
+
+    #[test]
+    fn arrow_indices_in_range() {
+        let (vertices, indices) = arrow(8);
+        assert_eq!(8 * 3 * 6, indices.len());
+        assert!(indices.iter().all(|i| (*i as usize) < vertices.len() / 2));
+    }
+}
@@ -0,0 +1,188 @@
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+    sync::mpsc::{Receiver, TryRecvError},
+};
+
+use crate::{FileLoaders, ModelFolder, RenderModel, SharedRenderData};
+
+enum LoaderMessage {
+    Found(usize),
+    Loaded(PathBuf, ModelFolder),
+}
+
+/// The number of model folders loaded by a [ModelLoader].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LoadProgress {
+    /// The number of folders finished reading from disk.
+    pub loaded: usize,
+    /// The total number of folders or `None` if folders are still being found.
+    pub total: Option<usize>,
+}
+
+impl LoadProgress {
+    /// The fraction of folders loaded from `0.0` to `1.0` for displaying progress bars.
+    /// Returns `0.0` if the total is not known yet.
+    pub fn fraction(&self) -> f32 {
+        match self.total {
+            Some(0) => 1.0,
+            Some(total) => self.loaded as f32 / total as f32,
+            None => 0.0,
+        }
+    }
+}
+
+/// Loads model folders on a background thread to avoid blocking the calling thread.
+///
+/// Call [ModelLoader::poll] or [ModelLoader::poll_render_models] regularly such as once per frame
+/// to receive folders as they finish loading.
+/// This allows GUI applications to show progress and render models before all folders have loaded.
+/// Dropping the loader stops loading any remaining folders.
+///
+/// # Examples
+/**
+```rust no_run
+# fn test(device: &wgpu::Device, queue: &wgpu::Queue, shared_data: &ssbh_wgpu::SharedRenderData) {
+use ssbh_wgpu::ModelLoader;
+
+let mut loader = ModelLoader::new("/mario/model");
+let mut render_models = Vec::new();
+
+// Upload at most 2 models each frame.
+while !loader.is_finished() {
+    for (_, _, render_model) in loader.poll_render_models(device, queue, shared_data, 2) {
+        render_models.push(render_model);
+    }
+    println!("{:.0}%", loader.progress().fraction() * 100.0);
+}
+# }
+```
+ */
+pub struct ModelLoader {
+    receiver: Receiver<LoaderMessage>,
+    pending: VecDeque<(PathBuf, ModelFolder)>,
+    progress: LoadProgress,
+    is_disconnected: bool,
+}
+
+impl ModelLoader {
+    /// Starts loading model folders from `root` on a background thread.
+    ///
+    /// This loads the same folders as [load_model_folders](crate::load_model_folders).
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        Self::with_loaders(root, FileLoaders::default())
+    }
+
+    /// Starts loading model folders like [ModelLoader::new]
+    /// while also reading any files with an extension registered in `loaders`.
+    pub fn with_loaders<P: AsRef<Path>>(root: P, loaders: FileLoaders) -> Self {
+        let root = root.as_ref().to_owned();
+        let (sender, receiver) = std::sync::mpsc::channel();
+
+        std::thread::spawn(move || {
+            let folders = crate::model_folder_paths(&root);
+            if sender.send(LoaderMessage::Found(folders.len())).is_err() {
+                return;
+            }
+
+            for folder in folders {
+                let model = ModelFolder::load_folder_with_loaders(&folder, &loaders);
+                // The receiver is dropped if the loader is no longer needed.
+                if sender.send(LoaderMessage::Loaded(folder, model)).is_err() {
+                    return;
+                }
+            }
+        });
+
+        Self {
+            receiver,
+            pending: VecDeque::new(),
+            progress: LoadProgress::default(),
+            is_disconnected: false,
+        }
+    }
+
+    /// Returns the folders that finished loading since the last call without blocking.
+    pub fn poll(&mut self) -> Vec<(PathBuf, ModelFolder)> {
+        self.receive();
+        self.pending.drain(..).collect()
+    }
+
+    /// Creates render models for at most `max_count` loaded folders without waiting on the background thread.
+    ///
+    /// Creating a [RenderModel] uploads data to the GPU and can be expensive for large models.
+    /// Small values for `max_count` spread the cost over multiple frames.
+    pub fn poll_render_models(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        shared_data: &SharedRenderData,
+        max_count: usize,
+    ) -> Vec<(PathBuf, ModelFolder, RenderModel)> {
+        self.receive();
+
+        let count = max_count.min(self.pending.len());
+        self.pending
+            .drain(..count)
+            .map(|(path, model)| {
+                let render_model = RenderModel::from_folder(device, queue, &model, shared_data);
+                (path, model, render_model)
+            })
+            .collect()
+    }
+
+    /// The current progress for reading folders from disk.
+    pub fn progress(&self) -> LoadProgress {
+        self.progress
+    }
+
+    /// Returns `true` if all folders have been loaded and returned from polling.
+    pub fn is_finished(&self) -> bool {
+        self.is_disconnected && self.pending.is_empty()
+    }
+
+    fn receive(&mut self) {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(LoaderMessage::Found(total)) => self.progress.total = Some(total),
+                Ok(LoaderMessage::Loaded(path, model)) => {
+                    self.progress.loaded += 1;
+                    self.pending.push_back((path, model));
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.is_disconnected = true;
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_progress_fraction() {
+        let progress = |loaded, total| LoadProgress { loaded, total };
+        assert_eq!(0.0, progress(0, None).fraction());
+        assert_eq!(1.0, progress(0, Some(0)).fraction());
+        assert_eq!(0.25, progress(1, Some(4)).fraction());
+    }
+
+    #[test]
+    fn model_loader_missing_folder() {
+        let mut loader = ModelLoader::new("this/folder/does/not/exist");
+        while !loader.is_finished() {
+            assert!(loader.poll().is_empty());
+        }
+        assert_eq!(
+            LoadProgress {
+                loaded: 0,
+                total: Some(0)
+            },
+            loader.progress()
+        );
+    }
+}
@@ -1,29 +1,63 @@
 use crate::{
-    animation::{animate_materials, animate_skel, animate_visibility, AnimationTransforms},
+    animation::{
+        animate_materials, animate_skel_blended, animate_skel_layers, animate_visibility,
+        blend_materials, preview_constraint, skinning_buffer_bone_count, visibility_group,
+        AnimLayerMode, AnimationTransforms, HlpbConstraint,
+    },
+    bone_filter::matches_pattern,
     bone_rendering::*,
+    bounding::{BoundingBox, BoundingSphere, Frustum},
+    capture::map_slice,
+    find_file,
+    hitbox::HitboxCapsule,
+    hitbox_rendering::{draw_hitboxes, HitboxData},
+    picking::{skin_positions, PickingGeometry},
+    shader::skinning::SkinningTransform,
     shape::IndexedMeshBuffers,
     swing::SwingPrc,
     swing_rendering::{draw_swing_collisions, SwingRenderData},
-    vertex::CombinedMeshBuffers,
-    ModelFolder, QueueExt, ShaderDatabase, SharedRenderData,
+    texture::is_same_texture,
+    vertex::{color_set_offset, CombinedMeshBuffers},
+    BoneFilter, BoneHitRegion, CameraTransforms, LodMode, MaterialValue, ModelFolder, QueueExt,
+    RestPose, ShaderDatabase, SharedRenderData, SsbhWgpuError,
 };
-use log::{debug, info};
+use log::{debug, error, info};
 use mesh_creation::{
-    material_data, Material, MeshBufferAccess, RenderMeshSharedData, TransformBuffers,
+    material_data, skinning_transforms_buffer, GeometryData, Material, MeshBufferAccess,
+    RenderMeshSharedData, TransformBuffers,
 };
-use pipeline::{pipeline, PipelineKey};
+use nutexb_wgpu::NutexbFile;
+use pipeline::{pipeline, DepthPrepassPipelines, OitPipelines, PipelineKey};
 use ssbh_data::{
+    anim_data::GroupData,
     matl_data::{MatlEntryData, SamplerData},
     meshex_data::EntryFlags,
     prelude::*,
 };
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
-mod mesh_creation;
+pub(crate) mod mesh_creation;
 pub mod pipeline;
 
 pub type SamplerCache = Vec<(SamplerData, wgpu::Sampler)>;
 
+/// The number of state changes when drawing meshes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct BindCounts {
+    pub pipelines: u32,
+    pub bind_groups: u32,
+}
+
+impl std::ops::AddAssign for BindCounts {
+    fn add_assign(&mut self, rhs: Self) {
+        self.pipelines += rhs.pipelines;
+        self.bind_groups += rhs.bind_groups;
+    }
+}
+
 /// A renderable version of a [ModelFolder].
 ///
 /// This encapsulates data shared between [RenderMesh] like materials, bones, and textures.
@@ -35,12 +69,20 @@ pub struct RenderModel {
     pub is_visible: bool,
     /// Outline all the meshes in this model when `true` regardless of which meshes are selected.
     pub is_selected: bool,
+    /// The pose used by [RenderModel::apply_rest_pose].
+    pub rest_pose: RestPose,
+    /// The level of detail used by [RenderModel::update_lod].
+    pub lod_mode: LodMode,
 
     transforms: TransformBuffers,
+    world_transform: glam::Mat4,
     material_data_by_label: HashMap<String, Material>,
     default_material_data: Material,
     pipelines: HashMap<PipelineKey, wgpu::RenderPipeline>,
-    textures: Vec<(String, wgpu::Texture, wgpu::TextureViewDimension)>,
+    // The MSAA sample count used to create the pipelines.
+    sample_count: u32,
+    textures: Vec<(String, Arc<wgpu::Texture>, wgpu::TextureViewDimension)>,
+    comparison_materials: Option<ComparisonMaterials>,
 
     per_model_bind_group: crate::shader::model::bind_groups::BindGroup1,
 
@@ -48,10 +90,152 @@ pub struct RenderModel {
     bone_render_data: BoneRenderData,
     animation_transforms: Box<AnimationTransforms>,
     bone_names: Vec<String>,
+    bone_parent_indices: Vec<Option<usize>>,
 
     swing_render_data: SwingRenderData,
 
     mesh_buffers: CombinedMeshBuffers,
+
+    // Shared skinning data for all meshes to reduce bind group count.
+    // Meshes use dynamic offsets into the combined vertex buffers.
+    skinning_vertices_bind_group: Option<wgpu::BindGroup>,
+    renormal_vertices_bind_group: Option<wgpu::BindGroup>,
+    skinning_transforms_bind_group: crate::shader::skinning::bind_groups::BindGroup1,
+    mesh_object_info_bind_group: wgpu::BindGroup,
+
+    mesh_diagnostics: Vec<MeshDiagnostic>,
+    texture_diagnostics: Vec<FileDiagnostic>,
+
+    #[cfg(feature = "frame_stats")]
+    pub(crate) animation_stats: crate::frame_stats::AnimationStats,
+}
+
+/// A mesh object that was skipped when creating a [RenderModel].
+///
+/// Invalid meshes like meshes with no vertices or out of range vertex indices
+/// are skipped instead of panicking to still render the rest of the model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeshDiagnostic {
+    /// The name of the mesh object.
+    pub name: String,
+    /// The subindex of the mesh object if names are repeated.
+    pub subindex: u64,
+    /// A description of why the mesh was skipped.
+    pub message: String,
+}
+
+/// A problem with a mesh that causes it to render incorrectly.
+/// See [RenderModel::validation_errors].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeshValidationError {
+    /// The name of the mesh object.
+    pub name: String,
+    /// The subindex of the mesh object if names are repeated.
+    pub subindex: u64,
+    pub kind: MeshValidationErrorKind,
+}
+
+/// The type of problem for a [MeshValidationError].
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum MeshValidationErrorKind {
+    /// The mesh is drawn using [RenderSettings::invalid_attributes_color](crate::RenderSettings::invalid_attributes_color).
+    #[error(
+        "shader {shader_label:?} requires missing attributes {}",
+        attributes.join(", ")
+    )]
+    MissingAttributes {
+        shader_label: String,
+        /// The required attribute names like `"map1"` not present in the mesh.
+        attributes: Vec<String>,
+    },
+
+    /// The mesh is drawn using [RenderSettings::invalid_shader_color](crate::RenderSettings::invalid_shader_color).
+    #[error("shader {shader_label:?} is not in the shader database")]
+    UnrecognizedShader { shader_label: String },
+
+    /// The mesh is not drawn like in game.
+    #[error("material label {material_label:?} has no matching matl entry")]
+    MissingMaterial { material_label: String },
+
+    /// The influence weights are ignored for skinning.
+    #[error("influence bone {bone_name:?} is not in the skel")]
+    MissingInfluenceBone { bone_name: String },
+
+    /// The influence weights are ignored for vertices outside the vertex count.
+    #[error(
+        "influence bone {bone_name:?} has vertex index {vertex_index} out of range for {vertex_count} vertices"
+    )]
+    InfluenceVertexIndex {
+        bone_name: String,
+        vertex_index: u32,
+        vertex_count: usize,
+    },
+}
+
+/// A file that failed to load when creating a [RenderModel].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileDiagnostic {
+    /// The file name like `"model.numshb"`.
+    pub name: String,
+    /// A description of the error.
+    pub message: String,
+}
+
+/// A texture assigned in a matl entry that was replaced with a default texture.
+///
+/// The fallback textures render without errors but likely won't match the appearance in game.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextureFallback {
+    /// The material label of the matl entry.
+    pub material_label: String,
+    /// The texture parameter like [ParamId::Texture0](ssbh_data::matl_data::ParamId::Texture0).
+    pub param_id: ssbh_data::matl_data::ParamId,
+    /// The texture path assigned in the matl like `"def_mario_001_col"`.
+    pub path: String,
+    /// The default texture applied instead like `"/common/shader/sfxpbs/default_white"`.
+    pub fallback: String,
+    /// A description of why the assigned texture couldn't be used.
+    pub message: String,
+}
+
+/// The vertices of a mesh object after skinning from [RenderModel::read_skinned_positions].
+///
+/// The vertices have the same order as the vertex data in the numshb.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkinnedVertices {
+    /// The name of the mesh object.
+    pub name: String,
+    /// The subindex of the mesh object.
+    pub subindex: u64,
+    /// Positions with the animated pose and model transform applied.
+    pub positions: Vec<[f32; 3]>,
+    /// Normalized normals with the animated pose and model transform applied.
+    pub normals: Vec<[f32; 3]>,
+}
+
+/// The items that failed to load from [RenderModel::from_folder_with_diagnostics].
+///
+/// Failed items are skipped or replaced with defaults to still create a partial [RenderModel].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LoadDiagnostics {
+    /// Files in the [ModelFolder] that failed to read.
+    pub files: Vec<FileDiagnostic>,
+    /// Nutexb files that could not be converted to textures.
+    pub textures: Vec<FileDiagnostic>,
+    /// Mesh objects that were skipped.
+    pub meshes: Vec<MeshDiagnostic>,
+    /// Matl texture assignments that were missing or invalid and replaced with defaults.
+    pub texture_fallbacks: Vec<TextureFallback>,
+}
+
+impl LoadDiagnostics {
+    /// Returns `true` if all items loaded successfully.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+            && self.textures.is_empty()
+            && self.meshes.is_empty()
+            && self.texture_fallbacks.is_empty()
+    }
 }
 
 /// A view over the data for a single mesh object in the parent [RenderModel].
@@ -67,27 +251,203 @@ pub struct RenderMesh {
     pub is_visible: bool,
     /// Outline this mesh when `true`.
     pub is_selected: bool,
-    meshex_flags: EntryFlags, // TODO: How to update these?
+    mesh_object_index: usize,
+    meshex_flags: EntryFlags,
+    lod_hidden: bool,
     material_label: String,
     shader_label: String,
-    renormal_bind_group: crate::shader::renormal::bind_groups::BindGroup0,
-    skinning_bind_group: crate::shader::skinning::bind_groups::BindGroup0,
-    skinning_transforms_bind_group: crate::shader::skinning::bind_groups::BindGroup1,
-    mesh_object_info_bind_group: crate::shader::skinning::bind_groups::BindGroup2,
+    // The offset into the mesh object info buffer of the parent RenderModel.
+    mesh_object_info_offset: u32,
     // TODO: How to update this when materials/shaders change?
     pipeline_key: PipelineKey,
     vertex_count: usize,
     vertex_index_count: usize,
+    sort_bias: i32,
     access: MeshBufferAccess,
+    vertex_buffer1: Arc<wgpu::Buffer>,
+    // A copy of the mesh's range of vertex_buffer1 for updating individual attributes.
+    buffer1_data: Vec<u8>,
     attribute_names: Vec<String>,
+    // Bounds in the resting pose with parent bone transforms applied.
+    bounding_box: Option<BoundingBox>,
+    // Bounds for frustum culling in the resting pose.
+    bounding_sphere: Option<BoundingSphere>,
+    // Bounds of the vertex positions before applying any transforms.
+    local_bounding_box: Option<BoundingBox>,
+    parent_bone_index: Option<usize>,
+    influence_bone_indices: Vec<usize>,
+    // Invalid influences are only available when creating the mesh.
+    influence_errors: Vec<MeshValidationErrorKind>,
+    pub(crate) picking: PickingGeometry,
+}
+
+impl RenderMesh {
+    /// Writes new vertex colors for the colorSet attribute with `set_index` like `1` for `"colorSet1"`.
+    ///
+    /// This only updates the vertex buffers and is fast enough for previewing vertex color painting.
+    /// `values` should have one RGBA value for each vertex.
+    /// The values for `"colorSet2"` replace the combined value for colorSet2, colorSet2_1, and colorSet2_2.
+    pub fn update_color_set(
+        &mut self,
+        queue: &wgpu::Queue,
+        set_index: usize,
+        values: &[[f32; 4]],
+    ) -> Result<(), SsbhWgpuError> {
+        let attribute_offset =
+            color_set_offset(set_index).ok_or(SsbhWgpuError::ColorSetIndex(set_index))?;
+        if values.len() != self.vertex_count {
+            return Err(SsbhWgpuError::ColorSetLength {
+                expected: self.vertex_count,
+                actual: values.len(),
+            });
+        }
+
+        // Attributes are interleaved, so patch the colorSet for each vertex and write the mesh once.
+        let stride = std::mem::size_of::<crate::shader::model::VertexInput1>();
+        let value_size = std::mem::size_of::<[f32; 4]>();
+        for (vertex, value) in self.buffer1_data.chunks_exact_mut(stride).zip(values) {
+            let offset = attribute_offset as usize;
+            vertex[offset..offset + value_size].copy_from_slice(bytemuck::cast_slice(value));
+        }
+
+        queue.write_buffer(
+            &self.vertex_buffer1,
+            self.access.buffer1.start(),
+            &self.buffer1_data,
+        );
+
+        Ok(())
+    }
+
+    /// The index of the mesh object in the numshb.
+    ///
+    /// This may differ from the index in [RenderModel::meshes] if some mesh objects failed to load.
+    pub fn mesh_object_index(&self) -> usize {
+        self.mesh_object_index
+    }
+
+    /// The bounds of the vertex positions in the resting pose or `None` if there are no positions.
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        self.bounding_box
+    }
+
+    /// The render pass tag at the end of the shader label like `"opaque"` or `"sort"`
+    /// or `""` if the shader label has no tag.
+    pub fn render_pass(&self) -> &str {
+        self.shader_label
+            .get(SHADER_LABEL_PREFIX_LEN..)
+            .map(|tag| tag.trim_start_matches('_'))
+            .unwrap_or("")
+    }
+
+    /// Replaces the render pass tag at the end of the shader label with `pass` like `"near"`.
+    /// The render order is opaque -> far -> sort -> near.
+    ///
+    /// This previews changing the shader label's tag in the matl without reloading the model.
+    /// The shader label is reset when reassigning or recreating materials.
+    /// Meshes without a valid shader label are unchanged.
+    pub fn set_render_pass(&mut self, pass: &str) {
+        if let Some(prefix) = self.shader_label.get(..SHADER_LABEL_PREFIX_LEN) {
+            self.shader_label = format!("{prefix}_{pass}");
+        }
+    }
+
+    /// The rendering flags from the numshexb or the default flags if the mesh has no numshexb entry.
+    pub fn meshex_flags(&self) -> EntryFlags {
+        self.meshex_flags
+    }
+
+    /// Sets the rendering flags for this mesh.
+    /// Meshes with `draw_model` disabled are hidden even if [RenderMesh::is_visible] is `true`.
+    ///
+    /// Save the changes with [RenderModel::meshex].
+    pub fn set_meshex_flags(&mut self, flags: EntryFlags) {
+        self.meshex_flags = flags;
+    }
+
+    /// Returns `false` if this mesh is hidden by the [LodMode] from [RenderModel::update_lod].
+    pub fn is_lod_visible(&self) -> bool {
+        !self.lod_hidden
+    }
+
+    /// The sort bias from the numshb for ordering blended meshes within the same render pass.
+    /// Blended meshes with a lower sort bias are drawn first for [DrawOrder::Sorted](crate::DrawOrder::Sorted).
+    /// Meshes with the same sort bias are drawn in the same order as the numshb.
+    pub fn sort_bias(&self) -> i32 {
+        self.sort_bias
+    }
+
+    /// Overrides the sort bias to preview draw order changes without reloading the model.
+    pub fn set_sort_bias(&mut self, sort_bias: i32) {
+        self.sort_bias = sort_bias;
+    }
+
+    /// The material label from the numdlb or `""` if the mesh has no assigned material.
+    pub fn material_label(&self) -> &str {
+        &self.material_label
+    }
+
+    /// The shader label from the assigned matl entry like `"SFX_PBS_0100000008008269_opaque"`
+    /// or `""` if the mesh has no assigned material.
+    pub fn shader_label(&self) -> &str {
+        &self.shader_label
+    }
+}
+
+/// Criteria for selecting multiple meshes at once with [RenderModel::set_meshes_selected].
+#[derive(Debug, Clone, PartialEq)]
+pub enum MeshSelector {
+    /// All meshes in the model.
+    All,
+    /// Meshes assigned to the material with this material label.
+    Material(String),
+    /// Meshes with names matching a pattern where `*` matches any characters like `"*_VIS_O_*"`.
+    Name(String),
+    /// Meshes using a shader label with or without the render pass tag
+    /// like `"SFX_PBS_0100000008008269"` or `"SFX_PBS_0100000008008269_opaque"`.
+    Shader(String),
+}
+
+impl MeshSelector {
+    /// Returns `true` if `mesh` meets the selection criteria.
+    pub fn matches(&self, mesh: &RenderMesh) -> bool {
+        self.matches_labels(&mesh.name, &mesh.material_label, &mesh.shader_label)
+    }
+
+    fn matches_labels(&self, name: &str, material_label: &str, shader_label: &str) -> bool {
+        match self {
+            MeshSelector::All => true,
+            MeshSelector::Material(label) => material_label == label,
+            MeshSelector::Name(pattern) => matches_pattern(name, pattern),
+            MeshSelector::Shader(label) => shader_label_matches(shader_label, label),
+        }
+    }
+}
+
+// The length of a shader label without the render pass tag like "SFX_PBS_0101000008018278".
+const SHADER_LABEL_PREFIX_LEN: usize = 24;
+
+// Check for the same shader label with or without the render pass tag.
+fn shader_label_matches(shader_label: &str, label: &str) -> bool {
+    shader_label == label
+        || (!label.is_empty() && shader_label.get(..SHADER_LABEL_PREFIX_LEN) == Some(label))
+}
+
+// A second set of materials for comparing materials on the same geometry.
+struct ComparisonMaterials {
+    material_data_by_label: HashMap<String, Material>,
+    // The pipeline for each mesh in the same order as the meshes.
+    pipeline_keys: Vec<PipelineKey>,
 }
 
 struct BoneRenderData {
+    bone_colors: wgpu::Buffer,
     joint_world_transforms: wgpu::Buffer,
+    bone_visibility: wgpu::Buffer,
     bone_data: crate::shader::skeleton::bind_groups::BindGroup1,
     joint_data: crate::shader::skeleton::bind_groups::BindGroup1,
-    // TODO: Use instancing instead?
-    bone_bind_groups: Vec<crate::shader::skeleton::bind_groups::BindGroup2>,
+    // Each bone is drawn as an instance to avoid a draw call per bone.
+    bone_count: u32,
 }
 
 impl RenderModel {
@@ -116,6 +476,73 @@ impl RenderModel {
         shared_data.to_render_model(device, queue)
     }
 
+    /// Creates a model like [RenderModel::from_folder] using the modl with `modl_name` like `"model_paint.numdlb"`.
+    ///
+    /// Some stages have additional modl files for effects like ink with their own meshes and materials.
+    /// The mesh, skel, and matl are found using the file names in the modl.
+    /// The meshex and adj files use the same name as the mesh like `"model_paint.numshexb"`.
+    /// See [ModelFolder::modl_names] for the available modls.
+    pub fn from_folder_modl(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        model: &ModelFolder,
+        modl_name: &str,
+        shared_data: &SharedRenderData,
+    ) -> Self {
+        info!("Creating render model for {}.", modl_name);
+        let modl = model.find_modl_by_name(modl_name);
+
+        // Fall back to the default file names if the modl is missing.
+        let mesh_name = modl
+            .map(|m| m.mesh_file_name.as_str())
+            .unwrap_or("model.numshb");
+        let skel_name = modl
+            .map(|m| m.skeleton_file_name.as_str())
+            .unwrap_or("model.nusktb");
+        let matl_name = modl
+            .and_then(|m| m.material_file_names.first())
+            .map(|m| m.as_str())
+            .unwrap_or("model.numatb");
+        let mesh_stem = mesh_name.strip_suffix(".numshb").unwrap_or(mesh_name);
+
+        let shared_data = RenderMeshSharedData {
+            mesh: find_file(&model.meshes, mesh_name),
+            meshex: find_file(&model.meshexes, &format!("{mesh_stem}.numshexb")),
+            modl,
+            skel: find_file(&model.skels, skel_name),
+            matl: find_file(&model.matls, matl_name),
+            adj: find_file(&model.adjs, &format!("{mesh_stem}.adjb")),
+            hlpb: model.find_hlpb(),
+            model_xmb: model.find_model_xmb(),
+            nutexbs: &model.nutexbs,
+            shared_data,
+        };
+
+        shared_data.to_render_model(device, queue)
+    }
+
+    /// Creates a model like [RenderModel::from_folder] and collects any items that failed to load.
+    ///
+    /// Invalid files, textures, and meshes are skipped instead of panicking.
+    /// This is useful for batch processing many folders where some files may be malformed.
+    pub fn from_folder_with_diagnostics(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        model: &ModelFolder,
+        shared_data: &SharedRenderData,
+    ) -> (Self, LoadDiagnostics) {
+        let render_model = Self::from_folder(device, queue, model, shared_data);
+
+        let diagnostics = LoadDiagnostics {
+            files: model.file_errors(),
+            textures: render_model.texture_diagnostics.clone(),
+            meshes: render_model.mesh_diagnostics.clone(),
+            texture_fallbacks: render_model.texture_fallbacks(),
+        };
+
+        (render_model, diagnostics)
+    }
+
     /// Finds the texture with the given `file_name`.
     pub fn get_texture(
         &self,
@@ -124,7 +551,184 @@ impl RenderModel {
         self.textures
             .iter()
             .find(|(f, _, _)| f == file_name)
-            .map(|(_, t, d)| (t, d))
+            .map(|(_, t, d)| (t.as_ref(), d))
+    }
+}
+
+impl RenderModel {
+    /// Calculates the combined bounds of all the visible meshes.
+    /// Meshes hidden by [RenderMesh::is_visible], the numshexb, or the current [LodMode] are ignored.
+    ///
+    /// If `animated` is `true`, the bounds account for the current pose from [RenderModel::apply_anims].
+    /// Animated bounds are conservative and may be larger than the actual skinned geometry.
+    pub fn bounding_box(&self, animated: bool) -> Option<BoundingBox> {
+        self.meshes
+            .iter()
+            .filter(|m| m.is_visible && m.meshex_flags.draw_model && !m.lod_hidden)
+            .filter_map(|m| {
+                if animated {
+                    self.animated_bounding_box(m)
+                } else {
+                    m.bounding_box
+                }
+            })
+            .reduce(|a, b| a.union(&b))
+            .map(|b| b.transform(self.world_transform))
+    }
+
+    /// Reads the output of the skinning compute shader for all mesh objects named `mesh_name`
+    /// sorted by subindex.
+    ///
+    /// The vertices reflect the pose from the last time the model was rendered
+    /// after calling [RenderModel::apply_anims], including renormal adjustments to normals.
+    /// This blocks until the GPU finishes copying the data.
+    /// Exporters can use this to bake the animated pose or compute exact animated bounds.
+    pub fn read_skinned_positions(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        mesh_name: &str,
+    ) -> Result<Vec<SkinnedVertices>, SsbhWgpuError> {
+        let mut meshes: Vec<_> = self.meshes.iter().filter(|m| m.name == mesh_name).collect();
+        if meshes.is_empty() {
+            return Err(SsbhWgpuError::MeshName(mesh_name.to_string()));
+        }
+        meshes.sort_by_key(|m| m.subindex);
+
+        let vertex_size = std::mem::size_of::<crate::shader::skinning::VertexInput0>() as u64;
+        let sizes: Vec<_> = meshes
+            .iter()
+            .map(|m| m.vertex_count as u64 * vertex_size)
+            .collect();
+
+        // Mapping an empty buffer is a validation error.
+        let total_size = sizes.iter().sum();
+        if total_size == 0 {
+            return Ok(meshes
+                .iter()
+                .map(|mesh| SkinnedVertices {
+                    name: mesh.name.clone(),
+                    subindex: mesh.subindex,
+                    positions: Vec::new(),
+                    normals: Vec::new(),
+                })
+                .collect());
+        }
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Skinned Vertex Readback Buffer"),
+            size: total_size,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Skinned Vertex Readback Encoder"),
+        });
+        let mut offset = 0;
+        for (mesh, size) in meshes.iter().zip(&sizes) {
+            encoder.copy_buffer_to_buffer(
+                &self.mesh_buffers.vertex_buffer0,
+                mesh.access.buffer0.start(),
+                &buffer,
+                offset,
+                *size,
+            );
+            offset += size;
+        }
+        queue.submit([encoder.finish()]);
+
+        let slice = buffer.slice(..);
+        map_slice(device, &slice)?;
+
+        let vertices = {
+            let data = slice.get_mapped_range();
+            // The mapped data may not be aligned for the vertex type.
+            let vertices: Vec<crate::shader::skinning::VertexInput0> = data
+                .chunks_exact(vertex_size as usize)
+                .map(bytemuck::pod_read_unaligned)
+                .collect();
+
+            let mut start = 0;
+            meshes
+                .iter()
+                .map(|mesh| {
+                    let mesh_vertices = &vertices[start..start + mesh.vertex_count];
+                    start += mesh.vertex_count;
+                    SkinnedVertices {
+                        name: mesh.name.clone(),
+                        subindex: mesh.subindex,
+                        positions: mesh_vertices
+                            .iter()
+                            .map(|v| v.position0.truncate().to_array())
+                            .collect(),
+                        normals: mesh_vertices
+                            .iter()
+                            .map(|v| v.normal0.truncate().to_array())
+                            .collect(),
+                    }
+                })
+                .collect()
+        };
+        buffer.unmap();
+
+        Ok(vertices)
+    }
+
+    pub(crate) fn culling_sphere(&self, mesh: &RenderMesh) -> Option<BoundingSphere> {
+        // Only meshes affected by bones need to account for the current pose.
+        let sphere = if mesh.parent_bone_index.is_none() && mesh.influence_bone_indices.is_empty() {
+            mesh.bounding_sphere
+        } else {
+            self.animated_bounding_box(mesh)
+                .map(|b| b.bounding_sphere())
+        };
+        sphere.map(|s| s.transform(self.world_transform))
+    }
+
+    // Vertex positions with the current pose for CPU operations like picking.
+    pub(crate) fn animated_positions(&self, mesh: &RenderMesh) -> Vec<glam::Vec3> {
+        let parent_transform = mesh
+            .parent_bone_index
+            .and_then(|i| self.animation_transforms.world_transforms.get(i))
+            .copied();
+
+        let mut positions = skin_positions(
+            &mesh.picking,
+            parent_transform,
+            &self
+                .animation_transforms
+                .animated_world_transforms
+                .transforms,
+        );
+        for position in &mut positions {
+            *position = self.world_transform.transform_point3(*position);
+        }
+        positions
+    }
+
+    fn animated_bounding_box(&self, mesh: &RenderMesh) -> Option<BoundingBox> {
+        let local = mesh.local_bounding_box?;
+
+        let parent_transform = mesh
+            .parent_bone_index
+            .and_then(|i| self.animation_transforms.world_transforms.get(i))
+            .copied()
+            .unwrap_or(glam::Mat4::IDENTITY);
+        let bounds = local.transform(parent_transform);
+
+        // Skinned positions are a weighted average of the positions transformed by each bone.
+        // The union of the transformed boxes contains any weighted average of the transformed points.
+        let skinning_transforms = &self
+            .animation_transforms
+            .animated_world_transforms
+            .transforms;
+        Some(
+            mesh.influence_bone_indices
+                .iter()
+                .filter_map(|i| skinning_transforms.get(*i))
+                .fold(bounds, |b, t| b.union(&local.transform(*t))),
+        )
     }
 }
 
@@ -153,6 +757,56 @@ impl RenderModel {
         }
     }
 
+    /// Recreates the pipelines for all meshes using the current settings from `shared_data`.
+    ///
+    /// This is required for existing models after calling [SharedRenderData::set_sample_count],
+    /// [SharedRenderData::set_alpha_to_coverage], or changing custom shaders
+    /// with [SharedRenderData::register_custom_shader].
+    pub fn recreate_pipelines(&mut self, device: &wgpu::Device, shared_data: &SharedRenderData) {
+        // Custom shaders may have been added or removed since creating the keys.
+        for mesh in &mut self.meshes {
+            mesh.pipeline_key = mesh.pipeline_key.with_custom_shader(
+                shared_data
+                    .pipeline_data
+                    .custom_shader_id(&mesh.shader_label),
+            );
+        }
+        if let Some(comparison) = &mut self.comparison_materials {
+            // Comparison materials may use a different shader label than the mesh.
+            for (key, mesh) in comparison.pipeline_keys.iter_mut().zip(&self.meshes) {
+                let custom_shader = comparison
+                    .material_data_by_label
+                    .get(&mesh.material_label)
+                    .and_then(|m| m.entry.as_ref())
+                    .and_then(|e| shared_data.pipeline_data.custom_shader_id(&e.shader_label));
+                *key = key.with_custom_shader(custom_shader);
+            }
+        }
+
+        let keys: HashSet<_> = self
+            .meshes
+            .iter()
+            .map(|m| m.pipeline_key)
+            .chain(
+                self.comparison_materials
+                    .iter()
+                    .flat_map(|c| c.pipeline_keys.iter().copied()),
+            )
+            .collect();
+        self.pipelines = keys
+            .into_iter()
+            .map(|key| (key, pipeline(device, &shared_data.pipeline_data, &key)))
+            .collect();
+        self.sample_count = shared_data.sample_count();
+    }
+
+    /// The MSAA sample count of the model's pipelines from [SharedRenderData::sample_count].
+    ///
+    /// This must match [SsbhRenderer::sample_count](crate::SsbhRenderer::sample_count) when rendering.
+    pub fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
     /// Recreates the material render data from `materials`.
     ///
     /// This updates all material data, including texture assignments and pipeline changes like blending modes.
@@ -177,7 +831,9 @@ impl RenderModel {
                     .iter_mut()
                     .filter(|m| m.material_label == material.material_label)
                 {
-                    let pipeline_key = mesh.pipeline_key.with_material(Some(material));
+                    let pipeline_key = mesh
+                        .pipeline_key
+                        .with_material(Some(material), &shared_data.pipeline_data);
                     self.pipelines.entry(pipeline_key).or_insert_with(|| {
                         pipeline(device, &shared_data.pipeline_data, &pipeline_key)
                     });
@@ -198,46 +854,650 @@ impl RenderModel {
             .collect();
     }
 
-    /// Apply skeletal and material animations for this model.
-    ///
-    /// If `should_loop` is true, `frame` values less than `0.0`
-    /// or greater than the max frame count for each animation will wrap around.
-    pub fn apply_anims<'a>(
+    /// Replaces the CustomVector, CustomFloat, and CustomBoolean values for the material with `material_label`
+    /// without recreating any GPU resources.
+    ///
+    /// This is fast enough to call every frame for interactive editing like dragging a slider.
+    /// Values are kept until cleared with [RenderModel::clear_material_values] or
+    /// until the materials are recreated with [RenderModel::recreate_materials].
+    /// Values also take priority over any material animations.
+    pub fn update_material_values(
+        &mut self,
+        queue: &wgpu::Queue,
+        material_label: &str,
+        values: &[MaterialValue],
+    ) {
+        if let Some(material) = self.material_data_by_label.get_mut(material_label) {
+            for value in values {
+                material
+                    .overrides
+                    .retain(|v| v.param_id() != value.param_id());
+                material.overrides.push(*value);
+            }
+            material.write_uniforms(queue);
+        }
+    }
+
+    /// Removes any values set by [RenderModel::update_material_values] for the material with `material_label`.
+    pub fn clear_material_values(&mut self, queue: &wgpu::Queue, material_label: &str) {
+        if let Some(material) = self.material_data_by_label.get_mut(material_label) {
+            material.overrides.clear();
+            material.write_uniforms(queue);
+        }
+    }
+
+    /// Replaces the texture with `file_name` like `"def_mario_001_col.nutexb"` with `nutexb`
+    /// and only updates the materials that use the texture.
+    ///
+    /// This is much faster than [RenderModel::recreate_materials] for editing a single texture.
+    /// Material values and overrides are preserved.
+    /// Textures not already in the model are added for any materials that reference them.
+    /// Invalid nutexb files return an error and leave the textures unchanged.
+    pub fn replace_texture(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        file_name: &str,
+        nutexb: &NutexbFile,
+        shared_data: &SharedRenderData,
+    ) -> Result<(), SsbhWgpuError> {
+        let (texture, dimension) = nutexb_wgpu::create_texture(nutexb, device, queue)?;
+        self.set_texture(file_name, texture, dimension);
+
+        // Bind groups for other materials don't reference the texture.
+        let mut sampler_by_data = SamplerCache::new();
+        let materials = self.material_data_by_label.values_mut().chain(
+            self.comparison_materials
+                .iter_mut()
+                .flat_map(|c| c.material_data_by_label.values_mut()),
+        );
+        for material in materials.filter(|m| m.uses_texture(file_name)) {
+            material.recreate_bind_group(device, &self.textures, shared_data, &mut sampler_by_data);
+        }
+
+        Ok(())
+    }
+
+    // Materials need to be recreated to use the new texture.
+    // Names match like material texture paths, so "A.nutexb" also replaces "a.nutexb".
+    pub(crate) fn set_texture(
+        &mut self,
+        name: &str,
+        texture: wgpu::Texture,
+        dimension: wgpu::TextureViewDimension,
+    ) {
+        // Edited textures aren't shared with other models.
+        let texture = Arc::new(texture);
+        match self
+            .textures
+            .iter_mut()
+            .find(|(n, _, _)| is_same_texture(n, name))
+        {
+            Some(t) => *t = (name.to_owned(), texture, dimension),
+            None => self.textures.push((name.to_owned(), texture, dimension)),
+        }
+    }
+
+    /// Lists problems with the meshes in this model like missing attributes, materials, or bones.
+    ///
+    /// Meshes with errors still render but use fallback shading or ignore the invalid data.
+    /// Mesh objects that were skipped entirely are in [RenderModel::mesh_diagnostics].
+    pub fn validation_errors(&self, shader_database: &ShaderDatabase) -> Vec<MeshValidationError> {
+        self.meshes
+            .iter()
+            .flat_map(|mesh| {
+                let mut kinds = Vec::new();
+                // Meshes without materials are skipped before checking the shader.
+                if !self
+                    .material_data_by_label
+                    .contains_key(&mesh.material_label)
+                {
+                    kinds.push(MeshValidationErrorKind::MissingMaterial {
+                        material_label: mesh.material_label.clone(),
+                    });
+                } else if let Some(program) = shader_database.get(&mesh.shader_label) {
+                    let attributes = program.missing_required_attributes(&mesh.attribute_names);
+                    if !attributes.is_empty() {
+                        kinds.push(MeshValidationErrorKind::MissingAttributes {
+                            shader_label: mesh.shader_label.clone(),
+                            attributes,
+                        });
+                    }
+                } else {
+                    kinds.push(MeshValidationErrorKind::UnrecognizedShader {
+                        shader_label: mesh.shader_label.clone(),
+                    });
+                }
+                kinds.extend(mesh.influence_errors.iter().cloned());
+
+                kinds.into_iter().map(|kind| MeshValidationError {
+                    name: mesh.name.clone(),
+                    subindex: mesh.subindex,
+                    kind,
+                })
+            })
+            .collect()
+    }
+
+    /// Creates a numshexb for the mesh objects in `mesh` using the current [RenderMesh::meshex_flags].
+    ///
+    /// The `mesh` should be the numshb for this model after any edits like deleting mesh objects.
+    /// Mesh objects without a corresponding [RenderMesh] use the default flags.
+    pub fn meshex(&self, mesh: &MeshData) -> MeshExData {
+        let mut meshex = MeshExData::from_mesh_objects(&mesh.objects);
+        for group in &mut meshex.mesh_object_groups {
+            // Entries use the same order as the subindices.
+            for (subindex, flags) in group.entry_flags.iter_mut().enumerate() {
+                if let Some(render_mesh) = self.meshes.iter().find(|m| {
+                    m.name == group.mesh_object_full_name && m.subindex == subindex as u64
+                }) {
+                    *flags = render_mesh.meshex_flags;
+                }
+            }
+        }
+        meshex
+    }
+
+    /// The mesh objects that were skipped when creating the meshes for this model.
+    pub fn mesh_diagnostics(&self) -> &[MeshDiagnostic] {
+        &self.mesh_diagnostics
+    }
+
+    /// The matl texture assignments for the current materials that were replaced with default textures
+    /// sorted by material label.
+    ///
+    /// Fallbacks are updated when recreating materials, so this can be checked after editing the matl.
+    pub fn texture_fallbacks(&self) -> Vec<TextureFallback> {
+        let mut fallbacks: Vec<_> = self
+            .material_data_by_label
+            .values()
+            .flat_map(|m| m.texture_fallbacks.iter().cloned())
+            .collect();
+        // Fallbacks for each material are already in texture slot order.
+        fallbacks.sort_by(|a, b| a.material_label.cmp(&b.material_label));
+        fallbacks
+    }
+
+    /// Recreates the vertex and skinning data from `mesh` and `skel`
+    /// while keeping the existing materials, textures, and animated pose.
+    ///
+    /// Meshes with the same name and subindex as an existing mesh keep their material label, visibility, and selection.
+    /// New meshes are not drawn until assigned a material with [RenderModel::reassign_materials].
+    /// The `adj` and `hlpb` are used for recalculating normals and helper bone colors like in [RenderModel::from_folder].
+    /// This is faster than [RenderModel::from_folder] for iteratively editing and reimporting a mesh.
+    pub fn replace_geometry(
+        &mut self,
+        device: &wgpu::Device,
+        mesh: &MeshData,
+        skel: &SkelData,
+        adj: Option<&AdjData>,
+        hlpb: Option<&HlpbData>,
+        shared_data: &SharedRenderData,
+    ) {
+        let start = std::time::Instant::now();
+
+        // The skinning buffer is sized for the previous skel.
+        if skinning_buffer_bone_count(skel.bones.len()) * std::mem::size_of::<SkinningTransform>()
+            > self.transforms.skinning_transforms.size() as usize
+        {
+            self.transforms.skinning_transforms = skinning_transforms_buffer(
+                device,
+                &self.animation_transforms.animated_world_transforms,
+                skel.bones.len(),
+            );
+        }
+
+        // Materials and textures are reused, so only the geometry needs to be created.
+        let nutexbs = Vec::new();
+        let mesh_shared_data = RenderMeshSharedData {
+            mesh: Some(mesh),
+            meshex: None,
+            modl: None,
+            skel: Some(skel),
+            matl: None,
+            adj,
+            hlpb,
+            model_xmb: None,
+            nutexbs: &nutexbs,
+            shared_data,
+        };
+
+        let GeometryData {
+            mut meshes,
+            pipelines,
+            buffer_data,
+            skinning_vertices_bind_group,
+            renormal_vertices_bind_group,
+            skinning_transforms_bind_group,
+            mesh_object_info_bind_group,
+            mesh_diagnostics,
+        } = mesh_shared_data.create_geometry(device, &self.transforms);
+
+        for (key, pipeline) in pipelines {
+            self.pipelines.entry(key).or_insert(pipeline);
+        }
+
+        for new_mesh in &mut meshes {
+            if let Some(old_mesh) = self
+                .meshes
+                .iter()
+                .find(|m| m.name == new_mesh.name && m.subindex == new_mesh.subindex)
+            {
+                new_mesh.material_label = old_mesh.material_label.clone();
+                new_mesh.shader_label = old_mesh.shader_label.clone();
+                new_mesh.is_visible = old_mesh.is_visible;
+                new_mesh.is_selected = old_mesh.is_selected;
+                new_mesh.meshex_flags = old_mesh.meshex_flags;
+                new_mesh.lod_hidden = old_mesh.lod_hidden;
+
+                // Keep the material's pipeline state with the depth settings from the new mesh.
+                if let Some(mesh_object) = mesh
+                    .objects
+                    .iter()
+                    .find(|o| o.name == new_mesh.name && o.subindex == new_mesh.subindex)
+                {
+                    let pipeline_key = old_mesh.pipeline_key.with_depth(
+                        mesh_object.disable_depth_write,
+                        mesh_object.disable_depth_test,
+                    );
+                    self.pipelines.entry(pipeline_key).or_insert_with(|| {
+                        pipeline(device, &shared_data.pipeline_data, &pipeline_key)
+                    });
+                    new_mesh.pipeline_key = pipeline_key;
+                }
+            }
+        }
+
+        self.meshes = meshes;
+        self.mesh_buffers = buffer_data;
+        self.skinning_vertices_bind_group = skinning_vertices_bind_group;
+        self.renormal_vertices_bind_group = renormal_vertices_bind_group;
+        self.skinning_transforms_bind_group = skinning_transforms_bind_group;
+        self.mesh_object_info_bind_group = mesh_object_info_bind_group;
+        self.mesh_diagnostics = mesh_diagnostics;
+
+        self.bone_names = skel.bones.iter().map(|b| b.name.clone()).collect();
+        self.bone_parent_indices = skel.bones.iter().map(|b| b.parent_index).collect();
+        self.bone_render_data = mesh_shared_data.create_bone_render_data(
+            device,
+            &self.animation_transforms,
+            &self.transforms,
+        );
+
+        info!(
+            "Replaced geometry for {:?} render meshe(s): {:?}",
+            self.meshes.len(),
+            start.elapsed()
+        );
+    }
+
+    /// Creates a second set of materials from `materials` for comparisons
+    /// or removes the comparison materials if `None`.
+    ///
+    /// The comparison materials use the same textures and mesh material assignments as the regular materials.
+    /// See [SsbhRenderer::set_material_comparison](crate::SsbhRenderer::set_material_comparison)
+    /// for ways to compare the materials.
+    /// Avoid calling this every frame since creating new GPU resources is slow.
+    pub fn recreate_comparison_materials(
+        &mut self,
+        device: &wgpu::Device,
+        materials: Option<&[MatlEntryData]>,
+        shared_data: &SharedRenderData,
+    ) {
+        self.comparison_materials = materials.map(|materials| {
+            let pipeline_keys = self
+                .meshes
+                .iter()
+                .map(|mesh| {
+                    let material = materials
+                        .iter()
+                        .find(|m| m.material_label == mesh.material_label);
+                    let pipeline_key = mesh
+                        .pipeline_key
+                        .with_material(material, &shared_data.pipeline_data);
+                    self.pipelines.entry(pipeline_key).or_insert_with(|| {
+                        pipeline(device, &shared_data.pipeline_data, &pipeline_key)
+                    });
+                    pipeline_key
+                })
+                .collect();
+
+            let mut sampler_by_data = SamplerCache::new();
+            let material_data_by_label = materials
+                .iter()
+                .map(|material| {
+                    let data = material_data(
+                        device,
+                        material,
+                        &self.textures,
+                        shared_data,
+                        &mut sampler_by_data,
+                    );
+                    (material.material_label.clone(), data)
+                })
+                .collect();
+
+            ComparisonMaterials {
+                material_data_by_label,
+                pipeline_keys,
+            }
+        });
+    }
+
+    /// Apply skeletal and material animations for this model.
+    ///
+    /// If `should_loop` is true, `frame` values less than `0.0`
+    /// or greater than the max frame count for each animation will wrap around.
+    /// The `hlpb` constraints are always applied even if `anims` is empty.
+    ///
+    /// The skinning buffer is sized for the skel used to create the model.
+    /// Use [RenderModel::replace_geometry] to resize the buffer when changing to a `skel` with more bones.
+    pub fn apply_anims<'a>(
+        &mut self,
+        queue: &wgpu::Queue,
+        anims: impl Iterator<Item = &'a AnimData> + Clone,
+        skel: Option<&SkelData>,
+        matl: Option<&MatlData>,
+        hlpb: Option<&HlpbData>,
+        shared_data: &SharedRenderData,
+        current_frame: f32,
+    ) {
+        self.apply_anim_layers(
+            queue,
+            anims.map(|a| (a, AnimLayerMode::Override)),
+            skel,
+            matl,
+            hlpb,
+            shared_data,
+            current_frame,
+        );
+    }
+
+    /// Apply skeletal and material animations for this model with each layer combined using its [AnimLayerMode].
+    ///
+    /// This allows layering partial animations like facial expressions on top of a body animation.
+    /// Visibility and material animations always override the values from previous layers.
+    /// See [RenderModel::apply_anims] for details.
+    pub fn apply_anim_layers<'a>(
+        &mut self,
+        queue: &wgpu::Queue,
+        layers: impl Iterator<Item = (&'a AnimData, AnimLayerMode)> + Clone,
+        skel: Option<&SkelData>,
+        matl: Option<&MatlData>,
+        hlpb: Option<&HlpbData>,
+        shared_data: &SharedRenderData,
+        current_frame: f32,
+    ) {
+        // Update the buffers associated with each skel.
+        // This avoids updating per mesh object and allocating new buffers.
+        let start = std::time::Instant::now();
+        let mut buffer_writes = std::time::Duration::ZERO;
+
+        // TODO: Restructure this to iterate the animations only once?
+        for (anim, _) in layers.clone() {
+            // Assume final_frame_index is set to the length of the longest track.
+            animate_visibility(anim, current_frame, &mut self.meshes);
+
+            if let Some(matl) = matl {
+                self.update_material_uniforms(anim, current_frame, matl, shared_data, queue);
+            }
+        }
+
+        buffer_writes += self.animate_skeleton(queue, layers, skel, hlpb, current_frame);
+
+        let elapsed = start.elapsed();
+        debug!(
+            "Apply Anim: {:?}, Buffer Writes: {:?}",
+            elapsed, buffer_writes
+        );
+
+        #[cfg(feature = "frame_stats")]
+        {
+            self.animation_stats = crate::frame_stats::AnimationStats {
+                animation: elapsed.saturating_sub(buffer_writes),
+                buffer_writes,
+            };
+        }
+    }
+
+    /// Apply skeletal and material animations blended between `anim_a` at `frame_a` and `anim_b` at `frame_b`.
+    ///
+    /// A `blend_factor` of `0.0` uses only `anim_a`, and a `blend_factor` of `1.0` uses only `anim_b`.
+    /// Gradually increasing the `blend_factor` previews transitions between animations like idle to walk.
+    /// Visibility and boolean material values can't be blended
+    /// and use the values from the more heavily weighted animation.
+    #[allow(clippy::too_many_arguments)]
+    pub fn apply_anims_blended(
+        &mut self,
+        queue: &wgpu::Queue,
+        anim_a: &AnimData,
+        frame_a: f32,
+        anim_b: &AnimData,
+        frame_b: f32,
+        blend_factor: f32,
+        skel: Option<&SkelData>,
+        matl: Option<&MatlData>,
+        hlpb: Option<&HlpbData>,
+        shared_data: &SharedRenderData,
+    ) {
+        let blend_factor = blend_factor.clamp(0.0, 1.0);
+
+        if blend_factor < 0.5 {
+            animate_visibility(anim_a, frame_a, &mut self.meshes);
+        } else {
+            animate_visibility(anim_b, frame_b, &mut self.meshes);
+        }
+
+        if let Some(matl) = matl {
+            let materials = blend_materials(
+                &animate_materials(anim_a, frame_a, &matl.entries),
+                &animate_materials(anim_b, frame_b, &matl.entries),
+                blend_factor,
+            );
+            self.update_materials(queue, &materials, shared_data);
+        }
+
+        if let Some(skel) = skel {
+            animate_skel_blended(
+                &mut self.animation_transforms,
+                skel,
+                anim_a,
+                frame_a,
+                anim_b,
+                frame_b,
+                blend_factor,
+                hlpb,
+            );
+        }
+        self.write_animation_transforms(queue, skel);
+    }
+
+    /// Resets the skeleton to the pose set by [RenderModel::rest_pose].
+    ///
+    /// The `hlpb` is only used for [RestPose::Constrained].
+    /// This does not reset material animations or mesh visibility.
+    pub fn apply_rest_pose(
+        &mut self,
+        queue: &wgpu::Queue,
+        skel: Option<&SkelData>,
+        hlpb: Option<&HlpbData>,
+    ) {
+        let hlpb = match self.rest_pose {
+            RestPose::Skel => None,
+            RestPose::Constrained => hlpb,
+        };
+        self.animate_skeleton(queue, std::iter::empty(), skel, hlpb, 0.0);
+    }
+
+    /// Places the model in the scene with `transform` applied after any skeletal animations.
+    ///
+    /// This affects meshes, bones, swing collisions, bounds, and picking.
+    /// This allows rendering multiple models at different positions like a fighter on a stage.
+    pub fn set_world_transform(&mut self, queue: &wgpu::Queue, transform: glam::Mat4) {
+        self.world_transform = transform;
+        queue.write_data(
+            &self.transforms.model_transform,
+            &[mesh_creation::model_transform(transform)],
+        );
+    }
+
+    /// The transform from [RenderModel::set_world_transform] or the identity matrix if not set.
+    pub fn world_transform(&self) -> glam::Mat4 {
+        self.world_transform
+    }
+
+    /// Hides the mesh LOD variants like `"body_LOD1"` not selected by [RenderModel::lod_mode].
+    ///
+    /// [LodMode::Auto] depends on the `camera` and should be updated whenever the camera changes.
+    /// Other modes only need to be updated after changing [RenderModel::lod_mode].
+    pub fn update_lod(&mut self, camera: &CameraTransforms) {
+        self.apply_lod(Some(camera));
+    }
+
+    pub(crate) fn apply_lod(&mut self, camera: Option<&CameraTransforms>) {
+        // Group LOD variants using the name without the LOD tag.
+        let mut groups: HashMap<String, Vec<(usize, usize)>> = HashMap::new();
+        for (i, mesh) in self.meshes.iter().enumerate() {
+            let (name, level) = split_lod_name(&mesh.name);
+            groups.entry(name).or_default().push((i, level));
+        }
+
+        for meshes in groups.values() {
+            let levels: Vec<_> = meshes.iter().map(|(_, level)| *level).collect();
+            let target = match self.lod_mode {
+                LodMode::Auto => camera
+                    .and_then(|camera| {
+                        // Use the bounds of the highest detail mesh for the entire group.
+                        let (i, _) = meshes.iter().min_by_key(|(_, level)| *level)?;
+                        let sphere = self.culling_sphere(&self.meshes[*i])?;
+                        Some(auto_lod_level(&sphere, camera))
+                    })
+                    .unwrap_or(0),
+                LodMode::ForceLod(level) => level,
+                LodMode::HighestOnly => 0,
+            };
+            let selected = select_lod(&levels, target);
+
+            for (i, level) in meshes {
+                self.meshes[*i].lod_hidden = *level != selected;
+            }
+        }
+    }
+
+    /// The meshes assigned to the material with `material_label`.
+    ///
+    /// These are the meshes drawn for the material mask in [ModelRenderOptions::mask_material_label](crate::ModelRenderOptions::mask_material_label).
+    pub fn meshes_with_material(&self, material_label: &str) -> Vec<&RenderMesh> {
+        self.meshes
+            .iter()
+            .filter(|m| m.material_label == material_label)
+            .collect()
+    }
+
+    /// Creates a visibility group for a `.nuanmb` from the current [RenderMesh::is_visible] values.
+    ///
+    /// This allows authoring visibility animations by toggling meshes in the viewport.
+    /// The group has a single frame, so applications can combine groups for multiple frames if needed.
+    pub fn visibility_to_anim_group(&self) -> GroupData {
+        visibility_group(self.meshes.iter().map(|m| (m.name.as_str(), m.is_visible)))
+    }
+
+    /// Sets [RenderMesh::is_selected] to `is_selected` for the meshes matching `selector`
+    /// and returns the number of matching meshes.
+    /// Other meshes are unchanged, so use [MeshSelector::All] to clear the selection first.
+    ///
+    /// Selected meshes are outlined without changing the material mask.
+    pub fn set_meshes_selected(&mut self, selector: &MeshSelector, is_selected: bool) -> usize {
+        let mut count = 0;
+        for mesh in self.meshes.iter_mut().filter(|m| selector.matches(m)) {
+            mesh.is_selected = is_selected;
+            count += 1;
+        }
+        count
+    }
+
+    /// The world transform of the bone with `bone_name` in the current animated pose
+    /// with the transform from [RenderModel::set_world_transform] applied.
+    ///
+    /// This allows attaching objects like weapons or effects to bones without animating the skel again.
+    /// Returns `None` if the skel has no bone with the given name.
+    pub fn bone_world_transform(&self, bone_name: &str) -> Option<glam::Mat4> {
+        let index = self.bone_names.iter().position(|n| n == bone_name)?;
+        let transform = self.animation_transforms.world_transforms.get(index)?;
+        Some(self.world_transform * *transform)
+    }
+
+    /// Applies a single edited or new `constraint` to the current animated pose
+    /// and updates the transforms of only the target bone and its descendants.
+    ///
+    /// This gives immediate feedback when editing `hlpb` constraint parameters
+    /// without evaluating the entire animation again with [RenderModel::apply_anims].
+    /// The `skel` should be the same skel used to create and animate the model.
+    /// Returns `false` if the skel has no bone matching the constraint's target bone.
+    pub fn preview_constraint(
+        &mut self,
+        queue: &wgpu::Queue,
+        skel: &SkelData,
+        constraint: &HlpbConstraint,
+    ) -> bool {
+        if preview_constraint(&mut self.animation_transforms, skel, constraint) {
+            self.write_animation_transforms(queue, Some(skel));
+            true
+        } else {
+            false
+        }
+    }
+
+    // Returns the time spent writing buffers.
+    fn animate_skeleton<'a>(
         &mut self,
         queue: &wgpu::Queue,
-        anims: impl Iterator<Item = &'a AnimData> + Clone,
+        layers: impl Iterator<Item = (&'a AnimData, AnimLayerMode)>,
         skel: Option<&SkelData>,
-        matl: Option<&MatlData>,
         hlpb: Option<&HlpbData>,
-        shared_data: &SharedRenderData,
         current_frame: f32,
-    ) {
-        // Update the buffers associated with each skel.
-        // This avoids updating per mesh object and allocating new buffers.
-        let start = std::time::Instant::now();
-
-        // TODO: Restructure this to iterate the animations only once?
-        for anim in anims.clone() {
-            // Assume final_frame_index is set to the length of the longest track.
-            animate_visibility(anim, current_frame, &mut self.meshes);
-
-            if let Some(matl) = matl {
-                self.update_material_uniforms(anim, current_frame, matl, shared_data, queue);
-            }
-        }
-
+    ) -> std::time::Duration {
         if let Some(skel) = skel {
-            animate_skel(
+            animate_skel_layers(
                 &mut self.animation_transforms,
                 skel,
-                anims,
+                layers,
                 hlpb,
                 current_frame,
             );
+        }
+
+        self.write_animation_transforms(queue, skel)
+    }
+
+    fn write_animation_transforms(
+        &mut self,
+        queue: &wgpu::Queue,
+        skel: Option<&SkelData>,
+    ) -> std::time::Duration {
+        let mut buffer_writes = std::time::Duration::ZERO;
+
+        if let Some(skel) = skel {
+            // TODO: Avoid allocating here?
+            let joint_transforms = joint_transforms(skel, &self.animation_transforms);
 
+            let write_start = std::time::Instant::now();
+            // The skinning buffer only has space for the bones in the model's skel.
+            let bone_count = self.transforms.skinning_transforms.size() as usize
+                / std::mem::size_of::<SkinningTransform>();
+            if skel.bones.len() > bone_count {
+                error!(
+                    "Skel has {} bones but the skinning buffer only has space for {} bones. Call replace_geometry to resize the buffer.",
+                    skel.bones.len(),
+                    bone_count
+                );
+            }
             queue.write_data(
                 &self.transforms.skinning_transforms,
-                &[self.animation_transforms.animated_world_transforms],
+                &self
+                    .animation_transforms
+                    .animated_world_transforms
+                    .skinning_transforms(bone_count),
             );
 
             queue.write_data(
@@ -245,12 +1505,11 @@ impl RenderModel {
                 &self.animation_transforms.world_transforms,
             );
 
-            // TODO: Avoid allocating here?
-            let joint_transforms = joint_transforms(skel, &self.animation_transforms);
             queue.write_data(
                 &self.bone_render_data.joint_world_transforms,
                 &joint_transforms,
             );
+            buffer_writes += write_start.elapsed();
         }
 
         self.swing_render_data.animate_collisions(
@@ -259,7 +1518,7 @@ impl RenderModel {
             &self.animation_transforms.world_transforms,
         );
 
-        debug!("Apply Anim: {:?}", start.elapsed());
+        buffer_writes
     }
 
     /// Creates the data for rendering the collisions in `swing_prc`.
@@ -280,6 +1539,62 @@ impl RenderModel {
         );
     }
 
+    /// Creates the data for rendering the hitbox and hurtbox capsules in `hitboxes`.
+    /// This method should be called any time hitboxes are added, edited, or removed
+    /// like when advancing to a new frame in a move's script.
+    /// Pass an empty slice to remove all hitboxes.
+    ///
+    /// Hitboxes follow their bone automatically in [RenderModel::apply_anims].
+    pub fn recreate_hitboxes(
+        &mut self,
+        device: &wgpu::Device,
+        hitboxes: &[HitboxCapsule],
+        skel: Option<&SkelData>,
+    ) {
+        self.swing_render_data.hitboxes = HitboxData::from_hitboxes(device, hitboxes, skel);
+    }
+
+    /// Updates which bones are drawn for [ModelRenderOptions::draw_bones](crate::ModelRenderOptions::draw_bones).
+    ///
+    /// The `skel` should be the same skel used to create this model.
+    pub fn update_bone_filter(
+        &self,
+        queue: &wgpu::Queue,
+        skel: Option<&SkelData>,
+        filter: &BoneFilter,
+    ) {
+        queue.write_data(
+            &self.bone_render_data.bone_visibility,
+            &[bone_visibility(skel, filter)],
+        );
+    }
+
+    /// Draws all bones for [ModelRenderOptions::draw_bones](crate::ModelRenderOptions::draw_bones)
+    /// with `color` or the default bone colors if `None`.
+    ///
+    /// This distinguishes overlapping skeletons when comparing skeletons
+    /// with [SsbhRenderer::update_skeleton_comparison](crate::SsbhRenderer::update_skeleton_comparison).
+    /// The `skel` and `hlpb` should be the same files used to create this model.
+    pub fn update_bone_colors(
+        &self,
+        queue: &wgpu::Queue,
+        skel: Option<&SkelData>,
+        hlpb: Option<&HlpbData>,
+        color: Option<[f32; 4]>,
+    ) {
+        queue.write_data(
+            &self.bone_render_data.bone_colors,
+            &bone_colors(skel, hlpb, color),
+        );
+    }
+
+    /// The name and world position of each bone in the current animated pose.
+    pub(crate) fn bone_world_positions(&self) -> Vec<(&str, glam::Vec3)> {
+        self.bone_names_animated_world_transforms()
+            .map(|(name, transform)| (name.as_str(), transform.w_axis.truncate()))
+            .collect()
+    }
+
     fn update_material_uniforms(
         &mut self,
         anim: &AnimData,
@@ -291,11 +1606,20 @@ impl RenderModel {
         // Get a list of changed materials.
         // TODO: Avoid per frame allocations here?
         let animated_materials = animate_materials(anim, frame, &matl.entries);
-        for material in animated_materials {
+        self.update_materials(queue, &animated_materials, shared_data);
+    }
+
+    fn update_materials(
+        &mut self,
+        queue: &wgpu::Queue,
+        materials: &[MatlEntryData],
+        shared_data: &SharedRenderData,
+    ) {
+        for material in materials {
             self.material_data_by_label
                 .entry(material.material_label.clone())
                 .and_modify(|material_data| {
-                    material_data.update(queue, &material, &shared_data.database);
+                    material_data.update(queue, material, &shared_data.database);
                 });
         }
     }
@@ -379,7 +1703,6 @@ impl RenderModel {
         camera_bind_group: &'a crate::shader::skeleton::bind_groups::BindGroup0,
         skeleton_pipeline: &'a wgpu::RenderPipeline,
     ) {
-        // TODO: Instancing?
         self.draw_skel_inner(
             render_pass,
             skeleton_pipeline,
@@ -396,7 +1719,6 @@ impl RenderModel {
         camera_bind_group: &'a crate::shader::skeleton::bind_groups::BindGroup0,
         axes_pipeline: &'a wgpu::RenderPipeline,
     ) {
-        // TODO: Instancing?
         self.draw_skel_inner(
             render_pass,
             axes_pipeline,
@@ -414,17 +1736,20 @@ impl RenderModel {
         camera_bind_group: &'a crate::shader::skeleton::bind_groups::BindGroup0,
         bone_data_bind_group: &'a crate::shader::skeleton::bind_groups::BindGroup1,
     ) {
-        render_pass.set_pipeline(pipeline);
-        buffers.set(render_pass);
-
-        for bind_group2 in &self.bone_render_data.bone_bind_groups {
+        // The instance index is the bone index.
+        if self.bone_render_data.bone_count > 0 {
+            render_pass.set_pipeline(pipeline);
+            buffers.set(render_pass);
             crate::shader::skeleton::set_bind_groups(
                 render_pass,
                 camera_bind_group,
                 bone_data_bind_group,
-                bind_group2,
             );
-            render_pass.draw_indexed(0..buffers.index_count, 0, 0..1);
+            render_pass.draw_indexed(
+                0..buffers.index_count,
+                0,
+                0..self.bone_render_data.bone_count,
+            );
         }
     }
 
@@ -435,7 +1760,6 @@ impl RenderModel {
         swing_camera_bind_group: &crate::shader::swing::bind_groups::BindGroup0,
         hidden_collisions: &HashSet<u64>,
     ) {
-        // TODO: Is it noticeably more efficient to batch shapes together?
         draw_swing_collisions(
             &self.swing_render_data,
             render_pass,
@@ -445,6 +1769,20 @@ impl RenderModel {
         );
     }
 
+    pub(crate) fn draw_hitboxes(
+        &self,
+        render_pass: &mut wgpu::RenderPass<'_>,
+        swing_pipeline: &wgpu::RenderPipeline,
+        swing_camera_bind_group: &crate::shader::swing::bind_groups::BindGroup0,
+    ) {
+        draw_hitboxes(
+            &self.swing_render_data,
+            render_pass,
+            swing_pipeline,
+            swing_camera_bind_group,
+        );
+    }
+
     fn draw_mesh<'a>(
         &'a self,
         render_pass: &mut wgpu::RenderPass<'a>,
@@ -476,43 +1814,121 @@ impl RenderModel {
         invalid_shader_pipeline: &'a wgpu::RenderPipeline,
         invalid_attributes_pipeline: &'a wgpu::RenderPipeline,
         pass: &str,
-    ) {
+        sort: bool,
+        comparison: bool,
+        frustum: Option<&Frustum>,
+        oit_pipelines: Option<&OitPipelines>,
+    ) -> BindCounts {
         // TODO: How to store all data in RenderModel but still draw sorted meshes?
         // TODO: Does sort bias only effect meshes within a model or the entire pass?
         // TODO: Test in game and add test cases for sorting.
 
-        // The numshexb can disable rendering of some meshes.
-        // This allows invisible meshes to still cast shadows.
-        for mesh in self
+        // Models without comparison materials always use their regular materials.
+        let comparison = comparison
+            .then_some(self.comparison_materials.as_ref())
+            .flatten();
+        let materials = comparison
+            .map(|c| &c.material_data_by_label)
+            .unwrap_or(&self.material_data_by_label);
+
+        let mut draws: Vec<_> = self
             .meshes
             .iter()
-            .filter(|m| m.is_visible && m.shader_label.ends_with(pass) && m.meshex_flags.draw_model)
-        {
-            // Meshes with no modl entry or an entry with an invalid material label are skipped entirely in game.
-            // If the material entry is deleted from the matl, the mesh is also skipped.
-            if let Some(material_data) = self.material_data_by_label.get(&mesh.material_label) {
+            .enumerate()
+            .filter(|(_, m)| {
+                // The numshexb can disable rendering of some meshes.
+                // This allows invisible meshes to still cast shadows.
+                m.is_visible
+                    && m.shader_label.ends_with(pass)
+                    && m.meshex_flags.draw_model
+                    && !m.lod_hidden
+                    // Prevent potential validation error from empty meshes.
+                    && m.vertex_index_count > 0
+            })
+            .filter(|(_, m)| {
+                // Meshes without bounds can't be culled.
+                frustum
+                    .zip(self.culling_sphere(m))
+                    .map(|(frustum, sphere)| frustum.intersects_sphere(&sphere))
+                    .unwrap_or(true)
+            })
+            .filter_map(|(i, mesh)| {
+                // Meshes with no modl entry or an entry with an invalid material label are skipped entirely in game.
+                // If the material entry is deleted from the matl, the mesh is also skipped.
+                let material_data = materials.get(&mesh.material_label)?;
+
+                let pipeline_key = comparison
+                    .and_then(|c| c.pipeline_keys.get(i))
+                    .unwrap_or(&mesh.pipeline_key);
+
+                // These meshes are drawn separately by draw_meshes_oit.
+                if oit_pipelines
+                    .is_some_and(|p| is_oit_draw(mesh, pipeline_key, shader_database, p))
+                {
+                    return None;
+                }
+
                 // TODO: Does the invalid shader pipeline take priority?
-                if let Some(info) = shader_database.get(&mesh.shader_label) {
+                let pipeline = if let Some(info) = shader_database.get(&mesh.shader_label) {
                     if info.has_required_attributes(&mesh.attribute_names) {
                         // TODO: Don't assume the pipeline exists?
-                        render_pass.set_pipeline(&self.pipelines[&mesh.pipeline_key]);
+                        &self.pipelines[pipeline_key]
                     } else {
-                        render_pass.set_pipeline(invalid_attributes_pipeline);
+                        invalid_attributes_pipeline
                     }
                 } else {
                     // TODO: Does this include invalid tags?
-                    render_pass.set_pipeline(invalid_shader_pipeline);
-                }
+                    invalid_shader_pipeline
+                };
 
-                self.draw_mesh(
-                    render_pass,
-                    mesh,
-                    per_frame_bind_group,
-                    &self.per_model_bind_group,
-                    &material_data.material_uniforms_bind_group,
-                );
+                Some((mesh, pipeline, material_data, pipeline_key.is_blended()))
+            })
+            .collect();
+
+        // Group draws by pipeline and then material to reduce state changes.
+        // Addresses uniquely identify pipelines and materials for the duration of the pass.
+        if sort {
+            sort_draws(
+                &mut draws,
+                |(_, _, _, is_blended)| *is_blended,
+                |(mesh, _, _, _)| mesh.sort_bias,
+                |(_, pipeline, material, _)| {
+                    (
+                        *pipeline as *const wgpu::RenderPipeline as usize,
+                        *material as *const Material as usize,
+                    )
+                },
+            );
+        }
+
+        // Only the material bind group changes between meshes.
+        let mut counts = BindCounts {
+            pipelines: 0,
+            bind_groups: 2,
+        };
+        per_frame_bind_group.set(render_pass);
+        self.per_model_bind_group.set(render_pass);
+
+        let mut previous_pipeline = None;
+        let mut previous_material = None;
+        for (mesh, pipeline, material_data, _) in draws {
+            if !previous_pipeline.is_some_and(|p| std::ptr::eq(p, pipeline)) {
+                render_pass.set_pipeline(pipeline);
+                previous_pipeline = Some(pipeline);
+                counts.pipelines += 1;
             }
+
+            if !previous_material.is_some_and(|m| std::ptr::eq(m, material_data)) {
+                material_data.material_uniforms_bind_group.set(render_pass);
+                previous_material = Some(material_data);
+                counts.bind_groups += 1;
+            }
+
+            self.set_mesh_buffers(render_pass, mesh);
+            render_pass.draw_indexed(0..mesh.vertex_index_count as u32, 0, 0..1);
         }
+
+        counts
     }
 
     pub(crate) fn draw_meshes_material_mask<'a>(
@@ -527,7 +1943,7 @@ impl RenderModel {
         for mesh in self
             .meshes
             .iter()
-            .filter(|m| m.is_visible && m.material_label == material_label)
+            .filter(|m| m.is_visible && !m.lod_hidden && m.material_label == material_label)
         {
             self.draw_mesh(
                 render_pass,
@@ -539,13 +1955,96 @@ impl RenderModel {
         }
     }
 
+    /// Draws the blended meshes in the `"sort"` pass skipped by [RenderModel::draw_meshes]
+    /// with order independent transparency.
+    pub(crate) fn draw_meshes_oit<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        per_frame_bind_group: &'a crate::shader::model::bind_groups::BindGroup0,
+        shader_database: &ShaderDatabase,
+        oit_pipelines: &'a OitPipelines,
+        comparison: bool,
+        frustum: Option<&Frustum>,
+    ) -> BindCounts {
+        let comparison = comparison
+            .then_some(self.comparison_materials.as_ref())
+            .flatten();
+        let materials = comparison
+            .map(|c| &c.material_data_by_label)
+            .unwrap_or(&self.material_data_by_label);
+
+        let mut draws: Vec<_> = self
+            .meshes
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| {
+                m.is_visible
+                    && m.meshex_flags.draw_model
+                    && !m.lod_hidden
+                    && m.vertex_index_count > 0
+            })
+            .filter(|(_, m)| {
+                frustum
+                    .zip(self.culling_sphere(m))
+                    .map(|(frustum, sphere)| frustum.intersects_sphere(&sphere))
+                    .unwrap_or(true)
+            })
+            .filter_map(|(i, mesh)| {
+                let material_data = materials.get(&mesh.material_label)?;
+                let pipeline_key = comparison
+                    .and_then(|c| c.pipeline_keys.get(i))
+                    .unwrap_or(&mesh.pipeline_key);
+                if !is_oit_draw(mesh, pipeline_key, shader_database, oit_pipelines) {
+                    return None;
+                }
+                Some((mesh, oit_pipelines.get(pipeline_key)?, material_data))
+            })
+            .collect();
+
+        // Blending is order independent, so only sort to reduce state changes.
+        draws.sort_by_key(|(_, pipeline, material)| {
+            (
+                *pipeline as *const wgpu::RenderPipeline as usize,
+                *material as *const Material as usize,
+            )
+        });
+
+        let mut counts = BindCounts {
+            pipelines: 0,
+            bind_groups: 2,
+        };
+        per_frame_bind_group.set(render_pass);
+        self.per_model_bind_group.set(render_pass);
+
+        let mut previous_pipeline = None;
+        let mut previous_material = None;
+        for (mesh, pipeline, material_data) in draws {
+            if !previous_pipeline.is_some_and(|p| std::ptr::eq(p, pipeline)) {
+                render_pass.set_pipeline(pipeline);
+                previous_pipeline = Some(pipeline);
+                counts.pipelines += 1;
+            }
+
+            if !previous_material.is_some_and(|m| std::ptr::eq(m, material_data)) {
+                material_data.material_uniforms_bind_group.set(render_pass);
+                previous_material = Some(material_data);
+                counts.bind_groups += 1;
+            }
+
+            self.set_mesh_buffers(render_pass, mesh);
+            render_pass.draw_indexed(0..mesh.vertex_index_count as u32, 0, 0..1);
+        }
+
+        counts
+    }
+
     pub(crate) fn draw_meshes_debug<'a>(
         &'a self,
         render_pass: &mut wgpu::RenderPass<'a>,
         per_frame_bind_group: &'a crate::shader::model::bind_groups::BindGroup0,
     ) {
         // Assume the pipeline is already set.
-        for mesh in self.meshes.iter().filter(|m| m.is_visible) {
+        for mesh in self.meshes.iter().filter(|m| m.is_visible && !m.lod_hidden) {
             // Models should always show up in debug mode.
             let material_data = self
                 .material_data_by_label
@@ -604,6 +2103,37 @@ impl RenderModel {
         }
     }
 
+    pub(crate) fn draw_meshes_uv_layout<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        per_frame_bind_group: &'a crate::shader::model::bind_groups::BindGroup0,
+        mesh_filter: impl Fn(&RenderMesh) -> bool,
+        mesh_colors: bool,
+    ) {
+        // Assume the pipeline is already set.
+        for (i, mesh) in self
+            .meshes
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| mesh_filter(m))
+        {
+            let color = if mesh_colors {
+                uv_layout_color(i)
+            } else {
+                wgpu::Color::WHITE
+            };
+            render_pass.set_blend_constant(color);
+
+            self.draw_mesh(
+                render_pass,
+                mesh,
+                per_frame_bind_group,
+                &self.per_model_bind_group,
+                &self.default_material_data.material_uniforms_bind_group,
+            );
+        }
+    }
+
     pub(crate) fn bone_names_animated_world_transforms(
         &self,
     ) -> impl Iterator<Item = (&String, glam::Mat4)> {
@@ -614,10 +2144,36 @@ impl RenderModel {
                 .get(i)
                 .unwrap_or(&glam::Mat4::IDENTITY);
 
-            (name, transform)
+            (name, self.world_transform * transform)
         })
     }
 
+    /// Calculates the screen space regions for the bones drawn with the current animated pose.
+    ///
+    /// The `filter` should match the filter from [Self::update_bone_filter].
+    pub fn bone_hit_regions(
+        &self,
+        camera: &CameraTransforms,
+        filter: &BoneFilter,
+    ) -> Vec<BoneHitRegion> {
+        let positions: Vec<_> = self
+            .bone_names_animated_world_transforms()
+            .map(|(_, transform)| transform.w_axis.truncate())
+            .collect();
+
+        crate::bone_hit::bone_hit_regions(
+            &positions,
+            &self.bone_parent_indices,
+            &self.visible_bones(filter),
+            filter.min_screen_size,
+            camera,
+        )
+    }
+
+    pub(crate) fn visible_bones(&self, filter: &BoneFilter) -> Vec<bool> {
+        filter.visible_bones_from_hierarchy(&self.bone_names, &self.bone_parent_indices)
+    }
+
     fn set_mesh_buffers<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, mesh: &RenderMesh) {
         render_pass.set_vertex_buffer(
             0,
@@ -633,10 +2189,68 @@ impl RenderModel {
         );
     }
 
+    /// The meshes that can be drawn in a depth pre-pass and their distance from `camera_position`.
+    pub(crate) fn depth_prepass_draws(
+        &self,
+        shader_database: &ShaderDatabase,
+        frustum: Option<&Frustum>,
+        camera_position: glam::Vec3,
+    ) -> Vec<(usize, f32)> {
+        self.meshes
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| {
+                m.is_visible
+                    && m.shader_label.ends_with("opaque")
+                    && m.meshex_flags.draw_model
+                    && !m.lod_hidden
+                    && m.vertex_index_count > 0
+                    && m.pipeline_key.is_depth_prepass_compatible()
+                    && self.material_data_by_label.contains_key(&m.material_label)
+            })
+            .filter(|(_, m)| {
+                // Alpha testing depends on the textures, so only the model pass can write depth.
+                // Meshes with missing attributes use a different vertex shader.
+                shader_database
+                    .get(&m.shader_label)
+                    .map(|info| !info.discard && info.has_required_attributes(&m.attribute_names))
+                    .unwrap_or(false)
+            })
+            .filter_map(|(i, m)| {
+                // Meshes without bounds can't be culled or sorted.
+                let sphere = self.culling_sphere(m)?;
+                if frustum.is_some_and(|f| !f.intersects_sphere(&sphere)) {
+                    return None;
+                }
+                Some((i, sphere.center.distance(camera_position)))
+            })
+            .collect()
+    }
+
+    pub(crate) fn draw_mesh_depth_prepass<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        per_frame_bind_group: &'a crate::shader::model::bind_groups::BindGroup0,
+        pipelines: &'a DepthPrepassPipelines,
+        mesh_index: usize,
+    ) {
+        let mesh = &self.meshes[mesh_index];
+        if let Some(material_data) = self.material_data_by_label.get(&mesh.material_label) {
+            render_pass.set_pipeline(pipelines.get(mesh.pipeline_key.cull_mode()));
+            per_frame_bind_group.set(render_pass);
+            self.per_model_bind_group.set(render_pass);
+            material_data.material_uniforms_bind_group.set(render_pass);
+
+            self.set_mesh_buffers(render_pass, mesh);
+            render_pass.draw_indexed(0..mesh.vertex_index_count as u32, 0, 0..1);
+        }
+    }
+
     pub(crate) fn draw_meshes_depth<'a>(
         &'a self,
         render_pass: &mut wgpu::RenderPass<'a>,
         per_frame_bind_group: &'a crate::shader::model::bind_groups::BindGroup0,
+        light_index: u32,
     ) {
         // Assume only shared bind groups for all meshes.
         per_frame_bind_group.set(render_pass);
@@ -646,28 +2260,51 @@ impl RenderModel {
         for mesh in self
             .meshes
             .iter()
-            .filter(|m| m.is_visible && m.meshex_flags.cast_shadow)
+            .filter(|m| m.is_visible && m.meshex_flags.cast_shadow && !m.lod_hidden)
         {
             // Prevent potential validation error from empty meshes.
             if mesh.vertex_index_count > 0 {
                 self.set_mesh_buffers(render_pass, mesh);
 
-                render_pass.draw_indexed(0..mesh.vertex_index_count as u32, 0, 0..1);
+                // The instance index selects the light in the shader.
+                render_pass.draw_indexed(
+                    0..mesh.vertex_index_count as u32,
+                    0,
+                    light_index..light_index + 1,
+                );
             }
         }
     }
 }
 
-pub fn dispatch_renormal<'a>(meshes: &'a [RenderMesh], compute_pass: &mut wgpu::ComputePass<'a>) {
+pub fn dispatch_renormal<'a>(model: &'a RenderModel, compute_pass: &mut wgpu::ComputePass<'a>) {
+    let Some(vertices_bind_group) = &model.renormal_vertices_bind_group else {
+        return;
+    };
+
     // Assume the pipeline is already set.
     // Some meshes have a material label tag to enable the recalculating of normals.
     // This helps with animations with large deformations.
     // TODO: Is this check case sensitive?
-    for mesh in meshes
+    for mesh in model
+        .meshes
         .iter()
         .filter(|m| m.material_label.contains("RENORMAL"))
     {
-        crate::shader::renormal::set_bind_groups(compute_pass, &mesh.renormal_bind_group);
+        // Only the offsets into the combined buffers change between meshes.
+        compute_pass.set_bind_group(
+            0,
+            vertices_bind_group,
+            &[
+                mesh.access.buffer0.start() as u32,
+                mesh.access.adjacency.start() as u32,
+            ],
+        );
+        compute_pass.set_bind_group(
+            1,
+            &model.mesh_object_info_bind_group,
+            &[mesh.mesh_object_info_offset],
+        );
 
         // Round up with ceil to avoid skipping vertices.
         let [workgroup_x, _, _] = crate::shader::renormal::compute::MAIN_WORKGROUP_SIZE;
@@ -677,18 +2314,33 @@ pub fn dispatch_renormal<'a>(meshes: &'a [RenderMesh], compute_pass: &mut wgpu::
 }
 
 pub fn dispatch_skinning<'a>(
-    meshes: &'a [RenderMesh],
+    model: &'a RenderModel,
     compute_pass: &mut wgpu::ComputePass<'a>,
     bind_group3: &'a crate::shader::skinning::bind_groups::BindGroup3,
 ) {
+    let Some(vertices_bind_group) = &model.skinning_vertices_bind_group else {
+        return;
+    };
+
     // Assume the pipeline is already set.
-    for mesh in meshes {
-        crate::shader::skinning::set_bind_groups(
-            compute_pass,
-            &mesh.skinning_bind_group,
-            &mesh.skinning_transforms_bind_group,
-            &mesh.mesh_object_info_bind_group,
-            bind_group3,
+    // Only the offsets into the combined buffers change between meshes.
+    model.skinning_transforms_bind_group.set(compute_pass);
+    bind_group3.set(compute_pass);
+
+    for mesh in &model.meshes {
+        compute_pass.set_bind_group(
+            0,
+            vertices_bind_group,
+            &[
+                mesh.access.buffer0.start() as u32,
+                mesh.access.weights.start() as u32,
+                mesh.access.buffer0.start() as u32,
+            ],
+        );
+        compute_pass.set_bind_group(
+            2,
+            &model.mesh_object_info_bind_group,
+            &[mesh.mesh_object_info_offset],
         );
 
         // Round up with ceil to avoid skipping vertices.
@@ -697,3 +2349,229 @@ pub fn dispatch_skinning<'a>(
         compute_pass.dispatch_workgroups(workgroup_count, 1, 1);
     }
 }
+
+// Opaque draws can be drawn in any order due to depth testing.
+// Sort the unblended draws by key and draw the blended draws last by sort bias.
+fn sort_draws<T, K: Ord>(
+    draws: &mut [T],
+    is_blended: impl Fn(&T) -> bool,
+    sort_bias: impl Fn(&T) -> i32,
+    key: impl Fn(&T) -> K,
+) {
+    // The sort is stable, so blended draws with the same bias preserve their relative order.
+    draws.sort_by_key(|d| {
+        if is_blended(d) {
+            (true, sort_bias(d), None)
+        } else {
+            (false, 0, Some(key(d)))
+        }
+    });
+}
+
+// Blended meshes in the sort pass with valid shaders can use order independent transparency.
+fn is_oit_draw(
+    mesh: &RenderMesh,
+    pipeline_key: &PipelineKey,
+    shader_database: &ShaderDatabase,
+    oit_pipelines: &OitPipelines,
+) -> bool {
+    mesh.shader_label.ends_with("sort")
+        && shader_database
+            .get(&mesh.shader_label)
+            .is_some_and(|info| info.has_required_attributes(&mesh.attribute_names))
+        && oit_pipelines.get(pipeline_key).is_some()
+}
+
+// The fraction of the screen height covered by a mesh to use the highest detail.
+const AUTO_LOD_SCREEN_SIZE: f32 = 0.5;
+
+// Split names like "body_LOD1_VIS_O_OBJShape" into "body_VIS_O_OBJShape" and 1.
+fn split_lod_name(name: &str) -> (String, usize) {
+    if let Some(start) = name.find("_LOD") {
+        let rest = &name[start + "_LOD".len()..];
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        if let Ok(level) = rest[..digits].parse() {
+            return (format!("{}{}", &name[..start], &rest[digits..]), level);
+        }
+    }
+    (name.to_string(), 0)
+}
+
+// Each level halves the screen size of the bounding sphere.
+fn auto_lod_level(sphere: &BoundingSphere, camera: &CameraTransforms) -> usize {
+    let distance = sphere.center.distance(camera.camera_pos.truncate());
+    // The perspective projection scales by 1 / tan(fov_y / 2).
+    let screen_size = sphere.radius * camera.projection_matrix.y_axis.y / distance.max(1e-6);
+    if screen_size > 0.0 {
+        (AUTO_LOD_SCREEN_SIZE / screen_size).log2().max(0.0) as usize
+    } else {
+        usize::MAX
+    }
+}
+
+// Find the closest available level with at least as much detail as the target.
+fn select_lod(levels: &[usize], target: usize) -> usize {
+    levels
+        .iter()
+        .copied()
+        .filter(|l| *l <= target)
+        .max()
+        .or_else(|| levels.iter().copied().min())
+        .unwrap_or(0)
+}
+
+// Space hues using the golden ratio to give similar indices distinct colors.
+fn uv_layout_color(index: usize) -> wgpu::Color {
+    let hue = (index as f64 * 0.618_033_988_75).fract() * 6.0;
+    let channel = |offset: f64| {
+        // The distance around the color wheel from the channel's primary hue.
+        let x = (hue - offset).rem_euclid(6.0);
+        (2.0 - x.min(6.0 - x)).clamp(0.0, 1.0)
+    };
+    wgpu::Color {
+        r: channel(0.0),
+        g: channel(2.0),
+        b: channel(4.0),
+        a: 1.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shader_label_matches_tag() {
+        let label = "SFX_PBS_0100000008008269_opaque";
+        assert!(shader_label_matches(label, label));
+        assert!(shader_label_matches(label, "SFX_PBS_0100000008008269"));
+        assert!(!shader_label_matches(
+            label,
+            "SFX_PBS_0100000008008269_sort"
+        ));
+        assert!(!shader_label_matches(label, ""));
+        assert!(!shader_label_matches("", "SFX_PBS_0100000008008269"));
+    }
+
+    #[test]
+    fn mesh_selector_material() {
+        let selector = MeshSelector::Material("skin".to_string());
+        assert!(selector.matches_labels("body", "skin", ""));
+        assert!(!selector.matches_labels("body", "skin_eye", ""));
+    }
+
+    #[test]
+    fn mesh_selector_name_pattern() {
+        let selector = MeshSelector::Name("*_VIS_O_*".to_string());
+        assert!(selector.matches_labels("Eye_VIS_O_OBJShape", "", ""));
+        assert!(!selector.matches_labels("body", "", ""));
+    }
+
+    #[test]
+    fn mesh_selector_shader() {
+        let label = "SFX_PBS_0100000008008269_opaque";
+        assert!(MeshSelector::Shader(label.to_string()).matches_labels("", "", label));
+        assert!(MeshSelector::Shader("SFX_PBS_0100000008008269".to_string())
+            .matches_labels("", "", label));
+        assert!(
+            !MeshSelector::Shader("SFX_PBS_0100000008008269_sort".to_string())
+                .matches_labels("", "", label)
+        );
+        assert!(!MeshSelector::Shader(String::new()).matches_labels("", "", label));
+    }
+
+    #[test]
+    fn split_lod_name_variants() {
+        assert_eq!(("body".to_string(), 0), split_lod_name("body"));
+        assert_eq!(("body".to_string(), 2), split_lod_name("body_LOD2"));
+        assert_eq!(
+            ("body_VIS_O_OBJShape".to_string(), 12),
+            split_lod_name("body_LOD12_VIS_O_OBJShape")
+        );
+        // A tag without a level is part of the name.
+        assert_eq!(
+            ("body_LODShape".to_string(), 0),
+            split_lod_name("body_LODShape")
+        );
+    }
+
+    #[test]
+    fn select_lod_missing_levels() {
+        assert_eq!(0, select_lod(&[0, 1, 2], 0));
+        assert_eq!(2, select_lod(&[0, 1, 2], 5));
+        assert_eq!(1, select_lod(&[0, 1, 3], 2));
+        assert_eq!(1, select_lod(&[1, 2], 0));
+    }
+
+    #[test]
+    fn auto_lod_level_distance() {
+        let camera = CameraTransforms {
+            model_view_matrix: glam::Mat4::IDENTITY,
+            projection_matrix: glam::Mat4::perspective_rh(90.0f32.to_radians(), 1.0, 0.1, 100.0),
+            mvp_matrix: glam::Mat4::IDENTITY,
+            mvp_inv_matrix: glam::Mat4::IDENTITY,
+            camera_pos: glam::Vec4::W,
+            screen_dimensions: glam::Vec4::ONE,
+        };
+        let sphere = |z| BoundingSphere {
+            center: glam::vec3(0.0, 0.0, z),
+            radius: 1.0,
+        };
+        assert_eq!(0, auto_lod_level(&sphere(-1.0), &camera));
+        assert_eq!(1, auto_lod_level(&sphere(-4.0), &camera));
+        assert_eq!(2, auto_lod_level(&sphere(-8.0), &camera));
+    }
+
+    #[test]
+    fn uv_layout_color_distinct_hues() {
+        assert_eq!(
+            wgpu::Color {
+                r: 1.0,
+                g: 0.0,
+                b: 0.0,
+                a: 1.0
+            },
+            uv_layout_color(0)
+        );
+        let colors: Vec<_> = (0..4).map(uv_layout_color).collect();
+        for (i, a) in colors.iter().enumerate() {
+            for b in &colors[i + 1..] {
+                assert_ne!(a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn sort_draws_empty() {
+        let mut draws: Vec<(bool, u32)> = Vec::new();
+        sort_draws(&mut draws, |d| d.0, |_| 0, |d| d.1);
+        assert!(draws.is_empty());
+    }
+
+    #[test]
+    fn sort_draws_opaque_by_key() {
+        let mut draws = vec![(false, 2), (false, 1), (false, 2), (false, 0)];
+        sort_draws(&mut draws, |d| d.0, |_| 0, |d| d.1);
+        assert_eq!(vec![(false, 0), (false, 1), (false, 2), (false, 2)], draws);
+    }
+
+    #[test]
+    fn sort_draws_preserve_blended_order() {
+        let mut draws = vec![(true, 2), (false, 3), (true, 0), (false, 1), (true, 1)];
+        sort_draws(&mut draws, |d| d.0, |_| 0, |d| d.1);
+        assert_eq!(
+            vec![(false, 1), (false, 3), (true, 2), (true, 0), (true, 1)],
+            draws
+        );
+    }
+
+    #[test]
+    fn sort_draws_blended_sort_bias() {
+        let mut draws = vec![(true, 2, 0), (false, 3, 5), (true, 0, -1), (true, 1, 0)];
+        sort_draws(&mut draws, |d| d.0, |d| d.2, |d| d.1);
+        assert_eq!(
+            vec![(false, 3, 5), (true, 0, -1), (true, 2, 0), (true, 1, 0)],
+            draws
+        );
+    }
+}
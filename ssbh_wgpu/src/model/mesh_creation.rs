@@ -1,30 +1,47 @@
-use super::pipeline::{pipeline, PipelineKey};
+use super::pipeline::{
+    mesh_object_info_layout, pipeline, renormal_vertices_layout, skinning_vertices_layout,
+    PipelineKey,
+};
 use crate::{
-    animation::AnimationTransforms,
+    animation::{skinning_buffer_bone_count, AnimatedWorldTransforms, AnimationTransforms},
     bone_rendering::*,
+    bounding::BoundingBox,
     model::{BoneRenderData, SamplerCache},
+    picking::PickingGeometry,
     renderer::RGBA_COLOR_FORMAT,
+    shader::model::PerMaterial,
+    shader_database::mesh_attribute_names,
     swing_rendering::SwingRenderData,
+    texture::is_texture_path,
+    texture_cache::TextureKey,
     uniforms::{
         default_material_uniforms_bind_group, default_uniforms_buffer,
-        material_uniforms_bind_group, per_material, uniforms_buffer,
+        material_uniforms_bind_group, per_material, uniforms_buffer, DEFAULT_PER_MATERIAL,
     },
     vertex::{buffer0, buffer1, combined_mesh_buffers, skin_weights, CombinedMeshBuffers},
-    DeviceBufferExt, ModelFiles, RenderMesh, RenderModel, ShaderDatabase, SharedRenderData,
+    DeviceBufferExt, FileDiagnostic, LodMode, MaterialValue, MeshDiagnostic,
+    MeshValidationErrorKind, ModelFiles, RenderMesh, RenderModel, RestPose, ShaderDatabase,
+    SharedRenderData, TextureFallback,
 };
-use encase::{DynamicStorageBuffer, ShaderType};
+use encase::{DynamicStorageBuffer, DynamicUniformBuffer, ShaderType};
 use log::{error, info};
 use nutexb_wgpu::NutexbFile;
 use ssbh_data::{
     adj_data::AdjEntryData, matl_data::MatlEntryData, mesh_data::MeshObjectData,
     meshex_data::EntryFlags, prelude::*,
 };
-use std::{collections::HashMap, error::Error, num::NonZeroU64};
+use std::{collections::HashMap, error::Error, num::NonZeroU64, sync::Arc};
 use xmb_lib::XmbFile;
 
 pub struct Material {
     pub material_uniforms_bind_group: crate::shader::model::bind_groups::BindGroup2,
     pub uniforms_buffer: wgpu::Buffer,
+    // Keep the values without overrides to support removing overrides later.
+    pub uniforms: PerMaterial,
+    pub overrides: Vec<MaterialValue>,
+    pub texture_fallbacks: Vec<TextureFallback>,
+    // Keep the texture assignments to recreate the bind group if a texture changes.
+    pub entry: Option<MatlEntryData>,
 }
 
 impl Material {
@@ -37,7 +54,47 @@ impl Material {
         // Material animations don't assign textures.
         // We only need to update the material parameter buffer.
         // This avoids creating GPU resources each frame.
-        let uniforms = per_material(material, database);
+        self.uniforms = per_material(material, database);
+        self.write_uniforms(queue);
+    }
+
+    /// Returns `true` if any of the texture paths in the material refer to the texture file `name`.
+    pub fn uses_texture(&self, name: &str) -> bool {
+        self.entry.as_ref().is_some_and(|entry| {
+            entry
+                .textures
+                .iter()
+                .any(|t| is_texture_path(name, &t.data))
+        })
+    }
+
+    /// Recreates the texture bindings while keeping the existing uniforms and overrides.
+    pub fn recreate_bind_group(
+        &mut self,
+        device: &wgpu::Device,
+        textures: &[(String, Arc<wgpu::Texture>, wgpu::TextureViewDimension)],
+        shared_data: &SharedRenderData,
+        sampler_by_data: &mut SamplerCache,
+    ) {
+        if let Some(entry) = &self.entry {
+            let (bind_group, texture_fallbacks) = material_uniforms_bind_group(
+                entry,
+                device,
+                textures,
+                &shared_data.default_textures,
+                &self.uniforms_buffer,
+                sampler_by_data,
+            );
+            self.material_uniforms_bind_group = bind_group;
+            self.texture_fallbacks = texture_fallbacks;
+        }
+    }
+
+    pub fn write_uniforms(&self, queue: &wgpu::Queue) {
+        let mut uniforms = self.uniforms;
+        for value in &self.overrides {
+            value.apply(&mut uniforms);
+        }
         queue.write_buffer(&self.uniforms_buffer, 0, bytemuck::cast_slice(&[uniforms]));
     }
 }
@@ -45,14 +102,46 @@ impl Material {
 pub struct TransformBuffers {
     pub skinning_transforms: wgpu::Buffer,
     pub world_transforms: wgpu::Buffer,
+    pub model_transform: wgpu::Buffer,
+}
+
+/// Creates a storage buffer with enough skinning transforms for `bone_count` bones.
+pub fn skinning_transforms_buffer(
+    device: &wgpu::Device,
+    transforms: &AnimatedWorldTransforms,
+    bone_count: usize,
+) -> wgpu::Buffer {
+    device.create_buffer_from_data(
+        "Bone Transforms Buffer",
+        &transforms.skinning_transforms(skinning_buffer_bone_count(bone_count)),
+        wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+    )
+}
+
+pub fn model_transform(transform: glam::Mat4) -> crate::shader::skinning::ModelTransform {
+    crate::shader::skinning::ModelTransform {
+        transform,
+        transform_inv_transpose: transform.inverse().transpose(),
+    }
 }
 
 struct RenderMeshData {
-    meshes: Vec<RenderMesh>,
     material_data_by_label: HashMap<String, Material>,
-    textures: Vec<(String, wgpu::Texture, wgpu::TextureViewDimension)>,
-    pipelines: HashMap<PipelineKey, wgpu::RenderPipeline>,
-    buffer_data: CombinedMeshBuffers,
+    textures: Vec<(String, Arc<wgpu::Texture>, wgpu::TextureViewDimension)>,
+    texture_diagnostics: Vec<FileDiagnostic>,
+    geometry: GeometryData,
+}
+
+/// Vertex and skinning data that doesn't depend on materials or textures.
+pub(crate) struct GeometryData {
+    pub meshes: Vec<RenderMesh>,
+    pub pipelines: HashMap<PipelineKey, wgpu::RenderPipeline>,
+    pub buffer_data: CombinedMeshBuffers,
+    pub skinning_vertices_bind_group: Option<wgpu::BindGroup>,
+    pub renormal_vertices_bind_group: Option<wgpu::BindGroup>,
+    pub skinning_transforms_bind_group: crate::shader::skinning::bind_groups::BindGroup1,
+    pub mesh_object_info_bind_group: wgpu::BindGroup,
+    pub mesh_diagnostics: Vec<MeshDiagnostic>,
 }
 
 // TODO: Come up with a better name.
@@ -84,10 +173,10 @@ impl<'a> RenderMeshSharedData<'a> {
             .unwrap_or_else(AnimationTransforms::identity);
 
         // Share the transforms buffer to avoid redundant updates.
-        let skinning_transforms_buffer = device.create_buffer_from_data(
-            "Bone Transforms Buffer",
-            &[animation_transforms.animated_world_transforms],
-            wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        let skinning_transforms_buffer = skinning_transforms_buffer(
+            device,
+            &animation_transforms.animated_world_transforms,
+            self.skel.map(|s| s.bones.len()).unwrap_or_default(),
         );
 
         let world_transforms = device.create_buffer_from_data(
@@ -96,16 +185,24 @@ impl<'a> RenderMeshSharedData<'a> {
             wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
         );
 
-        let swing_render_data = SwingRenderData::new(device, &world_transforms);
-
-        let bone_render_data =
-            self.create_bone_render_data(device, &animation_transforms, &world_transforms);
+        // Models are placed at the origin until moved with RenderModel::set_world_transform.
+        let model_transform = device.create_buffer_from_data(
+            "Model Transform Buffer",
+            &[model_transform(glam::Mat4::IDENTITY)],
+            wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        );
 
         let mesh_buffers = TransformBuffers {
             skinning_transforms: skinning_transforms_buffer,
             world_transforms,
+            model_transform,
         };
 
+        let swing_render_data = SwingRenderData::new(device, &mesh_buffers);
+
+        let bone_render_data =
+            self.create_bone_render_data(device, &animation_transforms, &mesh_buffers);
+
         let default_material_data = default_material_data(device, self.shared_data);
 
         let per_model_buffer = self.per_model_buffer(device);
@@ -122,12 +219,26 @@ impl<'a> RenderMeshSharedData<'a> {
             .map(|skel| skel.bones.iter().map(|b| b.name.clone()).collect())
             .unwrap_or_default();
 
+        let bone_parent_indices = self
+            .skel
+            .map(|skel| skel.bones.iter().map(|b| b.parent_index).collect())
+            .unwrap_or_default();
+
         let RenderMeshData {
-            meshes,
             material_data_by_label,
             textures,
-            pipelines,
-            buffer_data,
+            texture_diagnostics,
+            geometry:
+                GeometryData {
+                    meshes,
+                    pipelines,
+                    buffer_data,
+                    skinning_vertices_bind_group,
+                    renormal_vertices_bind_group,
+                    skinning_transforms_bind_group,
+                    mesh_object_info_bind_group,
+                    mesh_diagnostics,
+                },
         } = self.create_render_mesh_data(device, queue, &mesh_buffers);
 
         info!(
@@ -138,22 +249,39 @@ impl<'a> RenderMeshSharedData<'a> {
             start.elapsed()
         );
 
-        RenderModel {
+        let mut model = RenderModel {
             is_visible: true,
             is_selected: false,
+            rest_pose: RestPose::default(),
+            lod_mode: LodMode::default(),
             meshes,
             transforms: mesh_buffers,
+            world_transform: glam::Mat4::IDENTITY,
             material_data_by_label,
             default_material_data,
             textures,
             pipelines,
+            sample_count: self.shared_data.sample_count(),
+            comparison_materials: None,
             bone_render_data,
             mesh_buffers: buffer_data,
+            skinning_vertices_bind_group,
+            renormal_vertices_bind_group,
             animation_transforms: Box::new(animation_transforms),
             swing_render_data,
             per_model_bind_group,
             bone_names,
-        }
+            bone_parent_indices,
+            skinning_transforms_bind_group,
+            mesh_object_info_bind_group,
+            mesh_diagnostics,
+            texture_diagnostics,
+            #[cfg(feature = "frame_stats")]
+            animation_stats: Default::default(),
+        };
+        // Hide the lower detail meshes by default.
+        model.apply_lod(None);
+        model
     }
 
     fn per_model_buffer(&self, device: &wgpu::Device) -> wgpu::Buffer {
@@ -182,11 +310,11 @@ impl<'a> RenderMeshSharedData<'a> {
         )
     }
 
-    fn create_bone_render_data(
+    pub(crate) fn create_bone_render_data(
         &self,
         device: &wgpu::Device,
         animation_transforms: &AnimationTransforms,
-        world_transforms: &wgpu::Buffer,
+        transforms: &TransformBuffers,
     ) -> BoneRenderData {
         let bone_colors = bone_colors_buffer(device, self.skel, self.hlpb);
 
@@ -201,15 +329,36 @@ impl<'a> RenderMeshSharedData<'a> {
             wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
         );
 
-        let bone_data = bone_bind_group1(device, world_transforms, &bone_colors);
-        let joint_data = bone_bind_group1(device, &joint_world_transforms, &bone_colors);
-        let bone_bind_groups = bone_bind_groups(device, self.skel);
+        let bone_visibility = bone_visibility_buffer(device, self.skel);
+
+        let bone_data = bone_bind_group1(
+            device,
+            &transforms.world_transforms,
+            &bone_colors,
+            &bone_visibility,
+            &transforms.model_transform,
+        );
+        let joint_data = bone_bind_group1(
+            device,
+            &joint_world_transforms,
+            &bone_colors,
+            &bone_visibility,
+            &transforms.model_transform,
+        );
+
+        // Limit the instance count to the size of the transforms buffers.
+        let bone_count = self
+            .skel
+            .map(|skel| skel.bones.len().min(crate::animation::MAX_BONE_COUNT) as u32)
+            .unwrap_or_default();
 
         BoneRenderData {
+            bone_colors,
             joint_world_transforms,
+            bone_visibility,
             bone_data,
             joint_data,
-            bone_bind_groups,
+            bone_count,
         }
     }
 
@@ -223,51 +372,42 @@ impl<'a> RenderMeshSharedData<'a> {
 
         // Initialize textures exactly once for performance.
         // Unused textures are rare, so we won't lazy load them.
-        let textures = self.create_textures(device, queue);
+        let (textures, texture_diagnostics) = self.create_textures(device, queue);
 
         // Materials can be shared between mesh objects.
         let material_data_by_label = self.create_materials(device, &textures);
 
-        // DynamicStorageBuffer ensures mesh object offsets are properly aligned.
-        let mut model_buffer0_data = DynamicStorageBuffer::new(Vec::new());
-        let mut model_buffer1_data = Vec::new();
-        let mut model_skin_weights_data = DynamicStorageBuffer::new(Vec::new());
-
-        let mut model_indices = Vec::new();
-
-        let mut accesses = Vec::new();
-
-        // TODO: Refactor this to use iterators.
-        if let Some(mesh) = self.mesh.as_ref() {
-            for mesh_object in &mesh.objects {
-                // TODO: Find a way to have fewer function parameters?
-                match append_mesh_object_buffer_data(
-                    &mut model_buffer0_data,
-                    &mut model_buffer1_data,
-                    &mut model_skin_weights_data,
-                    &mut model_indices,
-                    mesh_object,
-                    self,
-                ) {
-                    Ok(access) => {
-                        accesses.push(access);
-                    }
-                    Err(e) => {
-                        error!(
-                            "Error accessing vertex data for mesh {}: {}",
-                            mesh_object.name, e
-                        );
-                    }
-                }
-            }
+        RenderMeshData {
+            material_data_by_label,
+            textures,
+            texture_diagnostics,
+            geometry: self.create_geometry(device, mesh_buffers),
         }
+    }
+
+    pub(crate) fn create_geometry(
+        &self,
+        device: &wgpu::Device,
+        mesh_buffers: &TransformBuffers,
+    ) -> GeometryData {
+        let MeshBufferData {
+            buffer0,
+            buffer1,
+            skin_weights,
+            adjacency,
+            indices,
+            accesses,
+            binding_sizes,
+            mut mesh_diagnostics,
+        } = mesh_buffer_data(self.mesh, self.skel, self.adj, &device.limits());
 
         let combined_mesh_buffers = combined_mesh_buffers(
             device,
-            &model_buffer0_data.into_inner(),
-            &model_buffer1_data,
-            &model_skin_weights_data.into_inner(),
-            &model_indices,
+            &buffer0,
+            &buffer1,
+            &skin_weights,
+            &adjacency,
+            &indices,
         );
 
         // Mesh objects control the depth state of the pipeline.
@@ -275,32 +415,52 @@ impl<'a> RenderMeshSharedData<'a> {
         // Cache materials separately since materials may share a pipeline.
         let mut pipelines = HashMap::new();
 
-        let meshes = self
+        let mut meshes = self
             .create_render_meshes(
                 accesses,
                 device,
                 &mut pipelines,
-                mesh_buffers,
                 &combined_mesh_buffers,
+                &buffer1,
+                &mut mesh_diagnostics,
             )
             .unwrap_or_default();
 
-        RenderMeshData {
+        // All meshes share the same transforms.
+        let skinning_transforms_bind_group =
+            crate::shader::skinning::bind_groups::BindGroup1::from_bindings(
+                device,
+                crate::shader::skinning::bind_groups::BindGroupLayout1 {
+                    transforms: mesh_buffers.skinning_transforms.as_entire_buffer_binding(),
+                    world_transforms: mesh_buffers.world_transforms.as_entire_buffer_binding(),
+                    model_transform: mesh_buffers.model_transform.as_entire_buffer_binding(),
+                },
+            );
+
+        let mesh_object_info_bind_group = mesh_object_info_bind_group(device, &mut meshes);
+        let (skinning_vertices_bind_group, renormal_vertices_bind_group) =
+            vertices_bind_groups(device, &combined_mesh_buffers, binding_sizes);
+
+        GeometryData {
             meshes,
-            material_data_by_label,
-            textures,
             pipelines,
             buffer_data: combined_mesh_buffers,
+            skinning_vertices_bind_group,
+            renormal_vertices_bind_group,
+            skinning_transforms_bind_group,
+            mesh_object_info_bind_group,
+            mesh_diagnostics,
         }
     }
 
     fn create_render_meshes(
         &self,
-        accesses: Vec<MeshBufferAccess>,
+        accesses: Vec<Option<MeshBufferAccess>>,
         device: &wgpu::Device,
         pipelines: &mut HashMap<PipelineKey, wgpu::RenderPipeline>,
-        transform_buffers: &TransformBuffers,
         mesh_buffers: &CombinedMeshBuffers,
+        buffer1: &[u8],
+        mesh_diagnostics: &mut Vec<MeshDiagnostic>,
     ) -> Option<Vec<RenderMesh>> {
         Some(
             self.mesh?
@@ -309,10 +469,8 @@ impl<'a> RenderMeshSharedData<'a> {
                 .zip(accesses)
                 .enumerate()
                 .filter_map(|(i, (mesh_object, access))| {
-                    // Some mesh objects have associated triangle adjacency.
-                    let adj_entry = self
-                        .adj
-                        .and_then(|adj| adj.entries.iter().find(|e| e.mesh_object_index == i));
+                    // Skipped meshes already have a diagnostic.
+                    let access = access?;
 
                     // Find rendering flags from the numshexb.
                     let meshex_flags = self
@@ -327,19 +485,24 @@ impl<'a> RenderMeshSharedData<'a> {
 
                     self.create_render_mesh(
                         device,
+                        i,
                         mesh_object,
-                        adj_entry,
                         meshex_flags.copied(),
                         pipelines,
-                        transform_buffers,
                         access,
                         mesh_buffers,
+                        buffer1,
                     )
                     .map_err(|e| {
                         error!(
                             "Error creating render mesh for mesh {}: {}",
                             mesh_object.name, e
                         );
+                        mesh_diagnostics.push(MeshDiagnostic {
+                            name: mesh_object.name.clone(),
+                            subindex: mesh_object.subindex,
+                            message: e.to_string(),
+                        });
                         e
                     })
                     .ok()
@@ -352,26 +515,56 @@ impl<'a> RenderMeshSharedData<'a> {
         &self,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
-    ) -> Vec<(String, wgpu::Texture, wgpu::TextureViewDimension)> {
-        self.nutexbs
+    ) -> (
+        Vec<(String, Arc<wgpu::Texture>, wgpu::TextureViewDimension)>,
+        Vec<FileDiagnostic>,
+    ) {
+        let mut diagnostics = Vec::new();
+
+        // Nutexb files that failed to read are already reported with the other files.
+        let textures = self
+            .nutexbs
             .iter()
             .filter_map(|(name, nutexb)| {
                 let nutexb = nutexb
                     .as_ref()
                     .inspect_err(|e| error!("Failed to read nutexb file {}: {}", name, e))
                     .ok()?;
-                let (texture, dim) = nutexb_wgpu::create_texture(nutexb, device, queue)
-                    .inspect_err(|e| error!("Failed to create nutexb texture {}: {}", name, e))
-                    .ok()?;
+
+                // Identical textures are shared with other models using the same shared data.
+                // Only lock while accessing the cache so other threads can hash and upload textures.
+                let key = TextureKey::new(nutexb);
+                let cached = self.shared_data.texture_cache.lock().unwrap().get(key);
+                let (texture, dim) = match cached {
+                    Some(cached) => cached,
+                    None => {
+                        let (texture, dim) = nutexb_wgpu::create_texture(nutexb, device, queue)
+                            .inspect_err(|e| {
+                                error!("Failed to create nutexb texture {}: {}", name, e);
+                                diagnostics.push(FileDiagnostic {
+                                    name: name.clone(),
+                                    message: e.to_string(),
+                                });
+                            })
+                            .ok()?;
+                        self.shared_data
+                            .texture_cache
+                            .lock()
+                            .unwrap()
+                            .insert(key, texture, dim)
+                    }
+                };
                 Some((name.clone(), texture, dim))
             })
-            .collect()
+            .collect();
+
+        (textures, diagnostics)
     }
 
     fn create_materials(
         &self,
         device: &wgpu::Device,
-        textures: &[(String, wgpu::Texture, wgpu::TextureViewDimension)],
+        textures: &[(String, Arc<wgpu::Texture>, wgpu::TextureViewDimension)],
     ) -> HashMap<String, Material> {
         // Some devices only support up to 4000 sampler allocations.
         // Models use very few unique sampler settings in practice.
@@ -407,13 +600,13 @@ impl<'a> RenderMeshSharedData<'a> {
     fn create_render_mesh(
         &self,
         device: &wgpu::Device,
+        mesh_object_index: usize,
         mesh_object: &MeshObjectData,
-        adj_entry: Option<&AdjEntryData>,
         meshex_flags: Option<EntryFlags>,
         pipelines: &mut HashMap<PipelineKey, wgpu::RenderPipeline>,
-        transforms: &TransformBuffers,
         access: MeshBufferAccess,
         buffers: &CombinedMeshBuffers,
+        buffer1: &[u8],
     ) -> Result<RenderMesh, Box<dyn Error>> {
         // TODO: These could be cleaner as functions.
         // TODO: Is using a default for the material label ok?
@@ -445,7 +638,12 @@ impl<'a> RenderMeshSharedData<'a> {
             mesh_object.disable_depth_test,
             material,
             RGBA_COLOR_FORMAT,
-        );
+        )
+        .with_custom_shader(material.and_then(|m| {
+            self.shared_data
+                .pipeline_data
+                .custom_shader_id(&m.shader_label)
+        }));
 
         pipelines
             .entry(pipeline_key)
@@ -453,66 +651,13 @@ impl<'a> RenderMeshSharedData<'a> {
 
         let vertex_count = mesh_object.vertex_count()?;
 
-        // TODO: Function for this?
-        let adjacency = adj_entry
-            .map(|e| e.vertex_adjacency.iter().map(|i| *i as i32).collect())
-            .unwrap_or_else(|| vec![-1i32; vertex_count * 18]);
-        let adj_buffer = device.create_buffer_from_data(
-            "Adjacency Buffer",
-            &adjacency,
-            wgpu::BufferUsages::STORAGE,
-        );
-
-        // This is applied after skinning, so the source and destination buffer are the same.
-        // TODO: Can this be done in a single dispatch for the entire model?
         // TODO: Add a proper error for empty meshes.
         // TODO: Investigate why empty meshes crash on emulators.
-        let buffer0_binding = access.buffer0.binding(&buffers.vertex_buffer0)?;
-        let buffer0_source_binding = access.buffer0.binding(&buffers.vertex_buffer0_source)?;
-        let weights_binding = access.weights.binding(&buffers.skinning_buffer)?;
-
-        let renormal_bind_group = crate::shader::renormal::bind_groups::BindGroup0::from_bindings(
-            device,
-            crate::shader::renormal::bind_groups::BindGroupLayout0 {
-                vertices: buffer0_binding.clone(),
-                adj_data: adj_buffer.as_entire_buffer_binding(),
-            },
-        );
-
-        let skinning_bind_group = crate::shader::skinning::bind_groups::BindGroup0::from_bindings(
-            device,
-            crate::shader::skinning::bind_groups::BindGroupLayout0 {
-                src: buffer0_source_binding,
-                vertex_weights: weights_binding,
-                dst: buffer0_binding.clone(),
-            },
-        );
-
-        let skinning_transforms_bind_group =
-            crate::shader::skinning::bind_groups::BindGroup1::from_bindings(
-                device,
-                crate::shader::skinning::bind_groups::BindGroupLayout1 {
-                    transforms: transforms.skinning_transforms.as_entire_buffer_binding(),
-                    world_transforms: transforms.world_transforms.as_entire_buffer_binding(),
-                },
-            );
+        if vertex_count == 0 {
+            return Err("Mesh has no vertices. Failed to create vertex buffers.".into());
+        }
 
         let parent_index = find_parent_index(mesh_object, self.skel);
-        let mesh_object_info_buffer = device.create_buffer_from_data(
-            "Mesh Object Info Buffer",
-            &[crate::shader::skinning::MeshObjectInfo {
-                parent_index: glam::IVec4::new(parent_index, -1, -1, -1),
-            }],
-            wgpu::BufferUsages::UNIFORM,
-        );
-
-        let mesh_object_info_bind_group =
-            crate::shader::skinning::bind_groups::BindGroup2::from_bindings(
-                device,
-                crate::shader::skinning::bind_groups::BindGroupLayout2 {
-                    mesh_object_info: mesh_object_info_buffer.as_entire_buffer_binding(),
-                },
-            );
 
         // The end of the shader label is used to determine draw order.
         // ex: "SFX_PBS_0101000008018278_sort" has a tag of "sort".
@@ -523,20 +668,68 @@ impl<'a> RenderMeshSharedData<'a> {
             .unwrap_or("")
             .to_string();
 
-        let attribute_names = mesh_object
-            .positions
-            .iter()
-            .map(|a| a.name.clone())
-            .chain(mesh_object.normals.iter().map(|a| a.name.clone()))
-            .chain(mesh_object.tangents.iter().map(|a| a.name.clone()))
-            .chain(
+        let attribute_names = mesh_attribute_names(mesh_object);
+
+        // Calculate bounds once since the vertex data isn't stored on the CPU.
+        let local_bounding_box = mesh_object.positions.first().and_then(|a| {
+            BoundingBox::from_points(
+                a.data
+                    .to_vec4_with_w(1.0)
+                    .into_iter()
+                    .map(|[x, y, z, _]| glam::vec3(x, y, z)),
+            )
+        });
+
+        let parent_bone_index = usize::try_from(parent_index).ok();
+        let rest_parent_transform = self
+            .skel
+            .zip(parent_bone_index)
+            .and_then(|(skel, i)| skel.calculate_world_transform(skel.bones.get(i)?).ok())
+            .map(|t| glam::Mat4::from_cols_array_2d(&t))
+            .unwrap_or(glam::Mat4::IDENTITY);
+        let bounding_box = local_bounding_box.map(|b| b.transform(rest_parent_transform));
+        let bounding_sphere = bounding_box.map(|b| b.bounding_sphere());
+
+        let influence_bone_indices = self
+            .skel
+            .map(|skel| {
                 mesh_object
-                    .texture_coordinates
+                    .bone_influences
                     .iter()
-                    .map(|a| a.name.clone()),
-            )
-            .chain(mesh_object.color_sets.iter().map(|a| a.name.clone()))
-            .collect();
+                    .filter_map(|i| skel.bones.iter().position(|b| b.name == i.bone_name))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let influence_errors = influence_errors(mesh_object, self.skel, vertex_count);
+
+        // Attributes are interleaved, so editing a single attribute needs the other attribute values.
+        let buffer1_start = access.buffer1.start() as usize;
+        let buffer1_data = buffer1
+            .get(buffer1_start..buffer1_start + access.buffer1.size() as usize)
+            .unwrap_or_default()
+            .to_vec();
+
+        // Keep positions on the CPU since reading back GPU buffers requires waiting.
+        let picking = PickingGeometry {
+            positions: mesh_object
+                .positions
+                .first()
+                .map(|a| {
+                    a.data
+                        .to_vec4_with_w(1.0)
+                        .into_iter()
+                        .map(|[x, y, z, _]| glam::vec3(x, y, z))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            indices: mesh_object.vertex_indices.clone(),
+            weights: if influence_bone_indices.is_empty() {
+                Vec::new()
+            } else {
+                skin_weights(mesh_object, self.skel)?
+            },
+        };
 
         // TODO: Set entry flags?
         Ok(RenderMesh {
@@ -545,20 +738,30 @@ impl<'a> RenderMeshSharedData<'a> {
             shader_label,
             is_visible: true,
             is_selected: false,
+            mesh_object_index,
             meshex_flags: meshex_flags.unwrap_or(EntryFlags {
                 draw_model: true,
                 cast_shadow: true,
             }),
-            skinning_bind_group,
-            skinning_transforms_bind_group,
-            mesh_object_info_bind_group,
+            lod_hidden: false,
+            // Assigned later when creating the combined buffer for all meshes.
+            mesh_object_info_offset: 0,
             pipeline_key,
-            renormal_bind_group,
             subindex: mesh_object.subindex,
             vertex_count,
             vertex_index_count: mesh_object.vertex_indices.len(),
+            sort_bias: mesh_object.sort_bias,
             access,
+            vertex_buffer1: buffers.vertex_buffer1.clone(),
+            buffer1_data,
             attribute_names,
+            bounding_box,
+            bounding_sphere,
+            local_bounding_box,
+            parent_bone_index,
+            influence_bone_indices,
+            influence_errors,
+            picking,
         })
     }
 }
@@ -567,12 +770,16 @@ fn bone_bind_group1(
     device: &wgpu::Device,
     world_transforms: &wgpu::Buffer,
     bone_colors: &wgpu::Buffer,
+    bone_visibility: &wgpu::Buffer,
+    model_transform: &wgpu::Buffer,
 ) -> crate::shader::skeleton::bind_groups::BindGroup1 {
     crate::shader::skeleton::bind_groups::BindGroup1::from_bindings(
         device,
         crate::shader::skeleton::bind_groups::BindGroupLayout1 {
             world_transforms: world_transforms.as_entire_buffer_binding(),
             bone_colors: bone_colors.as_entire_buffer_binding(),
+            bone_visibility: bone_visibility.as_entire_buffer_binding(),
+            model_transform: model_transform.as_entire_buffer_binding(),
         },
     )
 }
@@ -580,12 +787,13 @@ fn bone_bind_group1(
 pub fn material_data(
     device: &wgpu::Device,
     material: &MatlEntryData,
-    textures: &[(String, wgpu::Texture, wgpu::TextureViewDimension)],
+    textures: &[(String, Arc<wgpu::Texture>, wgpu::TextureViewDimension)],
     shared_data: &SharedRenderData,
     sampler_by_data: &mut SamplerCache,
 ) -> Material {
-    let uniforms_buffer = uniforms_buffer(material, device, &shared_data.database);
-    let material_uniforms_bind_group = material_uniforms_bind_group(
+    let uniforms = per_material(material, &shared_data.database);
+    let uniforms_buffer = uniforms_buffer(&uniforms, device);
+    let (material_uniforms_bind_group, texture_fallbacks) = material_uniforms_bind_group(
         material,
         device,
         textures,
@@ -597,9 +805,45 @@ pub fn material_data(
     Material {
         material_uniforms_bind_group,
         uniforms_buffer,
+        uniforms,
+        overrides: Vec::new(),
+        texture_fallbacks,
+        entry: Some(material.clone()),
     }
 }
 
+fn influence_errors(
+    mesh_object: &MeshObjectData,
+    skel: Option<&SkelData>,
+    vertex_count: usize,
+) -> Vec<MeshValidationErrorKind> {
+    let mut errors = Vec::new();
+    for influence in &mesh_object.bone_influences {
+        // Models without a skel don't use influences.
+        if skel.is_some_and(|skel| !skel.bones.iter().any(|b| b.name == influence.bone_name)) {
+            errors.push(MeshValidationErrorKind::MissingInfluenceBone {
+                bone_name: influence.bone_name.clone(),
+            });
+        }
+
+        // Only report the largest index to avoid an error for every vertex.
+        if let Some(vertex_index) = influence
+            .vertex_weights
+            .iter()
+            .map(|w| w.vertex_index)
+            .filter(|i| *i as usize >= vertex_count)
+            .max()
+        {
+            errors.push(MeshValidationErrorKind::InfluenceVertexIndex {
+                bone_name: influence.bone_name.clone(),
+                vertex_index,
+                vertex_count,
+            });
+        }
+    }
+    errors
+}
+
 pub fn default_material_data(device: &wgpu::Device, shared_data: &SharedRenderData) -> Material {
     let uniforms_buffer = default_uniforms_buffer(device);
     let material_uniforms_bind_group = default_material_uniforms_bind_group(
@@ -611,13 +855,155 @@ pub fn default_material_data(device: &wgpu::Device, shared_data: &SharedRenderDa
     Material {
         material_uniforms_bind_group,
         uniforms_buffer,
+        uniforms: DEFAULT_PER_MATERIAL,
+        overrides: Vec::new(),
+        texture_fallbacks: Vec::new(),
+        entry: None,
+    }
+}
+
+/// The combined vertex and index data for all the mesh objects in a model.
+///
+/// This is the CPU side data used to create the vertex buffers for a [RenderModel].
+pub struct MeshBufferData {
+    /// Positions, normals, and tangents for skinning.
+    pub buffer0: Vec<u8>,
+    /// Texture coordinates and vertex colors.
+    pub buffer1: Vec<u8>,
+    /// Bone indices and weights for up to 4 influences per vertex.
+    pub skin_weights: Vec<u8>,
+    /// Adjacent vertex indices for recalculating normals.
+    pub adjacency: Vec<u8>,
+    pub indices: Vec<u32>,
+    /// The buffer ranges for each mesh object or `None` if the mesh object is invalid.
+    pub(crate) accesses: Vec<Option<MeshBufferAccess>>,
+    pub(crate) binding_sizes: DynamicBindingSizes,
+    /// Mesh objects that will be skipped when rendering.
+    pub mesh_diagnostics: Vec<MeshDiagnostic>,
+}
+
+/// The binding size of each combined storage buffer.
+///
+/// Every mesh binds the same size with a dynamic offset to share a single bind group.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct DynamicBindingSizes {
+    pub buffer0: u64,
+    pub weights: u64,
+    pub adjacency: u64,
+}
+
+/// Packs the vertex data for each mesh object in `mesh` into combined buffers
+/// without requiring a [wgpu::Device].
+///
+/// Mesh objects that would exceed the buffer sizes in `limits` are skipped.
+pub fn mesh_buffer_data(
+    mesh: Option<&MeshData>,
+    skel: Option<&SkelData>,
+    adj: Option<&AdjData>,
+    limits: &wgpu::Limits,
+) -> MeshBufferData {
+    // DynamicStorageBuffer ensures mesh object offsets are properly aligned.
+    let mut model_buffer0_data = DynamicStorageBuffer::new(Vec::new());
+    let mut model_buffer1_data = Vec::new();
+    let mut model_skin_weights_data = DynamicStorageBuffer::new(Vec::new());
+    let mut model_adjacency_data = DynamicStorageBuffer::new(Vec::new());
+
+    let mut model_indices = Vec::new();
+
+    // Store an access for each mesh object to keep the indices aligned.
+    let mut accesses = Vec::new();
+    let mut mesh_diagnostics = Vec::new();
+
+    // TODO: Refactor this to use iterators.
+    for (i, mesh_object) in mesh.iter().flat_map(|m| &m.objects).enumerate() {
+        // Some mesh objects have associated triangle adjacency.
+        let adj_entry = adj.and_then(|adj| adj.entries.iter().find(|e| e.mesh_object_index == i));
+
+        // Skip invalid meshes before creating any buffers to avoid panics or validation errors.
+        let access = validate_mesh_object(
+            mesh_object,
+            limits,
+            model_buffer0_data.as_ref().len(),
+            model_indices.len(),
+        )
+        .and_then(|_| {
+            append_mesh_object_buffer_data(
+                &mut model_buffer0_data,
+                &mut model_buffer1_data,
+                &mut model_skin_weights_data,
+                &mut model_adjacency_data,
+                &mut model_indices,
+                mesh_object,
+                adj_entry,
+                skel,
+            )
+            .map_err(Into::into)
+        });
+
+        match access {
+            Ok(access) => {
+                accesses.push(Some(access));
+            }
+            Err(e) => {
+                error!(
+                    "Error accessing vertex data for mesh {}: {}",
+                    mesh_object.name, e
+                );
+                mesh_diagnostics.push(MeshDiagnostic {
+                    name: mesh_object.name.clone(),
+                    subindex: mesh_object.subindex,
+                    message: e.to_string(),
+                });
+                accesses.push(None);
+            }
+        }
+    }
+
+    let mut buffer0 = model_buffer0_data.into_inner();
+    let mut skin_weights = model_skin_weights_data.into_inner();
+    let mut adjacency = model_adjacency_data.into_inner();
+
+    let valid_accesses = || accesses.iter().flatten();
+    let binding_sizes = DynamicBindingSizes {
+        buffer0: pad_dynamic_binding(&mut buffer0, valid_accesses().map(|a| &a.buffer0)),
+        weights: pad_dynamic_binding(&mut skin_weights, valid_accesses().map(|a| &a.weights)),
+        adjacency: pad_dynamic_binding(&mut adjacency, valid_accesses().map(|a| &a.adjacency)),
+    };
+
+    MeshBufferData {
+        buffer0,
+        buffer1: model_buffer1_data,
+        skin_weights,
+        adjacency,
+        indices: model_indices,
+        accesses,
+        binding_sizes,
+        mesh_diagnostics,
     }
 }
 
+// Dynamic offset bindings use the same size for every mesh.
+// Pad the end of the data so the largest binding fits at every offset.
+fn pad_dynamic_binding<'a>(
+    data: &mut Vec<u8>,
+    accesses: impl Iterator<Item = &'a BufferAccess> + Clone,
+) -> u64 {
+    let binding_size = accesses.clone().map(|a| a.size).max().unwrap_or_default();
+    let size = accesses
+        .map(|a| a.start + binding_size)
+        .max()
+        .unwrap_or_default() as usize;
+    if size > data.len() {
+        data.resize(size, 0);
+    }
+    binding_size
+}
+
 pub struct MeshBufferAccess {
     pub buffer0: BufferAccess,
     pub buffer1: BufferAccess,
     pub weights: BufferAccess,
+    pub adjacency: BufferAccess,
     pub indices: BufferAccess,
 }
 
@@ -629,18 +1015,12 @@ pub struct BufferAccess {
 // TODO: Avoid creating empty accesses entirely.
 // TODO: Double check that this handles empty meshes properly.
 impl BufferAccess {
-    pub fn binding<'a>(
-        &self,
-        buffer: &'a wgpu::Buffer,
-    ) -> Result<wgpu::BufferBinding<'a>, Box<dyn Error>> {
-        Ok(wgpu::BufferBinding::<'a> {
-            buffer,
-            offset: self.start,
-            size: Some(
-                NonZeroU64::new(self.size)
-                    .ok_or("Mesh has no vertices. Failed to create vertex buffers.")?,
-            ),
-        })
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
     }
 
     pub fn slice<'a>(&self, buffer: &'a wgpu::Buffer) -> wgpu::BufferSlice<'a> {
@@ -648,13 +1028,67 @@ impl BufferAccess {
     }
 }
 
+// Check for data that would cause panics or validation errors when creating or binding buffers.
+fn validate_mesh_object(
+    mesh_object: &MeshObjectData,
+    limits: &wgpu::Limits,
+    model_buffer0_size: usize,
+    model_index_count: usize,
+) -> Result<(), Box<dyn Error>> {
+    let vertex_count = mesh_object.vertex_count()?;
+    if vertex_count == 0 {
+        return Err("Mesh has no vertices.".into());
+    }
+    if mesh_object.vertex_indices.is_empty() {
+        return Err("Mesh has no vertex indices.".into());
+    }
+    if let Some(index) = mesh_object
+        .vertex_indices
+        .iter()
+        .find(|i| **i as usize >= vertex_count)
+    {
+        return Err(
+            format!("Vertex index {index} is out of range for {vertex_count} vertices.").into(),
+        );
+    }
+
+    // Each mesh binds its vertices and adjacency as storage buffers.
+    let vertex_size = std::mem::size_of::<crate::shader::skinning::VertexInput0>()
+        .max(std::mem::size_of::<[i32; 18]>());
+    let max_vertices = limits.max_storage_buffer_binding_size as usize / vertex_size;
+    if vertex_count > max_vertices {
+        return Err(format!(
+            "Vertex count {vertex_count} exceeds the limit of {max_vertices} vertices."
+        )
+        .into());
+    }
+
+    // All meshes share the same buffers, so also check the total size.
+    let max_buffer_size = limits.max_buffer_size as usize;
+    let buffer0_size = model_buffer0_size
+        + vertex_count * std::mem::size_of::<crate::shader::skinning::VertexInput0>();
+    let index_size =
+        (model_index_count + mesh_object.vertex_indices.len()) * std::mem::size_of::<u32>();
+    if buffer0_size > max_buffer_size || index_size > max_buffer_size {
+        return Err(format!(
+            "Combined vertex data for the model exceeds the buffer size limit of {max_buffer_size} bytes."
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
 fn append_mesh_object_buffer_data(
     model_buffer0_data: &mut DynamicStorageBuffer<Vec<u8>>,
     model_buffer1_data: &mut Vec<u8>,
     model_skin_weights_data: &mut DynamicStorageBuffer<Vec<u8>>,
+    model_adjacency_data: &mut DynamicStorageBuffer<Vec<u8>>,
     model_index_data: &mut Vec<u32>,
     mesh_object: &MeshObjectData,
-    shared_data: &RenderMeshSharedData,
+    adj_entry: Option<&AdjEntryData>,
+    skel: Option<&SkelData>,
 ) -> Result<MeshBufferAccess, ssbh_data::mesh_data::error::Error> {
     // DynamicStorageBuffer enforces the offset alignment for each mesh.
     let buffer0_vertices = buffer0(mesh_object)?;
@@ -673,9 +1107,17 @@ fn append_mesh_object_buffer_data(
     let buffer1_offset = model_buffer1_data.len() as u64;
     model_buffer1_data.extend_from_slice(bytemuck::cast_slice(buffer1_data));
 
-    let skin_weights = skin_weights(mesh_object, shared_data.skel)?;
+    let skin_weights = skin_weights(mesh_object, skel)?;
     let weights_offset = model_skin_weights_data.write(&skin_weights).unwrap();
 
+    // Meshes without adjacency use -1 to skip recalculating normals.
+    // TODO: Function for this?
+    let vertex_count = mesh_object.vertex_count()?;
+    let adjacency: Vec<i32> = adj_entry
+        .map(|e| e.vertex_adjacency.iter().map(|i| *i as i32).collect())
+        .unwrap_or_else(|| vec![-1i32; vertex_count * 18]);
+    let adjacency_offset = model_adjacency_data.write(&adjacency).unwrap();
+
     // Only the index buffer is tightly packed.
     let index_offset = (model_index_data.len() * std::mem::size_of::<u32>()) as u64;
     model_index_data.extend_from_slice(&mesh_object.vertex_indices);
@@ -693,52 +1135,131 @@ fn append_mesh_object_buffer_data(
             start: weights_offset,
             size: skin_weights.size().get(),
         },
+        adjacency: BufferAccess {
+            start: adjacency_offset,
+            size: adjacency.size().get(),
+        },
         indices: BufferAccess {
             start: index_offset,
-            size: (model_index_data.len() * std::mem::size_of::<u32>()) as u64,
+            size: std::mem::size_of_val(mesh_object.vertex_indices.as_slice()) as u64,
         },
     })
 }
 
-fn bone_bind_groups(
-    device: &wgpu::Device,
-    skel: Option<&SkelData>,
-) -> Vec<crate::shader::skeleton::bind_groups::BindGroup2> {
-    skel.map(|skel| {
-        skel.bones
-            .iter()
-            .enumerate()
-            .map(|(i, bone)| {
-                // TODO: Use instancing instead.
-                let per_bone = device.create_buffer_from_data(
-                    "Mesh Object Info Buffer",
-                    &[crate::shader::skeleton::PerBone {
-                        indices: glam::IVec4::new(
-                            i as i32,
-                            parent_index(bone.parent_index),
-                            -1,
-                            -1,
-                        ),
-                    }],
-                    wgpu::BufferUsages::UNIFORM,
-                );
+// TODO: Where to put this?
+// TODO: Module for skinning buffers?
+fn parent_index(index: Option<usize>) -> i32 {
+    index.map(|i| i as i32).unwrap_or(-1)
+}
 
-                crate::shader::skeleton::bind_groups::BindGroup2::from_bindings(
-                    device,
-                    crate::shader::skeleton::bind_groups::BindGroupLayout2 {
-                        per_bone: per_bone.as_entire_buffer_binding(),
-                    },
-                )
+// Store the info for all meshes in a single buffer to reduce allocations and bind groups.
+// Each mesh binds its info using a dynamic offset.
+fn mesh_object_info_bind_group(
+    device: &wgpu::Device,
+    meshes: &mut [RenderMesh],
+) -> wgpu::BindGroup {
+    // DynamicUniformBuffer ensures the offsets are properly aligned.
+    let mut data = DynamicUniformBuffer::new(Vec::new());
+    for mesh in meshes.iter_mut() {
+        let parent_index = mesh.parent_bone_index.map(|i| i as i32).unwrap_or(-1);
+        let offset = data
+            .write(&crate::shader::skinning::MeshObjectInfo {
+                parent_index: glam::IVec4::new(parent_index, -1, -1, -1),
+                vertex_count: glam::UVec4::new(mesh.vertex_count as u32, 0, 0, 0),
             })
-            .collect()
+            .unwrap();
+        mesh.mesh_object_info_offset = offset as u32;
+    }
+
+    // Bindings can't be empty, so always include at least one entry.
+    if meshes.is_empty() {
+        data.write(&crate::shader::skinning::MeshObjectInfo {
+            parent_index: glam::IVec4::splat(-1),
+            vertex_count: glam::UVec4::ZERO,
+        })
+        .unwrap();
+    }
+
+    let buffer = device.create_buffer_from_bytes(
+        "Mesh Object Info Buffer",
+        &data.into_inner(),
+        wgpu::BufferUsages::UNIFORM,
+    );
+
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Mesh Object Info Bind Group"),
+        layout: &mesh_object_info_layout(device),
+        entries: &[wgpu::BindGroupEntry {
+            binding: 0,
+            resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                buffer: &buffer,
+                offset: 0,
+                size: Some(crate::shader::skinning::MeshObjectInfo::min_size()),
+            }),
+        }],
     })
-    .unwrap_or_default()
 }
 
-// TODO: Where to put this?
-// TODO: Module for skinning buffers?
-fn parent_index(index: Option<usize>) -> i32 {
-    index.map(|i| i as i32).unwrap_or(-1)
+// Each mesh uses dynamic offsets into the combined buffers to avoid a bind group per mesh.
+// Returns `None` if there are no meshes since bindings can't be empty.
+fn vertices_bind_groups(
+    device: &wgpu::Device,
+    buffers: &CombinedMeshBuffers,
+    sizes: DynamicBindingSizes,
+) -> (Option<wgpu::BindGroup>, Option<wgpu::BindGroup>) {
+    let binding = |buffer, size| {
+        Some(wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+            buffer,
+            offset: 0,
+            size: Some(NonZeroU64::new(size)?),
+        }))
+    };
+
+    let skinning = binding(&buffers.vertex_buffer0_source, sizes.buffer0)
+        .zip(binding(&buffers.skinning_buffer, sizes.weights))
+        .zip(binding(&buffers.vertex_buffer0, sizes.buffer0))
+        .map(|((src, vertex_weights), dst)| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Skinning Vertices Bind Group"),
+                layout: &skinning_vertices_layout(device),
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: src,
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: vertex_weights,
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: dst,
+                    },
+                ],
+            })
+        });
+
+    // This is applied after skinning, so the source and destination buffer are the same.
+    let renormal = binding(&buffers.vertex_buffer0, sizes.buffer0)
+        .zip(binding(&buffers.adjacency_buffer, sizes.adjacency))
+        .map(|(vertices, adj_data)| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Renormal Vertices Bind Group"),
+                layout: &renormal_vertices_layout(device),
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: vertices,
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: adj_data,
+                    },
+                ],
+            })
+        });
+
+    (skinning, renormal)
 }
 
 fn find_parent_index(mesh: &MeshObjectData, skel: Option<&SkelData>) -> i32 {
@@ -754,3 +1275,172 @@ fn find_parent_index(mesh: &MeshObjectData, skel: Option<&SkelData>) -> i32 {
         -1
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ssbh_data::{
+        mesh_data::{AttributeData, BoneInfluence, VectorData, VertexWeight},
+        skel_data::{BillboardType, BoneData},
+    };
+
+    fn mesh_object(vertex_count: usize, vertex_indices: Vec<u32>) -> MeshObjectData {
+        MeshObjectData {
+            positions: vec![AttributeData {
+                name: "Position0".to_string(),
+                data: VectorData::Vector3(vec![[0.0; 3]; vertex_count]),
+            }],
+            vertex_indices,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn validate_mesh_object_valid() {
+        assert!(validate_mesh_object(
+            &mesh_object(3, vec![0, 1, 2]),
+            &wgpu::Limits::default(),
+            0,
+            0
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn validate_mesh_object_empty() {
+        let limits = wgpu::Limits::default();
+        assert!(validate_mesh_object(&mesh_object(0, Vec::new()), &limits, 0, 0).is_err());
+        assert!(validate_mesh_object(&mesh_object(3, Vec::new()), &limits, 0, 0).is_err());
+    }
+
+    #[test]
+    fn validate_mesh_object_index_out_of_range() {
+        assert!(validate_mesh_object(
+            &mesh_object(3, vec![0, 1, 3]),
+            &wgpu::Limits::default(),
+            0,
+            0
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn mesh_buffer_data_skips_invalid() {
+        let mut invalid = mesh_object(3, vec![0, 1, 3]);
+        invalid.name = "invalid".to_string();
+
+        let data = mesh_buffer_data(
+            Some(&MeshData {
+                major_version: 1,
+                minor_version: 10,
+                objects: vec![
+                    mesh_object(3, vec![0, 1, 2]),
+                    invalid,
+                    mesh_object(4, vec![0, 1, 2, 3]),
+                ],
+            }),
+            None,
+            None,
+            &wgpu::Limits::default(),
+        );
+
+        // Accesses should still line up with the mesh objects.
+        assert_eq!(3, data.accesses.len());
+        assert!(data.accesses[0].is_some());
+        assert!(data.accesses[1].is_none());
+        assert!(data.accesses[2].is_some());
+        assert_eq!(vec![0, 1, 2, 0, 1, 2, 3], data.indices);
+
+        let access = data.accesses[2].as_ref().unwrap();
+        assert_eq!(12, access.indices.start);
+        assert_eq!(16, access.indices.size);
+        assert!(access.buffer0.start + access.buffer0.size <= data.buffer0.len() as u64);
+        assert!(access.weights.start + access.weights.size <= data.skin_weights.len() as u64);
+
+        // The largest binding should fit at every dynamic offset.
+        for access in data.accesses.iter().flatten() {
+            let sizes = data.binding_sizes;
+            assert!(access.buffer0.start + sizes.buffer0 <= data.buffer0.len() as u64);
+            assert!(access.weights.start + sizes.weights <= data.skin_weights.len() as u64);
+            assert!(access.adjacency.start + sizes.adjacency <= data.adjacency.len() as u64);
+        }
+
+        assert_eq!(1, data.mesh_diagnostics.len());
+        assert_eq!("invalid", data.mesh_diagnostics[0].name);
+    }
+
+    #[test]
+    fn mesh_buffer_data_no_mesh() {
+        let data = mesh_buffer_data(None, None, None, &wgpu::Limits::default());
+        assert!(data.buffer0.is_empty());
+        assert!(data.accesses.is_empty());
+    }
+
+    #[test]
+    fn validate_mesh_object_exceeds_limits() {
+        let limits = wgpu::Limits {
+            max_storage_buffer_binding_size: 128,
+            ..Default::default()
+        };
+        assert!(validate_mesh_object(&mesh_object(3, vec![0, 1, 2]), &limits, 0, 0).is_err());
+
+        let limits = wgpu::Limits {
+            max_buffer_size: 64,
+            ..Default::default()
+        };
+        assert!(validate_mesh_object(&mesh_object(3, vec![0, 1, 2]), &limits, 0, 0).is_err());
+    }
+
+    #[test]
+    fn influence_errors_invalid_bones_and_indices() {
+        let influence = |name: &str, vertex_indices: &[u32]| BoneInfluence {
+            bone_name: name.to_string(),
+            vertex_weights: vertex_indices
+                .iter()
+                .map(|i| VertexWeight {
+                    vertex_index: *i,
+                    vertex_weight: 1.0,
+                })
+                .collect(),
+        };
+        let mesh_object = MeshObjectData {
+            bone_influences: vec![
+                influence("A", &[0, 1]),
+                influence("B", &[2, 5, 3]),
+                influence("C", &[0]),
+            ],
+            ..Default::default()
+        };
+        let skel = SkelData {
+            major_version: 1,
+            minor_version: 0,
+            bones: ["A", "B"]
+                .iter()
+                .map(|name| BoneData {
+                    name: name.to_string(),
+                    transform: glam::Mat4::IDENTITY.to_cols_array_2d(),
+                    parent_index: None,
+                    billboard_type: BillboardType::Disabled,
+                })
+                .collect(),
+        };
+
+        assert_eq!(
+            vec![
+                MeshValidationErrorKind::InfluenceVertexIndex {
+                    bone_name: "B".to_string(),
+                    vertex_index: 5,
+                    vertex_count: 3
+                },
+                MeshValidationErrorKind::MissingInfluenceBone {
+                    bone_name: "C".to_string()
+                },
+            ],
+            influence_errors(&mesh_object, Some(&skel), 3)
+        );
+
+        // Missing bones are ignored without a skel.
+        assert!(influence_errors(&mesh_object, None, 6).is_empty());
+    }
+}
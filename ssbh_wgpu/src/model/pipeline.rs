@@ -1,18 +1,90 @@
+use encase::ShaderType;
 use ssbh_data::matl_data::{BlendFactor, BlendStateData, MatlEntryData};
 
-use crate::renderer::{INVERTED_STENCIL_MASK_STATE, MSAA_SAMPLE_COUNT, RGBA_COLOR_FORMAT};
+use super::shader_label_matches;
+use crate::renderer::{
+    INVERTED_STENCIL_MASK_STATE, MSAA_SAMPLE_COUNT, OIT_ACCUMULATION_FORMAT, OIT_REVEALAGE_FORMAT,
+    RGBA_COLOR_FORMAT,
+};
 
 // Create some helper structs to simplify the function signatures.
 pub struct PipelineData {
     pub layout: wgpu::PipelineLayout,
     pub shader: wgpu::ShaderModule,
+    pub sample_count: u32,
+    pub alpha_to_coverage: bool,
+    pub custom_shaders: Vec<CustomShader>,
+    pub next_custom_shader_id: u64,
+}
+
+// A fragment shader that replaces fs_main for materials with a matching shader label.
+pub struct CustomShader {
+    // Pipeline keys use the ID since modules can't be hashed.
+    pub id: u64,
+    pub shader_label: String,
+    // Use an entry point from the model shader if None.
+    pub module: Option<wgpu::ShaderModule>,
+    pub entry_point: String,
 }
 
 impl PipelineData {
     pub fn new(device: &wgpu::Device) -> Self {
         let shader = crate::shader::model::create_shader_module(device);
         let layout = crate::shader::model::create_pipeline_layout(device);
-        Self { layout, shader }
+        Self {
+            layout,
+            shader,
+            sample_count: MSAA_SAMPLE_COUNT,
+            alpha_to_coverage: true,
+            custom_shaders: Vec::new(),
+            next_custom_shader_id: 0,
+        }
+    }
+
+    pub fn add_custom_shader(
+        &mut self,
+        shader_label: &str,
+        module: Option<wgpu::ShaderModule>,
+        entry_point: &str,
+    ) {
+        self.custom_shaders
+            .retain(|s| s.shader_label != shader_label);
+        self.custom_shaders.push(CustomShader {
+            id: self.next_custom_shader_id,
+            shader_label: shader_label.to_string(),
+            module,
+            entry_point: entry_point.to_string(),
+        });
+        // Use a new ID to avoid reusing pipelines created for the previous shader.
+        self.next_custom_shader_id += 1;
+    }
+
+    /// The ID of the custom shader for materials with `shader_label` if present.
+    pub fn custom_shader_id(&self, shader_label: &str) -> Option<u64> {
+        // Prefer an exact match over a label without the render pass tag.
+        self.custom_shaders
+            .iter()
+            .find(|s| s.shader_label == shader_label)
+            .or_else(|| {
+                self.custom_shaders
+                    .iter()
+                    .find(|s| shader_label_matches(shader_label, &s.shader_label))
+            })
+            .map(|s| s.id)
+    }
+
+    fn fragment_shader(&self, pipeline_key: &PipelineKey) -> (&wgpu::ShaderModule, &str) {
+        // Shaders removed after creating the key use the default shader.
+        pipeline_key
+            .custom_shader
+            .and_then(|id| self.custom_shaders.iter().find(|s| s.id == id))
+            .map(|s| {
+                (
+                    s.module.as_ref().unwrap_or(&self.shader),
+                    s.entry_point.as_str(),
+                )
+            })
+            .unwrap_or((&self.shader, "fs_main"))
     }
 }
 
@@ -29,6 +101,7 @@ pub struct PipelineKey {
     polygon_mode: wgpu::PolygonMode,
     alpha_to_coverage_enabled: bool,
     surface_format: wgpu::TextureFormat,
+    custom_shader: Option<u64>,
 }
 
 impl PipelineKey {
@@ -59,16 +132,77 @@ impl PipelineKey {
                 .map(|b| b.alpha_sample_to_coverage)
                 .unwrap_or(false),
             surface_format,
+            custom_shader: None,
+        }
+    }
+
+    /// Returns `true` if the pipeline uses alpha blending.
+    /// Blended draws depend on draw order and should not be reordered.
+    pub fn is_blended(&self) -> bool {
+        self.blend.is_some()
+    }
+
+    /// Returns `true` if the pipeline writes and tests depth without blending or alpha to coverage.
+    /// These draws can be safely drawn in a depth pre-pass.
+    pub fn is_depth_prepass_compatible(&self) -> bool {
+        self.enable_depth_write
+            && self.enable_depth_test
+            && !self.is_blended()
+            && !self.alpha_to_coverage_enabled
+    }
+
+    pub fn cull_mode(&self) -> Option<wgpu::Face> {
+        self.cull_mode
+    }
+
+    /// Returns `Some(premultiplied)` if the pipeline can be drawn with [OitPipelines].
+    ///
+    /// Only "over" blending with depth testing is supported.
+    /// Other blend modes like additive blending should still be drawn in order.
+    pub fn oit_premultiplied(&self) -> Option<bool> {
+        // Custom shaders don't have an OIT fragment entry point.
+        if self.custom_shader.is_some() {
+            return None;
+        }
+        let blend = self.blend?;
+        if !self.enable_depth_test || blend.color.dst_factor != wgpu::BlendFactor::OneMinusSrcAlpha
+        {
+            return None;
+        }
+        match blend.color.src_factor {
+            wgpu::BlendFactor::SrcAlpha => Some(false),
+            wgpu::BlendFactor::One => Some(true),
+            _ => None,
         }
     }
 
-    pub fn with_material(&self, material: Option<&MatlEntryData>) -> Self {
+    pub fn with_depth(&self, disable_depth_write: bool, disable_depth_test: bool) -> Self {
+        Self {
+            enable_depth_write: !disable_depth_write,
+            enable_depth_test: !disable_depth_test,
+            ..*self
+        }
+    }
+
+    pub fn with_material(
+        &self,
+        material: Option<&MatlEntryData>,
+        pipeline_data: &PipelineData,
+    ) -> Self {
         Self::new(
             !self.enable_depth_write,
             !self.enable_depth_test,
             material,
             self.surface_format,
         )
+        .with_custom_shader(material.and_then(|m| pipeline_data.custom_shader_id(&m.shader_label)))
+    }
+
+    pub fn with_custom_shader(&self, custom_shader: Option<u64>) -> Self {
+        Self {
+            custom_shader,
+            ..*self
+        }
     }
 }
 
@@ -80,6 +214,7 @@ pub fn pipeline(
     // Each model pipeline uses the same WGSL code.
     // Use the shader from the pipeline_data to ensure it's only compiled once.
     // This greatly speeds up pipeline creation.
+    let (fragment_module, fragment_entry) = pipeline_data.fragment_shader(pipeline_key);
     device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
         label: Some("Render Pipeline"),
         layout: Some(&pipeline_data.layout),
@@ -91,8 +226,8 @@ pub fn pipeline(
             ),
         ),
         fragment: Some(wgpu::FragmentState {
-            module: &pipeline_data.shader,
-            entry_point: Some("fs_main"),
+            module: fragment_module,
+            entry_point: Some(fragment_entry),
             targets: &[Some(wgpu::ColorTargetState {
                 format: pipeline_key.surface_format,
                 blend: pipeline_key.blend,
@@ -117,8 +252,11 @@ pub fn pipeline(
         )),
         multisample: wgpu::MultisampleState {
             // MSAA is required for alpha to coverage to work on metal.
-            count: MSAA_SAMPLE_COUNT,
-            alpha_to_coverage_enabled: pipeline_key.alpha_to_coverage_enabled,
+            // Alpha to coverage is not allowed for a single sample.
+            count: pipeline_data.sample_count,
+            alpha_to_coverage_enabled: pipeline_key.alpha_to_coverage_enabled
+                && pipeline_data.alpha_to_coverage
+                && pipeline_data.sample_count > 1,
             ..Default::default()
         },
         multiview: None,
@@ -126,6 +264,106 @@ pub fn pipeline(
     })
 }
 
+// wgsl_to_wgpu doesn't support dynamic offsets, so create this layout manually.
+pub fn mesh_object_info_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Mesh Object Info Layout"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: true,
+                min_binding_size: Some(crate::shader::skinning::MeshObjectInfo::min_size()),
+            },
+            count: None,
+        }],
+    })
+}
+
+// The vertex data for all meshes in a model uses combined buffers with dynamic offsets.
+pub fn skinning_vertices_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Skinning Vertices Layout"),
+        entries: &[
+            dynamic_storage_entry(0, true),
+            dynamic_storage_entry(1, true),
+            dynamic_storage_entry(2, false),
+        ],
+    })
+}
+
+pub fn renormal_vertices_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Renormal Vertices Layout"),
+        entries: &[
+            dynamic_storage_entry(0, false),
+            dynamic_storage_entry(1, true),
+        ],
+    })
+}
+
+fn dynamic_storage_entry(binding: u32, read_only: bool) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only },
+            has_dynamic_offset: true,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+pub fn skinning_pipeline(device: &wgpu::Device) -> wgpu::ComputePipeline {
+    let module = crate::shader::skinning::create_shader_module(device);
+
+    // The vertices and mesh object info for all meshes in a model use combined buffers with dynamic offsets.
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Skinning Pipeline Layout"),
+        bind_group_layouts: &[
+            &skinning_vertices_layout(device),
+            &crate::shader::skinning::bind_groups::BindGroup1::get_bind_group_layout(device),
+            &mesh_object_info_layout(device),
+            &crate::shader::skinning::bind_groups::BindGroup3::get_bind_group_layout(device),
+        ],
+        push_constant_ranges: &[],
+    });
+
+    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some("Skinning Pipeline"),
+        layout: Some(&layout),
+        module: &module,
+        entry_point: Some("main"),
+        compilation_options: Default::default(),
+        cache: None,
+    })
+}
+
+pub fn renormal_pipeline(device: &wgpu::Device) -> wgpu::ComputePipeline {
+    let module = crate::shader::renormal::create_shader_module(device);
+
+    // Share the vertex buffers and mesh object info with skinning.
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Renormal Pipeline Layout"),
+        bind_group_layouts: &[
+            &renormal_vertices_layout(device),
+            &mesh_object_info_layout(device),
+        ],
+        push_constant_ranges: &[],
+    });
+
+    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some("Renormal Pipeline"),
+        layout: Some(&layout),
+        module: &module,
+        entry_point: Some("main"),
+        compilation_options: Default::default(),
+        cache: None,
+    })
+}
+
 pub fn depth_pipeline(device: &wgpu::Device) -> wgpu::RenderPipeline {
     let module = crate::shader::model::create_shader_module(device);
 
@@ -160,35 +398,264 @@ pub fn depth_pipeline(device: &wgpu::Device) -> wgpu::RenderPipeline {
     })
 }
 
-pub fn invalid_shader_pipeline(device: &wgpu::Device) -> wgpu::RenderPipeline {
+/// Depth only pipelines for each cull mode for drawing opaque meshes before the model pass.
+pub struct DepthPrepassPipelines {
+    none: wgpu::RenderPipeline,
+    back: wgpu::RenderPipeline,
+    front: wgpu::RenderPipeline,
+}
+
+impl DepthPrepassPipelines {
+    pub fn new(device: &wgpu::Device, sample_count: u32) -> Self {
+        Self {
+            none: depth_prepass_pipeline(device, None, sample_count),
+            back: depth_prepass_pipeline(device, Some(wgpu::Face::Back), sample_count),
+            front: depth_prepass_pipeline(device, Some(wgpu::Face::Front), sample_count),
+        }
+    }
+
+    pub fn get(&self, cull_mode: Option<wgpu::Face>) -> &wgpu::RenderPipeline {
+        match cull_mode {
+            None => &self.none,
+            Some(wgpu::Face::Back) => &self.back,
+            Some(wgpu::Face::Front) => &self.front,
+        }
+    }
+}
+
+/// Pipelines for each cull mode for drawing blended meshes with weighted blended order independent transparency.
+/// See [PipelineKey::oit_premultiplied].
+pub struct OitPipelines {
+    // Indexed by premultiplied and then cull mode.
+    pipelines: [[wgpu::RenderPipeline; 3]; 2],
+}
+
+impl OitPipelines {
+    pub fn new(device: &wgpu::Device, sample_count: u32) -> Self {
+        let cull_modes = [None, Some(wgpu::Face::Back), Some(wgpu::Face::Front)];
+        Self {
+            pipelines: [false, true].map(|premultiplied| {
+                cull_modes
+                    .map(|cull_mode| oit_pipeline(device, cull_mode, premultiplied, sample_count))
+            }),
+        }
+    }
+
+    pub fn get(&self, pipeline_key: &PipelineKey) -> Option<&wgpu::RenderPipeline> {
+        let premultiplied = pipeline_key.oit_premultiplied()?;
+        let cull_index = match pipeline_key.cull_mode {
+            None => 0,
+            Some(wgpu::Face::Back) => 1,
+            Some(wgpu::Face::Front) => 2,
+        };
+        Some(&self.pipelines[premultiplied as usize][cull_index])
+    }
+}
+
+fn oit_pipeline(
+    device: &wgpu::Device,
+    cull_mode: Option<wgpu::Face>,
+    premultiplied: bool,
+    sample_count: u32,
+) -> wgpu::RenderPipeline {
+    let module = crate::shader::model::create_shader_module(device);
+    let render_pipeline_layout = crate::shader::model::create_pipeline_layout(device);
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Model Order Independent Transparency"),
+        layout: Some(&render_pipeline_layout),
+        vertex: crate::shader::model::vertex_state(
+            &module,
+            &crate::shader::model::vs_main_entry(
+                wgpu::VertexStepMode::Vertex,
+                wgpu::VertexStepMode::Vertex,
+            ),
+        ),
+        fragment: Some(wgpu::FragmentState {
+            module: &module,
+            entry_point: Some(if premultiplied {
+                "fs_oit_premultiplied"
+            } else {
+                "fs_oit"
+            }),
+            targets: &[
+                // Sum the weighted colors and alpha.
+                Some(wgpu::ColorTargetState {
+                    format: OIT_ACCUMULATION_FORMAT,
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::One,
+                            dst_factor: wgpu::BlendFactor::One,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                        alpha: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::One,
+                            dst_factor: wgpu::BlendFactor::One,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                }),
+                // Multiply the transmittance of each fragment.
+                Some(wgpu::ColorTargetState {
+                    format: OIT_REVEALAGE_FORMAT,
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::Zero,
+                            dst_factor: wgpu::BlendFactor::OneMinusSrc,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                        alpha: wgpu::BlendComponent::REPLACE,
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                }),
+            ],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            cull_mode,
+            ..Default::default()
+        },
+        // Test against opaque meshes without occluding other transparent meshes.
+        depth_stencil: Some(depth_stencil_state(false, true)),
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            ..Default::default()
+        },
+        multiview: None,
+        cache: None,
+    })
+}
+
+fn depth_prepass_pipeline(
+    device: &wgpu::Device,
+    cull_mode: Option<wgpu::Face>,
+    sample_count: u32,
+) -> wgpu::RenderPipeline {
+    let module = crate::shader::model::create_shader_module(device);
+    let render_pipeline_layout = crate::shader::model::create_pipeline_layout(device);
+
+    // Use the same vertex shader as the model pass to produce identical depth values.
+    // The culling needs to match to avoid occluding meshes behind culled faces.
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Model Depth Prepass"),
+        layout: Some(&render_pipeline_layout),
+        vertex: crate::shader::model::vertex_state(
+            &module,
+            &crate::shader::model::vs_main_entry(
+                wgpu::VertexStepMode::Vertex,
+                wgpu::VertexStepMode::Vertex,
+            ),
+        ),
+        fragment: None,
+        primitive: wgpu::PrimitiveState {
+            cull_mode,
+            ..Default::default()
+        },
+        depth_stencil: Some(depth_stencil_state(true, true)),
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            ..Default::default()
+        },
+        multiview: None,
+        cache: None,
+    })
+}
+
+pub fn invalid_shader_pipeline(device: &wgpu::Device, sample_count: u32) -> wgpu::RenderPipeline {
     model_pipeline_from_entry(
         device,
         "vs_main_invalid",
         "fs_invalid_shader",
         "Model Invalid Shader",
+        sample_count,
     )
 }
 
-pub fn selected_material_pipeline(device: &wgpu::Device) -> wgpu::RenderPipeline {
-    model_pipeline_from_entry(
+pub fn selected_material_pipeline(
+    device: &wgpu::Device,
+    sample_count: u32,
+) -> wgpu::RenderPipeline {
+    model_pipeline_from_entry_with_target(
         device,
         "vs_main",
         "fs_selected_material",
         "Model Selected Material",
+        sample_count,
+        wgpu::ColorTargetState {
+            format: RGBA_COLOR_FORMAT,
+            // Replace the color with the blend constant for each mask.
+            blend: Some(wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::Constant,
+                    dst_factor: wgpu::BlendFactor::Zero,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: wgpu::BlendComponent::REPLACE,
+            }),
+            write_mask: wgpu::ColorWrites::ALL,
+        },
     )
 }
 
-pub fn invalid_attributes_pipeline(device: &wgpu::Device) -> wgpu::RenderPipeline {
+pub fn invalid_attributes_pipeline(
+    device: &wgpu::Device,
+    sample_count: u32,
+) -> wgpu::RenderPipeline {
     model_pipeline_from_entry(
         device,
         "vs_main_invalid",
         "fs_invalid_attributes",
         "Model Invalid Attributes",
+        sample_count,
     )
 }
 
-pub fn debug_pipeline(device: &wgpu::Device) -> wgpu::RenderPipeline {
-    model_pipeline_from_entry(device, "vs_main", "fs_debug", "Model Debug")
+pub fn debug_pipeline(device: &wgpu::Device, sample_count: u32) -> wgpu::RenderPipeline {
+    model_pipeline_from_entry(device, "vs_main", "fs_debug", "Model Debug", sample_count)
+}
+
+pub fn overdraw_pipeline(device: &wgpu::Device, sample_count: u32) -> wgpu::RenderPipeline {
+    let module = crate::shader::model::create_shader_module(device);
+    let render_pipeline_layout = crate::shader::model::create_pipeline_layout(device);
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Model Overdraw"),
+        layout: Some(&render_pipeline_layout),
+        vertex: crate::shader::model::vertex_state(
+            &module,
+            &crate::shader::model::vs_main_entry(
+                wgpu::VertexStepMode::Vertex,
+                wgpu::VertexStepMode::Vertex,
+            ),
+        ),
+        fragment: Some(wgpu::FragmentState {
+            module: &module,
+            entry_point: Some("fs_overdraw"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format: RGBA_COLOR_FORMAT,
+                // Count every fragment including occluded fragments.
+                blend: Some(wgpu::BlendState {
+                    color: wgpu::BlendComponent {
+                        src_factor: wgpu::BlendFactor::One,
+                        dst_factor: wgpu::BlendFactor::One,
+                        operation: wgpu::BlendOperation::Add,
+                    },
+                    alpha: wgpu::BlendComponent::OVER,
+                }),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: Some(depth_stencil_state(false, false)),
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            ..Default::default()
+        },
+        multiview: None,
+        cache: None,
+    })
 }
 
 pub fn silhouette_pipeline(
@@ -222,7 +689,7 @@ pub fn silhouette_pipeline(
     })
 }
 
-pub fn wireframe_pipeline(device: &wgpu::Device) -> wgpu::RenderPipeline {
+pub fn wireframe_pipeline(device: &wgpu::Device, sample_count: u32) -> wgpu::RenderPipeline {
     let module = crate::shader::model::create_shader_module(device);
     let render_pipeline_layout = crate::shader::model::create_pipeline_layout(device);
 
@@ -248,7 +715,7 @@ pub fn wireframe_pipeline(device: &wgpu::Device) -> wgpu::RenderPipeline {
         },
         depth_stencil: Some(depth_stencil_state(true, true)),
         multisample: wgpu::MultisampleState {
-            count: MSAA_SAMPLE_COUNT,
+            count: sample_count,
             ..Default::default()
         },
         multiview: None,
@@ -261,6 +728,25 @@ pub fn model_pipeline_from_entry(
     vertex_entry: &str,
     entry_point: &str,
     label: &str,
+    sample_count: u32,
+) -> wgpu::RenderPipeline {
+    model_pipeline_from_entry_with_target(
+        device,
+        vertex_entry,
+        entry_point,
+        label,
+        sample_count,
+        RGBA_COLOR_FORMAT.into(),
+    )
+}
+
+fn model_pipeline_from_entry_with_target(
+    device: &wgpu::Device,
+    vertex_entry: &str,
+    entry_point: &str,
+    label: &str,
+    sample_count: u32,
+    target: wgpu::ColorTargetState,
 ) -> wgpu::RenderPipeline {
     let module = crate::shader::model::create_shader_module(device);
     let render_pipeline_layout = crate::shader::model::create_pipeline_layout(device);
@@ -284,7 +770,7 @@ pub fn model_pipeline_from_entry(
         fragment: Some(wgpu::FragmentState {
             module: &module,
             entry_point: Some(entry_point),
-            targets: &[Some(RGBA_COLOR_FORMAT.into())],
+            targets: &[Some(target)],
             compilation_options: wgpu::PipelineCompilationOptions::default(),
         }),
         primitive: wgpu::PrimitiveState {
@@ -294,7 +780,7 @@ pub fn model_pipeline_from_entry(
         },
         depth_stencil: Some(depth_stencil_state(true, true)),
         multisample: wgpu::MultisampleState {
-            count: MSAA_SAMPLE_COUNT,
+            count: sample_count,
             ..Default::default()
         },
         multiview: None,
@@ -352,6 +838,105 @@ pub fn uv_pipeline(
     })
 }
 
+pub fn uv_layout_pipeline(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+) -> wgpu::RenderPipeline {
+    let module = crate::shader::model::create_shader_module(device);
+    let render_pipeline_layout = crate::shader::model::create_pipeline_layout(device);
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Model UV Layout"),
+        layout: Some(&render_pipeline_layout),
+        vertex: crate::shader::model::vertex_state(
+            &module,
+            &crate::shader::model::vs_uv_entry(
+                wgpu::VertexStepMode::Vertex,
+                wgpu::VertexStepMode::Vertex,
+            ),
+        ),
+        fragment: Some(wgpu::FragmentState {
+            module: &module,
+            entry_point: Some("fs_uv"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                // Multiply the white output by the blend constant to set the color per mesh.
+                blend: Some(wgpu::BlendState {
+                    color: wgpu::BlendComponent {
+                        src_factor: wgpu::BlendFactor::Constant,
+                        dst_factor: wgpu::BlendFactor::Zero,
+                        operation: wgpu::BlendOperation::Add,
+                    },
+                    alpha: wgpu::BlendComponent {
+                        src_factor: wgpu::BlendFactor::Constant,
+                        dst_factor: wgpu::BlendFactor::Zero,
+                        operation: wgpu::BlendOperation::Add,
+                    },
+                }),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            // Use wireframe rendering to show UV edges.
+            polygon_mode: wgpu::PolygonMode::Line,
+            ..Default::default()
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            ..Default::default()
+        },
+        multiview: None,
+        cache: None,
+    })
+}
+
+pub fn uv_coverage_pipeline(
+    device: &wgpu::Device,
+    surface_format: wgpu::TextureFormat,
+) -> wgpu::RenderPipeline {
+    let module = crate::shader::model::create_shader_module(device);
+    let render_pipeline_layout = crate::shader::model::create_pipeline_layout(device);
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Model UV Coverage"),
+        layout: Some(&render_pipeline_layout),
+        vertex: crate::shader::model::vertex_state(
+            &module,
+            &crate::shader::model::vs_uv_entry(
+                wgpu::VertexStepMode::Vertex,
+                wgpu::VertexStepMode::Vertex,
+            ),
+        ),
+        fragment: Some(wgpu::FragmentState {
+            module: &module,
+            entry_point: Some("fs_uv_coverage"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format: surface_format,
+                // Accumulate faces to highlight overlapping UVs.
+                blend: Some(wgpu::BlendState {
+                    color: wgpu::BlendComponent {
+                        src_factor: wgpu::BlendFactor::One,
+                        dst_factor: wgpu::BlendFactor::One,
+                        operation: wgpu::BlendOperation::Add,
+                    },
+                    alpha: wgpu::BlendComponent::OVER,
+                }),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        // All UVs have the same depth, so depth testing would hide overlaps.
+        depth_stencil: Some(depth_stencil_state(false, false)),
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    })
+}
+
 // TODO: These can be easily unit tested.
 fn blend_state(blend_state: &BlendStateData) -> wgpu::BlendState {
     wgpu::BlendState {
@@ -383,3 +968,55 @@ fn blend_factor(factor: BlendFactor) -> wgpu::BlendFactor {
         BlendFactor::SourceAlphaSaturate => wgpu::BlendFactor::SrcAlphaSaturated,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(blend: Option<wgpu::BlendState>, enable_depth_test: bool) -> PipelineKey {
+        PipelineKey {
+            enable_depth_write: false,
+            enable_depth_test,
+            blend,
+            cull_mode: None,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            alpha_to_coverage_enabled: false,
+            surface_format: RGBA_COLOR_FORMAT,
+            custom_shader: None,
+        }
+    }
+
+    #[test]
+    fn oit_premultiplied_blend_modes() {
+        assert_eq!(None, key(None, true).oit_premultiplied());
+        assert_eq!(
+            Some(false),
+            key(Some(wgpu::BlendState::ALPHA_BLENDING), true).oit_premultiplied()
+        );
+        assert_eq!(
+            Some(true),
+            key(Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING), true).oit_premultiplied()
+        );
+        assert_eq!(
+            None,
+            key(Some(wgpu::BlendState::ALPHA_BLENDING), false).oit_premultiplied()
+        );
+
+        // Additive blending is already order independent.
+        let additive = wgpu::BlendState {
+            color: wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::SrcAlpha,
+                dst_factor: wgpu::BlendFactor::One,
+                operation: wgpu::BlendOperation::Add,
+            },
+            alpha: wgpu::BlendComponent::OVER,
+        };
+        assert_eq!(None, key(Some(additive), true).oit_premultiplied());
+    }
+
+    #[test]
+    fn oit_premultiplied_custom_shader() {
+        let key = key(Some(wgpu::BlendState::ALPHA_BLENDING), true).with_custom_shader(Some(0));
+        assert_eq!(None, key.oit_premultiplied());
+    }
+}
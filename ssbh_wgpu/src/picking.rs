@@ -0,0 +1,341 @@
+use glam::{Vec2, Vec3, Vec4Swizzles};
+
+use crate::{
+    bounding::BoundingSphere, shader::skinning::VertexWeight, BoneFilter, CameraTransforms,
+    RenderModel,
+};
+
+/// The closest [RenderMesh](crate::RenderMesh) under the cursor from [pick_mesh].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeshHit {
+    /// The index of the model in the `render_models` passed to [pick_mesh].
+    pub model_index: usize,
+    /// The index of the mesh in [RenderModel::meshes].
+    pub mesh_index: usize,
+    /// The name of the mesh object.
+    pub mesh_name: String,
+    /// The subindex of the mesh object if names are repeated.
+    pub subindex: u64,
+    /// The world space position of the hit on the mesh surface.
+    pub position: Vec3,
+    /// The distance from the camera to [MeshHit::position].
+    pub distance: f32,
+}
+
+/// The closest bone under the cursor from [pick_bone].
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoneHit {
+    /// The index of the model in the `render_models` passed to [pick_bone].
+    pub model_index: usize,
+    /// The index of the bone in the skel.
+    pub bone_index: usize,
+}
+
+// Vertex data kept on the CPU since the GPU buffers can't be read synchronously.
+#[derive(Debug, Default)]
+pub(crate) struct PickingGeometry {
+    pub positions: Vec<Vec3>,
+    pub indices: Vec<u32>,
+    // Empty for meshes without skinning.
+    pub weights: Vec<VertexWeight>,
+}
+
+/// A ray in world space with a normalized direction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Ray {
+    origin: Vec3,
+    direction: Vec3,
+}
+
+impl Ray {
+    fn from_screen(position: Vec2, camera: &CameraTransforms) -> Option<Self> {
+        let (width, height) = (camera.screen_dimensions.x, camera.screen_dimensions.y);
+        if width <= 0.0 || height <= 0.0 {
+            return None;
+        }
+
+        // Convert from screen space [0,width] or [0,height] to clip space [-1,1].
+        // Flip y vertically to match wgpu conventions.
+        let x = position.x / width * 2.0 - 1.0;
+        let y = 1.0 - position.y / height * 2.0;
+
+        let near = camera.mvp_inv_matrix * glam::vec4(x, y, 0.0, 1.0);
+        let far = camera.mvp_inv_matrix * glam::vec4(x, y, 1.0, 1.0);
+        let near = near.xyz() / near.w;
+        let far = far.xyz() / far.w;
+
+        Some(Self {
+            origin: near,
+            direction: (far - near).try_normalize()?,
+        })
+    }
+
+    fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        let offset = sphere.center - self.origin;
+        let t = offset.dot(self.direction);
+        let closest = self.origin + self.direction * t.max(0.0);
+        closest.distance_squared(sphere.center) <= sphere.radius * sphere.radius
+    }
+
+    // Möller–Trumbore intersection returning the distance along the ray.
+    // Triangles are double sided since culling depends on the material.
+    fn intersect_triangle(&self, [v0, v1, v2]: [Vec3; 3]) -> Option<f32> {
+        let edge1 = v1 - v0;
+        let edge2 = v2 - v0;
+        let p = self.direction.cross(edge2);
+        let determinant = edge1.dot(p);
+        if determinant.abs() < f32::EPSILON {
+            return None;
+        }
+
+        let inv_determinant = 1.0 / determinant;
+        let s = self.origin - v0;
+        let u = s.dot(p) * inv_determinant;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let q = s.cross(edge1);
+        let v = self.direction.dot(q) * inv_determinant;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let t = edge2.dot(q) * inv_determinant;
+        (t >= 0.0).then_some(t)
+    }
+}
+
+/// Finds the closest visible mesh under the screen `position` in physical pixels
+/// with the origin at the top left corner of the screen.
+///
+/// Meshes use the current animated pose from [RenderModel::apply_anims] or [RenderModel::apply_rest_pose].
+/// This assumes parenting and skinning are enabled in [SkinningSettings](crate::SkinningSettings).
+pub fn pick_mesh(
+    position: Vec2,
+    camera: &CameraTransforms,
+    render_models: &[RenderModel],
+) -> Option<MeshHit> {
+    let ray = Ray::from_screen(position, camera)?;
+
+    render_models
+        .iter()
+        .enumerate()
+        .filter(|(_, model)| model.is_visible)
+        .flat_map(|(model_index, model)| {
+            model
+                .meshes
+                .iter()
+                .enumerate()
+                .filter(|(_, mesh)| mesh.is_visible && mesh.is_lod_visible())
+                .filter_map(move |(mesh_index, mesh)| {
+                    // Avoid checking every triangle for meshes not under the cursor.
+                    if !model
+                        .culling_sphere(mesh)
+                        .map(|s| ray.intersects_sphere(&s))
+                        .unwrap_or(true)
+                    {
+                        return None;
+                    }
+
+                    let positions = model.animated_positions(mesh);
+                    let distance = closest_triangle_hit(&ray, &positions, &mesh.picking.indices)?;
+
+                    Some(MeshHit {
+                        model_index,
+                        mesh_index,
+                        mesh_name: mesh.name.clone(),
+                        subindex: mesh.subindex,
+                        position: ray.origin + ray.direction * distance,
+                        distance,
+                    })
+                })
+        })
+        .min_by(|a, b| a.distance.total_cmp(&b.distance))
+}
+
+/// Finds the closest bone under the screen `position` in physical pixels
+/// with the origin at the top left corner of the screen.
+///
+/// This uses the same regions as [RenderModel::bone_hit_regions] for all visible models.
+pub fn pick_bone(
+    position: Vec2,
+    camera: &CameraTransforms,
+    render_models: &[RenderModel],
+    filter: &BoneFilter,
+) -> Option<BoneHit> {
+    let (model_indices, regions): (Vec<_>, Vec<_>) = render_models
+        .iter()
+        .enumerate()
+        .filter(|(_, model)| model.is_visible)
+        .flat_map(|(model_index, model)| {
+            model
+                .bone_hit_regions(camera, filter)
+                .into_iter()
+                .map(move |region| (model_index, region))
+        })
+        .unzip();
+
+    // Check all models at once to find the closest bone to the camera.
+    let hit = crate::hit_test(&regions, position)?;
+    let index = regions.iter().position(|r| std::ptr::eq(r, hit))?;
+
+    Some(BoneHit {
+        model_index: model_indices[index],
+        bone_index: hit.bone_index,
+    })
+}
+
+fn closest_triangle_hit(ray: &Ray, positions: &[Vec3], indices: &[u32]) -> Option<f32> {
+    indices
+        .chunks_exact(3)
+        .filter_map(|triangle| {
+            let v0 = positions.get(triangle[0] as usize)?;
+            let v1 = positions.get(triangle[1] as usize)?;
+            let v2 = positions.get(triangle[2] as usize)?;
+            ray.intersect_triangle([*v0, *v1, *v2])
+        })
+        .min_by(|a, b| a.total_cmp(b))
+}
+
+// This should match the vertex transforms in skinning.wgsl.
+pub(crate) fn skin_positions(
+    geometry: &PickingGeometry,
+    parent_transform: Option<glam::Mat4>,
+    skinning_transforms: &[glam::Mat4],
+) -> Vec<Vec3> {
+    geometry
+        .positions
+        .iter()
+        .enumerate()
+        .map(|(i, position)| {
+            match geometry.weights.get(i) {
+                // Skinning replaces any parent transforms.
+                Some(influence) if influence.bone_indices.x >= 0 => (0..4)
+                    .filter_map(|j| {
+                        let bone_index = usize::try_from(influence.bone_indices[j]).ok()?;
+                        // Only 511 influences are supported in game.
+                        let transform = skinning_transforms
+                            .get(bone_index)
+                            .filter(|_| bone_index < 511)?;
+                        Some(transform.transform_point3(*position) * influence.weights[j])
+                    })
+                    .sum(),
+                _ => parent_transform
+                    .map(|t| t.transform_point3(*position))
+                    .unwrap_or(*position),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use glam::Mat4;
+
+    use crate::assert_vector_relative_eq;
+
+    fn camera(width: f32, height: f32) -> CameraTransforms {
+        let model_view_matrix = Mat4::look_at_rh(Vec3::new(0.0, 0.0, 10.0), Vec3::ZERO, Vec3::Y);
+        let projection_matrix = Mat4::perspective_rh(0.5, width / height, 0.1, 100.0);
+        let mvp_matrix = projection_matrix * model_view_matrix;
+        CameraTransforms {
+            model_view_matrix,
+            projection_matrix,
+            mvp_matrix,
+            mvp_inv_matrix: mvp_matrix.inverse(),
+            camera_pos: glam::vec4(0.0, 0.0, 10.0, 1.0),
+            screen_dimensions: glam::vec4(width, height, 1.0, 0.0),
+        }
+    }
+
+    #[test]
+    fn ray_from_screen_center() {
+        let ray = Ray::from_screen(Vec2::new(100.0, 50.0), &camera(200.0, 100.0)).unwrap();
+        assert_vector_relative_eq!([0.0, 0.0, -1.0], ray.direction.to_array());
+        assert_vector_relative_eq!([0.0, 0.0, 9.9], ray.origin.to_array());
+    }
+
+    #[test]
+    fn ray_from_screen_empty_dimensions() {
+        assert_eq!(None, Ray::from_screen(Vec2::ZERO, &camera(0.0, 0.0)));
+    }
+
+    #[test]
+    fn ray_intersect_triangle() {
+        let ray = Ray {
+            origin: Vec3::new(0.25, 0.25, 5.0),
+            direction: Vec3::NEG_Z,
+        };
+        let triangle = [Vec3::ZERO, Vec3::X, Vec3::Y];
+        assert_eq!(Some(5.0), ray.intersect_triangle(triangle));
+
+        // Triangles are double sided.
+        let reversed = [Vec3::ZERO, Vec3::Y, Vec3::X];
+        assert_eq!(Some(5.0), ray.intersect_triangle(reversed));
+
+        // Outside the triangle.
+        let ray = Ray {
+            origin: Vec3::new(1.0, 1.0, 5.0),
+            direction: Vec3::NEG_Z,
+        };
+        assert_eq!(None, ray.intersect_triangle(triangle));
+
+        // Behind the ray origin.
+        let ray = Ray {
+            origin: Vec3::new(0.25, 0.25, -5.0),
+            direction: Vec3::NEG_Z,
+        };
+        assert_eq!(None, ray.intersect_triangle(triangle));
+    }
+
+    #[test]
+    fn closest_triangle_hit_multiple() {
+        let ray = Ray {
+            origin: Vec3::new(0.25, 0.25, 5.0),
+            direction: Vec3::NEG_Z,
+        };
+        let positions = [
+            Vec3::ZERO,
+            Vec3::X,
+            Vec3::Y,
+            Vec3::new(0.0, 0.0, 2.0),
+            Vec3::new(1.0, 0.0, 2.0),
+            Vec3::new(0.0, 1.0, 2.0),
+        ];
+        // Out of range indices are skipped.
+        let indices = [0, 1, 2, 3, 4, 5, 0, 1, 100];
+        assert_eq!(Some(3.0), closest_triangle_hit(&ray, &positions, &indices));
+    }
+
+    #[test]
+    fn skin_positions_weights_and_parent() {
+        let geometry = PickingGeometry {
+            positions: vec![Vec3::ONE, Vec3::ONE, Vec3::ONE],
+            indices: Vec::new(),
+            weights: vec![
+                VertexWeight {
+                    bone_indices: glam::ivec4(0, 1, -1, -1),
+                    weights: glam::vec4(0.5, 0.5, 0.0, 0.0),
+                },
+                VertexWeight {
+                    bone_indices: glam::IVec4::splat(-1),
+                    weights: glam::Vec4::ZERO,
+                },
+            ],
+        };
+        let skinning_transforms = [
+            Mat4::from_translation(Vec3::X),
+            Mat4::from_translation(Vec3::Y),
+        ];
+        let parent = Mat4::from_translation(Vec3::Z);
+
+        let positions = skin_positions(&geometry, Some(parent), &skinning_transforms);
+        assert_vector_relative_eq!([1.5, 1.5, 1.0], positions[0].to_array());
+        // Vertices without influences use the parent transform.
+        assert_vector_relative_eq!([1.0, 1.0, 2.0], positions[1].to_array());
+        assert_vector_relative_eq!([1.0, 1.0, 2.0], positions[2].to_array());
+    }
+}
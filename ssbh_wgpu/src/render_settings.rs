@@ -1,5 +1,7 @@
 use strum::{Display, EnumIter, EnumString};
 
+use crate::animation::lighting::LightingPreset;
+
 // TODO: Separate modes for selecting parameters by index (ex: Booleans[3])?
 /// Model rendering modes supported by [SsbhRenderer](crate::SsbhRenderer).
 #[derive(PartialEq, Eq, Copy, Clone, Display, EnumIter, EnumString)]
@@ -54,7 +56,62 @@ pub enum DebugMode {
     /// The color after applying textures and materials but without any lighting.
     Unlit,
     /// Relative shader complexity based on instruction count.
+    /// See [RenderSettings::shader_complexity_heatmap_value] for the colormap scale.
     ShaderComplexity,
+    /// The albedo color after applying textures and materials but without any lighting or emission.
+    Albedo,
+    /// The diffuse contribution from the direct light including shadows.
+    DirectDiffuse,
+    /// The specular contribution from the direct light including shadows.
+    DirectSpecular,
+    /// The diffuse ambient lighting and specular environment reflections.
+    Ambient,
+    /// The emission contribution.
+    Emission,
+    /// A heat map of the number of overlapping fragments for each pixel.
+    /// This ignores depth testing to include meshes hidden behind other meshes.
+    Overdraw,
+    /// The mip level sampled for the texture in [RenderSettings::mip_level_texture].
+    /// Red is the full resolution texture with smaller mips going from yellow to green to blue.
+    /// Magnified textures blend from red to white.
+    MipLevel,
+    /// A heat map of the texel density of Texture0 on the map1 UVs compared to [RenderSettings::texel_density].
+    /// Stretched UVs with too few texels per unit are blue, and UVs with too many texels per unit are red.
+    TexelDensity,
+}
+
+impl DebugMode {
+    /// Returns `true` if the mode uses the shadows, lighting, and post processing of [DebugMode::Shaded].
+    /// This includes the modes isolating individual shading terms like [DebugMode::DirectDiffuse].
+    pub fn is_shaded(&self) -> bool {
+        matches!(
+            self,
+            DebugMode::Shaded
+                | DebugMode::Albedo
+                | DebugMode::DirectDiffuse
+                | DebugMode::DirectSpecular
+                | DebugMode::Ambient
+                | DebugMode::Emission
+        )
+    }
+
+    /// The in game scale factor applied to the vertex color values for the color set modes like [DebugMode::ColorSet1].
+    /// Returns `None` for modes that don't display vertex colors.
+    ///
+    /// Color sets are stored as bytes in the range `0.0` to `1.0` and scaled in the shaders to support larger values.
+    /// The values for [DebugMode::ColorSet2] are squared before scaling.
+    pub fn color_set_scale(&self) -> Option<f32> {
+        match self {
+            DebugMode::ColorSet1 => Some(2.0),
+            DebugMode::ColorSet2 => Some(7.0),
+            DebugMode::ColorSet3 => Some(2.0),
+            DebugMode::ColorSet4 => Some(2.0),
+            DebugMode::ColorSet5 => Some(3.0),
+            DebugMode::ColorSet6 => Some(3.0),
+            DebugMode::ColorSet7 => Some(1.0),
+            _ => None,
+        }
+    }
 }
 
 /// The secondary material for material transitions when using [DebugMode::Shaded].
@@ -70,6 +127,144 @@ pub enum TransitionMaterial {
     Ditto,
 }
 
+/// A scripted change in [RenderSettings::transition_factor] over time for previewing material transitions.
+///
+/// In game, transitions like the metal box gradually blend between the regular and transition materials.
+/// Start a curve with [SsbhRenderer::animate_transition](crate::SsbhRenderer::animate_transition).
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct TransitionCurve {
+    /// The transition factor at the start of the curve.
+    pub start: f32,
+    /// The transition factor at the end of the curve.
+    pub end: f32,
+    /// The length of the transition in seconds.
+    pub duration: f32,
+    /// The value of [RenderSettings::time_seconds] when the transition starts.
+    pub start_time: f32,
+}
+
+impl TransitionCurve {
+    /// The transition factor at `time_seconds` with smooth easing at the start and end.
+    /// Times after the end of the curve use the [end](#structfield.end) value.
+    pub fn factor(&self, time_seconds: f32) -> f32 {
+        let t = if self.duration > 0.0 {
+            ((time_seconds - self.start_time) / self.duration).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        let t = t * t * (3.0 - 2.0 * t);
+        self.start + (self.end - self.start) * t
+    }
+
+    /// Returns `true` if the curve has reached its [end](#structfield.end) value at `time_seconds`.
+    pub fn is_finished(&self, time_seconds: f32) -> bool {
+        time_seconds >= self.start_time + self.duration
+    }
+}
+
+/// The order for drawing meshes in [SsbhRenderer::render_models](crate::SsbhRenderer::render_models).
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default, Display, EnumIter, EnumString)]
+pub enum DrawOrder {
+    /// Draw meshes grouped by shader label tag like "opaque" or "sort"
+    /// and sorted to reduce state changes within each tag.
+    /// This matches the in game render passes.
+    #[default]
+    Sorted,
+    /// Draw all meshes in the order of the mesh objects in the numshb without sorting.
+    /// This is useful for debugging effects that depend on draw order.
+    Authored,
+}
+
+/// The UV map displayed by [SsbhRenderer::render_models_uv](crate::SsbhRenderer::render_models_uv).
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default, Display, EnumIter, EnumString)]
+pub enum UvMap {
+    /// The map1 attribute used by most textures.
+    #[default]
+    Map1,
+    /// The bake1 attribute used for baked lighting like stage lightmaps.
+    Bake1,
+    /// The uvSet attribute.
+    UvSet,
+    /// The uvSet1 attribute.
+    UvSet1,
+    /// The uvSet2 attribute.
+    UvSet2,
+}
+
+/// Options for [SsbhRenderer::render_uv_layout](crate::SsbhRenderer::render_uv_layout).
+#[derive(Debug, PartialEq, Clone)]
+pub struct UvLayoutOptions {
+    /// Draw each mesh in a different color instead of white when `true`.
+    /// This helps distinguish UV islands from different meshes.
+    pub mesh_colors: bool,
+    /// Smooth the wireframe edges using multisampling when `true`.
+    pub antialiasing: bool,
+    /// The RGBA background color.
+    pub background_color: [f64; 4],
+}
+
+impl Default for UvLayoutOptions {
+    fn default() -> Self {
+        Self {
+            mesh_colors: false,
+            antialiasing: true,
+            background_color: [0.0; 4],
+        }
+    }
+}
+
+/// Modes for comparing materials from [RenderModel::recreate_comparison_materials](crate::RenderModel::recreate_comparison_materials).
+///
+/// Models without comparison materials use their regular materials.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum MaterialComparison {
+    /// Draw the regular materials left of the split and the comparison materials right of the split.
+    /// The split position is a fraction of the viewport width from `0.0` to `1.0`.
+    Split(f32),
+    /// Draw only the comparison materials.
+    Comparison,
+}
+
+/// The appearance of meshes with an invalid shader label or missing required attributes.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default, Display, EnumIter, EnumString)]
+pub enum InvalidMeshStyle {
+    /// A screen space checkerboard with the error color similar to the in game error shaders.
+    #[default]
+    Checkerboard,
+    /// A flat error color.
+    Solid,
+    /// Simple shading with the error color as the albedo.
+    /// Use a neutral color like gray for cleaner screenshots.
+    Shaded,
+}
+
+/// The level of detail for mesh objects with LOD variants like `"body_LOD1"`.
+/// See [RenderModel::update_lod](crate::RenderModel::update_lod).
+///
+/// Meshes without a LOD tag use level 0 for the highest detail.
+/// Levels missing from a group of LOD variants use the closest lower level.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub enum LodMode {
+    /// Select the level for each group based on its size on screen.
+    Auto,
+    /// Draw the given level for each group.
+    ForceLod(usize),
+    /// Draw only the highest detail level for each group.
+    #[default]
+    HighestOnly,
+}
+
+/// The skeleton pose for a [RenderModel](crate::RenderModel) without any animations.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default, Display, EnumIter, EnumString)]
+pub enum RestPose {
+    /// The bone transforms from the skel.
+    #[default]
+    Skel,
+    /// The bone transforms from the skel with hlpb constraints applied.
+    /// This more closely matches the neutral pose in game for models with helper bones.
+    Constrained,
+}
+
 /// Settings for configuring the rendered output of an [crate::SsbhRenderer].
 /// These settings modify internal WGPU state and should only be updated as needed.
 #[derive(PartialEq, Clone, Copy)]
@@ -86,12 +281,29 @@ pub struct RenderSettings {
     pub render_specular: bool,
     pub render_emission: bool,
     pub render_rim_lighting: bool,
+    /// The scale applied to the stage's rim lighting for [render_rim_lighting](#structfield.render_rim_lighting).
+    /// The default of `1.0` uses the intensity from the stage lighting unmodified.
+    pub rim_light_intensity: f32,
+    /// The linear RGB color multiplied with the stage's rim light color.
+    /// The default of white uses the color from the stage lighting unmodified.
+    pub rim_light_color: [f32; 3],
     pub render_shadows: bool,
+    /// The linear RGB fraction of the direct light that remains in fully shadowed areas
+    /// for [render_shadows](#structfield.render_shadows).
+    ///
+    /// The default of black completely removes direct lighting in shadowed areas.
+    /// [RenderProfile::Character] uses a blue tint approximating the character shadows on training stage.
+    pub shadow_tint: [f32; 3],
     pub render_bloom: bool,
     pub render_vertex_color: bool,
     /// Apply the in game scale factors such as `2.0` for colorSet1 when `true`.
     /// This applies to all modes including [DebugMode::Shaded].
+    /// See [RenderSettings::vertex_color_scale] for the scale used by the current [DebugMode].
     pub scale_vertex_color: bool,
+    /// Display the color set modes like [DebugMode::ColorSet1] as sRGB when `true`.
+    /// The displayed colors will match the stored values in external programs.
+    /// Otherwise, values are treated as linear and will appear brighter.
+    pub vertex_color_gamma: bool,
     pub render_rgba: [bool; 4],
     /// Replaces the RGBA channels of the nor map (Texture4) with a default when false.
     pub render_nor: [bool; 4],
@@ -99,30 +311,98 @@ pub struct RenderSettings {
     pub render_prm: [bool; 4],
     /// Use a UV test pattern for UV debug modes when `true`. Otherwise, display UVs as RGB colors.
     pub use_uv_pattern: bool,
+    /// The UV map to display with [SsbhRenderer::render_models_uv](crate::SsbhRenderer::render_models_uv).
+    pub uv_map: UvMap,
+    /// Draw filled UV faces with additive blending instead of UV edges when `true`.
+    /// Overlapping UV islands appear brighter, which is useful for checking lightmap UVs with [UvMap::Bake1].
+    pub uv_coverage: bool,
+    /// The texture slot like `0` for Texture0 to visualize with [DebugMode::MipLevel].
+    /// Cube map slots like Texture2 are not supported.
+    pub mip_level_texture: u32,
+    /// The target texels per world unit for [DebugMode::TexelDensity].
+    pub texel_density: f32,
+    /// The complexity for the start of the colormap for [DebugMode::ShaderComplexity].
+    /// Shaders with lower complexity use the same color as the minimum.
+    pub shader_complexity_min: f32,
+    /// The complexity for the end of the colormap for [DebugMode::ShaderComplexity].
+    /// Shaders with higher complexity use the same color as the maximum.
+    pub shader_complexity_max: f32,
+    /// The appearance of meshes that can't be rendered with their assigned shader.
+    pub invalid_mesh_style: InvalidMeshStyle,
+    /// The linear RGB color for meshes with an unrecognized shader label.
+    pub invalid_shader_color: [f32; 3],
+    /// The linear RGB color for meshes missing vertex attributes required by the shader.
+    pub invalid_attributes_color: [f32; 3],
+    /// The elapsed time in seconds for time driven effects like sprite sheets.
+    /// These effects animate even if no material animation is applied.
+    /// Scrolling UVs and pulsing emission are not time driven since the in game parameters are unknown.
+    /// Update this each frame with [SsbhRenderer::update_time](crate::SsbhRenderer::update_time).
+    pub time_seconds: f32,
 }
 
 impl From<&RenderSettings> for crate::shader::model::RenderSettings {
     fn from(r: &RenderSettings) -> Self {
         Self {
-            debug_mode: glam::UVec4::splat(r.debug_mode as u32),
+            debug_mode: glam::uvec4(r.debug_mode as u32, r.mip_level_texture, 0, 0),
             transition_material: glam::UVec4::splat(r.transition_material as u32),
             transition_factor: glam::vec4(r.transition_factor, 0.0, 0.0, 0.0),
             render_diffuse: glam::UVec4::splat(r.render_diffuse as u32),
             render_specular: glam::UVec4::splat(r.render_specular as u32),
             render_emission: glam::UVec4::splat(r.render_emission as u32),
             render_rim_lighting: glam::UVec4::splat(r.render_rim_lighting as u32),
+            rim_light: glam::Vec3::from(r.rim_light_color).extend(r.rim_light_intensity),
             render_shadows: glam::UVec4::splat(r.render_shadows as u32),
+            shadow_tint: glam::Vec3::from(r.shadow_tint).extend(0.0),
             render_bloom: glam::UVec4::splat(r.render_bloom as u32),
             render_vertex_color: glam::UVec4::splat(r.render_vertex_color as u32),
-            scale_vertex_color: glam::UVec4::splat(r.scale_vertex_color as u32),
+            scale_vertex_color: glam::uvec4(
+                r.scale_vertex_color as u32,
+                r.vertex_color_gamma as u32,
+                0,
+                0,
+            ),
             render_rgba: r.render_rgba.map(|b| if b { 1.0 } else { 0.0 }).into(),
             render_nor: r.render_nor.map(|b| b as u32).into(),
             render_prm: r.render_prm.map(|b| b as u32).into(),
             render_uv_pattern: glam::UVec4::splat(r.use_uv_pattern as u32),
+            uv_map: glam::uvec4(r.uv_map as u32, 0, 0, 0),
+            texel_density: glam::vec4(r.texel_density, 0.0, 0.0, 0.0),
+            invalid_mesh_style: glam::UVec4::splat(r.invalid_mesh_style as u32),
+            invalid_shader_color: glam::Vec3::from(r.invalid_shader_color).extend(1.0),
+            invalid_attributes_color: glam::Vec3::from(r.invalid_attributes_color).extend(1.0),
+            // The game updates effects at 60 frames per second.
+            time: glam::vec4(r.time_seconds, r.time_seconds * 60.0, 0.0, 0.0),
+            shader_complexity_range: glam::vec4(
+                r.shader_complexity_min,
+                r.shader_complexity_max,
+                0.0,
+                0.0,
+            ),
         }
     }
 }
 
+impl RenderSettings {
+    /// The scale factor currently applied to the vertex colors displayed by [RenderSettings::debug_mode].
+    /// This is `1.0` if [RenderSettings::scale_vertex_color] is `false`.
+    /// Returns `None` if the debug mode doesn't display vertex colors.
+    pub fn vertex_color_scale(&self) -> Option<f32> {
+        self.debug_mode
+            .color_set_scale()
+            .map(|scale| if self.scale_vertex_color { scale } else { 1.0 })
+    }
+
+    /// The position from `0.0` to `1.0` on the colormap for [DebugMode::ShaderComplexity]
+    /// for a shader with the given `complexity` like from [ShaderDatabase::complexity](crate::ShaderDatabase::complexity).
+    ///
+    /// Values outside the range from [RenderSettings::shader_complexity_min]
+    /// to [RenderSettings::shader_complexity_max] are clamped to match the shader.
+    pub fn shader_complexity_heatmap_value(&self, complexity: f64) -> f32 {
+        let range = (self.shader_complexity_max - self.shader_complexity_min).max(1e-8);
+        ((complexity as f32 - self.shader_complexity_min) / range).clamp(0.0, 1.0)
+    }
+}
+
 impl Default for RenderSettings {
     fn default() -> Self {
         Self {
@@ -133,18 +413,219 @@ impl Default for RenderSettings {
             render_specular: true,
             render_emission: true,
             render_rim_lighting: true,
+            rim_light_intensity: 1.0,
+            rim_light_color: [1.0; 3],
             render_shadows: true,
+            shadow_tint: [0.0; 3],
             render_bloom: true,
             render_vertex_color: true,
             scale_vertex_color: true,
+            vertex_color_gamma: false,
             render_rgba: [true; 4],
             render_nor: [true; 4],
             render_prm: [true; 4],
             use_uv_pattern: true,
+            uv_map: UvMap::Map1,
+            uv_coverage: false,
+            mip_level_texture: 0,
+            texel_density: 128.0,
+            // The min complexity isn't 0.0 since shaders are all non empty.
+            shader_complexity_min: 0.10816174646489705,
+            shader_complexity_max: 1.0,
+            invalid_mesh_style: InvalidMeshStyle::Checkerboard,
+            invalid_shader_color: [1.0, 0.0, 0.0],
+            invalid_attributes_color: [1.0, 1.0, 0.0],
+            time_seconds: 0.0,
+        }
+    }
+}
+
+/// Settings for the depth of field pass applied before bloom by [SsbhRenderer](crate::SsbhRenderer).
+///
+/// Stages configure depth of field separately, so this is disabled by default.
+/// Camera animations don't animate these values since the in game camera tracks
+/// for depth of field are unknown.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct DofSettings {
+    pub enabled: bool,
+    /// The distance from the camera that is completely in focus.
+    pub focus_distance: f32,
+    /// The distance from the [focus_distance](#structfield.focus_distance) where the blur reaches its maximum.
+    pub focus_range: f32,
+    /// The maximum blur radius as a fraction of the screen height.
+    pub blur_strength: f32,
+}
+
+impl DofSettings {
+    pub(crate) fn uniforms(
+        &self,
+        projection_matrix: glam::Mat4,
+    ) -> crate::shader::dof::DofSettings {
+        crate::shader::dof::DofSettings {
+            focus: glam::vec4(
+                self.focus_distance,
+                self.focus_range,
+                if self.enabled {
+                    self.blur_strength
+                } else {
+                    0.0
+                },
+                0.0,
+            ),
+            // The view distance is m32 / (depth + m22) for a perspective projection.
+            depth_params: glam::vec4(
+                projection_matrix.z_axis.z,
+                projection_matrix.w_axis.z,
+                0.0,
+                0.0,
+            ),
+        }
+    }
+}
+
+impl Default for DofSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            focus_distance: 60.0,
+            focus_range: 100.0,
+            blur_strength: 0.01,
         }
     }
 }
 
+/// Operators for mapping the HDR scene colors to the output in the post processing pass.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Display, EnumIter, EnumString)]
+pub enum ToneMapping {
+    /// An approximation of the in game color grading using the color grading LUT.
+    InGame,
+    /// Skip tone mapping and color grading and output the linear colors.
+    None,
+    /// A filmic curve that preserves more detail in highlights.
+    /// The color grading LUT is not applied.
+    Filmic,
+}
+
+/// Settings for the final post processing pass of [SsbhRenderer](crate::SsbhRenderer).
+///
+/// The defaults match the in game post processing.
+/// Adjust these values to match screenshots against in game captures.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct PostProcessSettings {
+    /// The exposure adjustment in stops applied before tone mapping.
+    /// Each increase of `1.0` doubles the brightness.
+    pub exposure: f32,
+    /// The gamma applied after tone mapping.
+    /// Values greater than `1.0` brighten the midtones.
+    pub gamma: f32,
+    pub tone_mapping: ToneMapping,
+}
+
+impl PostProcessSettings {
+    pub(crate) fn uniforms(
+        &self,
+        transparent_background: bool,
+    ) -> crate::shader::post_process::PostProcessSettings {
+        crate::shader::post_process::PostProcessSettings {
+            exposure_gamma: glam::vec4(
+                self.exposure.exp2(),
+                1.0 / self.gamma.max(0.0001),
+                0.0,
+                0.0,
+            ),
+            tone_mapping: glam::uvec4(
+                match self.tone_mapping {
+                    ToneMapping::InGame => 0,
+                    ToneMapping::None => 1,
+                    ToneMapping::Filmic => 2,
+                },
+                transparent_background as u32,
+                0,
+                0,
+            ),
+        }
+    }
+}
+
+impl Default for PostProcessSettings {
+    fn default() -> Self {
+        Self {
+            exposure: 0.0,
+            gamma: 1.0,
+            tone_mapping: ToneMapping::InGame,
+        }
+    }
+}
+
+/// Common rendering configurations for the different types of models in game.
+/// Apply a profile with [SsbhRenderer::apply_profile](crate::SsbhRenderer::apply_profile).
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Display, EnumIter, EnumString)]
+pub enum RenderProfile {
+    /// Fighters and other character models with fitted shadows and no depth of field.
+    Character,
+    /// Stage models with depth of field and the shadow transforms from the stage lighting.
+    Stage,
+    /// Item and stock icons with neutral lighting, no shadows or bloom, and a transparent background.
+    ItemIcon,
+}
+
+// The fraction of direct lighting remaining in character shadows on training stage.
+const TRAINING_SHADOW_TINT: [f32; 3] = [0.05, 0.08, 0.15];
+
+impl RenderProfile {
+    /// The settings for this profile.
+    /// Individual settings can be overridden before calling
+    /// [SsbhRenderer::apply_profile](crate::SsbhRenderer::apply_profile).
+    pub fn settings(&self) -> ProfileSettings {
+        let character = ProfileSettings {
+            render_settings: RenderSettings {
+                shadow_tint: TRAINING_SHADOW_TINT,
+                ..Default::default()
+            },
+            dof_settings: DofSettings::default(),
+            shadow_fitting: true,
+            lighting_preset: None,
+            clear_color: None,
+        };
+
+        match self {
+            RenderProfile::Character => character,
+            RenderProfile::Stage => ProfileSettings {
+                dof_settings: DofSettings {
+                    enabled: true,
+                    ..Default::default()
+                },
+                shadow_fitting: false,
+                ..character
+            },
+            RenderProfile::ItemIcon => ProfileSettings {
+                render_settings: RenderSettings {
+                    render_shadows: false,
+                    render_bloom: false,
+                    ..Default::default()
+                },
+                lighting_preset: Some(LightingPreset::BrightFrontal),
+                clear_color: Some([0.0; 4]),
+                ..character
+            },
+        }
+    }
+}
+
+/// Settings applied together by [SsbhRenderer::apply_profile](crate::SsbhRenderer::apply_profile).
+/// Create the settings for a [RenderProfile] with [RenderProfile::settings].
+#[derive(PartialEq, Clone, Copy)]
+pub struct ProfileSettings {
+    pub render_settings: RenderSettings,
+    pub dof_settings: DofSettings,
+    /// See [SsbhRenderer::set_shadow_fitting](crate::SsbhRenderer::set_shadow_fitting).
+    pub shadow_fitting: bool,
+    /// The stage lighting to apply or `None` to keep the current lighting.
+    pub lighting_preset: Option<LightingPreset>,
+    /// The viewport background color or `None` to keep the current color.
+    pub clear_color: Option<[f64; 4]>,
+}
+
 /// Settings for configuring vertex skinning and skeletal animation rendering.
 /// These settings modify internal WGPU state and should only be updated as needed.
 #[derive(PartialEq, Clone, Copy)]
@@ -171,10 +652,41 @@ impl Default for SkinningSettings {
     }
 }
 
+/// The method for drawing blended meshes in the `"sort"` render pass.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default, Display, EnumIter, EnumString)]
+pub enum TransparencyMode {
+    /// Sort blended meshes by their sort bias and draw them in order like in game.
+    /// Overlapping meshes may blend in the wrong order or change order while moving the camera.
+    #[default]
+    Sorted,
+    /// Blend meshes without sorting using weighted blended order independent transparency.
+    /// This avoids popping artifacts for overlapping meshes but only approximates the blended colors.
+    ///
+    /// Only meshes using alpha blending with depth testing are affected.
+    /// This has no effect for [DrawOrder::Authored].
+    WeightedBlended,
+}
+
+/// A material drawn in a solid color by [ModelRenderOptions::material_masks].
+#[derive(Debug, PartialEq, Clone)]
+pub struct MaterialMask {
+    /// The index of the model in the models passed to [SsbhRenderer::render_models](crate::SsbhRenderer::render_models).
+    pub model_index: usize,
+    pub material_label: String,
+    /// The RGB color of the mask.
+    pub color: [f32; 3],
+}
+
+impl MaterialMask {
+    /// The color for [ModelRenderOptions::mask_material_label].
+    pub const DEFAULT_COLOR: [f32; 3] = [0.0, 1.0, 1.0];
+}
+
 /// Lightweight settings for configuring model rendering each frame.
 ///
 /// Renders materials in a solid color for the given `mask_model_index` and
 /// `mask_material_label`. Use `""` for disabling the mask.
+/// Use [material_masks](#structfield.material_masks) to mask multiple materials at once.
 #[derive(Debug, Default)]
 pub struct ModelRenderOptions {
     pub draw_bones: bool,
@@ -182,8 +694,227 @@ pub struct ModelRenderOptions {
     // TODO: Make these Option instead?
     pub mask_model_index: usize,
     pub mask_material_label: String,
+    /// Additional materials to draw in a solid color with a separate color for each mask.
+    /// This allows highlighting related materials like all materials using a texture.
+    /// Later masks draw on top of earlier masks for the same meshes.
+    pub material_masks: Vec<MaterialMask>,
     /// Draw a wireframe on shaded when `true` for all modes except [DebugMode::Shaded].
     pub draw_wireframe: bool,
     /// Draw an infinite grid on the XZ-axis when `true`.
     pub draw_floor_grid: bool,
+    /// Draw all visible meshes even if they are outside the camera's view when `true`.
+    /// This is mostly useful for debugging culling issues.
+    pub disable_frustum_culling: bool,
+    /// Draw the camera set with [SsbhRenderer::update_camera_gizmo](crate::SsbhRenderer::update_camera_gizmo) when `true`.
+    pub draw_camera_frustum: bool,
+    /// Draw arrows and spheres for the directions of the character and stage lights when `true`.
+    pub draw_light_gizmos: bool,
+    /// Draw the depth of opaque meshes sorted front to back before the model pass when `true`.
+    /// This reduces overdraw for scenes with many overlapping opaque meshes like stages.
+    ///
+    /// The pre-pass is skipped for alpha tested meshes and material comparisons.
+    pub depth_prepass: bool,
+    /// The method for drawing blended meshes in the `"sort"` render pass.
+    pub transparency: TransparencyMode,
+}
+
+impl ModelRenderOptions {
+    /// The model index, material label, and color for all masks in draw order.
+    pub(crate) fn masks(&self) -> Vec<(usize, &str, [f32; 3])> {
+        (!self.mask_material_label.is_empty())
+            .then_some((
+                self.mask_model_index,
+                self.mask_material_label.as_str(),
+                MaterialMask::DEFAULT_COLOR,
+            ))
+            .into_iter()
+            .chain(
+                self.material_masks
+                    .iter()
+                    .map(|m| (m.model_index, m.material_label.as_str(), m.color)),
+            )
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vertex_color_scale() {
+        let settings = |debug_mode, scale_vertex_color| RenderSettings {
+            debug_mode,
+            scale_vertex_color,
+            ..Default::default()
+        };
+        assert_eq!(None, settings(DebugMode::Shaded, true).vertex_color_scale());
+        assert_eq!(
+            Some(2.0),
+            settings(DebugMode::ColorSet1, true).vertex_color_scale()
+        );
+        assert_eq!(
+            Some(7.0),
+            settings(DebugMode::ColorSet2, true).vertex_color_scale()
+        );
+        assert_eq!(
+            Some(1.0),
+            settings(DebugMode::ColorSet5, false).vertex_color_scale()
+        );
+    }
+
+    #[test]
+    fn uniforms_uv_map() {
+        let settings = RenderSettings {
+            uv_map: UvMap::Bake1,
+            ..Default::default()
+        };
+        assert_eq!(
+            glam::uvec4(1, 0, 0, 0),
+            crate::shader::model::RenderSettings::from(&settings).uv_map
+        );
+    }
+
+    #[test]
+    fn uniforms_rim_light_shadow_tint() {
+        let settings = RenderSettings {
+            rim_light_intensity: 0.5,
+            rim_light_color: [1.0, 0.5, 0.25],
+            shadow_tint: [0.0, 0.25, 0.5],
+            ..Default::default()
+        };
+        let uniforms = crate::shader::model::RenderSettings::from(&settings);
+        assert_eq!(glam::vec4(1.0, 0.5, 0.25, 0.5), uniforms.rim_light);
+        assert_eq!(glam::vec4(0.0, 0.25, 0.5, 0.0), uniforms.shadow_tint);
+    }
+
+    #[test]
+    fn uniforms_default_shadow_tint() {
+        let uniforms = crate::shader::model::RenderSettings::from(&RenderSettings::default());
+        assert_eq!(glam::Vec4::ZERO, uniforms.shadow_tint);
+    }
+
+    #[test]
+    fn shader_complexity_heatmap_value() {
+        let settings = RenderSettings {
+            shader_complexity_min: 0.25,
+            shader_complexity_max: 0.75,
+            ..Default::default()
+        };
+        assert_eq!(0.0, settings.shader_complexity_heatmap_value(0.1));
+        assert_eq!(0.5, settings.shader_complexity_heatmap_value(0.5));
+        assert_eq!(1.0, settings.shader_complexity_heatmap_value(0.9));
+        assert_eq!(
+            glam::vec4(0.25, 0.75, 0.0, 0.0),
+            crate::shader::model::RenderSettings::from(&settings).shader_complexity_range
+        );
+    }
+
+    #[test]
+    fn model_render_options_masks() {
+        let options = ModelRenderOptions {
+            mask_model_index: 1,
+            mask_material_label: "a".to_string(),
+            material_masks: vec![MaterialMask {
+                model_index: 0,
+                material_label: "b".to_string(),
+                color: [1.0, 0.0, 0.0],
+            }],
+            ..Default::default()
+        };
+        assert_eq!(
+            vec![
+                (1, "a", MaterialMask::DEFAULT_COLOR),
+                (0, "b", [1.0, 0.0, 0.0])
+            ],
+            options.masks()
+        );
+
+        // The empty label disables the single mask.
+        assert!(ModelRenderOptions::default().masks().is_empty());
+    }
+
+    #[test]
+    fn dof_uniforms_depth_params() {
+        let projection = glam::Mat4::perspective_rh(0.5, 1.0, 1.0, 100.0);
+        let uniforms = DofSettings::default().uniforms(projection);
+
+        // Depth values of 0.0 and 1.0 should map to the near and far clip distances.
+        let distance = |depth: f32| uniforms.depth_params.y / (depth + uniforms.depth_params.x);
+        approx::assert_relative_eq!(1.0, distance(0.0), epsilon = 0.0001f32);
+        approx::assert_relative_eq!(100.0, distance(1.0), epsilon = 0.001f32);
+
+        // Disabling depth of field removes any blur.
+        assert_eq!(0.0, uniforms.focus.z);
+    }
+
+    #[test]
+    fn profile_settings() {
+        let character = RenderProfile::Character.settings();
+        assert!(!character.dof_settings.enabled);
+        assert!(character.shadow_fitting);
+        assert_eq!(TRAINING_SHADOW_TINT, character.render_settings.shadow_tint);
+        assert_eq!(None, character.clear_color);
+
+        let stage = RenderProfile::Stage.settings();
+        assert!(stage.dof_settings.enabled);
+        assert!(!stage.shadow_fitting);
+
+        let icon = RenderProfile::ItemIcon.settings();
+        assert!(!icon.render_settings.render_bloom);
+        assert!(!icon.render_settings.render_shadows);
+        assert_eq!(Some(LightingPreset::BrightFrontal), icon.lighting_preset);
+        assert_eq!(Some([0.0; 4]), icon.clear_color);
+    }
+
+    #[test]
+    fn post_process_uniforms() {
+        let uniforms = PostProcessSettings {
+            exposure: 1.0,
+            gamma: 2.0,
+            tone_mapping: ToneMapping::Filmic,
+        }
+        .uniforms(false);
+        assert_eq!(glam::vec4(2.0, 0.5, 0.0, 0.0), uniforms.exposure_gamma);
+        assert_eq!(glam::uvec4(2, 0, 0, 0), uniforms.tone_mapping);
+
+        // The defaults shouldn't modify the in game post processing.
+        let uniforms = PostProcessSettings::default().uniforms(false);
+        assert_eq!(glam::vec4(1.0, 1.0, 0.0, 0.0), uniforms.exposure_gamma);
+        assert_eq!(glam::UVec4::ZERO, uniforms.tone_mapping);
+    }
+
+    #[test]
+    fn post_process_uniforms_transparent_background() {
+        let uniforms = PostProcessSettings::default().uniforms(true);
+        assert_eq!(glam::uvec4(0, 1, 0, 0), uniforms.tone_mapping);
+    }
+
+    #[test]
+    fn transition_curve_factor() {
+        let curve = TransitionCurve {
+            start: 0.0,
+            end: 1.0,
+            duration: 2.0,
+            start_time: 1.0,
+        };
+        assert_eq!(0.0, curve.factor(0.0));
+        assert_eq!(0.0, curve.factor(1.0));
+        assert_eq!(0.5, curve.factor(2.0));
+        assert_eq!(1.0, curve.factor(3.0));
+        assert_eq!(1.0, curve.factor(10.0));
+        assert!(!curve.is_finished(2.0));
+        assert!(curve.is_finished(3.0));
+    }
+
+    #[test]
+    fn transition_curve_factor_zero_duration() {
+        let curve = TransitionCurve {
+            start: 1.0,
+            end: 0.0,
+            duration: 0.0,
+            start_time: 0.0,
+        };
+        assert_eq!(0.0, curve.factor(0.0));
+    }
 }
@@ -1,16 +1,30 @@
 use std::collections::HashSet;
 
 use crate::{
-    animation::lighting::animate_lighting,
+    animation::camera::CameraAnimValues,
+    animation::lighting::{
+        animate_lighting, fit_light_transform, LightingPreset, LightingSettings,
+    },
     bone_rendering::{BoneBuffers, BonePipelines},
+    capture::{
+        copy_output_to_buffer, downsample, is_bgra, read_buffer_rgba, CaptureSettings, Turntable,
+        TurntableSettings,
+    },
+    debug_gizmo::DebugGizmoRenderData,
     floor_grid::FloorGridRenderData,
-    model::pipeline::*,
+    gpu_timings::{GpuPass, GpuProfiler, GpuTimings, GPU_TIMING_FEATURES},
+    light_gizmo::LightGizmoRenderData,
+    model::{pipeline::*, BindCounts},
     render_settings::*,
+    scene_bounds,
     swing_rendering::swing_pipeline,
     texture::{load_default_lut, uv_pattern, TextureSamplerView},
-    CameraTransforms, DeviceBufferExt, QueueExt, RenderModel, ShaderDatabase,
+    transform_gizmo::TransformGizmoRenderData,
+    BoundingBox, CameraTransforms, DeviceBufferExt, Frustum, QueueExt, RenderMesh, RenderModel,
+    ShaderDatabase, SsbhWgpuError, TransformGizmo,
 };
 use glam::UVec4;
+use log::error;
 use nutexb_wgpu::NutexbFile;
 use ssbh_data::anim_data::AnimData;
 use wgpu::ComputePassDescriptor;
@@ -29,8 +43,16 @@ const BLOOM_COLOR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float
 
 // Alpha to coverage on metal requires a sample count above 1.
 // 4 is a widely supported value for MSAA samples.
+// The default for SharedRenderData can be changed with SharedRenderData::set_sample_count.
 pub const MSAA_SAMPLE_COUNT: u32 = 4;
 
+// The shader label tags for each render pass in the order they are drawn in game.
+const MODEL_PASS_TAGS: [&str; 4] = ["opaque", "far", "sort", "near"];
+
+// The weighted colors and coverage for order independent transparency.
+pub const OIT_ACCUMULATION_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+pub const OIT_REVEALAGE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R8Unorm;
+
 pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
 pub const DEPTH_STENCIL_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth24PlusStencil8;
 
@@ -40,6 +62,12 @@ const VARIANCE_SHADOW_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rg16Flo
 
 const SHADOW_MAP_WIDTH: u32 = 1024;
 const SHADOW_MAP_HEIGHT: u32 = 1024;
+// Each light has its own shadow map layer.
+// Layer 0 is for LightChr, and the remaining layers are for the 8 LightStg lights.
+const SHADOW_MAP_LAYER_COUNT: u32 = 9;
+
+// The distance from the scene center for light gizmos without scene bounds.
+const DEFAULT_LIGHT_DISTANCE: f32 = 20.0;
 
 // Halve the dimensions for additional smoothing.
 const VARIANCE_SHADOW_WIDTH: u32 = 512;
@@ -91,6 +119,38 @@ const STENCIL_COMPARE_EQUAL: wgpu::StencilState = wgpu::StencilState {
     write_mask: 0xff,
 };
 
+/// A rectangular region of the output in physical pixels with the origin in the top left corner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScissorRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// A region of the output with its own camera for [SsbhRenderer::render_viewports].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    pub rect: ScissorRect,
+    /// The camera for this viewport.
+    /// The projection should use the aspect ratio of `rect`.
+    pub camera: CameraTransforms,
+}
+
+/// Strategies for allocating intermediate textures when calling [SsbhRenderer::resize].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextureAllocation {
+    /// Recreate textures to exactly match the new size.
+    #[default]
+    Exact,
+    /// Round texture dimensions up to a multiple of the bucket size in pixels.
+    /// Textures are only recreated if the new size does not fit in the current textures.
+    /// This makes frequent resizes like dragging a window much cheaper.
+    ///
+    /// Use [SsbhRenderer::trim] to release unused texture memory.
+    Bucketed(u32),
+}
+
 /// A renderer for drawing a collection of [RenderModel].
 ///
 /// Create a renderer with [SsbhRenderer::new].
@@ -104,6 +164,8 @@ pub struct SsbhRenderer {
     bloom_combine_pipeline: wgpu::RenderPipeline,
     bloom_upscale_pipeline: wgpu::RenderPipeline,
     post_process_pipeline: wgpu::RenderPipeline,
+    post_process_transparent_pipeline: wgpu::RenderPipeline,
+    dof_pipeline: wgpu::RenderPipeline,
 
     // TODO: Group model related pipelines?
     skinning_pipeline: wgpu::ComputePipeline,
@@ -112,13 +174,21 @@ pub struct SsbhRenderer {
     variance_shadow_pipeline: wgpu::RenderPipeline,
     invalid_shader_pipeline: wgpu::RenderPipeline,
     invalid_attributes_pipeline: wgpu::RenderPipeline,
+    depth_prepass_pipelines: DepthPrepassPipelines,
+    oit_pipelines: OitPipelines,
+    oit_composite_pipeline: wgpu::RenderPipeline,
     debug_pipeline: wgpu::RenderPipeline,
+    overdraw_pipeline: wgpu::RenderPipeline,
+    overdraw_heat_map_pipeline: wgpu::RenderPipeline,
     silhouette_pipeline: wgpu::RenderPipeline,
     outline_pipeline: wgpu::RenderPipeline,
     uv_pipeline: wgpu::RenderPipeline,
+    uv_coverage_pipeline: wgpu::RenderPipeline,
     overlay_pipeline: wgpu::RenderPipeline,
+    overlay_transparent_pipeline: wgpu::RenderPipeline,
     wireframe_pipeline: wgpu::RenderPipeline,
     selected_material_pipeline: wgpu::RenderPipeline,
+    clear_pipeline: wgpu::RenderPipeline,
 
     swing_camera_bind_group: crate::shader::swing::bind_groups::BindGroup0,
     swing_pipeline: wgpu::RenderPipeline,
@@ -127,31 +197,65 @@ pub struct SsbhRenderer {
     bone_buffers: BoneBuffers,
 
     floor_grid: FloorGridRenderData,
+    light_gizmo: LightGizmoRenderData,
+    debug_gizmo: DebugGizmoRenderData,
+    transform_gizmo: TransformGizmoRenderData,
 
     // Store camera state for efficiently updating it later.
     // This avoids exposing shader implementations like bind groups.
     camera_buffer: wgpu::Buffer,
+    camera: CameraTransforms,
     stage_uniforms_buffer: wgpu::Buffer,
+    // Keep the unfitted light transforms to avoid accumulating changes from shadow fitting.
+    stage_uniforms: crate::shader::model::StageUniforms,
+    shadow_fitting: bool,
+    shadow_bounds: Option<BoundingBox>,
     per_frame_bind_group: crate::shader::model::bind_groups::BindGroup0,
     skeleton_camera_bind_group: crate::shader::skeleton::bind_groups::BindGroup0,
 
-    shadow_depth: TextureSamplerView,
-    variance_shadow: TextureSamplerView,
-    variance_bind_group: crate::shader::variance_shadow::bind_groups::BindGroup0,
+    shadow_maps: ShadowMaps,
 
     pass_info: PassInfo,
+    texture_allocation: TextureAllocation,
+    // The dimensions used for rendering, which may be smaller than the allocated textures.
+    width: u32,
+    height: u32,
+    scale_factor: f32,
 
     color_lut: TextureSamplerView,
 
     clear_color: [f64; 4],
+    transparent_background: bool,
+
+    scissor_rect: Option<ScissorRect>,
+
+    draw_order: DrawOrder,
+    material_comparison: Option<MaterialComparison>,
 
     render_settings: RenderSettings,
     render_settings_buffer: wgpu::Buffer,
+    transition_curve: Option<TransitionCurve>,
+
+    dof_settings: DofSettings,
+    dof_buffer: wgpu::Buffer,
+    dof_bind_group: crate::shader::dof::bind_groups::BindGroup1,
+
+    post_process_settings: PostProcessSettings,
+    post_process_buffer: wgpu::Buffer,
+    post_process_bind_group: crate::shader::post_process::bind_groups::BindGroup1,
 
     skinning_settings_buffer: wgpu::Buffer,
     skinning_settings_bind_group: crate::shader::skinning::bind_groups::BindGroup3,
 
     surface_format: wgpu::TextureFormat,
+
+    // Track state changes from drawing meshes for debugging performance.
+    bind_counts: std::cell::Cell<BindCounts>,
+
+    #[cfg(feature = "frame_stats")]
+    frame_stats: std::cell::Cell<crate::FrameStats>,
+
+    gpu_profiler: Option<GpuProfiler>,
 }
 
 impl SsbhRenderer {
@@ -166,6 +270,9 @@ impl SsbhRenderer {
     /// [wgpu::TextureFormat::Bgra8Unorm] or [wgpu::TextureFormat::Bgra8UnormSrgb] have the best compatibility.
     /// The final render pass will transform output colors accordingly
     /// depending on whether `surface_format` is an sRGB format or not.
+    ///
+    /// Model rendering uses [MSAA_SAMPLE_COUNT] samples by default to match [SharedRenderData](crate::SharedRenderData).
+    /// Change the sample count with [SsbhRenderer::set_sample_count].
     pub fn new(
         device: &wgpu::Device,
         queue: &wgpu::Queue,
@@ -175,15 +282,37 @@ impl SsbhRenderer {
         clear_color: [f64; 4],
         surface_format: wgpu::TextureFormat,
     ) -> Self {
+        let sample_count = MSAA_SAMPLE_COUNT;
+
         let shader = crate::shader::post_process::create_shader_module(device);
         let layout = crate::shader::post_process::create_pipeline_layout(device);
         let post_process_pipeline =
             create_screen_pipeline(device, &shader, &layout, "fs_main", RGBA_COLOR_FORMAT);
+        let post_process_transparent_pipeline = create_screen_pipeline_with_blend(
+            device,
+            &shader,
+            &layout,
+            "fs_main",
+            RGBA_COLOR_FORMAT,
+            None,
+        );
+        let overdraw_heat_map_pipeline =
+            create_screen_pipeline(device, &shader, &layout, "fs_overdraw", RGBA_COLOR_FORMAT);
+
+        let dof_pipeline = create_dof_pipeline(device, sample_count);
 
         let shader = crate::shader::overlay::create_shader_module(device);
         let layout = crate::shader::overlay::create_pipeline_layout(device);
         let overlay_pipeline =
             create_screen_pipeline(device, &shader, &layout, "fs_main", surface_format);
+        let overlay_transparent_pipeline = create_screen_pipeline_with_blend(
+            device,
+            &shader,
+            &layout,
+            "fs_main",
+            surface_format,
+            None,
+        );
 
         // Shared shaders for bloom passes.
         // TODO: Should this be all screen texture shaders?
@@ -203,8 +332,8 @@ impl SsbhRenderer {
         let bloom_combine_pipeline =
             create_screen_pipeline(device, &shader, &layout, "fs_main", RGBA_COLOR_FORMAT);
 
-        let skinning_pipeline = crate::shader::skinning::compute::create_main_pipeline(device);
-        let renormal_pipeline = crate::shader::renormal::compute::create_main_pipeline(device);
+        let skinning_pipeline = skinning_pipeline(device);
+        let renormal_pipeline = renormal_pipeline(device);
 
         let shadow_pipeline = depth_pipeline(device);
 
@@ -224,19 +353,21 @@ impl SsbhRenderer {
             scale_factor,
             &color_lut,
             surface_format,
+            sample_count,
         );
 
         // Assume the user will update the camera, so these values don't matter.
+        let camera = crate::shader::model::CameraTransforms {
+            model_view_matrix: glam::Mat4::IDENTITY,
+            projection_matrix: glam::Mat4::IDENTITY,
+            mvp_matrix: glam::Mat4::IDENTITY,
+            mvp_inv_matrix: glam::Mat4::IDENTITY,
+            camera_pos: glam::vec4(0.0, 0.0, -1.0, 1.0),
+            screen_dimensions: glam::vec4(1.0, 1.0, 1.0, 1.0),
+        };
         let camera_buffer = device.create_buffer_from_data(
             "Camera Buffer",
-            &[crate::shader::model::CameraTransforms {
-                model_view_matrix: glam::Mat4::IDENTITY,
-                projection_matrix: glam::Mat4::IDENTITY,
-                mvp_matrix: glam::Mat4::IDENTITY,
-                mvp_inv_matrix: glam::Mat4::IDENTITY,
-                camera_pos: glam::vec4(0.0, 0.0, -1.0, 1.0),
-                screen_dimensions: glam::vec4(1.0, 1.0, 1.0, 1.0),
-            }],
+            &[camera],
             wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
         );
 
@@ -249,17 +380,8 @@ impl SsbhRenderer {
                 },
             );
 
-        // Depth from the perspective of the light.
-        // TODO: Multiple lights require multiple depth maps?
-        let shadow_depth = create_depth(device, SHADOW_MAP_WIDTH, SHADOW_MAP_HEIGHT, 1);
-
-        let variance_shadow = create_texture_sampler(
-            device,
-            VARIANCE_SHADOW_WIDTH,
-            VARIANCE_SHADOW_HEIGHT,
-            VARIANCE_SHADOW_FORMAT,
-            1,
-        );
+        // Depth from the perspective of each light.
+        let shadow_maps = ShadowMaps::new(device);
 
         let render_settings = RenderSettings::default();
         let render_settings_buffer = device.create_buffer_from_data(
@@ -270,9 +392,10 @@ impl SsbhRenderer {
 
         // The light nuanmb should be public with conversions for quaternions, vectors, etc being private.
         // stage light nuanmb -> uniform struct -> buffer
+        let stage_uniforms = crate::shader::model::StageUniforms::training();
         let stage_uniforms_buffer = device.create_buffer_from_data(
             "Stage Uniforms Buffer",
-            &[crate::shader::model::StageUniforms::training()],
+            &[stage_uniforms],
             wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
         );
 
@@ -293,7 +416,7 @@ impl SsbhRenderer {
             device,
             crate::shader::model::bind_groups::BindGroupLayout0 {
                 camera: camera_buffer.as_entire_buffer_binding(),
-                texture_shadow: &variance_shadow.view,
+                texture_shadow: &shadow_maps.variance_array,
                 default_sampler: &default_sampler,
                 render_settings: render_settings_buffer.as_entire_buffer_binding(),
                 stage_uniforms: stage_uniforms_buffer.as_entire_buffer_binding(),
@@ -301,29 +424,23 @@ impl SsbhRenderer {
             },
         );
 
-        // TODO: Is it ok to just use the variance shadow map sampler?
-        // We don't want a comparison sampler for this pipeline.
-        let variance_bind_group =
-            crate::shader::variance_shadow::bind_groups::BindGroup0::from_bindings(
-                device,
-                crate::shader::variance_shadow::bind_groups::BindGroupLayout0 {
-                    texture_shadow: &shadow_depth.view,
-                    sampler_shadow: &variance_shadow.sampler,
-                },
-            );
-
-        let invalid_shader_pipeline = invalid_shader_pipeline(device);
-        let invalid_attributes_pipeline = invalid_attributes_pipeline(device);
-        let debug_pipeline = debug_pipeline(device);
+        let invalid_shader_pipeline = invalid_shader_pipeline(device, sample_count);
+        let invalid_attributes_pipeline = invalid_attributes_pipeline(device, sample_count);
+        let depth_prepass_pipelines = DepthPrepassPipelines::new(device, sample_count);
+        let oit_pipelines = OitPipelines::new(device, sample_count);
+        let oit_composite_pipeline = create_oit_composite_pipeline(device, sample_count);
+        let debug_pipeline = debug_pipeline(device, sample_count);
+        let overdraw_pipeline = overdraw_pipeline(device, sample_count);
         let silhouette_pipeline = silhouette_pipeline(device, surface_format);
         let outline_pipeline = create_outline_pipeline(device, surface_format);
         let uv_pipeline = uv_pipeline(device, surface_format);
-        let wireframe_pipeline = wireframe_pipeline(device);
+        let uv_coverage_pipeline = uv_coverage_pipeline(device, surface_format);
+        let wireframe_pipeline = wireframe_pipeline(device, sample_count);
 
         let bone_pipelines = BonePipelines::new(device, RGBA_COLOR_FORMAT);
         let bone_buffers = BoneBuffers::new(device);
 
-        let selected_material_pipeline = selected_material_pipeline(device);
+        let selected_material_pipeline = selected_material_pipeline(device, sample_count);
 
         let skinning_settings_buffer = device.create_buffer_from_data(
             "Skinning Settings Buffer",
@@ -348,48 +465,125 @@ impl SsbhRenderer {
             },
         );
 
-        let floor_grid = FloorGridRenderData::new(device, &camera_buffer, RGBA_COLOR_FORMAT);
+        let floor_grid =
+            FloorGridRenderData::new(device, &camera_buffer, RGBA_COLOR_FORMAT, sample_count);
+
+        let light_gizmo = LightGizmoRenderData::new(device, &camera_buffer, surface_format);
+        light_gizmo.update(queue, stage_uniforms.light_chr.direction, glam::Vec3::ZERO);
+
+        let transform_gizmo = TransformGizmoRenderData::new(device, &camera_buffer, surface_format);
+
+        let mut debug_gizmo = DebugGizmoRenderData::new(device, &camera_buffer, RGBA_COLOR_FORMAT);
+        debug_gizmo.update_lights(
+            queue,
+            &stage_uniforms,
+            glam::Vec3::ZERO,
+            DEFAULT_LIGHT_DISTANCE,
+        );
 
         let swing_pipeline = swing_pipeline(device, surface_format);
 
+        let clear_pipeline = create_clear_pipeline(device, surface_format);
+
+        let dof_settings = DofSettings::default();
+        let dof_buffer = device.create_buffer_from_data(
+            "Depth of Field Buffer",
+            &[dof_settings.uniforms(camera.projection_matrix)],
+            wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        );
+        let dof_bind_group = crate::shader::dof::bind_groups::BindGroup1::from_bindings(
+            device,
+            crate::shader::dof::bind_groups::BindGroupLayout1 {
+                dof: dof_buffer.as_entire_buffer_binding(),
+            },
+        );
+
+        let post_process_settings = PostProcessSettings::default();
+        let post_process_buffer = device.create_buffer_from_data(
+            "Post Process Buffer",
+            &[post_process_settings.uniforms(false)],
+            wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        );
+        let post_process_bind_group =
+            crate::shader::post_process::bind_groups::BindGroup1::from_bindings(
+                device,
+                crate::shader::post_process::bind_groups::BindGroupLayout1 {
+                    post_process: post_process_buffer.as_entire_buffer_binding(),
+                },
+            );
+
         Self {
             bloom_threshold_pipeline,
             bloom_blur_pipeline,
             bloom_combine_pipeline,
             bloom_upscale_pipeline,
             post_process_pipeline,
+            post_process_transparent_pipeline,
+            dof_pipeline,
             skinning_pipeline,
             renormal_pipeline,
             shadow_pipeline,
             camera_buffer,
+            camera,
             per_frame_bind_group,
             skeleton_camera_bind_group,
             pass_info,
+            texture_allocation: TextureAllocation::Exact,
+            width,
+            height,
+            scale_factor,
             color_lut,
-            shadow_depth,
+            shadow_maps,
             variance_shadow_pipeline,
-            variance_shadow,
-            variance_bind_group,
             clear_color,
+            transparent_background: false,
+            scissor_rect: None,
+            draw_order: DrawOrder::default(),
+            material_comparison: None,
+            bind_counts: Default::default(),
+            #[cfg(feature = "frame_stats")]
+            frame_stats: Default::default(),
+            gpu_profiler: None,
+            transition_curve: None,
             stage_uniforms_buffer,
+            stage_uniforms,
+            shadow_fitting: false,
+            shadow_bounds: None,
             bone_pipelines,
             invalid_shader_pipeline,
             invalid_attributes_pipeline,
+            depth_prepass_pipelines,
+            oit_pipelines,
+            oit_composite_pipeline,
             debug_pipeline,
+            overdraw_pipeline,
+            overdraw_heat_map_pipeline,
             silhouette_pipeline,
             outline_pipeline,
             uv_pipeline,
+            uv_coverage_pipeline,
             render_settings,
             render_settings_buffer,
+            dof_settings,
+            dof_buffer,
+            dof_bind_group,
+            post_process_settings,
+            post_process_buffer,
+            post_process_bind_group,
             bone_buffers,
             overlay_pipeline,
+            overlay_transparent_pipeline,
             wireframe_pipeline,
             selected_material_pipeline,
+            clear_pipeline,
             skinning_settings_buffer,
             skinning_settings_bind_group,
             swing_camera_bind_group,
             swing_pipeline,
             floor_grid,
+            light_gizmo,
+            debug_gizmo,
+            transform_gizmo,
             surface_format,
         }
     }
@@ -405,20 +599,205 @@ impl SsbhRenderer {
     /// This should usually match the current monitor's scaling factor
     /// in the OS such as `1.5` for 150% scaling. If unsure, use a value of `1.0`.
     pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32, scale_factor: f32) {
+        let (allocated_width, allocated_height) = match self.texture_allocation {
+            TextureAllocation::Exact => (width, height),
+            TextureAllocation::Bucketed(bucket_size) => {
+                if width <= self.pass_info.width
+                    && height <= self.pass_info.height
+                    && scale_factor == self.scale_factor
+                {
+                    (self.pass_info.width, self.pass_info.height)
+                } else {
+                    (
+                        bucketed_size(width, bucket_size),
+                        bucketed_size(height, bucket_size),
+                    )
+                }
+            }
+        };
+
+        self.width = width;
+        self.height = height;
+        self.scale_factor = scale_factor;
+
+        if (allocated_width, allocated_height) != (self.pass_info.width, self.pass_info.height)
+            || self.pass_info.scale_factor != scale_factor
+        {
+            self.pass_info = PassInfo::new(
+                device,
+                allocated_width,
+                allocated_height,
+                scale_factor,
+                &self.color_lut,
+                self.surface_format,
+                self.pass_info.sample_count,
+            );
+        }
+
+        // Only sample the region of the textures used for rendering.
+        self.pass_info.overlay_bind_group = create_overlay_bind_group(
+            device,
+            &self.pass_info.color_final,
+            &self.pass_info.silhouette_outlines,
+            &self.pass_info.skel_outlines,
+            self.surface_format.is_srgb(),
+            self.uv_scale(),
+        );
+    }
+
+    /// Sets the strategy used for allocating intermediate textures in [SsbhRenderer::resize].
+    pub fn set_texture_allocation(&mut self, allocation: TextureAllocation) {
+        self.texture_allocation = allocation;
+    }
+
+    /// Recreates intermediate textures to exactly match the current size
+    /// to release memory from oversized textures.
+    pub fn trim(&mut self, device: &wgpu::Device) {
+        if (self.width, self.height) != (self.pass_info.width, self.pass_info.height) {
+            self.pass_info = PassInfo::new(
+                device,
+                self.width,
+                self.height,
+                self.scale_factor,
+                &self.color_lut,
+                self.surface_format,
+                self.pass_info.sample_count,
+            );
+        }
+    }
+
+    /// The MSAA sample count for model rendering from [SsbhRenderer::set_sample_count].
+    pub fn sample_count(&self) -> u32 {
+        self.pass_info.sample_count
+    }
+
+    /// Sets the MSAA sample count for model rendering and recreates the affected pipelines and textures.
+    /// The default is 4.
+    ///
+    /// The `sample_count` should be one of the values from [supported_sample_counts](crate::supported_sample_counts).
+    /// Lower values improve performance at the cost of more aliased edges,
+    /// and a value of 1 disables MSAA and alpha to coverage.
+    /// Models must use pipelines with the same sample count,
+    /// so also call [SharedRenderData::set_sample_count](crate::SharedRenderData::set_sample_count)
+    /// and [RenderModel::recreate_pipelines] for existing models.
+    /// Models with pipelines for a different sample count are skipped when rendering.
+    pub fn set_sample_count(&mut self, device: &wgpu::Device, sample_count: u32) {
+        if sample_count == self.pass_info.sample_count {
+            return;
+        }
+
+        self.invalid_shader_pipeline = invalid_shader_pipeline(device, sample_count);
+        self.invalid_attributes_pipeline = invalid_attributes_pipeline(device, sample_count);
+        self.depth_prepass_pipelines = DepthPrepassPipelines::new(device, sample_count);
+        self.oit_pipelines = OitPipelines::new(device, sample_count);
+        self.oit_composite_pipeline = create_oit_composite_pipeline(device, sample_count);
+        self.debug_pipeline = debug_pipeline(device, sample_count);
+        self.overdraw_pipeline = overdraw_pipeline(device, sample_count);
+        self.wireframe_pipeline = wireframe_pipeline(device, sample_count);
+        self.selected_material_pipeline = selected_material_pipeline(device, sample_count);
+        self.dof_pipeline = create_dof_pipeline(device, sample_count);
+        self.floor_grid =
+            FloorGridRenderData::new(device, &self.camera_buffer, RGBA_COLOR_FORMAT, sample_count);
+
         self.pass_info = PassInfo::new(
             device,
-            width,
-            height,
-            scale_factor,
+            self.pass_info.width,
+            self.pass_info.height,
+            self.scale_factor,
             &self.color_lut,
             self.surface_format,
+            sample_count,
+        );
+        self.pass_info.overlay_bind_group = create_overlay_bind_group(
+            device,
+            &self.pass_info.color_final,
+            &self.pass_info.silhouette_outlines,
+            &self.pass_info.skel_outlines,
+            self.surface_format.is_srgb(),
+            self.uv_scale(),
+        );
+    }
+
+    fn uv_scale(&self) -> glam::Vec2 {
+        glam::vec2(
+            self.width as f32 / self.pass_info.width.max(1) as f32,
+            self.height as f32 / self.pass_info.height.max(1) as f32,
+        )
+    }
+
+    fn viewport(&self) -> ScissorRect {
+        // Intermediate textures may be larger than the rendered area.
+        ScissorRect {
+            x: 0,
+            y: 0,
+            width: self.width.max(1),
+            height: self.height.max(1),
+        }
+    }
+
+    fn set_viewport(&self, pass: &mut wgpu::RenderPass<'_>) {
+        let viewport = self.viewport();
+        pass.set_viewport(
+            viewport.x as f32,
+            viewport.y as f32,
+            viewport.width as f32,
+            viewport.height as f32,
+            0.0,
+            1.0,
         );
     }
 
     // TODO: Document that anything that takes a device reference shouldn't be called each frame.
     /// Updates the camera transforms.
     pub fn update_camera(&mut self, queue: &wgpu::Queue, transforms: CameraTransforms) {
+        self.camera = transforms;
         queue.write_data(&self.camera_buffer, &[transforms]);
+
+        // The transform gizmo has a constant size on screen.
+        self.transform_gizmo.update_camera(queue, &transforms);
+
+        // Depth of field depends on the near and far clip distances.
+        let dof_settings = self.dof_settings;
+        self.update_dof_settings(queue, &dof_settings);
+    }
+
+    /// Updates the depth of field settings.
+    /// This is cheap enough to call every frame for animated cameras.
+    pub fn update_dof_settings(&mut self, queue: &wgpu::Queue, dof_settings: &DofSettings) {
+        self.dof_settings = *dof_settings;
+        queue.write_data(
+            &self.dof_buffer,
+            &[dof_settings.uniforms(self.camera.projection_matrix)],
+        );
+    }
+
+    /// The current depth of field settings from [SsbhRenderer::update_dof_settings].
+    pub fn dof_settings(&self) -> DofSettings {
+        self.dof_settings
+    }
+
+    /// Updates the exposure, gamma, and tone mapping for the post processing pass.
+    pub fn update_post_process_settings(
+        &mut self,
+        queue: &wgpu::Queue,
+        post_process_settings: &PostProcessSettings,
+    ) {
+        self.post_process_settings = *post_process_settings;
+        self.write_post_process_uniforms(queue);
+    }
+
+    fn write_post_process_uniforms(&self, queue: &wgpu::Queue) {
+        queue.write_data(
+            &self.post_process_buffer,
+            &[self
+                .post_process_settings
+                .uniforms(self.transparent_background)],
+        );
+    }
+
+    /// The current post processing settings from [SsbhRenderer::update_post_process_settings].
+    pub fn post_process_settings(&self) -> PostProcessSettings {
+        self.post_process_settings
     }
 
     /// Updates the render settings.
@@ -434,6 +813,54 @@ impl SsbhRenderer {
         );
     }
 
+    /// Updates [RenderSettings::time_seconds] for time driven material effects.
+    /// This also updates [RenderSettings::transition_factor] for any curve from [SsbhRenderer::animate_transition].
+    /// This is cheap enough to call every frame.
+    pub fn update_time(&mut self, queue: &wgpu::Queue, time_seconds: f32) {
+        let transition_factor = match self.transition_curve {
+            Some(curve) => {
+                if curve.is_finished(time_seconds) {
+                    self.transition_curve = None;
+                }
+                curve.factor(time_seconds)
+            }
+            None => self.render_settings.transition_factor,
+        };
+
+        let render_settings = RenderSettings {
+            time_seconds,
+            transition_factor,
+            ..self.render_settings
+        };
+        self.update_render_settings(queue, &render_settings);
+    }
+
+    /// Gradually changes [RenderSettings::transition_factor] from `start` to `end` over `duration` seconds.
+    ///
+    /// The transition starts at the current [RenderSettings::time_seconds]
+    /// and advances with each call to [SsbhRenderer::update_time].
+    /// For example, animate from `0.0` to `1.0` to preview a fighter turning to metal with [TransitionMaterial::MetalBox].
+    pub fn animate_transition(&mut self, queue: &wgpu::Queue, start: f32, end: f32, duration: f32) {
+        let curve = TransitionCurve {
+            start,
+            end,
+            duration,
+            start_time: self.render_settings.time_seconds,
+        };
+        self.transition_curve = Some(curve);
+
+        let render_settings = RenderSettings {
+            transition_factor: start,
+            ..self.render_settings
+        };
+        self.update_render_settings(queue, &render_settings);
+    }
+
+    /// The curve from [SsbhRenderer::animate_transition] or `None` if no transition is in progress.
+    pub fn transition_curve(&self) -> Option<TransitionCurve> {
+        self.transition_curve
+    }
+
     /// Updates the skinning settings.
     pub fn update_skinning_settings(
         &mut self,
@@ -449,70 +876,253 @@ impl SsbhRenderer {
     }
 
     /// Updates the stage lighting data to the given `frame`.
+    ///
+    /// The LightChr and each LightStg light render their own shadow map.
+    /// Models use the shadows for the light assigned to them by the model's light set.
     pub fn update_stage_uniforms(&mut self, queue: &wgpu::Queue, data: &AnimData, frame: f32) {
-        let stage_uniforms = animate_lighting(data, frame);
-        queue.write_data(&self.stage_uniforms_buffer, &[stage_uniforms]);
+        self.stage_uniforms = animate_lighting(data, frame);
+        self.write_stage_uniforms(queue);
     }
 
     /// Resets the stage uniforms and lighting to their default values.
     pub fn reset_stage_uniforms(&mut self, queue: &wgpu::Queue) {
-        queue.write_data(
-            &self.stage_uniforms_buffer,
-            &[crate::shader::model::StageUniforms::training()],
-        );
+        self.stage_uniforms = crate::shader::model::StageUniforms::training();
+        self.write_stage_uniforms(queue);
+    }
+
+    /// Replaces the stage uniforms and lighting with a built in [LightingPreset].
+    /// This is overwritten by [SsbhRenderer::update_stage_uniforms] and [SsbhRenderer::reset_stage_uniforms].
+    pub fn set_lighting_preset(&mut self, queue: &wgpu::Queue, preset: LightingPreset) {
+        self.stage_uniforms = preset.stage_uniforms();
+        self.write_stage_uniforms(queue);
+    }
+
+    /// Replaces the stage uniforms and lighting with the values in `settings`.
+    ///
+    /// This allows editing lighting directly like sliders in a lighting editor without a lighting anim.
+    /// This is overwritten by [SsbhRenderer::update_stage_uniforms] and [SsbhRenderer::reset_stage_uniforms].
+    pub fn update_lighting(&mut self, queue: &wgpu::Queue, settings: &LightingSettings) {
+        self.stage_uniforms = settings.stage_uniforms();
+        self.write_stage_uniforms(queue);
+    }
+
+    /// The current stage lighting as [LightingSettings] for initializing a lighting editor.
+    ///
+    /// Light colors are already scaled by their intensity, so each light has an intensity of `1.0`.
+    pub fn lighting_settings(&self) -> LightingSettings {
+        LightingSettings::from_stage_uniforms(&self.stage_uniforms)
+    }
+
+    /// Sets the rotation of the character light used for shading and shadows.
+    /// The light color and stage lights are not changed.
+    ///
+    /// This allows previewing lighting changes without a lighting animation.
+    /// Euler angles from a UI can be converted with [glam::Quat::from_euler].
+    /// The rotation is overwritten by [SsbhRenderer::update_stage_uniforms] and [SsbhRenderer::reset_stage_uniforms].
+    pub fn set_light_rotation(&mut self, queue: &wgpu::Queue, rotation: glam::Quat) {
+        self.stage_uniforms.light_chr.set_rotation(rotation);
+        self.write_stage_uniforms(queue);
+    }
+
+    /// The current rotation of the character light.
+    pub fn light_rotation(&self) -> glam::Quat {
+        self.stage_uniforms.light_chr.rotation()
+    }
+
+    /// Enables or disables fitting the light shadow projections to the scene bounds.
+    /// Fitting is disabled by default and enabled by [RenderProfile::Character](crate::RenderProfile::Character).
+    ///
+    /// When disabled, shadows use the light transforms from the stage lighting
+    /// like in game, which may clip shadows on large stages or be blurry for small models.
+    pub fn set_shadow_fitting(&mut self, queue: &wgpu::Queue, enabled: bool) {
+        self.shadow_fitting = enabled;
+        self.write_stage_uniforms(queue);
+    }
+
+    /// Fits the light shadow projections to contain the visible meshes in `render_models`.
+    /// This should be called each frame after [RenderModel::apply_anims] for animated shadows.
+    ///
+    /// The projections are stabilized to avoid shimmering as the bounds change.
+    /// This has no effect if shadow fitting is disabled with [SsbhRenderer::set_shadow_fitting].
+    pub fn update_shadow_bounds(&mut self, queue: &wgpu::Queue, render_models: &[RenderModel]) {
+        let bounds = scene_bounds(render_models, true);
+        if bounds != self.shadow_bounds {
+            self.shadow_bounds = bounds;
+            self.write_stage_uniforms(queue);
+        }
+    }
+
+    fn write_stage_uniforms(&mut self, queue: &wgpu::Queue) {
+        let mut stage_uniforms = self.stage_uniforms;
+        if let (true, Some(bounds)) = (self.shadow_fitting, &self.shadow_bounds) {
+            let lights = std::iter::once(&mut stage_uniforms.light_chr)
+                .chain(stage_uniforms.light_stage.iter_mut());
+            for light in lights {
+                if let Some(transform) =
+                    fit_light_transform(light.direction, bounds, SHADOW_MAP_WIDTH)
+                {
+                    light.transform = transform;
+                }
+            }
+        }
+        queue.write_data(&self.stage_uniforms_buffer, &[stage_uniforms]);
+
+        // Point the gizmo at the center of the scene if possible.
+        let target = self
+            .shadow_bounds
+            .map(|b| b.center())
+            .unwrap_or(glam::Vec3::ZERO);
+        self.light_gizmo
+            .update(queue, self.stage_uniforms.light_chr.direction, target);
+
+        let distance = self
+            .shadow_bounds
+            .map(|b| b.bounding_sphere().radius)
+            .unwrap_or(DEFAULT_LIGHT_DISTANCE);
+        self.debug_gizmo
+            .update_lights(queue, &self.stage_uniforms, target, distance);
     }
 
     /// Updates the stage color grading LUT texture.
-    /// Invalid nutexb files are ignored and the texture will not be updated.
+    /// Invalid nutexb files or textures that aren't 3D return an error and leave the LUT unchanged.
     pub fn update_color_lut(
         &mut self,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         nutexb: &NutexbFile,
-    ) {
-        // TODO: Return or log errors?
-        if let Ok((texture, dim)) = nutexb_wgpu::create_texture(nutexb, device, queue) {
-            if dim == wgpu::TextureViewDimension::D3 {
-                let color_lut = TextureSamplerView {
-                    view: texture.create_view(&wgpu::TextureViewDescriptor::default()),
-                    sampler: device.create_sampler(&wgpu::SamplerDescriptor {
-                        min_filter: wgpu::FilterMode::Linear,
-                        mag_filter: wgpu::FilterMode::Linear,
-                        ..Default::default()
-                    }),
-                };
-                self.pass_info.post_process_bind_group = create_post_process_bind_group(
-                    device,
-                    &self.pass_info.color,
-                    &self.pass_info.bloom_upscaled,
-                    &color_lut,
-                );
-            }
+    ) -> Result<(), SsbhWgpuError> {
+        let (texture, dimension) = nutexb_wgpu::create_texture(nutexb, device, queue)?;
+        if dimension != wgpu::TextureViewDimension::D3 {
+            return Err(SsbhWgpuError::TextureDimension {
+                expected: wgpu::TextureViewDimension::D3,
+                actual: dimension,
+            });
         }
+
+        let color_lut = TextureSamplerView {
+            view: texture.create_view(&wgpu::TextureViewDescriptor::default()),
+            sampler: device.create_sampler(&wgpu::SamplerDescriptor {
+                min_filter: wgpu::FilterMode::Linear,
+                mag_filter: wgpu::FilterMode::Linear,
+                ..Default::default()
+            }),
+        };
+        self.pass_info.update_color_lut(device, &color_lut);
+        Ok(())
     }
 
     /// Resets the color grading LUT texture to its default value.
     pub fn reset_color_lut(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
         let color_lut = load_default_lut(device, queue);
-        self.pass_info.post_process_bind_group = create_post_process_bind_group(
-            device,
-            &self.pass_info.color,
-            &self.pass_info.bloom_upscaled,
-            &color_lut,
-        );
+        self.pass_info.update_color_lut(device, &color_lut);
+    }
+
+    /// Applies the render settings, depth of field, shadow, lighting, and background settings for a profile.
+    /// Use [RenderProfile::settings] to get the settings for a [RenderProfile].
+    ///
+    /// Each setting can still be changed individually after applying the profile.
+    pub fn apply_profile(&mut self, queue: &wgpu::Queue, settings: &ProfileSettings) {
+        self.update_render_settings(queue, &settings.render_settings);
+        self.update_dof_settings(queue, &settings.dof_settings);
+        if let Some(preset) = settings.lighting_preset {
+            self.stage_uniforms = preset.stage_uniforms();
+        }
+        if let Some(color) = settings.clear_color {
+            self.clear_color = color;
+        }
+        // Shadow fitting and lighting changes share the same buffer write.
+        self.shadow_fitting = settings.shadow_fitting;
+        self.write_stage_uniforms(queue);
+    }
+
+    /// Sets the camera for drawing a frustum with [ModelRenderOptions::draw_camera_frustum]
+    /// or `None` to hide the frustum.
+    ///
+    /// This is typically the animated camera from [animate_camera](crate::animation::camera::animate_camera)
+    /// while viewing the scene from a different camera set with [SsbhRenderer::update_camera].
+    pub fn update_camera_gizmo(&mut self, queue: &wgpu::Queue, camera: Option<&CameraTransforms>) {
+        self.debug_gizmo.update_frustum(queue, camera);
+    }
+
+    /// Sets the skeletons to compare for [ModelRenderOptions::draw_bones] or `None` to hide the comparison.
+    ///
+    /// Lines connect bones with the same name in each model's current animated pose.
+    /// Bones with a different index in each skeleton use a different line color to show reordered bones.
+    /// This should be called after animating the models to keep the lines in sync with the bones.
+    /// Use [RenderModel::update_bone_colors] to distinguish the overlapping skeletons.
+    pub fn update_skeleton_comparison(
+        &mut self,
+        queue: &wgpu::Queue,
+        models: Option<(&RenderModel, &RenderModel)>,
+    ) {
+        match models {
+            Some((a, b)) => self.debug_gizmo.update_skeleton_comparison(
+                queue,
+                &a.bone_world_positions(),
+                &b.bone_world_positions(),
+            ),
+            None => self.debug_gizmo.update_skeleton_comparison(queue, &[], &[]),
+        }
     }
 
-    /// Sets the viewport background color.
+    /// Sets the RGBA viewport background color.
+    ///
+    /// The alpha is written to the output, so an alpha of `0.0` produces a transparent background.
+    /// Bloom over the background is discarded unless [SsbhRenderer::set_transparent_background] is enabled.
     pub fn set_clear_color(&mut self, color: [f64; 4]) {
         self.clear_color = color;
     }
 
+    /// The current viewport background color from [SsbhRenderer::set_clear_color].
+    pub fn clear_color(&self) -> [f64; 4] {
+        self.clear_color
+    }
+
+    /// Renders with a fully transparent background instead of the clear color.
+    ///
+    /// Bloom over the background contributes to the output alpha instead of being discarded.
+    /// The output uses straight rather than premultiplied alpha,
+    /// so the result can be composited over any backdrop.
+    pub fn set_transparent_background(&mut self, queue: &wgpu::Queue, transparent: bool) {
+        self.transparent_background = transparent;
+        self.write_post_process_uniforms(queue);
+    }
+
+    /// Returns `true` if [SsbhRenderer::set_transparent_background] is enabled.
+    pub fn transparent_background(&self) -> bool {
+        self.transparent_background
+    }
+
+    /// Restricts drawing and clearing the output in [SsbhRenderer::render_models] to `rect`.
+    /// Use `None` to draw to the entire output.
+    ///
+    /// This allows multiple viewports to share the same output texture without overwriting each other.
+    /// The renderer should be resized to the dimensions of `rect`.
+    pub fn set_scissor_rect(&mut self, rect: Option<ScissorRect>) {
+        self.scissor_rect = rect;
+    }
+
+    /// Sets the order for drawing meshes in [SsbhRenderer::render_models].
+    pub fn set_draw_order(&mut self, draw_order: DrawOrder) {
+        self.draw_order = draw_order;
+    }
+
+    /// Sets the mode for comparing materials in [SsbhRenderer::render_models]
+    /// or `None` to only draw the regular materials.
+    ///
+    /// Both sets of materials share the same skinning and shadow passes.
+    /// Comparison materials are set with [RenderModel::recreate_comparison_materials].
+    pub fn set_material_comparison(&mut self, comparison: Option<MaterialComparison>) {
+        self.material_comparison = comparison;
+    }
+
     // TODO: Add a code example to show how to drop the pass.
     // TODO: Simplify parameters?
     /// Renders the `render_models` to `output_view` using the standard rendering passes for Smash Ultimate.
     ///
     /// The `output_view` should have the format [surface_format].
     /// The output is cleared before drawing.
+    /// Only the region set by [SsbhRenderer::set_scissor_rect] is cleared if present.
     ///
     /// Returns the final color pass with no depth attachment.
     /// This enables adding efficient overlays.
@@ -527,12 +1137,257 @@ impl SsbhRenderer {
     ) -> wgpu::RenderPass<'a> {
         self.begin_render_models(encoder, render_models, shader_database, options);
 
-        let mut pass = create_color_pass(encoder, output_view, Some("Overlay Pass"));
+        let mut pass = match self.scissor_rect {
+            Some(rect) => {
+                // Clearing affects the entire attachment.
+                // Emulate a clear using a fullscreen triangle to only clear the scissor region.
+                let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Overlay Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: output_view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                    timestamp_writes: None,
+                    occlusion_query_set: None,
+                });
+                pass.set_viewport(
+                    rect.x as f32,
+                    rect.y as f32,
+                    rect.width as f32,
+                    rect.height as f32,
+                    0.0,
+                    1.0,
+                );
+                pass.set_scissor_rect(rect.x, rect.y, rect.width, rect.height);
+                pass.set_pipeline(&self.clear_pipeline);
+                pass.draw(0..3, 0..1);
+                pass
+            }
+            None => create_color_pass(encoder, output_view, Some("Overlay Pass")),
+        };
         self.end_render_models(&mut pass);
 
         pass
     }
 
+    /// Renders the `render_models` to each of the `viewports` in `output_view` like quad views in a model editor.
+    ///
+    /// Each viewport is rendered like [SsbhRenderer::render_models] with its own camera and
+    /// a separate queue submission since the camera is stored in a shared buffer.
+    /// Only the region of each viewport is cleared, so overlapping viewports draw over previous viewports.
+    ///
+    /// The intermediate textures are shared between viewports and sized to fit the largest viewport.
+    /// Use [SsbhRenderer::trim] to release the larger textures if no longer rendering viewports.
+    /// The renderer's size, camera, and scissor rect are restored afterwards.
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_viewports(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        output_view: &wgpu::TextureView,
+        viewports: &[Viewport],
+        render_models: &[RenderModel],
+        shader_database: &ShaderDatabase,
+        options: &ModelRenderOptions,
+    ) {
+        let previous_size = (self.width, self.height, self.scale_factor);
+        let previous_camera = self.camera;
+        let previous_scissor_rect = self.scissor_rect;
+        let previous_allocation = self.texture_allocation;
+
+        // Allocate once for the largest viewport to avoid recreating textures for each viewport.
+        self.texture_allocation = match previous_allocation {
+            TextureAllocation::Exact => TextureAllocation::Bucketed(1),
+            bucketed => bucketed,
+        };
+        let max_width = viewports.iter().map(|v| v.rect.width).max().unwrap_or(0);
+        let max_height = viewports.iter().map(|v| v.rect.height).max().unwrap_or(0);
+        self.resize(device, max_width, max_height, previous_size.2);
+
+        for viewport in viewports {
+            let ScissorRect { width, height, .. } = viewport.rect;
+            if width == 0 || height == 0 {
+                continue;
+            }
+
+            self.resize(device, width, height, previous_size.2);
+            let mut camera = viewport.camera;
+            camera.screen_dimensions =
+                glam::vec4(width as f32, height as f32, previous_size.2, 0.0);
+            self.update_camera(queue, camera);
+            self.scissor_rect = Some(viewport.rect);
+
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Viewport Encoder"),
+            });
+            drop(self.render_models(
+                &mut encoder,
+                output_view,
+                render_models,
+                shader_database,
+                options,
+            ));
+            queue.submit([encoder.finish()]);
+        }
+
+        // Restore the previous size without shrinking the textures.
+        self.resize(device, previous_size.0, previous_size.1, previous_size.2);
+        self.texture_allocation = previous_allocation;
+        self.scissor_rect = previous_scissor_rect;
+        self.update_camera(queue, previous_camera);
+    }
+
+    /// Renders the `render_models` to a new RGBA image with the given dimensions.
+    ///
+    /// This submits commands to `queue` and blocks until rendering completes.
+    /// The current camera's projection should use the same aspect ratio as `width` and `height`.
+    /// The renderer's size, camera, and background settings are restored afterwards.
+    /// Use [SsbhRenderer::trim] to release the larger textures used for supersampling.
+    ///
+    /// Returns an error if the surface format is not an 8-bit RGBA or BGRA format
+    /// or the dimensions exceed the device limits.
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_to_image(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        width: u32,
+        height: u32,
+        render_models: &[RenderModel],
+        shader_database: &ShaderDatabase,
+        options: &ModelRenderOptions,
+        settings: &CaptureSettings,
+    ) -> Result<image::RgbaImage, SsbhWgpuError> {
+        let bgra = is_bgra(self.surface_format)?;
+
+        let supersampling = settings.supersampling.max(1);
+        let max_dimension = device.limits().max_texture_dimension_2d;
+        let (render_width, render_height) = match (
+            width.checked_mul(supersampling),
+            height.checked_mul(supersampling),
+        ) {
+            (Some(w), Some(h)) if w > 0 && h > 0 && w <= max_dimension && h <= max_dimension => {
+                (w, h)
+            }
+            _ => {
+                return Err(SsbhWgpuError::CaptureSizeExceedsLimit {
+                    width,
+                    height,
+                    supersampling,
+                    max_dimension,
+                })
+            }
+        };
+
+        let previous_size = (self.width, self.height, self.scale_factor);
+        let previous_camera = self.camera;
+        let previous_transparent_background = self.transparent_background;
+        let previous_scissor_rect = self.scissor_rect.take();
+
+        // Scale screen based effects like outlines and bloom to match the output size.
+        let scale_factor = self.scale_factor * supersampling as f32;
+        self.resize(device, render_width, render_height, scale_factor);
+        let mut camera = self.camera;
+        camera.screen_dimensions =
+            glam::vec4(render_width as f32, render_height as f32, scale_factor, 0.0);
+        queue.write_data(&self.camera_buffer, &[camera]);
+        if settings.transparent_background {
+            self.set_transparent_background(queue, true);
+        }
+
+        let output = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Capture Texture"),
+            size: wgpu::Extent3d {
+                width: render_width,
+                height: render_height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.surface_format,
+            usage: wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        let output_view = output.create_view(&Default::default());
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Capture Encoder"),
+        });
+        drop(self.render_models(
+            &mut encoder,
+            &output_view,
+            render_models,
+            shader_database,
+            options,
+        ));
+        let buffer = copy_output_to_buffer(device, &mut encoder, &output);
+        queue.submit([encoder.finish()]);
+
+        let result = read_buffer_rgba(device, &buffer, render_width, render_height, bgra);
+
+        self.set_transparent_background(queue, previous_transparent_background);
+        self.scissor_rect = previous_scissor_rect;
+        self.resize(device, previous_size.0, previous_size.1, previous_size.2);
+        self.update_camera(queue, previous_camera);
+
+        let mut data = result?;
+        if supersampling > 1 {
+            data = downsample(&data, render_width, render_height, supersampling);
+        }
+        image::RgbaImage::from_raw(width, height, data).ok_or(SsbhWgpuError::ImageDataMismatch)
+    }
+
+    /// Renders the `render_models` from a camera orbiting the models
+    /// and returns an iterator over the RGBA images for each frame.
+    ///
+    /// The camera looks at the center of the visible models using the field of view and clip distances
+    /// of the current camera. Each frame is rendered like [SsbhRenderer::render_to_image].
+    /// The frames can be saved as an image sequence or encoded with [encode_apng](crate::encode_apng).
+    ///
+    /// The renderer's camera is restored when the returned iterator is dropped.
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_turntable<'a>(
+        &'a mut self,
+        device: &'a wgpu::Device,
+        queue: &'a wgpu::Queue,
+        width: u32,
+        height: u32,
+        render_models: &'a [RenderModel],
+        shader_database: &'a ShaderDatabase,
+        options: &'a ModelRenderOptions,
+        settings: &TurntableSettings,
+    ) -> Turntable<'a> {
+        let bounds = scene_bounds(render_models, true)
+            .map(|b| b.bounding_sphere())
+            .unwrap_or(crate::BoundingSphere {
+                center: glam::Vec3::ZERO,
+                radius: 1.0,
+            });
+
+        Turntable {
+            width,
+            height,
+            scale_factor: self.scale_factor as f64,
+            camera: CameraAnimValues::from_transforms(&self.camera),
+            previous_camera: self.camera,
+            renderer: self,
+            device,
+            queue,
+            render_models,
+            shader_database,
+            options,
+            settings: *settings,
+            bounds,
+            frame: 0,
+        }
+    }
+
     /// Renders the `render_models` to internal textures.
     /// Complete rendering to the final output pass using [Self::end_render_models].
     pub fn begin_render_models<'a>(
@@ -542,41 +1397,72 @@ impl SsbhRenderer {
         shader_database: &ShaderDatabase,
         options: &ModelRenderOptions,
     ) {
+        #[cfg(feature = "frame_stats")]
+        let start = std::time::Instant::now();
+        self.bind_counts.set(BindCounts::default());
+
         // TODO: How to have RenderModel own all resources but still sort RenderMesh?
 
+        if let Some(profiler) = &self.gpu_profiler {
+            profiler.begin_frame(encoder);
+        }
+
         // Transform the vertex positions and normals.
         // Always run compute passes to preserve vertex positions when switching to debug shading.
         self.skinning_pass(encoder, render_models.iter());
         self.renormal_pass(encoder, render_models.iter());
+        self.timestamp(encoder, GpuPass::Skinning);
 
         // TODO: Benchmark and investigate compute shaders for post processing.
         // TODO: Don't make color_final a parameter since we already take self.
-        if self.render_settings.debug_mode != DebugMode::Shaded {
+        if self.render_settings.debug_mode == DebugMode::Overdraw {
+            // Count the fragments for each pixel before converting to colors.
+            self.model_overdraw_pass(encoder, render_models);
+            self.overdraw_heat_map_pass(encoder, &self.pass_info.color_final.view);
+            self.timestamp(encoder, GpuPass::Model);
+        } else if !self.render_settings.debug_mode.is_shaded() {
             // TODO: Use msaa and resolve to color_final
             self.model_debug_pass(
                 encoder,
                 render_models,
-                options.mask_model_index,
-                &options.mask_material_label,
+                &options.masks(),
                 options.draw_wireframe,
                 options.draw_floor_grid,
             );
+            self.timestamp(encoder, GpuPass::Model);
         } else {
-            // Depth only pass for shadow maps.
-            self.shadow_pass(encoder, render_models.iter());
-
-            // Create the two channel shadow map for variance shadows.
-            self.variance_shadow_pass(encoder);
+            // Depth only and variance passes for each light's shadow map.
+            self.shadow_passes(encoder, render_models);
+            self.timestamp(encoder, GpuPass::Shadows);
+
+            // Skip meshes outside the camera's view.
+            let frustum = (!options.disable_frustum_culling)
+                .then(|| Frustum::from_matrix(self.camera.mvp_matrix));
+
+            // Comparison materials may use different culling or depth settings.
+            let depth_prepass = options.depth_prepass && self.material_comparison.is_none();
+            if depth_prepass {
+                self.depth_prepass(encoder, render_models, shader_database, frustum.as_ref());
+            }
 
             // Draw the models to the initial color texture.
             self.model_pass(
                 encoder,
                 render_models,
                 shader_database,
-                options.mask_model_index,
-                &options.mask_material_label,
+                &options.masks(),
                 options.draw_floor_grid,
+                frustum.as_ref(),
+                depth_prepass,
+                options.transparency,
             );
+            self.timestamp(encoder, GpuPass::Model);
+
+            // Blur the model colors based on depth before extracting bloom.
+            if self.dof_settings.enabled {
+                self.dof_pass(encoder);
+            }
+            self.timestamp(encoder, GpuPass::DepthOfField);
 
             // TODO: Will these be faster as compute passes?
             // Extract the portions of the image that contribute to bloom.
@@ -590,12 +1476,14 @@ impl SsbhRenderer {
 
             // Upscale with bilinear filtering to smooth the result.
             self.bloom_upscale_pass(encoder);
+            self.timestamp(encoder, GpuPass::Bloom);
 
             // TODO: Models with _near should be drawn after bloom but before post processing?
             // TODO: How does this impact the depth buffer?
 
             // Combine the model and bloom contributions and apply color grading.
             self.post_processing_pass(encoder, &self.pass_info.color_final.view);
+            self.timestamp(encoder, GpuPass::PostProcessing);
         }
 
         // The skeleton pass needs to happen before the silhouettes.
@@ -607,8 +1495,7 @@ impl SsbhRenderer {
             encoder,
             render_models.iter(),
             &self.pass_info.color_final.view,
-            options.draw_bones,
-            options.draw_bone_axes,
+            options,
         );
 
         // Check if silhouettes were rendered since the outline pass is slow.
@@ -643,6 +1530,71 @@ impl SsbhRenderer {
             &self.pass_info.skel_depth_stencil.view,
             &self.pass_info.skel_outline_bind_group,
         );
+
+        if let Some(profiler) = &self.gpu_profiler {
+            profiler.end_frame(encoder);
+        }
+
+        #[cfg(feature = "frame_stats")]
+        self.update_frame_stats(render_models, start.elapsed());
+    }
+
+    /// Starts recording GPU times for each pass in [SsbhRenderer::render_models].
+    ///
+    /// Returns `false` and leaves timings disabled if `device` was not created with [GPU_TIMING_FEATURES].
+    /// Recording timestamps has a small performance cost, so only enable timings when profiling.
+    pub fn enable_gpu_timings(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> bool {
+        if device.features().contains(GPU_TIMING_FEATURES) {
+            if self.gpu_profiler.is_none() {
+                self.gpu_profiler = Some(GpuProfiler::new(device, queue));
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Stops recording GPU times enabled by [SsbhRenderer::enable_gpu_timings].
+    pub fn disable_gpu_timings(&mut self) {
+        self.gpu_profiler = None;
+    }
+
+    /// The most recent GPU times for [SsbhRenderer::render_models]
+    /// or `None` if timings are disabled or not yet available.
+    ///
+    /// Timings are read back without waiting on the GPU, so the values are usually a few frames old.
+    /// The command encoder used for rendering should be submitted every frame.
+    pub fn last_frame_timings(&self) -> Option<GpuTimings> {
+        self.gpu_profiler.as_ref().and_then(|p| p.timings())
+    }
+
+    fn timestamp(&self, encoder: &mut wgpu::CommandEncoder, pass: GpuPass) {
+        if let Some(profiler) = &self.gpu_profiler {
+            profiler.timestamp(encoder, pass);
+        }
+    }
+
+    /// CPU timings for the most recent call to [SsbhRenderer::render_models]
+    /// and the [RenderModel::apply_anims] calls for the rendered models.
+    #[cfg(feature = "frame_stats")]
+    pub fn frame_stats(&self) -> crate::FrameStats {
+        self.frame_stats.get()
+    }
+
+    #[cfg(feature = "frame_stats")]
+    fn update_frame_stats(&self, render_models: &[RenderModel], encode: std::time::Duration) {
+        let bind_counts = self.bind_counts.get();
+        let mut stats = crate::FrameStats {
+            encode,
+            pipeline_binds: bind_counts.pipelines,
+            bind_group_binds: bind_counts.bind_groups,
+            ..Default::default()
+        };
+        for model in render_models {
+            stats.animation += model.animation_stats.animation;
+            stats.buffer_writes += model.animation_stats.buffer_writes;
+        }
+        self.frame_stats.set(stats);
     }
 
     /// Completes rendering by drawing the models and any overlays to `render_pass`.
@@ -653,14 +1605,70 @@ impl SsbhRenderer {
         self.overlay_pass(render_pass);
     }
 
+    /// Runs the skinning and shadow passes for `render_models`
+    /// before drawing with [SsbhRenderer::render_models_to_pass].
+    ///
+    /// This must be recorded to `encoder` before beginning the external render pass.
+    pub fn prepare_render_models(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        render_models: &[RenderModel],
+    ) {
+        self.bind_counts.set(BindCounts::default());
+        self.skinning_pass(encoder, render_models.iter());
+        self.renormal_pass(encoder, render_models.iter());
+        self.shadow_passes(encoder, render_models);
+    }
+
+    /// Draws the shaded `render_models` to an externally managed `render_pass`
+    /// without any bloom, post processing, or overlays.
+    /// Call [SsbhRenderer::prepare_render_models] first to update skinning and shadows.
+    ///
+    /// This allows integrating model rendering into an existing application's render passes.
+    /// The color attachment should use [RGBA_COLOR_FORMAT] and
+    /// the depth attachment should use [DEPTH_FORMAT].
+    /// Both attachments should use [SsbhRenderer::sample_count] samples.
+    /// Only [ModelRenderOptions::disable_frustum_culling] applies to this pass.
+    ///
+    /// The `viewport` should match the viewport of `render_pass`
+    /// and determines the split for [MaterialComparison::Split].
+    pub fn render_models_to_pass<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        render_models: &'a [RenderModel],
+        shader_database: &ShaderDatabase,
+        options: &ModelRenderOptions,
+        viewport: ScissorRect,
+    ) {
+        // The pass uses the same camera, so meshes outside the view can be skipped.
+        let frustum = (!options.disable_frustum_culling)
+            .then(|| Frustum::from_matrix(self.camera.mvp_matrix));
+        self.draw_render_models_by_tag(
+            render_models,
+            render_pass,
+            shader_database,
+            frustum.as_ref(),
+            &MODEL_PASS_TAGS,
+            None,
+            viewport,
+        );
+    }
+
     /// Renders UVs for all of the meshes with `is_selected` set to `true`.
+    ///
+    /// The UV map and display style are set by [RenderSettings::uv_map] and [RenderSettings::uv_coverage].
+    /// Select all meshes in a stage model with [UvMap::Bake1] and coverage enabled to check for overlapping lightmap UVs.
     pub fn render_models_uv<'a>(
         &'a self,
         render_pass: &mut wgpu::RenderPass<'a>,
         render_models: &'a [RenderModel],
     ) {
         // Take a render pass instead of an encoder to make this easier to integrate.
-        render_pass.set_pipeline(&self.uv_pipeline);
+        if self.render_settings.uv_coverage {
+            render_pass.set_pipeline(&self.uv_coverage_pipeline);
+        } else {
+            render_pass.set_pipeline(&self.uv_pipeline);
+        }
 
         // TODO: Just take an iterator over render meshes instead?
         for model in render_models {
@@ -668,6 +1676,101 @@ impl SsbhRenderer {
         }
     }
 
+    /// Renders the UV wireframe for the meshes in `render_model` matching `mesh_filter`
+    /// to a new texture with the given dimensions.
+    ///
+    /// This submits commands to `queue` and is independent of the renderer's size and camera.
+    /// The UV map is set by [RenderSettings::uv_map].
+    /// The returned texture has the format [wgpu::TextureFormat::Rgba8Unorm]
+    /// and supports copies for reading the data.
+    /// Avoid calling this every frame since this creates new GPU resources.
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_uv_layout(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        render_model: &RenderModel,
+        mesh_filter: impl Fn(&RenderMesh) -> bool,
+        width: u32,
+        height: u32,
+        options: &UvLayoutOptions,
+    ) -> wgpu::Texture {
+        let format = wgpu::TextureFormat::Rgba8Unorm;
+        let sample_count = if options.antialiasing {
+            MSAA_SAMPLE_COUNT
+        } else {
+            1
+        };
+        let pipeline = uv_layout_pipeline(device, format, sample_count);
+
+        let size = wgpu::Extent3d {
+            width: width.max(1),
+            height: height.max(1),
+            depth_or_array_layers: 1,
+        };
+        let output = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("UV Layout Texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::COPY_SRC
+                | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let output_view = output.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let msaa_view = (sample_count > 1).then(|| {
+            device
+                .create_texture(&wgpu::TextureDescriptor {
+                    label: Some("UV Layout MSAA Texture"),
+                    size,
+                    mip_level_count: 1,
+                    sample_count,
+                    dimension: wgpu::TextureDimension::D2,
+                    format,
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                    view_formats: &[],
+                })
+                .create_view(&wgpu::TextureViewDescriptor::default())
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("UV Layout Encoder"),
+        });
+
+        let [r, g, b, a] = options.background_color;
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("UV Layout Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: msaa_view.as_ref().unwrap_or(&output_view),
+                resolve_target: msaa_view.as_ref().map(|_| &output_view),
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color { r, g, b, a }),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        pass.set_pipeline(&pipeline);
+        render_model.draw_meshes_uv_layout(
+            &mut pass,
+            &self.per_frame_bind_group,
+            mesh_filter,
+            options.mesh_colors,
+        );
+        drop(pass);
+
+        queue.submit([encoder.finish()]);
+
+        output
+    }
+
     /// Render the collision shapes for `render_model` with hashes not in `hidden_collisions`.
     ///
     /// Collision data should be initialized first using [RenderModel::recreate_swing_collisions].
@@ -686,22 +1789,66 @@ impl SsbhRenderer {
         );
     }
 
-    fn draw_material_mask<'a>(
+    /// Render the hitbox and hurtbox capsules for `render_model`.
+    ///
+    /// Hitbox data should be initialized first using [RenderModel::recreate_hitboxes].
+    /// Capsules are drawn with the same transparent style as [SsbhRenderer::render_swing].
+    pub fn render_hitboxes(
+        &self,
+        render_pass: &mut wgpu::RenderPass<'_>,
+        render_model: &RenderModel,
+    ) {
+        render_model.draw_hitboxes(
+            render_pass,
+            &self.swing_pipeline,
+            &self.swing_camera_bind_group,
+        );
+    }
+
+    /// Draws an arrow pointing in the direction of the character light to `render_pass`.
+    /// The arrow points to the center of the scene bounds from [SsbhRenderer::update_shadow_bounds].
+    ///
+    /// The `render_pass` should use the format from [SsbhRenderer::new] like the pass from [SsbhRenderer::render_models].
+    pub fn render_light_gizmo(&self, render_pass: &mut wgpu::RenderPass<'_>) {
+        self.light_gizmo.draw(render_pass);
+    }
+
+    /// Updates the handles and highlighted state for the transform gizmo or hides the gizmo if `None`.
+    ///
+    /// This should be called after changing any fields of `gizmo` like [TransformGizmo::hovered].
+    /// The gizmo size on screen is updated automatically by [SsbhRenderer::update_camera].
+    pub fn update_transform_gizmo(&mut self, queue: &wgpu::Queue, gizmo: Option<&TransformGizmo>) {
+        self.transform_gizmo.update(queue, gizmo, &self.camera);
+    }
+
+    /// Draws the translate, rotate, or scale handles from [SsbhRenderer::update_transform_gizmo] to `render_pass`.
+    /// The handles draw on top of any existing geometry.
+    ///
+    /// The `render_pass` should use the format from [SsbhRenderer::new] like the pass from [SsbhRenderer::render_models].
+    pub fn render_transform_gizmo(&self, render_pass: &mut wgpu::RenderPass<'_>) {
+        self.transform_gizmo.draw(render_pass);
+    }
+
+    fn draw_material_masks<'a>(
         &'a self,
         pass: &mut wgpu::RenderPass<'a>,
-        render_models: impl Iterator<Item = &'a RenderModel>,
-        model_index: usize,
-        material_label: &str,
+        render_models: &'a [RenderModel],
+        masks: &[(usize, &str, [f32; 3])],
     ) {
-        // Material labels may be repeated in multiple models.
-        // Only show the selected material for the specified model.
-        if let Some(model) = render_models.into_iter().nth(model_index) {
-            model.draw_meshes_material_mask(
-                pass,
-                &self.per_frame_bind_group,
-                &self.selected_material_pipeline,
-                material_label,
-            );
+        for (model_index, material_label, color) in masks {
+            // Material labels may be repeated in multiple models.
+            // Only show the selected material for the specified model.
+            if let Some(model) = render_models.get(*model_index) {
+                // The mask pipeline uses the blend constant as the output color.
+                let [r, g, b] = color.map(f64::from);
+                pass.set_blend_constant(wgpu::Color { r, g, b, a: 0.0 });
+                model.draw_meshes_material_mask(
+                    pass,
+                    &self.per_frame_bind_group,
+                    &self.selected_material_pipeline,
+                    material_label,
+                );
+            }
         }
     }
 
@@ -734,7 +1881,11 @@ impl SsbhRenderer {
                 "Bloom Threshold Pass",
                 &self.bloom_threshold_pipeline,
                 &self.pass_info.bloom_threshold.view,
-                &self.pass_info.bloom_threshold_bind_group,
+                if self.dof_settings.enabled {
+                    &self.pass_info.dof_bloom_threshold_bind_group
+                } else {
+                    &self.pass_info.bloom_threshold_bind_group
+                },
             );
         } else {
             // TODO: Find a more efficient way to toggle bloom rendering.
@@ -755,11 +1906,25 @@ impl SsbhRenderer {
         }
     }
 
-    fn variance_shadow_pass(&self, encoder: &mut wgpu::CommandEncoder) {
+    fn shadow_passes(&self, encoder: &mut wgpu::CommandEncoder, render_models: &[RenderModel]) {
+        let lights = std::iter::once(&self.stage_uniforms.light_chr)
+            .chain(self.stage_uniforms.light_stage.iter());
+        for (layer, light) in lights.enumerate() {
+            // Lights missing from the lighting anim have no direction.
+            // Clear the shadow maps for unused lights to avoid shadowing with stale data.
+            let casts_shadows = light.direction != glam::Vec4::ZERO;
+            self.shadow_pass(encoder, render_models, layer, casts_shadows);
+
+            // Create the two channel shadow map for variance shadows.
+            self.variance_shadow_pass(encoder, layer);
+        }
+    }
+
+    fn variance_shadow_pass(&self, encoder: &mut wgpu::CommandEncoder, layer: usize) {
         let mut variance_shadow_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("Variance Shadow Pass"),
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &self.variance_shadow.view,
+                view: &self.shadow_maps.variance_layers[layer],
                 resolve_target: None,
                 ops: wgpu::Operations {
                     load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
@@ -773,7 +1938,7 @@ impl SsbhRenderer {
         variance_shadow_pass.set_pipeline(&self.variance_shadow_pipeline);
         crate::shader::variance_shadow::set_bind_groups(
             &mut variance_shadow_pass,
-            &self.variance_bind_group,
+            &self.shadow_maps.variance_bind_groups[layer],
         );
         variance_shadow_pass.draw(0..3, 0..1);
     }
@@ -794,7 +1959,7 @@ impl SsbhRenderer {
 
         for model in render_models {
             crate::model::dispatch_skinning(
-                &model.meshes,
+                model,
                 &mut skinning_pass,
                 &self.skinning_settings_bind_group,
             );
@@ -814,32 +1979,210 @@ impl SsbhRenderer {
         });
         renormal_pass.set_pipeline(&self.renormal_pipeline);
         for model in render_models {
-            crate::model::dispatch_renormal(&model.meshes, &mut renormal_pass);
+            crate::model::dispatch_renormal(model, &mut renormal_pass);
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn model_pass(
         &self,
         encoder: &mut wgpu::CommandEncoder,
         render_models: &[RenderModel],
         shader_database: &ShaderDatabase,
-        mask_model_index: usize,
-        mask_material_label: &str,
+        masks: &[(usize, &str, [f32; 3])],
         floor_grid: bool,
+        frustum: Option<&Frustum>,
+        depth_prepass: bool,
+        transparency: TransparencyMode,
     ) {
+        // Authored draw order has no separate pass for blended meshes.
+        let oit = transparency == TransparencyMode::WeightedBlended
+            && self.draw_order == DrawOrder::Sorted;
+
+        let mut pass = if oit {
+            // The sort pass is split into an order independent pass and a fullscreen composite.
+            // Blended meshes that don't support OIT like additive blending are still drawn in order.
+            let mut pass = self.begin_model_pass(encoder, Some("Model Pass"), true, depth_prepass);
+            self.set_viewport(&mut pass);
+            self.draw_render_models_by_tag(
+                render_models,
+                &mut pass,
+                shader_database,
+                frustum,
+                &["opaque", "far", "sort"],
+                Some(&self.oit_pipelines),
+                self.viewport(),
+            );
+            drop(pass);
+
+            self.oit_pass(encoder, render_models, shader_database, frustum);
+
+            let mut pass = self.begin_model_pass(encoder, Some("Model Near Pass"), false, true);
+            self.set_viewport(&mut pass);
+            pass.set_pipeline(&self.oit_composite_pipeline);
+            self.pass_info.oit_composite_bind_group.set(&mut pass);
+            pass.draw(0..3, 0..1);
+            pass
+        } else {
+            let mut pass = self.begin_model_pass(encoder, Some("Model Pass"), true, depth_prepass);
+            self.set_viewport(&mut pass);
+            pass
+        };
+
+        let tags: &[&str] = if oit { &["near"] } else { &MODEL_PASS_TAGS };
+        self.draw_render_models_by_tag(
+            render_models,
+            &mut pass,
+            shader_database,
+            frustum,
+            tags,
+            None,
+            self.viewport(),
+        );
+
+        self.draw_material_masks(&mut pass, render_models, masks);
+
+        // Draw this last to avoid obscuring models or masks.
+        if floor_grid {
+            self.floor_grid.draw(&mut pass);
+        }
+    }
+
+    fn begin_model_pass<'a>(
+        &'a self,
+        encoder: &'a mut wgpu::CommandEncoder,
+        label: Option<&'a str>,
+        clear_color: bool,
+        load_depth: bool,
+    ) -> wgpu::RenderPass<'a> {
         // TODO: Force having a color attachment for each fragment shader output in wgsl_to_wgpu?
         // TODO: Should this pass draw to a floating point target?
         // The in game format isn't 8-bit yet.
-        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("Model Pass"),
+        let (view, resolve_target) = self.pass_info.msaa_color_views(&self.pass_info.color);
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label,
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &self.pass_info.color_msaa.view,
-                resolve_target: Some(&self.pass_info.color.view),
+                view,
+                resolve_target,
                 ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    load: if clear_color {
+                        wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT)
+                    } else {
+                        wgpu::LoadOp::Load
+                    },
                     store: wgpu::StoreOp::Store,
                 },
             })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.pass_info.depth.view,
+                depth_ops: Some(wgpu::Operations {
+                    // Keep the depth from the pre-pass to skip shading occluded fragments.
+                    load: if load_depth {
+                        wgpu::LoadOp::Load
+                    } else {
+                        wgpu::LoadOp::Clear(1.0)
+                    },
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        })
+    }
+
+    fn oit_pass(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        render_models: &[RenderModel],
+        shader_database: &ShaderDatabase,
+        frustum: Option<&Frustum>,
+    ) {
+        let (accumulation, accumulation_resolve) = msaa_views(
+            &self.pass_info.oit_accumulation_msaa,
+            &self.pass_info.oit_accumulation,
+        );
+        let (revealage, revealage_resolve) = msaa_views(
+            &self.pass_info.oit_revealage_msaa,
+            &self.pass_info.oit_revealage,
+        );
+
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Order Independent Transparency Pass"),
+            color_attachments: &[
+                Some(wgpu::RenderPassColorAttachment {
+                    view: accumulation,
+                    resolve_target: accumulation_resolve,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                }),
+                Some(wgpu::RenderPassColorAttachment {
+                    view: revealage,
+                    resolve_target: revealage_resolve,
+                    ops: wgpu::Operations {
+                        // Pixels without transparent fragments are fully revealed.
+                        load: wgpu::LoadOp::Clear(wgpu::Color::WHITE),
+                        store: wgpu::StoreOp::Store,
+                    },
+                }),
+            ],
+            // Test against the depth from the opaque meshes.
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.pass_info.depth.view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        self.set_viewport(&mut pass);
+
+        self.draw_with_comparison(&mut pass, self.viewport(), |pass, comparison| {
+            let mut bind_counts = self.bind_counts.get();
+            for model in render_models.iter().filter(|m| m.is_visible) {
+                bind_counts += model.draw_meshes_oit(
+                    pass,
+                    &self.per_frame_bind_group,
+                    shader_database,
+                    &self.oit_pipelines,
+                    comparison,
+                    frustum,
+                );
+            }
+            self.bind_counts.set(bind_counts);
+        });
+    }
+
+    fn depth_prepass(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        render_models: &[RenderModel],
+        shader_database: &ShaderDatabase,
+        frustum: Option<&Frustum>,
+    ) {
+        // Sort opaque meshes front to back across all models.
+        // This fills the depth buffer with the nearest surfaces as early as possible.
+        let camera_position = self.camera.camera_pos.truncate();
+        let mut draws: Vec<_> = render_models
+            .iter()
+            .filter(|m| m.is_visible)
+            .flat_map(|model| {
+                model
+                    .depth_prepass_draws(shader_database, frustum, camera_position)
+                    .into_iter()
+                    .map(move |(mesh_index, distance)| (model, mesh_index, distance))
+            })
+            .collect();
+        draws.sort_by(|(_, _, a), (_, _, b)| a.total_cmp(b));
+
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Depth Prepass"),
+            color_attachments: &[],
             depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
                 view: &self.pass_info.depth.view,
                 depth_ops: Some(wgpu::Operations {
@@ -851,23 +2194,127 @@ impl SsbhRenderer {
             timestamp_writes: None,
             occlusion_query_set: None,
         });
+        self.set_viewport(&mut pass);
 
-        // TODO: Investigate sorting.
-        self.draw_render_models(render_models.iter(), &mut pass, shader_database, "opaque");
-        self.draw_render_models(render_models.iter(), &mut pass, shader_database, "far");
-        self.draw_render_models(render_models.iter(), &mut pass, shader_database, "sort");
-        self.draw_render_models(render_models.iter(), &mut pass, shader_database, "near");
+        for (model, mesh_index, _) in draws {
+            model.draw_mesh_depth_prepass(
+                &mut pass,
+                &self.per_frame_bind_group,
+                &self.depth_prepass_pipelines,
+                mesh_index,
+            );
+        }
+    }
 
-        self.draw_material_mask(
-            &mut pass,
-            render_models.iter(),
-            mask_model_index,
-            mask_material_label,
-        );
+    #[allow(clippy::too_many_arguments)]
+    fn draw_render_models_by_tag<'a>(
+        &'a self,
+        render_models: &'a [RenderModel],
+        pass: &mut wgpu::RenderPass<'a>,
+        shader_database: &ShaderDatabase,
+        frustum: Option<&Frustum>,
+        tags: &[&str],
+        oit_pipelines: Option<&OitPipelines>,
+        viewport: ScissorRect,
+    ) {
+        // Report mismatched sample counts clearly instead of as a pipeline validation error.
+        // These models are skipped by draw_render_models.
+        for model in render_models
+            .iter()
+            .filter(|m| m.sample_count() != self.pass_info.sample_count)
+        {
+            error!(
+                "Skipping model with sample count {} instead of {}. Models should be recreated or use RenderModel::recreate_pipelines after changing the sample count.",
+                model.sample_count(),
+                self.pass_info.sample_count
+            );
+        }
 
-        // Draw this last to avoid obscuring models or masks.
-        if floor_grid {
-            self.floor_grid.draw(&mut pass);
+        self.draw_with_comparison(pass, viewport, |pass, comparison| {
+            self.draw_render_models_by_tag_inner(
+                render_models,
+                pass,
+                shader_database,
+                comparison,
+                frustum,
+                tags,
+                oit_pipelines,
+            )
+        });
+    }
+
+    // Call draw with comparison set to true for the meshes that should use comparison materials.
+    fn draw_with_comparison<'a>(
+        &self,
+        pass: &mut wgpu::RenderPass<'a>,
+        viewport: ScissorRect,
+        mut draw: impl FnMut(&mut wgpu::RenderPass<'a>, bool),
+    ) {
+        match self.material_comparison {
+            Some(MaterialComparison::Split(split)) => {
+                // Draw each side with scissors instead of separate passes.
+                let ScissorRect {
+                    x,
+                    y,
+                    width,
+                    height,
+                } = viewport;
+                let split_x = (split.clamp(0.0, 1.0) * width as f32).round() as u32;
+                if split_x > 0 {
+                    pass.set_scissor_rect(x, y, split_x, height);
+                    draw(pass, false);
+                }
+                if split_x < width {
+                    pass.set_scissor_rect(x + split_x, y, width - split_x, height);
+                    draw(pass, true);
+                }
+                pass.set_scissor_rect(x, y, width, height);
+            }
+            Some(MaterialComparison::Comparison) => draw(pass, true),
+            None => draw(pass, false),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn draw_render_models_by_tag_inner<'a>(
+        &'a self,
+        render_models: &'a [RenderModel],
+        pass: &mut wgpu::RenderPass<'a>,
+        shader_database: &ShaderDatabase,
+        comparison: bool,
+        frustum: Option<&Frustum>,
+        tags: &[&str],
+        oit_pipelines: Option<&OitPipelines>,
+    ) {
+        match self.draw_order {
+            DrawOrder::Sorted => {
+                // Draws are only sorted within each tag to preserve the in game render order.
+                for tag in tags {
+                    self.draw_render_models(
+                        render_models.iter(),
+                        pass,
+                        shader_database,
+                        tag,
+                        true,
+                        comparison,
+                        frustum,
+                        oit_pipelines,
+                    );
+                }
+            }
+            DrawOrder::Authored => {
+                // All shader labels end with the empty string.
+                self.draw_render_models(
+                    render_models.iter(),
+                    pass,
+                    shader_database,
+                    "",
+                    false,
+                    comparison,
+                    frustum,
+                    None,
+                );
+            }
         }
     }
 
@@ -877,17 +2324,30 @@ impl SsbhRenderer {
         model_pass: &mut wgpu::RenderPass<'a>,
         shader_database: &ShaderDatabase,
         pass: &str,
+        sort: bool,
+        comparison: bool,
+        frustum: Option<&Frustum>,
+        oit_pipelines: Option<&OitPipelines>,
     ) {
-        for model in render_models.into_iter().filter(|m| m.is_visible) {
-            model.draw_meshes(
+        let mut bind_counts = self.bind_counts.get();
+        for model in render_models
+            .into_iter()
+            .filter(|m| m.is_visible && m.sample_count() == self.pass_info.sample_count)
+        {
+            bind_counts += model.draw_meshes(
                 model_pass,
                 &self.per_frame_bind_group,
                 shader_database,
                 &self.invalid_shader_pipeline,
                 &self.invalid_attributes_pipeline,
                 pass,
+                sort,
+                comparison,
+                frustum,
+                oit_pipelines,
             );
         }
+        self.bind_counts.set(bind_counts);
     }
 
     fn model_silhouette_pass<'a>(
@@ -919,6 +2379,7 @@ impl SsbhRenderer {
             timestamp_writes: None,
             occlusion_query_set: None,
         });
+        self.set_viewport(&mut pass);
 
         pass.set_pipeline(&self.silhouette_pipeline);
 
@@ -933,18 +2394,18 @@ impl SsbhRenderer {
         &self,
         encoder: &mut wgpu::CommandEncoder,
         render_models: &[RenderModel],
-        mask_model_index: usize,
-        mask_material_label: &str,
+        masks: &[(usize, &str, [f32; 3])],
         wireframe: bool,
         floor_grid: bool,
     ) {
+        let (view, resolve_target) = self.pass_info.msaa_color_views(&self.pass_info.color_final);
         let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("Model Debug Pass"),
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &self.pass_info.color_msaa.view,
-                resolve_target: Some(&self.pass_info.color_final.view),
+                view,
+                resolve_target,
                 ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(self.clear_color()),
+                    load: wgpu::LoadOp::Clear(self.background_color()),
                     store: wgpu::StoreOp::Store,
                 },
             })],
@@ -959,6 +2420,7 @@ impl SsbhRenderer {
             timestamp_writes: None,
             occlusion_query_set: None,
         });
+        self.set_viewport(&mut pass);
 
         pass.set_pipeline(&self.debug_pipeline);
         for model in render_models.iter().filter(|m| m.is_visible) {
@@ -973,12 +2435,7 @@ impl SsbhRenderer {
             }
         }
 
-        self.draw_material_mask(
-            &mut pass,
-            render_models.iter(),
-            mask_model_index,
-            mask_material_label,
-        );
+        self.draw_material_masks(&mut pass, render_models, masks);
 
         // Draw this last to avoid obscuring models or masks.
         if floor_grid {
@@ -986,12 +2443,79 @@ impl SsbhRenderer {
         }
     }
 
-    fn clear_color(&self) -> wgpu::Color {
-        wgpu::Color {
-            r: self.clear_color[0],
-            g: self.clear_color[1],
-            b: self.clear_color[2],
-            a: self.clear_color[3],
+    fn model_overdraw_pass(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        render_models: &[RenderModel],
+    ) {
+        let (view, resolve_target) = self.pass_info.msaa_color_views(&self.pass_info.color);
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Model Overdraw Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.pass_info.depth.view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        self.set_viewport(&mut pass);
+
+        pass.set_pipeline(&self.overdraw_pipeline);
+        for model in render_models.iter().filter(|m| m.is_visible) {
+            model.draw_meshes_debug(&mut pass, &self.per_frame_bind_group);
+        }
+    }
+
+    fn overdraw_heat_map_pass(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        output_view: &wgpu::TextureView,
+    ) {
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Overdraw Heat Map Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: output_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(self.background_color()),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        pass.set_pipeline(&self.overdraw_heat_map_pipeline);
+        crate::shader::post_process::set_bind_groups(
+            &mut pass,
+            &self.pass_info.post_process_bind_group,
+            &self.post_process_bind_group,
+        );
+        pass.draw(0..3, 0..1);
+    }
+
+    fn background_color(&self) -> wgpu::Color {
+        if self.transparent_background {
+            wgpu::Color::TRANSPARENT
+        } else {
+            wgpu::Color {
+                r: self.clear_color[0],
+                g: self.clear_color[1],
+                b: self.clear_color[2],
+                a: self.clear_color[3],
+            }
         }
     }
 
@@ -1000,8 +2524,7 @@ impl SsbhRenderer {
         encoder: &mut wgpu::CommandEncoder,
         render_models: impl Iterator<Item = &'a RenderModel>,
         view: &wgpu::TextureView,
-        draw_bones: bool,
-        draw_bone_axes: bool,
+        options: &ModelRenderOptions,
     ) {
         // TODO: Force having a color attachment for each fragment shader output in wgsl_to_wgpu?
         let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
@@ -1031,15 +2554,25 @@ impl SsbhRenderer {
             timestamp_writes: None,
             occlusion_query_set: None,
         });
+        self.set_viewport(&mut pass);
+
+        if options.draw_camera_frustum || options.draw_light_gizmos || options.draw_bones {
+            self.debug_gizmo.draw(
+                &mut pass,
+                options.draw_camera_frustum,
+                options.draw_light_gizmos,
+                options.draw_bones,
+            );
+        }
 
-        if draw_bones {
+        if options.draw_bones {
             for model in render_models {
                 model.draw_skeleton(
                     &self.bone_buffers,
                     &mut pass,
                     &self.skeleton_camera_bind_group,
                     &self.bone_pipelines,
-                    draw_bone_axes,
+                    options.draw_bone_axes,
                 );
             }
         }
@@ -1078,6 +2611,7 @@ impl SsbhRenderer {
             timestamp_writes: None,
             occlusion_query_set: None,
         });
+        self.set_viewport(&mut pass);
 
         if draw_bones {
             for model in render_models {
@@ -1135,7 +2669,12 @@ impl SsbhRenderer {
     }
 
     fn overlay_pass(&self, pass: &mut wgpu::RenderPass<'_>) {
-        pass.set_pipeline(&self.overlay_pipeline);
+        // Blending over the cleared output would premultiply the transparent background.
+        if self.transparent_background {
+            pass.set_pipeline(&self.overlay_transparent_pipeline);
+        } else {
+            pass.set_pipeline(&self.overlay_pipeline);
+        }
         crate::shader::overlay::set_bind_groups(pass, &self.pass_info.overlay_bind_group);
         pass.draw(0..3, 0..1);
     }
@@ -1152,7 +2691,7 @@ impl SsbhRenderer {
                 view: output_view,
                 resolve_target: None,
                 ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(self.clear_color()),
+                    load: wgpu::LoadOp::Clear(self.background_color()),
                     store: wgpu::StoreOp::Store,
                 },
             })],
@@ -1160,11 +2699,37 @@ impl SsbhRenderer {
             timestamp_writes: None,
             occlusion_query_set: None,
         });
-        pass.set_pipeline(&self.post_process_pipeline);
+        if self.transparent_background {
+            pass.set_pipeline(&self.post_process_transparent_pipeline);
+        } else {
+            pass.set_pipeline(&self.post_process_pipeline);
+        }
         crate::shader::post_process::set_bind_groups(
             &mut pass,
-            &self.pass_info.post_process_bind_group,
+            if self.dof_settings.enabled {
+                &self.pass_info.dof_post_process_bind_group
+            } else {
+                &self.pass_info.post_process_bind_group
+            },
+            &self.post_process_bind_group,
+        );
+        pass.draw(0..3, 0..1);
+    }
+
+    fn dof_pass(&self, encoder: &mut wgpu::CommandEncoder) {
+        let mut pass = create_color_pass(
+            encoder,
+            &self.pass_info.color_dof.view,
+            Some("Depth of Field Pass"),
         );
+
+        pass.set_pipeline(&self.dof_pipeline);
+        // The settings bind group layout is identical for both shaders.
+        match &self.pass_info.dof_bind_group {
+            DofBindGroup::Multisampled(bind_group) => bind_group.set(&mut pass),
+            DofBindGroup::SingleSample(bind_group) => bind_group.set(&mut pass),
+        }
+        self.dof_bind_group.set(&mut pass);
         pass.draw(0..3, 0..1);
     }
 
@@ -1183,16 +2748,18 @@ impl SsbhRenderer {
         pass.draw(0..3, 0..1);
     }
 
-    fn shadow_pass<'a>(
+    fn shadow_pass(
         &self,
         encoder: &mut wgpu::CommandEncoder,
-        render_models: impl Iterator<Item = &'a RenderModel>,
+        render_models: &[RenderModel],
+        layer: usize,
+        casts_shadows: bool,
     ) {
         let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("Shadow Pass"),
             color_attachments: &[],
             depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                view: &self.shadow_depth.view,
+                view: &self.shadow_maps.depth_layers[layer],
                 depth_ops: Some(wgpu::Operations {
                     load: wgpu::LoadOp::Clear(1.0),
                     store: wgpu::StoreOp::Store,
@@ -1203,9 +2770,13 @@ impl SsbhRenderer {
             occlusion_query_set: None,
         });
 
+        if !casts_shadows {
+            return;
+        }
+
         pass.set_pipeline(&self.shadow_pipeline);
-        for model in render_models.into_iter().filter(|m| m.is_visible) {
-            model.draw_meshes_depth(&mut pass, &self.per_frame_bind_group);
+        for model in render_models.iter().filter(|m| m.is_visible) {
+            model.draw_meshes_depth(&mut pass, &self.per_frame_bind_group, layer as u32);
         }
     }
 
@@ -1225,12 +2796,51 @@ impl SsbhRenderer {
     }
 }
 
+fn create_dof_pipeline(device: &wgpu::Device, sample_count: u32) -> wgpu::RenderPipeline {
+    if sample_count > 1 {
+        let shader = crate::shader::dof::create_shader_module(device);
+        let layout = crate::shader::dof::create_pipeline_layout(device);
+        create_screen_pipeline(device, &shader, &layout, "fs_main", RGBA_COLOR_FORMAT)
+    } else {
+        let shader = crate::shader::dof_single_sample::create_shader_module(device);
+        let layout = crate::shader::dof_single_sample::create_pipeline_layout(device);
+        create_screen_pipeline(device, &shader, &layout, "fs_main", RGBA_COLOR_FORMAT)
+    }
+}
+
 fn create_screen_pipeline(
     device: &wgpu::Device,
     module: &wgpu::ShaderModule,
     layout: &wgpu::PipelineLayout,
     fs_main: &str,
     target: wgpu::TextureFormat,
+) -> wgpu::RenderPipeline {
+    // Enable blending to allow transparent screenshots.
+    // Use max so an opaque clear color forces opaque output.
+    create_screen_pipeline_with_blend(
+        device,
+        module,
+        layout,
+        fs_main,
+        target,
+        Some(wgpu::BlendState {
+            color: wgpu::BlendComponent::OVER,
+            alpha: wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::One,
+                dst_factor: wgpu::BlendFactor::One,
+                operation: wgpu::BlendOperation::Max,
+            },
+        }),
+    )
+}
+
+fn create_screen_pipeline_with_blend(
+    device: &wgpu::Device,
+    module: &wgpu::ShaderModule,
+    layout: &wgpu::PipelineLayout,
+    fs_main: &str,
+    target: wgpu::TextureFormat,
+    blend: Option<wgpu::BlendState>,
 ) -> wgpu::RenderPipeline {
     device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
         // TODO: Labels?
@@ -1247,21 +2857,79 @@ fn create_screen_pipeline(
             entry_point: Some(fs_main),
             targets: &[Some(wgpu::ColorTargetState {
                 format: target,
-                // Enable blending to allow transparent screenshots.
-                // Use max so an opaque clear color forces opaque output.
-                blend: Some(wgpu::BlendState {
-                    color: wgpu::BlendComponent::OVER,
-                    alpha: wgpu::BlendComponent {
-                        src_factor: wgpu::BlendFactor::One,
-                        dst_factor: wgpu::BlendFactor::One,
-                        operation: wgpu::BlendOperation::Max,
-                    },
-                }),
+                blend,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    })
+}
+
+fn create_oit_composite_pipeline(device: &wgpu::Device, sample_count: u32) -> wgpu::RenderPipeline {
+    let module = crate::shader::oit_composite::create_shader_module(device);
+    let layout = crate::shader::oit_composite::create_pipeline_layout(device);
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("OIT Composite Pipeline"),
+        layout: Some(&layout),
+        vertex: wgpu::VertexState {
+            module: &module,
+            entry_point: Some("vs_main"),
+            buffers: &[],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &module,
+            entry_point: Some("fs_main"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format: RGBA_COLOR_FORMAT,
+                // Blend the averaged transparent color over the opaque meshes.
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
                 write_mask: wgpu::ColorWrites::ALL,
             })],
             compilation_options: wgpu::PipelineCompilationOptions::default(),
         }),
         primitive: wgpu::PrimitiveState::default(),
+        // The composite is drawn in the model pass with the model depth attachment.
+        depth_stencil: Some(depth_stencil_state(false, false)),
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            ..Default::default()
+        },
+        multiview: None,
+        cache: None,
+    })
+}
+
+fn create_clear_pipeline(
+    device: &wgpu::Device,
+    surface_format: wgpu::TextureFormat,
+) -> wgpu::RenderPipeline {
+    let module = crate::shader::clear::create_shader_module(device);
+    let layout = crate::shader::clear::create_pipeline_layout(device);
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Clear Pipeline"),
+        layout: Some(&layout),
+        vertex: wgpu::VertexState {
+            module: &module,
+            entry_point: Some("vs_main"),
+            buffers: &[],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &module,
+            entry_point: Some("fs_main"),
+            // Disable blending to overwrite the existing contents.
+            targets: &[Some(surface_format.into())],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState::default(),
         depth_stencil: None,
         multisample: wgpu::MultisampleState::default(),
         multiview: None,
@@ -1291,12 +2959,32 @@ fn create_color_pass<'a>(
 }
 
 // TODO: Move this to it's own module?
+// The depth texture binding type depends on the sample count.
+enum DofBindGroup {
+    Multisampled(crate::shader::dof::bind_groups::BindGroup0),
+    SingleSample(crate::shader::dof_single_sample::bind_groups::BindGroup0),
+}
+
 struct PassInfo {
+    // The allocated dimensions of the textures.
+    width: u32,
+    height: u32,
+    scale_factor: f32,
+    sample_count: u32,
+
     // TODO: most of these just need a view?
     color: TextureSamplerView,
-    color_msaa: TextureSamplerView,
+    // Passes render directly to the resolve target without MSAA.
+    color_msaa: Option<TextureSamplerView>,
     depth: TextureSamplerView,
 
+    // Weighted colors and revealage for order independent transparency.
+    oit_accumulation: TextureSamplerView,
+    oit_accumulation_msaa: Option<TextureSamplerView>,
+    oit_revealage: TextureSamplerView,
+    oit_revealage_msaa: Option<TextureSamplerView>,
+    oit_composite_bind_group: crate::shader::oit_composite::bind_groups::BindGroup0,
+
     // TODO: Most of these textures can just be cleared and reused.
     skel_depth_stencil: TextureSamplerView,
     skel_mask: TextureSamplerView,
@@ -1306,6 +2994,12 @@ struct PassInfo {
     // Final color before applying overlays
     color_final: TextureSamplerView,
 
+    // Blurred color used in place of color when depth of field is enabled.
+    color_dof: TextureSamplerView,
+    dof_bind_group: DofBindGroup,
+    dof_bloom_threshold_bind_group: crate::shader::bloom::bind_groups::BindGroup0,
+    dof_post_process_bind_group: crate::shader::post_process::bind_groups::BindGroup0,
+
     bloom_threshold: TextureSamplerView,
 
     silhouette_stencil: TextureSamplerView,
@@ -1338,8 +3032,9 @@ impl PassInfo {
         scale_factor: f32,
         color_lut: &TextureSamplerView,
         surface_format: wgpu::TextureFormat,
+        sample_count: u32,
     ) -> Self {
-        let depth = create_depth(device, width, height, MSAA_SAMPLE_COUNT);
+        let depth = create_depth(device, width, height, sample_count);
 
         // TODO: Reuse textures for outlines?
         let skel_depth_stencil = create_depth_stencil(device, width, height);
@@ -1348,10 +3043,52 @@ impl PassInfo {
         let skel_outline_bind_group = create_outline_bind_group(device, &skel_mask);
 
         let color = create_texture_sampler(device, width, height, RGBA_COLOR_FORMAT, 1);
-        let color_msaa =
-            create_texture_sampler(device, width, height, RGBA_COLOR_FORMAT, MSAA_SAMPLE_COUNT);
+        let color_msaa = (sample_count > 1).then(|| {
+            create_texture_sampler(device, width, height, RGBA_COLOR_FORMAT, sample_count)
+        });
         let color_final = create_texture_sampler(device, width, height, RGBA_COLOR_FORMAT, 1);
 
+        let oit_accumulation =
+            create_texture_sampler(device, width, height, OIT_ACCUMULATION_FORMAT, 1);
+        let oit_accumulation_msaa = (sample_count > 1).then(|| {
+            create_texture_sampler(device, width, height, OIT_ACCUMULATION_FORMAT, sample_count)
+        });
+        let oit_revealage = create_texture_sampler(device, width, height, OIT_REVEALAGE_FORMAT, 1);
+        let oit_revealage_msaa = (sample_count > 1).then(|| {
+            create_texture_sampler(device, width, height, OIT_REVEALAGE_FORMAT, sample_count)
+        });
+        let oit_composite_bind_group =
+            crate::shader::oit_composite::bind_groups::BindGroup0::from_bindings(
+                device,
+                crate::shader::oit_composite::bind_groups::BindGroupLayout0 {
+                    accumulation_texture: &oit_accumulation.view,
+                    revealage_texture: &oit_revealage.view,
+                },
+            );
+
+        let color_dof = create_texture_sampler(device, width, height, RGBA_COLOR_FORMAT, 1);
+        let dof_bind_group = if sample_count > 1 {
+            DofBindGroup::Multisampled(crate::shader::dof::bind_groups::BindGroup0::from_bindings(
+                device,
+                crate::shader::dof::bind_groups::BindGroupLayout0 {
+                    color_texture: &color.view,
+                    color_sampler: &color.sampler,
+                    depth_texture: &depth.view,
+                },
+            ))
+        } else {
+            DofBindGroup::SingleSample(
+                crate::shader::dof_single_sample::bind_groups::BindGroup0::from_bindings(
+                    device,
+                    crate::shader::dof_single_sample::bind_groups::BindGroupLayout0 {
+                        color_texture: &color.view,
+                        color_sampler: &color.sampler,
+                        depth_texture: &depth.view,
+                    },
+                ),
+            )
+        };
+
         // Bloom uses successively smaller render targets to increase the blur.
         // Account for monitor scaling to avoid a smaller perceived radius on high DPI screens.
         // Some devices like laptops or phones have weak GPUs but high DPI screens.
@@ -1367,6 +3104,14 @@ impl PassInfo {
             &color,
             BLOOM_COLOR_FORMAT,
         );
+        let dof_bloom_threshold_bind_group =
+            crate::shader::bloom::bind_groups::BindGroup0::from_bindings(
+                device,
+                crate::shader::bloom::bind_groups::BindGroupLayout0 {
+                    color_texture: &color_dof.view,
+                    color_sampler: &color_dof.sampler,
+                },
+            );
         let bloom_blur_colors = create_bloom_blur_bind_groups(
             device,
             bloom_width / 4,
@@ -1391,6 +3136,8 @@ impl PassInfo {
 
         let post_process_bind_group =
             create_post_process_bind_group(device, &color, &bloom_upscaled, color_lut);
+        let dof_post_process_bind_group =
+            create_post_process_bind_group(device, &color_dof, &bloom_upscaled, color_lut);
 
         let silhouette_stencil = create_depth_stencil(device, width, height);
         let silhouette_mask = create_texture_sampler(device, width, height, surface_format, 1);
@@ -1403,16 +3150,30 @@ impl PassInfo {
             &silhouette_outlines,
             &skel_outlines,
             surface_format.is_srgb(),
+            glam::Vec2::ONE,
         );
 
         Self {
+            width,
+            height,
+            scale_factor,
+            sample_count,
             depth,
             skel_depth_stencil,
             skel_mask,
             skel_outlines,
             color,
             color_msaa,
+            oit_accumulation,
+            oit_accumulation_msaa,
+            oit_revealage,
+            oit_revealage_msaa,
+            oit_composite_bind_group,
             color_final,
+            color_dof,
+            dof_bind_group,
+            dof_bloom_threshold_bind_group,
+            dof_post_process_bind_group,
             bloom_threshold,
             bloom_threshold_bind_group,
             bloom_blur_colors,
@@ -1429,6 +3190,128 @@ impl PassInfo {
             skel_outline_bind_group,
         }
     }
+
+    // Render to the MSAA texture and resolve to target if MSAA is enabled.
+    fn msaa_color_views<'a>(
+        &'a self,
+        target: &'a TextureSamplerView,
+    ) -> (&'a wgpu::TextureView, Option<&'a wgpu::TextureView>) {
+        msaa_views(&self.color_msaa, target)
+    }
+
+    fn update_color_lut(&mut self, device: &wgpu::Device, color_lut: &TextureSamplerView) {
+        self.post_process_bind_group =
+            create_post_process_bind_group(device, &self.color, &self.bloom_upscaled, color_lut);
+        self.dof_post_process_bind_group = create_post_process_bind_group(
+            device,
+            &self.color_dof,
+            &self.bloom_upscaled,
+            color_lut,
+        );
+    }
+}
+
+fn msaa_views<'a>(
+    msaa: &'a Option<TextureSamplerView>,
+    target: &'a TextureSamplerView,
+) -> (&'a wgpu::TextureView, Option<&'a wgpu::TextureView>) {
+    match msaa {
+        Some(msaa) => (&msaa.view, Some(&target.view)),
+        None => (&target.view, None),
+    }
+}
+
+fn bucketed_size(size: u32, bucket_size: u32) -> u32 {
+    size.max(1).next_multiple_of(bucket_size.max(1))
+}
+
+// Shadow maps for each light stored as layers of array textures.
+struct ShadowMaps {
+    depth_layers: Vec<wgpu::TextureView>,
+    variance_layers: Vec<wgpu::TextureView>,
+    variance_array: wgpu::TextureView,
+    variance_bind_groups: Vec<crate::shader::variance_shadow::bind_groups::BindGroup0>,
+}
+
+impl ShadowMaps {
+    fn new(device: &wgpu::Device) -> Self {
+        let size = |width, height| wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: SHADOW_MAP_LAYER_COUNT,
+        };
+        let depth = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Shadow Depth Texture"),
+            size: size(SHADOW_MAP_WIDTH, SHADOW_MAP_HEIGHT),
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: DEPTH_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let variance = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Variance Shadow Texture"),
+            size: size(VARIANCE_SHADOW_WIDTH, VARIANCE_SHADOW_HEIGHT),
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: VARIANCE_SHADOW_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+
+        // Render passes can only write to a single layer at a time.
+        let layer_view = |texture: &wgpu::Texture, layer| {
+            texture.create_view(&wgpu::TextureViewDescriptor {
+                dimension: Some(wgpu::TextureViewDimension::D2),
+                base_array_layer: layer,
+                array_layer_count: Some(1),
+                ..Default::default()
+            })
+        };
+        let depth_layers: Vec<_> = (0..SHADOW_MAP_LAYER_COUNT)
+            .map(|i| layer_view(&depth, i))
+            .collect();
+        let variance_layers = (0..SHADOW_MAP_LAYER_COUNT)
+            .map(|i| layer_view(&variance, i))
+            .collect();
+
+        let variance_array = variance.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+
+        // TODO: Is it ok to just use the variance shadow map sampler?
+        // We don't want a comparison sampler for this pipeline.
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+        let variance_bind_groups = depth_layers
+            .iter()
+            .map(|view| {
+                crate::shader::variance_shadow::bind_groups::BindGroup0::from_bindings(
+                    device,
+                    crate::shader::variance_shadow::bind_groups::BindGroupLayout0 {
+                        texture_shadow: view,
+                        sampler_shadow: &sampler,
+                    },
+                )
+            })
+            .collect();
+
+        Self {
+            depth_layers,
+            variance_layers,
+            variance_array,
+            variance_bind_groups,
+        }
+    }
 }
 
 fn create_depth(
@@ -1644,11 +3527,13 @@ fn create_overlay_bind_group(
     outline_texture: &TextureSamplerView,
     skel_outline_texture: &TextureSamplerView,
     is_srgb: bool,
+    uv_scale: glam::Vec2,
 ) -> crate::shader::overlay::bind_groups::BindGroup0 {
     let buffer = device.create_buffer_from_data(
         "Overlay Settings Buffer",
         &[crate::shader::overlay::OverlaySettings {
             is_srgb: UVec4::splat(is_srgb as u32),
+            uv_scale: uv_scale.extend(0.0).extend(0.0),
         }],
         wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
     );
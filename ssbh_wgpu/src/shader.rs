@@ -9,14 +9,38 @@ pub mod bloom_combine {
     include!(concat!(env!("OUT_DIR"), "/bloom_combine.rs"));
 }
 #[allow(dead_code)]
+pub mod clear {
+    include!(concat!(env!("OUT_DIR"), "/clear.rs"));
+}
+#[allow(dead_code)]
+pub mod debug_gizmo {
+    include!(concat!(env!("OUT_DIR"), "/debug_gizmo.rs"));
+}
+#[allow(dead_code)]
+pub mod dof {
+    include!(concat!(env!("OUT_DIR"), "/dof.rs"));
+}
+#[allow(dead_code)]
+pub mod dof_single_sample {
+    include!(concat!(env!("OUT_DIR"), "/dof_single_sample.rs"));
+}
+#[allow(dead_code)]
 pub mod floor_grid {
     include!(concat!(env!("OUT_DIR"), "/floor_grid.rs"));
 }
 #[allow(dead_code)]
+pub mod light_gizmo {
+    include!(concat!(env!("OUT_DIR"), "/light_gizmo.rs"));
+}
+#[allow(dead_code)]
 pub mod model {
     include!(concat!(env!("OUT_DIR"), "/model.rs"));
 }
 #[allow(dead_code)]
+pub mod oit_composite {
+    include!(concat!(env!("OUT_DIR"), "/oit_composite.rs"));
+}
+#[allow(dead_code)]
 pub mod outline {
     include!(concat!(env!("OUT_DIR"), "/outline.rs"));
 }
@@ -45,6 +69,10 @@ pub mod swing {
     include!(concat!(env!("OUT_DIR"), "/swing.rs"));
 }
 #[allow(dead_code)]
+pub mod transform_gizmo {
+    include!(concat!(env!("OUT_DIR"), "/transform_gizmo.rs"));
+}
+#[allow(dead_code)]
 pub mod variance_shadow {
     include!(concat!(env!("OUT_DIR"), "/variance_shadow.rs"));
 }
@@ -0,0 +1,20 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
+    // A fullscreen triangle using index calculations.
+    var out: VertexOutput;
+    let x = f32((i32(in_vertex_index) << 1u) & 2);
+    let y = f32(i32(in_vertex_index & 2u));
+    out.position = vec4(x * 2.0 - 1.0, y * 2.0 - 1.0, 0.0, 1.0);
+    return out;
+}
+
+// Emulate clearing to transparent for the current scissor rect.
+// The pipeline does not use blending, so the output replaces the existing value.
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4(0.0);
+}
@@ -0,0 +1,72 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uvs: vec4<f32>,
+};
+
+struct DofSettings {
+    // focus distance, focus range, blur strength, _
+    focus: vec4<f32>,
+    // Projection matrix values for converting depth to view distance.
+    depth_params: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var color_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var color_sampler: sampler;
+@group(0) @binding(2)
+var depth_texture: texture_depth_multisampled_2d;
+
+@group(1) @binding(0)
+var<uniform> dof: DofSettings;
+
+@vertex
+fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
+    // A fullscreen triangle using index calculations.
+    var out: VertexOutput;
+    let x = f32((i32(in_vertex_index) << 1u) & 2);
+    let y = f32(i32(in_vertex_index & 2u));
+    out.position = vec4(x * 2.0 - 1.0, y * 2.0 - 1.0, 0.0, 1.0);
+    out.uvs = vec4(x, 1.0 - y, 0.0, 0.0);
+    return out;
+}
+
+fn ViewDistance(uvs: vec2<f32>) -> f32 {
+    // Use the first sample if depth is multisampled.
+    let dimensions = vec2<f32>(textureDimensions(depth_texture));
+    let coords = vec2<i32>(clamp(uvs * dimensions, vec2(0.0), dimensions - 1.0));
+    let depth = textureLoad(depth_texture, coords, 0);
+    return dof.depth_params.y / (depth + dof.depth_params.x);
+}
+
+// The blur radius from 0.0 for sharp to 1.0 for maximum blur.
+fn CircleOfConfusion(distance: f32) -> f32 {
+    let range = max(dof.focus.y, 0.0001);
+    return clamp(abs(distance - dof.focus.x) / range, 0.0, 1.0);
+}
+
+// The number of samples along a golden angle spiral.
+const SAMPLE_COUNT: u32 = 32u;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(color_texture, color_sampler, in.uvs.xy);
+
+    let coc = CircleOfConfusion(ViewDistance(in.uvs.xy));
+
+    // Scale by height to have a consistent blur for different aspect ratios and resolutions.
+    let dimensions = vec2<f32>(textureDimensions(color_texture));
+    let radius = coc * dof.focus.z * vec2(dimensions.y / dimensions.x, 1.0);
+
+    // Distribute samples evenly over a disk.
+    // TODO: Prevent blurred backgrounds from bleeding over sharp foreground edges.
+    var result = color;
+    for (var i = 1u; i < SAMPLE_COUNT; i = i + 1u) {
+        let t = f32(i) / f32(SAMPLE_COUNT);
+        let angle = f32(i) * 2.39996323;
+        let offset = vec2(cos(angle), sin(angle)) * sqrt(t) * radius;
+        result = result + textureSampleLevel(color_texture, color_sampler, in.uvs.xy + offset, 0.0);
+    }
+
+    return result / f32(SAMPLE_COUNT);
+}
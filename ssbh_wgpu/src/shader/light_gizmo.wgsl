@@ -0,0 +1,53 @@
+struct VertexInput {
+    @location(0) position: vec4<f32>,
+    @location(1) normal: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) position: vec4<f32>,
+    @location(1) normal: vec4<f32>,
+};
+
+struct CameraTransforms {
+    model_view_matrix: mat4x4<f32>,
+    projection_matrix: mat4x4<f32>,
+    mvp_matrix: mat4x4<f32>,
+    mvp_inv_matrix: mat4x4<f32>,
+    camera_pos: vec4<f32>,
+    screen_dimensions: vec4<f32>, // width, height, scale, _
+};
+
+struct LightGizmo {
+    // Rotates the arrow from +Y to the light direction and translates the tip to the target.
+    transform: mat4x4<f32>,
+    color: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> camera: CameraTransforms;
+
+@group(1) @binding(0)
+var<uniform> gizmo: LightGizmo;
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+
+    // Keep a constant size in pixels on screen.
+    let target_pos = gizmo.transform * vec4(0.0, 0.0, 0.0, 1.0);
+    let scale_factor = distance(target_pos.xyz, camera.camera_pos.xyz) * 0.15;
+
+    let position = gizmo.transform * vec4(in.position.xyz * scale_factor, 1.0);
+    out.clip_position = camera.mvp_matrix * position;
+    out.position = position;
+    out.normal = gizmo.transform * vec4(in.normal.xyz, 0.0);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let viewVector = normalize(camera.camera_pos.xyz - in.position.xyz);
+    let shading = mix(0.5, 1.0, abs(dot(viewVector, normalize(in.normal.xyz))));
+    return vec4(gizmo.color.rgb * shading, 1.0);
+}
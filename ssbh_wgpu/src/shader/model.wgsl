@@ -17,17 +17,26 @@ struct RenderSettings {
     render_specular: vec4<u32>,
     render_emission: vec4<u32>,
     render_rim_lighting: vec4<u32>,
+    rim_light: vec4<f32>, // color.rgb, intensity
     render_shadows: vec4<u32>,
+    shadow_tint: vec4<f32>, // color.rgb, _
     render_bloom: vec4<u32>,
     render_vertex_color: vec4<u32>,
-    scale_vertex_color: vec4<u32>,
+    scale_vertex_color: vec4<u32>, // scale, gamma, _, _
     render_rgba: vec4<f32>,
     render_nor: vec4<u32>,
     render_prm: vec4<u32>,
+    texel_density: vec4<f32>, // target texels per unit, _, _, _
+    invalid_mesh_style: vec4<u32>,
+    invalid_shader_color: vec4<f32>,
+    invalid_attributes_color: vec4<f32>,
+    time: vec4<f32>, // seconds, frame, _, _
+    uv_map: vec4<u32>, // map1, bake1, uvSet, uvSet1, uvSet2
+    shader_complexity_range: vec4<f32>, // min, max, _, _
 };
 
 // Stage lighting is stored in nuanmb files like light00.nuanmb
-// TODO: Does Smash Ultimate support shadow casting from multiple lights?
+// Lights in lighting anims are all directional lights.
 struct Light {
     // Store combined CustomVector0 and CustomFloat0
     color: vec4<f32>,
@@ -43,7 +52,7 @@ struct SceneAttributesForShaderFx {
 };
 
 // TODO: What is the upper limit on light sets?
-// TODO: How to decide on which light is used for shadow casting?
+// Each light has its own shadow map layer with LightChr at layer 0.
 struct StageUniforms {
     light_chr: Light,
     light_stage: array<Light, 8>,
@@ -56,7 +65,7 @@ struct StageUniforms {
 var<uniform> camera: CameraTransforms;
 
 @group(0) @binding(1)
-var texture_shadow: texture_2d<f32>;
+var texture_shadow: texture_2d_array<f32>;
 @group(0) @binding(2)
 var default_sampler: sampler;
 
@@ -201,7 +210,8 @@ struct VertexInput1 {
 // TODO: Some shaders use IN_VertexLightMap like dracula castle clock tower.
 // TODO: Create a separate vertex shader for debug shading?
 struct VertexOutput {
-    @builtin(position) clip_position: vec4<f32>,
+    // The depth prepass and model pass use different pipelines but must produce identical depth.
+    @builtin(position) @invariant clip_position: vec4<f32>,
     @location(0) position: vec4<f32>,
     @location(1) normal: vec4<f32>,
     @location(2) tangent: vec4<f32>,
@@ -222,6 +232,8 @@ struct VertexOutput {
 struct VertexOutputInvalid {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) position: vec4<f32>,
+    @location(1) world_position: vec4<f32>,
+    @location(2) normal: vec4<f32>,
 };
 
 fn Blend(a: vec3<f32>, b: vec4<f32>) -> vec3<f32> {
@@ -248,6 +260,14 @@ fn TransformUv(uv: vec2<f32>, transform: vec4<f32>) -> vec2<f32> {
     return vec2(x, y);
 }
 
+fn VertexColorDebug(color: vec4<f32>) -> vec4<f32> {
+    // Treat the values as sRGB to match how they appear in external programs.
+    if render_settings.scale_vertex_color.y == 1u {
+        return vec4(pow(max(color.rgb, vec3(0.0)), vec3(2.2)), color.a);
+    }
+    return color;
+}
+
 // TODO: Rework texture blending to match the in game behavior.
 // The game usually uses white for missing required textures.
 // We use a single shader for all possible shaders.
@@ -266,6 +286,7 @@ fn GetEmissionColor(uv1: vec2<f32>, uv2: vec2<f32>) -> vec4<f32> {
         return vec4(Blend(emissionColor.rgb, emission2Color), emissionColor.a);
     }
 
+    // TODO: Pulse emission without a material animation once the in game parameters are known.
     return emissionColor;
 }
 
@@ -397,6 +418,29 @@ fn GetLight() -> Light {
     }
 }
 
+// The shadow map layer for the light from GetLight().
+fn GetShadowLayer() -> i32 {
+    if per_model.light_set_index.x == 0u {
+        return 0;
+    } else {
+        return i32(min(per_model.light_set_index.y, 7u)) + 1;
+    }
+}
+
+// The light for the shadow map layer from GetShadowLayer().
+fn GetShadowLight(layer: u32) -> Light {
+    if layer == 0u {
+        return stage_uniforms.light_chr;
+    } else {
+        return stage_uniforms.light_stage[min(layer - 1u, 7u)];
+    }
+}
+
+struct DiffuseTerms {
+    direct: vec3<f32>,
+    ambient: vec3<f32>,
+}
+
 fn DiffuseTerm(
     bake1: vec2<f32>,
     albedo: vec3<f32>,
@@ -408,7 +452,7 @@ fn DiffuseTerm(
     sss_blend: f32,
     shadow: f32,
     colorSet2: vec4<f32>
-) -> vec3<f32> {
+) -> DiffuseTerms {
     // TODO: This can be cleaned up.
     var directShading = albedo * max(nDotL, 0.0);
 
@@ -452,13 +496,16 @@ fn DiffuseTerm(
     // Assume the mix factor is 0.0 if the material doesn't have CustomVector11.
     ambientTerm *= mix(albedo, sss_color, sss_blend);
 
-    var result = directLight * shadow + ambientTerm;
+    // A nonzero tint allows shadows to only partially remove direct lighting.
+    let shadowTint = mix(render_settings.shadow_tint.rgb, vec3(1.0), shadow);
+    var result = DiffuseTerms(directLight * shadowTint, ambientTerm);
 
     // Baked stage lighting.
     // TODO: How is this different from colorSet1?
     // TODO: Check the king model on zelda_tower.
     if per_material.has_color_set1234.y == 1u && render_settings.render_vertex_color.x == 1u {
-        result *= colorSet2.rgb;
+        result.direct *= colorSet2.rgb;
+        result.ambient *= colorSet2.rgb;
     }
 
     return result;
@@ -552,8 +599,13 @@ fn SpecularBrdf(normal: vec3<f32>, tangent: vec3<f32>, bitangent: vec3<f32>, nDo
     }
 }
 
+struct SpecularTerms {
+    direct: vec3<f32>,
+    indirect: vec3<f32>,
+}
+
 fn SpecularTerm(normal: vec3<f32>, tangent: vec3<f32>, bitangent: vec3<f32>, nDotH: f32, nDotL: f32, nDotV: f32, halfAngle: vec3<f32>,
-    roughness: f32, prm_alpha: f32, specularIbl: vec3<f32>, kDirect: vec3<f32>, kIndirect: vec3<f32>) -> vec3<f32> {
+    roughness: f32, prm_alpha: f32, specularIbl: vec3<f32>, kDirect: vec3<f32>, kIndirect: vec3<f32>) -> SpecularTerms {
     var directSpecular = vec3(4.0) * SpecularBrdf(normal, tangent, bitangent, nDotH, nDotL, nDotV, halfAngle, roughness, prm_alpha);
     if per_material.has_boolean[3].x == 1u && per_material.custom_boolean[3].x == 0u {
         directSpecular = vec3(0.0);
@@ -564,9 +616,7 @@ fn SpecularTerm(normal: vec3<f32>, tangent: vec3<f32>, bitangent: vec3<f32>, nDo
         indirectSpecular = vec3(0.0);
     }
 
-    let specularTerm = directSpecular * kDirect + indirectSpecular * kIndirect;
-
-    return specularTerm;
+    return SpecularTerms(directSpecular * kDirect, indirectSpecular * kIndirect);
 }
 
 fn EmissionTerm(emissionColor: vec4<f32>) -> vec4<f32> {
@@ -587,6 +637,7 @@ fn GetF0FromIor(ior: f32) -> f32 {
 // TODO: Does this depend on the light direction and intensity?
 fn GetRimBlend(baseColor: vec3<f32>, diffusePass: vec3<f32>, nDotV: f32, nDotL: f32, occlusion: f32, vertexAmbient: vec3<f32>) -> vec3<f32> {
     var rimColor = per_material.custom_vector[14].rgb * stage_uniforms.scene_attributes.custom_vector[8].rgb;
+    rimColor = rimColor * render_settings.rim_light.rgb;
 
     // TODO: How is the overall intensity controlled?
     // Hardcoded shader constant.
@@ -603,6 +654,7 @@ fn GetRimBlend(baseColor: vec3<f32>, diffusePass: vec3<f32>, nDotV: f32, nDotL:
 
     let fresnel = pow(1.0 - nDotV, 5.0);
     var rimBlend = fresnel * stage_uniforms.scene_attributes.custom_vector[8].w * per_material.custom_vector[14].w * 0.6;
+    rimBlend = rimBlend * render_settings.rim_light.w;
     rimBlend = rimBlend * occlusion;
 
     // TODO: Rim lighting is directional?
@@ -657,8 +709,9 @@ fn GetShadow(light_position: vec4<f32>) -> f32 {
     let adjust_offset = 10.0;
 
     // Translated variance shadow mapping from in game.
-    let m1 = textureSample(texture_shadow, default_sampler, light_local).r;
-    let m2 = textureSample(texture_shadow, default_sampler, light_local).g;
+    let layer = GetShadowLayer();
+    let m1 = textureSample(texture_shadow, default_sampler, light_local, layer).r;
+    let m2 = textureSample(texture_shadow, default_sampler, light_local, layer).g;
     let sigma2 = clamp(m2 - m1 * m1 + 0.0001 * adjust_offset, 0.0, 1.0);
     let tDif = max(currentDepth - m1, 0.0);
     // Approximate Pr(x >= t) using one of Chebychev's inqequalities.
@@ -685,7 +738,9 @@ fn vs_main(
     buffer1: VertexInput1
 ) -> VertexOutput {
     var out: VertexOutput;
-    out.position = buffer0.position0;
+    // All output locations are used, so store the view space depth for OIT in the unused w component.
+    let viewDepth = -(camera.model_view_matrix * vec4(buffer0.position0.xyz, 1.0)).z;
+    out.position = vec4(buffer0.position0.xyz, viewDepth);
     out.clip_position = camera.mvp_matrix * vec4(buffer0.position0.xyz, 1.0);
     if per_material.has_float[16].x == 1u {
         // Z offset used for the ore club item.
@@ -742,10 +797,12 @@ fn vs_main(
         if per_material.custom_boolean[9].x == 1u {
             map1 /= round(per_material.custom_vector[18].xy);
             spriteIndex = (round(per_material.custom_vector[18].z) - 1.0) % spriteCount;
+        } else {
+            // Advance every CustomVector18.z frames to animate without a material animation.
+            // TODO: Check the in game behavior for the UV scale in this case.
+            let framesPerSprite = max(round(per_material.custom_vector[18].z), 1.0);
+            spriteIndex = floor(floor(render_settings.time.y) / framesPerSprite) % spriteCount;
         }
-        // else {
-        //     spriteIndex = (round(per_material.custom_vector[18].z) / floor(currentFrame)) % spriteCount;
-        // }
 
         map1.x += (1.0 / columnCount) * (spriteIndex % columnCount);
         map1.y += (1.0 / rowCount) * floor(spriteIndex / columnCount);
@@ -788,9 +845,11 @@ fn vs_main(
 @vertex
 fn vs_depth(
     buffer0: VertexInput0,
-    buffer1: VertexInput1
+    buffer1: VertexInput1,
+    @builtin(instance_index) layer: u32
 ) -> @builtin(position) vec4<f32> {
-    return GetLight().transform * vec4(buffer0.position0.xyz, 1.0);
+    // Each shadow pass draws all models from the perspective of a single light.
+    return GetShadowLight(layer).transform * vec4(buffer0.position0.xyz, 1.0);
 }
 
 @vertex
@@ -798,9 +857,23 @@ fn vs_uv(
     buffer0: VertexInput0,
     buffer1: VertexInput1
 ) -> @builtin(position) vec4<f32> {
-    // TODO: Add an option to select the UV map.
-    let uv = vec2(buffer1.map1_uvset.x, 1.0 - buffer1.map1_uvset.y);
-    return vec4(uv, 0.0, 1.0);
+    var uv = buffer1.map1_uvset.xy;
+    switch render_settings.uv_map.x {
+        case 1u: {
+            uv = buffer1.bake1.xy;
+        }
+        case 2u: {
+            uv = buffer1.map1_uvset.zw;
+        }
+        case 3u: {
+            uv = buffer1.uv_set1_uv_set2.xy;
+        }
+        case 4u: {
+            uv = buffer1.uv_set1_uv_set2.zw;
+        }
+        default: {}
+    }
+    return vec4(uv.x, 1.0 - uv.y, 0.0, 1.0);
 }
 
 fn ScreenCheckerBoard(screenPosition: vec2<f32>) -> f32 {
@@ -892,24 +965,42 @@ fn vs_main_invalid(
     var out: VertexOutputInvalid;
     out.clip_position = camera.mvp_matrix * vec4(buffer0.position0.xyz, 1.0);
     out.position = out.clip_position;
+    out.world_position = buffer0.position0;
+    out.normal = buffer0.normal0;
 
     return out;
 }
 
+fn InvalidMeshColor(in: VertexOutputInvalid, color: vec3<f32>) -> vec4<f32> {
+    switch (render_settings.invalid_mesh_style.x) {
+        case 1u: {
+            // Solid
+            return vec4(color, 1.0);
+        }
+        case 2u: {
+            // Shaded with a light from the camera.
+            let normal = normalize(in.normal.xyz);
+            let viewVector = normalize(camera.camera_pos.xyz - in.world_position.xyz);
+            let lambert = max(dot(normal, viewVector), 0.0);
+            return vec4(color * (0.25 + 0.75 * lambert), 1.0);
+        }
+        default: {
+            let position_clip = (in.position.xy / in.position.w) * 0.5 + 0.5;
+            // Account for screen dimensions and scale.
+            let checker = ScreenCheckerBoard(position_clip * camera.screen_dimensions.xy / camera.screen_dimensions.z);
+            return vec4(color * checker, 1.0);
+        }
+    }
+}
+
 @fragment
 fn fs_invalid_shader(in: VertexOutputInvalid) -> @location(0) vec4<f32> {
-    let position_clip = (in.position.xy / in.position.w) * 0.5 + 0.5;
-    // Account for screen dimensions and scale.
-    let checker = ScreenCheckerBoard(position_clip * camera.screen_dimensions.xy / camera.screen_dimensions.z);
-    return vec4(checker, 0.0, 0.0, 1.0);
+    return InvalidMeshColor(in, render_settings.invalid_shader_color.rgb);
 }
 
 @fragment
 fn fs_invalid_attributes(in: VertexOutputInvalid) -> @location(0) vec4<f32> {
-    let position_clip = (in.position.xy / in.position.w) * 0.5 + 0.5;
-    // Account for screen dimensions and scale.
-    let checker = ScreenCheckerBoard(position_clip * camera.screen_dimensions.xy / camera.screen_dimensions.z);
-    return vec4(checker, checker, 0.0, 1.0);
+    return InvalidMeshColor(in, render_settings.invalid_attributes_color.rgb);
 }
 
 @fragment
@@ -918,11 +1009,17 @@ fn fs_solid(in: VertexOutput) -> @location(0) vec4<f32> {
     return vec4(1.0);
 }
 
+@fragment
+fn fs_overdraw(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Each fragment adds one to the count with additive blending.
+    return vec4(1.0, 0.0, 0.0, 1.0);
+}
+
 @fragment
 fn fs_selected_material(in: VertexOutput) -> @location(0) vec4<f32> {
-    // TODO: Customize this color?
+    // The color is multiplied by the blend constant to allow a different color for each mask.
     // Zero alpha as a workaround to disable post processing.
-    return vec4(0.0, 1.0, 1.0, 0.0);
+    return vec4(1.0, 1.0, 1.0, 0.0);
 }
 
 @fragment
@@ -931,6 +1028,128 @@ fn fs_uv() -> @location(0) vec4<f32> {
     return vec4(1.0);
 }
 
+@fragment
+fn fs_uv_coverage() -> @location(0) vec4<f32> {
+    // Additive blending makes each overlapping face brighter.
+    return vec4(0.25, 0.25, 0.25, 1.0);
+}
+
+// The mip level selected by the hardware for UVs with the given screen space derivatives.
+fn MipLevel(uvDx: vec2<f32>, uvDy: vec2<f32>, dimensions: vec2<u32>) -> f32 {
+    let size = vec2<f32>(dimensions);
+    let dx = uvDx * size;
+    let dy = uvDy * size;
+    let d = max(dot(dx, dx), dot(dy, dy));
+    return 0.5 * log2(max(d, 1e-8));
+}
+
+fn MipLevelColor(level: f32, mipCount: u32) -> vec3<f32> {
+    // Magnified textures with negative levels don't have enough resolution.
+    if level < 0.0 {
+        return mix(vec3(1.0, 0.0, 0.0), vec3(1.0, 1.0, 1.0), clamp(-level / 2.0, 0.0, 1.0));
+    }
+
+    // Red -> yellow -> green -> cyan -> blue for each level.
+    // Smaller mips indicate the texture resolution is larger than needed.
+    let colors = array(
+        vec3(1.0, 0.0, 0.0),
+        vec3(1.0, 1.0, 0.0),
+        vec3(0.0, 1.0, 0.0),
+        vec3(0.0, 1.0, 1.0),
+        vec3(0.0, 0.0, 1.0),
+    );
+    // Sampling is clamped to the smallest mip.
+    let x = min(level, f32(max(mipCount, 1u) - 1u));
+    let i = min(u32(x), 3u);
+    return mix(colors[i], colors[i + 1u], clamp(x - f32(i), 0.0, 1.0));
+}
+
+fn TextureMipLevelColor(slot: u32, map1Dx: vec2<f32>, map1Dy: vec2<f32>, uvSetDx: vec2<f32>, uvSetDy: vec2<f32>, bake1Dx: vec2<f32>, bake1Dy: vec2<f32>) -> vec3<f32> {
+    // Use the same UV attributes as the texture debug modes.
+    // Cube maps are sampled with vectors and aren't supported.
+    var level = 0.0;
+    var mipCount = 1u;
+    switch (slot) {
+        case 0u: {
+            level = MipLevel(map1Dx, map1Dy, textureDimensions(texture0));
+            mipCount = textureNumLevels(texture0);
+        }
+        case 1u: {
+            level = MipLevel(uvSetDx, uvSetDy, textureDimensions(texture1));
+            mipCount = textureNumLevels(texture1);
+        }
+        case 3u: {
+            level = MipLevel(bake1Dx, bake1Dy, textureDimensions(texture3));
+            mipCount = textureNumLevels(texture3);
+        }
+        case 4u: {
+            level = MipLevel(map1Dx, map1Dy, textureDimensions(texture4));
+            mipCount = textureNumLevels(texture4);
+        }
+        case 5u: {
+            level = MipLevel(map1Dx, map1Dy, textureDimensions(texture5));
+            mipCount = textureNumLevels(texture5);
+        }
+        case 6u: {
+            level = MipLevel(map1Dx, map1Dy, textureDimensions(texture6));
+            mipCount = textureNumLevels(texture6);
+        }
+        case 9u: {
+            level = MipLevel(bake1Dx, bake1Dy, textureDimensions(texture9));
+            mipCount = textureNumLevels(texture9);
+        }
+        case 10u: {
+            level = MipLevel(map1Dx, map1Dy, textureDimensions(texture10));
+            mipCount = textureNumLevels(texture10);
+        }
+        case 11u: {
+            level = MipLevel(uvSetDx, uvSetDy, textureDimensions(texture11));
+            mipCount = textureNumLevels(texture11);
+        }
+        case 12u: {
+            level = MipLevel(map1Dx, map1Dy, textureDimensions(texture12));
+            mipCount = textureNumLevels(texture12);
+        }
+        case 13u: {
+            level = MipLevel(map1Dx, map1Dy, textureDimensions(texture13));
+            mipCount = textureNumLevels(texture13);
+        }
+        case 14u: {
+            level = MipLevel(uvSetDx, uvSetDy, textureDimensions(texture14));
+            mipCount = textureNumLevels(texture14);
+        }
+        default: {
+            return vec3(0.5);
+        }
+    }
+
+    // Show meshes without the texture in gray.
+    if per_material.has_texture[slot].x == 0u {
+        return vec3(0.5);
+    }
+
+    return MipLevelColor(level, mipCount);
+}
+
+fn TexelDensityColor(uvDx: vec2<f32>, uvDy: vec2<f32>, positionDx: vec3<f32>, positionDy: vec3<f32>, dimensions: vec2<u32>) -> vec3<f32> {
+    // Compare the areas of the screen pixel in texture space and world space.
+    let size = vec2<f32>(dimensions);
+    let texelArea = abs(determinant(mat2x2(uvDx * size, uvDy * size)));
+    let worldArea = length(cross(positionDx, positionDy));
+    let density = sqrt(texelArea / max(worldArea, 1e-8));
+
+    // Use a log scale to show the same color for the same relative difference from the target.
+    let targetDensity = max(render_settings.texel_density.x, 1e-8);
+    let x = clamp(log2(max(density, 1e-8) / targetDensity) / 2.0, -1.0, 1.0);
+
+    // Blue for stretched UVs, green for the target density, and red for wasted texture resolution.
+    if x < 0.0 {
+        return mix(vec3(0.0, 1.0, 0.0), vec3(0.0, 0.0, 1.0), -x);
+    } else {
+        return mix(vec3(0.0, 1.0, 0.0), vec3(1.0, 0.0, 0.0), x);
+    }
+}
+
 @fragment
 fn fs_debug(in: VertexOutput) -> @location(0) vec4<f32> {
     let map1 = in.map1.xy;
@@ -940,6 +1159,16 @@ fn fs_debug(in: VertexOutput) -> @location(0) vec4<f32> {
     let uvSet2 = in.uv_set2_bake1.xy;
     let bake1 = in.uv_set2_bake1.zw;
 
+    // Calculate derivatives before any non uniform control flow.
+    let map1Dx = dpdx(map1);
+    let map1Dy = dpdy(map1);
+    let uvSetDx = dpdx(uvSet);
+    let uvSetDy = dpdy(uvSet);
+    let bake1Dx = dpdx(bake1);
+    let bake1Dy = dpdy(bake1);
+    let positionDx = dpdx(in.position.xyz);
+    let positionDy = dpdy(in.position.xyz);
+
     let colorSet1 = in.color_set1;
     let colorSet2 = in.color_set2_combined;
     let colorSet3 = in.color_set3;
@@ -1008,25 +1237,25 @@ fn fs_debug(in: VertexOutput) -> @location(0) vec4<f32> {
             outColor = vec4(pow(color, vec3(2.2)), in.tangent.w);
         }
         case 4u: {
-            outColor = colorSet1;
+            outColor = VertexColorDebug(colorSet1);
         }
         case 5u: {
-            outColor = colorSet2;
+            outColor = VertexColorDebug(colorSet2);
         }
         case 6u: {
-            outColor = colorSet3;
+            outColor = VertexColorDebug(colorSet3);
         }
         case 7u: {
-            outColor = colorSet4;
+            outColor = VertexColorDebug(colorSet4);
         }
         case 8u: {
-            outColor = colorSet5;
+            outColor = VertexColorDebug(colorSet5);
         }
         case 9u: {
-            outColor = colorSet6;
+            outColor = VertexColorDebug(colorSet6);
         }
         case 10u: {
-            outColor = colorSet7;
+            outColor = VertexColorDebug(colorSet7);
         }
         case 11u: {
             outColor = textureSample(texture0, sampler0, map1);
@@ -1131,13 +1360,23 @@ fn fs_debug(in: VertexOutput) -> @location(0) vec4<f32> {
             outColor = vec4(albedoColorFinal.rgb + emissionColor.rgb, albedoColor.a * emissionColor.a);
         }
         case 36u: {
-            // The min complexity isn't 0.0 since shaders are all non empty.
             // Normalize to the 0.0 to 1.0 range to use the full colormap.
-            let min = 0.10816174646489705;
-            let complexity = (per_material.shader_complexity.x - min) / (1.0 - min);
+            let minComplexity = render_settings.shader_complexity_range.x;
+            let maxComplexity = render_settings.shader_complexity_range.y;
+            let range = max(maxComplexity - minComplexity, 1e-8);
+            let complexity = clamp((per_material.shader_complexity.x - minComplexity) / range, 0.0, 1.0);
             let color = plasma_colormap(complexity);
             outColor = vec4(pow(color, vec3(2.2)), 1.0);
         }
+        case 43u: {
+            let color = TextureMipLevelColor(render_settings.debug_mode.y, map1Dx, map1Dy, uvSetDx, uvSetDy, bake1Dx, bake1Dy);
+            outColor = vec4(pow(color, vec3(2.2)), 1.0);
+        }
+        case 44u: {
+            // Triangles are flat, so the derivatives are constant for each triangle.
+            let color = TexelDensityColor(map1Dx, map1Dy, positionDx, positionDy, textureDimensions(texture0));
+            outColor = vec4(pow(color, vec3(2.2)), 1.0);
+        }
         default: {
             outColor = vec4(1.0);
         }
@@ -1394,6 +1633,42 @@ fn GetPbrParams(in: VertexOutput, is_front: bool) -> PbrParams {
 // consistent naming conventions with rust code?
 @fragment
 fn fs_main(in: VertexOutput, @builtin(front_facing) is_front: bool) -> @location(0) vec4<f32> {
+    return ShadedColor(in, is_front);
+}
+
+struct OitOutput {
+    @location(0) accumulation: vec4<f32>,
+    @location(1) revealage: vec4<f32>,
+}
+
+// Weighted blended order independent transparency.
+// https://jcgt.org/published/0002/02/09/
+fn OitColor(color: vec3<f32>, alpha: f32, view_depth: f32) -> OitOutput {
+    // Weight closer and more opaque fragments more heavily using equation 7 from the paper.
+    // Nonlinear depth values are almost constant, so use the linear view space depth.
+    let z = abs(view_depth);
+    let weight = alpha * clamp(10.0 / (1e-5 + pow(z / 5.0, 2.0) + pow(z / 200.0, 6.0)), 1e-2, 3e3);
+
+    var out: OitOutput;
+    out.accumulation = vec4(color, alpha) * weight;
+    out.revealage = vec4(alpha);
+    return out;
+}
+
+@fragment
+fn fs_oit(in: VertexOutput, @builtin(front_facing) is_front: bool) -> OitOutput {
+    // Apply the source alpha blend factor in the shader.
+    let color = ShadedColor(in, is_front);
+    return OitColor(color.rgb * color.a, color.a, in.position.w);
+}
+
+@fragment
+fn fs_oit_premultiplied(in: VertexOutput, @builtin(front_facing) is_front: bool) -> OitOutput {
+    let color = ShadedColor(in, is_front);
+    return OitColor(color.rgb, color.a, in.position.w);
+}
+
+fn ShadedColor(in: VertexOutput, is_front: bool) -> vec4<f32> {
     let map1 = in.map1.xy;
     let map1_dual = in.map1.zw;
     let uvSet = in.uv_set_uv_set1.xy;
@@ -1456,20 +1731,51 @@ fn fs_main(in: VertexOutput, @builtin(front_facing) is_front: bool) -> @location
     let kIndirect = FresnelSchlick(nDotV, kSpecular) * params.nor.a * 0.5; // TODO: Why is 0.5 needed here?
     let specularPass = SpecularTerm(normal, tangent, bitangent, nDotH, max(nDotL, 0.0), nDotV, halfAngle, params.roughness, params.prm_alpha, specularIbl, kDirect, kIndirect);
 
+    let kDiffuse = max(vec3(1.0 - params.metalness), vec3(0.0));
+    let diffuseDirect = (diffusePass.direct * kDiffuse) / 3.14159;
+    let diffuseAmbient = (diffusePass.ambient * kDiffuse) / 3.14159;
+
+    // Assume materials without PRM omit the specular code entirely.
+    var specularDirect = vec3(0.0);
+    var specularIndirect = vec3(0.0);
+    if params.has_specular {
+        specularDirect = specularPass.direct * params.ambient_occlusion;
+        specularIndirect = specularPass.indirect * params.ambient_occlusion;
+    }
+
+    // TODO: Emission is weakened somehow?
+    let emission = EmissionTerm(params.emission);
+
+    // Isolate the individual shading terms before any remaining color or alpha adjustments.
+    switch (render_settings.debug_mode.x) {
+        case 37u: {
+            return vec4(params.albedo.rgb, outAlpha);
+        }
+        case 38u: {
+            return vec4(diffuseDirect, outAlpha);
+        }
+        case 39u: {
+            return vec4(specularDirect, outAlpha);
+        }
+        case 40u: {
+            return vec4(diffuseAmbient + specularIndirect, outAlpha);
+        }
+        case 41u: {
+            return vec4(emission.rgb * 0.5, outAlpha * emission.a);
+        }
+        default: {}
+    }
+
     var outColor = vec3(0.0, 0.0, 0.0);
     if render_settings.render_diffuse.x == 1u {
-        let kDiffuse = max(vec3(1.0 - params.metalness), vec3(0.0));
-        outColor += (diffusePass * kDiffuse) / 3.14159;
+        outColor += diffuseDirect + diffuseAmbient;
     }
 
-    // Assume materials without PRM omit the specular code entirely.
-    if render_settings.render_specular.x == 1u && params.has_specular {
-        outColor += specularPass * params.ambient_occlusion;
+    if render_settings.render_specular.x == 1u {
+        outColor += specularDirect + specularIndirect;
     }
 
     if render_settings.render_emission.x == 1u {
-        // TODO: Emission is weakened somehow?
-        let emission = EmissionTerm(params.emission);
         outColor += emission.rgb * 0.5;
         outAlpha *= emission.a;
     }
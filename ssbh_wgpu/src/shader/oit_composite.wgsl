@@ -0,0 +1,34 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
+    // A fullscreen triangle using index calculations.
+    var out: VertexOutput;
+    let x = f32((i32(in_vertex_index) << 1u) & 2);
+    let y = f32(i32(in_vertex_index & 2u));
+    out.position = vec4(x * 2.0 - 1.0, y * 2.0 - 1.0, 0.0, 1.0);
+    return out;
+}
+
+@group(0) @binding(0)
+var accumulation_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var revealage_texture: texture_2d<f32>;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let coords = vec2<i32>(in.position.xy);
+    let revealage = textureLoad(revealage_texture, coords, 0).r;
+
+    // Skip pixels without any transparent fragments.
+    if revealage >= 1.0 {
+        discard;
+    }
+
+    // Calculate the weighted average color of all the transparent fragments.
+    let accumulation = textureLoad(accumulation_texture, coords, 0);
+    let averageColor = accumulation.rgb / max(accumulation.a, 1e-5);
+    return vec4(averageColor, 1.0 - revealage);
+}
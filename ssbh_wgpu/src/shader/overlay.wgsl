@@ -27,7 +27,9 @@ var outline_texture2: texture_2d<f32>;
 var outline_sampler: sampler;
 
 struct OverlaySettings {
-    is_srgb: vec4<u32>
+    is_srgb: vec4<u32>,
+    // The fraction of the input textures used for rendering.
+    uv_scale: vec4<f32>,
 }
 
 @group(0) @binding(5)
@@ -43,10 +45,11 @@ fn GetSrgb(colorLinear: f32) -> f32 {
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    let color = textureSample(color_texture, color_sampler, in.uvs.xy);
-    let outline1 = textureSample(outline_texture1, outline_sampler, in.uvs.xy).r;
+    let uvs = in.uvs.xy * settings.uv_scale.xy;
+    let color = textureSample(color_texture, color_sampler, uvs);
+    let outline1 = textureSample(outline_texture1, outline_sampler, uvs).r;
     // TODO: Find a better way to handle the outline channels.
-    let outline2 = textureSample(outline_texture2, outline_sampler, in.uvs.xy).r;
+    let outline2 = textureSample(outline_texture2, outline_sampler, uvs).r;
 
     // TODO: Set outline color?
     var output = mix(color.rgb, vec3(0.0, 1.0, 1.0), outline1);
@@ -29,6 +29,16 @@ var bloom_texture: texture_2d<f32>;
 @group(0) @binding(5)
 var bloom_sampler: sampler;
 
+struct PostProcessSettings {
+    // exposure scale, inverse gamma, _, _
+    exposure_gamma: vec4<f32>,
+    // tone mapping, transparent background, _, _
+    tone_mapping: vec4<u32>,
+};
+
+@group(1) @binding(0)
+var<uniform> post_process: PostProcessSettings;
+
 fn GetPostProcessingResult(colorLinear: vec3<f32>) -> vec3<f32> {
     let srgb = pow(colorLinear, vec3(0.4545449912548065));
     var result = srgb * 0.9375 + 0.03125;
@@ -43,6 +53,26 @@ fn GetPostProcessingResult(colorLinear: vec3<f32>) -> vec3<f32> {
     return result;
 }
 
+// Narkowicz's fitted ACES curve.
+fn FilmicToneMapping(colorLinear: vec3<f32>) -> vec3<f32> {
+    let x = colorLinear;
+    return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), vec3(0.0), vec3(1.0));
+}
+
+fn ToneMapping(colorLinear: vec3<f32>) -> vec3<f32> {
+    switch (post_process.tone_mapping.x) {
+        case 1u: {
+            return colorLinear;
+        }
+        case 2u: {
+            return FilmicToneMapping(colorLinear);
+        }
+        default: {
+            return GetPostProcessingResult(colorLinear);
+        }
+    }
+}
+
 // TODO: Is this the same computation as in game?
 fn GetSrgb(colorLinear: f32) -> f32 {
     if colorLinear <= 0.00031308 {
@@ -56,6 +86,32 @@ fn GetSrgbVec3(colorLinear: vec3<f32>) -> vec3<f32> {
     return vec3(GetSrgb(colorLinear.x), GetSrgb(colorLinear.y), GetSrgb(colorLinear.z));
 }
 
+// The number of overlapping fragments that maps to the end of the heat ramp.
+const MAX_OVERDRAW: f32 = 10.0;
+
+fn HeatRamp(t: f32) -> vec3<f32> {
+    // Black -> blue -> green -> yellow -> red -> white.
+    let colors = array(
+        vec3(0.0, 0.0, 0.0),
+        vec3(0.0, 0.0, 1.0),
+        vec3(0.0, 1.0, 0.0),
+        vec3(1.0, 1.0, 0.0),
+        vec3(1.0, 0.0, 0.0),
+        vec3(1.0, 1.0, 1.0),
+    );
+    let x = clamp(t, 0.0, 1.0) * 5.0;
+    let i = min(u32(x), 4u);
+    return mix(colors[i], colors[i + 1u], x - f32(i));
+}
+
+@fragment
+fn fs_overdraw(in: VertexOutput) -> @location(0) vec4<f32> {
+    // The red channel stores the number of fragments drawn for each pixel.
+    let count = textureSample(color_texture, color_sampler, in.uvs.xy).r;
+    let color = HeatRamp(count / MAX_OVERDRAW);
+    return vec4(pow(color, vec3(2.2)), 1.0);
+}
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     let color = textureSample(color_texture, color_sampler, in.uvs.xy);
@@ -63,12 +119,27 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     let bloom = textureSample(bloom_texture, bloom_sampler, in.uvs.xy).rgb;
     var output = color.rgb + bloom;
 
+    var alpha = clamp(color.a, 0.0, 1.0);
+    if post_process.tone_mapping.y == 1u {
+        // Let bloom over the background contribute to coverage instead of discarding it.
+        alpha = clamp(max(alpha, max(bloom.r, max(bloom.g, bloom.b))), 0.0, 1.0);
+        // Models are blended over a background with zero alpha, so the color is premultiplied.
+        // Convert to straight alpha before the nonlinear tone mapping and gamma
+        // for compositing over any backdrop.
+        if alpha > 0.0 {
+            output = output / alpha;
+        }
+    }
+
+    output = output * post_process.exposure_gamma.x;
+
     // Don't post process the background but still allow bloom.
     // TODO: Investigate how this is handled in game.
-    output = mix(output, GetPostProcessingResult(output.rgb), clamp(color.a, 0.0, 1.0));
+    output = mix(output, ToneMapping(output.rgb), clamp(color.a, 0.0, 1.0));
+    output = pow(max(output, vec3(0.0)), vec3(post_process.exposure_gamma.y));
 
     // Assume an sRGB frame buffer and don't gamma correct here.
     // Set alpha to allow for transparent screenshots.
     // TODO: What to use for alpha here?
-    return vec4(output, clamp(color.a, 0.0, 1.0));
+    return vec4(output, alpha);
 }
\ No newline at end of file
@@ -5,15 +5,24 @@ struct VertexInput0 {
     tangent0: vec4<f32>,
 };
 
+// This should be identical to the MeshObjectInfo struct in skinning.wgsl.
+struct MeshObjectInfo {
+    parent_index: vec4<i32>,
+    vertex_count: vec4<u32>,
+};
+
+// All meshes in a model share the same buffers with dynamic offsets for each mesh.
 @group(0) @binding(0) var<storage, read_write> vertices: array<VertexInput0>;
 @group(0) @binding(1) var<storage, read> adj_data: array<i32>;
 
+@group(1) @binding(0) var<uniform> mesh_object_info: MeshObjectInfo;
+
 // TODO: Can this be done in the skinning compute pass?
 // A single shader would require synchronization to ensure all writes to position0 finish.
 @compute
 @workgroup_size(256)
 fn main(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
-    let vertexCountLength = arrayLength(&vertices);
+    let vertexCountLength = mesh_object_info.vertex_count.x;
     let index = global_invocation_id.x;
     if index >= vertexCountLength {
         return;
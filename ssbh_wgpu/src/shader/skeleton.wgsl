@@ -7,6 +7,7 @@ struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) position: vec4<f32>,
     @location(1) normal: vec4<f32>,
+    @location(2) @interpolate(flat) bone_index: i32,
 };
 
 struct CameraTransforms {
@@ -22,15 +23,26 @@ struct WorldTransforms {
     transforms: array<mat4x4<f32>, 512>
 };
 
+struct ModelTransform {
+    // The world transform applied to the entire model.
+    transform: mat4x4<f32>,
+    // Inverse transpose of above to use for normals and tangents.
+    transform_inv_transpose: mat4x4<f32>,
+};
+
 struct BoneColors {
     // The world transform of each bone.
     // This is used for parenting objects to bones.
     colors: array<vec4<f32>, 512>
 };
 
-struct PerBone {
-    // index, parent_index, _, _
-    indices: vec4<i32>
+// Values are packed into vec4 to reduce the size of the uniform buffer.
+struct BoneVisibility {
+    // Nonzero if the bone is not hidden by the bone filter.
+    visible: array<vec4<u32>, 128>,
+    parent_indices: array<vec4<i32>, 128>,
+    // min_screen_size, _, _, _
+    min_screen_size: vec4<f32>,
 };
 
 // TODO: Bind groups should be ordered by how frequently they change for performance.
@@ -43,17 +55,70 @@ var<uniform> world_transforms: WorldTransforms;
 @group(1) @binding(1)
 var<uniform> bone_colors: BoneColors;
 
-// TODO: Just use instancing?
-@group(2) @binding(0)
-var<uniform> per_bone: PerBone;
+@group(1) @binding(2)
+var<uniform> bone_visibility: BoneVisibility;
+
+@group(1) @binding(3)
+var<uniform> model_transform: ModelTransform;
+
+// The bone's world transform after placing the model in the scene.
+fn bone_transform(bone_index: i32) -> mat4x4<f32> {
+    return model_transform.transform * world_transforms.transforms[bone_index];
+}
+
+// The distance between two world positions in logical pixels on screen.
+fn screen_length(a: vec3<f32>, b: vec3<f32>) -> f32 {
+    let clip_a = camera.mvp_matrix * vec4(a, 1.0);
+    let clip_b = camera.mvp_matrix * vec4(b, 1.0);
+    let ndc_difference = clip_a.xy / clip_a.w - clip_b.xy / clip_b.w;
+    return length(ndc_difference * 0.5 * camera.screen_dimensions.xy / camera.screen_dimensions.z);
+}
+
+fn is_visible(bone_index: i32, position: vec3<f32>, parent_position: vec3<f32>) -> bool {
+    let i = u32(bone_index);
+    if bone_visibility.visible[i / 4u][i % 4u] == 0u {
+        return false;
+    }
 
+    // Bones without a parent have no length, so always show them.
+    let parent_index = bone_visibility.parent_indices[i / 4u][i % 4u];
+    if parent_index < 0 {
+        return true;
+    }
+
+    return screen_length(position, parent_position) >= bone_visibility.min_screen_size.x;
+}
+
+fn is_bone_visible(bone_index: i32) -> bool {
+    let position = bone_transform(bone_index)[3].xyz;
+
+    let i = u32(bone_index);
+    let parent_index = bone_visibility.parent_indices[i / 4u][i % 4u];
+    var parent_position = position;
+    if parent_index >= 0 && parent_index < 512 {
+        parent_position = bone_transform(parent_index)[3].xyz;
+    }
+
+    return is_visible(bone_index, position, parent_position);
+}
+
+fn is_joint_visible(bone_index: i32) -> bool {
+    // Joint transforms point from the bone to its parent along the Y-axis.
+    let transform = bone_transform(bone_index);
+    let position = (transform * vec4(0.0, 0.0, 0.0, 1.0)).xyz;
+    let parent_position = (transform * vec4(0.0, 1.0, 0.0, 1.0)).xyz;
+    return is_visible(bone_index, position, parent_position);
+}
+
+// Each bone is drawn as a separate instance.
 @vertex
-fn vs_axes(in: VertexInput) -> VertexOutput {
-    let bone_index = per_bone.indices.x;
+fn vs_axes(in: VertexInput, @builtin(instance_index) instance_index: u32) -> VertexOutput {
+    let bone_index = i32(instance_index);
     var out: VertexOutput;
-    if bone_index >= 0 && bone_index < 512 {
+    out.bone_index = bone_index;
+    if bone_index >= 0 && bone_index < 512 && is_bone_visible(bone_index) {
         let position = vec4(in.position.xyz, 1.0);
-        out.clip_position = camera.mvp_matrix * world_transforms.transforms[bone_index] * position;
+        out.clip_position = camera.mvp_matrix * bone_transform(bone_index) * position;
         out.position = vec4(in.position.xyz, 1.0);
         // Use the normal as the color.
         out.normal = vec4(in.position.xyz, 0.0);
@@ -63,38 +128,42 @@ fn vs_axes(in: VertexInput) -> VertexOutput {
 }
 
 @vertex
-fn vs_bone(in: VertexInput) -> VertexOutput {
-    let bone_index = per_bone.indices.x;
+fn vs_bone(in: VertexInput, @builtin(instance_index) instance_index: u32) -> VertexOutput {
+    let bone_index = i32(instance_index);
     var out: VertexOutput;
-    if bone_index >= 0 && bone_index < 512 {
-        let bone_pos = world_transforms.transforms[per_bone.indices.x] * vec4(0.0, 0.0, 0.0, 1.0);
+    out.bone_index = bone_index;
+    if bone_index >= 0 && bone_index < 512 && is_bone_visible(bone_index) {
+        let bone_pos = bone_transform(bone_index) * vec4(0.0, 0.0, 0.0, 1.0);
 
         // Keep a constant size in pixels on screen.
+        // This should match BONE_SCALE in bone_hit.rs.
         let scale_factor = distance(bone_pos.xyz, camera.camera_pos.xyz) * 0.0025;
         let position = vec4(in.position.xyz * scale_factor, 1.0);
 
-        out.clip_position = camera.mvp_matrix * world_transforms.transforms[per_bone.indices.x] * position;
+        out.clip_position = camera.mvp_matrix * bone_transform(bone_index) * position;
         out.position = in.position;
-        out.normal = world_transforms.transforms[per_bone.indices.x] * vec4(in.normal.xyz, 0.0);
+        out.normal = bone_transform(bone_index) * vec4(in.normal.xyz, 0.0);
     }
     return out;
 }
 
 @vertex
-fn vs_joint(in: VertexInput) -> VertexOutput {
-    let bone_index = per_bone.indices.x;
+fn vs_joint(in: VertexInput, @builtin(instance_index) instance_index: u32) -> VertexOutput {
+    let bone_index = i32(instance_index);
     var out: VertexOutput;
-    if bone_index >= 0 && bone_index < 512 {
-        let bone_pos = world_transforms.transforms[bone_index] * vec4(0.0, 0.0, 0.0, 1.0);
+    out.bone_index = bone_index;
+    if bone_index >= 0 && bone_index < 512 && is_joint_visible(bone_index) {
+        let bone_pos = bone_transform(bone_index) * vec4(0.0, 0.0, 0.0, 1.0);
 
         // Keep a constant size in pixels on screen.
         // Only scale the ends of the joint without affecting the height.
+        // This should match JOINT_SCALE in bone_hit.rs.
         let scale_factor = distance(bone_pos.xyz, camera.camera_pos.xyz) * 0.005;
         let position = vec4(in.position.xyz * vec3(scale_factor, 1.0, scale_factor), 1.0);
 
-        out.clip_position = camera.mvp_matrix * world_transforms.transforms[bone_index] * position;
+        out.clip_position = camera.mvp_matrix * bone_transform(bone_index) * position;
         out.position = in.position;
-        out.normal = world_transforms.transforms[bone_index] * vec4(in.normal.xyz, 0.0);
+        out.normal = bone_transform(bone_index) * vec4(in.normal.xyz, 0.0);
     }
     return out;
 }
@@ -104,7 +173,7 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     let viewVector = normalize(camera.camera_pos.xyz - in.position.xyz);
     let shading = mix(0.5, 1.0, dot(viewVector, normalize(in.normal.xyz)));
     var color = vec3(0.0);
-    let bone_index = per_bone.indices.x;
+    let bone_index = in.bone_index;
     if bone_index >= 0 && bone_index < 512 {
         color = bone_colors.colors[bone_index].xyz * shading;
     }
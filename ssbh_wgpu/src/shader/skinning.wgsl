@@ -13,13 +13,12 @@ struct VertexWeight {
 // The in game buffer is vec4[4096] with the first vec4 containing a u32 bone count.
 // This allows at most 511 bones with 2 matrices per bone.
 // TODO: What two matrices are stored per bone?
-// Remove the length field to improve compatibility.
-// This gives a more generous alignment without exceeding 65536 bytes.
-struct AnimatedWorldTransforms {
+// Use a storage buffer sized for the model's bones to save memory for models with few bones.
+struct SkinningTransform {
     // bone_world.inv() * animated_bone_world
-    transforms: array<mat4x4<f32>, 512>,
+    transform: mat4x4<f32>,
     // Inverse transpose of above to use for normals and tangents.
-    transforms_inv_transpose: array<mat4x4<f32>, 512>,
+    transform_inv_transpose: mat4x4<f32>,
 };
 
 struct WorldTransforms {
@@ -28,11 +27,20 @@ struct WorldTransforms {
     transforms: array<mat4x4<f32>, 512>
 };
 
+struct ModelTransform {
+    // The world transform applied to the entire model.
+    transform: mat4x4<f32>,
+    // Inverse transpose of above to use for normals and tangents.
+    transform_inv_transpose: mat4x4<f32>,
+};
+
 // TODO: Rework this to PerMesh?
 struct MeshObjectInfo {
     // TODO: Alignment?
     // Just use X for now.
-    parent_index: vec4<i32>
+    parent_index: vec4<i32>,
+    // The vertex buffers use the same binding size for all meshes, so also store the vertex count.
+    vertex_count: vec4<u32>,
 };
 
 struct SkinningSettings {
@@ -40,12 +48,14 @@ struct SkinningSettings {
     enable_skinning: vec4<u32>
 };
 
+// All meshes in a model share the same buffers with dynamic offsets for each mesh.
 @group(0) @binding(0) var<storage, read> src : array<VertexInput0>;
 @group(0) @binding(1) var<storage, read> vertex_weights : array<VertexWeight>;
 @group(0) @binding(2) var<storage, read_write> dst : array<VertexInput0>;
 
-@group(1) @binding(0) var<uniform> transforms: AnimatedWorldTransforms;
+@group(1) @binding(0) var<storage, read> transforms: array<SkinningTransform>;
 @group(1) @binding(1) var<uniform> world_transforms: WorldTransforms;
+@group(1) @binding(2) var<uniform> model_transform: ModelTransform;
 
 @group(2) @binding(0) var<uniform> mesh_object_info: MeshObjectInfo;
 
@@ -54,7 +64,7 @@ struct SkinningSettings {
 @compute
 @workgroup_size(256)
 fn main(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
-    let total = arrayLength(&src);
+    let total = mesh_object_info.vertex_count.x;
     let index = global_invocation_id.x;
     if index >= total {
         return;
@@ -88,17 +98,24 @@ fn main(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
         normal = vec3(0.0);
         tangent = vec3(0.0);
 
+        let bone_count = i32(arrayLength(&transforms));
         for (var i = 0; i < 4; i = i + 1) {
             // Only 511 influences are supported in game.
             let bone_index = influence.bone_indices[i];
-            if bone_index >= 0 && bone_index < 511 {
-                position = position + (transforms.transforms[bone_index] * vec4(vertex.position0.xyz, 1.0) * influence.weights[i]).xyz;
-                normal = normal + (transforms.transforms_inv_transpose[bone_index] * vec4(vertex.normal0.xyz, 0.0) * influence.weights[i]).xyz;
-                tangent = tangent + (transforms.transforms_inv_transpose[bone_index] * vec4(vertex.tangent0.xyz, 0.0) * influence.weights[i]).xyz;
+            if bone_index >= 0 && bone_index < 511 && bone_index < bone_count {
+                let skinning = transforms[bone_index];
+                position = position + (skinning.transform * vec4(vertex.position0.xyz, 1.0) * influence.weights[i]).xyz;
+                normal = normal + (skinning.transform_inv_transpose * vec4(vertex.normal0.xyz, 0.0) * influence.weights[i]).xyz;
+                tangent = tangent + (skinning.transform_inv_transpose * vec4(vertex.tangent0.xyz, 0.0) * influence.weights[i]).xyz;
             }
         }
     }
 
+    // Place the model in the scene after any bone transforms.
+    position = (model_transform.transform * vec4(position, 1.0)).xyz;
+    normal = (model_transform.transform_inv_transpose * vec4(normal, 0.0)).xyz;
+    tangent = (model_transform.transform_inv_transpose * vec4(tangent, 0.0)).xyz;
+
     var out: VertexInput0;
     out.position0 = vec4(position, 1.0);
     out.normal0 = vec4(normalize(normal), 0.0);
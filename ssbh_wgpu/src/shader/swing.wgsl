@@ -5,6 +5,7 @@ struct VertexInput {
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
 };
 
 struct CameraTransforms {
@@ -20,6 +21,13 @@ struct WorldTransforms {
     transforms: array<mat4x4<f32>, 512>
 };
 
+struct ModelTransform {
+    // The world transform applied to the entire model.
+    transform: mat4x4<f32>,
+    // Inverse transpose of above to use for normals and tangents.
+    transform_inv_transpose: mat4x4<f32>,
+};
+
 // Swing collisions can use two bones like capsules.
 // Some shapes like spheres will use only one bone.
 struct PerShape {
@@ -33,25 +41,32 @@ var<uniform> camera: CameraTransforms;
 
 @group(1) @binding(0)
 var<uniform> world_transforms: WorldTransforms;
+@group(1) @binding(1)
+var<uniform> model_transform: ModelTransform;
 
+// Each shape is drawn as an instance.
 @group(2) @binding(0)
-var<uniform> per_shape: PerShape;
+var<storage, read> shapes: array<PerShape>;
 
 // TODO: Is it easier to make this part of skeleton.wgsl?
 // TODO: Add a second transform for the bone transform?
 
 @vertex
-fn vs_main(in: VertexInput) -> VertexOutput {
+fn vs_main(in: VertexInput, @builtin(instance_index) instance_index: u32) -> VertexOutput {
     // TODO: Use a consistent naming convention like PerScene, PerSkel, PerObject etc.
     // Assume the vertex buffer is centered on the origin with unit size.
     var out: VertexOutput;
+    let per_shape = shapes[instance_index];
 
     var world_position = per_shape.start_transform * vec4(in.position.xyz, 1.0);
     if per_shape.bone_indices.x >= 0 && per_shape.bone_indices.x < 512 {
         world_position = world_transforms.transforms[per_shape.bone_indices.x] * world_position;
     }
 
+    world_position = model_transform.transform * world_position;
+
     out.clip_position = camera.mvp_matrix * world_position;
+    out.color = per_shape.color;
     return out;
 }
 
@@ -59,5 +74,5 @@ fn vs_main(in: VertexInput) -> VertexOutput {
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     // Premultiplied alpha.
     let alpha = 0.15;
-    return vec4(per_shape.color.rgb * alpha, alpha);
+    return vec4(in.color.rgb * alpha, alpha);
 }
\ No newline at end of file
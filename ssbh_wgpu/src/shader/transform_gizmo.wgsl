@@ -0,0 +1,42 @@
+struct VertexInput {
+    @location(0) position: vec4<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+};
+
+struct CameraTransforms {
+    model_view_matrix: mat4x4<f32>,
+    projection_matrix: mat4x4<f32>,
+    mvp_matrix: mat4x4<f32>,
+    mvp_inv_matrix: mat4x4<f32>,
+    camera_pos: vec4<f32>,
+    screen_dimensions: vec4<f32>, // width, height, scale, _
+};
+
+struct TransformGizmo {
+    // Positions and orients the handles with a scale for a constant size on screen.
+    transform: mat4x4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> camera: CameraTransforms;
+
+@group(1) @binding(0)
+var<uniform> gizmo: TransformGizmo;
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = camera.mvp_matrix * gizmo.transform * vec4(in.position.xyz, 1.0);
+    out.color = in.color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4(in.color.rgb, 1.0);
+}
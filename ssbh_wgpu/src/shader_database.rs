@@ -1,4 +1,5 @@
 use serde_json::Value;
+use ssbh_data::mesh_data::MeshObjectData;
 use std::collections::HashMap;
 
 #[derive(Debug, Default)]
@@ -25,29 +26,58 @@ pub struct ShaderProgram {
 }
 
 impl ShaderProgram {
-    /// Returns `true` if `attributes` has all the vertex attributes required by this shader program.
-    // TODO: Take an iterator instead?
-    pub fn has_required_attributes(&self, attributes: &[String]) -> bool {
+    /// Returns the mesh vertex attribute names like `"map1"` or `"colorSet1"` required by this shader program.
+    ///
+    /// This excludes `"ink_color_set"` since it isn't part of the mesh.
+    pub fn required_attributes(&self) -> Vec<&str> {
+        // TODO: Investigate how "ink_color_set" is generated.
         self.vertex_attributes
             .iter()
             .map(|a| attribute_name_no_channels(a))
             .filter(|a| *a != "ink_color_set")
+            .collect()
+    }
+
+    /// Returns the texture parameter names like `"Texture0"` used by this shader program.
+    pub fn required_textures(&self) -> Vec<&str> {
+        self.material_parameters
+            .iter()
+            .map(|p| split_param(p).0)
+            .filter(|p| p.starts_with("Texture"))
+            .collect()
+    }
+
+    /// Returns `true` if `attributes` has all the vertex attributes required by this shader program.
+    // TODO: Take an iterator instead?
+    pub fn has_required_attributes(&self, attributes: &[String]) -> bool {
+        self.required_attributes()
+            .into_iter()
             .all(|required| attributes.iter().any(|a| a == required))
     }
 
     /// Returns the vertex attribute names required by this shader program not present in `attributes`.
     // TODO: Take an iterator instead?
     pub fn missing_required_attributes(&self, attributes: &[String]) -> Vec<String> {
-        self.vertex_attributes
-            .iter()
-            .map(|a| attribute_name_no_channels(a))
-            .filter(|required| {
-                *required != "ink_color_set" && !attributes.iter().any(|a| a == required)
-            })
+        self.required_attributes()
+            .into_iter()
+            .filter(|required| !attributes.iter().any(|a| a == required))
             .map(|a| a.to_string())
             .collect()
     }
 
+    /// Returns `true` if `mesh` has all the vertex attributes required by this shader program.
+    ///
+    /// The renderer draws meshes with missing attributes using
+    /// [RenderSettings::invalid_attributes_color](crate::RenderSettings::invalid_attributes_color).
+    pub fn has_required_mesh_attributes(&self, mesh: &MeshObjectData) -> bool {
+        self.has_required_attributes(&mesh_attribute_names(mesh))
+    }
+
+    /// Returns the vertex attribute names required by this shader program not present in `mesh`.
+    pub fn missing_mesh_attributes(&self, mesh: &MeshObjectData) -> Vec<String> {
+        self.missing_required_attributes(&mesh_attribute_names(mesh))
+    }
+
     /// Returns the color channels accessed by the shaders as `[x, y, z, w]`.
     pub fn accessed_channels(&self, param_name: &str) -> [bool; 4] {
         let mut channels = [false; 4];
@@ -73,6 +103,18 @@ impl ShaderProgram {
     }
 }
 
+/// The names of all the vertex attributes in `mesh` like `"Position0"` or `"map1"`.
+pub(crate) fn mesh_attribute_names(mesh: &MeshObjectData) -> Vec<String> {
+    mesh.positions
+        .iter()
+        .chain(&mesh.normals)
+        .chain(&mesh.tangents)
+        .chain(&mesh.texture_coordinates)
+        .chain(&mesh.color_sets)
+        .map(|a| a.name.clone())
+        .collect()
+}
+
 fn attribute_name_no_channels(attribute: &str) -> &str {
     // "map1.xy" -> "map1"
     // "map1" -> "map1"
@@ -150,6 +192,33 @@ impl ShaderDatabase {
     pub fn get(&self, shader_label: &str) -> Option<&ShaderProgram> {
         self.0.get(shader_label.get(..24).unwrap_or(""))
     }
+
+    /// The [ShaderProgram::complexity] for the specified `shader_label` while ignoring tags like `"_opaque"`.
+    ///
+    /// This allows sorting materials by cost or showing a legend for
+    /// [DebugMode::ShaderComplexity](crate::DebugMode::ShaderComplexity).
+    pub fn complexity(&self, shader_label: &str) -> Option<f64> {
+        self.get(shader_label).map(|p| p.complexity)
+    }
+
+    /// The minimum and maximum [ShaderProgram::complexity] of all programs
+    /// or `None` if the database is empty.
+    pub fn complexity_range(&self) -> Option<(f64, f64)> {
+        self.0
+            .values()
+            .map(|p| p.complexity)
+            .fold(None, |range, c| {
+                Some(match range {
+                    Some((min, max)) => (f64::min(min, c), f64::max(max, c)),
+                    None => (c, c),
+                })
+            })
+    }
+
+    /// An iterator over the shader labels without tags and their programs.
+    pub fn programs(&self) -> impl Iterator<Item = (&str, &ShaderProgram)> {
+        self.0.iter().map(|(k, v)| (k.as_str(), v))
+    }
 }
 
 impl Default for ShaderDatabase {
@@ -252,6 +321,26 @@ mod tests {
         .has_required_attributes(&["map1".to_string()]));
     }
 
+    #[test]
+    fn program_complexity() {
+        let database = ShaderDatabase::new();
+        let (min, max) = database.complexity_range().unwrap();
+        let complexity = database
+            .complexity("SFX_PBS_0100000008008269_opaque")
+            .unwrap();
+        assert!(min > 0.0 && max <= 1.0);
+        assert!(complexity >= min && complexity <= max);
+        assert_eq!(None, database.complexity("SFX_PBS_invalid"));
+    }
+
+    #[test]
+    fn complexity_range_empty() {
+        assert_eq!(
+            None,
+            ShaderDatabase::from_iter(Vec::new()).complexity_range()
+        );
+    }
+
     #[test]
     fn missing_required_attributes_empty() {
         // Check that "ink_color_set" is ignored since it isn't part of the mesh
@@ -277,4 +366,44 @@ mod tests {
             .missing_required_attributes(&[])
         );
     }
+
+    #[test]
+    fn required_attributes_textures() {
+        let program = ShaderProgram {
+            vertex_attributes: vec![
+                "ink_color_set".to_string(),
+                "map1.xy".to_string(),
+                "colorSet1.xyzw".to_string(),
+            ],
+            material_parameters: vec![
+                "CustomVector8.xyzw".to_string(),
+                "Texture0.xyzw".to_string(),
+                "Sampler0".to_string(),
+                "Texture9.xyz".to_string(),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(vec!["map1", "colorSet1"], program.required_attributes());
+        assert_eq!(vec!["Texture0", "Texture9"], program.required_textures());
+    }
+
+    #[test]
+    fn missing_mesh_attributes() {
+        let program = ShaderProgram {
+            vertex_attributes: vec!["map1.xy".to_string(), "colorSet1.xyzw".to_string()],
+            ..Default::default()
+        };
+        let mesh = MeshObjectData {
+            texture_coordinates: vec![ssbh_data::mesh_data::AttributeData {
+                name: "map1".to_string(),
+                data: ssbh_data::mesh_data::VectorData::Vector2(Vec::new()),
+            }],
+            ..Default::default()
+        };
+        assert!(!program.has_required_mesh_attributes(&mesh));
+        assert_eq!(
+            vec!["colorSet1".to_string()],
+            program.missing_mesh_attributes(&mesh)
+        );
+    }
 }
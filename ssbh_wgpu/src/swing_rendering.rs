@@ -1,10 +1,12 @@
-use std::collections::HashSet;
+use std::{collections::HashSet, ops::Range};
 
 use glam::Vec4Swizzles;
 use prc::hash40::Hash40;
 use ssbh_data::skel_data::SkelData;
 
 use crate::{
+    hitbox_rendering::HitboxData,
+    model::mesh_creation::TransformBuffers,
     shape::{
         capsule_mesh_buffers, capsule_vertices, plane_mesh_buffers, sphere_mesh_buffers,
         IndexedMeshBuffers,
@@ -25,27 +27,75 @@ pub struct SwingRenderData {
     pub plane_buffers: IndexedMeshBuffers,
     pub bind_group1: crate::shader::swing::bind_groups::BindGroup1,
     pub collisions: CollisionData,
+    pub hitboxes: HitboxData,
 }
 
+/// Per shape data for all collision shapes.
+///
+/// Each shape is drawn as an instance with its index in the per shape buffer.
+/// Shapes of the same type are contiguous to draw all shapes of a type in a single call.
 pub struct CollisionData {
-    pub spheres: Vec<ShapeRenderData>,
-    pub ovals: Vec<(IndexedMeshBuffers, ShapeRenderData)>,
-    pub ellipsoids: Vec<ShapeRenderData>,
-    pub capsules: Vec<(IndexedMeshBuffers, ShapeRenderData)>,
-    pub planes: Vec<ShapeRenderData>,
+    per_shape: Vec<crate::shader::swing::PerShape>,
+    hashes: Vec<u64>,
+    // Bindings can't be empty, so only create buffers if there are shapes.
+    shape_buffers: Option<ShapeBuffers>,
+    spheres: Range<u32>,
+    ellipsoids: Range<u32>,
+    planes: Range<u32>,
+    ovals: Range<u32>,
+    capsules: Range<u32>,
+    // Ovals and capsules have unique vertex data for each shape.
+    oval_buffers: Vec<IndexedMeshBuffers>,
+    capsule_buffers: Vec<IndexedMeshBuffers>,
     // TODO: Is there another way to store bone information?
     pub prc_capsules: Vec<Capsule>,
     pub prc_ovals: Vec<Oval>,
 }
 
+pub struct ShapeBuffers {
+    // Store the buffer for updating shapes without allocating new bind groups.
+    pub buffer: wgpu::Buffer,
+    pub bind_group: crate::shader::swing::bind_groups::BindGroup2,
+}
+
+impl ShapeBuffers {
+    pub fn new(
+        device: &wgpu::Device,
+        per_shape: &[crate::shader::swing::PerShape],
+    ) -> Option<Self> {
+        // Bindings can't be empty, so only create buffers if there are shapes.
+        (!per_shape.is_empty()).then(|| {
+            let buffer = device.create_buffer_from_data(
+                "Swing Per Shape Buffer",
+                per_shape,
+                wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            );
+
+            let bind_group = crate::shader::swing::bind_groups::BindGroup2::from_bindings(
+                device,
+                crate::shader::swing::bind_groups::BindGroupLayout2 {
+                    shapes: buffer.as_entire_buffer_binding(),
+                },
+            );
+
+            Self { buffer, bind_group }
+        })
+    }
+}
+
 impl CollisionData {
     pub fn new() -> Self {
         Self {
-            spheres: Vec::new(),
-            ovals: Vec::new(),
-            ellipsoids: Vec::new(),
-            capsules: Vec::new(),
-            planes: Vec::new(),
+            per_shape: Vec::new(),
+            hashes: Vec::new(),
+            shape_buffers: None,
+            spheres: 0..0,
+            ellipsoids: 0..0,
+            planes: 0..0,
+            ovals: 0..0,
+            capsules: 0..0,
+            oval_buffers: Vec::new(),
+            capsule_buffers: Vec::new(),
             // TODO: Find a better way to store the prc data for animating.
             prc_capsules: Vec::new(),
             prc_ovals: Vec::new(),
@@ -58,12 +108,41 @@ impl CollisionData {
         skel: Option<&SkelData>,
         world_transforms: &[glam::Mat4],
     ) -> Self {
+        let mut per_shape = Vec::new();
+        let mut hashes = Vec::new();
+
+        let spheres = push_shapes(
+            &mut per_shape,
+            &mut hashes,
+            spheres(&swing_prc.spheres, skel),
+        );
+        let ellipsoids = push_shapes(
+            &mut per_shape,
+            &mut hashes,
+            ellipsoids(&swing_prc.ellipsoids, skel),
+        );
+        let planes = push_shapes(&mut per_shape, &mut hashes, planes(&swing_prc.planes, skel));
+
+        let (oval_shapes, oval_buffers) = ovals(device, &swing_prc.ovals, skel, world_transforms);
+        let ovals = push_shapes(&mut per_shape, &mut hashes, oval_shapes);
+
+        let (capsule_shapes, capsule_buffers) =
+            capsules(device, &swing_prc.capsules, skel, world_transforms);
+        let capsules = push_shapes(&mut per_shape, &mut hashes, capsule_shapes);
+
+        let shape_buffers = ShapeBuffers::new(device, &per_shape);
+
         Self {
-            spheres: spheres(device, &swing_prc.spheres, skel),
-            ovals: ovals(device, &swing_prc.ovals, skel, world_transforms),
-            ellipsoids: ellipsoids(device, &swing_prc.ellipsoids, skel),
-            capsules: capsules(device, &swing_prc.capsules, skel, world_transforms),
-            planes: planes(device, &swing_prc.planes, skel),
+            per_shape,
+            hashes,
+            shape_buffers,
+            spheres,
+            ellipsoids,
+            planes,
+            ovals,
+            capsules,
+            oval_buffers,
+            capsule_buffers,
             // TODO: Find a better way to store the prc data for animating.
             prc_capsules: swing_prc.capsules.clone(),
             prc_ovals: swing_prc.ovals.clone(),
@@ -73,14 +152,15 @@ impl CollisionData {
 
 // TODO: Figure out which objects don't need to be recreated every frame.
 impl SwingRenderData {
-    pub fn new(device: &wgpu::Device, bone_world_transforms_buffer: &wgpu::Buffer) -> Self {
+    pub fn new(device: &wgpu::Device, transforms: &TransformBuffers) -> Self {
         let sphere_buffers = sphere_mesh_buffers(device);
         let plane_buffers = plane_mesh_buffers(device);
 
         let bind_group1 = crate::shader::swing::bind_groups::BindGroup1::from_bindings(
             device,
             crate::shader::swing::bind_groups::BindGroupLayout1 {
-                world_transforms: bone_world_transforms_buffer.as_entire_buffer_binding(),
+                world_transforms: transforms.world_transforms.as_entire_buffer_binding(),
+                model_transform: transforms.model_transform.as_entire_buffer_binding(),
             },
         );
 
@@ -90,6 +170,7 @@ impl SwingRenderData {
             bind_group1,
             // TODO: Group these together into a struct?
             collisions: CollisionData::new(),
+            hitboxes: HitboxData::new(),
         }
     }
 
@@ -106,112 +187,75 @@ impl SwingRenderData {
     }
 
     pub fn animate_collisions(
-        &self,
+        &mut self,
         queue: &wgpu::Queue,
         skel: Option<&SkelData>,
         world_transforms: &[glam::Mat4],
     ) {
-        // TODO: Write to the buffer for capsules and ovals.
+        let collisions = &mut self.collisions;
+
+        // Only capsule like shapes need to be updated since other shapes use the bone transforms.
         // TODO: Find a better way to store the PRC collision data.
         // We need information from the PRC to regenerate vertex data while animating for some shapes.
-        for ((buffers, shape), c) in self
-            .collisions
+        for ((i, buffers), c) in collisions
             .capsules
-            .iter()
-            .zip(self.collisions.prc_capsules.iter())
+            .clone()
+            .zip(collisions.capsule_buffers.iter())
+            .zip(collisions.prc_capsules.iter())
         {
             // TODO: Find a way to avoid specifying this logic in multiple places.
-            // TODO: How to reduce repeated logic for buffer creation and writing?
             let (height, per_shape) = capsules_per_shape(skel, c, world_transforms, CAPSULE_COLOR);
             let data = capsule_vertices(8, 8, height, c.start_radius, c.end_radius);
             queue.write_data(&buffers.vertex_buffer, &data);
 
-            // TODO: Find a way to avoid needing the swing_prc again.
-            shape.update(queue, per_shape);
+            collisions.per_shape[i as usize] = per_shape;
         }
 
-        for ((buffers, shape), o) in self
-            .collisions
+        for ((i, buffers), o) in collisions
             .ovals
-            .iter()
-            .zip(self.collisions.prc_ovals.iter())
+            .clone()
+            .zip(collisions.oval_buffers.iter())
+            .zip(collisions.prc_ovals.iter())
         {
-            // TODO: Find a way to avoid specifying this logic in multiple places.
-            // TODO: How to reduce repeated logic for buffer creation and writing?
             // TODO: Implement proper oval rendering.
             // Use capsules for now since they both use a start/end bone.
-            let capsule = Capsule {
-                name: o.name,
-                start_bonename: o.start_bonename,
-                end_bonename: o.end_bonename,
-                start_offset_x: o.start_offset_x,
-                start_offset_y: o.start_offset_y,
-                start_offset_z: o.start_offset_z,
-                end_offset_x: o.end_offset_x,
-                end_offset_y: o.end_offset_y,
-                end_offset_z: o.end_offset_z,
-                start_radius: o.radius,
-                end_radius: o.radius,
-            };
+            let capsule = oval_capsule(o);
             let (height, per_shape) =
                 capsules_per_shape(skel, &capsule, world_transforms, OVAL_COLOR);
             let data = capsule_vertices(8, 8, height, o.radius, o.radius);
             queue.write_data(&buffers.vertex_buffer, &data);
 
-            // TODO: Find a way to avoid needing the swing_prc again.
-            shape.update(queue, per_shape);
+            collisions.per_shape[i as usize] = per_shape;
         }
-    }
-}
-
-pub struct ShapeRenderData {
-    hash: u64,
-    // Store the buffer for updating shapes without allocating new bind groups.
-    buffer: wgpu::Buffer,
-    bind_group: crate::shader::swing::bind_groups::BindGroup2,
-}
-
-impl ShapeRenderData {
-    pub fn new(
-        device: &wgpu::Device,
-        hash: u64,
-        per_shape: crate::shader::swing::PerShape,
-    ) -> Self {
-        let buffer = device.create_buffer_from_data(
-            "Swing Buffer2",
-            &[per_shape],
-            wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        );
-
-        let bind_group = crate::shader::swing::bind_groups::BindGroup2::from_bindings(
-            device,
-            crate::shader::swing::bind_groups::BindGroupLayout2 {
-                per_shape: buffer.as_entire_buffer_binding(),
-            },
-        );
 
-        Self {
-            hash,
-            buffer,
-            bind_group,
+        // Update all shapes at once to avoid many small writes.
+        if let Some(shape_buffers) = &collisions.shape_buffers {
+            queue.write_data(&shape_buffers.buffer, &collisions.per_shape);
         }
     }
+}
 
-    pub fn update(&self, queue: &wgpu::Queue, per_shape: crate::shader::swing::PerShape) {
-        queue.write_data(&self.buffer, &[per_shape]);
+fn push_shapes(
+    per_shape: &mut Vec<crate::shader::swing::PerShape>,
+    hashes: &mut Vec<u64>,
+    shapes: Vec<(u64, crate::shader::swing::PerShape)>,
+) -> Range<u32> {
+    let start = per_shape.len() as u32;
+    for (hash, shape) in shapes {
+        hashes.push(hash);
+        per_shape.push(shape);
     }
+    start..per_shape.len() as u32
 }
 
 fn spheres(
-    device: &wgpu::Device,
     spheres: &[Sphere],
     skel: Option<&SkelData>,
-) -> Vec<ShapeRenderData> {
+) -> Vec<(u64, crate::shader::swing::PerShape)> {
     spheres
         .iter()
         .map(|s| {
-            ShapeRenderData::new(
-                device,
+            (
                 s.name.0,
                 crate::shader::swing::PerShape {
                     bone_indices: glam::IVec4::new(bone_index(skel, s.bonename), -1, -1, -1),
@@ -225,16 +269,14 @@ fn spheres(
 }
 
 fn ellipsoids(
-    device: &wgpu::Device,
     ellipsoids: &[Ellipsoid],
     skel: Option<&SkelData>,
-) -> Vec<ShapeRenderData> {
+) -> Vec<(u64, crate::shader::swing::PerShape)> {
     ellipsoids
         .iter()
         .map(|e| {
             // TODO: Is r rotation since it's usually 0?
-            ShapeRenderData::new(
-                device,
+            (
                 e.name.0,
                 crate::shader::swing::PerShape {
                     bone_indices: glam::IVec4::new(bone_index(skel, e.bonename), -1, -1, -1),
@@ -247,40 +289,44 @@ fn ellipsoids(
         .collect()
 }
 
+fn oval_capsule(o: &Oval) -> Capsule {
+    Capsule {
+        name: o.name,
+        start_bonename: o.start_bonename,
+        end_bonename: o.end_bonename,
+        start_offset_x: o.start_offset_x,
+        start_offset_y: o.start_offset_y,
+        start_offset_z: o.start_offset_z,
+        end_offset_x: o.end_offset_x,
+        end_offset_y: o.end_offset_y,
+        end_offset_z: o.end_offset_z,
+        start_radius: o.radius,
+        end_radius: o.radius,
+    }
+}
+
 fn ovals(
     device: &wgpu::Device,
     ovals: &[Oval],
     skel: Option<&SkelData>,
     world_transforms: &[glam::Mat4],
-) -> Vec<(IndexedMeshBuffers, ShapeRenderData)> {
+) -> (
+    Vec<(u64, crate::shader::swing::PerShape)>,
+    Vec<IndexedMeshBuffers>,
+) {
     ovals
         .iter()
         .map(|o| {
             // TODO: Implement proper oval rendering.
             // Use capsules for now since they both use a start/end bone.
-            let capsule = Capsule {
-                name: o.name,
-                start_bonename: o.start_bonename,
-                end_bonename: o.end_bonename,
-                start_offset_x: o.start_offset_x,
-                start_offset_y: o.start_offset_y,
-                start_offset_z: o.start_offset_z,
-                end_offset_x: o.end_offset_x,
-                end_offset_y: o.end_offset_y,
-                end_offset_z: o.end_offset_z,
-                start_radius: o.radius,
-                end_radius: o.radius,
-            };
+            let capsule = oval_capsule(o);
             let (height, per_shape) =
                 capsules_per_shape(skel, &capsule, world_transforms, OVAL_COLOR);
             let mesh_buffers = capsule_mesh_buffers(device, height, o.radius, o.radius);
 
-            (
-                mesh_buffers,
-                ShapeRenderData::new(device, o.name.0, per_shape),
-            )
+            ((o.name.0, per_shape), mesh_buffers)
         })
-        .collect()
+        .unzip()
 }
 
 fn capsules(
@@ -288,7 +334,10 @@ fn capsules(
     capsules: &[Capsule],
     skel: Option<&SkelData>,
     world_transforms: &[glam::Mat4],
-) -> Vec<(IndexedMeshBuffers, ShapeRenderData)> {
+) -> (
+    Vec<(u64, crate::shader::swing::PerShape)>,
+    Vec<IndexedMeshBuffers>,
+) {
     capsules
         .iter()
         .map(|c| {
@@ -296,12 +345,9 @@ fn capsules(
             // TODO: Rework this to get the vertex data to write to an existing buffer.
             let mesh_buffers = capsule_mesh_buffers(device, height, c.start_radius, c.end_radius);
 
-            (
-                mesh_buffers,
-                ShapeRenderData::new(device, c.name.0, per_shape),
-            )
+            ((c.name.0, per_shape), mesh_buffers)
         })
-        .collect()
+        .unzip()
 }
 
 fn capsules_per_shape(
@@ -354,19 +400,14 @@ fn capsule_transform(
     ))
 }
 
-fn planes(
-    device: &wgpu::Device,
-    planes: &[Plane],
-    skel: Option<&SkelData>,
-) -> Vec<ShapeRenderData> {
+fn planes(planes: &[Plane], skel: Option<&SkelData>) -> Vec<(u64, crate::shader::swing::PerShape)> {
     planes
         .iter()
         .map(|p| {
             // Assume the plane points in the direction of the positive Z-axis.
             // Rotate the plane to point in the direction (nx, ny, nz).
             // TODO: Does this correctly match the in game behavior?
-            ShapeRenderData::new(
-                device,
+            (
                 p.name.0,
                 crate::shader::swing::PerShape {
                     bone_indices: glam::IVec4::new(bone_index(skel, p.bonename), -1, -1, -1),
@@ -401,104 +442,109 @@ pub fn draw_swing_collisions(
     swing_camera_bind_group: &crate::shader::swing::bind_groups::BindGroup0,
     hidden_collisions: &HashSet<u64>,
 ) {
+    let collisions = &render_data.collisions;
+    let Some(shape_buffers) = &collisions.shape_buffers else {
+        return;
+    };
+
     pass.set_pipeline(swing_pipeline);
+    crate::shader::swing::set_bind_groups(
+        pass,
+        swing_camera_bind_group,
+        &render_data.bind_group1,
+        &shape_buffers.bind_group,
+    );
 
     // Just draw most shapes as spheres for now.
-    // TODO: Create vertex and index buffers for each shape.
-    // TODO: Not all bind groups need to be set more than once.
     // TODO: Allow toggling rendering of certain shapes or shape types.
-    draw_shapes(
+    draw_instances(
         pass,
         &render_data.sphere_buffers,
-        &render_data.collisions.spheres,
-        &render_data.bind_group1,
-        swing_camera_bind_group,
+        collisions.spheres.clone(),
+        &collisions.hashes,
         hidden_collisions,
     );
 
-    draw_shapes_with_buffers(
+    // Ellipsoids use the sphere geometry.
+    draw_instances(
         pass,
-        &render_data.collisions.ovals,
-        &render_data.bind_group1,
-        swing_camera_bind_group,
+        &render_data.sphere_buffers,
+        collisions.ellipsoids.clone(),
+        &collisions.hashes,
         hidden_collisions,
     );
 
-    // Ellipsoids use the sphere geometry.
-    draw_shapes(
+    draw_instances(
         pass,
-        &render_data.sphere_buffers,
-        &render_data.collisions.ellipsoids,
-        &render_data.bind_group1,
-        swing_camera_bind_group,
+        &render_data.plane_buffers,
+        collisions.planes.clone(),
+        &collisions.hashes,
         hidden_collisions,
     );
 
-    draw_shapes_with_buffers(
+    draw_instances_with_buffers(
         pass,
-        &render_data.collisions.capsules,
-        &render_data.bind_group1,
-        swing_camera_bind_group,
+        &collisions.oval_buffers,
+        collisions.ovals.clone(),
+        &collisions.hashes,
         hidden_collisions,
     );
 
-    draw_shapes(
+    draw_instances_with_buffers(
         pass,
-        &render_data.plane_buffers,
-        &render_data.collisions.planes,
-        &render_data.bind_group1,
-        swing_camera_bind_group,
+        &collisions.capsule_buffers,
+        collisions.capsules.clone(),
+        &collisions.hashes,
         hidden_collisions,
     );
 }
 
-fn draw_shapes(
+fn draw_instances(
     pass: &mut wgpu::RenderPass<'_>,
     buffers: &IndexedMeshBuffers,
-    shapes: &[ShapeRenderData],
-    bind_group1: &crate::shader::swing::bind_groups::BindGroup1,
-    swing_camera_bind_group: &crate::shader::swing::bind_groups::BindGroup0,
+    instances: Range<u32>,
+    hashes: &[u64],
     hidden_collisions: &HashSet<u64>,
 ) {
+    if instances.is_empty() {
+        return;
+    }
+
     buffers.set(pass);
-    for shape in shapes
-        .iter()
-        .filter(|s| !hidden_collisions.contains(&s.hash))
-    {
-        draw_shape(pass, swing_camera_bind_group, bind_group1, shape, buffers);
+    for visible in visible_instances(instances, hashes, hidden_collisions) {
+        pass.draw_indexed(0..buffers.index_count, 0, visible);
     }
 }
 
-fn draw_shapes_with_buffers(
+fn draw_instances_with_buffers(
     pass: &mut wgpu::RenderPass<'_>,
-    shapes: &[(IndexedMeshBuffers, ShapeRenderData)],
-    bind_group1: &crate::shader::swing::bind_groups::BindGroup1,
-    swing_camera_bind_group: &crate::shader::swing::bind_groups::BindGroup0,
+    buffers: &[IndexedMeshBuffers],
+    instances: Range<u32>,
+    hashes: &[u64],
     hidden_collisions: &HashSet<u64>,
 ) {
-    for (buffers, shape) in shapes
-        .iter()
-        .filter(|s| !hidden_collisions.contains(&s.1.hash))
-    {
-        buffers.set(pass);
-        draw_shape(pass, swing_camera_bind_group, bind_group1, shape, buffers);
+    for (i, buffers) in instances.zip(buffers) {
+        if !hidden_collisions.contains(&hashes[i as usize]) {
+            buffers.set(pass);
+            pass.draw_indexed(0..buffers.index_count, 0, i..i + 1);
+        }
     }
 }
 
-fn draw_shape(
-    pass: &mut wgpu::RenderPass<'_>,
-    swing_camera_bind_group: &crate::shader::swing::bind_groups::BindGroup0,
-    bind_group1: &crate::shader::swing::bind_groups::BindGroup1,
-    shape: &ShapeRenderData,
-    buffers: &IndexedMeshBuffers,
-) {
-    crate::shader::swing::set_bind_groups(
-        pass,
-        swing_camera_bind_group,
-        bind_group1,
-        &shape.bind_group,
-    );
-    pass.draw_indexed(0..buffers.index_count, 0, 0..1);
+// Group consecutive visible instances to use as few draw calls as possible.
+fn visible_instances(
+    instances: Range<u32>,
+    hashes: &[u64],
+    hidden_collisions: &HashSet<u64>,
+) -> Vec<Range<u32>> {
+    let mut ranges: Vec<Range<u32>> = Vec::new();
+    for i in instances.filter(|i| !hidden_collisions.contains(&hashes[*i as usize])) {
+        match ranges.last_mut() {
+            Some(range) if range.end == i => range.end = i + 1,
+            _ => ranges.push(i..i + 1),
+        }
+    }
+    ranges
 }
 
 pub fn swing_pipeline(
@@ -551,3 +597,29 @@ pub fn swing_pipeline(
         cache: None,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn visible_instances_none_hidden() {
+        assert_eq!(
+            vec![2..5],
+            visible_instances(2..5, &[0, 1, 2, 3, 4], &HashSet::new())
+        );
+    }
+
+    #[test]
+    fn visible_instances_some_hidden() {
+        assert_eq!(
+            vec![0..1, 3..4],
+            visible_instances(0..4, &[0, 1, 2, 3], &[1, 2].into())
+        );
+    }
+
+    #[test]
+    fn visible_instances_all_hidden() {
+        assert!(visible_instances(0..2, &[5, 5], &[5].into()).is_empty());
+    }
+}
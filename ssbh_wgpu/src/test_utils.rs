@@ -0,0 +1,28 @@
+//! Fixtures shared by the unit tests in multiple modules.
+use glam::{Mat4, Vec3};
+use ssbh_data::skel_data::{BoneData, SkelData};
+
+use crate::CameraTransforms;
+
+/// A camera mapping `[-1,1]` on XY to the full screen with the camera looking down -Z.
+pub fn orthographic_camera(width: f32, height: f32) -> CameraTransforms {
+    let model_view_matrix = Mat4::from_translation(Vec3::new(0.0, 0.0, -10.0));
+    let projection_matrix = Mat4::orthographic_rh(-1.0, 1.0, -1.0, 1.0, 0.1, 100.0);
+    let mvp_matrix = projection_matrix * model_view_matrix;
+    CameraTransforms {
+        model_view_matrix,
+        projection_matrix,
+        mvp_matrix,
+        mvp_inv_matrix: mvp_matrix.inverse(),
+        camera_pos: glam::vec4(0.0, 0.0, 10.0, 1.0),
+        screen_dimensions: glam::vec4(width, height, 1.0, 0.0),
+    }
+}
+
+pub fn skel(bones: Vec<BoneData>) -> SkelData {
+    SkelData {
+        major_version: 1,
+        minor_version: 0,
+        bones,
+    }
+}
@@ -1,6 +1,6 @@
 use image::EncodableLayout;
 use ssbh_data::matl_data::{ParamId, SamplerData};
-use std::path::Path;
+use std::{path::Path, sync::Arc};
 use wgpu::{
     util::DeviceExt, Device, Queue, Sampler, SamplerDescriptor, Texture, TextureDescriptor,
     TextureDimension, TextureFormat, TextureUsages, TextureView, TextureViewDescriptor,
@@ -24,7 +24,7 @@ pub struct TextureSamplerView {
 
 pub fn load_texture(
     material_path: &str,
-    textures: &[(String, Texture, TextureViewDimension)],
+    textures: &[(String, Arc<Texture>, TextureViewDimension)],
     default_textures: &[(String, Texture, TextureViewDimension)],
     dimension: TextureViewDimension,
 ) -> Result<TextureView, LoadTextureError> {
@@ -32,13 +32,9 @@ pub fn load_texture(
     // This shouldn't require an actual file system for better portability.
     let (_, t, d) = textures
         .iter()
-        .chain(default_textures)
-        .find(|(p, _, _)| {
-            Path::new(&p)
-                .with_extension("")
-                .as_os_str()
-                .eq_ignore_ascii_case(material_path)
-        })
+        .map(|(p, t, d)| (p, t.as_ref(), d))
+        .chain(default_textures.iter().map(|(p, t, d)| (p, t, d)))
+        .find(|(p, _, _)| is_texture_path(p, material_path))
         .ok_or(LoadTextureError::PathNotFound)?;
 
     if *d == dimension {
@@ -61,6 +57,14 @@ pub fn create_sampler(device: &Device, param_id: ParamId, data: &SamplerData) ->
     })
 }
 
+/// The name of the default texture in [create_default_textures] applied by [load_default].
+pub fn default_texture_name(param_id: ParamId) -> &'static str {
+    match param_id {
+        ParamId::Texture2 | ParamId::Texture7 | ParamId::Texture8 => "#replace_cubemap",
+        _ => "/common/shader/sfxpbs/default_white",
+    }
+}
+
 pub fn load_default(
     param_id: ParamId,
     stage_cube: &Texture,
@@ -79,6 +83,24 @@ pub fn load_default(
     }
 }
 
+/// Returns `true` if the texture file `name` like `"def_mario_001_col.nutexb"`
+/// matches the matl texture path like `"def_mario_001_col"`.
+pub(crate) fn is_texture_path(name: &str, material_path: &str) -> bool {
+    Path::new(name)
+        .with_extension("")
+        .as_os_str()
+        .eq_ignore_ascii_case(material_path)
+}
+
+/// Returns `true` if the texture files `a` and `b` like `"def_mario_001_col.nutexb"`
+/// match the same matl texture path.
+pub(crate) fn is_same_texture(a: &str, b: &str) -> bool {
+    Path::new(a)
+        .with_extension("")
+        .as_os_str()
+        .eq_ignore_ascii_case(Path::new(b).with_extension(""))
+}
+
 pub fn load_default_spec_cube(device: &Device, queue: &Queue) -> (Texture, Sampler) {
     let size = wgpu::Extent3d {
         width: 64,
@@ -86,7 +108,9 @@ pub fn load_default_spec_cube(device: &Device, queue: &Queue) -> (Texture, Sampl
         depth_or_array_layers: 6,
     };
 
-    let texture = device.create_texture_with_data(
+    // Decode on the CPU if needed for devices without BC support.
+    let texture = nutexb_wgpu::create_texture_with_data(
+        device,
         queue,
         &TextureDescriptor {
             label: Some("Default Stage Specular Cube"),
@@ -98,7 +122,6 @@ pub fn load_default_spec_cube(device: &Device, queue: &Queue) -> (Texture, Sampl
             usage: TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING,
             view_formats: &[],
         },
-        wgpu::util::TextureDataOrder::LayerMajor,
         include_bytes!("resources/stage_cube_surface.bin"),
     );
 
@@ -311,7 +334,8 @@ pub fn default_diffuse2(
     };
 
     // This default texture isn't a solid color, so load the actual surface from a file.
-    let texture = device.create_texture_with_data(
+    let texture = nutexb_wgpu::create_texture_with_data(
+        device,
         queue,
         &wgpu::TextureDescriptor {
             label: Some("/common/shader/sfxpbs/default_diffuse2"),
@@ -323,7 +347,6 @@ pub fn default_diffuse2(
             usage: TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING,
             view_formats: &[],
         },
-        wgpu::util::TextureDataOrder::LayerMajor,
         include_bytes!("resources/default_diffuse2_surface.bin"),
     );
 
@@ -393,5 +416,38 @@ fn create_default_lut() -> Vec<u8> {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     // TODO: Add tests cases for handling of paths and special paths like "#replace_cubemap".
+
+    #[test]
+    fn default_texture_names() {
+        assert_eq!(
+            "/common/shader/sfxpbs/default_white",
+            default_texture_name(ParamId::Texture0)
+        );
+        assert_eq!("#replace_cubemap", default_texture_name(ParamId::Texture2));
+        assert_eq!("#replace_cubemap", default_texture_name(ParamId::Texture7));
+        assert_eq!("#replace_cubemap", default_texture_name(ParamId::Texture8));
+    }
+
+    #[test]
+    fn same_texture_ignores_case_and_extension() {
+        assert!(is_same_texture(
+            "def_mario_001_col.nutexb",
+            "def_mario_001_col.nutexb"
+        ));
+        assert!(is_same_texture(
+            "DEF_Mario_001_col.nutexb",
+            "def_mario_001_col"
+        ));
+        assert!(is_same_texture(
+            "def_mario_001_col",
+            "def_mario_001_col.NUTEXB"
+        ));
+        assert!(!is_same_texture(
+            "def_mario_001_col.nutexb",
+            "def_mario_001_nor.nutexb"
+        ));
+    }
 }
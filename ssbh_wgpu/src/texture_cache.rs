@@ -0,0 +1,260 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Weak},
+};
+
+use nutexb_wgpu::NutexbFile;
+
+/// Textures shared between [RenderModel](crate::RenderModel) with identical nutexb contents.
+///
+/// Fighter costume slots often duplicate the same textures, so sharing avoids uploading the same data multiple times.
+/// By default, the cache only keeps weak references, so textures are freed once no model uses them.
+/// Setting a memory budget also keeps recently used textures alive up to the budget.
+/// Textures still referenced by a model can't be freed, so eviction only releases the cache's reference.
+/// Evicted textures are uploaded again the next time a model requests them.
+pub(crate) struct TextureCache {
+    entries: HashMap<TextureKey, CachedTexture>,
+    budget: Option<u64>,
+    access_count: u64,
+}
+
+// The footer and a cryptographic hash of the data identify textures without storing a copy of the data.
+// Collisions for a 256-bit hash are not a practical concern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct TextureKey {
+    footer: [u32; 6],
+    data_hash: [u8; 32],
+}
+
+impl TextureKey {
+    pub fn new(nutexb: &NutexbFile) -> Self {
+        Self {
+            footer: footer_key(nutexb),
+            data_hash: *blake3::hash(&nutexb.data).as_bytes(),
+        }
+    }
+}
+
+struct CachedTexture {
+    // Models share ownership, so the texture stays alive until every reference is dropped.
+    texture: Weak<wgpu::Texture>,
+    // The cache's own reference that can be released for eviction.
+    // This is only set if there is a memory budget.
+    retained: Option<Arc<wgpu::Texture>>,
+    dimension: wgpu::TextureViewDimension,
+    size_in_bytes: u64,
+    last_used: u64,
+}
+
+impl CachedTexture {
+    fn is_alive(&self) -> bool {
+        self.texture.strong_count() > 0
+    }
+}
+
+impl TextureCache {
+    pub fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+            budget: None,
+            access_count: 0,
+        }
+    }
+
+    pub fn budget(&self) -> Option<u64> {
+        self.budget
+    }
+
+    pub fn set_budget(&mut self, budget: Option<u64>) {
+        self.budget = budget;
+        self.evict();
+    }
+
+    /// The estimated size in bytes of all textures still alive.
+    pub fn memory_usage(&self) -> u64 {
+        self.alive_entries().map(|e| e.size_in_bytes).sum()
+    }
+
+    /// The number of unique textures still alive.
+    pub fn len(&self) -> usize {
+        self.alive_entries().count()
+    }
+
+    /// Returns the texture for `key` if it is still alive.
+    pub fn get(
+        &mut self,
+        key: TextureKey,
+    ) -> Option<(Arc<wgpu::Texture>, wgpu::TextureViewDimension)> {
+        self.access_count += 1;
+        let access_count = self.access_count;
+        let retain = self.budget.is_some();
+
+        let entry = self.entries.get_mut(&key)?;
+        let texture = entry.texture.upgrade()?;
+        entry.last_used = access_count;
+        if retain {
+            entry.retained = Some(texture.clone());
+        }
+        let dimension = entry.dimension;
+
+        self.evict();
+        Some((texture, dimension))
+    }
+
+    /// Adds a `texture` created from the nutexb for `key` and returns the shared texture.
+    ///
+    /// If another thread already added a texture with the same contents,
+    /// the existing texture is returned instead.
+    pub fn insert(
+        &mut self,
+        key: TextureKey,
+        texture: wgpu::Texture,
+        dimension: wgpu::TextureViewDimension,
+    ) -> (Arc<wgpu::Texture>, wgpu::TextureViewDimension) {
+        if let Some(existing) = self.get(key) {
+            return existing;
+        }
+
+        let texture = Arc::new(texture);
+        self.entries.insert(
+            key,
+            CachedTexture {
+                texture: Arc::downgrade(&texture),
+                retained: self.budget.is_some().then(|| texture.clone()),
+                dimension,
+                size_in_bytes: texture_size_in_bytes(&texture),
+                last_used: self.access_count,
+            },
+        );
+        self.evict();
+
+        (texture, dimension)
+    }
+
+    /// Releases all references held by the cache.
+    /// Textures used by models remain alive and shared.
+    pub fn clear(&mut self) {
+        for entry in self.entries.values_mut() {
+            entry.retained = None;
+        }
+        self.remove_dead_entries();
+    }
+
+    fn alive_entries(&self) -> impl Iterator<Item = &CachedTexture> {
+        self.entries.values().filter(|e| e.is_alive())
+    }
+
+    fn remove_dead_entries(&mut self) {
+        self.entries.retain(|_, e| e.is_alive());
+    }
+
+    fn evict(&mut self) {
+        let Some(budget) = self.budget else {
+            // Only models keep textures alive without a budget.
+            self.clear();
+            return;
+        };
+
+        // Textures no longer used by any model can be removed entirely.
+        self.remove_dead_entries();
+
+        let mut retained: Vec<_> = self
+            .entries
+            .values_mut()
+            .filter(|e| e.retained.is_some())
+            .collect();
+        retained.sort_by_key(|e| e.last_used);
+
+        let mut usage: u64 = retained.iter().map(|e| e.size_in_bytes).sum();
+        for entry in retained {
+            if usage <= budget {
+                break;
+            }
+            entry.retained = None;
+            usage -= entry.size_in_bytes;
+        }
+
+        self.remove_dead_entries();
+    }
+}
+
+fn footer_key(nutexb: &NutexbFile) -> [u32; 6] {
+    [
+        nutexb.footer.width,
+        nutexb.footer.height,
+        nutexb.footer.depth,
+        nutexb.footer.image_format as u32,
+        nutexb.footer.mipmap_count,
+        nutexb.footer.layer_count,
+    ]
+}
+
+fn texture_size_in_bytes(texture: &wgpu::Texture) -> u64 {
+    texture_data_size(
+        texture.size(),
+        texture.dimension(),
+        texture.format(),
+        texture.mip_level_count(),
+    )
+}
+
+fn texture_data_size(
+    size: wgpu::Extent3d,
+    dimension: wgpu::TextureDimension,
+    format: wgpu::TextureFormat,
+    mip_level_count: u32,
+) -> u64 {
+    let (block_width, block_height) = format.block_dimensions();
+    let block_size = format.block_copy_size(None).unwrap_or(0) as u64;
+
+    (0..mip_level_count)
+        .map(|mip| {
+            let mip_size = size.mip_level_size(mip, dimension);
+            let width_blocks = mip_size.width.div_ceil(block_width) as u64;
+            let height_blocks = mip_size.height.div_ceil(block_height) as u64;
+            width_blocks * height_blocks * mip_size.depth_or_array_layers as u64 * block_size
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn texture_data_size_rgba8_mipmaps() {
+        let size = wgpu::Extent3d {
+            width: 4,
+            height: 4,
+            depth_or_array_layers: 1,
+        };
+        assert_eq!(
+            (16 + 4 + 1) * 4,
+            texture_data_size(
+                size,
+                wgpu::TextureDimension::D2,
+                wgpu::TextureFormat::Rgba8Unorm,
+                3
+            )
+        );
+    }
+
+    #[test]
+    fn texture_data_size_bc7_cube() {
+        // Array layers are not reduced for mipmaps.
+        let size = wgpu::Extent3d {
+            width: 8,
+            height: 8,
+            depth_or_array_layers: 6,
+        };
+        assert_eq!(
+            (4 + 1) * 16 * 6,
+            texture_data_size(
+                size,
+                wgpu::TextureDimension::D2,
+                wgpu::TextureFormat::Bc7RgbaUnorm,
+                2
+            )
+        );
+    }
+}
@@ -0,0 +1,604 @@
+use std::f32::consts::PI;
+
+use glam::{Mat4, Vec2, Vec3};
+
+use crate::{
+    bone_hit::{distance_to_segment, project},
+    CameraTransforms, DeviceBufferExt, QueueExt,
+};
+
+const SEGMENTS: usize = 16;
+const RING_SEGMENTS: usize = 64;
+const TUBE_SEGMENTS: usize = 4;
+// Rotation rings are the largest handles with a quad for each ring and tube segment.
+const MAX_VERTEX_COUNT: usize = 3 * RING_SEGMENTS * TUBE_SEGMENTS * 6;
+
+// Handle dimensions relative to the axis length.
+const SHAFT_RADIUS: f32 = 0.015;
+const HEAD_LENGTH: f32 = 0.2;
+const HEAD_RADIUS: f32 = 0.06;
+const CUBE_SIZE: f32 = 0.1;
+const PLANE_START: f32 = 0.25;
+const PLANE_END: f32 = 0.45;
+const TUBE_RADIUS: f32 = 0.015;
+
+// The maximum distance in logical pixels for hovering axes and rings.
+const HIT_DISTANCE: f32 = 8.0;
+
+const ACTIVE_COLOR: [f32; 4] = [1.0, 0.9, 0.1, 1.0];
+
+/// The type of transform edited with a [TransformGizmo].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GizmoMode {
+    /// Arrows for each axis and squares for each plane.
+    #[default]
+    Translate,
+    /// Rings around each axis.
+    Rotate,
+    /// Cubes for each axis and squares for each plane.
+    Scale,
+}
+
+/// A handle of a [TransformGizmo] in the gizmo's local space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GizmoAxis {
+    X,
+    Y,
+    Z,
+    XY,
+    XZ,
+    YZ,
+}
+
+impl GizmoAxis {
+    /// The local axes affected by this handle like `(1.0, 1.0, 0.0)` for [GizmoAxis::XY].
+    pub fn mask(&self) -> Vec3 {
+        match self {
+            GizmoAxis::X => Vec3::X,
+            GizmoAxis::Y => Vec3::Y,
+            GizmoAxis::Z => Vec3::Z,
+            GizmoAxis::XY => Vec3::X + Vec3::Y,
+            GizmoAxis::XZ => Vec3::X + Vec3::Z,
+            GizmoAxis::YZ => Vec3::Y + Vec3::Z,
+        }
+    }
+
+    fn color(&self) -> [f32; 4] {
+        // Planes use the color of their normal axis.
+        match self {
+            GizmoAxis::X | GizmoAxis::YZ => [0.9, 0.2, 0.2, 1.0],
+            GizmoAxis::Y | GizmoAxis::XZ => [0.3, 0.8, 0.2, 1.0],
+            GizmoAxis::Z | GizmoAxis::XY => [0.2, 0.4, 0.9, 1.0],
+        }
+    }
+
+    fn plane_axes(&self) -> Option<(Vec3, Vec3)> {
+        match self {
+            GizmoAxis::XY => Some((Vec3::X, Vec3::Y)),
+            GizmoAxis::XZ => Some((Vec3::X, Vec3::Z)),
+            GizmoAxis::YZ => Some((Vec3::Y, Vec3::Z)),
+            _ => None,
+        }
+    }
+}
+
+/// A translate, rotate, or scale manipulator drawn with
+/// [SsbhRenderer::render_transform_gizmo](crate::SsbhRenderer::render_transform_gizmo).
+///
+/// The gizmo only handles drawing and hit testing.
+/// Applications apply the changes from mouse input to the selected object or bone.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransformGizmo {
+    pub mode: GizmoMode,
+    /// The world transform for the gizmo's position and orientation.
+    /// Scale is ignored, so the gizmo appears the same size for all objects.
+    pub transform: Mat4,
+    /// The length of the axes on screen in logical pixels.
+    pub size: f32,
+    /// The handle under the cursor that is drawn highlighted.
+    pub hovered: Option<GizmoAxis>,
+    /// The handle being dragged that is drawn on top of the other handles.
+    pub active: Option<GizmoAxis>,
+}
+
+impl Default for TransformGizmo {
+    fn default() -> Self {
+        Self {
+            mode: GizmoMode::Translate,
+            transform: Mat4::IDENTITY,
+            size: 100.0,
+            hovered: None,
+            active: None,
+        }
+    }
+}
+
+impl TransformGizmo {
+    /// The world space length of the axes that appears as [TransformGizmo::size] pixels on screen.
+    pub fn world_size(&self, camera: &CameraTransforms) -> f32 {
+        let (_, _, translation) = self.transform.to_scale_rotation_translation();
+        let clip_w = (camera.mvp_matrix * translation.extend(1.0)).w.abs();
+
+        // Perspective and orthographic projections both scale view space y by the same factor.
+        // Convert from screen space [0,height] to clip space [-1,1].
+        let scale = camera.projection_matrix.y_axis.y.abs() * camera.screen_dimensions.y;
+        self.size * camera.screen_dimensions.z * 2.0 * clip_w / scale.max(f32::EPSILON)
+    }
+
+    /// The transform from the gizmo handles to world space with a constant size on screen.
+    pub fn world_transform(&self, camera: &CameraTransforms) -> Mat4 {
+        let (_, rotation, translation) = self.transform.to_scale_rotation_translation();
+        Mat4::from_scale_rotation_translation(
+            Vec3::splat(self.world_size(camera)),
+            rotation,
+            translation,
+        )
+    }
+
+    /// Finds the handle at `point` in physical pixels with the origin at the top left corner of the screen.
+    ///
+    /// Use the result to set [TransformGizmo::hovered] or [TransformGizmo::active] when starting a drag.
+    pub fn hit_test(&self, camera: &CameraTransforms, point: Vec2) -> Option<GizmoAxis> {
+        let transform = self.world_transform(camera);
+        let max_distance = HIT_DISTANCE * camera.screen_dimensions.z;
+        let project_local =
+            |p: Vec3| project(transform.transform_point3(p), camera).map(|(p, _)| p);
+
+        let closest = |distances: Vec<(GizmoAxis, f32)>| {
+            distances
+                .into_iter()
+                .filter(|(_, d)| *d <= max_distance)
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(axis, _)| axis)
+        };
+
+        match self.mode {
+            GizmoMode::Translate | GizmoMode::Scale => {
+                // Plane handles are small, so check them before the axes.
+                for axis in [GizmoAxis::XY, GizmoAxis::XZ, GizmoAxis::YZ] {
+                    let corners: Option<Vec<_>> =
+                        plane_corners(axis).into_iter().map(project_local).collect();
+                    if corners.is_some_and(|c| contains_convex(point, &c)) {
+                        return Some(axis);
+                    }
+                }
+
+                let distances = [GizmoAxis::X, GizmoAxis::Y, GizmoAxis::Z]
+                    .into_iter()
+                    .filter_map(|axis| {
+                        let start = project_local(Vec3::ZERO)?;
+                        let end = project_local(axis.mask())?;
+                        Some((axis, distance_to_segment(point, start, end)))
+                    })
+                    .collect();
+                closest(distances)
+            }
+            GizmoMode::Rotate => {
+                let distances = [GizmoAxis::X, GizmoAxis::Y, GizmoAxis::Z]
+                    .into_iter()
+                    .filter_map(|axis| {
+                        let points: Vec<_> = ring_points(axis)
+                            .into_iter()
+                            .map(project_local)
+                            .collect::<Option<_>>()?;
+                        let distance = points
+                            .windows(2)
+                            .map(|p| distance_to_segment(point, p[0], p[1]))
+                            .fold(f32::INFINITY, f32::min);
+                        Some((axis, distance))
+                    })
+                    .collect();
+                closest(distances)
+            }
+        }
+    }
+}
+
+pub struct TransformGizmoRenderData {
+    pipeline: wgpu::RenderPipeline,
+    camera_bind_group: crate::shader::transform_gizmo::bind_groups::BindGroup0,
+    gizmo_bind_group: crate::shader::transform_gizmo::bind_groups::BindGroup1,
+    gizmo_buffer: wgpu::Buffer,
+    vertex_buffer: wgpu::Buffer,
+    vertex_count: u32,
+    gizmo: Option<TransformGizmo>,
+}
+
+impl TransformGizmoRenderData {
+    pub fn new(
+        device: &wgpu::Device,
+        camera_buffer: &wgpu::Buffer,
+        surface_format: wgpu::TextureFormat,
+    ) -> Self {
+        let shader = crate::shader::transform_gizmo::create_shader_module(device);
+        let layout = crate::shader::transform_gizmo::create_pipeline_layout(device);
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Transform Gizmo Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[
+                    crate::shader::transform_gizmo::VertexInput::vertex_buffer_layout(
+                        wgpu::VertexStepMode::Vertex,
+                    ),
+                ],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(surface_format.into())],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            // Draw on top of everything in the final pass like the light gizmo.
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let camera_bind_group =
+            crate::shader::transform_gizmo::bind_groups::BindGroup0::from_bindings(
+                device,
+                crate::shader::transform_gizmo::bind_groups::BindGroupLayout0 {
+                    camera: camera_buffer.as_entire_buffer_binding(),
+                },
+            );
+
+        let gizmo_buffer = device.create_buffer_from_data(
+            "Transform Gizmo Buffer",
+            &[crate::shader::transform_gizmo::TransformGizmo {
+                transform: Mat4::IDENTITY,
+            }],
+            wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        );
+
+        let gizmo_bind_group =
+            crate::shader::transform_gizmo::bind_groups::BindGroup1::from_bindings(
+                device,
+                crate::shader::transform_gizmo::bind_groups::BindGroupLayout1 {
+                    gizmo: gizmo_buffer.as_entire_buffer_binding(),
+                },
+            );
+
+        // Allocate enough space for the largest mode to avoid recreating buffers.
+        let vertex_buffer = device.create_buffer_from_data(
+            "Transform Gizmo Vertex Buffer",
+            &[[0.0f32; 4]; MAX_VERTEX_COUNT * 2],
+            wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        );
+
+        Self {
+            pipeline,
+            camera_bind_group,
+            gizmo_bind_group,
+            gizmo_buffer,
+            vertex_buffer,
+            vertex_count: 0,
+            gizmo: None,
+        }
+    }
+
+    /// Updates the handles for `gizmo` or hides the gizmo if `None`.
+    pub fn update(
+        &mut self,
+        queue: &wgpu::Queue,
+        gizmo: Option<&TransformGizmo>,
+        camera: &CameraTransforms,
+    ) {
+        self.gizmo = gizmo.cloned();
+
+        let vertices = gizmo
+            .map(|g| gizmo_vertices(g.mode, g.hovered, g.active))
+            .unwrap_or_default();
+        self.vertex_count = (vertices.len() / 2) as u32;
+        if !vertices.is_empty() {
+            queue.write_data(&self.vertex_buffer, &vertices);
+        }
+
+        self.update_camera(queue, camera);
+    }
+
+    /// Updates the scale to keep a constant size on screen.
+    pub fn update_camera(&self, queue: &wgpu::Queue, camera: &CameraTransforms) {
+        if let Some(gizmo) = &self.gizmo {
+            queue.write_data(
+                &self.gizmo_buffer,
+                &[crate::shader::transform_gizmo::TransformGizmo {
+                    transform: gizmo.world_transform(camera),
+                }],
+            );
+        }
+    }
+
+    pub fn draw(&self, pass: &mut wgpu::RenderPass<'_>) {
+        if self.gizmo.is_none() || self.vertex_count == 0 {
+            return;
+        }
+
+        pass.set_pipeline(&self.pipeline);
+        crate::shader::transform_gizmo::set_bind_groups(
+            pass,
+            &self.camera_bind_group,
+            &self.gizmo_bind_group,
+        );
+        pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        pass.draw(0..self.vertex_count, 0..1);
+    }
+}
+
+fn handle_color(
+    axis: GizmoAxis,
+    hovered: Option<GizmoAxis>,
+    active: Option<GizmoAxis>,
+) -> [f32; 4] {
+    let color = glam::Vec4::from(axis.color());
+    if active == Some(axis) {
+        ACTIVE_COLOR
+    } else if active.is_some() {
+        // Dim the other handles while dragging.
+        (color * 0.5).with_w(1.0).to_array()
+    } else if hovered == Some(axis) {
+        color.lerp(glam::Vec4::ONE, 0.5).to_array()
+    } else {
+        color.to_array()
+    }
+}
+
+fn gizmo_vertices(
+    mode: GizmoMode,
+    hovered: Option<GizmoAxis>,
+    active: Option<GizmoAxis>,
+) -> Vec<[f32; 4]> {
+    let mut handles = match mode {
+        GizmoMode::Rotate => vec![GizmoAxis::X, GizmoAxis::Y, GizmoAxis::Z],
+        GizmoMode::Translate | GizmoMode::Scale => vec![
+            GizmoAxis::XY,
+            GizmoAxis::XZ,
+            GizmoAxis::YZ,
+            GizmoAxis::X,
+            GizmoAxis::Y,
+            GizmoAxis::Z,
+        ],
+    };
+    // There is no depth buffer, so draw highlighted handles last to appear on top.
+    handles.sort_by_key(|h| Some(*h) == active || Some(*h) == hovered);
+
+    let mut vertices = Vec::new();
+    for axis in handles {
+        let color = handle_color(axis, hovered, active);
+        match (mode, axis.plane_axes()) {
+            (GizmoMode::Rotate, _) => add_ring(&mut vertices, axis, color),
+            (_, Some(_)) => add_quad(&mut vertices, plane_corners(axis), color),
+            (GizmoMode::Translate, None) => {
+                let direction = axis.mask();
+                let head_start = direction * (1.0 - HEAD_LENGTH);
+                add_cylinder(&mut vertices, Vec3::ZERO, head_start, SHAFT_RADIUS, color);
+                add_cone(&mut vertices, head_start, direction, HEAD_RADIUS, color);
+            }
+            (GizmoMode::Scale, None) => {
+                let direction = axis.mask();
+                let cube_center = direction * (1.0 - CUBE_SIZE * 0.5);
+                add_cylinder(&mut vertices, Vec3::ZERO, cube_center, SHAFT_RADIUS, color);
+                add_cube(&mut vertices, cube_center, CUBE_SIZE * 0.5, color);
+            }
+        }
+    }
+    vertices
+}
+
+fn plane_corners(axis: GizmoAxis) -> [Vec3; 4] {
+    let (u, v) = axis.plane_axes().unwrap_or((Vec3::X, Vec3::Y));
+    [
+        u * PLANE_START + v * PLANE_START,
+        u * PLANE_END + v * PLANE_START,
+        u * PLANE_END + v * PLANE_END,
+        u * PLANE_START + v * PLANE_END,
+    ]
+}
+
+// A closed circle with unit radius perpendicular to the axis.
+fn ring_points(axis: GizmoAxis) -> Vec<Vec3> {
+    let (u, v) = ring_axes(axis);
+    (0..=RING_SEGMENTS)
+        .map(|i| {
+            let (s, c) = (2.0 * PI * i as f32 / RING_SEGMENTS as f32).sin_cos();
+            u * c + v * s
+        })
+        .collect()
+}
+
+fn ring_axes(axis: GizmoAxis) -> (Vec3, Vec3) {
+    match axis {
+        GizmoAxis::X => (Vec3::Y, Vec3::Z),
+        GizmoAxis::Y => (Vec3::Z, Vec3::X),
+        _ => (Vec3::X, Vec3::Y),
+    }
+}
+
+fn contains_convex(point: Vec2, corners: &[Vec2]) -> bool {
+    // The point is inside if it's on the same side of every edge.
+    let sides: Vec<_> = corners
+        .iter()
+        .zip(corners.iter().cycle().skip(1))
+        .map(|(a, b)| (*b - *a).perp_dot(point - *a))
+        .collect();
+    sides.iter().all(|s| *s >= 0.0) || sides.iter().all(|s| *s <= 0.0)
+}
+
+fn add_triangle(vertices: &mut Vec<[f32; 4]>, points: [Vec3; 3], color: [f32; 4]) {
+    // Pos0 Color0 Pos1 Color1 ...
+    for p in points {
+        vertices.push(p.extend(1.0).to_array());
+        vertices.push(color);
+    }
+}
+
+fn add_quad(vertices: &mut Vec<[f32; 4]>, [a, b, c, d]: [Vec3; 4], color: [f32; 4]) {
+    add_triangle(vertices, [a, b, c], color);
+    add_triangle(vertices, [c, d, a], color);
+}
+
+fn add_cylinder(
+    vertices: &mut Vec<[f32; 4]>,
+    start: Vec3,
+    end: Vec3,
+    radius: f32,
+    color: [f32; 4],
+) {
+    let (u, v) = (end - start).normalize().any_orthonormal_pair();
+    let offset = |i: usize| {
+        let (s, c) = (2.0 * PI * i as f32 / SEGMENTS as f32).sin_cos();
+        (u * c + v * s) * radius
+    };
+    for i in 0..SEGMENTS {
+        let (o0, o1) = (offset(i), offset(i + 1));
+        add_quad(
+            vertices,
+            [start + o0, start + o1, end + o1, end + o0],
+            color,
+        );
+    }
+}
+
+fn add_cone(vertices: &mut Vec<[f32; 4]>, base: Vec3, tip: Vec3, radius: f32, color: [f32; 4]) {
+    let (u, v) = (tip - base).normalize().any_orthonormal_pair();
+    let offset = |i: usize| {
+        let (s, c) = (2.0 * PI * i as f32 / SEGMENTS as f32).sin_cos();
+        (u * c + v * s) * radius
+    };
+    for i in 0..SEGMENTS {
+        let (o0, o1) = (offset(i), offset(i + 1));
+        add_triangle(vertices, [base + o0, base + o1, tip], color);
+        add_triangle(vertices, [base + o1, base + o0, base], color);
+    }
+}
+
+fn add_cube(vertices: &mut Vec<[f32; 4]>, center: Vec3, half_size: f32, color: [f32; 4]) {
+    for normal in [Vec3::X, Vec3::Y, Vec3::Z, -Vec3::X, -Vec3::Y, -Vec3::Z] {
+        let (u, v) = normal.any_orthonormal_pair();
+        let face = center + normal * half_size;
+        let (u, v) = (u * half_size, v * half_size);
+        add_quad(
+            vertices,
+            [face - u - v, face + u - v, face + u + v, face - u + v],
+            color,
+        );
+    }
+}
+
+fn add_ring(vertices: &mut Vec<[f32; 4]>, axis: GizmoAxis, color: [f32; 4]) {
+    // A thin torus with unit radius perpendicular to the axis.
+    let normal = axis.mask();
+    let circle = ring_points(axis);
+    let point = |i: usize, j: usize| {
+        let (s, c) = (2.0 * PI * j as f32 / TUBE_SEGMENTS as f32).sin_cos();
+        circle[i] + (circle[i] * c + normal * s) * TUBE_RADIUS
+    };
+    for i in 0..RING_SEGMENTS {
+        for j in 0..TUBE_SEGMENTS {
+            add_quad(
+                vertices,
+                [
+                    point(i, j),
+                    point(i + 1, j),
+                    point(i + 1, j + 1),
+                    point(i, j + 1),
+                ],
+                color,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_utils::orthographic_camera;
+
+    #[test]
+    fn world_size_orthographic() {
+        let gizmo = TransformGizmo {
+            size: 25.0,
+            ..Default::default()
+        };
+        // The screen height of 100 pixels covers 2.0 world units.
+        assert_eq!(0.5, gizmo.world_size(&orthographic_camera(200.0, 100.0)));
+    }
+
+    #[test]
+    fn hit_test_translate() {
+        let camera = orthographic_camera(200.0, 100.0);
+        let gizmo = TransformGizmo {
+            size: 50.0,
+            ..Default::default()
+        };
+        // The origin is at the center of the screen and the axes have a length of 1.0.
+        assert_eq!(
+            Some(GizmoAxis::X),
+            gizmo.hit_test(&camera, Vec2::new(150.0, 52.0))
+        );
+        assert_eq!(
+            Some(GizmoAxis::Y),
+            gizmo.hit_test(&camera, Vec2::new(101.0, 10.0))
+        );
+        assert_eq!(
+            Some(GizmoAxis::XY),
+            gizmo.hit_test(&camera, Vec2::new(135.0, 32.0))
+        );
+        assert_eq!(None, gizmo.hit_test(&camera, Vec2::new(10.0, 90.0)));
+    }
+
+    #[test]
+    fn hit_test_rotate() {
+        let camera = orthographic_camera(200.0, 100.0);
+        let gizmo = TransformGizmo {
+            mode: GizmoMode::Rotate,
+            size: 50.0,
+            ..Default::default()
+        };
+        // The X and Y rings appear as lines through the center when viewed along Z.
+        assert_eq!(
+            Some(GizmoAxis::Z),
+            gizmo.hit_test(&camera, Vec2::new(170.7, 14.6))
+        );
+        assert_eq!(None, gizmo.hit_test(&camera, Vec2::new(140.0, 30.0)));
+    }
+
+    #[test]
+    fn gizmo_vertices_fit_in_buffer() {
+        for mode in [GizmoMode::Translate, GizmoMode::Rotate, GizmoMode::Scale] {
+            let vertices = gizmo_vertices(mode, None, None);
+            assert!(!vertices.is_empty());
+            assert!(vertices.len() <= MAX_VERTEX_COUNT * 2);
+        }
+    }
+
+    #[test]
+    fn gizmo_vertices_active_last() {
+        let vertices = gizmo_vertices(GizmoMode::Translate, None, Some(GizmoAxis::X));
+        assert_eq!(ACTIVE_COLOR, vertices[vertices.len() - 1]);
+        // Other handles are dimmed while dragging.
+        assert_eq!([0.1, 0.2, 0.45, 1.0], vertices[1]);
+    }
+
+    #[test]
+    fn contains_convex_winding() {
+        let corners = [
+            Vec2::ZERO,
+            Vec2::new(1.0, 0.0),
+            Vec2::ONE,
+            Vec2::new(0.0, 1.0),
+        ];
+        assert!(contains_convex(Vec2::splat(0.5), &corners));
+        assert!(contains_convex(
+            Vec2::splat(0.5),
+            &[corners[3], corners[2], corners[1], corners[0]]
+        ));
+        assert!(!contains_convex(Vec2::new(1.5, 0.5), &corners));
+    }
+}
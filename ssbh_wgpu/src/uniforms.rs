@@ -1,13 +1,14 @@
-use std::str::FromStr;
+use std::{str::FromStr, sync::Arc};
 
 use crate::{
     model::SamplerCache,
     shader::model::PerMaterial,
     split_param,
-    texture::{create_sampler, load_default, load_texture, LoadTextureError},
-    DeviceBufferExt, ShaderDatabase,
+    texture::{create_sampler, default_texture_name, load_default, load_texture, LoadTextureError},
+    DeviceBufferExt, ShaderDatabase, TextureFallback,
 };
 use log::warn;
+use serde_json::json;
 use ssbh_data::matl_data::*;
 use wgpu::SamplerDescriptor;
 
@@ -15,11 +16,14 @@ use wgpu::SamplerDescriptor;
 pub fn material_uniforms_bind_group(
     material: &ssbh_data::matl_data::MatlEntryData,
     device: &wgpu::Device,
-    textures: &[(String, wgpu::Texture, wgpu::TextureViewDimension)],
+    textures: &[(String, Arc<wgpu::Texture>, wgpu::TextureViewDimension)],
     default_textures: &[(String, wgpu::Texture, wgpu::TextureViewDimension)],
     uniforms_buffer: &wgpu::Buffer, // TODO: Just return this?
     sampler_by_data: &mut SamplerCache,
-) -> crate::shader::model::bind_groups::BindGroup2 {
+) -> (
+    crate::shader::model::bind_groups::BindGroup2,
+    Vec<TextureFallback>,
+) {
     // TODO: Do all 2D textures default to white if the path isn't correct?
     let default_white = &default_textures
         .iter()
@@ -33,7 +37,9 @@ pub fn material_uniforms_bind_group(
         .unwrap()
         .1;
 
-    let load_texture = |texture_id, dim| {
+    let mut fallbacks = Vec::new();
+
+    let mut load_texture = |texture_id, dim| {
         // TODO: Add proper path and parameter handling.
         // TODO: Find a way to test texture path loading.
         // This should also handle paths like "../texture.nutexb" and "/render/shader/bin/texture.nutexb".
@@ -43,15 +49,24 @@ pub fn material_uniforms_bind_group(
                     .map(|t| t.data.as_str())
             .and_then(|material_path| {
                 load_texture(material_path, textures, default_textures, dim).map_err(|e| {
-                    match e {
+                    let message = match e {
                         LoadTextureError::PathNotFound => {
-                            warn!("Missing texture {:?} assigned to {}. Applying default texture.", material_path, texture_id)
+                            warn!("Missing texture {:?} assigned to {}. Applying default texture.", material_path, texture_id);
+                            "Texture not found".to_string()
                         },
                         LoadTextureError::DimensionMismatch { expected, actual } => {
                             warn!("Texture {:?} assigned to {} has invalid dimensions. Expected {:?} but found {:?}.", 
-                                material_path, texture_id, expected, actual)
+                                material_path, texture_id, expected, actual);
+                            format!("Expected {expected:?} texture but found {actual:?}")
                         },
-                    }
+                    };
+                    fallbacks.push(TextureFallback {
+                        material_label: material.material_label.clone(),
+                        param_id: texture_id,
+                        path: material_path.to_string(),
+                        fallback: default_texture_name(texture_id).to_string(),
+                        message,
+                    });
                 }
                 ).ok()
             }).unwrap_or_else(|| load_default(texture_id, default_cube, default_white))
@@ -72,7 +87,7 @@ pub fn material_uniforms_bind_group(
     };
 
     // TODO: Default texture for other cube maps?
-    crate::shader::model::bind_groups::BindGroup2::from_bindings(
+    let bind_group = crate::shader::model::bind_groups::BindGroup2::from_bindings(
         device,
         crate::shader::model::bind_groups::BindGroupLayout2 {
             texture0: &load_texture(ParamId::Texture0, wgpu::TextureViewDimension::D2),
@@ -107,7 +122,9 @@ pub fn material_uniforms_bind_group(
             sampler14: load_sampler(ParamId::Sampler14),
             per_material: uniforms_buffer.as_entire_buffer_binding(),
         },
-    )
+    );
+
+    (bind_group, fallbacks)
 }
 
 pub fn default_material_uniforms_bind_group(
@@ -187,15 +204,10 @@ fn update_sampler_cache(
     }
 }
 
-pub fn uniforms_buffer(
-    material: &MatlEntryData,
-    device: &wgpu::Device,
-    database: &ShaderDatabase,
-) -> wgpu::Buffer {
-    let uniforms = per_material(material, database);
+pub fn uniforms_buffer(uniforms: &PerMaterial, device: &wgpu::Device) -> wgpu::Buffer {
     device.create_buffer_from_data(
         "Material Uniforms Buffer",
-        &[uniforms],
+        &[*uniforms],
         wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
     )
 }
@@ -242,8 +254,10 @@ pub fn per_material(material: &MatlEntryData, database: &ShaderDatabase) -> PerM
         for param_name in &program.material_parameters {
             // TODO: This is redundant to split twice.
             let (param, _) = split_param(param_name);
-            // It's safe to assume the database has valid parameters.
-            let id = ParamId::from_str(param).unwrap();
+            // Skip invalid parameters instead of panicking for custom databases.
+            let Ok(id) = ParamId::from_str(param) else {
+                continue;
+            };
             if let Some(i) = texture_index(id) {
                 has_texture[i] = program.accessed_channels(param_name).map(u32::from).into();
             } else if let Some(i) = boolean_index(id) {
@@ -320,6 +334,184 @@ pub fn per_material(material: &MatlEntryData, database: &ShaderDatabase) -> PerM
     }
 }
 
+/// The effective material parameter values used for rendering after applying defaults
+/// and the required parameters and settings from the [ShaderDatabase].
+///
+/// Arrays are indexed by parameter number like `custom_vector[8]` for CustomVector8.
+/// This is useful for comparing the rendered values with the original matl values.
+/// Create this with [material_uniforms] and convert to JSON with [MaterialUniforms::to_json].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaterialUniforms {
+    pub custom_vector: [[f32; 4]; 64],
+    pub custom_float: [f32; 20],
+    pub custom_boolean: [bool; 20],
+    /// The RGBA channels of each vector accessed by the shader.
+    pub has_vector: [[bool; 4]; 64],
+    pub has_float: [bool; 20],
+    pub has_boolean: [bool; 20],
+    /// The RGBA channels of each texture accessed by the shader.
+    pub has_texture: [[bool; 4]; 19],
+    /// The colorSet1 to colorSet7 attributes used by the shader.
+    pub has_color_set: [bool; 7],
+    pub discard: bool,
+    pub premultiplied: bool,
+    pub anisotropic_rotation: bool,
+    pub lighting: bool,
+    pub sh: bool,
+    pub receives_shadow: bool,
+    pub shader_complexity: f32,
+}
+
+/// A material parameter value for [RenderModel::update_material_values](crate::RenderModel::update_material_values).
+///
+/// Parameters with a [ParamId] that doesn't correspond to the type of value are ignored.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum MaterialValue {
+    /// A value for a parameter like [ParamId::CustomVector8].
+    Vector(ParamId, [f32; 4]),
+    /// A value for a parameter like [ParamId::CustomFloat8].
+    Float(ParamId, f32),
+    /// A value for a parameter like [ParamId::CustomBoolean1].
+    Boolean(ParamId, bool),
+}
+
+impl MaterialValue {
+    /// The parameter that this value replaces.
+    pub fn param_id(&self) -> ParamId {
+        match self {
+            MaterialValue::Vector(id, _)
+            | MaterialValue::Float(id, _)
+            | MaterialValue::Boolean(id, _) => *id,
+        }
+    }
+
+    pub(crate) fn apply(&self, uniforms: &mut PerMaterial) {
+        match *self {
+            MaterialValue::Vector(id, value) => {
+                if let Some(i) = vector_index(id) {
+                    uniforms.custom_vector[i] = value.into();
+                }
+            }
+            MaterialValue::Float(id, value) => {
+                if let Some(i) = float_index(id) {
+                    uniforms.custom_float[i][0] = value;
+                }
+            }
+            MaterialValue::Boolean(id, value) => {
+                if let Some(i) = boolean_index(id) {
+                    uniforms.custom_boolean[i][0] = value as u32;
+                }
+            }
+        }
+    }
+}
+
+/// Calculates the values written to the GPU uniform buffer for `material`.
+///
+/// Material animations are not applied.
+/// Use [animate_materials](crate::animation::animate_materials) to get the animated values for a frame.
+pub fn material_uniforms(material: &MatlEntryData, database: &ShaderDatabase) -> MaterialUniforms {
+    MaterialUniforms::from(&per_material(material, database))
+}
+
+impl From<&PerMaterial> for MaterialUniforms {
+    fn from(u: &PerMaterial) -> Self {
+        let channels = |v: glam::UVec4| v.to_array().map(|c| c != 0);
+
+        Self {
+            custom_vector: u.custom_vector.map(|v| v.to_array()),
+            custom_float: u.custom_float.map(|v| v.x),
+            custom_boolean: u.custom_boolean.map(|v| v.x != 0),
+            has_vector: u.has_vector.map(channels),
+            has_float: u.has_float.map(|v| v.x != 0),
+            has_boolean: u.has_boolean.map(|v| v.x != 0),
+            has_texture: u.has_texture.map(channels),
+            has_color_set: [
+                u.has_color_set1234.x != 0,
+                u.has_color_set1234.y != 0,
+                u.has_color_set1234.z != 0,
+                u.has_color_set1234.w != 0,
+                u.has_color_set567.x != 0,
+                u.has_color_set567.y != 0,
+                u.has_color_set567.z != 0,
+            ],
+            discard: u.shader_settings.x != 0,
+            premultiplied: u.shader_settings.y != 0,
+            anisotropic_rotation: u.shader_settings.z != 0,
+            lighting: u.lighting_settings.x != 0,
+            sh: u.lighting_settings.y != 0,
+            receives_shadow: u.lighting_settings.z != 0,
+            shader_complexity: u.shader_complexity.x,
+        }
+    }
+}
+
+impl MaterialUniforms {
+    /// Converts the values to JSON with parameters named like in the matl such as `"CustomVector8"`.
+    ///
+    /// Only parameters used by the shader or with non zero values are included to make the output easier to compare.
+    pub fn to_json(&self) -> serde_json::Value {
+        let vectors: serde_json::Map<_, _> = self
+            .custom_vector
+            .iter()
+            .zip(&self.has_vector)
+            .enumerate()
+            .filter(|(_, (value, used))| used.contains(&true) || value.iter().any(|v| *v != 0.0))
+            .map(|(i, (value, used))| {
+                let param = json!({ "value": value, "used_channels": used });
+                (format!("CustomVector{i}"), param)
+            })
+            .collect();
+
+        let floats: serde_json::Map<_, _> = self
+            .custom_float
+            .iter()
+            .zip(&self.has_float)
+            .enumerate()
+            .filter(|(_, (value, used))| **used || **value != 0.0)
+            .map(|(i, (value, used))| {
+                let param = json!({ "value": value, "used": used });
+                (format!("CustomFloat{i}"), param)
+            })
+            .collect();
+
+        let booleans: serde_json::Map<_, _> = self
+            .custom_boolean
+            .iter()
+            .zip(&self.has_boolean)
+            .enumerate()
+            .filter(|(_, (value, used))| **used || **value)
+            .map(|(i, (value, used))| {
+                let param = json!({ "value": value, "used": used });
+                (format!("CustomBoolean{i}"), param)
+            })
+            .collect();
+
+        let textures: serde_json::Map<_, _> = self
+            .has_texture
+            .iter()
+            .enumerate()
+            .filter(|(_, used)| used.contains(&true))
+            .map(|(i, used)| (format!("Texture{i}"), json!({ "used_channels": used })))
+            .collect();
+
+        json!({
+            "vectors": vectors,
+            "floats": floats,
+            "booleans": booleans,
+            "textures": textures,
+            "has_color_set": self.has_color_set,
+            "discard": self.discard,
+            "premultiplied": self.premultiplied,
+            "anisotropic_rotation": self.anisotropic_rotation,
+            "lighting": self.lighting,
+            "sh": self.sh,
+            "receives_shadow": self.receives_shadow,
+            "shader_complexity": self.shader_complexity,
+        })
+    }
+}
+
 pub const DEFAULT_PER_MATERIAL: PerMaterial =
     // Missing values are always set to zero.
     PerMaterial {
@@ -710,4 +902,73 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn material_uniforms_json() {
+        let uniforms = material_uniforms(
+            &MatlEntryData {
+                material_label: String::new(),
+                shader_label: "SFX_PBS_010002000800824f_opaque".to_owned(),
+                blend_states: Vec::new(),
+                floats: vec![FloatParam {
+                    param_id: ParamId::CustomFloat3,
+                    data: 0.5,
+                }],
+                booleans: Vec::new(),
+                vectors: Vec::new(),
+                rasterizer_states: Vec::new(),
+                samplers: Vec::new(),
+                textures: Vec::new(),
+                uv_transforms: Vec::new(),
+            },
+            &ShaderDatabase::from_iter([(
+                "SFX_PBS_010002000800824f".to_owned(),
+                ShaderProgram {
+                    discard: true,
+                    material_parameters: vec![
+                        "Texture0.xyz".to_owned(),
+                        "CustomVector8.x".to_owned(),
+                    ],
+                    ..Default::default()
+                },
+            )]),
+        );
+        assert_eq!(0.5, uniforms.custom_float[3]);
+        assert_eq!([true, false, false, false], uniforms.has_vector[8]);
+        assert!(uniforms.discard);
+
+        // Unused parameters with default values are skipped.
+        let json = uniforms.to_json();
+        assert_eq!(
+            json!({
+                "CustomVector8": { "value": [0.0, 0.0, 0.0, 0.0], "used_channels": [true, false, false, false] }
+            }),
+            json["vectors"]
+        );
+        assert_eq!(
+            json!({ "CustomFloat3": { "value": 0.5, "used": false } }),
+            json["floats"]
+        );
+        assert_eq!(json!({}), json["booleans"]);
+        assert_eq!(
+            json!({ "Texture0": { "used_channels": [true, true, true, false] } }),
+            json["textures"]
+        );
+        assert_eq!(json!(true), json["discard"]);
+    }
+
+    #[test]
+    fn apply_material_values() {
+        let mut uniforms = DEFAULT_PER_MATERIAL;
+        MaterialValue::Vector(ParamId::CustomVector8, [1.0, 2.0, 3.0, 4.0]).apply(&mut uniforms);
+        MaterialValue::Float(ParamId::CustomFloat19, 0.5).apply(&mut uniforms);
+        MaterialValue::Boolean(ParamId::CustomBoolean1, true).apply(&mut uniforms);
+        // Mismatched parameter types are ignored.
+        MaterialValue::Float(ParamId::CustomVector0, 2.0).apply(&mut uniforms);
+
+        assert_eq!(glam::vec4(1.0, 2.0, 3.0, 4.0), uniforms.custom_vector[8]);
+        assert_eq!(glam::Vec4::ZERO, uniforms.custom_vector[0]);
+        assert_eq!(0.5, uniforms.custom_float[19].x);
+        assert_eq!(1, uniforms.custom_boolean[1].x);
+    }
 }
@@ -10,6 +10,7 @@ use ssbh_data::{
     mesh_data::{error::Error, MeshObjectData},
     skel_data::SkelData,
 };
+use std::sync::Arc;
 use wgpu::Device;
 
 // TODO: Create a function and tests that groups attributes into two buffers
@@ -94,8 +95,23 @@ macro_rules! set_uv_attribute {
 macro_rules! set_color_attribute {
     ($v:ident, $data:expr, $field:ident, $dst: literal) => {
         match $data {
-            ssbh_data::mesh_data::VectorData::Vector2(_) => todo!(),
-            ssbh_data::mesh_data::VectorData::Vector3(_) => todo!(),
+            // Color sets should always have 4 components.
+            // Pad other lengths instead of panicking for invalid meshes.
+            ssbh_data::mesh_data::VectorData::Vector2(values) => {
+                for (i, value) in values.iter().enumerate() {
+                    $v[i].$field[$dst] = value[0];
+                    $v[i].$field[$dst + 1] = value[1];
+                    $v[i].$field[$dst + 3] = 1.0;
+                }
+            }
+            ssbh_data::mesh_data::VectorData::Vector3(values) => {
+                for (i, value) in values.iter().enumerate() {
+                    $v[i].$field[$dst] = value[0];
+                    $v[i].$field[$dst + 1] = value[1];
+                    $v[i].$field[$dst + 2] = value[2];
+                    $v[i].$field[$dst + 3] = 1.0;
+                }
+            }
             ssbh_data::mesh_data::VectorData::Vector4(values) => {
                 for (i, value) in values.iter().enumerate() {
                     $v[i].$field[$dst] = value[0];
@@ -158,6 +174,22 @@ pub fn buffer1(mesh_data: &MeshObjectData) -> Result<Vec<VertexInput1>, Error> {
     Ok(vertices)
 }
 
+/// The byte offset of the colorSet attribute with `set_index` like `1` for `"colorSet1"`
+/// within each [VertexInput1] vertex.
+pub fn color_set_offset(set_index: usize) -> Option<u64> {
+    let offset = match set_index {
+        1 => std::mem::offset_of!(VertexInput1, color_set1),
+        2 => std::mem::offset_of!(VertexInput1, color_set2_combined),
+        3 => std::mem::offset_of!(VertexInput1, color_set3),
+        4 => std::mem::offset_of!(VertexInput1, color_set4),
+        5 => std::mem::offset_of!(VertexInput1, color_set5),
+        6 => std::mem::offset_of!(VertexInput1, color_set6),
+        7 => std::mem::offset_of!(VertexInput1, color_set7),
+        _ => return None,
+    };
+    Some(offset as u64)
+}
+
 impl VertexWeight {
     fn add_weight(&mut self, index: i32, weight: f32) -> bool {
         // Assume unitialized indices have an index of -1.
@@ -231,8 +263,10 @@ pub fn skin_weights(
 pub struct CombinedMeshBuffers {
     pub vertex_buffer0_source: wgpu::Buffer,
     pub vertex_buffer0: wgpu::Buffer,
-    pub vertex_buffer1: wgpu::Buffer,
+    // Meshes write to their range of the buffer when editing attributes.
+    pub vertex_buffer1: Arc<wgpu::Buffer>,
     pub skinning_buffer: wgpu::Buffer,
+    pub adjacency_buffer: wgpu::Buffer,
     pub index_buffer: wgpu::Buffer,
 }
 
@@ -242,6 +276,7 @@ pub fn combined_mesh_buffers(
     buffer0: &[u8],
     buffer1: &[u8],
     skin_weights: &[u8],
+    adjacency: &[u8],
     vertex_indices: &[u32],
 ) -> CombinedMeshBuffers {
     // TODO: Clean this up.
@@ -256,12 +291,19 @@ pub fn combined_mesh_buffers(
     let vertex_buffer0 = device.create_buffer(&wgpu::BufferDescriptor {
         label: Some("Vertex Storage Buffer 0"),
         size: buffer0.len() as u64,
-        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::STORAGE,
+        // Support reading the skinned vertices back to the CPU.
+        usage: wgpu::BufferUsages::VERTEX
+            | wgpu::BufferUsages::STORAGE
+            | wgpu::BufferUsages::COPY_SRC,
         mapped_at_creation: false,
     });
 
-    let vertex_buffer1 =
-        device.create_buffer_from_bytes("Vertex Buffer 1", buffer1, wgpu::BufferUsages::VERTEX);
+    // Support editing attributes like vertex colors without recreating the buffer.
+    let vertex_buffer1 = device.create_buffer_from_bytes(
+        "Vertex Buffer 1",
+        buffer1,
+        wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+    );
 
     let skinning_buffer = device.create_buffer_from_bytes(
         "Skinning Buffer",
@@ -269,13 +311,17 @@ pub fn combined_mesh_buffers(
         wgpu::BufferUsages::STORAGE,
     );
 
+    let adjacency_buffer =
+        device.create_buffer_from_bytes("Adjacency Buffer", adjacency, wgpu::BufferUsages::STORAGE);
+
     let index_buffer = device.create_index_buffer("Index Buffer", vertex_indices);
 
     CombinedMeshBuffers {
         vertex_buffer0_source,
         vertex_buffer0,
-        vertex_buffer1,
+        vertex_buffer1: Arc::new(vertex_buffer1),
         skinning_buffer,
+        adjacency_buffer,
         index_buffer,
     }
 }
@@ -379,6 +425,36 @@ mod tests {
         assert!(buffer1(&MeshObjectData::default()).unwrap().is_empty());
     }
 
+    #[test]
+    fn buffer1_color_sets_padding() {
+        let vertices = buffer1(&MeshObjectData {
+            color_sets: vec![
+                AttributeData {
+                    name: "colorSet1".to_string(),
+                    data: ssbh_data::mesh_data::VectorData::Vector2(vec![[0.1, 0.2]]),
+                },
+                AttributeData {
+                    name: "colorSet3".to_string(),
+                    data: ssbh_data::mesh_data::VectorData::Vector3(vec![[0.1, 0.2, 0.3]]),
+                },
+            ],
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert_eq!(glam::vec4(0.1, 0.2, 0.0, 1.0), vertices[0].color_set1);
+        assert_eq!(glam::vec4(0.1, 0.2, 0.3, 1.0), vertices[0].color_set3);
+    }
+
+    #[test]
+    fn color_set_offsets() {
+        assert_eq!(None, color_set_offset(0));
+        assert_eq!(Some(48), color_set_offset(1));
+        assert_eq!(Some(64), color_set_offset(2));
+        assert_eq!(Some(144), color_set_offset(7));
+        assert_eq!(None, color_set_offset(8));
+    }
+
     #[test]
     fn add_vertex_weights() {
         let mut weight = VertexWeight::default();
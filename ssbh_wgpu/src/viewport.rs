@@ -1,4 +1,6 @@
-use glam::Vec4Swizzles;
+use glam::{Mat4, Vec3, Vec4Swizzles};
+
+use crate::{scene_bounds, BoundingSphere, CameraTransforms, RenderModel};
 
 // TODO: Document what the input and output value ranges should be.
 // TODO: Add tests.
@@ -12,3 +14,107 @@ pub fn world_to_screen(point: glam::Vec3, mvp: glam::Mat4, width: u32, height: u
     let position_y_screen = height as f32 * (1.0 - (position_clip.y * 0.5 + 0.5));
     (position_x_screen, position_y_screen)
 }
+
+/// Calculates a camera looking down the negative Z-axis that fits the visible meshes in the visible `render_models`.
+///
+/// The bounds account for the current pose from [RenderModel::apply_anims].
+/// The clip distances are fit to the bounds to maximize depth precision,
+/// so the camera should be recalculated after moving or loading models.
+/// Returns a camera looking at the origin if there are no visible meshes.
+///
+/// The `fov_y` is in radians, and `aspect` is the viewport width divided by height.
+/// The `screen_dimensions` use a height of 1.0 and should be updated to match the viewport size.
+pub fn frame_models(render_models: &[RenderModel], fov_y: f32, aspect: f32) -> CameraTransforms {
+    let bounds = scene_bounds(render_models, true)
+        .map(|b| b.bounding_sphere())
+        .unwrap_or(BoundingSphere {
+            center: Vec3::ZERO,
+            radius: 1.0,
+        });
+    frame_sphere(&bounds, fov_y, aspect)
+}
+
+fn frame_sphere(bounds: &BoundingSphere, fov_y: f32, aspect: f32) -> CameraTransforms {
+    // Fit the sphere to the smaller of the horizontal and vertical field of view.
+    let fov_x = 2.0 * ((fov_y / 2.0).tan() * aspect).atan();
+    let fov = fov_y.min(fov_x);
+    // Empty or single point meshes still need a valid projection.
+    let radius = bounds.radius.max(0.001);
+    let distance = radius / (fov / 2.0).sin();
+
+    let camera_pos = bounds.center + Vec3::Z * distance;
+    let model_view_matrix = Mat4::from_translation(-camera_pos);
+
+    let near_clip = (distance - radius).max(distance * 0.001);
+    let far_clip = distance + radius;
+    let projection_matrix = Mat4::perspective_rh(fov_y, aspect, near_clip, far_clip);
+
+    let mvp_matrix = projection_matrix * model_view_matrix;
+
+    CameraTransforms {
+        model_view_matrix,
+        projection_matrix,
+        mvp_matrix,
+        mvp_inv_matrix: mvp_matrix.inverse(),
+        camera_pos: camera_pos.extend(1.0),
+        screen_dimensions: glam::vec4(aspect, 1.0, 1.0, 0.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::assert_vector_relative_eq;
+
+    #[test]
+    fn frame_sphere_fits_view() {
+        let bounds = BoundingSphere {
+            center: Vec3::new(0.0, 10.0, 0.0),
+            radius: 5.0,
+        };
+        let camera = frame_sphere(&bounds, 0.5, 2.0);
+
+        // The camera is centered on the bounds.
+        assert_vector_relative_eq!(
+            [0.0, 10.0, 5.0 / 0.25f32.sin(), 1.0],
+            camera.camera_pos.to_array()
+        );
+        let (x, y) = world_to_screen(bounds.center, camera.mvp_matrix, 200, 100);
+        approx::assert_relative_eq!(100.0, x, epsilon = 0.0001);
+        approx::assert_relative_eq!(50.0, y, epsilon = 0.0001);
+
+        // The top of the sphere is inside the view for the vertical field of view.
+        let (_, top) = world_to_screen(Vec3::new(0.0, 15.0, 0.0), camera.mvp_matrix, 200, 100);
+        assert!(top >= 0.0 && top < 50.0);
+
+        // The sphere is between the near and far planes.
+        for z in [-4.9, 4.9] {
+            let clip = camera.mvp_matrix.project_point3(Vec3::new(0.0, 10.0, z));
+            assert!(clip.z >= 0.0 && clip.z <= 1.0);
+        }
+    }
+
+    #[test]
+    fn frame_sphere_narrow_aspect() {
+        let bounds = BoundingSphere {
+            center: Vec3::ZERO,
+            radius: 1.0,
+        };
+        // Narrow viewports should move the camera back to fit horizontally.
+        let wide = frame_sphere(&bounds, 0.5, 1.0);
+        let narrow = frame_sphere(&bounds, 0.5, 0.5);
+        assert!(narrow.camera_pos.z > wide.camera_pos.z);
+
+        let (left, _) = world_to_screen(Vec3::new(-1.0, 0.0, 0.0), narrow.mvp_matrix, 50, 100);
+        assert!((0.0..25.0).contains(&left));
+    }
+
+    #[test]
+    fn frame_models_empty() {
+        let camera = frame_models(&[], 0.5, 1.0);
+        assert_eq!(0.0, camera.camera_pos.x);
+        assert_eq!(0.0, camera.camera_pos.y);
+        assert!(camera.camera_pos.z > 0.0);
+    }
+}
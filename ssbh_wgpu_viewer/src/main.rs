@@ -1,9 +1,9 @@
 use pico_args::Arguments;
 use ssbh_data::prelude::*;
 use ssbh_wgpu::animation::camera::animate_camera;
+use ssbh_wgpu::hot_reload::FolderWatcher;
 use ssbh_wgpu::next_frame;
 use ssbh_wgpu::swing::SwingPrc;
-use ssbh_wgpu::BoneNameRenderer;
 use ssbh_wgpu::CameraTransforms;
 use ssbh_wgpu::DebugMode;
 use ssbh_wgpu::ModelFolder;
@@ -13,8 +13,9 @@ use ssbh_wgpu::RenderModel;
 use ssbh_wgpu::RenderSettings;
 use ssbh_wgpu::SharedRenderData;
 use ssbh_wgpu::TransitionMaterial;
-use ssbh_wgpu::REQUIRED_FEATURES;
-use ssbh_wgpu::{load_model_folders, load_render_models, SsbhRenderer};
+use ssbh_wgpu::{BoneNameOptions, BoneNameRenderer};
+use ssbh_wgpu::{ModelLoader, SsbhRenderer};
+use ssbh_wgpu::{OPTIONAL_FEATURES, REQUIRED_FEATURES};
 use std::collections::HashSet;
 use std::path::PathBuf;
 use winit::keyboard::KeyCode;
@@ -62,6 +63,11 @@ struct State<'a> {
     // Parallel lists for models and renderable models.
     models: Vec<(PathBuf, ModelFolder)>,
     render_models: Vec<RenderModel>,
+    // Models are added as they finish loading.
+    loader: ModelLoader,
+    // Reload edited materials and textures for each model folder.
+    watchers: Vec<FolderWatcher>,
+    swing_prc: Option<SwingPrc>,
 
     renderer: SsbhRenderer,
     name_renderer: BoneNameRenderer,
@@ -120,7 +126,7 @@ impl<'a> State<'a> {
             .request_device(
                 &wgpu::DeviceDescriptor {
                     label: None,
-                    required_features: wgpu::Features::default() | REQUIRED_FEATURES,
+                    required_features: REQUIRED_FEATURES | (adapter.features() & OPTIONAL_FEATURES),
                     required_limits: wgpu::Limits::default(),
                     memory_hints: wgpu::MemoryHints::default(),
                 },
@@ -167,20 +173,14 @@ impl<'a> State<'a> {
             .as_ref()
             .and_then(|f| NutexbFile::read_from_file(f.join("reflection_cubemap.nutexb")).ok())
         {
-            shared_data.update_stage_cube_map(&device, &queue, &nutexb);
-        }
-
-        let models = load_model_folders(folder);
-        let mut render_models =
-            load_render_models(&device, &queue, models.iter().map(|(_, m)| m), &shared_data);
-
-        // Assume only one folder is loaded and apply the swing prc to every folder.
-        if let Some(swing_prc) = &swing_prc {
-            for (render_model, (_, model)) in render_models.iter_mut().zip(models.iter()) {
-                render_model.recreate_swing_collisions(&device, swing_prc, model.find_skel());
+            if let Err(e) = shared_data.update_stage_cube_map(&device, &queue, &nutexb) {
+                eprintln!("Error updating stage cube map: {e}");
             }
         }
 
+        // Load models in the background to avoid freezing the window for large folders.
+        let loader = ModelLoader::new(folder);
+
         let mut renderer = SsbhRenderer::new(
             &device,
             &queue,
@@ -200,7 +200,9 @@ impl<'a> State<'a> {
             )
             .ok()
         }) {
-            renderer.update_color_lut(&device, &queue, &nutexb);
+            if let Err(e) = renderer.update_color_lut(&device, &queue, &nutexb) {
+                eprintln!("Error updating color grading LUT: {e}");
+            }
         }
 
         let font_bytes = font_path.map(|font_path| std::fs::read(font_path).unwrap());
@@ -213,8 +215,11 @@ impl<'a> State<'a> {
             queue,
             config,
             size,
-            models,
-            render_models,
+            models: Vec::new(),
+            render_models: Vec::new(),
+            loader,
+            watchers: Vec::new(),
+            swing_prc,
             renderer,
             previous_cursor_position: PhysicalPosition { x: 0.0, y: 0.0 },
             is_mouse_left_clicked: false,
@@ -354,6 +359,16 @@ impl<'a> State<'a> {
                         "6" => self.render.debug_mode = DebugMode::ColorSet5,
                         "7" => self.render.debug_mode = DebugMode::ColorSet6,
                         "8" => self.render.debug_mode = DebugMode::ColorSet7,
+                        "9" => {
+                            if event.state == ElementState::Released {
+                                self.render.scale_vertex_color = !self.render.scale_vertex_color;
+                            }
+                        }
+                        "0" => {
+                            if event.state == ElementState::Released {
+                                self.render.vertex_color_gamma = !self.render.vertex_color_gamma;
+                            }
+                        }
                         "q" => self.render.debug_mode = DebugMode::Texture0,
                         "w" => self.render.debug_mode = DebugMode::Texture1,
                         "e" => self.render.debug_mode = DebugMode::Texture2,
@@ -389,6 +404,16 @@ impl<'a> State<'a> {
                     winit::keyboard::Key::Dead(_) => (),
                 }
 
+                // Explain why imported vertex colors may appear brighter or darker than expected.
+                if let Some(scale) = self.render.vertex_color_scale() {
+                    if event.state == ElementState::Released {
+                        println!(
+                            "{}: scale {scale}, gamma {}",
+                            self.render.debug_mode, self.render.vertex_color_gamma
+                        );
+                    }
+                }
+
                 true
             }
             _ => false,
@@ -421,7 +446,52 @@ impl<'a> State<'a> {
             .update_render_settings(&self.queue, &self.render);
     }
 
+    fn load_models(&mut self) {
+        if self.loader.is_finished() {
+            return;
+        }
+
+        // Limit the models per frame to keep the window responsive.
+        for (path, model, mut render_model) in
+            self.loader
+                .poll_render_models(&self.device, &self.queue, &self.shared_data, 1)
+        {
+            // Assume only one folder is loaded and apply the swing prc to every folder.
+            if let Some(swing_prc) = &self.swing_prc {
+                render_model.recreate_swing_collisions(&self.device, swing_prc, model.find_skel());
+            }
+            self.watchers.push(FolderWatcher::new(&path));
+            self.models.push((path, model));
+            self.render_models.push(render_model);
+        }
+    }
+
+    fn reload_models(&mut self) {
+        for ((watcher, (_, model)), render_model) in self
+            .watchers
+            .iter_mut()
+            .zip(self.models.iter_mut())
+            .zip(self.render_models.iter_mut())
+        {
+            for event in watcher.poll() {
+                if let Err(e) = render_model.reload(
+                    &self.device,
+                    &self.queue,
+                    &event,
+                    model.find_matl(),
+                    &self.shared_data,
+                ) {
+                    eprintln!("{e}");
+                }
+                model.update_file(event);
+            }
+        }
+    }
+
     fn render(&mut self, scale_factor: f64) -> Result<(), wgpu::SurfaceError> {
+        self.load_models();
+        self.reload_models();
+
         let current_frame_start = std::time::Instant::now();
         if self.is_playing {
             self.current_frame = next_frame(
@@ -446,6 +516,13 @@ impl<'a> State<'a> {
                 1.0,
                 true,
             );
+
+            // Time driven material effects should animate even without a material animation.
+            self.render.time_seconds += current_frame_start
+                .duration_since(self.previous_frame_start)
+                .as_secs_f32();
+            self.renderer
+                .update_time(&self.queue, self.render.time_seconds);
         }
         self.previous_frame_start = current_frame_start;
 
@@ -494,6 +571,9 @@ impl<'a> State<'a> {
             }
         }
 
+        self.renderer
+            .update_shadow_bounds(&self.queue, &self.render_models);
+
         let mut final_pass = self.renderer.render_models(
             &mut encoder,
             &output_view,
@@ -527,7 +607,10 @@ impl<'a> State<'a> {
             self.size.width,
             self.size.height,
             mvp,
-            18.0,
+            &BoneNameOptions {
+                font_size: 18.0,
+                ..Default::default()
+            },
         );
 
         drop(final_pass);